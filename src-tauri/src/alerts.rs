@@ -0,0 +1,193 @@
+//! Alerts when IOC rules newly flag rows as critical.
+//!
+//! After IOC flags may have changed (rules saved or imported, data
+//! re-imported, ...), the rows flagged critical are compared with those
+//! already reported, kept in `ALERTED_ROWS_FILE`. New ones are summarized in
+//! a single `CriticalAlert` that is posted to the project's webhook and/or
+//! shown in the app. The webhook is posted with `ureq` on a background
+//! thread, so a slow receiver never holds up the command.
+
+use std::{collections::BTreeSet, fs, path::Path, thread, time::Duration};
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::{
+    ioc::{critical_ioc_rows, CriticalIocRow},
+    models::ProjectMeta,
+    state::AppState,
+};
+
+pub const CRITICAL_ALERT_EVENT: &str = "critical-ioc-alert";
+pub const ALERTED_ROWS_FILE: &str = "alerted_rows.json";
+/// Rows listed in an alert; the counts always cover all of them.
+const MAX_ALERT_ROWS: usize = 50;
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(15);
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CriticalAlert {
+    pub project_id: Uuid,
+    pub project_name: String,
+    pub new_critical_rows: usize,
+    pub total_critical_rows: usize,
+    /// The first new rows with the tags of the rules that matched them.
+    pub rows: Vec<CriticalIocRow>,
+    pub created_at: DateTime<Utc>,
+}
+
+pub type AlertListener = Box<dyn Fn(&CriticalAlert) + Send + Sync>;
+
+/// Delivers in-app alerts; the app registers a listener that forwards them
+/// to the frontend.
+#[derive(Default)]
+pub struct AlertSink {
+    listener: Mutex<Option<AlertListener>>,
+}
+
+impl AlertSink {
+    pub fn set_listener(&self, listener: AlertListener) {
+        *self.listener.lock() = Some(listener);
+    }
+
+    fn notify(&self, alert: &CriticalAlert) {
+        if let Some(listener) = self.listener.lock().as_ref() {
+            listener(alert);
+        }
+    }
+}
+
+fn load_alerted_rows(project_dir: &Path) -> Result<BTreeSet<usize>> {
+    let path = project_dir.join(ALERTED_ROWS_FILE);
+    if !path.exists() {
+        return Ok(BTreeSet::new());
+    }
+    let data = fs::read(&path).with_context(|| format!("failed to read {:?}", path))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse {:?}", path))
+}
+
+fn save_alerted_rows(project_dir: &Path, rows: &BTreeSet<usize>) -> Result<()> {
+    let path = project_dir.join(ALERTED_ROWS_FILE);
+    fs::write(&path, serde_json::to_vec(rows)?)
+        .with_context(|| format!("failed to write {:?}", path))
+}
+
+/// Marks the rows currently flagged critical as reported, so turning alerts
+/// on does not report what the analyst already sees.
+pub fn reset_alert_baseline(meta: &ProjectMeta, project_dir: &Path) -> Result<()> {
+    let current: BTreeSet<usize> =
+        critical_ioc_rows(project_dir, meta.ioc_policy, meta.unsearched_columns())?
+            .into_iter()
+            .map(|row| row.row_index)
+            .collect();
+    save_alerted_rows(project_dir, &current)
+}
+
+/// Summarizes the critical rows not reported before and records them as
+/// reported. Rows that stop being critical are forgotten, so they are
+/// reported again if a later change flags them once more.
+pub fn collect_new_critical(
+    meta: &ProjectMeta,
+    project_dir: &Path,
+) -> Result<Option<CriticalAlert>> {
+    let critical = critical_ioc_rows(project_dir, meta.ioc_policy, meta.unsearched_columns())?;
+    let alerted = load_alerted_rows(project_dir)?;
+    let current: BTreeSet<usize> = critical.iter().map(|row| row.row_index).collect();
+    if current != alerted {
+        save_alerted_rows(project_dir, &current)?;
+    }
+    let total_critical_rows = critical.len();
+    let new_rows: Vec<CriticalIocRow> = critical
+        .into_iter()
+        .filter(|row| !alerted.contains(&row.row_index))
+        .collect();
+    if new_rows.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(CriticalAlert {
+        project_id: meta.id,
+        project_name: meta.name.clone(),
+        new_critical_rows: new_rows.len(),
+        total_critical_rows,
+        rows: new_rows.into_iter().take(MAX_ALERT_ROWS).collect(),
+        created_at: Utc::now(),
+    }))
+}
+
+/// Posts an alert as JSON. A receiver answering with an error status fails
+/// with that status.
+fn post_webhook(url: &str, body: &[u8]) -> Result<()> {
+    let agent = ureq::AgentBuilder::new().timeout(WEBHOOK_TIMEOUT).build();
+    match agent
+        .post(url)
+        .set("Content-Type", "application/json")
+        .send_bytes(body)
+    {
+        Ok(_) => Ok(()),
+        Err(ureq::Error::Status(code, response)) => {
+            bail!("webhook answered HTTP {} {}", code, response.status_text())
+        }
+        Err(err) => bail!("failed to reach the webhook: {}", err),
+    }
+}
+
+/// Posts an alert without rows to the project's webhook and waits for the
+/// answer, so a wrong URL shows up before a real alert is lost.
+pub fn send_test_alert(meta: &ProjectMeta) -> Result<()> {
+    let Some(url) = &meta.alerts.webhook_url else {
+        bail!("no webhook URL is set");
+    };
+    let alert = CriticalAlert {
+        project_id: meta.id,
+        project_name: meta.name.clone(),
+        new_critical_rows: 0,
+        total_critical_rows: 0,
+        rows: Vec::new(),
+        created_at: Utc::now(),
+    };
+    post_webhook(url, &serde_json::to_vec(&alert)?)
+}
+
+/// Reports rows newly flagged critical through the project's alert
+/// settings. Failures are logged and never fail the calling command.
+pub fn check_project(state: &AppState, project_id: &Uuid) {
+    let Some(meta) = state.projects.find(project_id) else {
+        return;
+    };
+    if !meta.alerts.enabled() {
+        return;
+    }
+    let project_dir = state.projects.project_dir(&meta.id);
+    let alert = match collect_new_critical(&meta, &project_dir) {
+        Ok(Some(alert)) => alert,
+        Ok(None) => return,
+        Err(err) => {
+            tracing::warn!("failed to check critical rows of {}: {:?}", meta.id, err);
+            return;
+        }
+    };
+    tracing::info!(
+        "{} rows of project {} newly flagged critical",
+        alert.new_critical_rows,
+        meta.id
+    );
+    if meta.alerts.notify {
+        state.alerts.notify(&alert);
+    }
+    if let Some(url) = meta.alerts.webhook_url {
+        let body = match serde_json::to_vec(&alert) {
+            Ok(body) => body,
+            Err(err) => {
+                tracing::warn!("failed to encode alert: {:?}", err);
+                return;
+            }
+        };
+        thread::spawn(move || {
+            if let Err(err) = post_webhook(&url, &body) {
+                tracing::warn!("failed to post critical alert webhook: {:?}", err);
+            }
+        });
+    }
+}
@@ -0,0 +1,169 @@
+//! Periodic snapshots of a project's annotations.
+//!
+//! Flags live in a sled database and IOC rules in `iocs.json`; either can be
+//! lost to a corrupted database or a careless bulk edit. A background thread
+//! copies both into `backups/` inside the project directory every
+//! `BACKUP_INTERVAL`, keeping the newest `BACKUP_RETENTION` snapshots.
+
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Wry};
+
+use crate::{
+    ioc::load_ioc_entries,
+    models::{AnnotationsBackup, FlagEntry, IocEntry},
+    state::AppState,
+    storage::load_flags,
+};
+
+pub const BACKUP_DIR: &str = "backups";
+const BACKUP_PREFIX: &str = "annotations-";
+const BACKUP_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const BACKUP_RETENTION: usize = 20;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnnotationsSnapshot {
+    pub created_at: DateTime<Utc>,
+    pub flags: HashMap<usize, FlagEntry>,
+    pub iocs: Vec<IocEntry>,
+}
+
+impl AnnotationsSnapshot {
+    fn same_annotations(&self, other: &AnnotationsSnapshot) -> bool {
+        self.flags == other.flags
+            && serde_json::to_value(&self.iocs).ok() == serde_json::to_value(&other.iocs).ok()
+    }
+}
+
+fn backup_dir(project_dir: &Path) -> PathBuf {
+    project_dir.join(BACKUP_DIR)
+}
+
+fn backup_path(project_dir: &Path, id: &str) -> PathBuf {
+    backup_dir(project_dir).join(format!("{}.json", id))
+}
+
+/// Snapshot ids, oldest first. The timestamp in the name sorts chronologically.
+fn backup_ids(project_dir: &Path) -> Result<Vec<String>> {
+    let dir = backup_dir(project_dir);
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut ids = Vec::new();
+    for entry in fs::read_dir(&dir).with_context(|| format!("failed to read {:?}", dir))? {
+        let name = entry?.file_name().to_string_lossy().into_owned();
+        if let Some(id) = name.strip_suffix(".json") {
+            if id.starts_with(BACKUP_PREFIX) {
+                ids.push(id.to_string());
+            }
+        }
+    }
+    ids.sort();
+    Ok(ids)
+}
+
+fn read_snapshot(path: &Path) -> Result<AnnotationsSnapshot> {
+    let data = fs::read(path).with_context(|| format!("failed to read backup {:?}", path))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse backup {:?}", path))
+}
+
+/// Reads the snapshot with the given id, refusing anything that is not one of
+/// the project's listed backups.
+pub fn read_backup(project_dir: &Path, id: &str) -> Result<AnnotationsSnapshot> {
+    if !backup_ids(project_dir)?.iter().any(|known| known == id) {
+        return Err(anyhow!("backup {} not found", id));
+    }
+    read_snapshot(&backup_path(project_dir, id))
+}
+
+/// Lists a project's snapshots, newest first.
+pub fn list_backups(project_dir: &Path) -> Result<Vec<AnnotationsBackup>> {
+    let mut backups = Vec::new();
+    for id in backup_ids(project_dir)?.into_iter().rev() {
+        let path = backup_path(project_dir, &id);
+        let snapshot = read_snapshot(&path)?;
+        backups.push(AnnotationsBackup {
+            id,
+            created_at: snapshot.created_at,
+            flagged_rows: snapshot.flags.len(),
+            ioc_count: snapshot.iocs.len(),
+            size_bytes: fs::metadata(&path).map(|meta| meta.len()).unwrap_or(0),
+        });
+    }
+    Ok(backups)
+}
+
+/// Writes a snapshot of the current flags and IOC rules, unless they match
+/// the latest one or there is nothing to protect yet. Returns the new id.
+pub fn snapshot_project(project_dir: &Path) -> Result<Option<String>> {
+    let snapshot = AnnotationsSnapshot {
+        created_at: Utc::now(),
+        flags: load_flags(&project_dir.join("flags.json"))?,
+        iocs: load_ioc_entries(project_dir)?,
+    };
+    let ids = backup_ids(project_dir)?;
+    match ids.last() {
+        Some(latest) => {
+            if let Ok(previous) = read_snapshot(&backup_path(project_dir, latest)) {
+                if previous.same_annotations(&snapshot) {
+                    return Ok(None);
+                }
+            }
+        }
+        None if snapshot.flags.is_empty() && snapshot.iocs.is_empty() => return Ok(None),
+        None => {}
+    }
+
+    let dir = backup_dir(project_dir);
+    fs::create_dir_all(&dir).with_context(|| format!("failed to create {:?}", dir))?;
+    let id = format!(
+        "{}{}",
+        BACKUP_PREFIX,
+        snapshot.created_at.format("%Y%m%dT%H%M%S%3fZ")
+    );
+    let path = backup_path(project_dir, &id);
+    let staged = path.with_extension("json.tmp");
+    let data = serde_json::to_vec(&snapshot).context("failed to serialize backup")?;
+    fs::write(&staged, data).with_context(|| format!("failed to write {:?}", staged))?;
+    fs::rename(&staged, &path).with_context(|| format!("failed to write {:?}", path))?;
+
+    prune_backups(project_dir)?;
+    Ok(Some(id))
+}
+
+fn prune_backups(project_dir: &Path) -> Result<()> {
+    let ids = backup_ids(project_dir)?;
+    let excess = ids.len().saturating_sub(BACKUP_RETENTION);
+    for id in &ids[..excess] {
+        let path = backup_path(project_dir, id);
+        fs::remove_file(&path).with_context(|| format!("failed to remove {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// Starts the thread that snapshots every project on a fixed interval.
+pub fn spawn(app: AppHandle<Wry>) {
+    thread::spawn(move || loop {
+        thread::sleep(BACKUP_INTERVAL);
+        let state = app.state::<AppState>();
+        for meta in state.projects.all() {
+            // Snapshots would keep the annotations in plain text.
+            if meta.encryption.is_some() {
+                continue;
+            }
+            let project_dir = state.projects.project_dir(&meta.id);
+            if let Err(err) = snapshot_project(&project_dir) {
+                tracing::warn!("failed to snapshot {:?}: {:?}", project_dir, err);
+            }
+        }
+    });
+}
@@ -0,0 +1,397 @@
+use anyhow::{anyhow, Context, Result};
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use polars::prelude::{DataFrame, DataType, NamedFrom, Schema, Series, TimeUnit};
+
+use crate::{
+    models::{ColumnDtype, ColumnType, ImportOptions},
+    timezones::{datetime_micros, ProjectTimezone},
+    value_utils::anyvalue_to_search_string,
+};
+
+/// Timestamps with a UTC offset that RFC 3339 parsing rejects, such as
+/// Hayabusa's `2024-03-01 10:00:00.000 +09:00`.
+const OFFSET_DATETIME_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S%.f %:z", "%Y-%m-%d %H:%M:%S%.f %z"];
+/// Naive timestamp layouts tried after RFC 3339 when reading text as a datetime.
+const DATETIME_FORMATS: &[&str] = &[
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+pub fn polars_dtype(dtype: ColumnDtype) -> DataType {
+    match dtype {
+        ColumnDtype::String => DataType::String,
+        ColumnDtype::Int64 => DataType::Int64,
+        ColumnDtype::Float64 => DataType::Float64,
+        ColumnDtype::Boolean => DataType::Boolean,
+        ColumnDtype::Date => DataType::Date,
+        ColumnDtype::Datetime => DataType::Datetime(TimeUnit::Microseconds, None),
+    }
+}
+
+fn dtype_label(dtype: ColumnDtype) -> &'static str {
+    match dtype {
+        ColumnDtype::String => "string",
+        ColumnDtype::Int64 => "int64",
+        ColumnDtype::Float64 => "float64",
+        ColumnDtype::Boolean => "boolean",
+        ColumnDtype::Date => "date",
+        ColumnDtype::Datetime => "datetime",
+    }
+}
+
+/// Reads a timestamp written as RFC 3339 or with a trailing UTC offset,
+/// converted to UTC.
+fn parse_offset_datetime(text: &str) -> Option<NaiveDateTime> {
+    DateTime::parse_from_rfc3339(text)
+        .ok()
+        .or_else(|| {
+            OFFSET_DATETIME_FORMATS
+                .iter()
+                .find_map(|format| DateTime::parse_from_str(text, format).ok())
+        })
+        .map(|parsed| parsed.naive_utc())
+}
+
+/// Reads a common naive `date time` layout; a bare date is taken as midnight.
+fn parse_naive_datetime(text: &str) -> Option<NaiveDateTime> {
+    DATETIME_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .or_else(|| parse_date(text).and_then(|date| date.and_hms_opt(0, 0, 0)))
+}
+
+/// Reads a timestamp written as RFC 3339 or with a trailing UTC offset
+/// (converted to UTC), or as a common naive `date time` layout; a bare date
+/// is taken as midnight.
+pub fn parse_datetime(text: &str) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    parse_offset_datetime(text).or_else(|| parse_naive_datetime(text))
+}
+
+/// Like [`parse_datetime`], but naive values are wall-clock times in `zone`.
+pub fn parse_zoned_datetime(text: &str, zone: &ProjectTimezone) -> Option<NaiveDateTime> {
+    let text = text.trim();
+    parse_offset_datetime(text)
+        .or_else(|| parse_naive_datetime(text).map(|local| zone.local_to_utc(local)))
+}
+
+fn parse_date(text: &str) -> Option<NaiveDate> {
+    let text = text.trim();
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(text, "%Y/%m/%d"))
+        .ok()
+}
+
+fn parse_boolean(text: &str) -> Option<bool> {
+    match text.trim().to_ascii_lowercase().as_str() {
+        "true" | "yes" | "1" => Some(true),
+        "false" | "no" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Parses every value of a text column, failing on the first one that does
+/// not convert instead of silently turning it into a null.
+fn parse_text_column<T>(
+    series: &Series,
+    parse: impl Fn(&str) -> Option<T>,
+) -> Result<Vec<Option<T>>> {
+    series
+        .str()?
+        .into_iter()
+        .map(|value| match value {
+            None => Ok(None),
+            Some(text) if text.trim().is_empty() => Ok(None),
+            Some(text) => parse(text)
+                .map(Some)
+                .ok_or_else(|| anyhow!("value {:?} does not convert", text)),
+        })
+        .collect()
+}
+
+fn convert_column(series: &Series, dtype: ColumnDtype) -> Result<Series> {
+    let target = polars_dtype(dtype);
+    if series.dtype() != &DataType::String {
+        return Ok(series.strict_cast(&target)?);
+    }
+    let name = series.name();
+    let converted = match dtype {
+        ColumnDtype::String => series.clone(),
+        ColumnDtype::Int64 | ColumnDtype::Float64 => series.strict_cast(&target)?,
+        ColumnDtype::Boolean => Series::new(name, parse_text_column(series, parse_boolean)?),
+        ColumnDtype::Date => {
+            let days = parse_text_column(series, |text| {
+                parse_date(text).map(|date| date.signed_duration_since(NaiveDate::default()))
+            })?
+            .into_iter()
+            .map(|delta| delta.map(|delta| delta.num_days() as i32))
+            .collect::<Vec<_>>();
+            Series::new(name, days).cast(&target)?
+        }
+        ColumnDtype::Datetime => {
+            let micros = parse_text_column(series, |text| {
+                parse_datetime(text).map(|value| value.and_utc().timestamp_micros())
+            })?;
+            Series::new(name, micros).cast(&target)?
+        }
+    };
+    Ok(converted)
+}
+
+/// Converts a designated timestamp column to UTC datetimes. Text is parsed
+/// strictly; naive text, datetimes and dates are taken as local times in
+/// `zone`, while values with an offset or a time zone keep their instant.
+fn normalize_timestamp_column(series: &Series, zone: &ProjectTimezone) -> Result<Series> {
+    let name = series.name();
+    let target = polars_dtype(ColumnDtype::Datetime);
+    let micros: Vec<Option<i64>> = match series.dtype() {
+        DataType::String => parse_text_column(series, |text| {
+            parse_zoned_datetime(text, zone).map(|value| value.and_utc().timestamp_micros())
+        })?,
+        DataType::Datetime(_, Some(_)) => datetime_micros(series)?,
+        DataType::Datetime(_, None) | DataType::Date => {
+            let local = series.cast(&target)?;
+            datetime_micros(&local)?
+                .into_iter()
+                .map(|micros| {
+                    micros
+                        .and_then(DateTime::from_timestamp_micros)
+                        .map(|value| zone.local_to_utc(value.naive_utc()))
+                        .map(|value| value.and_utc().timestamp_micros())
+                })
+                .collect()
+        }
+        other => return Err(anyhow!("{} values are not timestamps", other)),
+    };
+    Ok(Series::new(name, micros).cast(&target)?)
+}
+
+/// `1,234,567.89` without its thousands separators; `None` unless the text
+/// is a number grouped in threes by commas or (narrow) no-break spaces.
+fn strip_thousands(text: &str) -> Option<String> {
+    let unsigned = text.strip_prefix(['+', '-']).unwrap_or(text);
+    let (whole, fraction) = match unsigned.split_once('.') {
+        Some((whole, fraction)) => (whole, Some(fraction)),
+        None => (unsigned, None),
+    };
+    let digits = |part: &str| !part.is_empty() && part.bytes().all(|b| b.is_ascii_digit());
+    if fraction.is_some_and(|fraction| !digits(fraction)) {
+        return None;
+    }
+    let separator = whole.chars().find(|c| !c.is_ascii_digit())?;
+    if !matches!(separator, ',' | '\u{00A0}' | '\u{202F}') {
+        return None;
+    }
+    let mut groups = whole.split(separator);
+    let first = groups.next()?;
+    if !digits(first) || first.len() > 3 || !groups.all(|group| group.len() == 3 && digits(group)) {
+        return None;
+    }
+    Some(text.chars().filter(|c| *c != separator).collect())
+}
+
+/// Cleans up the text values of freshly parsed data as the import asks:
+/// surrounding whitespace trimmed, chosen columns lowercased and thousands
+/// separators stripped from chosen columns. Other values are kept as they
+/// are, so a column that is not all numbers stays text.
+fn normalize_values(mut df: DataFrame, options: &ImportOptions) -> Result<DataFrame> {
+    let text_columns: Vec<String> = df
+        .get_columns()
+        .iter()
+        .filter(|series| series.dtype() == &DataType::String && !series.name().starts_with("__"))
+        .map(|series| series.name().to_string())
+        .collect();
+    for name in text_columns {
+        let lowercase = options.lowercase_columns.contains(&name);
+        let strip = options.strip_thousands_columns.contains(&name);
+        if !(options.trim_whitespace || lowercase || strip) {
+            continue;
+        }
+        let values: Vec<Option<String>> = df
+            .column(&name)?
+            .str()?
+            .into_iter()
+            .map(|value| {
+                value.map(|text| {
+                    let mut text = if options.trim_whitespace {
+                        text.trim().to_string()
+                    } else {
+                        text.to_string()
+                    };
+                    if let Some(number) = strip.then(|| strip_thousands(text.trim())).flatten() {
+                        text = number;
+                    }
+                    if lowercase {
+                        text = text.to_lowercase();
+                    }
+                    text
+                })
+            })
+            .collect();
+        df.replace(&name, Series::new(&name, values))?;
+    }
+    Ok(df)
+}
+
+/// Applies the requested column types to freshly parsed data. Overrides are
+/// strict: a value that cannot be read as the requested type is an error.
+/// With `parse_dates`, the remaining text columns whose every value reads as
+/// a timestamp become datetime columns. Designated timestamp columns are
+/// normalized to UTC first and take precedence over type overrides. Text
+/// is normalized before any of this, so `1,234` can be read as a number.
+/// Overridden or designated columns missing from this particular input are
+/// left to the caller to report.
+pub fn apply_column_types(df: DataFrame, options: &ImportOptions) -> Result<DataFrame> {
+    let mut df = normalize_values(df, options)?;
+    if !options.timestamp_columns.is_empty() {
+        let zone = match options.source_timezone.as_deref() {
+            Some(name) => ProjectTimezone::parse(name)?,
+            None => ProjectTimezone::utc(),
+        };
+        for name in &options.timestamp_columns {
+            let Ok(series) = df.column(name) else {
+                continue;
+            };
+            let normalized = normalize_timestamp_column(series, &zone)
+                .with_context(|| format!("column {} cannot be read as timestamps", name))?;
+            df.replace(name, normalized)?;
+        }
+    }
+    for (name, dtype) in &options.dtypes {
+        if options.timestamp_columns.contains(name) {
+            continue;
+        }
+        let Ok(series) = df.column(name) else {
+            continue;
+        };
+        if series.dtype() == &polars_dtype(*dtype) {
+            continue;
+        }
+        let converted = convert_column(series, *dtype).with_context(|| {
+            format!("column {} cannot be read as {}", name, dtype_label(*dtype))
+        })?;
+        df.replace(name, converted)?;
+    }
+    if options.parse_dates {
+        let candidates: Vec<String> = df
+            .get_columns()
+            .iter()
+            .filter(|series| {
+                series.dtype() == &DataType::String
+                    && series.null_count() < series.len()
+                    && !series.name().starts_with("__")
+                    && !options.dtypes.contains_key(series.name())
+            })
+            .map(|series| series.name().to_string())
+            .collect();
+        for name in candidates {
+            if let Ok(parsed) = convert_column(df.column(&name)?, ColumnDtype::Datetime) {
+                df.replace(&name, parsed)?;
+            }
+        }
+    }
+    Ok(df)
+}
+
+/// Picks the column a dataset is ordered by in time: the first date/time
+/// typed column, else the first one named like a timestamp.
+pub fn detect_timestamp_column(schema: &Schema) -> Option<String> {
+    let columns = || schema.iter().filter(|(name, _)| !name.starts_with("__"));
+    columns()
+        .find(|(_, dtype)| matches!(dtype, DataType::Datetime(_, _) | DataType::Date))
+        .or_else(|| {
+            columns().find(|(name, _)| {
+                let name = name.to_lowercase();
+                name.contains("time") || name.contains("date")
+            })
+        })
+        .map(|(name, _)| name.to_string())
+}
+
+/// Instants of a time column in microseconds since the epoch (UTC), for
+/// ordering rows; text is read with [`parse_datetime`], and values that do
+/// not read as a time are `None`.
+pub fn timeline_instants(series: &Series) -> Result<Vec<Option<i64>>> {
+    match series.dtype() {
+        DataType::Datetime(_, _) => datetime_micros(series),
+        DataType::Date => datetime_micros(&series.cast(&polars_dtype(ColumnDtype::Datetime))?),
+        _ => Ok(series
+            .iter()
+            .map(|value| {
+                anyvalue_to_search_string(&value)
+                    .and_then(|text| parse_datetime(&text))
+                    .map(|value| value.and_utc().timestamp_micros())
+            })
+            .collect()),
+    }
+}
+
+fn column_dtype_of(dtype: &DataType) -> Option<ColumnDtype> {
+    match dtype {
+        DataType::String => Some(ColumnDtype::String),
+        DataType::Int64 => Some(ColumnDtype::Int64),
+        DataType::Float64 => Some(ColumnDtype::Float64),
+        DataType::Boolean => Some(ColumnDtype::Boolean),
+        DataType::Date => Some(ColumnDtype::Date),
+        DataType::Datetime(TimeUnit::Microseconds, None) => Some(ColumnDtype::Datetime),
+        _ => None,
+    }
+}
+
+/// Converts a later batch of a streamed import to the column types chosen
+/// for the first batch. Only lossless conversions are made: anything to
+/// text, text parsed strictly, all-null columns, and integers to floats.
+pub fn conform_to_schema(mut df: DataFrame, schema: &Schema) -> Result<DataFrame> {
+    if df.width() != schema.len() {
+        return Err(anyhow!(
+            "expected {} columns, found {}",
+            schema.len(),
+            df.width()
+        ));
+    }
+    for (name, target) in schema.iter() {
+        let series = df
+            .column(name)
+            .with_context(|| format!("column {} is missing", name))?;
+        if series.dtype() == target {
+            continue;
+        }
+        let source = series.dtype().clone();
+        let converted = if target == &DataType::String
+            || series.null_count() == series.len()
+            || (source.is_integer() && target == &DataType::Float64)
+        {
+            series.cast(target)?
+        } else if source == DataType::String {
+            let dtype = column_dtype_of(target)
+                .ok_or_else(|| anyhow!("column {} cannot be read as {}", name, target))?;
+            convert_column(series, dtype).with_context(|| {
+                format!("column {} cannot be read as {}", name, dtype_label(dtype))
+            })?
+        } else {
+            return Err(anyhow!(
+                "column {} holds {} values where earlier rows held {}; set its column type",
+                name,
+                source,
+                target
+            ));
+        };
+        df.replace(name, converted)?;
+    }
+    Ok(df)
+}
+
+/// Lists the data columns of an imported frame with their resulting types.
+pub fn describe_column_types(schema: &Schema) -> Vec<ColumnType> {
+    schema
+        .iter()
+        .filter(|(name, _)| name.as_str() != "__rowid")
+        .map(|(name, dtype)| ColumnType {
+            name: name.to_string(),
+            dtype: dtype.to_string(),
+        })
+        .collect()
+}
@@ -0,0 +1,93 @@
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    alerts,
+    backups::{list_backups, read_backup, snapshot_project},
+    error::AppError,
+    ioc::{calculate_ioc_applied_records, save_ioc_entries},
+    models::{AnnotationsBackup, ProjectSummary},
+    state::AppState,
+    storage::{clear_ioc_flag_cache, count_flagged, save_flags},
+};
+
+use super::encryption::ensure_unsealed;
+
+#[derive(Debug, Deserialize)]
+pub struct AnnotationBackupsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RestoreBackupPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "backupId")]
+    pub backup_id: String,
+}
+
+/// Lists the automatic flag and IOC snapshots of a project, newest first.
+#[tauri::command]
+pub fn list_annotation_backups(
+    state: State<AppState>,
+    payload: AnnotationBackupsPayload,
+) -> Result<Vec<AnnotationsBackup>, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    let project_dir = state.projects.project_dir(&meta.id);
+    list_backups(&project_dir).map_err(AppError::from)
+}
+
+/// Replaces the project's flags and IOC rules with a snapshot. The current
+/// annotations are snapshotted first so the restore itself can be undone.
+#[tauri::command]
+pub fn restore_annotations_backup(
+    state: State<AppState>,
+    payload: RestoreBackupPayload,
+) -> Result<ProjectSummary, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state
+        .locks
+        .rewrite(&meta.id, "restore_annotations_backup")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let snapshot = read_backup(&project_dir, &payload.backup_id)
+        .map_err(|err| AppError::Message(format!("{:#}", err)))?;
+    snapshot_project(&project_dir).map_err(AppError::from)?;
+
+    let flags_path = project_dir.join("flags.json");
+    save_flags(&flags_path, &snapshot.flags).map_err(AppError::from)?;
+    save_ioc_entries(&project_dir, &snapshot.iocs).map_err(AppError::from)?;
+    state.watched.record(&state, &meta.id);
+    if let Err(err) = clear_ioc_flag_cache(&project_dir) {
+        tracing::warn!("failed to clear IOC cache for {:?}: {:?}", project_dir, err);
+    }
+
+    let flagged_records = count_flagged(&flags_path).map_err(AppError::from)?;
+    let ioc_applied_records =
+        calculate_ioc_applied_records(&project_dir, meta.ioc_policy, meta.unsearched_columns())
+            .map_err(AppError::from)?;
+    state
+        .projects
+        .update_counters(
+            &meta.id,
+            meta.total_records,
+            flagged_records,
+            ioc_applied_records,
+        )
+        .map_err(AppError::from)?;
+    state.changes.flags_updated(meta.id, None);
+    state.changes.iocs_updated(meta.id);
+    alerts::check_project(&state, &meta.id);
+
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+    Ok(ProjectSummary { meta })
+}
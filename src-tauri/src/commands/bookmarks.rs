@@ -0,0 +1,100 @@
+use chrono::Utc;
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::Bookmark,
+    state::AppState,
+    storage::{load_bookmarks, save_bookmarks, BOOKMARKS_FILE},
+};
+
+#[derive(Debug, Deserialize)]
+pub struct BookmarksPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct UpdateBookmarksPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub rows: Vec<usize>,
+    /// Short note shown with the bookmark; replaces the label of rows
+    /// already bookmarked. Ignored when removing.
+    #[serde(default)]
+    pub label: Option<String>,
+}
+
+/// Returns the project's bookmarks in row order.
+#[tauri::command]
+pub fn list_bookmarks(
+    state: State<AppState>,
+    payload: BookmarksPayload,
+) -> Result<Vec<Bookmark>, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    let path = state.projects.project_dir(&meta.id).join(BOOKMARKS_FILE);
+    load_bookmarks(&path).map_err(AppError::from)
+}
+
+/// Bookmarks rows to come back to later. Bookmarks are separate from flags,
+/// so they change neither the flag counts nor exports.
+#[tauri::command]
+pub fn add_bookmarks(
+    state: State<AppState>,
+    payload: UpdateBookmarksPayload,
+) -> Result<Vec<Bookmark>, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    if payload.rows.is_empty() {
+        return Err(AppError::Message("Choose the rows to bookmark.".into()));
+    }
+    if payload.rows.iter().any(|row| *row >= meta.total_records) {
+        return Err(AppError::Message(
+            "Row is out of range for this project.".into(),
+        ));
+    }
+    let label = payload
+        .label
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty());
+    let path = state.projects.project_dir(&meta.id).join(BOOKMARKS_FILE);
+    let mut bookmarks = load_bookmarks(&path).map_err(AppError::from)?;
+    let now = Utc::now();
+    for &row_index in &payload.rows {
+        match bookmarks
+            .iter_mut()
+            .find(|bookmark| bookmark.row_index == row_index)
+        {
+            Some(bookmark) => bookmark.label = label.clone(),
+            None => bookmarks.push(Bookmark {
+                row_index,
+                label: label.clone(),
+                created_at: now,
+            }),
+        }
+    }
+    bookmarks.sort_by_key(|bookmark| bookmark.row_index);
+    save_bookmarks(&path, &bookmarks).map_err(AppError::from)?;
+    Ok(bookmarks)
+}
+
+/// Removes the bookmarks of the given rows; rows without one are ignored.
+#[tauri::command]
+pub fn remove_bookmarks(
+    state: State<AppState>,
+    payload: UpdateBookmarksPayload,
+) -> Result<Vec<Bookmark>, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    let path = state.projects.project_dir(&meta.id).join(BOOKMARKS_FILE);
+    let mut bookmarks = load_bookmarks(&path).map_err(AppError::from)?;
+    bookmarks.retain(|bookmark| !payload.rows.contains(&bookmark.row_index));
+    save_bookmarks(&path, &bookmarks).map_err(AppError::from)?;
+    Ok(bookmarks)
+}
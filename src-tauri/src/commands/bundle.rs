@@ -0,0 +1,200 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    entities::ENTITY_INDEX_FILE,
+    error::AppError,
+    migrations::migrate_project,
+    models::{FlagEntry, ProjectMeta, ProjectSummary},
+    state::AppState,
+    storage::{load_flags, save_flags},
+    zip_reader::ZipArchive,
+    zip_writer::ZipWriter,
+};
+
+use super::{encryption::ensure_unsealed, export::create_export_dir};
+
+/// Archive member holding the bundle header and project metadata.
+const BUNDLE_MANIFEST: &str = "trivium-project.json";
+const BUNDLE_VERSION: u32 = 1;
+/// Flags live in a sled database inside the project; bundles carry them as JSON.
+const BUNDLE_FLAGS: &str = "flags.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleManifest {
+    format_version: u32,
+    meta: ProjectMeta,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportBundlePayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub destination: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportBundlePayload {
+    pub path: String,
+}
+
+/// Plain files of the project directory that travel in a bundle. Sled
+/// databases are directories and are left out: flags are written separately
+/// and caches, like the entity index, are rebuilt on first use.
+fn bundled_files(project_dir: &Path) -> anyhow::Result<Vec<(String, PathBuf)>> {
+    let mut files = Vec::new();
+    for entry in fs::read_dir(project_dir)
+        .with_context(|| format!("failed to read project dir {:?}", project_dir))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        if !entry.file_type()?.is_file()
+            || name == BUNDLE_FLAGS
+            || name == BUNDLE_MANIFEST
+            || name == ENTITY_INDEX_FILE
+            || name.ends_with(".reimport")
+        {
+            continue;
+        }
+        files.push((name, entry.path()));
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn write_bundle(project_dir: &Path, meta: &ProjectMeta, destination: &Path) -> anyhow::Result<()> {
+    let mut manifest = BundleManifest {
+        format_version: BUNDLE_VERSION,
+        meta: meta.clone(),
+    };
+    // Webhook URLs often embed a secret; the recipient sets up their own alerts.
+    manifest.meta.alerts = Default::default();
+    let flags = load_flags(&project_dir.join("flags.json"))?;
+    let mut zip = ZipWriter::create(destination)?;
+    zip.add_file(BUNDLE_MANIFEST, &serde_json::to_vec_pretty(&manifest)?)?;
+    zip.add_file(BUNDLE_FLAGS, &serde_json::to_vec(&flags)?)?;
+    for (name, path) in bundled_files(project_dir)? {
+        zip.add_streamed(&name, |entry| {
+            let mut reader = BufReader::new(
+                File::open(&path).with_context(|| format!("failed to open {:?}", path))?,
+            );
+            io::copy(&mut reader, entry)?;
+            Ok(())
+        })?;
+    }
+    zip.finish()
+}
+
+/// Packs a project (data, flags, memos, IOC rules and metadata) into a
+/// single `.trivium` archive for handing over to another analyst.
+#[tauri::command]
+pub fn export_project_bundle(
+    state: State<AppState>,
+    payload: ExportBundlePayload,
+) -> Result<(), AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let destination = PathBuf::from(payload.destination);
+    create_export_dir(&destination)?;
+    write_bundle(&project_dir, &meta, &destination)
+        .context("failed to write project bundle")
+        .map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Unpacks the bundle members into `project_dir`. Only plain file names are
+/// accepted so a crafted archive cannot write outside the project.
+fn extract_bundle(archive: &ZipArchive, project_dir: &Path) -> anyhow::Result<()> {
+    fs::create_dir_all(project_dir)
+        .with_context(|| format!("failed to create project dir {:?}", project_dir))?;
+    for entry in archive.entries() {
+        let name = entry.name.as_str();
+        if entry.is_dir() || name == BUNDLE_MANIFEST || name == BUNDLE_FLAGS {
+            continue;
+        }
+        if name.contains(['/', '\\']) || name.starts_with('.') {
+            return Err(anyhow!("unexpected bundle member {}", name));
+        }
+        let target = project_dir.join(name);
+        let mut out =
+            File::create(&target).with_context(|| format!("failed to create {:?}", target))?;
+        io::copy(&mut archive.open_entry(entry)?, &mut out)
+            .with_context(|| format!("failed to extract bundle member {}", name))?;
+    }
+    let mut flags_json = String::new();
+    if let Some(entry) = archive.find(BUNDLE_FLAGS) {
+        archive
+            .open_entry(entry)?
+            .read_to_string(&mut flags_json)
+            .context("failed to read bundled flags")?;
+    }
+    let flags: HashMap<usize, FlagEntry> = if flags_json.is_empty() {
+        Default::default()
+    } else {
+        serde_json::from_str(&flags_json).context("failed to parse bundled flags")?
+    };
+    save_flags(&project_dir.join("flags.json"), &flags)?;
+    if !project_dir.join("data.parquet").exists() {
+        return Err(anyhow!("bundle does not contain the project data"));
+    }
+    Ok(())
+}
+
+/// Adds the project packed in a `.trivium` archive. The project keeps its id,
+/// so row links from the sender still resolve, unless that id is taken.
+#[tauri::command]
+pub fn import_project_bundle(
+    state: State<AppState>,
+    payload: ImportBundlePayload,
+) -> Result<ProjectSummary, AppError> {
+    let path = PathBuf::from(payload.path);
+    let archive = ZipArchive::open(&path).map_err(AppError::from)?;
+    let manifest: BundleManifest = archive
+        .read_to_string(BUNDLE_MANIFEST)
+        .and_then(|text| serde_json::from_str(&text).context("failed to parse bundle manifest"))
+        .map_err(|_| {
+            AppError::Message(format!(
+                "{} is not a Trivium project bundle.",
+                path.display()
+            ))
+        })?;
+    if manifest.format_version > BUNDLE_VERSION {
+        return Err(AppError::Message(
+            "This bundle was created by a newer version of Trivium.".into(),
+        ));
+    }
+    let mut meta = manifest.meta;
+    // Recency and pins are the sender's preferences.
+    meta.last_opened_at = None;
+    meta.pinned = false;
+    meta.alerts = Default::default();
+    if state.projects.find(&meta.id).is_some() {
+        meta.id = Uuid::new_v4();
+    }
+    let project_dir = state.projects.project_dir(&meta.id);
+    if let Err(err) = extract_bundle(&archive, &project_dir)
+        .and_then(|_| migrate_project(&project_dir, &mut meta))
+    {
+        let _ = fs::remove_dir_all(&project_dir);
+        return Err(AppError::from(
+            err.context("failed to import project bundle"),
+        ));
+    }
+    state
+        .projects
+        .insert(meta.clone())
+        .map_err(AppError::from)?;
+    Ok(ProjectSummary { meta })
+}
@@ -0,0 +1,303 @@
+use std::{
+    collections::{hash_map::Entry, HashMap},
+    fs,
+};
+
+use anyhow::Context;
+use polars::prelude::{BooleanChunked, DataFrame, NewChunkedArray};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    alerts::reset_alert_baseline,
+    error::AppError,
+    ioc::calculate_ioc_applied_records,
+    models::{FlagEntry, ProjectSummary},
+    project_io::{read_project_dataframe, write_project_dataframe, SOURCE_FILE_COLUMN},
+    state::AppState,
+    storage::{
+        clear_ioc_flag_cache, clear_searchable_cache, load_flags, remap_bookmarks, save_flags,
+        BOOKMARKS_FILE, COLUMN_METRICS_FILE,
+    },
+    value_utils::anyvalue_to_search_string,
+};
+
+use super::{encryption::ensure_unsealed, projects::add_row_ids, utils::row_fingerprints};
+
+const DEFAULT_GROUP_LIMIT: usize = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicatesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Columns two rows must agree on; every data column except the source
+    /// file name when omitted or empty.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    /// Groups listed by `find_duplicates`; the counts cover all of them.
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DuplicateGroup {
+    /// Rows holding the same values, ascending; deduplication keeps the first.
+    pub rows: Vec<usize>,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct FindDuplicatesResponse {
+    /// Columns that were compared.
+    pub columns: Vec<String>,
+    pub total_groups: usize,
+    /// Rows deduplication would remove.
+    pub duplicate_rows: usize,
+    /// Largest groups first.
+    pub groups: Vec<DuplicateGroup>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DedupeResponse {
+    pub project: ProjectSummary,
+    pub removed_rows: usize,
+    /// Flags and memos moved from a removed copy onto the row that was kept.
+    pub carried_annotations: usize,
+    /// Annotations of removed copies whose kept row had its own.
+    pub dropped_annotations: usize,
+}
+
+fn comparison_columns(df: &DataFrame, requested: &[String]) -> Result<Vec<String>, AppError> {
+    if requested.is_empty() {
+        return Ok(df
+            .get_column_names()
+            .into_iter()
+            .filter(|name| *name != "__rowid" && *name != SOURCE_FILE_COLUMN)
+            .map(str::to_string)
+            .collect());
+    }
+    if let Some(unknown) = requested
+        .iter()
+        .find(|name| name.as_str() == "__rowid" || df.column(name).is_err())
+    {
+        return Err(AppError::Message(format!("Unknown column '{}'.", unknown)));
+    }
+    Ok(requested.to_vec())
+}
+
+/// Groups of two or more identical rows over `columns`, each ascending, in
+/// order of their first row.
+fn duplicate_groups(df: &DataFrame, columns: &[String]) -> Vec<Vec<usize>> {
+    let mut candidates: HashMap<u64, Vec<usize>> = HashMap::new();
+    for (row, fingerprint) in row_fingerprints(df, columns).into_iter().enumerate() {
+        candidates.entry(fingerprint).or_default().push(row);
+    }
+    // Fingerprints only narrow down the candidates; rows are compared value
+    // by value so a hash collision never merges distinct rows.
+    let series: Vec<_> = columns
+        .iter()
+        .filter_map(|name| df.column(name).ok())
+        .collect();
+    let row_values = |row: usize| -> Vec<Option<String>> {
+        series
+            .iter()
+            .map(|series| {
+                series
+                    .get(row)
+                    .ok()
+                    .and_then(|value| anyvalue_to_search_string(&value))
+            })
+            .collect()
+    };
+    let mut groups = Vec::new();
+    for mut remaining in candidates.into_values().filter(|rows| rows.len() > 1) {
+        while remaining.len() > 1 {
+            let first = row_values(remaining[0]);
+            let (same, other): (Vec<usize>, Vec<usize>) = remaining
+                .into_iter()
+                .partition(|row| row_values(*row) == first);
+            if same.len() > 1 {
+                groups.push(same);
+            }
+            remaining = other;
+        }
+    }
+    groups.sort_by_key(|rows| rows[0]);
+    groups
+}
+
+/// Lists groups of rows that hold the same values, e.g. events exported
+/// twice into merged logs.
+#[tauri::command]
+pub fn find_duplicates(
+    state: State<AppState>,
+    payload: DuplicatesPayload,
+) -> Result<FindDuplicatesResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let parquet_path = state.projects.project_dir(&meta.id).join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
+    let columns = comparison_columns(&df, &payload.columns)?;
+    let mut groups = duplicate_groups(&df, &columns);
+    groups.sort_by(|a, b| b.len().cmp(&a.len()).then(a[0].cmp(&b[0])));
+
+    let total_groups = groups.len();
+    let duplicate_rows = groups.iter().map(|rows| rows.len() - 1).sum();
+    let groups = groups
+        .into_iter()
+        .take(payload.limit.unwrap_or(DEFAULT_GROUP_LIMIT))
+        .map(|rows| DuplicateGroup {
+            count: rows.len(),
+            rows,
+        })
+        .collect();
+    Ok(FindDuplicatesResponse {
+        columns,
+        total_groups,
+        duplicate_rows,
+        groups,
+    })
+}
+
+/// Removes duplicate rows, keeping the first of each group. A removed
+/// copy's flag and memo move onto the kept row unless it has its own, and
+/// every other annotation follows its row to the new position.
+#[tauri::command]
+pub fn dedupe_project(
+    state: State<AppState>,
+    payload: DuplicatesPayload,
+) -> Result<DedupeResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "dedupe_project")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
+    let columns = comparison_columns(&df, &payload.columns)?;
+    let groups = duplicate_groups(&df, &columns);
+    if groups.is_empty() {
+        return Ok(DedupeResponse {
+            project: ProjectSummary { meta },
+            removed_rows: 0,
+            carried_annotations: 0,
+            dropped_annotations: 0,
+        });
+    }
+
+    let flags_path = project_dir.join("flags.json");
+    let mut flags = load_flags(&flags_path).map_err(AppError::from)?;
+    let mut kept_for: HashMap<usize, usize> = HashMap::new();
+    let mut carried_annotations = 0;
+    let mut dropped_annotations = 0;
+    for group in &groups {
+        let kept = group[0];
+        for &copy in &group[1..] {
+            kept_for.insert(copy, kept);
+            let Some(entry) = flags.remove(&copy) else {
+                continue;
+            };
+            match flags.entry(kept) {
+                Entry::Occupied(_) => dropped_annotations += 1,
+                Entry::Vacant(slot) => {
+                    slot.insert(entry);
+                    carried_annotations += 1;
+                }
+            }
+        }
+    }
+
+    let keep: Vec<bool> = (0..df.height())
+        .map(|row| !kept_for.contains_key(&row))
+        .collect();
+    let removed_rows = kept_for.len();
+    let mut new_index = HashMap::with_capacity(df.height() - removed_rows);
+    for (old, _) in keep.iter().enumerate().filter(|(_, kept)| **kept) {
+        new_index.insert(old, new_index.len());
+    }
+    let flags: HashMap<usize, FlagEntry> = flags
+        .into_iter()
+        .filter_map(|(row, entry)| new_index.get(&row).map(|row| (*row, entry)))
+        .collect();
+    let mut deduped = df
+        .filter(&BooleanChunked::from_slice("keep", &keep))
+        .map_err(|err| AppError::Other(err.into()))?;
+    add_row_ids(&mut deduped, 0).map_err(AppError::from)?;
+
+    let staged_path = project_dir.join("data.parquet.dedupe");
+    write_project_dataframe(&staged_path, &mut deduped).map_err(AppError::from)?;
+    fs::rename(&staged_path, &parquet_path)
+        .with_context(|| format!("failed to replace {:?}", parquet_path))
+        .map_err(AppError::from)?;
+    save_flags(&flags_path, &flags).map_err(AppError::from)?;
+    // A bookmark on a removed copy moves to the row that was kept.
+    remap_bookmarks(&project_dir.join(BOOKMARKS_FILE), |row| {
+        let row = kept_for.get(&row).copied().unwrap_or(row);
+        new_index.get(&row).copied()
+    })
+    .map_err(AppError::from)?;
+    let _ = fs::remove_file(project_dir.join(COLUMN_METRICS_FILE));
+    for (cache, result) in [
+        ("searchable", clear_searchable_cache(&project_dir)),
+        ("IOC", clear_ioc_flag_cache(&project_dir)),
+    ] {
+        if let Err(err) = result {
+            tracing::warn!(
+                "failed to clear {} cache for {:?}: {:?}",
+                cache,
+                project_dir,
+                err
+            );
+        }
+    }
+
+    let flagged_records = flags
+        .values()
+        .filter(|entry| !entry.flag.trim().is_empty())
+        .count();
+    let ioc_applied_records =
+        calculate_ioc_applied_records(&project_dir, meta.ioc_policy, meta.unsearched_columns())
+            .map_err(AppError::from)?;
+    state
+        .projects
+        .update_counters(
+            &meta.id,
+            deduped.height(),
+            flagged_records,
+            ioc_applied_records,
+        )
+        .map_err(AppError::from)?;
+    state.changes.flags_updated(meta.id, None);
+    // Row numbers moved, so the rows already reported are recorded afresh.
+    if meta.alerts.enabled() {
+        reset_alert_baseline(&meta, &project_dir).map_err(AppError::from)?;
+    }
+    tracing::info!(
+        "removed {} duplicate rows from project {}",
+        removed_rows,
+        meta.id
+    );
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+    Ok(DedupeResponse {
+        project: ProjectSummary { meta },
+        removed_rows,
+        carried_annotations,
+        dropped_annotations,
+    })
+}
@@ -0,0 +1,198 @@
+use std::fs;
+
+use anyhow::Context;
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    backups::BACKUP_DIR,
+    encryption::{is_sealed, new_encryption, seal_project, unlock_key, unseal_project},
+    error::AppError,
+    models::{ProjectMeta, ProjectSummary},
+    state::AppState,
+};
+
+const MIN_PASSPHRASE_CHARS: usize = 8;
+
+#[derive(Debug, Deserialize)]
+pub struct ProjectPassphrasePayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub passphrase: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LockProjectPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Needed only when the project was not opened with its passphrase in
+    /// this session.
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
+fn find_project(state: &AppState, id: &Uuid) -> Result<ProjectMeta, AppError> {
+    state
+        .projects
+        .find(id)
+        .ok_or(AppError::ProjectNotFound(*id))
+}
+
+/// Rejects work on an encrypted project not opened with its passphrase in
+/// this session, whether its files are sealed or were left unsealed by a run
+/// that crashed; `load_project` opens it with the passphrase.
+pub(super) fn ensure_unsealed(state: &AppState, id: &Uuid) -> Result<(), AppError> {
+    let encrypted = state
+        .projects
+        .find(id)
+        .is_some_and(|meta| meta.encryption.is_some());
+    if is_sealed(&state.projects.project_dir(id)) || (encrypted && !state.unlocked.contains(id)) {
+        return Err(AppError::PassphraseRequired(*id));
+    }
+    Ok(())
+}
+
+/// Unseals an encrypted project for `load_project` and keeps its key until
+/// the project is locked again. Plain projects are left alone.
+pub(super) fn unlock_project(
+    state: &AppState,
+    meta: &ProjectMeta,
+    passphrase: Option<&str>,
+) -> Result<(), AppError> {
+    let Some(settings) = &meta.encryption else {
+        return Ok(());
+    };
+    let project_dir = state.projects.project_dir(&meta.id);
+    if !is_sealed(&project_dir) {
+        // Opened earlier in this session, or left unsealed by a run that
+        // crashed; the key then has to be recovered before the files are
+        // shown again. A passphrase is checked either way.
+        if passphrase.is_none() && state.unlocked.contains(&meta.id) {
+            return Ok(());
+        }
+        let passphrase = passphrase.ok_or(AppError::PassphraseRequired(meta.id))?;
+        let key = unlock_key(settings, passphrase)?;
+        state.unlocked.insert(meta.id, key);
+        return Ok(());
+    }
+    let passphrase = passphrase.ok_or(AppError::PassphraseRequired(meta.id))?;
+    let key = unlock_key(settings, passphrase)?;
+    let _lock = state.locks.rewrite(&meta.id, "unlock_project")?;
+    unseal_project(&project_dir, &key)?;
+    state.unlocked.insert(meta.id, key);
+    tracing::info!("unsealed encrypted project {}", meta.id);
+    Ok(())
+}
+
+/// Encrypts the project's data, flags and IOC rules at rest under a key
+/// derived from `passphrase`. The project is sealed right away and must be
+/// opened with the passphrase from then on.
+#[tauri::command]
+pub fn enable_project_encryption(
+    state: State<AppState>,
+    payload: ProjectPassphrasePayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = find_project(&state, &payload.project_id)?;
+    if meta.encryption.is_some() {
+        return Err(AppError::Message(
+            "The project is already encrypted.".into(),
+        ));
+    }
+    if payload.passphrase.chars().count() < MIN_PASSPHRASE_CHARS {
+        return Err(AppError::Message(format!(
+            "Use a passphrase of at least {} characters.",
+            MIN_PASSPHRASE_CHARS
+        )));
+    }
+    let _lock = state.locks.rewrite(&meta.id, "enable_project_encryption")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+
+    let (settings, key) = new_encryption(&payload.passphrase)?;
+    // The settings are stored first: sealed files without them could never
+    // be opened again.
+    state
+        .projects
+        .update_encryption(&meta.id, Some(settings))
+        .map_err(AppError::from)?;
+    if let Err(err) = seal_project(&project_dir, &key) {
+        if !is_sealed(&project_dir) {
+            if let Err(err) = state.projects.update_encryption(&meta.id, None) {
+                tracing::warn!("failed to reset encryption of {}: {:?}", meta.id, err);
+            }
+        }
+        return Err(AppError::from(err));
+    }
+    // Annotation snapshots hold flags and IOC rules in plain text.
+    let backups = project_dir.join(BACKUP_DIR);
+    if backups.exists() {
+        fs::remove_dir_all(&backups)
+            .with_context(|| format!("failed to remove {:?}", backups))
+            .map_err(AppError::from)?;
+    }
+    state.unlocked.take(&meta.id);
+    tracing::info!("encrypted project {}", meta.id);
+    let meta = find_project(&state, &meta.id)?;
+    Ok(ProjectSummary { meta })
+}
+
+/// Decrypts the project for good after checking the passphrase.
+#[tauri::command]
+pub fn disable_project_encryption(
+    state: State<AppState>,
+    payload: ProjectPassphrasePayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = find_project(&state, &payload.project_id)?;
+    let Some(settings) = &meta.encryption else {
+        return Err(AppError::Message("The project is not encrypted.".into()));
+    };
+    let key = unlock_key(settings, &payload.passphrase)?;
+    let _lock = state
+        .locks
+        .rewrite(&meta.id, "disable_project_encryption")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    if is_sealed(&project_dir) {
+        unseal_project(&project_dir, &key)?;
+    }
+    state
+        .projects
+        .update_encryption(&meta.id, None)
+        .map_err(AppError::from)?;
+    state.unlocked.take(&meta.id);
+    tracing::info!("decrypted project {}", meta.id);
+    let meta = find_project(&state, &meta.id)?;
+    Ok(ProjectSummary { meta })
+}
+
+/// Seals an open encrypted project again, e.g. before leaving the laptop
+/// unattended. Open projects are also sealed when the app exits.
+#[tauri::command]
+pub fn lock_project(
+    state: State<AppState>,
+    payload: LockProjectPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = find_project(&state, &payload.project_id)?;
+    let Some(settings) = &meta.encryption else {
+        return Err(AppError::Message("The project is not encrypted.".into()));
+    };
+    let _lock = state.locks.rewrite(&meta.id, "lock_project")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let held = state.unlocked.take(&meta.id);
+    if !is_sealed(&project_dir) {
+        let key = match (held, payload.passphrase.as_deref()) {
+            (Some(key), _) => key,
+            (None, Some(passphrase)) => unlock_key(settings, passphrase)?,
+            (None, None) => return Err(AppError::PassphraseRequired(meta.id)),
+        };
+        if let Err(err) = seal_project(&project_dir, &key) {
+            state.unlocked.insert(meta.id, key);
+            return Err(AppError::from(err));
+        }
+        tracing::info!("sealed encrypted project {}", meta.id);
+    }
+    Ok(ProjectSummary { meta })
+}
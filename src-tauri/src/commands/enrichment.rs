@@ -0,0 +1,708 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+use polars::prelude::{DataFrame, NamedFrom, Series};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    column_types::timeline_instants,
+    enrichment::{
+        decode_column, expand_json_keys, group_sessions, json_key_counts, rarity_scores,
+        script_input_columns, CompiledScript, Encoding,
+    },
+    error::AppError,
+    expressions::compute_column,
+    models::ProjectSummary,
+    project_io::{
+        read_project_column_subset, read_project_columns, read_project_dataframe,
+        write_project_dataframe,
+    },
+    state::AppState,
+    storage::{clear_ioc_flag_cache, clear_searchable_cache, COLUMN_METRICS_FILE},
+};
+
+use super::{encryption::ensure_unsealed, DEFAULT_PAGE_SIZE};
+
+#[derive(Debug, Deserialize)]
+pub struct ScriptColumnPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Rhai source run once per row.
+    pub script: String,
+    /// Columns the script reads; every data column when omitted.
+    #[serde(rename = "inputColumns", default)]
+    pub input_columns: Option<Vec<String>>,
+    /// Name of the new column.
+    pub column: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DecodedColumnPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Column holding the encoded values.
+    pub source: String,
+    pub encoding: Encoding,
+    /// Name of the new column; `<source>_decoded` when omitted.
+    #[serde(default)]
+    pub column: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ComputedColumnPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Expression computing each row's value, e.g. `end - start`; see
+    /// `crate::expressions` for the syntax.
+    pub expression: String,
+    /// Name of the new column.
+    pub column: String,
+}
+
+/// Idle time that ends a session when the payload sets none.
+const DEFAULT_SESSION_GAP_SECS: u64 = 30 * 60;
+
+#[derive(Debug, Deserialize)]
+pub struct AssignSessionsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Columns whose values together identify who or what a session
+    /// belongs to, e.g. user and host.
+    #[serde(rename = "keyColumns")]
+    pub key_columns: Vec<String>,
+    /// Column holding each row's time; the project's time column when omitted.
+    #[serde(rename = "timeColumn", default)]
+    pub time_column: Option<String>,
+    /// Longest idle time within a session, in seconds.
+    #[serde(rename = "gapSeconds", default)]
+    pub gap_seconds: Option<u64>,
+    /// Name of the new column; `session_id` when omitted.
+    #[serde(default)]
+    pub column: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct AssignSessionsResponse {
+    pub project: ProjectSummary,
+    pub column: String,
+    pub sessions: usize,
+    /// Rows given a session; rows without a key value or a readable time
+    /// are left empty.
+    pub assigned_rows: usize,
+}
+
+/// Values seen at most this often count as rare when the payload sets no
+/// threshold.
+const DEFAULT_RARE_MAX_COUNT: usize = 1;
+/// Rare values listed per column; `rare_values` counts all of them.
+const MAX_RARE_VALUES: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct RareValuesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Columns whose values are counted, e.g. process, parent and command line.
+    pub columns: Vec<String>,
+    /// Values occurring in at most this many rows are rare.
+    #[serde(rename = "maxCount", default)]
+    pub max_count: Option<usize>,
+    /// Rare rows listed; `total_rare_rows` counts all of them.
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Also stores every row's score in a new column.
+    #[serde(rename = "addColumn", default)]
+    pub add_column: bool,
+    /// Name of the new column; `rarity` when omitted.
+    #[serde(default)]
+    pub column: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RareValue {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnRarity {
+    pub column: String,
+    pub distinct_values: usize,
+    pub rare_values: usize,
+    /// Rarest first.
+    pub values: Vec<RareValue>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RareRow {
+    pub row_index: usize,
+    /// Rows sharing this row's rarest value.
+    pub score: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RareValuesResponse {
+    pub project: ProjectSummary,
+    pub max_count: usize,
+    pub columns: Vec<ColumnRarity>,
+    pub total_rare_rows: usize,
+    /// Rarest first, then in row order.
+    pub rows: Vec<RareRow>,
+    /// The column the scores were stored in, when requested.
+    pub column: Option<String>,
+}
+
+/// Most JSON keys listed when the payload sets no limit.
+const DEFAULT_JSON_KEY_LIMIT: usize = 200;
+
+#[derive(Debug, Deserialize)]
+pub struct JsonKeysPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Column holding JSON objects.
+    pub source: String,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonKey {
+    /// Key path, nested keys joined with `.`.
+    pub key: String,
+    /// Rows whose object holds the key.
+    pub count: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct JsonKeysResponse {
+    pub source: String,
+    /// Rows whose value parsed as a JSON object.
+    pub object_rows: usize,
+    pub total_keys: usize,
+    /// Most common first.
+    pub keys: Vec<JsonKey>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExpandJsonPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Column holding JSON objects.
+    pub source: String,
+    /// Key paths to expand, as listed by `list_json_keys`.
+    pub keys: Vec<String>,
+    /// Put in front of each key to name its column; `<source>.` when omitted.
+    #[serde(default)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExpandJsonResponse {
+    pub project: ProjectSummary,
+    /// The new columns, in the order of the keys.
+    pub columns: Vec<String>,
+}
+
+/// Every column name of `df`, `__rowid` included.
+fn column_names(df: &DataFrame) -> Vec<String> {
+    df.get_column_names()
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+fn validate_new_column(existing: &[String], column: &str) -> Result<(), AppError> {
+    if column.is_empty() || column.starts_with("__") {
+        return Err(AppError::Message(
+            "Choose a name for the new column.".into(),
+        ));
+    }
+    if existing
+        .iter()
+        .any(|name| name.eq_ignore_ascii_case(column))
+    {
+        return Err(AppError::Message(format!(
+            "Column '{}' already exists.",
+            column
+        )));
+    }
+    Ok(())
+}
+
+/// Replaces the project data with `df` and drops what was derived from the
+/// old data; the new column is searched like any other from then on.
+fn persist_derived_column(project_dir: &Path, df: &mut DataFrame) -> Result<(), AppError> {
+    let parquet_path = project_dir.join("data.parquet");
+    let staged_path = project_dir.join("data.parquet.enrich");
+    write_project_dataframe(&staged_path, df)?;
+    fs::rename(&staged_path, &parquet_path)
+        .with_context(|| format!("failed to replace {:?}", parquet_path))?;
+    let _ = fs::remove_file(project_dir.join(COLUMN_METRICS_FILE));
+    for (cache, result) in [
+        ("searchable", clear_searchable_cache(project_dir)),
+        ("IOC", clear_ioc_flag_cache(project_dir)),
+    ] {
+        if let Err(err) = result {
+            tracing::warn!(
+                "failed to clear {} cache for {:?}: {:?}",
+                cache,
+                project_dir,
+                err
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Adds a column computed by a Rhai script from each row's values. See
+/// `crate::enrichment` for what scripts see and may do.
+#[tauri::command]
+pub fn add_script_column(
+    state: State<AppState>,
+    payload: ScriptColumnPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "add_script_column")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let script = CompiledScript::compile(&payload.script)
+        .map_err(|err| AppError::Message(format!("Invalid script: {:#}", err)))?;
+
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let column = payload.column.trim();
+    let existing = column_names(&df);
+    validate_new_column(&existing, column)?;
+    let input_columns = script_input_columns(
+        &existing,
+        payload.input_columns.as_deref().unwrap_or_default(),
+    )
+    .map_err(|unknown| AppError::Message(format!("Unknown column '{}'.", unknown)))?;
+
+    let input = df
+        .select(&input_columns)
+        .map_err(|err| AppError::Other(err.into()))?;
+    let series = script
+        .run_column(&input, column)
+        .map_err(|err| AppError::Message(format!("Enrichment script failed: {:#}", err)))?;
+    df.with_column(series)
+        .map_err(|err| AppError::Other(err.into()))?;
+
+    persist_derived_column(&project_dir, &mut df)?;
+    tracing::info!("added script column {:?} to project {}", column, meta.id);
+    Ok(ProjectSummary { meta })
+}
+
+/// Adds a column holding the base64- or hex-decoded text of another column.
+/// Decoding is best effort: values that do not decode to readable text are
+/// left empty, and encoded tokens inside longer values (such as the
+/// argument of `powershell -enc`) are decoded on their own.
+#[tauri::command]
+pub fn add_decoded_column(
+    state: State<AppState>,
+    payload: DecodedColumnPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "add_decoded_column")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let existing = column_names(&df);
+    let source = payload.source.trim();
+    if source.starts_with("__") || !existing.iter().any(|name| name == source) {
+        return Err(AppError::Message(format!("Unknown column '{}'.", source)));
+    }
+    let column = payload
+        .column
+        .as_deref()
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{}_decoded", source));
+    validate_new_column(&existing, &column)?;
+
+    let source_series = df
+        .column(source)
+        .map_err(|err| AppError::Other(err.into()))?;
+    let series = decode_column(source_series, payload.encoding, &column);
+    df.with_column(series)
+        .map_err(|err| AppError::Other(err.into()))?;
+
+    persist_derived_column(&project_dir, &mut df)?;
+    tracing::info!(
+        "added decoded column {:?} from {:?} to project {}",
+        column,
+        source,
+        meta.id
+    );
+    Ok(ProjectSummary { meta })
+}
+
+/// Adds a column computed from other columns by an expression, such as the
+/// file name of a path or the seconds between two times.
+#[tauri::command]
+pub fn add_computed_column(
+    state: State<AppState>,
+    payload: ComputedColumnPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "add_computed_column")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let column = payload.column.trim();
+    validate_new_column(&column_names(&df), column)?;
+    let series = compute_column(&df, &payload.expression, column)
+        .map_err(|err| AppError::Message(format!("Invalid expression: {:#}", err)))?;
+    df.with_column(series)
+        .map_err(|err| AppError::Other(err.into()))?;
+
+    persist_derived_column(&project_dir, &mut df)?;
+    tracing::info!("added computed column {:?} to project {}", column, meta.id);
+    Ok(ProjectSummary { meta })
+}
+
+/// Lists the keys found in a column of JSON objects, such as the event
+/// data of EDR exports, so the ones worth a column of their own can be
+/// picked.
+#[tauri::command]
+pub fn list_json_keys(
+    state: State<AppState>,
+    payload: JsonKeysPayload,
+) -> Result<JsonKeysResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let parquet_path = state.projects.project_dir(&meta.id).join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let source = payload.source.trim().to_string();
+    let columns = read_project_columns(&parquet_path)?;
+    if source.starts_with("__") || !columns.contains(&source) {
+        return Err(AppError::Message(format!("Unknown column '{}'.", source)));
+    }
+    let df = read_project_column_subset(&parquet_path, std::slice::from_ref(&source))?;
+    let series = df
+        .column(&source)
+        .map_err(|err| AppError::Other(err.into()))?;
+    let (counts, object_rows) = json_key_counts(series);
+    let total_keys = counts.len();
+    let mut keys: Vec<JsonKey> = counts
+        .into_iter()
+        .map(|(key, count)| JsonKey { key, count })
+        .collect();
+    keys.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.key.cmp(&b.key)));
+    keys.truncate(payload.limit.unwrap_or(DEFAULT_JSON_KEY_LIMIT));
+    Ok(JsonKeysResponse {
+        source,
+        object_rows,
+        total_keys,
+        keys,
+    })
+}
+
+/// Expands chosen keys of a column of JSON objects into flat columns that
+/// can be searched, sorted and filtered by like any other. The JSON column
+/// itself is kept.
+#[tauri::command]
+pub fn expand_json_column(
+    state: State<AppState>,
+    payload: ExpandJsonPayload,
+) -> Result<ExpandJsonResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "expand_json_column")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let mut existing = column_names(&df);
+    let source = payload.source.trim();
+    if source.starts_with("__") || !existing.iter().any(|name| name == source) {
+        return Err(AppError::Message(format!("Unknown column '{}'.", source)));
+    }
+    let mut keys: Vec<String> = Vec::new();
+    for key in &payload.keys {
+        let key = key.trim();
+        if !key.is_empty() && !keys.iter().any(|known| known == key) {
+            keys.push(key.to_string());
+        }
+    }
+    if keys.is_empty() {
+        return Err(AppError::Message("Choose the keys to expand.".into()));
+    }
+    let prefix = payload.prefix.unwrap_or_else(|| format!("{}.", source));
+    let columns: Vec<String> = keys
+        .iter()
+        .map(|key| format!("{}{}", prefix, key))
+        .collect();
+    for column in &columns {
+        validate_new_column(&existing, column)?;
+        existing.push(column.clone());
+    }
+
+    let source_series = df
+        .column(source)
+        .map_err(|err| AppError::Other(err.into()))?;
+    for series in expand_json_keys(source_series, &keys, &columns) {
+        df.with_column(series)
+            .map_err(|err| AppError::Other(err.into()))?;
+    }
+
+    persist_derived_column(&project_dir, &mut df)?;
+    tracing::info!(
+        "expanded {} JSON keys of {:?} in project {}",
+        keys.len(),
+        source,
+        meta.id
+    );
+    Ok(ExpandJsonResponse {
+        project: ProjectSummary { meta },
+        columns,
+    })
+}
+
+/// Adds a column numbering the sessions rows belong to: rows sharing the
+/// key columns' values form one session until the key is idle for longer
+/// than the gap. Sessions are numbered in order of their first row in time.
+#[tauri::command]
+pub fn assign_sessions(
+    state: State<AppState>,
+    payload: AssignSessionsPayload,
+) -> Result<AssignSessionsResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "assign_sessions")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    if payload.key_columns.is_empty() {
+        return Err(AppError::Message(
+            "Choose the columns that identify a session.".into(),
+        ));
+    }
+    let gap_seconds = payload.gap_seconds.unwrap_or(DEFAULT_SESSION_GAP_SECS);
+    if gap_seconds == 0 {
+        return Err(AppError::Message(
+            "The session gap must be at least one second.".into(),
+        ));
+    }
+
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let existing = column_names(&df);
+    let time_column = payload
+        .time_column
+        .or_else(|| meta.timestamp_column.clone())
+        .ok_or_else(|| AppError::Message("Choose the column holding each row's time.".into()))?;
+    if let Some(unknown) = payload
+        .key_columns
+        .iter()
+        .chain(std::iter::once(&time_column))
+        .find(|name| name.starts_with("__") || !existing.contains(name))
+    {
+        return Err(AppError::Message(format!("Unknown column '{}'.", unknown)));
+    }
+    let column = payload
+        .column
+        .as_deref()
+        .map(str::trim)
+        .filter(|column| !column.is_empty())
+        .unwrap_or("session_id")
+        .to_string();
+    validate_new_column(&existing, &column)?;
+
+    let instants = df
+        .column(&time_column)
+        .map_err(anyhow::Error::from)
+        .and_then(timeline_instants)?;
+    let keys = payload
+        .key_columns
+        .iter()
+        .map(|name| df.column(name))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| AppError::Other(err.into()))?;
+    let assignment = group_sessions(&keys, &instants, gap_seconds as i64 * 1_000_000);
+    let assigned_rows = assignment
+        .session_ids
+        .iter()
+        .filter(|id| id.is_some())
+        .count();
+    df.with_column(Series::new(&column, assignment.session_ids))
+        .map_err(|err| AppError::Other(err.into()))?;
+
+    persist_derived_column(&project_dir, &mut df)?;
+    tracing::info!(
+        "assigned {} sessions over {:?} to project {}",
+        assignment.sessions,
+        payload.key_columns,
+        meta.id
+    );
+    Ok(AssignSessionsResponse {
+        project: ProjectSummary { meta },
+        column,
+        sessions: assignment.sessions,
+        assigned_rows,
+    })
+}
+
+/// Counts how often each value of the chosen columns occurs and lists the
+/// rows holding a rare one, rarest first. With `addColumn`, every row's
+/// score (the row count of its rarest value) is also stored in a new
+/// column, so sorting on it brings the outliers to the top.
+#[tauri::command]
+pub fn find_rare_values(
+    state: State<AppState>,
+    payload: RareValuesPayload,
+) -> Result<RareValuesResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = if payload.add_column {
+        state.locks.rewrite(&meta.id, "find_rare_values")?
+    } else {
+        state.locks.read(&meta.id)?
+    };
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    if payload.columns.is_empty() {
+        return Err(AppError::Message(
+            "Choose the columns to count values in.".into(),
+        ));
+    }
+    let max_count = payload.max_count.unwrap_or(DEFAULT_RARE_MAX_COUNT);
+    if max_count == 0 {
+        return Err(AppError::Message(
+            "The rarity threshold must be at least one row.".into(),
+        ));
+    }
+
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let existing = column_names(&df);
+    if let Some(unknown) = payload
+        .columns
+        .iter()
+        .find(|name| name.starts_with("__") || !existing.contains(name))
+    {
+        return Err(AppError::Message(format!("Unknown column '{}'.", unknown)));
+    }
+    let column = payload.add_column.then(|| {
+        payload
+            .column
+            .as_deref()
+            .map(str::trim)
+            .filter(|column| !column.is_empty())
+            .unwrap_or("rarity")
+            .to_string()
+    });
+    if let Some(column) = &column {
+        validate_new_column(&existing, column)?;
+    }
+
+    let series = payload
+        .columns
+        .iter()
+        .map(|name| df.column(name))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|err| AppError::Other(err.into()))?;
+    let scores = rarity_scores(&series);
+    let columns = scores
+        .columns
+        .into_iter()
+        .map(|frequencies| {
+            let distinct_values = frequencies.counts.len();
+            let mut values: Vec<RareValue> = frequencies
+                .counts
+                .into_iter()
+                .filter(|(_, count)| *count <= max_count)
+                .map(|(value, count)| RareValue { value, count })
+                .collect();
+            values.sort_by(|a, b| a.count.cmp(&b.count).then_with(|| a.value.cmp(&b.value)));
+            ColumnRarity {
+                column: frequencies.column,
+                distinct_values,
+                rare_values: values.len(),
+                values: values.into_iter().take(MAX_RARE_VALUES).collect(),
+            }
+        })
+        .collect();
+    let mut rare_rows: Vec<RareRow> = scores
+        .scores
+        .iter()
+        .enumerate()
+        .filter_map(|(row_index, score)| {
+            score
+                .filter(|score| *score <= max_count)
+                .map(|score| RareRow { row_index, score })
+        })
+        .collect();
+    rare_rows.sort_by_key(|row| (row.score, row.row_index));
+    let total_rare_rows = rare_rows.len();
+    rare_rows.truncate(payload.limit.unwrap_or(DEFAULT_PAGE_SIZE));
+
+    if let Some(column) = &column {
+        let values: Vec<Option<i64>> = scores
+            .scores
+            .iter()
+            .map(|score| score.map(|score| score as i64))
+            .collect();
+        df.with_column(Series::new(column, values))
+            .map_err(|err| AppError::Other(err.into()))?;
+        persist_derived_column(&project_dir, &mut df)?;
+        tracing::info!(
+            "added rarity column {:?} over {:?} to project {}",
+            column,
+            payload.columns,
+            meta.id
+        );
+    }
+    Ok(RareValuesResponse {
+        project: ProjectSummary { meta },
+        max_count,
+        columns,
+        total_rare_rows,
+        rows: rare_rows,
+        column,
+    })
+}
@@ -0,0 +1,327 @@
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap},
+};
+
+use chrono::{DateTime, Utc};
+use polars::prelude::Series;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    entities::{contains_value, load_entity_index, EntityKind},
+    error::AppError,
+    flags::normalize_flag_value,
+    ioc::{
+        append_memo_tags, compile_iocs, load_ioc_entries, order_iocs_for_policy,
+        resolve_ioc_matches,
+    },
+    models::ProjectRow,
+    project_io::{read_project_column_subset, read_project_columns, read_project_rows},
+    state::AppState,
+    storage::load_flags,
+    value_utils::{anyvalue_to_search_string, normalize_search_text},
+};
+
+use super::{
+    encryption::ensure_unsealed,
+    utils::{build_row_search_text, collect_row_record_from_series, time_display},
+    DEFAULT_PAGE_SIZE,
+};
+
+/// Rows returned per entity for pivoting; `row_count` covers all of them.
+const MAX_ENTITY_ROWS: usize = 100;
+const DEFAULT_ENTITY_LIMIT: usize = 500;
+
+#[derive(Debug, Deserialize)]
+pub struct ListEntitiesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(default)]
+    pub kinds: Vec<EntityKind>,
+    /// Case-insensitive substring the value must contain.
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+    /// Rescans the data even when the stored index is current.
+    #[serde(default)]
+    pub rebuild: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EntitySummary {
+    pub kind: EntityKind,
+    pub value: String,
+    pub row_count: usize,
+    /// The first rows the value appears in.
+    pub rows: Vec<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListEntitiesResponse {
+    pub built_at: DateTime<Utc>,
+    /// Text columns that were scanned.
+    pub columns: Vec<String>,
+    /// Entities matching the filters, before `limit` is applied.
+    pub total_entities: usize,
+    pub entities: Vec<EntitySummary>,
+}
+
+/// Lists the IPs, domains, hashes and email addresses found in the project,
+/// most frequent first. The index is built on first use and whenever the
+/// data changed since.
+#[tauri::command]
+pub fn list_entities(
+    state: State<AppState>,
+    payload: ListEntitiesPayload,
+) -> Result<ListEntitiesResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let index = load_entity_index(&project_dir, payload.rebuild).map_err(AppError::from)?;
+
+    let search = payload
+        .search
+        .as_deref()
+        .map(|text| text.trim().to_lowercase())
+        .filter(|text| !text.is_empty());
+    let matching: Vec<_> = index
+        .entities
+        .into_iter()
+        .filter(|entity| payload.kinds.is_empty() || payload.kinds.contains(&entity.kind))
+        .filter(|entity| match search.as_deref() {
+            Some(search) => entity.value.contains(search),
+            None => true,
+        })
+        .collect();
+    let total_entities = matching.len();
+    let entities = matching
+        .into_iter()
+        .take(payload.limit.unwrap_or(DEFAULT_ENTITY_LIMIT))
+        .map(|entity| EntitySummary {
+            kind: entity.kind,
+            value: entity.value,
+            row_count: entity.rows.len(),
+            rows: entity.rows.into_iter().take(MAX_ENTITY_ROWS).collect(),
+        })
+        .collect();
+    Ok(ListEntitiesResponse {
+        built_at: index.built_at,
+        columns: index.columns,
+        total_entities,
+        entities,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct PivotEntityPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// An extracted entity or any literal value.
+    pub value: String,
+    /// Columns to look in; every column when omitted or empty.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+/// Rows per effective flag; rows without one count as `unflagged`.
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct FlagBreakdown {
+    pub critical: usize,
+    pub suspicious: usize,
+    pub safe: usize,
+    pub unflagged: usize,
+}
+
+impl FlagBreakdown {
+    pub(crate) fn add(&mut self, flag: &str) {
+        match flag {
+            "critical" => self.critical += 1,
+            "suspicious" => self.suspicious += 1,
+            "safe" => self.safe += 1,
+            _ => self.unflagged += 1,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct PivotColumn {
+    pub column: String,
+    pub row_count: usize,
+    pub flags: FlagBreakdown,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PivotEntityResponse {
+    pub value: String,
+    /// Every matching row, ascending.
+    pub row_indices: Vec<usize>,
+    pub flags: FlagBreakdown,
+    /// Columns the value was found in, most rows first.
+    pub columns: Vec<PivotColumn>,
+    /// The requested page of matching rows, with their effective flag.
+    pub rows: Vec<ProjectRow>,
+    pub offset: usize,
+}
+
+/// Finds every row containing `value` as a whole token, grouped by the
+/// column it appears in, with the effective flag of each row counted. Flags
+/// set by IOC rules are included, as in `query_project_rows`.
+#[tauri::command]
+pub fn pivot_entity(
+    state: State<AppState>,
+    payload: PivotEntityPayload,
+) -> Result<PivotEntityResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let value = normalize_search_text(payload.value.trim());
+    if value.is_empty() {
+        return Err(AppError::Message("Enter a value to pivot on.".into()));
+    }
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+
+    let column_names = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let search_columns: Vec<String> = if payload.columns.is_empty() {
+        column_names.clone()
+    } else {
+        if let Some(unknown) = payload
+            .columns
+            .iter()
+            .find(|column| !column_names.contains(column))
+        {
+            return Err(AppError::Message(format!("Unknown column: {}", unknown)));
+        }
+        payload.columns.clone()
+    };
+    let df = read_project_column_subset(&parquet_path, &search_columns).map_err(AppError::from)?;
+
+    let mut rows_by_column: BTreeMap<&str, Vec<usize>> = BTreeMap::new();
+    let mut row_indices: Vec<usize> = Vec::new();
+    for row_idx in 0..df.height() {
+        let mut matched = false;
+        for series in df.get_columns() {
+            let hit = series
+                .get(row_idx)
+                .ok()
+                .and_then(|cell| anyvalue_to_search_string(&cell))
+                .is_some_and(|text| contains_value(&normalize_search_text(&text), &value));
+            if hit {
+                rows_by_column
+                    .entry(series.name())
+                    .or_default()
+                    .push(row_idx);
+                matched = true;
+            }
+        }
+        if matched {
+            row_indices.push(row_idx);
+        }
+    }
+
+    // Only the matching rows are read in full, to resolve their flags.
+    let flags = load_flags(&project_dir.join("flags.json")).map_err(AppError::from)?;
+    let iocs = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+    let ordered_iocs = order_iocs_for_policy(&iocs, meta.ioc_policy);
+    let compiled_iocs = compile_iocs(&ordered_iocs);
+    let ioc_columns: Vec<String> = column_names
+        .iter()
+        .filter(|name| !meta.unsearched_columns().contains(name))
+        .cloned()
+        .collect();
+    let matched_df =
+        read_project_rows(&parquet_path, &column_names, &row_indices).map_err(AppError::from)?;
+    let series_map: HashMap<&str, &Series> = matched_df
+        .get_columns()
+        .iter()
+        .map(|series| (series.name(), series))
+        .collect();
+
+    let offset = payload.offset.unwrap_or(0);
+    let limit = payload.limit.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
+    let display = time_display(&meta, &state.settings);
+    let mut flag_by_row: HashMap<usize, String> = HashMap::with_capacity(row_indices.len());
+    let mut breakdown = FlagBreakdown::default();
+    let mut rows = Vec::new();
+    for (position, &row_idx) in row_indices.iter().enumerate() {
+        let entry = flags.get(&row_idx);
+        let mut flag = entry
+            .map(|entry| normalize_flag_value(&entry.flag))
+            .unwrap_or_default();
+        let mut memo = entry
+            .and_then(|entry| entry.memo.clone())
+            .unwrap_or_default();
+        if flag.is_empty() && !compiled_iocs.is_empty() {
+            let (row_text, single_per_col) =
+                build_row_search_text(&ioc_columns, &series_map, position);
+            let resolution = resolve_ioc_matches(
+                compiled_iocs
+                    .iter()
+                    .filter(|ioc| ioc.matches_row(&row_text, &single_per_col))
+                    .map(|ioc| ioc.entry),
+                meta.ioc_policy,
+            );
+            flag = resolution.flag;
+            append_memo_tags(&mut memo, &resolution.tags);
+        }
+        breakdown.add(&flag);
+        if position >= offset && rows.len() < limit {
+            rows.push(ProjectRow {
+                row_index: row_idx,
+                data: collect_row_record_from_series(
+                    &series_map,
+                    &column_names,
+                    position,
+                    display.as_ref(),
+                ),
+                flag: flag.clone(),
+                memo: Some(memo).filter(|memo| !memo.is_empty()),
+            });
+        }
+        flag_by_row.insert(row_idx, flag);
+    }
+
+    let mut columns: Vec<PivotColumn> = rows_by_column
+        .into_iter()
+        .map(|(column, column_rows)| {
+            let mut flags = FlagBreakdown::default();
+            for row_idx in &column_rows {
+                flags.add(flag_by_row.get(row_idx).map_or("", String::as_str));
+            }
+            PivotColumn {
+                column: column.to_string(),
+                row_count: column_rows.len(),
+                flags,
+            }
+        })
+        .collect();
+    columns.sort_by_key(|column| Reverse(column.row_count));
+
+    Ok(PivotEntityResponse {
+        value,
+        row_indices,
+        flags: breakdown,
+        columns,
+        rows,
+        offset,
+    })
+}
@@ -1,178 +1,853 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::fs::{self, File};
-use std::io::BufWriter;
-use std::path::PathBuf;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::time::Instant;
 
 use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime, SecondsFormat, Utc};
+use encoding_rs::SHIFT_JIS;
 use polars::prelude::*;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use tauri::State;
 use uuid::Uuid;
 
 use crate::{
+    column_types::{detect_timestamp_column, parse_datetime},
     error::AppError,
-    flags::{normalize_flag_value, severity_rank},
-    ioc::load_ioc_entries,
-    project_io::read_project_dataframe,
-    search::{build_search_mask_boolean, to_rpn, tokenize_search_query, SearchToken},
+    export_parts::{part_path, row_parts, write_line_parts, SplitLimits},
+    flags::normalize_flag_value,
+    ioc::{
+        append_memo_tags, compile_iocs, load_ioc_entries, order_iocs_for_policy,
+        resolve_ioc_matches,
+    },
+    models::{FlagEntry, IocEntry, ProjectMeta},
+    perf::Operation,
+    project_io::{read_project_dataframe, write_project_dataframe},
+    redaction::{project_redaction_key, redact_series, RedactionMode},
+    settings::CsvEncoding,
     state::AppState,
     storage::load_flags,
+    value_utils::{anyvalue_to_json, anyvalue_to_search_string},
+    xlsx_writer::{write_xlsx, RowHighlight},
 };
 
-use super::utils::build_row_search_text;
+use super::{
+    encryption::ensure_unsealed,
+    rows::{build_search_mask, matches_flag_filter, sort_row_indices, SearchColumns},
+    utils::{add_alias_lookups, build_row_search_text},
+};
+
+/// File format written by `export_project`.
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ExportFormat {
+    #[default]
+    Csv,
+    /// One JSON object per line with `trivium-flag`, `trivium-memo` and
+    /// `trivium-tags` ahead of the data columns.
+    Jsonl,
+    /// Workbook with suspicious and critical rows colour-filled.
+    Xlsx,
+    /// Parquet keeping the column types of the project data.
+    Parquet,
+    /// JSONL as Timesketch ingests it, with `message`, `datetime` and
+    /// `timestamp_desc` built from the timeline mapping.
+    Timesketch,
+}
+
+impl CsvEncoding {
+    fn bom(self) -> &'static [u8] {
+        match self {
+            CsvEncoding::Utf8Bom => b"\xEF\xBB\xBF",
+            CsvEncoding::Utf16Le => b"\xFF\xFE",
+            CsvEncoding::Utf8 | CsvEncoding::ShiftJis => b"",
+        }
+    }
+
+    /// Converts CSV text rendered as UTF-8 into this encoding.
+    fn encode(self, utf8: Vec<u8>) -> Result<Vec<u8>, AppError> {
+        if matches!(self, CsvEncoding::Utf8 | CsvEncoding::Utf8Bom) {
+            return Ok(utf8);
+        }
+        let text = String::from_utf8(utf8).map_err(|e| AppError::Other(e.into()))?;
+        Ok(match self {
+            CsvEncoding::Utf16Le => text.encode_utf16().flat_map(u16::to_le_bytes).collect(),
+            _ => {
+                let (encoded, _, unmappable) = SHIFT_JIS.encode(&text);
+                if !unmappable {
+                    return Ok(encoded.into_owned());
+                }
+                // Encode per character so unmappable ones become `?` rather
+                // than the HTML references encoding_rs substitutes.
+                let mut out = Vec::with_capacity(text.len());
+                let mut buffer = [0u8; 4];
+                for ch in text.chars() {
+                    let (encoded, _, unmappable) = SHIFT_JIS.encode(ch.encode_utf8(&mut buffer));
+                    if unmappable {
+                        out.push(b'?');
+                    } else {
+                        out.extend_from_slice(&encoded);
+                    }
+                }
+                out
+            }
+        })
+    }
+}
 
 #[derive(Debug, Deserialize)]
 pub struct ExportProjectPayload {
     #[serde(rename = "projectId")]
     pub project_id: Uuid,
     pub destination: String,
+    #[serde(default)]
+    pub format: ExportFormat,
+    /// Search query of the current view; only matching rows are exported.
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default, rename = "wholeWord")]
+    pub whole_word: bool,
+    #[serde(default, rename = "flagFilter")]
+    pub flag_filter: Option<String>,
+    #[serde(default, rename = "sortKey")]
+    pub sort_key: Option<String>,
+    #[serde(default, rename = "sortDirection")]
+    pub sort_direction: Option<String>,
+    /// Data columns to write, in this order; all of them when absent.
+    #[serde(default)]
+    pub columns: Option<Vec<String>>,
+    /// Leaves out the columns hidden in the project view.
+    #[serde(default, rename = "excludeHidden")]
+    pub exclude_hidden: bool,
+    /// Timesketch only: column holding each event's time; defaults to the
+    /// project's timeline column.
+    #[serde(default, rename = "timestampColumn")]
+    pub timestamp_column: Option<String>,
+    /// Timesketch only: columns joined into `message`; all data columns as
+    /// `name: value` pairs when absent.
+    #[serde(default, rename = "messageColumns")]
+    pub message_columns: Option<Vec<String>>,
+    /// Timesketch only: `timestamp_desc`, the timestamp column name by default.
+    #[serde(default, rename = "timestampDesc")]
+    pub timestamp_desc: Option<String>,
+    /// Starts a new numbered part file after this many rows.
+    #[serde(default, rename = "splitRows")]
+    pub split_rows: Option<usize>,
+    /// Starts a new numbered part file before a part would exceed this many
+    /// bytes; CSV and JSONL formats only.
+    #[serde(default, rename = "splitBytes")]
+    pub split_bytes: Option<u64>,
+    /// CSV only: output encoding; the encoding chosen in the settings, UTF-8
+    /// without a BOM unless changed there, when omitted.
+    #[serde(default)]
+    pub encoding: Option<CsvEncoding>,
+    /// CSV only: field separator, a single ASCII character (`\t` for tabs).
+    #[serde(default)]
+    pub delimiter: Option<String>,
+    /// Columns to mask or replace with a keyed hash, see `redaction`.
+    #[serde(default)]
+    pub redact: BTreeMap<String, RedactionMode>,
+    /// Key for hashed columns; the project's own key when absent.
+    #[serde(default, rename = "redactionKey")]
+    pub redaction_key: Option<String>,
 }
 
-/// Exports the project data with derived trivium columns to a CSV file.
-#[tauri::command]
-pub fn export_project(state: State<AppState>, payload: ExportProjectPayload) -> Result<(), String> {
-    let meta = state
-        .projects
-        .find(&payload.project_id)
-        .ok_or_else(|| AppError::Message("Project not found.".into()))?;
-    let project_dir = state.projects.project_dir(&meta.id);
-    let parquet_path = project_dir.join("data.parquet");
-    let mut df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
-    let flags_path = project_dir.join("flags.json");
-    let flags = load_flags(&flags_path).map_err(AppError::from)?;
-    let iocs = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+#[derive(Debug, Serialize)]
+pub struct ExportProjectResponse {
+    pub rows_written: usize,
+    /// Rows left out because the format needs a value they lack, e.g. a
+    /// readable timestamp for Timesketch.
+    pub rows_skipped: usize,
+    /// Files written: the destination, or its numbered parts when split.
+    pub files: Vec<String>,
+}
+
+/// Fields Timesketch derives from the mapping; data columns of the same name
+/// are written with a `source_` prefix.
+const TIMESKETCH_FIELDS: [&str; 4] = ["message", "datetime", "timestamp", "timestamp_desc"];
 
-    let mut safe_flags: Vec<i32> = vec![0; df.height()];
-    let mut suspicious_flags: Vec<i32> = vec![0; df.height()];
-    let mut critical_flags: Vec<i32> = vec![0; df.height()];
-    let mut memo_series: Vec<String> = vec![String::new(); df.height()];
+/// Flag, memo and IOC tags of one exported row.
+#[derive(Clone)]
+pub(super) struct RowAnnotation {
+    pub flag: String,
+    pub memo: String,
+    pub tags: Vec<String>,
+}
 
+/// Resolves each row's user flag, falling back to the IOC verdict.
+fn annotate_rows(
+    df: &DataFrame,
+    meta: &ProjectMeta,
+    flags: &HashMap<usize, FlagEntry>,
+    iocs: &[IocEntry],
+) -> Vec<RowAnnotation> {
     let column_names: Vec<String> = df
         .get_column_names()
         .iter()
         .map(|s| s.to_string())
+        .filter(|name| !meta.unsearched_columns().contains(name))
         .collect();
     let column_series: HashMap<&str, &Series> =
         df.get_columns().iter().map(|s| (s.name(), s)).collect();
 
-    for i in 0..df.height() {
-        let mut ioc_flag = String::new();
-        let mut ioc_rank = 0;
-        let mut memo_tags = Vec::new();
-
-        if !iocs.is_empty() {
-            let (row_text, single_per_col) =
-                build_row_search_text(&column_names, &column_series, i);
-            let single_row = vec![row_text];
-            for ioc_entry in &iocs {
-                let query = ioc_entry.query.trim();
-                if query.is_empty() {
-                    continue;
-                }
-                let tokens = tokenize_search_query(query);
-                let mut terms: Vec<(Option<String>, String)> = Vec::new();
-                for t in &tokens {
-                    if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } =
-                        t
-                    {
-                        let key = (col.clone(), text.clone());
-                        if !text.is_empty() && !terms.contains(&key) {
-                            terms.push(key);
-                        }
-                    }
-                }
-                if terms.is_empty() {
-                    continue;
-                }
-                let rpn = to_rpn(&tokens);
-                let mask =
-                    build_search_mask_boolean(&rpn, &terms, &single_row, Some(&single_per_col));
-                let row_matches = mask.get(0).copied().unwrap_or(false);
-
-                if row_matches {
-                    let severity = normalize_flag_value(&ioc_entry.flag);
-                    let severity_rank_value = severity_rank(&severity);
-                    if severity_rank_value > ioc_rank {
-                        ioc_rank = severity_rank_value;
-                        ioc_flag = severity.clone();
-                    }
-                    let tag = ioc_entry.tag.trim();
-                    if !tag.is_empty() {
-                        let token = format!("[{}]", tag);
-                        if !memo_tags.contains(&token) {
-                            memo_tags.push(token);
-                        }
-                    }
-                }
+    let ordered_iocs = order_iocs_for_policy(iocs, meta.ioc_policy);
+    let compiled_iocs = compile_iocs(&ordered_iocs);
+
+    (0..df.height())
+        .map(|i| {
+            let mut ioc_flag = String::new();
+            let mut tags = Vec::new();
+
+            if !iocs.is_empty() {
+                let (row_text, single_per_col) =
+                    build_row_search_text(&column_names, &column_series, i);
+                let resolution = resolve_ioc_matches(
+                    compiled_iocs
+                        .iter()
+                        .filter(|ioc| ioc.matches_row(&row_text, &single_per_col))
+                        .map(|ioc| ioc.entry),
+                    meta.ioc_policy,
+                );
+                ioc_flag = resolution.flag;
+                tags = resolution.tags;
             }
-        }
 
-        let final_flag: String;
-        let mut final_memo: String;
+            match flags.get(&i) {
+                Some(user_entry) => RowAnnotation {
+                    flag: normalize_flag_value(user_entry.flag.trim()),
+                    memo: user_entry.memo.clone().unwrap_or_default(),
+                    tags,
+                },
+                None => RowAnnotation {
+                    flag: ioc_flag,
+                    memo: String::new(),
+                    tags,
+                },
+            }
+        })
+        .collect()
+}
 
-        if let Some(user_entry) = flags.get(&i) {
-            final_flag = normalize_flag_value(user_entry.flag.trim());
-            final_memo = user_entry.memo.clone().unwrap_or_default();
-        } else {
-            final_flag = ioc_flag;
-            final_memo = String::new();
-        }
+/// Picks the rows of the view described by the payload's search, flag
+/// filter and sort, in display order. `None` when every row is exported in
+/// stored order.
+fn select_view_rows(
+    df: &DataFrame,
+    meta: &ProjectMeta,
+    annotations: &[RowAnnotation],
+    payload: &ExportProjectPayload,
+) -> Option<Vec<usize>> {
+    let search = payload
+        .search
+        .as_deref()
+        .map(str::trim)
+        .filter(|search| !search.is_empty());
+    let flag_filter = payload
+        .flag_filter
+        .as_deref()
+        .filter(|filter| *filter != "all");
+    if search.is_none() && flag_filter.is_none() && payload.sort_key.is_none() {
+        return None;
+    }
 
-        for tag in memo_tags {
-            if !final_memo.contains(&tag) {
-                if !final_memo.is_empty() && !final_memo.ends_with(' ') {
-                    final_memo.push(' ');
-                }
-                final_memo.push_str(&tag);
+    let search_mask = search.and_then(|search| {
+        let columns: Vec<String> = df
+            .get_column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect();
+        let series: HashMap<&str, &Series> =
+            df.get_columns().iter().map(|s| (s.name(), s)).collect();
+        let mut series_lower: HashMap<String, &Series> = df
+            .get_columns()
+            .iter()
+            .map(|s| (s.name().to_lowercase(), s))
+            .collect();
+        add_alias_lookups(&mut series_lower, &meta.column_aliases);
+        build_search_mask(
+            search,
+            payload.whole_word,
+            &SearchColumns {
+                rows: df.height(),
+                columns: &columns,
+                series: &series,
+                series_lower: &series_lower,
+            },
+            &mut None,
+            &mut false,
+            &mut HashMap::new(),
+        )
+    });
+
+    let mut indices: Vec<usize> = (0..df.height()).collect();
+    if let Some(sort_key) = &payload.sort_key {
+        sort_row_indices(
+            df,
+            &mut indices,
+            sort_key,
+            payload.sort_direction.as_deref() == Some("desc"),
+        );
+    }
+    indices.retain(|&index| {
+        flag_filter.is_none_or(|filter| matches_flag_filter(&annotations[index].flag, filter))
+            && search_mask.as_ref().is_none_or(|mask| mask[index])
+    });
+    Some(indices)
+}
+
+/// Narrows the data to the requested columns, without the hidden ones when
+/// asked to.
+fn select_export_columns(
+    df: DataFrame,
+    meta: &ProjectMeta,
+    payload: &ExportProjectPayload,
+) -> Result<DataFrame, AppError> {
+    let mut columns: Vec<String> = match &payload.columns {
+        Some(requested) => {
+            if let Some(missing) = requested
+                .iter()
+                .find(|column| df.column(column.as_str()).is_err())
+            {
+                return Err(AppError::Message(format!(
+                    "Column {} was not found in the project data.",
+                    missing
+                )));
             }
+            requested.clone()
         }
+        None => df
+            .get_column_names()
+            .iter()
+            .map(|name| name.to_string())
+            .collect(),
+    };
+    if payload.exclude_hidden {
+        columns.retain(|column| !meta.hidden_columns.contains(column));
+    }
+    if columns.is_empty() {
+        return Err(AppError::Message("No columns left to export.".into()));
+    }
+    df.select(columns).map_err(|e| AppError::Other(e.into()))
+}
 
-        match final_flag.as_str() {
-            "safe" => safe_flags[i] = 1,
-            "suspicious" => suspicious_flags[i] = 1,
-            "critical" => critical_flags[i] = 1,
-            _ => {}
+/// Masks or hashes the columns listed in `redact`, before anything, such as
+/// a Timesketch message, is built from them.
+pub(super) fn redact_columns(
+    df: &mut DataFrame,
+    project_dir: &Path,
+    redact: &BTreeMap<String, RedactionMode>,
+    key: Option<&str>,
+) -> Result<(), AppError> {
+    if redact.is_empty() {
+        return Ok(());
+    }
+    let key = match key.map(str::trim).filter(|key| !key.is_empty()) {
+        Some(key) => key.to_string(),
+        None => project_redaction_key(project_dir)?,
+    };
+    for (column, mode) in redact {
+        let series = df.column(column).map_err(|_| {
+            AppError::Message(format!(
+                "Column {} was not found in the project data.",
+                column
+            ))
+        })?;
+        let redacted = redact_series(series, *mode, &key)?;
+        df.replace(column, redacted)
+            .map_err(|e| AppError::Other(e.into()))?;
+    }
+    Ok(())
+}
+
+/// Renames the exported columns that have an alias to it.
+fn apply_column_aliases(mut df: DataFrame, meta: &ProjectMeta) -> Result<DataFrame, AppError> {
+    for (column, alias) in &meta.column_aliases {
+        if df.column(column).is_ok() {
+            df.rename(column, alias)
+                .map_err(|e| AppError::Other(e.into()))?;
         }
-        memo_series[i] = final_memo;
     }
+    Ok(df)
+}
+
+pub(super) fn create_export_dir(destination: &Path) -> Result<(), AppError> {
+    if let Some(parent) = destination.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("failed to create export dir {:?}", parent))?;
+    }
+    Ok(())
+}
+
+pub(super) fn create_export_file(destination: &Path) -> Result<BufWriter<File>, AppError> {
+    create_export_dir(destination)?;
+    let file = File::create(destination)
+        .with_context(|| format!("failed to create export file {:?}", destination))?;
+    Ok(BufWriter::new(file))
+}
+
+/// Puts `trivium-safe/suspicious/critical` indicator columns and the memo
+/// (with IOC tags appended) in front of the data.
+fn with_trivium_columns(
+    df: &DataFrame,
+    annotations: &[RowAnnotation],
+) -> Result<DataFrame, AppError> {
+    let indicator = |flag: &str| -> Vec<i32> {
+        annotations
+            .iter()
+            .map(|row| i32::from(row.flag == flag))
+            .collect()
+    };
+    let memo_series: Vec<String> = annotations
+        .iter()
+        .map(|row| {
+            let mut memo = row.memo.clone();
+            append_memo_tags(&mut memo, &row.tags);
+            memo
+        })
+        .collect();
+
+    let mut out_cols: Vec<Series> = vec![
+        Series::new("trivium-safe", indicator("safe")),
+        Series::new("trivium-suspicious", indicator("suspicious")),
+        Series::new("trivium-critical", indicator("critical")),
+        Series::new("trivium-memo", memo_series),
+    ];
+    out_cols.extend(df.get_columns().iter().cloned());
+    DataFrame::new(out_cols).map_err(|e| AppError::Other(e.into()))
+}
+
+fn split_limits(payload: &ExportProjectPayload) -> Result<SplitLimits, AppError> {
+    if payload.split_rows == Some(0) || payload.split_bytes == Some(0) {
+        return Err(AppError::Message(
+            "Split sizes must be greater than zero.".into(),
+        ));
+    }
+    let limits = SplitLimits {
+        max_rows: payload.split_rows,
+        max_bytes: payload.split_bytes,
+    };
+    if limits.max_bytes.is_some()
+        && matches!(payload.format, ExportFormat::Xlsx | ExportFormat::Parquet)
+    {
+        return Err(AppError::Message(
+            "Excel and Parquet exports can only be split by row count.".into(),
+        ));
+    }
+    Ok(limits)
+}
+
+/// Destination of each part of a format written as a whole file, with the
+/// rows it holds.
+fn whole_file_parts(
+    destination: &Path,
+    limits: SplitLimits,
+    rows: usize,
+) -> Result<Vec<(PathBuf, std::ops::Range<usize>)>, AppError> {
+    create_export_dir(destination)?;
+    Ok(row_parts(rows, limits.max_rows)
+        .into_iter()
+        .enumerate()
+        .map(|(index, range)| {
+            let path = if limits.is_split() {
+                part_path(destination, index + 1)
+            } else {
+                destination.to_path_buf()
+            };
+            (path, range)
+        })
+        .collect())
+}
 
-    if let Ok(next) = df.drop("__rowid") {
-        df = next;
+/// Encoding and separator of a CSV export; rejected for other formats.
+fn csv_options(
+    payload: &ExportProjectPayload,
+    default_encoding: CsvEncoding,
+) -> Result<(CsvEncoding, u8), AppError> {
+    let delimiter = match payload.delimiter.as_deref() {
+        None | Some("") => b',',
+        Some("\\t") | Some("tab") => b'\t',
+        Some(text) => match text.as_bytes() {
+            [byte] if byte.is_ascii() && !matches!(byte, b'"' | b'\r' | b'\n') => *byte,
+            _ => {
+                return Err(AppError::Message(format!(
+                    "The CSV delimiter must be a single character, got {:?}.",
+                    text
+                )))
+            }
+        },
+    };
+    if payload.format != ExportFormat::Csv
+        && (payload
+            .encoding
+            .is_some_and(|encoding| encoding != CsvEncoding::Utf8)
+            || delimiter != b',')
+    {
+        return Err(AppError::Message(
+            "Encoding and delimiter options apply to CSV exports only.".into(),
+        ));
     }
-    if let Ok(next) = df.drop("flag") {
-        df = next;
+    Ok((payload.encoding.unwrap_or(default_encoding), delimiter))
+}
+
+fn write_csv_export(
+    df: &DataFrame,
+    annotations: &[RowAnnotation],
+    destination: &Path,
+    limits: SplitLimits,
+    (encoding, delimiter): (CsvEncoding, u8),
+) -> Result<Vec<PathBuf>, AppError> {
+    let df_out = with_trivium_columns(df, annotations)?;
+    let mut header_text = Vec::new();
+    CsvWriter::new(&mut header_text)
+        .with_separator(delimiter)
+        .finish(&mut df_out.head(Some(0)))
+        .context("failed to write export CSV")?;
+    // Every part starts with the byte order mark so each opens on its own.
+    let mut header = encoding.bom().to_vec();
+    header.extend(encoding.encode(header_text)?);
+    create_export_dir(destination)?;
+    let paths = write_line_parts(destination, limits, &header, df_out.height(), |range| {
+        let mut chunk = Vec::new();
+        CsvWriter::new(&mut chunk)
+            .include_header(false)
+            .with_separator(delimiter)
+            .finish(&mut df_out.slice(range.start as i64, range.len()))?;
+        Ok(encoding.encode(chunk)?)
+    })
+    .context("failed to write export CSV")?;
+    Ok(paths)
+}
+
+fn write_xlsx_export(
+    df: &DataFrame,
+    annotations: &[RowAnnotation],
+    destination: &Path,
+    limits: SplitLimits,
+) -> Result<Vec<PathBuf>, AppError> {
+    let df_out = with_trivium_columns(df, annotations)?;
+    let highlights: Vec<RowHighlight> = annotations
+        .iter()
+        .map(|row| match row.flag.as_str() {
+            "critical" => RowHighlight::Critical,
+            "suspicious" => RowHighlight::Suspicious,
+            _ => RowHighlight::None,
+        })
+        .collect();
+    let parts = whole_file_parts(destination, limits, df_out.height())?;
+    for (path, range) in &parts {
+        let part = df_out.slice(range.start as i64, range.len());
+        write_xlsx(path, &part, &highlights[range.clone()])
+            .context("failed to write export workbook")?;
     }
-    if let Ok(next) = df.drop("memo") {
-        df = next;
+    Ok(parts.into_iter().map(|(path, _)| path).collect())
+}
+
+fn write_parquet_export(
+    df: &DataFrame,
+    annotations: &[RowAnnotation],
+    destination: &Path,
+    limits: SplitLimits,
+) -> Result<Vec<PathBuf>, AppError> {
+    let df_out = with_trivium_columns(df, annotations)?;
+    let parts = whole_file_parts(destination, limits, df_out.height())?;
+    for (path, range) in &parts {
+        let mut part = df_out.slice(range.start as i64, range.len());
+        write_project_dataframe(path, &mut part).context("failed to write export Parquet")?;
     }
+    Ok(parts.into_iter().map(|(path, _)| path).collect())
+}
+
+/// Event time and message of one row as Timesketch sees them.
+struct TimelineEvent {
+    time: Option<NaiveDateTime>,
+    message: String,
+}
 
-    let mut out_cols: Vec<Series> = Vec::new();
-    out_cols.push(Series::new("trivium-safe", safe_flags));
-    out_cols.push(Series::new("trivium-suspicious", suspicious_flags));
-    out_cols.push(Series::new("trivium-critical", critical_flags));
-    out_cols.push(Series::new("trivium-memo", memo_series));
-    for name in df.get_column_names() {
-        if let Ok(series) = df.column(name) {
-            out_cols.push(series.clone());
+/// The project's timeline column: the one it was given, else the first
+/// date/time typed column, else the first column named like a timestamp.
+pub(super) fn timeline_column(df: &DataFrame, meta: &ProjectMeta) -> Option<String> {
+    if let Some(column) = &meta.timestamp_column {
+        if df.column(column).is_ok() {
+            return Some(column.clone());
         }
     }
-    df = DataFrame::new(out_cols).map_err(|e| AppError::Other(e.into()))?;
+    detect_timestamp_column(&df.schema())
+}
 
-    let destination = PathBuf::from(payload.destination);
-    if let Some(parent) = destination.parent() {
-        fs::create_dir_all(parent)
-            .with_context(|| format!("failed to create export dir {:?}", parent))
-            .map_err(AppError::from)?;
-    }
-
-    let file = File::create(&destination)
-        .with_context(|| format!("failed to create export file {:?}", destination))
-        .map_err(AppError::from)?;
-    let mut writer = CsvWriter::new(BufWriter::new(file));
-    let mut df_out = df.clone();
-    writer
-        .finish(&mut df_out)
-        .context("failed to write export CSV")
-        .map_err(AppError::from)?;
+/// Reads the timestamp and builds the message of every row from the
+/// Timesketch mapping in the payload.
+fn timeline_events(
+    df: &DataFrame,
+    meta: &ProjectMeta,
+    payload: &ExportProjectPayload,
+) -> Result<(String, Vec<TimelineEvent>), AppError> {
+    let timestamp_column = payload
+        .timestamp_column
+        .clone()
+        .or_else(|| timeline_column(df, meta))
+        .ok_or_else(|| {
+            AppError::Message("Choose the timestamp column for the Timesketch export.".into())
+        })?;
+    let column = |name: &str| {
+        df.column(name).map_err(|_| {
+            AppError::Message(format!(
+                "Column {} was not found in the project data.",
+                name
+            ))
+        })
+    };
+    let timestamps = column(&timestamp_column)?;
+    let message_series: Vec<&Series> = match &payload.message_columns {
+        Some(names) => names
+            .iter()
+            .map(|name| column(name))
+            .collect::<Result<_, _>>()?,
+        None => df.get_columns().iter().collect(),
+    };
+    let labelled = payload.message_columns.is_none();
+
+    let events = (0..df.height())
+        .map(|row| {
+            let text = |series: &Series| {
+                series
+                    .get(row)
+                    .ok()
+                    .and_then(|value| anyvalue_to_search_string(&value))
+                    .filter(|text| !text.trim().is_empty())
+            };
+            let parts: Vec<String> = message_series
+                .iter()
+                .filter_map(|series| {
+                    text(series).map(|value| {
+                        if labelled {
+                            format!("{}: {}", series.name(), value)
+                        } else {
+                            value
+                        }
+                    })
+                })
+                .collect();
+            TimelineEvent {
+                time: text(timestamps).and_then(|value| parse_datetime(&value)),
+                message: parts.join(" | "),
+            }
+        })
+        .collect();
+    Ok((timestamp_column, events))
+}
+
+/// Writes the rows with a readable timestamp in Timesketch's JSONL layout;
+/// returns the files and how many rows were written.
+fn write_timesketch_export(
+    df: &DataFrame,
+    annotations: &[RowAnnotation],
+    events: &[TimelineEvent],
+    timestamp_desc: &str,
+    destination: &Path,
+    limits: SplitLimits,
+) -> Result<(Vec<PathBuf>, usize), AppError> {
+    let columns = df.get_columns();
+    let renamed: Vec<String> = columns
+        .iter()
+        .map(|series| {
+            if TIMESKETCH_FIELDS.contains(&series.name()) {
+                format!("source_{}", series.name())
+            } else {
+                series.name().to_string()
+            }
+        })
+        .collect();
+    let timed: Vec<(usize, DateTime<Utc>)> = events
+        .iter()
+        .enumerate()
+        .filter_map(|(i, event)| event.time.map(|time| (i, time.and_utc())))
+        .collect();
+    create_export_dir(destination)?;
+    let paths = write_line_parts(destination, limits, b"", timed.len(), |range| {
+        let mut chunk = Vec::new();
+        for &(i, time) in &timed[range] {
+            let row = &annotations[i];
+            let mut fields: Vec<(&str, Value)> = vec![
+                ("message", Value::from(events[i].message.as_str())),
+                (
+                    "datetime",
+                    Value::from(time.to_rfc3339_opts(SecondsFormat::Micros, false)),
+                ),
+                ("timestamp", Value::from(time.timestamp_micros())),
+                ("timestamp_desc", Value::from(timestamp_desc)),
+                ("trivium-flag", Value::from(row.flag.as_str())),
+                ("trivium-memo", Value::from(row.memo.as_str())),
+                ("trivium-tags", Value::from(row.tags.clone())),
+            ];
+            for (series, name) in columns.iter().zip(&renamed) {
+                fields.push((name.as_str(), anyvalue_to_json(&series.get(i)?)));
+            }
+            write_json_line(&mut chunk, &fields)?;
+        }
+        Ok(chunk)
+    })
+    .context("failed to write Timesketch export")?;
+    Ok((paths, timed.len()))
+}
+
+/// Writes one JSON object per row. Keys are written by hand so they keep the
+/// column order instead of being sorted.
+fn write_jsonl_export(
+    df: &DataFrame,
+    annotations: &[RowAnnotation],
+    destination: &Path,
+    limits: SplitLimits,
+) -> Result<Vec<PathBuf>, AppError> {
+    let columns = df.get_columns();
+    create_export_dir(destination)?;
+    let paths = write_line_parts(destination, limits, b"", annotations.len(), |range| {
+        let mut chunk = Vec::new();
+        for i in range {
+            let row = &annotations[i];
+            let mut fields: Vec<(&str, Value)> = vec![
+                ("trivium-flag", Value::from(row.flag.as_str())),
+                ("trivium-memo", Value::from(row.memo.as_str())),
+                ("trivium-tags", Value::from(row.tags.clone())),
+            ];
+            for series in columns {
+                fields.push((series.name(), anyvalue_to_json(&series.get(i)?)));
+            }
+            write_json_line(&mut chunk, &fields)?;
+        }
+        Ok(chunk)
+    })
+    .context("failed to write export JSONL")?;
+    Ok(paths)
+}
+
+fn write_json_line(writer: &mut impl Write, fields: &[(&str, Value)]) -> anyhow::Result<()> {
+    writer.write_all(b"{")?;
+    for (index, (key, value)) in fields.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(b",")?;
+        }
+        serde_json::to_writer(&mut *writer, key)?;
+        writer.write_all(b":")?;
+        serde_json::to_writer(&mut *writer, value)?;
+    }
+    writer.write_all(b"}\n")?;
     Ok(())
 }
+
+/// Loads a project's data without internal columns, together with each
+/// row's resolved flag, memo and IOC tags.
+pub(super) fn load_annotated_project(
+    state: &AppState,
+    project_id: &Uuid,
+) -> Result<(ProjectMeta, DataFrame, Vec<RowAnnotation>), AppError> {
+    let meta = state
+        .projects
+        .find(project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(*project_id))?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    let mut df = read_project_dataframe(&parquet_path)?;
+    let flags_path = project_dir.join("flags.json");
+    let flags = load_flags(&flags_path)?;
+    let iocs = load_ioc_entries(&project_dir)?;
+
+    let annotations = annotate_rows(&df, &meta, &flags, &iocs);
+
+    for internal in ["__rowid", "flag", "memo"] {
+        if let Ok(next) = df.drop(internal) {
+            df = next;
+        }
+    }
+    Ok((meta, df, annotations))
+}
+
+/// Exports the project data with derived trivium columns as CSV, JSONL, an
+/// Excel workbook or Parquet, optionally split into numbered parts.
+#[tauri::command]
+pub fn export_project(
+    state: State<AppState>,
+    payload: ExportProjectPayload,
+) -> Result<ExportProjectResponse, AppError> {
+    let _span = tracing::debug_span!("export_project", project = %payload.project_id).entered();
+    let started = Instant::now();
+    ensure_unsealed(&state, &payload.project_id)?;
+    let _lock = state.locks.read(&payload.project_id)?;
+    let (meta, mut df, mut annotations) = load_annotated_project(&state, &payload.project_id)?;
+    let rows_scanned = df.height();
+
+    if let Some(indices) = select_view_rows(&df, &meta, &annotations, &payload) {
+        let take_idx =
+            UInt32Chunked::from_iter_values("take_idx", indices.iter().map(|&idx| idx as u32));
+        df = df.take(&take_idx).map_err(|e| AppError::Other(e.into()))?;
+        annotations = indices
+            .iter()
+            .map(|&index| annotations[index].clone())
+            .collect();
+    }
+    redact_columns(
+        &mut df,
+        &state.projects.project_dir(&meta.id),
+        &payload.redact,
+        payload.redaction_key.as_deref(),
+    )?;
+    // The mapping may use columns that are not exported themselves.
+    let timeline = match payload.format {
+        ExportFormat::Timesketch => Some(timeline_events(&df, &meta, &payload)?),
+        _ => None,
+    };
+    df = select_export_columns(df, &meta, &payload)?;
+    df = apply_column_aliases(df, &meta)?;
+
+    let destination = PathBuf::from(&payload.destination);
+    let limits = split_limits(&payload)?;
+    let csv = csv_options(&payload, state.settings.export_encoding())?;
+    let rows = df.height();
+    let (files, rows_written) = match payload.format {
+        ExportFormat::Csv => (
+            write_csv_export(&df, &annotations, &destination, limits, csv)?,
+            rows,
+        ),
+        ExportFormat::Jsonl => (
+            write_jsonl_export(&df, &annotations, &destination, limits)?,
+            rows,
+        ),
+        ExportFormat::Xlsx => (
+            write_xlsx_export(&df, &annotations, &destination, limits)?,
+            rows,
+        ),
+        ExportFormat::Parquet => (
+            write_parquet_export(&df, &annotations, &destination, limits)?,
+            rows,
+        ),
+        ExportFormat::Timesketch => {
+            let (timestamp_column, events) = timeline.unwrap_or_default();
+            let timestamp_desc = payload
+                .timestamp_desc
+                .as_deref()
+                .map(str::trim)
+                .filter(|desc| !desc.is_empty())
+                .unwrap_or(&timestamp_column);
+            write_timesketch_export(
+                &df,
+                &annotations,
+                &events,
+                timestamp_desc,
+                &destination,
+                limits,
+            )?
+        }
+    };
+    state
+        .perf
+        .record(Operation::Export, started.elapsed(), rows_scanned);
+    Ok(ExportProjectResponse {
+        rows_written,
+        rows_skipped: rows - rows_written,
+        files: files
+            .iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect(),
+    })
+}
@@ -1,19 +1,23 @@
-use serde::Deserialize;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
 use tauri::State;
 use uuid::Uuid;
 
 use crate::{
+    alerts,
     error::AppError,
-    ioc::calculate_ioc_applied_records,
-    models::{FlagEntry, ProjectRow},
-    project_io::read_project_dataframe,
+    flags::{memo_term_frequencies, render_comments_memo},
+    ioc::{adjust_ioc_applied_records, calculate_ioc_applied_records},
+    models::{FlagEntry, ProjectRow, RowComment},
+    project_io::{read_project_columns, read_project_rows},
     state::AppState,
-    storage::{
-        clear_ioc_flag_cache, clear_searchable_cache, count_flagged, remove_flag, upsert_flag,
-    },
+    storage::{count_flagged, load_flag, load_flags, remove_flag, upsert_flag},
 };
 
-use super::utils::collect_row_record;
+use super::{
+    encryption::ensure_unsealed,
+    utils::{collect_row_record, time_display},
+};
 
 #[derive(Debug, Deserialize)]
 pub struct UpdateFlagPayload {
@@ -24,11 +28,52 @@ pub struct UpdateFlagPayload {
     pub memo: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct RowCommentsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "rowIndex")]
+    pub row_index: usize,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AddRowCommentPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "rowIndex")]
+    pub row_index: usize,
+    pub text: String,
+    #[serde(default)]
+    pub author: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteRowCommentPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "rowIndex")]
+    pub row_index: usize,
+    /// Position of the comment in the row's list, oldest first.
+    pub index: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RowCommentsResponse {
+    pub row_index: usize,
+    pub comments: Vec<RowComment>,
+    /// The row's memo, rendered from its comments when it has any.
+    pub memo: Option<String>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct HiddenColumnsPayload {
     #[serde(rename = "projectId")]
     pub project_id: Uuid,
     pub hidden_columns: Vec<String>,
+    /// Whether search and IOC rules skip the hidden columns; unchanged when
+    /// omitted.
+    #[serde(rename = "searchVisibleOnly", default)]
+    pub search_visible_only: Option<bool>,
 }
 
 /// Applies or clears a user flag for a single row and updates counters.
@@ -36,86 +81,283 @@ pub struct HiddenColumnsPayload {
 pub fn update_flag(
     state: State<AppState>,
     payload: UpdateFlagPayload,
-) -> Result<ProjectRow, String> {
-    let Some(_) = state.projects.find(&payload.project_id) else {
-        return Err(AppError::Message("Project not found.".into()).into());
+) -> Result<ProjectRow, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
     };
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "update_flag")?;
     let project_dir = state.projects.project_dir(&payload.project_id);
+    // Only the edited row is read back, through a `__rowid` filter, so the
+    // edit costs the same whatever the project's size. A row outside the
+    // data fails here, before anything is written.
+    let parquet_path = project_dir.join("data.parquet");
+    let column_names = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let row_df = read_project_rows(&parquet_path, &column_names, &[payload.row_index])
+        .map_err(AppError::from)?;
+    let record = collect_row_record(
+        &row_df,
+        &column_names,
+        0,
+        time_display(&meta, &state.settings).as_ref(),
+    );
+
     let flags_path = project_dir.join("flags.json");
+    let (previous_flag, comments) = load_flag(&flags_path, payload.row_index)
+        .map_err(AppError::from)?
+        .map(|entry| (entry.flag, entry.comments))
+        .unwrap_or_default();
+    // Rows with comments keep them; their memo stays the comments' summary.
+    let memo = if comments.is_empty() {
+        payload.memo
+    } else {
+        render_comments_memo(&comments)
+    };
     let should_clear = payload.flag.trim().is_empty()
-        && payload
-            .memo
-            .as_ref()
-            .map(|m| m.trim().is_empty())
-            .unwrap_or(true);
+        && memo.as_ref().map(|m| m.trim().is_empty()).unwrap_or(true);
 
     if should_clear {
         remove_flag(&flags_path, payload.row_index).map_err(AppError::from)?;
     } else {
         let entry = FlagEntry {
             flag: payload.flag.clone(),
-            memo: payload.memo.clone(),
+            memo: memo.clone(),
+            comments,
         };
         upsert_flag(&flags_path, payload.row_index, &entry).map_err(AppError::from)?;
     }
 
-    if let Err(err) = clear_ioc_flag_cache(&project_dir) {
-        eprintln!(
-            "[cache] failed to clear IOC cache for {:?}: {:?}",
-            project_dir, err
-        );
-    }
-
     let flagged_records = count_flagged(&flags_path).map_err(AppError::from)?;
     state
         .projects
         .update_flagged_records(&payload.project_id, flagged_records)
         .map_err(AppError::from)?;
 
-    let ioc_applied_records =
-        calculate_ioc_applied_records(&project_dir).map_err(AppError::from)?;
+    // Only this row's user flag changed, so the IOC count moves by this row
+    // alone; the cached IOC flags are keyed by the user flags and rebuilt
+    // from the stored rule matches on the next query.
+    let ioc_applied_records = adjust_ioc_applied_records(
+        &project_dir,
+        meta.ioc_policy,
+        meta.unsearched_columns(),
+        meta.ioc_applied_records,
+        payload.row_index,
+        &previous_flag,
+        if should_clear { "" } else { &payload.flag },
+    )
+    .map_err(AppError::from)?;
     state
         .projects
         .update_ioc_applied_records(&payload.project_id, ioc_applied_records)
         .map_err(AppError::from)?;
-
-    let parquet_path = project_dir.join("data.parquet");
-    let df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
-    let column_names: Vec<String> = df
-        .get_column_names()
-        .iter()
-        .filter(|name| **name != "__rowid")
-        .map(|name| name.to_string())
-        .collect();
-    let record = collect_row_record(&df, &column_names, payload.row_index);
+    state
+        .changes
+        .flags_updated(payload.project_id, Some(vec![payload.row_index]));
 
     Ok(ProjectRow {
         row_index: payload.row_index,
         data: record,
         flag: payload.flag,
-        memo: payload.memo,
+        memo,
     })
 }
 
-/// Persists the set of hidden columns for a project and resets search cache.
+/// Persists the set of hidden columns for a project. Cached search text
+/// records the hidden columns it was built under and is rebuilt on change.
+/// When search skips hidden columns and the skipped set changes, the IOC
+/// counts are recomputed.
 #[tauri::command]
 pub fn set_hidden_columns(
     state: State<AppState>,
     payload: HiddenColumnsPayload,
-) -> Result<(), String> {
-    let Some(_) = state.projects.find(&payload.project_id) else {
-        return Err(AppError::Message("Project not found.".into()).into());
+) -> Result<(), AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
     };
-    let project_dir = state.projects.project_dir(&payload.project_id);
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
     state
         .projects
-        .update_hidden_columns(&payload.project_id, payload.hidden_columns)
+        .update_hidden_columns(
+            &payload.project_id,
+            payload.hidden_columns,
+            payload.search_visible_only,
+        )
+        .map_err(AppError::from)?;
+    let Some(updated) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    if updated.unsearched_columns() != meta.unsearched_columns() {
+        let project_dir = state.projects.project_dir(&updated.id);
+        let ioc_applied_records = calculate_ioc_applied_records(
+            &project_dir,
+            updated.ioc_policy,
+            updated.unsearched_columns(),
+        )
         .map_err(AppError::from)?;
-    if let Err(err) = clear_searchable_cache(&project_dir) {
-        eprintln!(
-            "[cache] failed to clear searchable cache for {:?}: {:?}",
-            project_dir, err
-        );
+        state
+            .projects
+            .update_ioc_applied_records(&updated.id, ioc_applied_records)
+            .map_err(AppError::from)?;
+        alerts::check_project(&state, &updated.id);
     }
     Ok(())
 }
+
+#[derive(Debug, Deserialize)]
+pub struct MemoTermFrequenciesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct MemoTermFrequency {
+    pub term: String,
+    /// Total occurrences across all memos.
+    pub count: usize,
+    /// Number of memos mentioning the term.
+    pub memos: usize,
+}
+
+/// Tokenizes every memo in the project and returns term frequencies, most frequent first.
+#[tauri::command]
+pub fn get_memo_term_frequencies(
+    state: State<AppState>,
+    payload: MemoTermFrequenciesPayload,
+) -> Result<Vec<MemoTermFrequency>, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let flags = load_flags(&project_dir.join("flags.json")).map_err(AppError::from)?;
+    let memos = flags.values().filter_map(|entry| entry.memo.as_deref());
+
+    let mut frequencies = memo_term_frequencies(memos);
+    if let Some(limit) = payload.limit {
+        frequencies.truncate(limit);
+    }
+    Ok(frequencies
+        .into_iter()
+        .map(|(term, count, memos)| MemoTermFrequency { term, count, memos })
+        .collect())
+}
+
+/// Returns a row's comments, oldest first.
+#[tauri::command]
+pub fn list_row_comments(
+    state: State<AppState>,
+    payload: RowCommentsPayload,
+) -> Result<RowCommentsResponse, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let flags_path = state.projects.project_dir(&meta.id).join("flags.json");
+    let entry = load_flag(&flags_path, payload.row_index).map_err(AppError::from)?;
+    Ok(RowCommentsResponse {
+        row_index: payload.row_index,
+        comments: entry
+            .as_ref()
+            .map(|entry| entry.comments.clone())
+            .unwrap_or_default(),
+        memo: entry.and_then(|entry| entry.memo),
+    })
+}
+
+/// Appends a timestamped comment to a row and re-renders its memo. A memo
+/// written before the row had comments becomes its first comment.
+#[tauri::command]
+pub fn add_row_comment(
+    state: State<AppState>,
+    payload: AddRowCommentPayload,
+) -> Result<RowCommentsResponse, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "add_row_comment")?;
+    if payload.row_index >= meta.total_records {
+        return Err(AppError::Message(
+            "Row is out of range for this project.".into(),
+        ));
+    }
+    let text = payload.text.trim();
+    if text.is_empty() {
+        return Err(AppError::Message("Enter a comment.".into()));
+    }
+    let flags_path = state.projects.project_dir(&meta.id).join("flags.json");
+    let mut entry = load_flag(&flags_path, payload.row_index)
+        .map_err(AppError::from)?
+        .unwrap_or(FlagEntry {
+            flag: String::new(),
+            memo: None,
+            comments: Vec::new(),
+        });
+    let now = Utc::now();
+    if entry.comments.is_empty() {
+        if let Some(memo) = entry.memo.take().filter(|memo| !memo.trim().is_empty()) {
+            entry.comments.push(RowComment {
+                author: None,
+                timestamp: now,
+                text: memo,
+            });
+        }
+    }
+    entry.comments.push(RowComment {
+        author: payload
+            .author
+            .map(|author| author.trim().to_string())
+            .filter(|author| !author.is_empty()),
+        timestamp: now,
+        text: text.to_string(),
+    });
+    entry.memo = render_comments_memo(&entry.comments);
+    upsert_flag(&flags_path, payload.row_index, &entry).map_err(AppError::from)?;
+    state
+        .changes
+        .flags_updated(meta.id, Some(vec![payload.row_index]));
+    Ok(RowCommentsResponse {
+        row_index: payload.row_index,
+        comments: entry.comments,
+        memo: entry.memo,
+    })
+}
+
+/// Deletes one comment from a row; a row left without flag or comments
+/// loses its entry.
+#[tauri::command]
+pub fn delete_row_comment(
+    state: State<AppState>,
+    payload: DeleteRowCommentPayload,
+) -> Result<RowCommentsResponse, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "delete_row_comment")?;
+    let flags_path = state.projects.project_dir(&meta.id).join("flags.json");
+    let mut entry = load_flag(&flags_path, payload.row_index)
+        .map_err(AppError::from)?
+        .filter(|entry| payload.index < entry.comments.len())
+        .ok_or_else(|| AppError::Message("Comment not found.".into()))?;
+    entry.comments.remove(payload.index);
+    entry.memo = render_comments_memo(&entry.comments);
+    if entry.flag.trim().is_empty() && entry.comments.is_empty() {
+        remove_flag(&flags_path, payload.row_index).map_err(AppError::from)?;
+    } else {
+        upsert_flag(&flags_path, payload.row_index, &entry).map_err(AppError::from)?;
+    }
+    state
+        .changes
+        .flags_updated(meta.id, Some(vec![payload.row_index]));
+    Ok(RowCommentsResponse {
+        row_index: payload.row_index,
+        comments: entry.comments,
+        memo: entry.memo,
+    })
+}
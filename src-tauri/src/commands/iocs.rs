@@ -1,20 +1,35 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use serde::Deserialize;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
 use tauri::State;
 use uuid::Uuid;
 
 use crate::{
+    alerts::{self, reset_alert_baseline},
     error::AppError,
     ioc::{
         calculate_ioc_applied_records, load_ioc_entries, prepare_ioc_entries, read_ioc_csv,
-        save_ioc_entries, write_ioc_csv,
+        save_ioc_entries, stored_ioc_rule_hits, validate_ioc_entries, write_ioc_csv, CollapsedIoc,
+        IocCsvMapping,
+    },
+    misp::read_misp_event,
+    models::{AlertSettings, IocConflictPolicy, IocEntry, ProjectMeta, ProjectSummary},
+    project_io::{read_project_columns, read_project_row_count},
+    sigma::{
+        load_sigma_mapping, read_sigma_rules, save_sigma_mapping, SigmaFieldMapping,
+        SkippedSigmaRule,
     },
-    models::IocEntry,
     state::AppState,
+    stix::{build_stix_bundle, SkippedStixRule},
     storage::clear_ioc_flag_cache,
 };
 
+use super::{
+    encryption::ensure_unsealed,
+    rows::{explain_query, ExplainSearchPayload, WarningKind},
+};
+
 #[derive(Debug, Deserialize)]
 pub struct SaveIocsPayload {
     #[serde(rename = "projectId")]
@@ -22,11 +37,99 @@ pub struct SaveIocsPayload {
     pub entries: Vec<IocEntry>,
 }
 
+#[derive(Debug, Serialize)]
+pub struct SaveIocsResponse {
+    pub entries: Vec<IocEntry>,
+    /// Duplicate or equivalent rules that were merged while saving.
+    pub collapsed: Vec<CollapsedIoc>,
+    pub report: IocSaveReport,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IocRuleIssue {
+    /// Position of the rule in the saved `entries`.
+    pub index: usize,
+    pub tag: String,
+    pub message: String,
+}
+
+/// Saved rules that likely do not match what they were written for.
+#[derive(Debug, Default, Serialize)]
+pub struct IocSaveReport {
+    /// Rules that are malformed or not read the way they look, such as a
+    /// dangling `|` or a bare `AND`.
+    pub invalid_syntax: Vec<IocRuleIssue>,
+    /// Rules naming columns the project does not have.
+    pub unknown_columns: Vec<IocRuleIssue>,
+    /// Enabled rules that match no row of the project.
+    pub zero_hits: Vec<IocRuleIssue>,
+}
+
 #[derive(Debug, Deserialize)]
 pub struct ImportIocsPayload {
     #[serde(rename = "projectId")]
     pub project_id: Uuid,
     pub path: String,
+    /// CSV columns for rule fields whose header is not recognized; unused
+    /// for MISP events.
+    #[serde(default)]
+    pub mapping: IocCsvMapping,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IocGroupPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub group: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IocGroupEnabledPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub group: String,
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProjectIocsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportSigmaPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub paths: Vec<String>,
+    /// Replaces the project's saved field mapping when provided.
+    #[serde(default)]
+    pub mapping: Option<SigmaFieldMapping>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ImportSigmaResponse {
+    pub entries: Vec<IocEntry>,
+    pub imported: usize,
+    pub skipped: Vec<SkippedSigmaRule>,
+    pub collapsed: Vec<CollapsedIoc>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct IocPolicyPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub policy: IocConflictPolicy,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ProjectAlertsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "webhookUrl", default)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub notify: bool,
 }
 
 #[derive(Debug, Deserialize)]
@@ -36,77 +139,359 @@ pub struct ExportIocsPayload {
     pub destination: String,
 }
 
+#[derive(Debug, Serialize)]
+pub struct ExportIocsStixResponse {
+    pub exported: usize,
+    /// Rules with no STIX pattern equivalent, such as regexes or free text.
+    pub skipped: Vec<SkippedStixRule>,
+}
+
+/// Validates and persists a prepared IOC set, then refreshes cached counts.
+fn store_ioc_entries(
+    state: &AppState,
+    project_id: &Uuid,
+    project_dir: &Path,
+    entries: &[IocEntry],
+) -> Result<(), AppError> {
+    ensure_unsealed(state, project_id)?;
+    validate_ioc_entries(entries).map_err(|err| AppError::Message(format!("{:#}", err)))?;
+    save_ioc_entries(project_dir, entries)?;
+    state.watched.record(state, project_id);
+
+    if let Err(err) = clear_ioc_flag_cache(project_dir) {
+        tracing::warn!("failed to clear IOC cache for {:?}: {:?}", project_dir, err);
+    }
+
+    let (policy, unsearched) = state
+        .projects
+        .find(project_id)
+        .map(|meta| (meta.ioc_policy, meta.unsearched_columns().to_vec()))
+        .unwrap_or_default();
+    let ioc_applied_records = calculate_ioc_applied_records(project_dir, policy, &unsearched)?;
+    state
+        .projects
+        .update_ioc_applied_records(project_id, ioc_applied_records)?;
+    state.changes.iocs_updated(*project_id);
+    alerts::check_project(state, project_id);
+    Ok(())
+}
+
 /// Normalizes and persists IOC definitions, updating cached counts.
 #[tauri::command]
-pub fn save_iocs(state: State<AppState>, payload: SaveIocsPayload) -> Result<(), String> {
+pub fn save_iocs(
+    state: State<AppState>,
+    payload: SaveIocsPayload,
+) -> Result<SaveIocsResponse, AppError> {
     let Some(meta) = state.projects.find(&payload.project_id) else {
-        return Err(AppError::Message("Project not found.".into()).into());
+        return Err(AppError::ProjectNotFound(payload.project_id));
     };
+    let _lock = state.locks.rewrite(&meta.id, "save_iocs")?;
     let project_dir = state.projects.project_dir(&meta.id);
-    let entries = prepare_ioc_entries(payload.entries);
-    save_ioc_entries(&project_dir, &entries).map_err(AppError::from)?;
-
-    if let Err(err) = clear_ioc_flag_cache(&project_dir) {
-        eprintln!(
-            "[cache] failed to clear IOC cache for {:?}: {:?}",
-            project_dir, err
-        );
+    let (entries, collapsed) = prepare_ioc_entries(payload.entries, meta.ioc_policy);
+    store_ioc_entries(&state, &meta.id, &project_dir, &entries)?;
+    let report = ioc_save_report(&meta, &project_dir, &entries)?;
+    Ok(SaveIocsResponse {
+        entries,
+        collapsed,
+        report,
+    })
+}
+
+/// Checks each saved rule the way `explain_search_query` reads it, and
+/// looks up the rows it matched when the counts were refreshed.
+fn ioc_save_report(
+    meta: &ProjectMeta,
+    project_dir: &Path,
+    entries: &[IocEntry],
+) -> Result<IocSaveReport, AppError> {
+    let parquet_path = project_dir.join("data.parquet");
+    let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let row_count = read_project_row_count(&parquet_path).map_err(AppError::from)?;
+    let hits = stored_ioc_rule_hits(project_dir, meta.ioc_policy, meta.unsearched_columns())
+        .unwrap_or_else(|err| {
+            tracing::warn!("failed to read IOC rule hits for {}: {:?}", meta.id, err);
+            None
+        });
+
+    let mut report = IocSaveReport::default();
+    for (index, entry) in entries.iter().enumerate() {
+        let issue = |message: String| IocRuleIssue {
+            index,
+            tag: entry.tag.clone(),
+            message,
+        };
+        let query = ExplainSearchPayload {
+            project_id: meta.id,
+            query: entry.query.clone(),
+            kind: Some(entry.kind),
+            columns: entry.columns.clone(),
+            whole_word: entry.whole_word,
+        };
+        let (_, warnings) = explain_query(meta, &columns, row_count, None, &query);
+        for warning in warnings {
+            match warning.kind {
+                WarningKind::UnknownColumn => report.unknown_columns.push(issue(warning.message)),
+                WarningKind::Syntax => report.invalid_syntax.push(issue(warning.message)),
+            }
+        }
+        if !entry.disabled && hits.as_ref().and_then(|hits| hits.get(index)) == Some(&0) {
+            report
+                .zero_hits
+                .push(issue("The rule matches no row.".to_string()));
+        }
     }
+    Ok(report)
+}
 
-    let ioc_applied_records =
-        calculate_ioc_applied_records(&project_dir).map_err(AppError::from)?;
-    state
-        .projects
-        .update_ioc_applied_records(&payload.project_id, ioc_applied_records)
-        .map_err(AppError::from)?;
+/// Applies `edit` to the stored rules and stores the result; `group` must
+/// name the group of at least one rule.
+fn edit_ioc_group(
+    state: &AppState,
+    project_id: &Uuid,
+    group: &str,
+    edit: impl FnOnce(&mut Vec<IocEntry>),
+) -> Result<Vec<IocEntry>, AppError> {
+    let Some(meta) = state.projects.find(project_id) else {
+        return Err(AppError::ProjectNotFound(*project_id));
+    };
+    let _lock = state.locks.rewrite(&meta.id, "edit_ioc_group")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let mut entries = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+    if !entries
+        .iter()
+        .any(|entry| entry.group.as_deref() == Some(group))
+    {
+        return Err(AppError::Message(format!(
+            "No IOC rule belongs to the group \"{}\".",
+            group
+        )));
+    }
+    edit(&mut entries);
+    store_ioc_entries(state, &meta.id, &project_dir, &entries)?;
+    Ok(entries)
+}
 
-    Ok(())
+/// Switches every rule of a group on or off; disabled rules are kept but
+/// not evaluated.
+#[tauri::command]
+pub fn set_ioc_group_enabled(
+    state: State<AppState>,
+    payload: IocGroupEnabledPayload,
+) -> Result<Vec<IocEntry>, AppError> {
+    let group = payload.group.trim();
+    edit_ioc_group(&state, &payload.project_id, group, |entries| {
+        for entry in entries.iter_mut() {
+            if entry.group.as_deref() == Some(group) {
+                entry.disabled = !payload.enabled;
+            }
+        }
+    })
 }
 
-/// Imports IOC rules from a CSV, replacing the current set.
+/// Deletes every rule of a group.
+#[tauri::command]
+pub fn delete_ioc_group(
+    state: State<AppState>,
+    payload: IocGroupPayload,
+) -> Result<Vec<IocEntry>, AppError> {
+    let group = payload.group.trim();
+    edit_ioc_group(&state, &payload.project_id, group, |entries| {
+        entries.retain(|entry| entry.group.as_deref() != Some(group))
+    })
+}
+
+/// Imports IOC rules from a CSV or MISP event JSON, replacing the current set.
 #[tauri::command]
 pub fn import_iocs(
     state: State<AppState>,
     payload: ImportIocsPayload,
-) -> Result<Vec<IocEntry>, String> {
+) -> Result<Vec<IocEntry>, AppError> {
     let Some(meta) = state.projects.find(&payload.project_id) else {
-        return Err(AppError::Message("Project not found.".into()).into());
+        return Err(AppError::ProjectNotFound(payload.project_id));
     };
+    let _lock = state.locks.rewrite(&meta.id, "import_iocs")?;
     let project_dir = state.projects.project_dir(&meta.id);
     let source = PathBuf::from(payload.path);
     if !source.exists() {
-        return Err(AppError::Message("Selected file does not exist.".into()).into());
+        return Err(AppError::Message("Selected file does not exist.".into()));
     }
-    let entries = prepare_ioc_entries(read_ioc_csv(&source).map_err(AppError::from)?);
-    save_ioc_entries(&project_dir, &entries).map_err(AppError::from)?;
-
-    if let Err(err) = clear_ioc_flag_cache(&project_dir) {
-        eprintln!(
-            "[cache] failed to clear IOC cache for {:?}: {:?}",
-            project_dir, err
-        );
+    let is_misp = source
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("json"))
+        .unwrap_or(false);
+    let imported = if is_misp {
+        let columns =
+            read_project_columns(&project_dir.join("data.parquet")).map_err(AppError::from)?;
+        read_misp_event(&source, &columns).map_err(AppError::from)?
+    } else {
+        read_ioc_csv(&source, &payload.mapping).map_err(AppError::from)?
+    };
+    let (entries, _) = prepare_ioc_entries(imported, meta.ioc_policy);
+    store_ioc_entries(&state, &meta.id, &project_dir, &entries)?;
+
+    let final_entries = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+    Ok(final_entries)
+}
+
+/// Converts Sigma YAML rules into IOC entries and appends them to the current set.
+#[tauri::command]
+pub fn import_sigma_rules(
+    state: State<AppState>,
+    payload: ImportSigmaPayload,
+) -> Result<ImportSigmaResponse, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    let _lock = state.locks.rewrite(&meta.id, "import_sigma_rules")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let sources: Vec<PathBuf> = payload.paths.iter().map(PathBuf::from).collect();
+    if sources.is_empty() || sources.iter().any(|path| !path.exists()) {
+        return Err(AppError::Message("Selected file does not exist.".into()));
     }
+    let mapping = match payload.mapping {
+        Some(mapping) => {
+            save_sigma_mapping(&project_dir, &mapping).map_err(AppError::from)?;
+            mapping
+        }
+        None => load_sigma_mapping(&project_dir).map_err(AppError::from)?,
+    };
+
+    let (converted, skipped) = read_sigma_rules(&sources, &mapping).map_err(AppError::from)?;
+    let imported = converted.len();
+    let mut entries = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+    entries.extend(converted);
+    let (entries, collapsed) = prepare_ioc_entries(entries, meta.ioc_policy);
+    store_ioc_entries(&state, &meta.id, &project_dir, &entries)?;
+
+    Ok(ImportSigmaResponse {
+        entries: load_ioc_entries(&project_dir).map_err(AppError::from)?,
+        imported,
+        skipped,
+        collapsed,
+    })
+}
+
+/// Returns the project's saved Sigma field-to-column mapping.
+#[tauri::command]
+pub fn get_sigma_mapping(
+    state: State<AppState>,
+    payload: ProjectIocsPayload,
+) -> Result<SigmaFieldMapping, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    let project_dir = state.projects.project_dir(&meta.id);
+    load_sigma_mapping(&project_dir).map_err(AppError::from)
+}
 
-    let ioc_applied_records =
-        calculate_ioc_applied_records(&project_dir).map_err(AppError::from)?;
+/// Changes how overlapping IOC matches combine and invalidates cached IOC flags.
+#[tauri::command]
+pub fn set_ioc_policy(state: State<AppState>, payload: IocPolicyPayload) -> Result<(), AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
     state
         .projects
-        .update_ioc_applied_records(&payload.project_id, ioc_applied_records)
+        .update_ioc_policy(&meta.id, payload.policy)
         .map_err(AppError::from)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    clear_ioc_flag_cache(&project_dir).map_err(AppError::from)?;
+    alerts::check_project(&state, &meta.id);
+    Ok(())
+}
 
-    let final_entries = load_ioc_entries(&project_dir).map_err(AppError::from)?;
-    Ok(final_entries)
+/// Sets where rows newly flagged critical by IOC rules are reported. Rows
+/// already critical when alerts are turned on are not reported.
+#[tauri::command]
+pub fn set_project_alerts(
+    state: State<AppState>,
+    payload: ProjectAlertsPayload,
+) -> Result<ProjectSummary, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    let webhook_url = payload
+        .webhook_url
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty());
+    if let Some(url) = &webhook_url {
+        let lower = url.to_ascii_lowercase();
+        if !(lower.starts_with("https://") || lower.starts_with("http://")) {
+            return Err(AppError::Message(
+                "The webhook URL must start with http:// or https://.".into(),
+            ));
+        }
+    }
+    let alerts = AlertSettings {
+        webhook_url,
+        notify: payload.notify,
+    };
+    if alerts.enabled() && !meta.alerts.enabled() {
+        let project_dir = state.projects.project_dir(&meta.id);
+        reset_alert_baseline(&meta, &project_dir).map_err(AppError::from)?;
+    }
+    state
+        .projects
+        .update_alerts(&meta.id, alerts)
+        .map_err(AppError::from)?;
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+    Ok(ProjectSummary { meta })
+}
+
+/// Posts a sample alert to the project's webhook and reports the receiver's
+/// error status, if any.
+#[tauri::command]
+pub fn send_test_alert(
+    state: State<AppState>,
+    payload: ProjectIocsPayload,
+) -> Result<(), AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    alerts::send_test_alert(&meta)
+        .map_err(|err| AppError::Message(format!("Webhook test failed: {:#}", err)))
 }
 
 /// Writes the current IOC set to a destination CSV file.
 #[tauri::command]
-pub fn export_iocs(state: State<AppState>, payload: ExportIocsPayload) -> Result<(), String> {
+pub fn export_iocs(state: State<AppState>, payload: ExportIocsPayload) -> Result<(), AppError> {
     let Some(meta) = state.projects.find(&payload.project_id) else {
-        return Err(AppError::Message("Project not found.".into()).into());
+        return Err(AppError::ProjectNotFound(payload.project_id));
     };
+    ensure_unsealed(&state, &meta.id)?;
     let project_dir = state.projects.project_dir(&meta.id);
     let entries = load_ioc_entries(&project_dir).map_err(AppError::from)?;
     let destination = PathBuf::from(payload.destination);
     write_ioc_csv(&entries, &destination).map_err(AppError::from)?;
     Ok(())
 }
+
+/// Writes the IOC set as a STIX 2.1 bundle of indicators for sharing with
+/// threat intelligence platforms.
+#[tauri::command]
+pub fn export_iocs_stix(
+    state: State<AppState>,
+    payload: ExportIocsPayload,
+) -> Result<ExportIocsStixResponse, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let entries = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+    let (bundle, skipped) = build_stix_bundle(&entries);
+    let destination = PathBuf::from(payload.destination);
+    let json = serde_json::to_vec_pretty(&bundle).map_err(|err| AppError::Other(err.into()))?;
+    std::fs::write(&destination, json)
+        .with_context(|| format!("failed to write STIX bundle {:?}", destination))
+        .map_err(AppError::from)?;
+    Ok(ExportIocsStixResponse {
+        exported: entries.len() - skipped.len(),
+        skipped,
+    })
+}
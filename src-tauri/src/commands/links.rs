@@ -0,0 +1,44 @@
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{error::AppError, links::format_row_permalink, models::LaunchRequest, state::AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct RowPermalinkPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "rowId")]
+    pub row_id: usize,
+}
+
+/// Produces a deep link that reopens the project at the given row.
+#[tauri::command]
+pub fn get_row_permalink(
+    state: State<AppState>,
+    payload: RowPermalinkPayload,
+) -> Result<String, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    if payload.row_id >= meta.total_records {
+        return Err(AppError::Message(
+            "Row is out of range for this project.".into(),
+        ));
+    }
+    Ok(format_row_permalink(&meta.id, payload.row_id))
+}
+
+/// Drains deep links and files the app was launched or activated with.
+#[tauri::command]
+pub fn take_launch_requests(state: State<AppState>) -> Result<Vec<LaunchRequest>, AppError> {
+    let requests: Vec<LaunchRequest> = state.pending_launch.lock().drain(..).collect();
+    Ok(requests
+        .into_iter()
+        .filter(|request| match request {
+            LaunchRequest::RowLink(link) => state.projects.find(&link.project_id).is_some(),
+            _ => true,
+        })
+        .collect())
+}
@@ -0,0 +1,54 @@
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::{
+    error::AppError,
+    logging::{self, LogLevel},
+    state::AppState,
+};
+
+const DEFAULT_LOG_LINES: usize = 500;
+
+#[derive(Debug, Default, Deserialize)]
+pub struct RecentLogsPayload {
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RecentLogsResponse {
+    pub level: LogLevel,
+    /// Active log file; rotated files sit next to it.
+    pub path: Option<String>,
+    pub lines: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LogLevelPayload {
+    pub level: LogLevel,
+}
+
+/// Returns the most recent log lines, oldest first, for the diagnostics panel.
+#[tauri::command]
+pub fn get_recent_logs(
+    state: State<AppState>,
+    payload: RecentLogsPayload,
+) -> Result<RecentLogsResponse, AppError> {
+    Ok(RecentLogsResponse {
+        level: state.settings.log_level(),
+        path: logging::log_file_path().map(|path| path.to_string_lossy().into_owned()),
+        lines: logging::recent_lines(payload.limit.unwrap_or(DEFAULT_LOG_LINES)),
+    })
+}
+
+/// Changes the log level immediately and keeps it for the next launch.
+#[tauri::command]
+pub fn set_log_level(state: State<AppState>, payload: LogLevelPayload) -> Result<(), AppError> {
+    logging::set_level(payload.level)?;
+    state
+        .settings
+        .update_log_level(payload.level)
+        .map_err(AppError::from)?;
+    tracing::info!("log level set to {:?}", payload.level);
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use anyhow::Context;
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    ioc::calculate_ioc_applied_records,
+    project_io::{read_project_dataframe, write_compacted_dataframe, write_project_dataframe},
+    state::AppState,
+    storage::{
+        clear_ioc_flag_cache, clear_searchable_cache, compact_cache_db, compact_flags_db,
+        load_column_metrics, load_flags, load_ioc_flag_cache, load_searchable_cache,
+        project_storage_usage, reset_cache_db, save_flags, COLUMN_METRICS_FILE,
+    },
+};
+
+use super::{encryption::ensure_unsealed, projects::add_row_ids};
+
+#[derive(Debug, Deserialize)]
+pub struct CheckProjectPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Fix what can be fixed without losing annotations.
+    #[serde(default)]
+    pub repair: bool,
+}
+
+/// One inconsistency found by `check_project`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectIssue {
+    pub kind: &'static str,
+    pub message: String,
+    pub repairable: bool,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CheckProjectResponse {
+    pub issues: Vec<ProjectIssue>,
+    /// No issues remain after the optional repair.
+    pub healthy: bool,
+}
+
+impl CheckProjectResponse {
+    fn new(issues: Vec<ProjectIssue>) -> Self {
+        let healthy = issues.iter().all(|issue| issue.repaired);
+        Self { issues, healthy }
+    }
+}
+
+fn row_ids_are_contiguous(df: &DataFrame) -> bool {
+    let Ok(series) = df
+        .column("__rowid")
+        .and_then(|series| series.cast(&DataType::Int64))
+    else {
+        return false;
+    };
+    let Ok(ids) = series.i64() else {
+        return false;
+    };
+    for (row, id) in ids.into_iter().enumerate() {
+        if id != Some(row as i64) {
+            return false;
+        }
+    }
+    true
+}
+
+/// Validates a project's data, flags, caches and counters against each
+/// other. With `repair`, row ids are renumbered, flags past the last row are
+/// dropped, stale caches are cleared and counters recomputed.
+#[tauri::command]
+pub fn check_project(
+    state: State<AppState>,
+    payload: CheckProjectPayload,
+) -> Result<CheckProjectResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = if payload.repair {
+        state.locks.rewrite(&meta.id, "check_project")?
+    } else {
+        state.locks.read(&meta.id)?
+    };
+    let project_dir = state.projects.project_dir(&meta.id);
+    let repair = payload.repair;
+    let mut issues = Vec::new();
+    let mut report = |kind: &'static str, message: String, repairable: bool| {
+        issues.push(ProjectIssue {
+            kind,
+            message,
+            repairable,
+            repaired: repair && repairable,
+        });
+    };
+
+    let parquet_path = project_dir.join("data.parquet");
+    let mut df = match read_project_dataframe(&parquet_path) {
+        Ok(df) => df,
+        Err(err) => {
+            report(
+                "data_unreadable",
+                format!("The project data cannot be read: {:#}", err),
+                false,
+            );
+            return Ok(CheckProjectResponse::new(issues));
+        }
+    };
+    let rows = df.height();
+
+    if !row_ids_are_contiguous(&df) {
+        report(
+            "row_ids",
+            "Row ids are missing or do not number the rows 0..n.".into(),
+            true,
+        );
+        if repair {
+            add_row_ids(&mut df, 0).map_err(AppError::from)?;
+            let staged_path = project_dir.join("data.parquet.repair");
+            write_project_dataframe(&staged_path, &mut df).map_err(AppError::from)?;
+            fs::rename(&staged_path, &parquet_path)
+                .with_context(|| format!("failed to replace {:?}", parquet_path))
+                .map_err(AppError::from)?;
+        }
+    }
+
+    let flags_path = project_dir.join("flags.json");
+    let flagged_records = match load_flags(&flags_path) {
+        Ok(mut flags) => {
+            let stray: Vec<usize> = flags.keys().filter(|row| **row >= rows).copied().collect();
+            if !stray.is_empty() {
+                report(
+                    "flags_out_of_range",
+                    format!(
+                        "{} flag entries point past the last row ({}).",
+                        stray.len(),
+                        rows
+                    ),
+                    true,
+                );
+                if repair {
+                    for row in &stray {
+                        flags.remove(row);
+                    }
+                    save_flags(&flags_path, &flags).map_err(AppError::from)?;
+                }
+            }
+            Some(
+                flags
+                    .values()
+                    .filter(|entry| !entry.flag.trim().is_empty())
+                    .count(),
+            )
+        }
+        Err(err) => {
+            report(
+                "flags_unreadable",
+                format!("The flags database cannot be read: {:#}", err),
+                false,
+            );
+            None
+        }
+    };
+
+    match (
+        load_searchable_cache(&project_dir),
+        load_ioc_flag_cache(&project_dir),
+    ) {
+        (Ok(search), Ok(ioc)) => {
+            let search_stale = search.as_ref().is_some_and(|cache| cache.row_count != rows);
+            let ioc_stale = ioc.as_ref().is_some_and(|cache| cache.flags.len() != rows);
+            if search_stale || ioc_stale {
+                report(
+                    "cache_stale",
+                    "Cached search or IOC results do not match the row count.".into(),
+                    true,
+                );
+                if repair {
+                    clear_searchable_cache(&project_dir).map_err(AppError::from)?;
+                    clear_ioc_flag_cache(&project_dir).map_err(AppError::from)?;
+                }
+            }
+        }
+        (Err(err), _) | (_, Err(err)) => {
+            report(
+                "cache_unreadable",
+                format!("The cache database cannot be read: {:#}", err),
+                true,
+            );
+            if repair {
+                reset_cache_db(&project_dir).map_err(AppError::from)?;
+            }
+        }
+    }
+
+    let metrics_path = project_dir.join(COLUMN_METRICS_FILE);
+    let columns: HashSet<String> = df
+        .get_column_names()
+        .into_iter()
+        .filter(|name| *name != "__rowid")
+        .map(str::to_string)
+        .collect();
+    let metrics_stale = match load_column_metrics(&metrics_path) {
+        Ok(Some(metrics)) => metrics.keys().cloned().collect::<HashSet<_>>() != columns,
+        Ok(None) => false,
+        Err(_) => true,
+    };
+    if metrics_stale {
+        report(
+            "column_metrics_stale",
+            "Cached column widths do not match the columns.".into(),
+            true,
+        );
+        if repair {
+            fs::remove_file(&metrics_path)
+                .with_context(|| format!("failed to remove {:?}", metrics_path))
+                .map_err(AppError::from)?;
+        }
+    }
+
+    // Counters are only trusted when the flags could be read.
+    if let Some(flagged_records) = flagged_records {
+        let ioc_applied_records =
+            calculate_ioc_applied_records(&project_dir, meta.ioc_policy, meta.unsearched_columns())
+                .map_err(AppError::from)?;
+        let expected = (rows, flagged_records, ioc_applied_records);
+        let recorded = (
+            meta.total_records,
+            meta.flagged_records,
+            meta.ioc_applied_records,
+        );
+        if expected != recorded {
+            report(
+                "counters",
+                format!(
+                    "Project counters (rows {}, flagged {}, IOC matches {}) should be {}, {} and {}.",
+                    recorded.0, recorded.1, recorded.2, expected.0, expected.1, expected.2
+                ),
+                true,
+            );
+            if repair {
+                state
+                    .projects
+                    .update_counters(&meta.id, rows, flagged_records, ioc_applied_records)
+                    .map_err(AppError::from)?;
+            }
+        }
+    }
+
+    Ok(CheckProjectResponse::new(issues))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompactProjectPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompactProjectResponse {
+    pub bytes_before: u64,
+    pub bytes_after: u64,
+    /// Leftover files that were deleted, by name.
+    pub removed_files: Vec<String>,
+}
+
+/// Files no code path reads: staging copies left by an interrupted
+/// re-import, repair or compaction, and JSON flags superseded by the database.
+fn orphaned_files(project_dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut orphans = Vec::new();
+    for entry in fs::read_dir(project_dir)
+        .with_context(|| format!("failed to read project dir {:?}", project_dir))?
+    {
+        let entry = entry?;
+        let name = entry.file_name().to_string_lossy().into_owned();
+        let staged = [".reimport", ".repair", ".compact", ".old"]
+            .iter()
+            .any(|suffix| name.ends_with(suffix));
+        let legacy_flags = name == "flags.json" && project_dir.join("flags.db").exists();
+        if staged || legacy_flags {
+            orphans.push(entry.path());
+        }
+    }
+    orphans.sort();
+    Ok(orphans)
+}
+
+/// Shrinks a long-lived project: drops orphaned files, rewrites the flags and
+/// cache databases without their dead space, and recompresses the Parquet
+/// data, keeping the old file if recompression does not make it smaller.
+#[tauri::command]
+pub fn compact_project(
+    state: State<AppState>,
+    payload: CompactProjectPayload,
+) -> Result<CompactProjectResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "compact_project")?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let bytes_before = project_storage_usage(&project_dir)
+        .map_err(AppError::from)?
+        .total_bytes;
+
+    let mut removed_files = Vec::new();
+    for path in orphaned_files(&project_dir).map_err(AppError::from)? {
+        let removed = if path.is_dir() {
+            fs::remove_dir_all(&path)
+        } else {
+            fs::remove_file(&path)
+        };
+        removed
+            .with_context(|| format!("failed to remove {:?}", path))
+            .map_err(AppError::from)?;
+        if let Some(name) = path.file_name() {
+            removed_files.push(name.to_string_lossy().into_owned());
+        }
+    }
+
+    compact_flags_db(&project_dir.join("flags.json")).map_err(AppError::from)?;
+    compact_cache_db(&project_dir).map_err(AppError::from)?;
+
+    let parquet_path = project_dir.join("data.parquet");
+    let mut df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
+    let staged_path = project_dir.join("data.parquet.compact");
+    write_compacted_dataframe(&staged_path, &mut df).map_err(AppError::from)?;
+    let size = |path: &Path| fs::metadata(path).map(|metadata| metadata.len());
+    let smaller = matches!(
+        (size(&staged_path), size(&parquet_path)),
+        (Ok(staged), Ok(current)) if staged < current
+    );
+    if smaller {
+        fs::rename(&staged_path, &parquet_path)
+            .with_context(|| format!("failed to replace {:?}", parquet_path))
+            .map_err(AppError::from)?;
+    } else {
+        let _ = fs::remove_file(&staged_path);
+    }
+
+    let bytes_after = project_storage_usage(&project_dir)
+        .map_err(AppError::from)?
+        .total_bytes;
+    Ok(CompactProjectResponse {
+        bytes_before,
+        bytes_after,
+        removed_files,
+    })
+}
@@ -1,19 +1,108 @@
 pub(crate) const DEFAULT_PAGE_SIZE: usize = 250;
 
+mod backups;
+mod bookmarks;
+mod bundle;
+mod duplicates;
+mod encryption;
+mod enrichment;
+mod entities;
 mod export;
 mod flags;
 mod iocs;
+mod links;
+mod logs;
+mod maintenance;
+mod notes;
+mod perf;
 mod projects;
+mod report;
 mod rows;
+mod session;
+mod settings;
+#[cfg(test)]
+mod tests;
 mod utils;
+mod views;
 
+pub use backups::{
+    __cmd__list_annotation_backups, __cmd__restore_annotations_backup, list_annotation_backups,
+    restore_annotations_backup,
+};
+pub use bookmarks::{
+    __cmd__add_bookmarks, __cmd__list_bookmarks, __cmd__remove_bookmarks, add_bookmarks,
+    list_bookmarks, remove_bookmarks,
+};
+pub use bundle::{
+    __cmd__export_project_bundle, __cmd__import_project_bundle, export_project_bundle,
+    import_project_bundle,
+};
+pub use duplicates::{
+    __cmd__dedupe_project, __cmd__find_duplicates, dedupe_project, find_duplicates,
+};
+pub use encryption::{
+    __cmd__disable_project_encryption, __cmd__enable_project_encryption, __cmd__lock_project,
+    disable_project_encryption, enable_project_encryption, lock_project,
+};
+pub use enrichment::{
+    __cmd__add_computed_column, __cmd__add_decoded_column, __cmd__add_script_column,
+    __cmd__assign_sessions, __cmd__expand_json_column, __cmd__find_rare_values,
+    __cmd__list_json_keys, add_computed_column, add_decoded_column, add_script_column,
+    assign_sessions, expand_json_column, find_rare_values, list_json_keys,
+};
+pub use entities::{__cmd__list_entities, __cmd__pivot_entity, list_entities, pivot_entity};
 pub use export::{__cmd__export_project, export_project};
-pub use flags::{__cmd__set_hidden_columns, __cmd__update_flag, set_hidden_columns, update_flag};
+pub use flags::{
+    __cmd__add_row_comment, __cmd__delete_row_comment, __cmd__get_memo_term_frequencies,
+    __cmd__list_row_comments, __cmd__set_hidden_columns, __cmd__update_flag, add_row_comment,
+    delete_row_comment, get_memo_term_frequencies, list_row_comments, set_hidden_columns,
+    update_flag,
+};
 pub use iocs::{
-    __cmd__export_iocs, __cmd__import_iocs, __cmd__save_iocs, export_iocs, import_iocs, save_iocs,
+    __cmd__delete_ioc_group, __cmd__export_iocs, __cmd__export_iocs_stix, __cmd__get_sigma_mapping,
+    __cmd__import_iocs, __cmd__import_sigma_rules, __cmd__save_iocs, __cmd__send_test_alert,
+    __cmd__set_ioc_group_enabled, __cmd__set_ioc_policy, __cmd__set_project_alerts,
+    delete_ioc_group, export_iocs, export_iocs_stix, get_sigma_mapping, import_iocs,
+    import_sigma_rules, save_iocs, send_test_alert, set_ioc_group_enabled, set_ioc_policy,
+    set_project_alerts,
+};
+pub use links::{
+    __cmd__get_row_permalink, __cmd__take_launch_requests, get_row_permalink, take_launch_requests,
+};
+pub use logs::{__cmd__get_recent_logs, __cmd__set_log_level, get_recent_logs, set_log_level};
+pub use maintenance::{
+    __cmd__check_project, __cmd__compact_project, check_project, compact_project,
+};
+pub use notes::{__cmd__get_case_notes, __cmd__save_case_notes, get_case_notes, save_case_notes};
+pub use perf::{
+    __cmd__get_performance_stats, __cmd__reset_performance_stats, get_performance_stats,
+    reset_performance_stats,
 };
 pub use projects::{
-    __cmd__create_project, __cmd__delete_project, __cmd__list_projects, __cmd__load_project,
-    create_project, delete_project, list_projects, load_project,
+    __cmd__clone_project, __cmd__create_project, __cmd__delete_project, __cmd__get_project_status,
+    __cmd__get_project_storage_info, __cmd__list_import_presets, __cmd__list_projects,
+    __cmd__list_sheets, __cmd__list_sqlite_tables, __cmd__load_project, __cmd__reimport_project,
+    __cmd__set_column_aliases, __cmd__set_column_description, __cmd__set_display_timezone,
+    __cmd__set_project_pinned, __cmd__set_timestamp_column, __cmd__verify_source_integrity,
+    clone_project, create_project, delete_project, get_project_status, get_project_storage_info,
+    list_import_presets, list_projects, list_sheets, list_sqlite_tables, load_project,
+    reimport_project, set_column_aliases, set_column_description, set_display_timezone,
+    set_project_pinned, set_timestamp_column, verify_source_integrity,
+};
+pub use report::{__cmd__export_report, export_report};
+pub use rows::{
+    __cmd__compare_rows, __cmd__explain_search_query, __cmd__query_project_rows, compare_rows,
+    explain_search_query, query_project_rows,
+};
+pub use session::{
+    __cmd__get_session_restore, __cmd__save_session_state, get_session_restore, save_session_state,
+};
+pub use settings::{
+    __cmd__get_data_root, __cmd__get_performance_settings, __cmd__get_settings,
+    __cmd__set_data_root, __cmd__set_performance_settings, __cmd__set_settings, get_data_root,
+    get_performance_settings, get_settings, set_data_root, set_performance_settings, set_settings,
+};
+pub use views::{
+    __cmd__delete_view, __cmd__list_views, __cmd__save_view, __cmd__set_default_view, delete_view,
+    list_views, save_view, set_default_view,
 };
-pub use rows::{__cmd__query_project_rows, query_project_rows};
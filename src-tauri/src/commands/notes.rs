@@ -0,0 +1,79 @@
+use chrono::Utc;
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::{CaseNoteEntry, CaseNotes},
+    state::AppState,
+    storage::{load_case_notes, save_case_notes as write_case_notes, CASE_NOTES_FILE},
+};
+
+use super::encryption::ensure_unsealed;
+
+#[derive(Debug, Deserialize)]
+pub struct CaseNotesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveCaseNotesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Replaces the notes document when given.
+    #[serde(default)]
+    pub text: Option<String>,
+    /// Appended as a new entry stamped with the current time.
+    #[serde(default)]
+    pub entry: Option<String>,
+}
+
+/// Returns the project's case notes; projects without notes get an empty document.
+#[tauri::command]
+pub fn get_case_notes(
+    state: State<AppState>,
+    payload: CaseNotesPayload,
+) -> Result<CaseNotes, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let path = state.projects.project_dir(&meta.id).join(CASE_NOTES_FILE);
+    load_case_notes(&path).map_err(AppError::from)
+}
+
+/// Updates the notes document and/or appends a timestamped entry.
+#[tauri::command]
+pub fn save_case_notes(
+    state: State<AppState>,
+    payload: SaveCaseNotesPayload,
+) -> Result<CaseNotes, AppError> {
+    let Some(meta) = state.projects.find(&payload.project_id) else {
+        return Err(AppError::ProjectNotFound(payload.project_id));
+    };
+    ensure_unsealed(&state, &meta.id)?;
+    let entry = payload
+        .entry
+        .map(|entry| entry.trim().to_string())
+        .filter(|entry| !entry.is_empty());
+    if payload.text.is_none() && entry.is_none() {
+        return Err(AppError::Message("Nothing to save.".into()));
+    }
+    let path = state.projects.project_dir(&meta.id).join(CASE_NOTES_FILE);
+    let mut notes = load_case_notes(&path).map_err(AppError::from)?;
+    let now = Utc::now();
+    if let Some(text) = payload.text {
+        notes.text = text;
+    }
+    if let Some(text) = entry {
+        notes.entries.push(CaseNoteEntry {
+            timestamp: now,
+            text,
+        });
+    }
+    notes.updated_at = Some(now);
+    write_case_notes(&path, &notes).map_err(AppError::from)?;
+    Ok(notes)
+}
@@ -0,0 +1,17 @@
+use tauri::State;
+
+use crate::{error::AppError, perf::PerformanceStats, state::AppState};
+
+/// Returns timings, rows scanned and cache hit rates of the query, IOC and
+/// export paths since the app started or the counters were reset.
+#[tauri::command]
+pub fn get_performance_stats(state: State<AppState>) -> Result<PerformanceStats, AppError> {
+    Ok(state.perf.snapshot())
+}
+
+/// Clears the counters, e.g. before reproducing a slow query.
+#[tauri::command]
+pub fn reset_performance_stats(state: State<AppState>) -> Result<(), AppError> {
+    state.perf.reset();
+    Ok(())
+}
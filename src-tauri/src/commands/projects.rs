@@ -1,6 +1,6 @@
-use std::collections::HashMap;
-use std::fs::{self, File};
-use std::path::PathBuf;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
 
 use anyhow::Context;
 use chrono::Utc;
@@ -10,32 +10,63 @@ use tauri::State;
 use uuid::Uuid;
 
 use crate::{
+    alerts,
+    column_types::{describe_column_types, detect_timestamp_column},
+    encryption::seal_project,
+    enrichment::ImportScripts,
     error::AppError,
     flags::normalize_flag_value,
-    ioc::{apply_iocs_to_rows, load_ioc_entries},
-    models::{FlagEntry, LoadProjectResponse, ProjectMeta, ProjectRow, ProjectSummary},
-    project_io::{read_project_dataframe, write_project_dataframe},
+    ioc::{apply_iocs_to_rows, calculate_ioc_applied_records, load_ioc_entries},
+    locks::ProjectStatus,
+    migrations::CURRENT_FORMAT_VERSION,
+    models::{
+        ColumnDtype, ColumnType, CreateProjectResponse, FlagEntry, ImportOptions, ImportReport,
+        LoadProjectResponse, ProjectMeta, ProjectRow, ProjectSort, ProjectStorageInfo,
+        ProjectSummary, ReimportProjectResponse, ScriptColumn, SourceIntegrity, StorageUsage,
+        WorkspaceStorageInfo,
+    },
+    presets::{list_presets, setup_for_columns, ImportPresetInfo},
+    project_io::{
+        describe_sources, expand_input_paths, read_input_dataframe, read_input_dataframes_parallel,
+        read_project_columns, read_project_dataframe, source_display_name, stream_csv_to_parquet,
+        verify_sources, write_project_dataframe, SOURCE_FILE_COLUMN,
+    },
+    settings::PerformanceSettings,
+    sigma::save_sigma_mapping,
+    sqlite::list_tables as list_database_tables,
     state::AppState,
     storage::{
-        clear_ioc_flag_cache, clear_searchable_cache, compute_column_max_chars,
-        load_column_metrics, load_flags, save_column_metrics, save_flags,
+        clear_ioc_flag_cache, clear_searchable_cache, compute_column_max_chars, copy_dir_recursive,
+        load_column_metrics, load_flags, load_views, project_storage_usage, remap_bookmarks,
+        save_column_metrics, save_flags, save_import_report, BOOKMARKS_FILE, COLUMN_METRICS_FILE,
+        COLUMN_WIDTH_SAMPLE_ROWS, IMPORT_REPORT_FILE, VIEWS_FILE,
     },
+    timezones::{ProjectTimezone, TimeDisplay},
     value_utils::anyvalue_to_search_string,
+    xlsx::list_sheets as list_workbook_sheets,
 };
 
-use super::{utils::collect_row_record, DEFAULT_PAGE_SIZE};
+use super::{
+    encryption::{ensure_unsealed, unlock_project},
+    rows::{query_project_rows, QueryRowsPayload},
+    utils::{alias_search_key, collect_row_record, row_fingerprints, time_display},
+    DEFAULT_PAGE_SIZE,
+};
 
-const COLUMN_METRICS_FILE: &str = "column_max_chars.json";
+/// Issues of a lenient import returned to the frontend; the rest stay in
+/// the project's import report file.
+const IMPORT_REPORT_PREVIEW: usize = 20;
 
 fn materialize_rows(
     df: &DataFrame,
     columns: &[String],
     row_indices: impl Iterator<Item = usize>,
     flags: &HashMap<usize, FlagEntry>,
+    display: Option<&TimeDisplay>,
 ) -> Vec<ProjectRow> {
     let mut rows = Vec::new();
     for row_idx in row_indices {
-        let record = collect_row_record(df, columns, row_idx);
+        let record = collect_row_record(df, columns, row_idx, display);
         let flag_entry = flags.get(&row_idx);
         rows.push(ProjectRow {
             row_index: row_idx,
@@ -52,40 +83,460 @@ fn materialize_rows(
 
 #[derive(Debug, Deserialize)]
 pub struct CreateProjectPayload {
+    #[serde(default)]
     pub path: String,
+    /// Source files merged into one project (multi-file import); entries may
+    /// be glob patterns such as `exports/*.csv`.
+    #[serde(default)]
+    pub paths: Vec<String>,
     pub description: Option<String>,
+    /// Worksheet to import when the source is an Excel workbook.
+    #[serde(default)]
+    pub sheet: Option<String>,
+    /// Table or view to import when the source is a SQLite database.
+    #[serde(default)]
+    pub table: Option<String>,
+    /// Column types to use instead of the inferred ones.
+    #[serde(default)]
+    pub dtypes: BTreeMap<String, ColumnDtype>,
+    /// Convert date and timestamp text into temporal columns.
+    #[serde(default, rename = "parseDates")]
+    pub parse_dates: bool,
+    /// Set to false for CSV exports without a header line.
+    #[serde(default, rename = "hasHeaders")]
+    pub has_headers: Option<bool>,
+    /// Repair or skip malformed CSV records instead of failing.
+    #[serde(default)]
+    pub lenient: bool,
+    /// Columns to read as timestamps and store in UTC.
+    #[serde(default, rename = "timestampColumns")]
+    pub timestamp_columns: Vec<String>,
+    /// Time zone of timestamps written without an offset, e.g. `Asia/Tokyo`
+    /// or `+09:00`; UTC when unset.
+    #[serde(default, rename = "sourceTimezone")]
+    pub source_timezone: Option<String>,
+    /// Trim surrounding whitespace from every text value.
+    #[serde(default, rename = "trimWhitespace")]
+    pub trim_whitespace: bool,
+    /// Columns whose text is lowercased.
+    #[serde(default, rename = "lowercaseColumns")]
+    pub lowercase_columns: Vec<String>,
+    /// Columns whose numbers lose their thousands separators.
+    #[serde(default, rename = "stripThousandsColumns")]
+    pub strip_thousands_columns: Vec<String>,
+    /// Columns computed by enrichment scripts for every imported row.
+    #[serde(default, rename = "scriptColumns")]
+    pub script_columns: Vec<ScriptColumn>,
+    /// Tool preset to set the project up with, or `auto` to detect one from
+    /// the column names.
+    #[serde(default)]
+    pub preset: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ListSheetsPayload {
+    pub path: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+pub struct ListProjectsPayload {
+    #[serde(default)]
+    pub sort: ProjectSort,
 }
 
-/// Lists saved projects ordered by creation time (newest first).
+/// Lists saved projects, newest created first unless another order is asked for.
 #[tauri::command]
-pub fn list_projects(state: State<AppState>) -> Result<Vec<ProjectSummary>, String> {
-    println!("[debug] list_projects called");
+pub fn list_projects(
+    state: State<AppState>,
+    payload: Option<ListProjectsPayload>,
+) -> Result<Vec<ProjectSummary>, AppError> {
+    tracing::debug!("list_projects called");
     let metas = state.projects.all();
     let mut result = Vec::with_capacity(metas.len());
     for meta in metas {
         result.push(ProjectSummary { meta: meta.clone() });
     }
-    result.sort_by(|a, b| b.meta.created_at.cmp(&a.meta.created_at));
+    let sort = payload.unwrap_or_default().sort;
+    result.sort_by(|a, b| {
+        let (a, b) = (&a.meta, &b.meta);
+        let by_created = b.created_at.cmp(&a.created_at);
+        let by_recent = || b.last_opened_at.cmp(&a.last_opened_at).then(by_created);
+        match sort {
+            ProjectSort::Created => by_created,
+            ProjectSort::Recent => by_recent(),
+            ProjectSort::Pinned => b.pinned.cmp(&a.pinned).then_with(by_recent),
+        }
+    });
     Ok(result)
 }
 
-/// Creates a new project from a CSV file and persists metadata plus optional flags.
+/// Reports how much disk each project uses, split into data, flags, caches
+/// and IOC files, with workspace totals.
 #[tauri::command]
-pub fn create_project(
+pub fn get_project_storage_info(state: State<AppState>) -> Result<WorkspaceStorageInfo, AppError> {
+    let mut projects = Vec::new();
+    let mut totals = StorageUsage::default();
+    for meta in state.projects.all() {
+        let usage =
+            project_storage_usage(&state.projects.project_dir(&meta.id)).map_err(AppError::from)?;
+        totals.add(&usage);
+        projects.push(ProjectStorageInfo {
+            project_id: meta.id,
+            name: meta.name,
+            usage,
+        });
+    }
+    projects.sort_by(|a, b| b.usage.total_bytes.cmp(&a.usage.total_bytes));
+    Ok(WorkspaceStorageInfo {
+        data_root: state.projects.root_dir().to_string_lossy().into_owned(),
+        projects,
+        totals,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetPinnedPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub pinned: bool,
+}
+
+/// Pins a project to the top of the list, or unpins it.
+#[tauri::command]
+pub fn set_project_pinned(
     state: State<AppState>,
-    payload: CreateProjectPayload,
-) -> Result<ProjectSummary, String> {
-    let source_path = PathBuf::from(&payload.path);
-    if !source_path.exists() {
-        return Err(AppError::Message("Selected file no longer exists.".into()).into());
+    payload: SetPinnedPayload,
+) -> Result<ProjectSummary, AppError> {
+    state
+        .projects
+        .update_pinned(&payload.project_id, payload.pinned)
+        .map_err(AppError::from)?;
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    Ok(ProjectSummary { meta })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetDisplayTimezonePayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// IANA name such as `Europe/Berlin` or an offset such as `+09:00`;
+    /// timestamps are shown in UTC when unset.
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// Sets the time zone timestamp values are shown in. Stored values, search
+/// and exports stay in UTC.
+#[tauri::command]
+pub fn set_display_timezone(
+    state: State<AppState>,
+    payload: SetDisplayTimezonePayload,
+) -> Result<ProjectSummary, AppError> {
+    let timezone = payload
+        .timezone
+        .map(|zone| zone.trim().to_string())
+        .filter(|zone| !zone.is_empty());
+    if let Some(zone) = &timezone {
+        ProjectTimezone::parse(zone)
+            .map_err(|err| AppError::Message(format!("Invalid display time zone: {}", err)))?;
+    }
+    state
+        .projects
+        .update_display_timezone(&payload.project_id, timezone)
+        .map_err(AppError::from)?;
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    Ok(ProjectSummary { meta })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetTimestampColumnPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Column holding the event time; clears the choice when unset.
+    #[serde(default)]
+    pub column: Option<String>,
+}
+
+/// Sets the project's time column, which the grid opens sorted by and the
+/// timeline view orders rows by.
+#[tauri::command]
+pub fn set_timestamp_column(
+    state: State<AppState>,
+    payload: SetTimestampColumnPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    let column = payload.column.filter(|column| !column.is_empty());
+    if let Some(column) = &column {
+        let parquet_path = state.projects.project_dir(&meta.id).join("data.parquet");
+        let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+        if !columns.contains(column) {
+            return Err(AppError::Message(format!("Unknown column: {}", column)));
+        }
+    }
+    state
+        .projects
+        .update_timestamp_column(&meta.id, column)
+        .map_err(AppError::from)?;
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+    Ok(ProjectSummary { meta })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetColumnAliasesPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Alias of each column, e.g. `c-ip` → `Client IP`; replaces the current
+    /// aliases. Columns left out or given an empty alias keep their name.
+    #[serde(default)]
+    pub aliases: BTreeMap<String, String>,
+}
+
+/// Sets the names columns are shown under. Search prefixes accept the alias
+/// with spaces written as `_` (`client_ip:10.0.0.1`), and exports and
+/// reports use it as the column header.
+#[tauri::command]
+pub fn set_column_aliases(
+    state: State<AppState>,
+    payload: SetColumnAliasesPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    let parquet_path = state.projects.project_dir(&meta.id).join("data.parquet");
+    let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let mut aliases = BTreeMap::new();
+    let mut search_keys: HashMap<String, &str> = HashMap::new();
+    for (column, alias) in &payload.aliases {
+        let alias = alias.split_whitespace().collect::<Vec<_>>().join(" ");
+        if alias.is_empty() || alias == *column {
+            continue;
+        }
+        if column == "__rowid" || !columns.contains(column) {
+            return Err(AppError::Message(format!("Unknown column: {}", column)));
+        }
+        // Real column names win in search prefixes, so an alias searched
+        // like another column's name could never be used.
+        let key = alias_search_key(&alias);
+        if columns.iter().any(|other| {
+            other != column && (other.eq_ignore_ascii_case(&alias) || other.to_lowercase() == key)
+        }) {
+            return Err(AppError::Message(format!(
+                "Alias {} is already the name of a column.",
+                alias
+            )));
+        }
+        if let Some(other) = search_keys.insert(key, column) {
+            return Err(AppError::Message(format!(
+                "Columns {} and {} cannot share the alias {}.",
+                other, column, alias
+            )));
+        }
+        aliases.insert(column.clone(), alias);
+    }
+    state
+        .projects
+        .update_column_aliases(&meta.id, aliases)
+        .map_err(AppError::from)?;
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+    Ok(ProjectSummary { meta })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SetColumnDescriptionPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub column: String,
+    /// Free text; removes the description when empty or unset.
+    #[serde(default)]
+    pub description: Option<String>,
+}
+
+/// Documents what a column holds. Descriptions are kept in the project
+/// metadata, so they come with `load_project` and travel in bundles.
+#[tauri::command]
+pub fn set_column_description(
+    state: State<AppState>,
+    payload: SetColumnDescriptionPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    let description = payload
+        .description
+        .map(|description| description.trim().to_string())
+        .filter(|description| !description.is_empty());
+    if description.is_some() {
+        let parquet_path = state.projects.project_dir(&meta.id).join("data.parquet");
+        let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+        if payload.column == "__rowid" || !columns.contains(&payload.column) {
+            return Err(AppError::Message(format!(
+                "Unknown column: {}",
+                payload.column
+            )));
+        }
     }
+    state
+        .projects
+        .update_column_description(&meta.id, &payload.column, description)
+        .map_err(AppError::from)?;
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+    Ok(ProjectSummary { meta })
+}
+
+/// Lists the worksheets of an Excel workbook so the user can pick one to import.
+#[tauri::command]
+pub fn list_sheets(payload: ListSheetsPayload) -> Result<Vec<String>, AppError> {
+    list_workbook_sheets(&PathBuf::from(&payload.path))
+        .map_err(|err| AppError::Message(format!("Failed to read the workbook: {:#}", err)))
+}
 
-    let file = File::open(&source_path)
-        .map_err(|e| AppError::Message(format!("Failed to open file: {}", e)))?;
-    let mut df = CsvReader::new(file)
-        .finish()
-        .map_err(|_| AppError::Message("Failed to parse the CSV data.".into()))?;
+/// Lists the tool presets that can be selected at import.
+#[tauri::command]
+pub fn list_import_presets() -> Vec<ImportPresetInfo> {
+    list_presets()
+}
+
+/// Lists the tables and views of a SQLite database so the user can pick one to import.
+#[tauri::command]
+pub fn list_sqlite_tables(payload: ListSheetsPayload) -> Result<Vec<String>, AppError> {
+    list_database_tables(&PathBuf::from(&payload.path))
+        .map_err(|err| AppError::Message(format!("Failed to read the database: {:#}", err)))
+}
+
+/// Parses the selected source files into one frame; several files are
+/// merged through a staging directory under `project_dir`.
+fn read_sources(
+    source_paths: &[PathBuf],
+    project_dir: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame, AppError> {
+    let df = if source_paths.len() > 1 {
+        let staging_dir = project_dir.join("import-staging");
+        read_input_dataframes_parallel(source_paths, &staging_dir, options, performance, report)
+            .map_err(|err| {
+                AppError::Message(format!("Failed to import the selected files: {:#}", err))
+            })?
+    } else {
+        read_input_dataframe(&source_paths[0], options, performance, report).map_err(|err| {
+            AppError::Message(format!("Failed to parse the selected file: {:#}", err))
+        })?
+    };
+    report.rows_imported = df.height();
+    check_dtype_overrides(options, &df.schema())?;
+    Ok(df)
+}
+
+fn check_dtype_overrides(options: &ImportOptions, schema: &Schema) -> Result<(), AppError> {
+    match options
+        .dtypes
+        .keys()
+        .chain(&options.timestamp_columns)
+        .chain(&options.lowercase_columns)
+        .chain(&options.strip_thousands_columns)
+        .find(|name| !schema.contains(name))
+    {
+        Some(missing) => Err(AppError::Message(format!(
+            "Column {} was not found in the imported data.",
+            missing
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// What [`import_into_project`] wrote for a new project.
+struct ImportedData {
+    rows: usize,
+    flags: HashMap<usize, FlagEntry>,
+    column_types: Vec<ColumnType>,
+    /// Column that looks like the event time, see [`detect_timestamp_column`].
+    timestamp_column: Option<String>,
+}
+
+/// Imports the sources into the project's Parquet file. A single CSV input
+/// is streamed in batches written as row groups, so inputs larger than
+/// memory can be imported; other inputs are read whole.
+fn import_into_project(
+    source_paths: &[PathBuf],
+    project_dir: &Path,
+    parquet_path: &Path,
+    options: &ImportOptions,
+    scripts: &ImportScripts<'_>,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<ImportedData, AppError> {
+    if let [source] = source_paths {
+        let mut flags = HashMap::new();
+        let streamed = stream_csv_to_parquet(
+            source,
+            parquet_path,
+            options,
+            performance,
+            report,
+            |batch, first_row| {
+                flags.extend(
+                    take_imported_flags(batch)
+                        .into_iter()
+                        .map(|(row, entry)| (first_row + row, entry)),
+                );
+                add_row_ids(batch, first_row)?;
+                scripts.apply(batch)
+            },
+        )
+        .map_err(|err| {
+            AppError::Message(format!("Failed to parse the selected file: {:#}", err))
+        })?;
+        if let Some(streamed) = streamed {
+            check_dtype_overrides(options, &streamed.schema)?;
+            report.rows_imported = streamed.rows;
+            return Ok(ImportedData {
+                rows: streamed.rows,
+                flags,
+                column_types: describe_column_types(&streamed.schema),
+                timestamp_column: detect_timestamp_column(&streamed.schema),
+            });
+        }
+    }
+
+    let mut df = read_sources(source_paths, project_dir, options, performance, report)?;
+    let flags = take_imported_flags(&mut df);
+    add_row_ids(&mut df, 0).map_err(AppError::from)?;
+    scripts.apply(&mut df).map_err(|err| {
+        AppError::Message(format!("Failed to enrich the imported rows: {:#}", err))
+    })?;
+    write_project_dataframe(parquet_path, &mut df).map_err(AppError::from)?;
+    Ok(ImportedData {
+        rows: df.height(),
+        flags,
+        column_types: describe_column_types(&df.schema()),
+        timestamp_column: detect_timestamp_column(&df.schema()),
+    })
+}
 
+/// Removes `trivium-*` columns written by a previous export and returns the
+/// flags and memos they carried, keyed by row index.
+fn take_imported_flags(df: &mut DataFrame) -> HashMap<usize, FlagEntry> {
     let mut imported_flags: HashMap<usize, FlagEntry> = HashMap::new();
     let has_safe = df.get_column_names().iter().any(|c| c == &"trivium-safe");
     let has_suspicious = df
@@ -166,6 +617,7 @@ pub fn create_project(
                 memo: memo_val
                     .map(|m| m.trim().to_string())
                     .filter(|m| !m.is_empty()),
+                comments: Vec::new(),
             };
             if !flag_entry.flag.is_empty() || flag_entry.memo.is_some() {
                 imported_flags.insert(row_idx, flag_entry);
@@ -173,63 +625,339 @@ pub fn create_project(
         }
 
         if let Ok(next) = df.drop("trivium-safe") {
-            df = next;
+            *df = next;
         }
         if let Ok(next) = df.drop("trivium-suspicious") {
-            df = next;
+            *df = next;
         }
         if let Ok(next) = df.drop("trivium-critical") {
-            df = next;
+            *df = next;
         }
         if let Ok(next) = df.drop("trivium-memo") {
-            df = next;
+            *df = next;
         }
     }
 
-    let row_ids: Vec<i64> = (0..df.height()).map(|idx| idx as i64).collect();
+    imported_flags
+}
+
+/// Numbers the rows of `df` from `first_row` in the `__rowid` column.
+pub(super) fn add_row_ids(df: &mut DataFrame, first_row: usize) -> anyhow::Result<()> {
+    let row_ids: Vec<i64> = (first_row..first_row + df.height())
+        .map(|idx| idx as i64)
+        .collect();
     let row_id_series = Series::new("__rowid", row_ids);
     df.with_column(row_id_series)
-        .map_err(|_| AppError::Message("Failed to add row ids to the dataset.".into()))?;
+        .context("failed to add row ids to the dataset")?;
+    Ok(())
+}
+
+/// Creates a new project from one or more CSV files and persists metadata plus optional flags.
+#[tauri::command]
+pub fn create_project(
+    state: State<AppState>,
+    payload: CreateProjectPayload,
+) -> Result<CreateProjectResponse, AppError> {
+    let patterns = if payload.paths.is_empty() {
+        std::slice::from_ref(&payload.path)
+    } else {
+        payload.paths.as_slice()
+    };
+    let source_paths = expand_input_paths(patterns).map_err(|err| {
+        AppError::Message(format!("Failed to resolve the selected files: {:#}", err))
+    })?;
+    if source_paths.iter().any(|path| !path.exists()) {
+        return Err(AppError::Message("Selected file no longer exists.".into()));
+    }
+    let source_path = source_paths[0].clone();
 
     let project_id = Uuid::new_v4();
     let project_dir = state.projects.project_dir(&project_id);
-    if !project_dir.exists() {
-        fs::create_dir_all(&project_dir)
-            .with_context(|| format!("failed to create project dir {:?}", project_dir))
-            .map_err(AppError::from)?;
+
+    let performance = state.settings.performance();
+    let options = ImportOptions {
+        sheet: payload.sheet.clone().filter(|sheet| !sheet.is_empty()),
+        table: payload.table.clone().filter(|table| !table.is_empty()),
+        dtypes: payload.dtypes.clone(),
+        parse_dates: payload.parse_dates,
+        has_headers: payload.has_headers.unwrap_or(true),
+        lenient: payload.lenient,
+        timestamp_columns: payload.timestamp_columns.clone(),
+        source_timezone: payload
+            .source_timezone
+            .as_deref()
+            .map(str::trim)
+            .filter(|zone| !zone.is_empty())
+            .map(str::to_string),
+        trim_whitespace: payload.trim_whitespace,
+        lowercase_columns: payload.lowercase_columns.clone(),
+        strip_thousands_columns: payload.strip_thousands_columns.clone(),
+        script_columns: payload.script_columns.clone(),
+    };
+    if let Some(zone) = &options.source_timezone {
+        ProjectTimezone::parse(zone)
+            .map_err(|err| AppError::Message(format!("Invalid source time zone: {}", err)))?;
+    }
+    let sources = describe_sources(&source_paths).map_err(AppError::from)?;
+    let scripts = ImportScripts::compile(&options.script_columns)
+        .map_err(|err| AppError::Message(format!("{:#}", err)))?;
+    fs::create_dir_all(&project_dir)
+        .with_context(|| format!("failed to create project dir {:?}", project_dir))
+        .map_err(AppError::from)?;
+    let parquet_path = project_dir.join("data.parquet");
+    let mut report = ImportReport::default();
+    let imported = import_into_project(
+        &source_paths,
+        &project_dir,
+        &parquet_path,
+        &options,
+        &scripts,
+        &performance,
+        &mut report,
+    )
+    .inspect_err(|_| {
+        let _ = fs::remove_dir_all(&project_dir);
+    })?;
+    let columns: Vec<String> = imported
+        .column_types
+        .iter()
+        .map(|column| column.name.clone())
+        .collect();
+    let setup = match payload
+        .preset
+        .as_deref()
+        .filter(|preset| !preset.is_empty())
+    {
+        Some(selection) => setup_for_columns(selection, &columns).map_err(|err| {
+            let _ = fs::remove_dir_all(&project_dir);
+            AppError::Message(format!("Failed to apply the import preset: {}", err))
+        })?,
+        None => None,
+    }
+    .unwrap_or_default();
+    if !setup.sigma_mapping.is_empty() {
+        save_sigma_mapping(&project_dir, &setup.sigma_mapping).map_err(AppError::from)?;
     }
 
+    let lenient = options.lenient;
+    let timestamp_column = options
+        .timestamp_columns
+        .first()
+        .cloned()
+        .or(setup.timestamp_column)
+        .or(imported.timestamp_column);
     let metadata = ProjectMeta {
         id: project_id,
-        name: source_path
-            .file_stem()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| "Imported Project".to_string()),
+        format_version: CURRENT_FORMAT_VERSION,
+        name: source_display_name(&source_path),
         description: payload.description.clone(),
         created_at: Utc::now(),
-        total_records: df.height(),
-        flagged_records: imported_flags
+        total_records: imported.rows,
+        flagged_records: imported
+            .flags
             .values()
             .filter(|entry| !entry.flag.trim().is_empty())
             .count(),
         ioc_applied_records: 0,
-        hidden_columns: Vec::new(),
+        hidden_columns: setup.hidden_columns,
+        search_visible_only: false,
+        column_aliases: BTreeMap::new(),
+        column_descriptions: BTreeMap::new(),
+        ioc_policy: Default::default(),
+        sources,
+        import_options: options,
+        preset: Some(setup.preset).filter(|preset| !preset.is_empty()),
+        timestamp_column,
+        last_opened_at: None,
+        pinned: false,
+        alerts: Default::default(),
+        display_timezone: None,
+        encryption: None,
     };
 
-    let parquet_path = project_dir.join("data.parquet");
-    write_project_dataframe(&parquet_path, &mut df).map_err(AppError::from)?;
-
     let flags_path = project_dir.join("flags.json");
-    if !imported_flags.is_empty() {
-        save_flags(&flags_path, &imported_flags).map_err(AppError::from)?;
+    if !imported.flags.is_empty() {
+        save_flags(&flags_path, &imported.flags).map_err(AppError::from)?;
     }
+    save_import_report(&project_dir.join(IMPORT_REPORT_FILE), &report).map_err(AppError::from)?;
 
     state
         .projects
         .insert(metadata.clone())
         .map_err(AppError::from)?;
 
-    Ok(ProjectSummary { meta: metadata })
+    Ok(CreateProjectResponse {
+        project: ProjectSummary { meta: metadata },
+        column_types: imported.column_types,
+        import_report: lenient.then(|| {
+            report.issues.truncate(IMPORT_REPORT_PREVIEW);
+            report
+        }),
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ReimportProjectPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Updated export to read instead of the recorded source files.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+/// Re-reads a project's source files (or an updated export), replaces its
+/// data, and moves flags and memos onto rows with identical content.
+/// Duplicate rows are paired in order of appearance.
+#[tauri::command]
+pub fn reimport_project(
+    state: State<AppState>,
+    payload: ReimportProjectPayload,
+) -> Result<ReimportProjectResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.rewrite(&meta.id, "reimport_project")?;
+    let source_paths: Vec<PathBuf> =
+        match payload.path.filter(|path| !path.is_empty()) {
+            Some(path) => expand_input_paths(&[path]).map_err(|err| {
+                AppError::Message(format!("Failed to resolve the selected files: {:#}", err))
+            })?,
+            None if !meta.sources.is_empty() => meta
+                .sources
+                .iter()
+                .map(|source| PathBuf::from(&source.path))
+                .collect(),
+            None => return Err(AppError::Message(
+                "This project has no recorded source file. Choose the updated export to re-import."
+                    .into(),
+            )),
+        };
+    if let Some(missing) = source_paths.iter().find(|path| !path.exists()) {
+        return Err(AppError::Message(format!(
+            "Source file no longer exists: {:?}",
+            missing
+        )));
+    }
+
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    let flags_path = project_dir.join("flags.json");
+    let previous = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
+    let previous_flags = load_flags(&flags_path).map_err(AppError::from)?;
+
+    let performance = state.settings.performance();
+    let sources = describe_sources(&source_paths).map_err(AppError::from)?;
+    let scripts = ImportScripts::compile(&meta.import_options.script_columns)
+        .map_err(|err| AppError::Message(format!("{:#}", err)))?;
+    let mut report = ImportReport::default();
+    let mut df = read_sources(
+        &source_paths,
+        &project_dir,
+        &meta.import_options,
+        &performance,
+        &mut report,
+    )?;
+    let mut flags = take_imported_flags(&mut df);
+
+    // Match on the columns both versions share, so an export that gained a
+    // column still lines up. File names are ignored since corrected exports
+    // are often renamed.
+    let shared: Vec<String> = df
+        .get_column_names()
+        .into_iter()
+        .filter(|name| *name != "__rowid" && *name != SOURCE_FILE_COLUMN)
+        .filter(|name| previous.column(name).is_ok())
+        .map(str::to_string)
+        .collect();
+    let mut matched_rows = 0;
+    let mut carried_annotations = 0;
+    let mut moved_rows: HashMap<usize, usize> = HashMap::new();
+    if !shared.is_empty() {
+        let mut previous_rows: HashMap<u64, VecDeque<usize>> = HashMap::new();
+        for (index, fingerprint) in row_fingerprints(&previous, &shared).into_iter().enumerate() {
+            previous_rows
+                .entry(fingerprint)
+                .or_default()
+                .push_back(index);
+        }
+        for (index, fingerprint) in row_fingerprints(&df, &shared).into_iter().enumerate() {
+            let Some(previous_index) = previous_rows
+                .get_mut(&fingerprint)
+                .and_then(VecDeque::pop_front)
+            else {
+                continue;
+            };
+            matched_rows += 1;
+            moved_rows.insert(previous_index, index);
+            if let Some(entry) = previous_flags.get(&previous_index) {
+                flags.insert(index, entry.clone());
+                carried_annotations += 1;
+            }
+        }
+    }
+    add_row_ids(&mut df, 0).map_err(AppError::from)?;
+    scripts.apply(&mut df).map_err(|err| {
+        AppError::Message(format!("Failed to enrich the imported rows: {:#}", err))
+    })?;
+
+    let staged_path = project_dir.join("data.parquet.reimport");
+    write_project_dataframe(&staged_path, &mut df).map_err(AppError::from)?;
+    fs::rename(&staged_path, &parquet_path)
+        .with_context(|| format!("failed to replace {:?}", parquet_path))
+        .map_err(AppError::from)?;
+    save_flags(&flags_path, &flags).map_err(AppError::from)?;
+    remap_bookmarks(&project_dir.join(BOOKMARKS_FILE), |row| {
+        moved_rows.get(&row).copied()
+    })
+    .map_err(AppError::from)?;
+    save_import_report(&project_dir.join(IMPORT_REPORT_FILE), &report).map_err(AppError::from)?;
+    let _ = fs::remove_file(project_dir.join(COLUMN_METRICS_FILE));
+    for (cache, result) in [
+        ("searchable", clear_searchable_cache(&project_dir)),
+        ("IOC", clear_ioc_flag_cache(&project_dir)),
+    ] {
+        if let Err(err) = result {
+            tracing::warn!(
+                "failed to clear {} cache for {:?}: {:?}",
+                cache,
+                project_dir,
+                err
+            );
+        }
+    }
+
+    let flagged_records = flags
+        .values()
+        .filter(|entry| !entry.flag.trim().is_empty())
+        .count();
+    state
+        .projects
+        .update_source(&meta.id, df.height(), flagged_records, sources)
+        .map_err(AppError::from)?;
+    let ioc_applied_records =
+        calculate_ioc_applied_records(&project_dir, meta.ioc_policy, meta.unsearched_columns())
+            .map_err(AppError::from)?;
+    state
+        .projects
+        .update_ioc_applied_records(&meta.id, ioc_applied_records)
+        .map_err(AppError::from)?;
+    state.changes.flags_updated(meta.id, None);
+    alerts::check_project(&state, &meta.id);
+    let meta = state
+        .projects
+        .find(&meta.id)
+        .ok_or_else(|| AppError::ProjectNotFound(meta.id))?;
+
+    Ok(ReimportProjectResponse {
+        project: ProjectSummary { meta },
+        matched_rows,
+        added_rows: df.height() - matched_rows,
+        removed_rows: previous.height() - matched_rows,
+        carried_annotations,
+        dropped_annotations: previous_flags.len() - carried_annotations,
+    })
 }
 
 #[derive(Debug, Deserialize)]
@@ -238,24 +966,42 @@ pub struct ProjectRequest {
     pub project_id: Uuid,
 }
 
+/// Re-hashes the files a project was imported from, for chain-of-custody
+/// records. Sources that were moved or deleted are reported as missing.
+#[tauri::command]
+pub fn verify_source_integrity(
+    state: State<AppState>,
+    request: ProjectRequest,
+) -> Result<Vec<SourceIntegrity>, AppError> {
+    let meta = state
+        .projects
+        .find(&request.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(request.project_id))?;
+    if meta.sources.is_empty() {
+        return Err(AppError::Message(
+            "This project has no recorded source file hashes.".into(),
+        ));
+    }
+    verify_sources(&meta.sources).map_err(AppError::from)
+}
+
 /// Removes a project directory and clears related caches.
 #[tauri::command]
-pub fn delete_project(state: State<AppState>, request: ProjectRequest) -> Result<(), String> {
+pub fn delete_project(state: State<AppState>, request: ProjectRequest) -> Result<(), AppError> {
     let Some(meta) = state.projects.find(&request.project_id) else {
         return Ok(());
     };
+    let _lock = state.locks.rewrite(&meta.id, "delete_project")?;
     let project_dir = state.projects.project_dir(&meta.id);
     if let Err(err) = clear_searchable_cache(&project_dir) {
-        eprintln!(
-            "[cache] failed to clear searchable cache for {:?}: {:?}",
-            project_dir, err
+        tracing::warn!(
+            "failed to clear searchable cache for {:?}: {:?}",
+            project_dir,
+            err
         );
     }
     if let Err(err) = clear_ioc_flag_cache(&project_dir) {
-        eprintln!(
-            "[cache] failed to clear IOC cache for {:?}: {:?}",
-            project_dir, err
-        );
+        tracing::warn!("failed to clear IOC cache for {:?}: {:?}", project_dir, err);
     }
     if project_dir.exists() {
         fs::remove_dir_all(&project_dir)
@@ -263,26 +1009,123 @@ pub fn delete_project(state: State<AppState>, request: ProjectRequest) -> Result
             .map_err(AppError::from)?;
     }
     state.projects.remove(&meta.id).map_err(AppError::from)?;
+    state.sorts.forget(&meta.id);
     Ok(())
 }
 
+#[derive(Debug, Deserialize)]
+pub struct CloneProjectPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Name of the copy; `<name> (copy)` when absent.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+/// Duplicates a project with its data, flags, IOC rules and caches under a
+/// new id, as a sandbox for bulk flagging or experimental rule sets.
+#[tauri::command]
+pub fn clone_project(
+    state: State<AppState>,
+    payload: CloneProjectPayload,
+) -> Result<ProjectSummary, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    let _lock = state.locks.read(&meta.id)?;
+    ensure_unsealed(&state, &meta.id)?;
+    let source_dir = state.projects.project_dir(&meta.id);
+    let mut copy = meta.clone();
+    copy.id = Uuid::new_v4();
+    copy.created_at = Utc::now();
+    copy.last_opened_at = None;
+    copy.pinned = false;
+    copy.name = payload
+        .name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("{} (copy)", meta.name));
+    let target_dir = state.projects.project_dir(&copy.id);
+    if let Err(err) = copy_dir_recursive(&source_dir, &target_dir) {
+        let _ = fs::remove_dir_all(&target_dir);
+        return Err(AppError::from(err));
+    }
+    // The copy of an open encrypted project shares its passphrase and is
+    // sealed right away, so its files never sit unencrypted.
+    if copy.encryption.is_some() {
+        let sealed = state
+            .unlocked
+            .with_key(&meta.id, |key| {
+                seal_project(&target_dir, key).map_err(AppError::from)
+            })
+            .unwrap_or(Err(AppError::PassphraseRequired(meta.id)));
+        if let Err(err) = sealed {
+            let _ = fs::remove_dir_all(&target_dir);
+            return Err(err);
+        }
+    }
+    state
+        .projects
+        .insert(copy.clone())
+        .map_err(AppError::from)?;
+    Ok(ProjectSummary { meta: copy })
+}
+
+/// Reports whether a command is rewriting the project and how many read it,
+/// so the UI can disable actions instead of running into a busy error.
+#[tauri::command]
+pub fn get_project_status(
+    state: State<AppState>,
+    request: ProjectRequest,
+) -> Result<ProjectStatus, AppError> {
+    let meta = state
+        .projects
+        .find(&request.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(request.project_id))?;
+    Ok(state.locks.status(&meta.id))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct LoadProjectRequest {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Unseals an encrypted project; see `encryption`.
+    #[serde(default)]
+    pub passphrase: Option<String>,
+}
+
 /// Loads project metadata, initial rows, IOC entries, and column metrics.
+/// A sealed encrypted project is unsealed first and fails with
+/// `passphrase_required` when no passphrase is given.
 #[tauri::command]
 pub fn load_project(
     state: State<AppState>,
-    request: ProjectRequest,
-) -> Result<LoadProjectResponse, String> {
+    request: LoadProjectRequest,
+) -> Result<LoadProjectResponse, AppError> {
     let meta = state
         .projects
         .find(&request.project_id)
-        .ok_or_else(|| AppError::Message("Project not found.".into()))?;
+        .ok_or_else(|| AppError::ProjectNotFound(request.project_id))?;
+    unlock_project(&state, &meta, request.passphrase.as_deref())?;
+    let _lock = state.locks.read(&meta.id)?;
+    if meta.format_version > CURRENT_FORMAT_VERSION {
+        return Err(AppError::Message(
+            "This project was saved by a newer version of Trivium.".into(),
+        ));
+    }
     let project_dir = state.projects.project_dir(&meta.id);
     let parquet_path = project_dir.join("data.parquet");
     if !parquet_path.exists() {
-        return Err(AppError::Message("Project data file missing.".into()).into());
+        return Err(AppError::DataFileMissing(parquet_path));
     }
 
     let df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
+    if let Err(err) = state.projects.mark_opened(&meta.id) {
+        tracing::warn!("failed to record opening {}: {:?}", meta.id, err);
+    }
     let columns: Vec<String> = df
         .get_column_names()
         .into_iter()
@@ -293,31 +1136,73 @@ pub fn load_project(
     let flags_path = project_dir.join("flags.json");
     let flags = load_flags(&flags_path).map_err(AppError::from)?;
 
+    // Only exact widths are stored. Sampled ones are cheap to measure again,
+    // and not storing them lets the exact setting take effect on next open.
     let metrics_path = project_dir.join(COLUMN_METRICS_FILE);
-    let mut column_max_chars = match load_column_metrics(&metrics_path).map_err(AppError::from)? {
+    let stored_metrics = load_column_metrics(&metrics_path)
+        .map_err(AppError::from)?
+        .filter(|map| columns.iter().all(|column| map.contains_key(column)));
+    let column_max_chars = match stored_metrics {
         Some(map) => map,
-        None => {
-            let computed = compute_column_max_chars(&df);
+        None if state.settings.performance().exact_column_widths => {
+            let computed = compute_column_max_chars(&df, None);
             save_column_metrics(&metrics_path, &computed).map_err(AppError::from)?;
             computed
         }
+        None => compute_column_max_chars(&df, Some(COLUMN_WIDTH_SAMPLE_ROWS)),
     };
-    if columns
-        .iter()
-        .any(|column| !column_max_chars.contains_key(column))
-    {
-        column_max_chars = compute_column_max_chars(&df);
-        save_column_metrics(&metrics_path, &column_max_chars).map_err(AppError::from)?;
-    }
 
     let iocs = load_ioc_entries(&project_dir).map_err(AppError::from)?;
+    let view = load_views(&project_dir.join(VIEWS_FILE))
+        .map_err(AppError::from)?
+        .into_iter()
+        .find(|view| view.is_default);
 
-    let page_limit = usize::min(DEFAULT_PAGE_SIZE, df.height());
-    let mut initial_rows = materialize_rows(&df, &columns, 0..page_limit, &flags);
-    apply_iocs_to_rows(&mut initial_rows, &iocs);
+    let mut hidden_columns = meta.hidden_columns.clone();
+    let initial_rows = match &view {
+        Some(view) => {
+            if !view.visible_columns.is_empty() {
+                hidden_columns = columns
+                    .iter()
+                    .filter(|column| !view.visible_columns.contains(column))
+                    .cloned()
+                    .collect();
+            }
+            let query = QueryRowsPayload {
+                project_id: meta.id,
+                search: view.search.clone(),
+                whole_word: view.whole_word,
+                columns: None,
+                flag_filter: view.flag_filter.clone(),
+                bookmarked_only: false,
+                offset: None,
+                limit: view.page_size,
+                sort_key: view.sort_key.clone(),
+                sort_direction: view.sort_direction.clone(),
+                timeline: false,
+                time_from: None,
+                time_to: None,
+                debug: false,
+            };
+            query_project_rows(state.clone(), query)?.rows
+        }
+        None => {
+            let page_size = state.settings.page_size().unwrap_or(DEFAULT_PAGE_SIZE);
+            let page_limit = usize::min(page_size, df.height());
+            let mut rows = materialize_rows(
+                &df,
+                &columns,
+                0..page_limit,
+                &flags,
+                time_display(&meta, &state.settings).as_ref(),
+            );
+            apply_iocs_to_rows(&mut rows, &iocs, meta.ioc_policy, meta.unsearched_columns());
+            rows
+        }
+    };
 
-    println!(
-        "[debug] load_project id={} total_rows={} initial_rows={}",
+    tracing::debug!(
+        "load_project id={} total_rows={} initial_rows={}",
         meta.id,
         df.height(),
         initial_rows.len()
@@ -328,9 +1213,10 @@ pub fn load_project(
     Ok(LoadProjectResponse {
         project: summary,
         columns,
-        hidden_columns: meta.hidden_columns.clone(),
+        hidden_columns,
         column_max_chars,
         iocs,
         initial_rows,
+        view,
     })
 }
@@ -0,0 +1,296 @@
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::Write;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use chrono::Utc;
+use polars::prelude::*;
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError, models::ProjectMeta, redaction::RedactionMode, state::AppState,
+    value_utils::anyvalue_to_search_string,
+};
+
+use super::{
+    encryption::ensure_unsealed,
+    export::{
+        create_export_file, load_annotated_project, redact_columns, timeline_column, RowAnnotation,
+    },
+};
+
+/// Flagged rows listed individually; the rest only count towards the tables.
+const REPORT_MAX_LISTED_ROWS: usize = 500;
+/// Longest memo or cell text shown in a report table.
+const REPORT_MAX_CELL_CHARS: usize = 160;
+const SEVERITIES: [&str; 3] = ["critical", "suspicious", "safe"];
+const UNTAGGED: &str = "(manual flag)";
+
+#[derive(Debug, Deserialize)]
+pub struct ExportReportPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub destination: String,
+    /// Columns to mask or hash in the listed rows, as for `export_project`.
+    #[serde(default)]
+    pub redact: BTreeMap<String, RedactionMode>,
+    #[serde(default, rename = "redactionKey")]
+    pub redaction_key: Option<String>,
+}
+
+/// Rows sharing one tag and severity, with the time span they cover.
+#[derive(Default)]
+struct Finding {
+    rows: usize,
+    first_seen: Option<String>,
+    last_seen: Option<String>,
+}
+
+impl Finding {
+    fn add(&mut self, timestamp: Option<&String>) {
+        self.rows += 1;
+        if let Some(timestamp) = timestamp {
+            if self
+                .first_seen
+                .as_ref()
+                .is_none_or(|first| timestamp < first)
+            {
+                self.first_seen = Some(timestamp.clone());
+            }
+            if self.last_seen.as_ref().is_none_or(|last| timestamp > last) {
+                self.last_seen = Some(timestamp.clone());
+            }
+        }
+    }
+}
+
+fn severity_rank(flag: &str) -> usize {
+    SEVERITIES
+        .iter()
+        .position(|severity| *severity == flag)
+        .unwrap_or(SEVERITIES.len())
+}
+
+/// Timestamps rendered as text. Ranges compare them as strings, which
+/// orders ISO 8601 and the rendered date/time types chronologically.
+fn timestamps(df: &DataFrame, column: Option<&str>) -> Vec<Option<String>> {
+    let Some(series) = column.and_then(|column| df.column(column).ok()) else {
+        return vec![None; df.height()];
+    };
+    (0..df.height())
+        .map(|row| {
+            series
+                .get(row)
+                .ok()
+                .and_then(|value| anyvalue_to_search_string(&value))
+                .map(|text| text.trim().to_string())
+                .filter(|text| !text.is_empty())
+        })
+        .collect()
+}
+
+/// Makes text safe for a single Markdown table cell.
+fn table_cell(text: &str) -> String {
+    let flattened: String = text
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .replace('|', "\\|");
+    if flattened.chars().count() > REPORT_MAX_CELL_CHARS {
+        let shortened: String = flattened.chars().take(REPORT_MAX_CELL_CHARS).collect();
+        format!("{}…", shortened)
+    } else {
+        flattened
+    }
+}
+
+fn time_range(first: Option<&String>, last: Option<&String>) -> String {
+    match (first, last) {
+        (Some(first), Some(last)) if first == last => table_cell(first),
+        (Some(first), Some(last)) => format!("{} – {}", table_cell(first), table_cell(last)),
+        _ => "–".to_string(),
+    }
+}
+
+fn build_report(meta: &ProjectMeta, df: &DataFrame, annotations: &[RowAnnotation]) -> String {
+    let timeline = timeline_column(df, meta);
+    let times = timestamps(df, timeline.as_deref());
+    let mut out = String::new();
+
+    let _ = writeln!(out, "# Trivium report: {}\n", meta.name);
+    if let Some(description) = meta.description.as_deref().filter(|d| !d.trim().is_empty()) {
+        let _ = writeln!(out, "{}\n", description.trim());
+    }
+    let _ = writeln!(
+        out,
+        "- Generated: {}",
+        Utc::now().format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    let _ = writeln!(
+        out,
+        "- Project created: {}",
+        meta.created_at.format("%Y-%m-%d %H:%M:%S UTC")
+    );
+    for source in &meta.sources {
+        let _ = writeln!(
+            out,
+            "- Source: `{}` (SHA-256 `{}`)",
+            source.path, source.sha256
+        );
+    }
+
+    let _ = writeln!(out, "\n## Overview\n");
+    let _ = writeln!(out, "| Measure | Value |\n| --- | --- |");
+    let _ = writeln!(out, "| Rows | {} |", df.height());
+    for severity in SEVERITIES {
+        let count = annotations
+            .iter()
+            .filter(|row| row.flag == severity)
+            .count();
+        let _ = writeln!(out, "| {} rows | {} |", severity, count);
+    }
+    let all_times: Vec<&String> = times.iter().flatten().collect();
+    let flagged_times: Vec<&String> = annotations
+        .iter()
+        .zip(&times)
+        .filter(|(row, _)| row.flag == "critical" || row.flag == "suspicious")
+        .filter_map(|(_, time)| time.as_ref())
+        .collect();
+    if let Some(column) = &timeline {
+        let _ = writeln!(
+            out,
+            "| Timeline column | `{}` |",
+            table_cell(meta.column_label(column))
+        );
+        let _ = writeln!(
+            out,
+            "| Time range covered | {} |",
+            time_range(
+                all_times.iter().min().copied(),
+                all_times.iter().max().copied()
+            )
+        );
+        let _ = writeln!(
+            out,
+            "| Time range of findings | {} |",
+            time_range(
+                flagged_times.iter().min().copied(),
+                flagged_times.iter().max().copied()
+            )
+        );
+    }
+
+    // Findings keyed by (severity rank, tag) so the table lists critical first.
+    let mut findings: BTreeMap<(usize, String), Finding> = BTreeMap::new();
+    for (row, time) in annotations.iter().zip(&times) {
+        if row.flag.is_empty() {
+            continue;
+        }
+        let rank = severity_rank(&row.flag);
+        if row.tags.is_empty() {
+            findings
+                .entry((rank, UNTAGGED.to_string()))
+                .or_default()
+                .add(time.as_ref());
+        }
+        for tag in &row.tags {
+            findings
+                .entry((rank, tag.clone()))
+                .or_default()
+                .add(time.as_ref());
+        }
+    }
+    let _ = writeln!(out, "\n## Findings by tag\n");
+    if findings.is_empty() {
+        let _ = writeln!(out, "No rows are flagged.");
+    } else {
+        let _ = writeln!(
+            out,
+            "| Severity | Tag | Rows | First seen | Last seen |\n| --- | --- | ---: | --- | --- |"
+        );
+        for ((rank, tag), finding) in &findings {
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                SEVERITIES.get(*rank).copied().unwrap_or("other"),
+                table_cell(tag),
+                finding.rows,
+                finding
+                    .first_seen
+                    .as_deref()
+                    .map(table_cell)
+                    .unwrap_or_else(|| "–".into()),
+                finding
+                    .last_seen
+                    .as_deref()
+                    .map(table_cell)
+                    .unwrap_or_else(|| "–".into()),
+            );
+        }
+    }
+
+    let mut flagged: Vec<usize> = (0..annotations.len())
+        .filter(|&row| matches!(annotations[row].flag.as_str(), "critical" | "suspicious"))
+        .collect();
+    flagged.sort_by(|a, b| {
+        severity_rank(&annotations[*a].flag)
+            .cmp(&severity_rank(&annotations[*b].flag))
+            .then_with(|| times[*a].cmp(&times[*b]))
+    });
+    let _ = writeln!(out, "\n## Critical and suspicious rows\n");
+    if flagged.is_empty() {
+        let _ = writeln!(out, "No rows are flagged critical or suspicious.");
+    } else {
+        let _ = writeln!(
+            out,
+            "| Row | Severity | Time | Tags | Memo |\n| ---: | --- | --- | --- | --- |"
+        );
+        for &row in flagged.iter().take(REPORT_MAX_LISTED_ROWS) {
+            let annotation = &annotations[row];
+            let _ = writeln!(
+                out,
+                "| {} | {} | {} | {} | {} |",
+                row,
+                annotation.flag,
+                times[row].as_deref().map(table_cell).unwrap_or_default(),
+                table_cell(&annotation.tags.join(" ")),
+                table_cell(&annotation.memo),
+            );
+        }
+        if flagged.len() > REPORT_MAX_LISTED_ROWS {
+            let _ = writeln!(
+                out,
+                "\n{} more rows are not listed.",
+                flagged.len() - REPORT_MAX_LISTED_ROWS
+            );
+        }
+    }
+    out
+}
+
+/// Writes a Markdown summary of the project's findings for tickets and case
+/// wikis.
+#[tauri::command]
+pub fn export_report(state: State<AppState>, payload: ExportReportPayload) -> Result<(), AppError> {
+    ensure_unsealed(&state, &payload.project_id)?;
+    let _lock = state.locks.read(&payload.project_id)?;
+    let (meta, mut df, annotations) = load_annotated_project(&state, &payload.project_id)?;
+    redact_columns(
+        &mut df,
+        &state.projects.project_dir(&meta.id),
+        &payload.redact,
+        payload.redaction_key.as_deref(),
+    )?;
+    let report = build_report(&meta, &df, &annotations);
+    let destination = PathBuf::from(payload.destination);
+    let mut writer = create_export_file(&destination)?;
+    writer
+        .write_all(report.as_bytes())
+        .and_then(|_| writer.flush())
+        .context("failed to write report")
+        .map_err(AppError::from)?;
+    Ok(())
+}
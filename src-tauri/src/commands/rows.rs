@@ -1,38 +1,73 @@
-use std::collections::HashMap;
-
-use anyhow::Error as AnyhowError;
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
 use polars::prelude::*;
+use regex::RegexBuilder;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use uuid::Uuid;
 
 use crate::{
+    column_types::{parse_zoned_datetime, timeline_instants},
     error::AppError,
-    flags::{normalize_flag_value, severity_rank},
-    ioc::load_ioc_entries,
-    models::{FlagEntry, ProjectRow},
-    project_io::read_project_dataframe,
+    flags::normalize_flag_value,
+    ioc::{
+        append_memo_tags, compile_iocs, ioc_flag_cache_key, ioc_flags_from_matches,
+        ioc_match_cache_key, load_ioc_entries, normalize_ioc_columns, order_iocs_for_policy,
+        resolve_ioc_matches,
+    },
+    ip_rules::parse_ip_rules,
+    models::{FlagEntry, IocKind, ProjectMeta, ProjectRow},
+    perf::{Cache, Operation},
+    project_io::{
+        read_project_column_subset, read_project_columns, read_project_dataframe,
+        read_project_row_count, read_project_row_range, read_project_rows,
+    },
     search::{
-        build_search_mask_boolean, ensure_searchable_text, to_rpn, tokenize_search_query,
-        SearchToken,
+        build_search_mask_boolean, describe_token, ensure_column_text_cache,
+        ensure_searchable_text, list_element_column, rpn_to_expression, to_rpn,
+        tokenize_search_query, SearchToken,
     },
+    sort_cache::data_stamp,
     state::AppState,
     storage::{
-        load_flags, load_ioc_flag_cache, load_searchable_cache, save_ioc_flag_cache,
-        save_searchable_cache,
+        clear_searchable_cache, load_bookmarks, load_flags, load_ioc_flag_cache,
+        load_ioc_match_cache, load_searchable_cache, load_searchable_chunk, save_ioc_flag_cache,
+        save_searchable_cache, save_searchable_chunk, IocFlagCache, SearchableCache,
+        BOOKMARKS_FILE,
     },
-    value_utils::anyvalue_to_search_string,
+    timezones::ProjectTimezone,
+    value_utils::{anyvalue_to_search_string, refang, SEARCH_TEXT_FORMAT},
 };
 
 use super::{
+    encryption::ensure_unsealed,
+    entities::FlagBreakdown,
     utils::{
-        build_row_search_text, collect_row_record_from_series, ensure_column_text_cache,
+        add_alias_lookups, aliased_column, build_row_search_text, collect_row_record,
+        collect_row_record_from_series, display_zone, time_display,
     },
     DEFAULT_PAGE_SIZE,
 };
 
-fn matches_flag_filter(current_flag: &str, filter: &str) -> bool {
+/// Projects with more rows than this build and search their row text in
+/// chunks of `SEARCH_CHUNK_ROWS`, keeping memory use flat however large the
+/// project grows.
+const SEARCH_CHUNK_THRESHOLD: usize = 1_000_000;
+const SEARCH_CHUNK_ROWS: usize = 250_000;
+
+fn search_chunk_rows(row_count: usize) -> usize {
+    if row_count > SEARCH_CHUNK_THRESHOLD {
+        SEARCH_CHUNK_ROWS
+    } else {
+        row_count.max(1)
+    }
+}
+
+pub(crate) fn matches_flag_filter(current_flag: &str, filter: &str) -> bool {
     match filter {
         "all" => true,
         "none" => current_flag.is_empty(),
@@ -42,16 +77,177 @@ fn matches_flag_filter(current_flag: &str, filter: &str) -> bool {
     }
 }
 
+/// Columns a search runs over, with lookups by exact and lowercase name.
+pub(crate) struct SearchColumns<'a> {
+    pub rows: usize,
+    pub columns: &'a [String],
+    pub series: &'a HashMap<&'a str, &'a Series>,
+    pub series_lower: &'a HashMap<String, &'a Series>,
+}
+
+/// Evaluates a search query to a per-row match mask, building the row and
+/// per-column text caches it needs. `None` when the query has no terms.
+pub(crate) fn build_search_mask(
+    search: &str,
+    whole_word: bool,
+    columns: &SearchColumns<'_>,
+    searchable_text: &mut Option<Vec<String>>,
+    searchable_text_built: &mut bool,
+    per_column_text: &mut HashMap<String, Vec<String>>,
+) -> Option<Vec<bool>> {
+    let search = search.trim();
+    if search.is_empty() {
+        return None;
+    }
+    let row_count = columns.rows;
+    let tokens = tokenize_search_query(search);
+    let mut terms: Vec<(Option<String>, String)> = Vec::new();
+    let mut needed_cols: Vec<String> = Vec::new();
+    for token in &tokens {
+        if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } = token {
+            let key = (col.clone(), text.clone());
+            if !text.is_empty() && !terms.contains(&key) {
+                terms.push(key);
+            }
+            if let Some(column_name) = col {
+                if !needed_cols.contains(column_name) {
+                    needed_cols.push(column_name.clone());
+                }
+            }
+        }
+    }
+    for column in needed_cols {
+        ensure_column_text_cache(&column, columns.series_lower, per_column_text, row_count);
+    }
+    if terms.is_empty() {
+        return None;
+    }
+    let rpn = to_rpn(&tokens);
+    let search_text = ensure_searchable_text(
+        searchable_text,
+        searchable_text_built,
+        row_count,
+        columns.columns,
+        columns.series,
+    );
+    Some(build_search_mask_boolean(
+        &rpn,
+        &terms,
+        search_text,
+        Some(per_column_text),
+        whole_word,
+    ))
+}
+
+/// Lowercased columns named by `column:term` filters in a search, with list
+/// element suffixes and aliases resolved to their column. `None` when the
+/// search has no terms and so filters nothing.
+fn scoped_search_columns(search: &str, aliases: &BTreeMap<String, String>) -> Option<Vec<String>> {
+    let mut has_terms = false;
+    let mut scoped = Vec::new();
+    for token in tokenize_search_query(search.trim()) {
+        if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } = token {
+            has_terms |= !text.is_empty();
+            if let Some(column) = col {
+                let column = column.to_lowercase();
+                let column = list_element_column(&column).unwrap_or(&column).to_string();
+                let column = aliased_column(&column, aliases)
+                    .map(|column| column.to_lowercase())
+                    .unwrap_or(column);
+                if !scoped.contains(&column) {
+                    scoped.push(column);
+                }
+            }
+        }
+    }
+    has_terms.then_some(scoped)
+}
+
+/// Orders row indices by their instant in time, keeping the stored order
+/// among equal times; rows without a readable time come last.
+fn sort_row_indices_by_time(indices: &mut [usize], instants: &[Option<i64>], descending: bool) {
+    indices.sort_by(|a, b| match (instants[*a], instants[*b]) {
+        (Some(a_time), Some(b_time)) if descending => b_time.cmp(&a_time),
+        (Some(a_time), Some(b_time)) => a_time.cmp(&b_time),
+        (Some(_), None) => std::cmp::Ordering::Less,
+        (None, Some(_)) => std::cmp::Ordering::Greater,
+        (None, None) => std::cmp::Ordering::Equal,
+    });
+}
+
+/// Reads a timeline window bound, in `zone` unless it carries an offset.
+fn time_bound(text: Option<&str>, zone: &ProjectTimezone) -> Result<Option<i64>, AppError> {
+    let Some(text) = text.map(str::trim).filter(|text| !text.is_empty()) else {
+        return Ok(None);
+    };
+    parse_zoned_datetime(text, zone)
+        .map(|value| Some(value.and_utc().timestamp_micros()))
+        .ok_or_else(|| AppError::Message(format!("Cannot read {:?} as a time.", text)))
+}
+
+/// Orders row indices by a column: numerically when values parse as numbers
+/// (ignoring thousands separators), otherwise case-insensitively.
+pub(crate) fn sort_row_indices(
+    df: &DataFrame,
+    indices: &mut [usize],
+    sort_key: &str,
+    descending: bool,
+) {
+    let Ok(series) = df.column(sort_key) else {
+        return;
+    };
+    indices.sort_by(|a, b| {
+        let a_s = series
+            .get(*a)
+            .ok()
+            .and_then(|v| anyvalue_to_search_string(&v))
+            .map(|s| s.trim().replace([',', '\u{00A0}'], ""));
+        let b_s = series
+            .get(*b)
+            .ok()
+            .and_then(|v| anyvalue_to_search_string(&v))
+            .map(|s| s.trim().replace([',', '\u{00A0}'], ""));
+
+        let a_num = a_s.as_ref().and_then(|s| s.parse::<f64>().ok());
+        let b_num = b_s.as_ref().and_then(|s| s.parse::<f64>().ok());
+
+        if a_num.is_some() || b_num.is_some() {
+            let av = a_num.unwrap_or(f64::INFINITY);
+            let bv = b_num.unwrap_or(f64::INFINITY);
+            let ord = av.partial_cmp(&bv).unwrap_or(std::cmp::Ordering::Equal);
+            return if descending { ord.reverse() } else { ord };
+        }
+
+        let ord = match (&a_s, &b_s) {
+            (Some(a_str), Some(b_str)) => a_str.to_lowercase().cmp(&b_str.to_lowercase()),
+            (Some(_), None) => std::cmp::Ordering::Greater,
+            (None, Some(_)) => std::cmp::Ordering::Less,
+            (None, None) => std::cmp::Ordering::Equal,
+        };
+        if descending {
+            ord.reverse()
+        } else {
+            ord
+        }
+    });
+}
+
 #[derive(Debug, Deserialize)]
 pub struct QueryRowsPayload {
     #[serde(rename = "projectId")]
     pub project_id: Uuid,
     #[serde(default)]
     pub search: Option<String>,
+    /// Search terms match whole words only.
+    #[serde(default, rename = "wholeWord")]
+    pub whole_word: bool,
     #[serde(default)]
     pub columns: Option<Vec<String>>,
     #[serde(default, rename = "flagFilter")]
     pub flag_filter: Option<String>,
+    /// Returns only bookmarked rows.
+    #[serde(default, rename = "bookmarkedOnly")]
+    pub bookmarked_only: bool,
     #[serde(default)]
     pub offset: Option<usize>,
     #[serde(default)]
@@ -60,15 +256,55 @@ pub struct QueryRowsPayload {
     pub sort_key: Option<String>,
     #[serde(rename = "sortDirection", default)]
     pub sort_direction: Option<String>,
+    /// Orders rows by the project's time column instead of `sortKey`, oldest
+    /// first unless `sortDirection` is `desc`.
+    #[serde(default)]
+    pub timeline: bool,
+    /// Inclusive bounds of the time window to return in the timeline view.
+    /// Times without an offset are read in the project's display time zone.
+    #[serde(rename = "timeFrom", default)]
+    pub time_from: Option<String>,
+    #[serde(rename = "timeTo", default)]
+    pub time_to: Option<String>,
+    /// Returns how long each phase of the query took.
+    #[serde(default)]
+    pub debug: bool,
+}
+
+/// Milliseconds spent in each phase of a row query. Phases that did not run
+/// are zero, and `total_ms` also covers loading flags, rules and caches.
+#[derive(Debug, Default, Serialize)]
+pub struct QueryTimings {
+    /// Reading row chunks and their cached search text.
+    pub read_ms: f64,
+    pub search_ms: f64,
+    pub ioc_ms: f64,
+    /// Ordering the rows, including reading the sort column.
+    pub sort_ms: f64,
+    /// Applying the flag, bookmark and search filters.
+    pub filter_ms: f64,
+    /// Reading the page's rows and building them.
+    pub materialize_ms: f64,
+    pub total_ms: f64,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
 }
 
 #[derive(Debug, Serialize)]
 pub struct QueryRowsResponse {
     pub rows: Vec<ProjectRow>,
     pub total_flagged: usize,
+    /// Filtered rows per effective flag, across every page.
+    pub flag_counts: FlagBreakdown,
     pub total_rows: usize,
     pub total_filtered_rows: usize,
     pub offset: usize,
+    /// Column the rows were ordered by in the timeline view.
+    pub time_column: Option<String>,
+    /// Set when the query asked for `debug`.
+    pub timings: Option<QueryTimings>,
 }
 
 /// Streams project rows with filtering, sorting, IOC application, and pagination.
@@ -76,244 +312,353 @@ pub struct QueryRowsResponse {
 pub fn query_project_rows(
     state: State<AppState>,
     payload: QueryRowsPayload,
-) -> Result<QueryRowsResponse, String> {
+) -> Result<QueryRowsResponse, AppError> {
     let meta = state
         .projects
         .find(&payload.project_id)
-        .ok_or_else(|| AppError::Message("Project not found.".into()))?;
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let _span = tracing::debug_span!("query_project_rows", project = %meta.id).entered();
+    let started = Instant::now();
     let project_dir = state.projects.project_dir(&meta.id);
     let parquet_path = project_dir.join("data.parquet");
     if !parquet_path.exists() {
-        return Err(AppError::Message("Project data file missing.".into()).into());
+        return Err(AppError::DataFileMissing(parquet_path));
     }
 
-    let df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
-    let columns: Vec<String> = df
-        .get_column_names()
-        .into_iter()
-        .filter(|name| name != &"__rowid")
-        .map(|name| name.to_string())
-        .collect();
+    let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let row_count = read_project_row_count(&parquet_path).map_err(AppError::from)?;
 
     let flags_path = project_dir.join("flags.json");
     let flags = load_flags(&flags_path).map_err(AppError::from)?;
     let iocs = load_ioc_entries(&project_dir).map_err(AppError::from)?;
 
     let offset = payload.offset.unwrap_or(0);
-    let limit = payload.limit.unwrap_or(DEFAULT_PAGE_SIZE).max(1);
-
-    let total_rows_before_flag_filter = df.height();
+    let limit = payload
+        .limit
+        .or_else(|| state.settings.page_size())
+        .unwrap_or(DEFAULT_PAGE_SIZE)
+        .max(1);
 
     let mut rows: Vec<ProjectRow> = Vec::with_capacity(limit);
     let mut total_flagged_after_ioc: usize = 0;
+    let mut flag_counts = FlagBreakdown::default();
 
     let column_names: Vec<String> = columns.clone();
-    let column_series: HashMap<&str, &Series> =
-        df.get_columns().iter().map(|s| (s.name(), s)).collect();
-    let column_series_lower: HashMap<String, &Series> = df
-        .get_columns()
-        .iter()
-        .map(|s| (s.name().to_lowercase(), s))
-        .collect();
-
     let search_cols: Vec<String> = payload
         .columns
         .as_ref()
         .cloned()
-        .unwrap_or_else(|| column_names.clone());
-    let row_count = df.height();
-    let cached_search = match load_searchable_cache(&project_dir) {
+        .unwrap_or_else(|| column_names.clone())
+        .into_iter()
+        .filter(|column| !meta.unsearched_columns().contains(column))
+        .collect();
+    let mut user_flag_vec: Vec<String> = vec![String::new(); row_count];
+    for (idx, entry) in flags.iter() {
+        if *idx < row_count {
+            user_flag_vec[*idx] = normalize_flag_value(&entry.flag);
+        }
+    }
+
+    let ordered_iocs = order_iocs_for_policy(&iocs, meta.ioc_policy);
+    let value_columns: Vec<String> = search_cols.iter().map(|c| c.to_lowercase()).collect();
+    let ioc_cache_key = ioc_flag_cache_key(&ordered_iocs, &value_columns, &user_flag_vec);
+    let cached_ioc_flags = match load_ioc_flag_cache(&project_dir) {
         Ok(cache) => cache,
         Err(err) => {
-            eprintln!(
-                "[cache] failed to load searchable cache for {:?}: {:?}",
-                project_dir, err
-            );
-            None
-        }
-    };
-    let mut searchable_text: Option<Vec<String>> = cached_search.and_then(|cached| {
-        if cached.len() == row_count && cached.iter().any(|s| !s.is_empty()) {
-            Some(cached)
-        } else {
+            tracing::warn!("failed to load IOC cache for {:?}: {:?}", project_dir, err);
             None
         }
+    }
+    .filter(|cached| cached.key == ioc_cache_key && cached.flags.len() == row_count)
+    .map(|cached| cached.flags)
+    // After a user flag edit the rule matches stored by the IOC counts still
+    // hold, so the flags are derived from them instead of matching again.
+    .or_else(|| {
+        let match_key = ioc_match_cache_key(&ordered_iocs, &value_columns);
+        let matches = load_ioc_match_cache(&project_dir).ok().flatten()?;
+        (matches.key == match_key && matches.row_count() == row_count)
+            .then(|| ioc_flags_from_matches(&matches, &user_flag_vec))
     });
-    let mut searchable_text_built = false;
-    let mut per_column_text: HashMap<String, Vec<String>> = HashMap::new();
+    let need_rebuild_ioc = cached_ioc_flags.is_none();
+    state.perf.record_cache(Cache::IocFlags, !need_rebuild_ioc);
+    let mut ioc_flag_vec: Vec<String> =
+        cached_ioc_flags.unwrap_or_else(|| vec![String::new(); row_count]);
+    let compiled_iocs = if need_rebuild_ioc {
+        compile_iocs(&ordered_iocs)
+    } else {
+        Vec::new()
+    };
 
-    let mut search_mask: Option<Vec<bool>> = None;
-    if let Some(search_str_raw) = payload
+    // Row text is built and searched chunk by chunk on large projects, so it
+    // is never held for every row at once. Only the columns a chunk needs are
+    // read; the page's own columns are read for its rows alone.
+    let scoped_columns = payload
         .search
-        .as_ref()
-        .map(|s| s.trim().to_string())
-        .filter(|s| !s.is_empty())
-    {
-        let tokens = tokenize_search_query(&search_str_raw);
-        let mut terms: Vec<(Option<String>, String)> = Vec::new();
-        let mut needed_cols: Vec<String> = Vec::new();
-        for token in &tokens {
-            if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } = token {
-                let key = (col.clone(), text.clone());
-                if !text.is_empty() && !terms.contains(&key) {
-                    terms.push(key);
-                }
-                if let Some(column_name) = col {
-                    if !needed_cols.contains(column_name) {
-                        needed_cols.push(column_name.clone());
-                    }
-                }
-            }
-        }
-        for column in needed_cols {
-            ensure_column_text_cache(
-                &column,
-                &column_series_lower,
-                &mut per_column_text,
-                df.height(),
+        .as_deref()
+        .and_then(|search| scoped_search_columns(search, &meta.column_aliases));
+    let chunk_rows = search_chunk_rows(row_count);
+    let text_cache_valid = match load_searchable_cache(&project_dir) {
+        Ok(cache) => cache.is_some_and(|cache| {
+            cache.chunk_rows == chunk_rows
+                && cache.is_valid_for(row_count, &search_cols, &meta.hidden_columns)
+        }),
+        Err(err) => {
+            tracing::warn!(
+                "failed to load searchable cache for {:?}: {:?}",
+                project_dir,
+                err
             );
+            false
         }
-        if !terms.is_empty() {
-            let rpn = to_rpn(&tokens);
-            let search_text = ensure_searchable_text(
-                &mut searchable_text,
-                &mut searchable_text_built,
-                row_count,
-                &search_cols,
-                &column_series,
+    };
+    let needs_row_text = scoped_columns.is_some() || !compiled_iocs.is_empty();
+    if needs_row_text {
+        state
+            .perf
+            .record_cache(Cache::SearchableText, text_cache_valid);
+    }
+    if needs_row_text && !text_cache_valid {
+        if let Err(err) = clear_searchable_cache(&project_dir) {
+            tracing::warn!(
+                "failed to clear searchable cache for {:?}: {:?}",
+                project_dir,
+                err
             );
-            let mask =
-                build_search_mask_boolean(&rpn, &terms, search_text, Some(&per_column_text));
-            search_mask = Some(mask);
         }
     }
+    let mut searchable_text_built = false;
+    let mut rows_scanned = 0;
+    let mut read_elapsed = Duration::ZERO;
+    let mut search_elapsed = Duration::ZERO;
+    let mut ioc_elapsed = Duration::ZERO;
+    let mut search_mask: Option<Vec<bool>> = scoped_columns
+        .as_ref()
+        .map(|_| Vec::with_capacity(row_count));
 
-    let mut user_flag_vec: Vec<String> = vec![String::new(); df.height()];
-    for (idx, entry) in flags.iter() {
-        if *idx < df.height() {
-            user_flag_vec[*idx] = normalize_flag_value(&entry.flag);
+    let chunk_count = if needs_row_text {
+        row_count.div_ceil(chunk_rows)
+    } else {
+        0
+    };
+    for chunk in 0..chunk_count {
+        let chunk_start = chunk * chunk_rows;
+        let chunk_len = chunk_rows.min(row_count - chunk_start);
+        let read_started = Instant::now();
+        let mut searchable_text = if text_cache_valid {
+            load_searchable_chunk(&project_dir, chunk).unwrap_or_else(|err| {
+                tracing::warn!(
+                    "failed to load searchable cache for {:?}: {:?}",
+                    project_dir,
+                    err
+                );
+                None
+            })
+        } else {
+            None
         }
-    }
+        .filter(|text| text.len() == chunk_len);
+        let read_all = searchable_text.is_none() || !compiled_iocs.is_empty();
+        let chunk_columns: Vec<String> = columns
+            .iter()
+            .filter(|name| {
+                read_all
+                    || scoped_columns
+                        .as_ref()
+                        .is_some_and(|scoped| scoped.contains(&name.to_lowercase()))
+            })
+            .cloned()
+            .collect();
+        let df = read_project_row_range(&parquet_path, &chunk_columns, chunk_start, chunk_len)
+            .map_err(AppError::from)?;
+        read_elapsed += read_started.elapsed();
+        rows_scanned += df.height();
+        let column_series: HashMap<&str, &Series> =
+            df.get_columns().iter().map(|s| (s.name(), s)).collect();
+        let column_series_lower: HashMap<String, &Series> = df
+            .get_columns()
+            .iter()
+            .map(|s| (s.name().to_lowercase(), s))
+            .collect();
+        let mut chunk_text_built = false;
+        let mut per_column_text: HashMap<String, Vec<String>> = HashMap::new();
 
-    let cached_ioc_flags = match load_ioc_flag_cache(&project_dir) {
-        Ok(cache) => cache,
-        Err(err) => {
-            eprintln!(
-                "[cache] failed to load IOC cache for {:?}: {:?}",
-                project_dir, err
+        let search_started = Instant::now();
+        if let (Some(search), Some(mask)) = (payload.search.as_deref(), search_mask.as_mut()) {
+            let mut search_series_lower = column_series_lower.clone();
+            add_alias_lookups(&mut search_series_lower, &meta.column_aliases);
+            let chunk_mask = build_search_mask(
+                search,
+                payload.whole_word,
+                &SearchColumns {
+                    rows: chunk_len,
+                    columns: &search_cols,
+                    series: &column_series,
+                    series_lower: &search_series_lower,
+                },
+                &mut searchable_text,
+                &mut chunk_text_built,
+                &mut per_column_text,
             );
-            None
+            mask.extend(chunk_mask.unwrap_or_else(|| vec![true; chunk_len]));
         }
-    };
-    let mut ioc_flag_vec: Vec<String> = cached_ioc_flags
-        .filter(|cached| cached.len() == row_count)
-        .unwrap_or_else(|| vec![String::new(); row_count]);
-    let mut sorted_iocs = iocs.clone();
-    sorted_iocs.sort_by_key(|e| std::cmp::Reverse(severity_rank(&normalize_flag_value(&e.flag))));
-    let need_rebuild_ioc = ioc_flag_vec.iter().all(|s| s.is_empty());
-    if need_rebuild_ioc {
-        for ioc_entry in &sorted_iocs {
-            let query = ioc_entry.query.trim();
-            if query.is_empty() {
-                continue;
-            }
-            let tokens = tokenize_search_query(query);
-            let mut terms: Vec<(Option<String>, String)> = Vec::new();
-            let mut needed_cols: Vec<String> = Vec::new();
-            for t in &tokens {
-                if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } = t {
-                    let key = (col.clone(), text.clone());
-                    if !text.is_empty() && !terms.contains(&key) {
-                        terms.push(key);
-                    }
-                    if let Some(c) = col {
-                        if !needed_cols.contains(c) {
-                            needed_cols.push(c.clone());
-                        }
-                    }
-                }
+        search_elapsed += search_started.elapsed();
+
+        let ioc_started = Instant::now();
+        for ioc in &compiled_iocs {
+            let mut needed_cols = ioc.scoped_columns();
+            if ioc.needs_column_values() {
+                needed_cols.extend(value_columns.iter().cloned());
             }
             for c in needed_cols {
-                ensure_column_text_cache(
-                    &c,
-                    &column_series_lower,
-                    &mut per_column_text,
-                    df.height(),
-                );
+                ensure_column_text_cache(&c, &column_series_lower, &mut per_column_text, chunk_len);
             }
-            if terms.is_empty() {
-                continue;
-            }
-            let rpn = to_rpn(&tokens);
             let search_text = ensure_searchable_text(
                 &mut searchable_text,
-                &mut searchable_text_built,
-                row_count,
+                &mut chunk_text_built,
+                chunk_len,
                 &search_cols,
                 &column_series,
             );
-            let mask = build_search_mask_boolean(&rpn, &terms, search_text, Some(&per_column_text));
-            for i in 0..df.height() {
-                if !ioc_flag_vec[i].is_empty() || !user_flag_vec[i].is_empty() {
+            let mask = ioc.evaluate(search_text, &per_column_text, &value_columns);
+            for (i, row) in (chunk_start..chunk_start + chunk_len).enumerate() {
+                if !ioc_flag_vec[row].is_empty() || !user_flag_vec[row].is_empty() {
                     continue;
                 }
                 if mask.get(i).copied().unwrap_or(false) {
-                    ioc_flag_vec[i] = normalize_flag_value(&ioc_entry.flag);
+                    ioc_flag_vec[row] = normalize_flag_value(&ioc.entry.flag);
+                }
+            }
+        }
+        ioc_elapsed += ioc_started.elapsed();
+
+        if chunk_text_built {
+            if let Some(text) = &searchable_text {
+                match save_searchable_chunk(&project_dir, chunk, text) {
+                    Ok(()) => searchable_text_built = true,
+                    Err(err) => tracing::warn!(
+                        "failed to persist searchable cache for {:?}: {:?}",
+                        project_dir,
+                        err
+                    ),
                 }
             }
         }
-        if let Err(err) = save_ioc_flag_cache(&project_dir, &ioc_flag_vec) {
-            eprintln!(
-                "[cache] failed to persist IOC cache for {:?}: {:?}",
-                project_dir, err
+    }
+
+    if searchable_text_built {
+        let cache = SearchableCache {
+            row_count,
+            chunk_rows,
+            columns: search_cols.clone(),
+            hidden_columns: meta.hidden_columns.clone(),
+            text_format: SEARCH_TEXT_FORMAT,
+        };
+        if let Err(err) = save_searchable_cache(&project_dir, &cache) {
+            tracing::warn!(
+                "failed to persist searchable cache for {:?}: {:?}",
+                project_dir,
+                err
             );
         }
     }
 
-    let mut ordered_indices: Vec<usize> = (0..df.height()).collect();
-    if let Some(sort_key) = &payload.sort_key {
-        if let Ok(series) = df.column(sort_key) {
-            ordered_indices.sort_by(|a, b| {
-                let a_s = series
-                    .get(*a)
-                    .ok()
-                    .and_then(|v| anyvalue_to_search_string(&v))
-                    .map(|s| s.trim().replace(',', "").replace('\u{00A0}', ""));
-                let b_s = series
-                    .get(*b)
-                    .ok()
-                    .and_then(|v| anyvalue_to_search_string(&v))
-                    .map(|s| s.trim().replace(',', "").replace('\u{00A0}', ""));
-
-                let a_num = a_s.as_ref().and_then(|s| s.parse::<f64>().ok());
-                let b_num = b_s.as_ref().and_then(|s| s.parse::<f64>().ok());
-
-                if a_num.is_some() || b_num.is_some() {
-                    let av = a_num.unwrap_or(f64::INFINITY);
-                    let bv = b_num.unwrap_or(f64::INFINITY);
-                    let mut ord = av.partial_cmp(&bv).unwrap_or(std::cmp::Ordering::Equal);
-                    if payload.sort_direction.as_deref() == Some("desc") {
-                        ord = ord.reverse();
-                    }
-                    return ord;
-                }
+    if !compiled_iocs.is_empty() {
+        state
+            .perf
+            .record(Operation::IocEvaluation, ioc_elapsed, row_count);
+    }
 
-                let mut ord = match (&a_s, &b_s) {
-                    (Some(a_str), Some(b_str)) => a_str.to_lowercase().cmp(&b_str.to_lowercase()),
-                    (Some(_), None) => std::cmp::Ordering::Greater,
-                    (None, Some(_)) => std::cmp::Ordering::Less,
-                    (None, None) => std::cmp::Ordering::Equal,
-                };
-                if payload.sort_direction.as_deref() == Some("desc") {
-                    ord = ord.reverse();
-                }
-                ord
+    if need_rebuild_ioc {
+        let cache = IocFlagCache {
+            key: ioc_cache_key,
+            flags: ioc_flag_vec,
+        };
+        if let Err(err) = save_ioc_flag_cache(&project_dir, &cache) {
+            tracing::warn!(
+                "failed to persist IOC cache for {:?}: {:?}",
+                project_dir,
+                err
+            );
+        }
+        ioc_flag_vec = cache.flags;
+    }
+
+    let sort_started = Instant::now();
+    let mut ordered_indices: Vec<usize> = (0..row_count).collect();
+    let mut time_column = None;
+    if payload.timeline {
+        let column = meta
+            .timestamp_column
+            .clone()
+            .filter(|column| columns.contains(column))
+            .ok_or_else(|| {
+                AppError::Message(
+                    "Choose the project's time column to use the timeline view.".into(),
+                )
+            })?;
+        let df = read_project_column_subset(&parquet_path, std::slice::from_ref(&column))
+            .map_err(AppError::from)?;
+        rows_scanned += df.height();
+        let instants = df
+            .column(&column)
+            .map_err(anyhow::Error::from)
+            .and_then(timeline_instants)
+            .map_err(AppError::from)?;
+        let zone = display_zone(&meta).unwrap_or_else(ProjectTimezone::utc);
+        let from = time_bound(payload.time_from.as_deref(), &zone)?;
+        let to = time_bound(payload.time_to.as_deref(), &zone)?;
+        if from.is_some() || to.is_some() {
+            ordered_indices.retain(|&idx| {
+                instants[idx].is_some_and(|time| {
+                    from.is_none_or(|from| time >= from) && to.is_none_or(|to| time <= to)
+                })
             });
         }
+        sort_row_indices_by_time(
+            &mut ordered_indices,
+            &instants,
+            payload.sort_direction.as_deref() == Some("desc"),
+        );
+        time_column = Some(column);
+    } else if let Some(sort_key) = payload
+        .sort_key
+        .as_ref()
+        .filter(|sort_key| columns.contains(sort_key))
+    {
+        // Orderings are kept per sort key until the data is rewritten, so
+        // paging and switching between sorts do not compare every row again.
+        let descending = payload.sort_direction.as_deref() == Some("desc");
+        let stamp = data_stamp(&parquet_path);
+        let cached = stamp
+            .and_then(|stamp| state.sorts.get(&meta.id, sort_key, descending, stamp))
+            .filter(|order| order.len() == row_count);
+        state.perf.record_cache(Cache::SortOrder, cached.is_some());
+        match cached {
+            Some(order) => ordered_indices = order.to_vec(),
+            None => {
+                let df = read_project_column_subset(&parquet_path, std::slice::from_ref(sort_key))
+                    .map_err(AppError::from)?;
+                rows_scanned += df.height();
+                sort_row_indices(&df, &mut ordered_indices, sort_key, descending);
+                if let Some(stamp) = stamp {
+                    state.sorts.insert(
+                        meta.id,
+                        sort_key.clone(),
+                        descending,
+                        stamp,
+                        Arc::new(ordered_indices.clone()),
+                    );
+                }
+            }
+        }
     }
+    let sort_elapsed = sort_started.elapsed();
 
-    let mut final_flag_vec: Vec<String> = Vec::with_capacity(df.height());
-    final_flag_vec.extend((0..df.height()).map(|i| {
+    let filter_started = Instant::now();
+    let mut final_flag_vec: Vec<String> = Vec::with_capacity(row_count);
+    final_flag_vec.extend((0..row_count).map(|i| {
         if !user_flag_vec[i].is_empty() {
             user_flag_vec[i].clone()
         } else {
@@ -321,8 +666,26 @@ pub fn query_project_rows(
         }
     }));
 
-    let mut filtered_indices: Vec<usize> = Vec::with_capacity(df.height());
+    let bookmarked: Option<HashSet<usize>> = if payload.bookmarked_only {
+        let bookmarks =
+            load_bookmarks(&project_dir.join(BOOKMARKS_FILE)).map_err(AppError::from)?;
+        Some(
+            bookmarks
+                .iter()
+                .map(|bookmark| bookmark.row_index)
+                .collect(),
+        )
+    } else {
+        None
+    };
+    let mut filtered_indices: Vec<usize> = Vec::with_capacity(row_count);
     for &idx in &ordered_indices {
+        if bookmarked
+            .as_ref()
+            .is_some_and(|bookmarked| !bookmarked.contains(&idx))
+        {
+            continue;
+        }
         let ff = &final_flag_vec[idx];
         let flag_ok = if let Some(filter) = &payload.flag_filter {
             matches_flag_filter(ff, filter)
@@ -343,20 +706,21 @@ pub fn query_project_rows(
         if !final_flag_vec[idx].trim().is_empty() {
             total_flagged_after_ioc += 1;
         }
+        flag_counts.add(&final_flag_vec[idx]);
     }
     let total_filtered_rows = filtered_indices.len();
+    let filter_elapsed = filter_started.elapsed();
 
+    let materialize_started = Instant::now();
     let selected_indices: Vec<usize> = filtered_indices
         .iter()
         .skip(offset)
         .take(limit)
         .copied()
         .collect();
-    let take_idx =
-        UInt32Chunked::from_iter_values("take_idx", selected_indices.iter().map(|&idx| idx as u32));
-    let taken_df = df
-        .take(&take_idx)
-        .map_err(|err| AppError::from(AnyhowError::from(err)))?;
+    let taken_df = read_project_rows(&parquet_path, &column_names, &selected_indices)
+        .map_err(AppError::from)?;
+    rows_scanned += taken_df.height();
     let taken_series_map: HashMap<&str, &Series> = taken_df
         .get_columns()
         .iter()
@@ -367,73 +731,31 @@ pub fn query_project_rows(
         .filter_map(|idx| flags.get(idx).cloned().map(|entry| (*idx, entry)))
         .collect();
 
+    let page_iocs = compile_iocs(&ordered_iocs);
+    let display = time_display(&meta, &state.settings);
     for (position, &row_idx) in selected_indices.iter().enumerate() {
-        let record = collect_row_record_from_series(&taken_series_map, &column_names, position);
+        let record = collect_row_record_from_series(
+            &taken_series_map,
+            &column_names,
+            position,
+            display.as_ref(),
+        );
         let user_memo = page_flags
             .get(&row_idx)
             .and_then(|e| e.memo.clone())
             .unwrap_or_default();
         let mut final_memo = user_memo;
         if !iocs.is_empty() && final_flag_vec[row_idx] == ioc_flag_vec[row_idx] {
-            let mut memo_tags: Vec<String> = Vec::new();
-            for ioc_entry in &iocs {
-                let query = ioc_entry.query.trim();
-                if query.is_empty() {
-                    continue;
-                }
-                let tokens = tokenize_search_query(query);
-                let mut terms: Vec<(Option<String>, String)> = Vec::new();
-                let mut needed_cols: Vec<String> = Vec::new();
-                for t in &tokens {
-                    if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } =
-                        t
-                    {
-                        let key = (col.clone(), text.clone());
-                        if !text.is_empty() && !terms.contains(&key) {
-                            terms.push(key);
-                        }
-                        if let Some(c) = col {
-                            if !needed_cols.contains(c) {
-                                needed_cols.push(c.clone());
-                            }
-                        }
-                    }
-                }
-                if terms.is_empty() {
-                    continue;
-                }
-                let rpn = to_rpn(&tokens);
-                for c in needed_cols {
-                    ensure_column_text_cache(
-                        &c,
-                        &column_series_lower,
-                        &mut per_column_text,
-                        df.height(),
-                    );
-                }
-                let (row_search_text, single_per_col) =
-                    build_row_search_text(&column_names, &column_series, row_idx);
-                let single_row = vec![row_search_text];
-                let single_mask =
-                    build_search_mask_boolean(&rpn, &terms, &single_row, Some(&single_per_col));
-                if single_mask.get(0).copied().unwrap_or(false) {
-                    let tag = ioc_entry.tag.trim();
-                    if !tag.is_empty() {
-                        let token = format!("[{}]", tag);
-                        if !memo_tags.contains(&token) {
-                            memo_tags.push(token);
-                        }
-                    }
-                }
-            }
-            for tag in memo_tags {
-                if !final_memo.contains(&tag) {
-                    if !final_memo.is_empty() && !final_memo.ends_with(' ') {
-                        final_memo.push(' ');
-                    }
-                    final_memo.push_str(&tag);
-                }
-            }
+            let (row_search_text, single_per_col) =
+                build_row_search_text(&search_cols, &taken_series_map, position);
+            let resolution = resolve_ioc_matches(
+                page_iocs
+                    .iter()
+                    .filter(|ioc| ioc.matches_row(&row_search_text, &single_per_col))
+                    .map(|ioc| ioc.entry),
+                meta.ioc_policy,
+            );
+            append_memo_tags(&mut final_memo, &resolution.tags);
         }
         rows.push(ProjectRow {
             row_index: row_idx,
@@ -447,22 +769,456 @@ pub fn query_project_rows(
         });
     }
 
-    if searchable_text_built {
-        if let Some(ref built) = searchable_text {
-            if let Err(err) = save_searchable_cache(&project_dir, built) {
-                eprintln!(
-                    "[cache] failed to persist searchable cache for {:?}: {:?}",
-                    project_dir, err
-                );
-            }
-        }
-    }
+    let materialize_elapsed = materialize_started.elapsed();
 
+    let elapsed = started.elapsed();
+    state.perf.record(Operation::Query, elapsed, rows_scanned);
+    let timings = payload.debug.then(|| QueryTimings {
+        read_ms: millis(read_elapsed),
+        search_ms: millis(search_elapsed),
+        ioc_ms: millis(ioc_elapsed),
+        sort_ms: millis(sort_elapsed),
+        filter_ms: millis(filter_elapsed),
+        materialize_ms: millis(materialize_elapsed),
+        total_ms: millis(elapsed),
+    });
     Ok(QueryRowsResponse {
         rows,
         total_flagged: total_flagged_after_ioc,
-        total_rows: total_rows_before_flag_filter,
+        flag_counts,
+        total_rows: row_count,
         total_filtered_rows,
         offset,
+        time_column,
+        timings,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExplainSearchPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub query: String,
+    /// Explains an IOC rule of this kind instead of a search.
+    #[serde(default)]
+    pub kind: Option<IocKind>,
+    /// Columns unscoped terms search: the view's columns for a search, the
+    /// rule's column restriction for an IOC rule. Every searchable column
+    /// when empty.
+    #[serde(default)]
+    pub columns: Vec<String>,
+    #[serde(default, rename = "wholeWord")]
+    pub whole_word: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TermScope {
+    /// The text of all searched columns of a row joined together.
+    RowText,
+    /// One column's value.
+    Column,
+    /// Each list element of one column on its own.
+    ListElements,
+    /// Each column value on its own, as regex and IP rules are matched.
+    ColumnValues,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExplainedTerm {
+    /// The term as it is matched: lowercased and refanged.
+    pub text: String,
+    pub quoted: bool,
+    /// Column prefix as written, lowercased.
+    pub prefix: Option<String>,
+    pub scope: TermScope,
+    /// Project columns the term is matched against.
+    pub columns: Vec<String>,
+    /// Served from the stored row text instead of the column data.
+    pub uses_cache: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SearchExplanation {
+    pub kind: IocKind,
+    /// The query with implicit ANDs and grouping spelled out.
+    pub expression: String,
+    /// Tokens after parsing, implicit ANDs included.
+    pub tokens: Vec<String>,
+    /// Evaluation order in reverse Polish notation.
+    pub rpn: Vec<String>,
+    pub terms: Vec<ExplainedTerm>,
+    pub whole_word: bool,
+    /// Whether the stored row text matches the searched columns.
+    pub searchable_text_cached: bool,
+    /// Parts of the query that likely do not do what was meant.
+    pub warnings: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum WarningKind {
+    /// The query names a column the project does not have.
+    UnknownColumn,
+    /// The query is malformed or likely not read the way it was meant.
+    Syntax,
+}
+
+pub(crate) struct QueryWarning {
+    pub kind: WarningKind,
+    pub message: String,
+}
+
+impl QueryWarning {
+    fn unknown_column(message: String) -> Self {
+        Self {
+            kind: WarningKind::UnknownColumn,
+            message,
+        }
+    }
+
+    fn syntax(message: String) -> Self {
+        Self {
+            kind: WarningKind::Syntax,
+            message,
+        }
+    }
+}
+
+/// Shows how a search or IOC rule is read: its tokens, evaluation order and
+/// grouping, the columns each term is matched against, and which terms the
+/// stored row text serves.
+#[tauri::command]
+pub fn explain_search_query(
+    state: State<AppState>,
+    payload: ExplainSearchPayload,
+) -> Result<SearchExplanation, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let row_count = read_project_row_count(&parquet_path).map_err(AppError::from)?;
+    let text_cache = load_searchable_cache(&project_dir).ok().flatten();
+
+    let (mut explanation, warnings) =
+        explain_query(&meta, &columns, row_count, text_cache.as_ref(), &payload);
+    explanation.warnings = warnings
+        .into_iter()
+        .map(|warning| warning.message)
+        .collect();
+    Ok(explanation)
+}
+
+/// The explanation of one search or rule together with its warnings, which
+/// the caller files into `SearchExplanation::warnings` or a report.
+pub(crate) fn explain_query(
+    meta: &ProjectMeta,
+    columns: &[String],
+    row_count: usize,
+    text_cache: Option<&SearchableCache>,
+    payload: &ExplainSearchPayload,
+) -> (SearchExplanation, Vec<QueryWarning>) {
+    let is_ioc = payload.kind.is_some();
+    let kind = payload.kind.unwrap_or_default();
+    let query = payload.query.trim();
+    let mut warnings: Vec<QueryWarning> = Vec::new();
+    let find_column = |name: &str| {
+        columns
+            .iter()
+            .find(|column| column.to_lowercase() == name.to_lowercase())
+            .cloned()
+    };
+    let restriction: Vec<String> = normalize_ioc_columns(&payload.columns)
+        .into_iter()
+        .filter_map(|column| {
+            let found = find_column(&column);
+            if found.is_none() {
+                warnings.push(QueryWarning::unknown_column(format!(
+                    "Column '{}' does not exist.",
+                    column
+                )));
+            }
+            found
+        })
+        .collect();
+    // Mirrors `query_project_rows`: IOC rules read the row text of every
+    // searchable column, a search that of the view's columns.
+    let search_cols: Vec<String> = if is_ioc || restriction.is_empty() {
+        columns.to_vec()
+    } else {
+        restriction.clone()
+    }
+    .into_iter()
+    .filter(|column| !meta.unsearched_columns().contains(column))
+    .collect();
+    let searchable_text_cached = text_cache.is_some_and(|cache| {
+        cache.chunk_rows == search_chunk_rows(row_count)
+            && cache.is_valid_for(row_count, &search_cols, &meta.hidden_columns)
+    });
+    let unscoped_columns = if is_ioc && !restriction.is_empty() {
+        restriction.clone()
+    } else {
+        search_cols.clone()
+    };
+
+    let mut explanation = SearchExplanation {
+        kind,
+        expression: String::new(),
+        tokens: Vec::new(),
+        rpn: Vec::new(),
+        terms: Vec::new(),
+        whole_word: payload.whole_word,
+        searchable_text_cached,
+        warnings: Vec::new(),
+    };
+    if kind != IocKind::Query && payload.whole_word {
+        warnings.push(QueryWarning::syntax(
+            "Whole-word matching applies to query rules only.".to_string(),
+        ));
+    }
+    match kind {
+        IocKind::Regex => {
+            if let Err(err) = RegexBuilder::new(query).case_insensitive(true).build() {
+                warnings.push(QueryWarning::syntax(format!(
+                    "Invalid regular expression: {}",
+                    err
+                )));
+            }
+            explanation.expression = format!("/{}/i", query);
+            explanation.terms.push(ExplainedTerm {
+                text: query.to_string(),
+                quoted: false,
+                prefix: None,
+                scope: TermScope::ColumnValues,
+                columns: unscoped_columns,
+                uses_cache: false,
+            });
+        }
+        IocKind::Ip => match parse_ip_rules(&refang(query)) {
+            Ok(rules) => {
+                for rule in rules {
+                    let column = rule.column.as_deref().map(&find_column);
+                    if let (Some(prefix), Some(None)) = (&rule.column, &column) {
+                        warnings.push(QueryWarning::unknown_column(format!(
+                            "Column '{}' does not exist, so `{}` never matches.",
+                            prefix,
+                            rule.describe_range()
+                        )));
+                    }
+                    explanation.terms.push(ExplainedTerm {
+                        text: rule.describe_range(),
+                        quoted: false,
+                        prefix: rule.column.clone(),
+                        scope: if column.is_some() {
+                            TermScope::Column
+                        } else {
+                            TermScope::ColumnValues
+                        },
+                        columns: match column {
+                            Some(found) => found.into_iter().collect(),
+                            None => unscoped_columns.clone(),
+                        },
+                        uses_cache: false,
+                    });
+                }
+                explanation.expression = explanation
+                    .terms
+                    .iter()
+                    .map(|term| match &term.prefix {
+                        Some(prefix) => format!("{}:{}", prefix, term.text),
+                        None => term.text.clone(),
+                    })
+                    .collect::<Vec<_>>()
+                    .join(" OR ");
+            }
+            Err(err) => warnings.push(QueryWarning::syntax(format!("Invalid IP rule: {}", err))),
+        },
+        IocKind::Query => {
+            let tokens = tokenize_search_query(query);
+            let rpn = to_rpn(&tokens);
+            explanation.tokens = tokens.iter().map(describe_token).collect();
+            explanation.rpn = rpn.iter().map(describe_token).collect();
+            explanation.expression = rpn_to_expression(&rpn);
+            if !operators_have_operands(&rpn) {
+                warnings.push(QueryWarning::syntax(
+                    "An operator has no term to apply to; check for a leading, trailing or \
+                     doubled `|` or `-`."
+                        .to_string(),
+                ));
+            }
+            for token in &tokens {
+                let (SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text }) =
+                    token
+                else {
+                    continue;
+                };
+                let quoted = matches!(token, SearchToken::QuotedTerm { .. });
+                if !quoted && matches!(text.as_str(), "and" | "or" | "not") {
+                    warnings.push(QueryWarning::syntax(format!(
+                        "`{}` is searched as a word; write `|` for OR, a space for AND and a \
+                         leading `-` for NOT.",
+                        text
+                    )));
+                }
+                if explanation
+                    .terms
+                    .iter()
+                    .any(|term| term.text == *text && term.prefix == *col && term.quoted == quoted)
+                {
+                    continue;
+                }
+                let (scope, term_columns) = match col {
+                    None => (TermScope::RowText, unscoped_columns.clone()),
+                    Some(prefix) => {
+                        let base = list_element_column(prefix).unwrap_or(prefix);
+                        // Aliases resolve in searches; IOC rules name columns.
+                        let column = find_column(base).or_else(|| {
+                            (!is_ioc)
+                                .then(|| aliased_column(base, &meta.column_aliases).cloned())
+                                .flatten()
+                        });
+                        if column.is_none() {
+                            warnings.push(QueryWarning::unknown_column(format!(
+                                "Column '{}' does not exist, so `{}` never matches.",
+                                prefix,
+                                describe_token(token)
+                            )));
+                        }
+                        let scope = if list_element_column(prefix).is_some() {
+                            TermScope::ListElements
+                        } else {
+                            TermScope::Column
+                        };
+                        (scope, column.into_iter().collect())
+                    }
+                };
+                explanation.terms.push(ExplainedTerm {
+                    text: text.clone(),
+                    quoted,
+                    prefix: col.clone(),
+                    uses_cache: scope == TermScope::RowText
+                        && searchable_text_cached
+                        && (!is_ioc || restriction.is_empty()),
+                    scope,
+                    columns: term_columns,
+                });
+            }
+            if explanation.terms.iter().all(|term| term.text.is_empty()) {
+                warnings.push(QueryWarning::syntax(if is_ioc {
+                    "The rule has no terms and never matches.".to_string()
+                } else {
+                    "The query has no terms and matches every row.".to_string()
+                }));
+            }
+        }
+    }
+    (explanation, warnings)
+}
+
+/// Whether every operator of an RPN stream finds its operands; evaluation
+/// reads a missing operand as no match.
+fn operators_have_operands(rpn: &[SearchToken]) -> bool {
+    let mut depth = 0usize;
+    for token in rpn {
+        let needed = match token {
+            SearchToken::Term { .. } | SearchToken::QuotedTerm { .. } => 0,
+            SearchToken::Not => 1,
+            SearchToken::And | SearchToken::Or => 2,
+        };
+        if depth < needed {
+            return false;
+        }
+        depth = depth - needed + 1;
+    }
+    depth <= 1
+}
+
+#[derive(Debug, Deserialize)]
+pub struct CompareRowsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    #[serde(rename = "rowIdA")]
+    pub row_id_a: usize,
+    #[serde(rename = "rowIdB")]
+    pub row_id_b: usize,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ColumnComparison {
+    pub column: String,
+    pub equal: bool,
+    pub value_a: serde_json::Value,
+    pub value_b: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct CompareRowsResponse {
+    pub row_a: usize,
+    pub row_b: usize,
+    pub different_columns: usize,
+    pub columns: Vec<ColumnComparison>,
+}
+
+/// Compares two rows column by column for a side-by-side diff view.
+#[tauri::command]
+pub fn compare_rows(
+    state: State<AppState>,
+    payload: CompareRowsPayload,
+) -> Result<CompareRowsResponse, AppError> {
+    let meta = state
+        .projects
+        .find(&payload.project_id)
+        .ok_or_else(|| AppError::ProjectNotFound(payload.project_id))?;
+    ensure_unsealed(&state, &meta.id)?;
+    let _lock = state.locks.read(&meta.id)?;
+    let project_dir = state.projects.project_dir(&meta.id);
+    let parquet_path = project_dir.join("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+
+    let df = read_project_dataframe(&parquet_path).map_err(AppError::from)?;
+    if payload.row_id_a >= df.height() || payload.row_id_b >= df.height() {
+        return Err(AppError::Message(
+            "Row is out of range for this project.".into(),
+        ));
+    }
+    let column_names: Vec<String> = df
+        .get_column_names()
+        .into_iter()
+        .filter(|name| name != &"__rowid")
+        .map(|name| name.to_string())
+        .collect();
+    let display = time_display(&meta, &state.settings);
+    let mut record_a = collect_row_record(&df, &column_names, payload.row_id_a, display.as_ref());
+    let mut record_b = collect_row_record(&df, &column_names, payload.row_id_b, display.as_ref());
+
+    let columns: Vec<ColumnComparison> = column_names
+        .into_iter()
+        .map(|column| {
+            let value_a = record_a.remove(&column).unwrap_or(serde_json::Value::Null);
+            let value_b = record_b.remove(&column).unwrap_or(serde_json::Value::Null);
+            ColumnComparison {
+                equal: value_a == value_b,
+                column,
+                value_a,
+                value_b,
+            }
+        })
+        .collect();
+    let different_columns = columns.iter().filter(|column| !column.equal).count();
+
+    Ok(CompareRowsResponse {
+        row_a: payload.row_id_a,
+        row_b: payload.row_id_b,
+        different_columns,
+        columns,
     })
 }
@@ -0,0 +1,55 @@
+use chrono::Utc;
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{error::AppError, models::SessionState, state::AppState};
+
+#[derive(Debug, Deserialize)]
+pub struct SaveSessionPayload {
+    #[serde(rename = "projectId", default)]
+    pub project_id: Option<Uuid>,
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default, rename = "flagFilter")]
+    pub flag_filter: Option<String>,
+    #[serde(rename = "sortKey", default)]
+    pub sort_key: Option<String>,
+    #[serde(rename = "sortDirection", default)]
+    pub sort_direction: Option<String>,
+    #[serde(default)]
+    pub offset: Option<usize>,
+}
+
+/// Records the open project and active query context for crash recovery.
+#[tauri::command]
+pub fn save_session_state(
+    state: State<AppState>,
+    payload: SaveSessionPayload,
+) -> Result<(), AppError> {
+    let session = SessionState {
+        project_id: payload.project_id,
+        search: payload.search,
+        flag_filter: payload.flag_filter,
+        sort_key: payload.sort_key,
+        sort_direction: payload.sort_direction,
+        offset: payload.offset.unwrap_or(0),
+        updated_at: Utc::now(),
+    };
+    state.session.save(session).map_err(AppError::from)?;
+    Ok(())
+}
+
+/// Returns the session from a run that ended unexpectedly, if one can still be restored.
+#[tauri::command]
+pub fn get_session_restore(state: State<AppState>) -> Result<Option<SessionState>, AppError> {
+    let Some(session) = state.session.take_recovered() else {
+        return Ok(None);
+    };
+    let project_exists = session
+        .project_id
+        .as_ref()
+        .map(|id| state.projects.find(id).is_some())
+        .unwrap_or(false);
+    Ok(project_exists.then_some(session))
+}
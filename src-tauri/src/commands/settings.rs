@@ -0,0 +1,199 @@
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use tauri::State;
+
+use crate::{
+    error::AppError,
+    settings::{check_date_format, CsvEncoding, PerformanceSettings},
+    state::AppState,
+};
+
+use super::DEFAULT_PAGE_SIZE;
+
+#[derive(Debug, Deserialize)]
+pub struct PerformanceSettingsPayload {
+    #[serde(rename = "maxThreads", default)]
+    pub max_threads: Option<usize>,
+    #[serde(rename = "importThreads", default)]
+    pub import_threads: Option<usize>,
+    #[serde(rename = "importChunkSize", default)]
+    pub import_chunk_size: Option<usize>,
+    #[serde(rename = "exactColumnWidths", default)]
+    pub exact_column_widths: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct PerformanceSettingsResponse {
+    pub settings: PerformanceSettings,
+    /// The Polars thread pool is sized once per process, so a change needs a restart.
+    pub restart_required: bool,
+}
+
+/// Returns the thread and chunk-size limits used by import and query paths.
+#[tauri::command]
+pub fn get_performance_settings(state: State<AppState>) -> Result<PerformanceSettings, AppError> {
+    Ok(state.settings.performance())
+}
+
+/// Persists thread and chunk-size limits; zero values fall back to defaults.
+#[tauri::command]
+pub fn set_performance_settings(
+    state: State<AppState>,
+    payload: PerformanceSettingsPayload,
+) -> Result<PerformanceSettingsResponse, AppError> {
+    let previous = state.settings.performance();
+    let settings = PerformanceSettings {
+        max_threads: payload.max_threads.filter(|n| *n > 0),
+        import_threads: payload.import_threads.filter(|n| *n > 0),
+        import_chunk_size: payload.import_chunk_size.filter(|n| *n > 0),
+        exact_column_widths: payload.exact_column_widths,
+    };
+    state
+        .settings
+        .update_performance(settings.clone())
+        .map_err(AppError::from)?;
+    Ok(PerformanceSettingsResponse {
+        restart_required: previous.max_threads != settings.max_threads,
+        settings,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DataRootPayload {
+    /// New data root; `None` moves the projects back to the app data directory.
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DataRootResponse {
+    /// Directory the projects are currently read from.
+    pub path: String,
+    pub default_path: String,
+    /// Configured data root, which differs from `path` while its volume is
+    /// not mounted.
+    pub configured_path: Option<String>,
+}
+
+fn data_root_response(state: &AppState) -> DataRootResponse {
+    DataRootResponse {
+        path: state.projects.root_dir().to_string_lossy().into_owned(),
+        default_path: state.app_dir.to_string_lossy().into_owned(),
+        configured_path: state
+            .settings
+            .data_root()
+            .map(|path| path.to_string_lossy().into_owned()),
+    }
+}
+
+/// Returns where project data is stored.
+#[tauri::command]
+pub fn get_data_root(state: State<AppState>) -> Result<DataRootResponse, AppError> {
+    Ok(data_root_response(&state))
+}
+
+/// Moves all projects to a new data root, e.g. an encrypted evidence volume,
+/// and remembers it for later sessions.
+#[tauri::command]
+pub fn set_data_root(
+    state: State<AppState>,
+    payload: DataRootPayload,
+) -> Result<DataRootResponse, AppError> {
+    move_data_root(&state, payload.path)?;
+    Ok(data_root_response(&state))
+}
+
+fn data_root_target(state: &AppState, path: Option<String>) -> Result<PathBuf, AppError> {
+    Ok(match path.filter(|path| !path.trim().is_empty()) {
+        Some(path) => {
+            let path = PathBuf::from(path);
+            if !path.is_absolute() {
+                return Err(AppError::Message("Choose an absolute folder path.".into()));
+            }
+            path
+        }
+        None => state.app_dir.clone(),
+    })
+}
+
+fn move_data_root(state: &AppState, path: Option<String>) -> Result<(), AppError> {
+    let target = data_root_target(state, path)?;
+    let configured = (target != state.app_dir).then(|| target.clone());
+    state
+        .projects
+        .relocate(&target, || state.settings.update_data_root(configured))
+        .map_err(|err| AppError::Message(format!("Failed to move the projects: {:#}", err)))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SettingsPayload {
+    /// Rows per page of the grid; 250 when omitted.
+    #[serde(rename = "pageSize", default)]
+    pub page_size: Option<usize>,
+    /// chrono format timestamps are shown in; the display time zone's
+    /// `%Y-%m-%d %H:%M:%S%.f %:z`, or the stored UTC text, when omitted.
+    #[serde(rename = "dateFormat", default)]
+    pub date_format: Option<String>,
+    #[serde(rename = "exportEncoding", default)]
+    pub export_encoding: CsvEncoding,
+    /// Data root; the projects move when it changes, see `set_data_root`.
+    #[serde(rename = "dataRoot", default)]
+    pub data_root: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SettingsResponse {
+    pub page_size: usize,
+    pub date_format: Option<String>,
+    pub export_encoding: CsvEncoding,
+    pub data_root: DataRootResponse,
+}
+
+fn settings_response(state: &AppState) -> SettingsResponse {
+    let settings = state.settings.settings();
+    SettingsResponse {
+        page_size: state.settings.page_size().unwrap_or(DEFAULT_PAGE_SIZE),
+        date_format: settings.date_format,
+        export_encoding: settings.export_encoding,
+        data_root: data_root_response(state),
+    }
+}
+
+/// Returns the application-wide defaults: page size, date format, export
+/// encoding and data root.
+#[tauri::command]
+pub fn get_settings(state: State<AppState>) -> Result<SettingsResponse, AppError> {
+    Ok(settings_response(&state))
+}
+
+/// Replaces the application-wide defaults. Omitted values go back to the
+/// built-in defaults; a changed data root moves the projects first.
+#[tauri::command]
+pub fn set_settings(
+    state: State<AppState>,
+    payload: SettingsPayload,
+) -> Result<SettingsResponse, AppError> {
+    if payload.page_size == Some(0) {
+        return Err(AppError::Message(
+            "The page size must be at least one row.".into(),
+        ));
+    }
+    let date_format = payload
+        .date_format
+        .filter(|format| !format.trim().is_empty());
+    if let Some(format) = &date_format {
+        check_date_format(format)
+            .map_err(|err| AppError::Message(format!("Invalid date format: {}", err)))?;
+    }
+    let target = data_root_target(&state, payload.data_root.clone())?;
+    let configured = (target != state.app_dir).then_some(target);
+    if configured != state.settings.data_root() {
+        move_data_root(&state, payload.data_root)?;
+    }
+    state
+        .settings
+        .update_defaults(payload.page_size, date_format, payload.export_encoding)
+        .map_err(AppError::from)?;
+    Ok(settings_response(&state))
+}
@@ -0,0 +1,4704 @@
+//! End-to-end tests driving the command layer over a headless `AppState`.
+
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+use tauri::test::{mock_builder, mock_context, noop_assets, MockRuntime};
+use tauri::{App, Manager, State};
+use uuid::Uuid;
+
+use crate::backups::snapshot_project;
+use crate::models::SourceIntegrityStatus;
+use crate::state::AppState;
+use crate::storage::load_searchable_cache;
+
+use super::*;
+
+const FIXTURE_EVENTS: &str = "events.csv";
+const FIXTURE_ROWS: usize = 6;
+
+fn fixture_path(name: &str) -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+        .join(name)
+}
+
+/// Deserializes a payload from the JSON the frontend would send.
+fn payload<T: DeserializeOwned>(value: Value) -> T {
+    serde_json::from_value(value).expect("payload must deserialize")
+}
+
+struct TestApp {
+    app: App<MockRuntime>,
+    root: PathBuf,
+}
+
+impl TestApp {
+    fn new() -> Self {
+        let root = std::env::temp_dir().join(format!("trivium-test-{}", Uuid::new_v4()));
+        let state = AppState::from_root(root.clone()).expect("headless state");
+        let app = mock_builder()
+            .manage(state)
+            .build(mock_context(noop_assets()))
+            .expect("mock app");
+        Self { app, root }
+    }
+
+    fn state(&self) -> State<'_, AppState> {
+        self.app.state::<AppState>()
+    }
+
+    fn create_fixture_project(&self) -> Uuid {
+        let path = fixture_path(FIXTURE_EVENTS);
+        let summary = create_project(
+            self.state(),
+            payload(json!({ "path": path, "description": "fixture" })),
+        )
+        .expect("project is created")
+        .project;
+        summary.meta.id
+    }
+
+    fn query(&self, project_id: Uuid, extra: Value) -> rows::QueryRowsResponse {
+        let mut request = json!({ "projectId": project_id, "limit": 100 });
+        if let (Some(target), Value::Object(fields)) = (request.as_object_mut(), extra) {
+            target.extend(fields);
+        }
+        query_project_rows(self.state(), payload(request)).expect("query succeeds")
+    }
+}
+
+impl Drop for TestApp {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+fn cell(row: &crate::models::ProjectRow, column: &str) -> String {
+    row.data
+        .get(column)
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[test]
+fn create_project_imports_fixture_rows() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let projects = list_projects(app.state(), None).expect("projects are listed");
+    assert_eq!(projects.len(), 1);
+    assert_eq!(projects[0].meta.total_records, FIXTURE_ROWS);
+
+    let loaded = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project loads");
+    assert!(loaded.columns.iter().any(|column| column == "command_line"));
+    assert!(!loaded.columns.iter().any(|column| column == "__rowid"));
+}
+
+#[test]
+fn query_applies_search_syntax() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let all = app.query(project_id, json!({}));
+    assert_eq!(all.total_filtered_rows, FIXTURE_ROWS);
+
+    let hits = app.query(project_id, json!({ "search": "mimikatz" }));
+    assert_eq!(hits.total_filtered_rows, 1);
+    assert_eq!(cell(&hits.rows[0], "user"), "bob");
+
+    let scoped = app.query(project_id, json!({ "search": "host:WS01|WS03" }));
+    assert_eq!(scoped.total_filtered_rows, 3);
+
+    let negated = app.query(project_id, json!({ "search": "host:WS01 -explorer" }));
+    assert_eq!(negated.total_filtered_rows, 1);
+    assert_eq!(cell(&negated.rows[0], "process"), "powershell.exe");
+}
+
+#[test]
+fn explain_shows_how_a_query_is_read() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let explained = explain_search_query(
+        app.state(),
+        payload(json!({ "projectId": project_id, "query": "host:WS01|WS02 -explorer" })),
+    )
+    .expect("query is explained");
+    assert_eq!(
+        explained.expression,
+        r#"host:"ws01" OR (host:"ws02" AND NOT "explorer")"#
+    );
+    assert_eq!(explained.rpn.len(), 6);
+    assert_eq!(explained.terms.len(), 3);
+    assert_eq!(explained.terms[0].columns, vec!["host".to_string()]);
+    assert_eq!(explained.terms[2].scope, rows::TermScope::RowText);
+    assert_eq!(explained.terms[2].columns.len(), 6);
+    assert!(explained.warnings.is_empty());
+
+    let suspicious = explain_search_query(
+        app.state(),
+        payload(json!({ "projectId": project_id, "query": "usr:bob OR alice |", "kind": "query" })),
+    )
+    .expect("rule is explained");
+    assert_eq!(suspicious.warnings.len(), 3);
+    assert!(suspicious.terms[0].columns.is_empty());
+
+    let ip = explain_search_query(
+        app.state(),
+        payload(json!({ "projectId": project_id, "query": "src_ip:10.0.0.0/24", "kind": "ip" })),
+    )
+    .expect("IP rule is explained");
+    assert_eq!(ip.expression, "src_ip:10.0.0.0-10.0.0.255");
+    assert_eq!(ip.terms[0].columns, vec!["src_ip".to_string()]);
+}
+
+#[test]
+fn escaped_characters_and_paths_are_searched_literally() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let path = app.query(project_id, json!({ "search": r"C:\data" }));
+    assert_eq!(path.total_filtered_rows, 1);
+    assert_eq!(cell(&path.rows[0], "process"), "robocopy.exe");
+    let url = app.query(project_id, json!({ "search": "https://example.com" }));
+    assert_eq!(url.total_filtered_rows, 1);
+
+    let negated = app.query(project_id, json!({ "search": "-enc" }));
+    assert_eq!(negated.total_filtered_rows, FIXTURE_ROWS - 1);
+    let literal = app.query(project_id, json!({ "search": r"\-enc" }));
+    assert_eq!(literal.total_filtered_rows, 1);
+    assert_eq!(cell(&literal.rows[0], "process"), "powershell.exe");
+
+    let colons = app.query(project_id, json!({ "search": r"sekurlsa\:\:logon" }));
+    assert_eq!(colons.total_filtered_rows, 1);
+}
+
+#[test]
+fn whole_word_search_and_iocs_skip_partial_matches() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let partial = app.query(project_id, json!({ "search": "logon" }));
+    assert_eq!(partial.total_filtered_rows, 1);
+    let whole = app.query(project_id, json!({ "search": "logon", "wholeWord": true }));
+    assert_eq!(whole.total_filtered_rows, 0);
+    let scoped = app.query(
+        project_id,
+        json!({ "search": "command_line:sekurlsa", "wholeWord": true }),
+    );
+    assert_eq!(scoped.total_filtered_rows, 1);
+    let dotted = app.query(project_id, json!({ "search": ".exe", "wholeWord": true }));
+    assert_eq!(dotted.total_filtered_rows, FIXTURE_ROWS);
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Power", "query": "power", "whole_word": true },
+                { "flag": "suspicious", "tag": "Recon", "query": "whoami", "whole_word": true }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 0);
+    let suspicious = app.query(project_id, json!({ "flagFilter": "suspicious" }));
+    assert_eq!(suspicious.total_filtered_rows, 1);
+    assert_eq!(cell(&suspicious.rows[0], "process"), "cmd.exe");
+}
+
+#[test]
+fn flags_are_persisted_and_filterable() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let row = update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "credential dumping"
+        })),
+    )
+    .expect("flag is stored");
+    assert_eq!(row.flag, "critical");
+    assert_eq!(row.data["process"], json!("mimikatz.exe"));
+
+    let flagged = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(flagged.total_filtered_rows, 1);
+    assert_eq!(flagged.rows[0].row_index, 2);
+    assert_eq!(flagged.rows[0].memo.as_deref(), Some("credential dumping"));
+
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(meta.flagged_records, 1);
+
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 2, "flag": "", "memo": null })),
+    )
+    .expect("flag is cleared");
+    let cleared = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(cleared.total_filtered_rows, 0);
+
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 99, "flag": "safe", "memo": null })),
+    )
+    .expect_err("rows outside the data cannot be flagged");
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(meta.flagged_records, 0);
+}
+
+#[test]
+fn memo_term_frequencies_count_terms_across_memos() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let memos = [
+        (1, "Encoded PowerShell launched by alice; powershell -enc"),
+        (
+            2,
+            "Credential dumping with mimikatz. PowerShell was not involved.",
+        ),
+        (3, "the 2 of it"),
+    ];
+    for (row_index, memo) in memos {
+        update_flag(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "row_index": row_index,
+                "flag": "suspicious",
+                "memo": memo
+            })),
+        )
+        .expect("memo is stored");
+    }
+    let frequencies = |limit: Value| {
+        get_memo_term_frequencies(
+            app.state(),
+            payload(json!({ "projectId": project_id, "limit": limit })),
+        )
+        .expect("terms are counted")
+    };
+
+    let all = frequencies(Value::Null);
+    assert_eq!(all[0].term, "powershell");
+    assert_eq!((all[0].count, all[0].memos), (3, 2));
+    let terms: Vec<&str> = all
+        .iter()
+        .map(|frequency| frequency.term.as_str())
+        .collect();
+    assert!(terms.contains(&"enc"), "dashes are trimmed");
+    assert!(terms.contains(&"mimikatz"));
+    for dropped in ["the", "of", "it", "by", "2"] {
+        assert!(!terms.contains(&dropped), "{dropped} is not counted");
+    }
+
+    let top: Vec<String> = frequencies(json!(2))
+        .into_iter()
+        .map(|frequency| frequency.term)
+        .collect();
+    assert_eq!(top, ["powershell", "alice"], "ties are ordered by term");
+
+    let err =
+        get_memo_term_frequencies(app.state(), payload(json!({ "projectId": Uuid::new_v4() })))
+            .expect_err("unknown projects are rejected");
+    assert_eq!(err.code(), "project_not_found");
+}
+
+#[test]
+fn later_launches_are_forwarded_to_the_running_instance() {
+    use crate::instance::{acquire, release, serve, InstanceRole};
+    use crate::models::{LaunchRequest, RowLink};
+
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let InstanceRole::Primary(listener) = acquire(&app.root, &[]).expect("first launch") else {
+        panic!("no instance is running yet");
+    };
+    serve(listener, app.app.handle());
+
+    let csv = fixture_path(FIXTURE_EVENTS).to_string_lossy().into_owned();
+    let args = [
+        crate::links::format_row_permalink(&project_id, 3),
+        csv.clone(),
+        "--unknown-flag".to_string(),
+    ];
+    let role = acquire(&app.root, &args).expect("second launch");
+    assert!(matches!(role, InstanceRole::Forwarded));
+    assert_eq!(
+        take_launch_requests(app.state()).expect("requests are drained"),
+        [
+            LaunchRequest::RowLink(RowLink {
+                project_id,
+                row_id: 3
+            }),
+            LaunchRequest::ImportCsv { path: csv }
+        ]
+    );
+    assert!(take_launch_requests(app.state())
+        .expect("requests are drained")
+        .is_empty());
+
+    release(&app.root);
+    let role = acquire(&app.root, &[]).expect("launch after exit");
+    assert!(
+        matches!(role, InstanceRole::Primary(_)),
+        "a released instance is not contacted"
+    );
+}
+
+#[test]
+fn ip_rules_match_ipv6_addresses_and_blocks() {
+    let app = TestApp::new();
+    let source = app.root.join("ipv6.csv");
+    fs::write(
+        &source,
+        "host,src_ip,dst_ip\nWS01,2001:db8::10,10.0.0.1\nWS02,2001:db9::1,2001:db8::20\nWS03,fe80::1,10.0.0.2\n",
+    )
+    .expect("source is written");
+    let project_id = create_project(app.state(), payload(json!({ "path": source })))
+        .expect("project is created")
+        .project
+        .meta
+        .id;
+    let matched = |query: &str| {
+        save_iocs(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "entries": [{ "flag": "suspicious", "tag": "IPv6", "query": query, "kind": "ip" }]
+            })),
+        )
+        .expect("IOC rules are saved");
+        app.query(project_id, json!({ "flagFilter": "suspicious" }))
+            .rows
+            .iter()
+            .map(|row| row.row_index)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(matched("fe80::1"), [2]);
+    assert_eq!(matched("2001:db8::/32"), [0, 1], "not a column named 2001");
+    assert_eq!(matched("dst_ip:2001:db8::/32"), [1]);
+    assert_eq!(matched("src_ip:2001:db8::10"), [0]);
+}
+
+#[test]
+fn query_counts_filtered_rows_per_flag() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "Internal", "query": "10.0.0.0/24", "kind": "ip" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    update_flag(
+        app.state(),
+        payload(
+            json!({ "projectId": project_id, "row_index": 2, "flag": "critical", "memo": null }),
+        ),
+    )
+    .expect("flag is stored");
+
+    let page = app.query(project_id, json!({ "limit": 1 }));
+    assert_eq!(page.rows.len(), 1);
+    assert_eq!(page.total_flagged, 5);
+    assert_eq!(page.flag_counts.critical, 1);
+    assert_eq!(page.flag_counts.suspicious, 4);
+    assert_eq!(page.flag_counts.safe, 0);
+    assert_eq!(page.flag_counts.unflagged, 1);
+
+    let filtered = app.query(project_id, json!({ "search": "host:WS02" }));
+    assert_eq!(filtered.flag_counts.critical, 1);
+    assert_eq!(filtered.flag_counts.suspicious, 1);
+    assert_eq!(filtered.flag_counts.unflagged, 0);
+}
+
+#[test]
+fn ioc_rules_tag_matching_rows() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" },
+                { "flag": "suspicious", "tag": "Internal", "query": "10.0.0.0/24", "kind": "ip" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(meta.ioc_applied_records, 5);
+
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+    let memo = critical.rows[0].memo.clone().unwrap_or_default();
+    assert!(memo.contains("[Mimikatz]"));
+
+    let invalid = save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Broken", "query": "(", "kind": "regex" }]
+        })),
+    );
+    assert!(invalid.is_err());
+}
+
+#[test]
+fn ioc_rules_restricted_to_columns_ignore_other_columns() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let saved = save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Mimikatz", "query": "mimikatz", "columns": ["Process"] },
+                { "flag": "suspicious", "tag": "Alice", "query": "alice", "columns": ["command_line"] },
+                {
+                    "flag": "suspicious",
+                    "tag": "Example",
+                    "query": "example.com",
+                    "columns": [" command_line ", "COMMAND_LINE", ""]
+                }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let example = saved
+        .entries
+        .iter()
+        .find(|entry| entry.tag == "Example")
+        .expect("rule is kept");
+    assert_eq!(example.columns, ["command_line"]);
+
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(
+        meta.ioc_applied_records, 2,
+        "alice appears only outside the rule's column"
+    );
+    let row_indexes = |flag: &str| {
+        app.query(project_id, json!({ "flagFilter": flag }))
+            .rows
+            .iter()
+            .map(|row| row.row_index)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(row_indexes("critical"), [2]);
+    assert_eq!(row_indexes("suspicious"), [5]);
+}
+
+#[test]
+fn ioc_context_fields_survive_saving_and_csv_round_trips() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let saved = save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                {
+                    "flag": "critical",
+                    "tag": "Mimikatz",
+                    "query": "sekurlsa",
+                    "description": "  LSASS access, see \"case 12\"  ",
+                    "reference_url": "https://intel.example/mimikatz",
+                    "author": "alice"
+                },
+                {
+                    "flag": "suspicious",
+                    "tag": "WS02",
+                    "query": "host:ws02",
+                    "description": "   ",
+                    "reference_url": ""
+                }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved")
+    .entries;
+    let by_tag = |entries: &[crate::models::IocEntry], tag: &str| {
+        entries
+            .iter()
+            .find(|entry| entry.tag == tag)
+            .cloned()
+            .unwrap_or_else(|| panic!("missing {tag}"))
+    };
+    let mimikatz = by_tag(&saved, "Mimikatz");
+    assert_eq!(
+        mimikatz.description.as_deref(),
+        Some("LSASS access, see \"case 12\"")
+    );
+    assert_eq!(
+        mimikatz.reference_url.as_deref(),
+        Some("https://intel.example/mimikatz")
+    );
+    assert_eq!(mimikatz.author.as_deref(), Some("alice"));
+    let ws02 = by_tag(&saved, "WS02");
+    assert!(ws02.description.is_none(), "blank text is dropped");
+    assert!(ws02.reference_url.is_none());
+
+    let destination = app.root.join("rules.csv");
+    export_iocs(
+        app.state(),
+        payload(json!({ "projectId": project_id, "destination": destination })),
+    )
+    .expect("IOC rules are exported");
+    let header = fs::read_to_string(&destination).expect("export is readable");
+    assert!(header.starts_with("flag,tag,query,kind,columns,description,reference_url,author,"));
+
+    let imported = import_iocs(
+        app.state(),
+        payload(json!({ "projectId": project_id, "path": destination })),
+    )
+    .expect("exported rules import");
+    let context =
+        |entry: crate::models::IocEntry| (entry.description, entry.reference_url, entry.author);
+    assert_eq!(context(by_tag(&imported, "Mimikatz")), context(mimikatz));
+    assert_eq!(context(by_tag(&imported, "WS02")), (None, None, None));
+}
+
+#[test]
+fn export_includes_flags_and_ioc_tags() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 0,
+            "flag": "safe",
+            "memo": "baseline"
+        })),
+    )
+    .expect("flag is stored");
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "Encoded", "query": "\"-enc\"" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let destination = app.root.join("export").join("events.csv");
+    export_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "destination": destination })),
+    )
+    .expect("export succeeds");
+
+    let mut reader = csv::Reader::from_path(&destination).expect("export is readable");
+    let headers = reader.headers().expect("export has headers").clone();
+    let index = |name: &str| {
+        headers
+            .iter()
+            .position(|header| header == name)
+            .unwrap_or_else(|| panic!("missing column {}", name))
+    };
+    let (safe, suspicious, memo) = (
+        index("trivium-safe"),
+        index("trivium-suspicious"),
+        index("trivium-memo"),
+    );
+    assert!(!headers.iter().any(|header| header == "__rowid"));
+
+    let records: Vec<csv::StringRecord> = reader
+        .records()
+        .collect::<Result<_, _>>()
+        .expect("records parse");
+    assert_eq!(records.len(), FIXTURE_ROWS);
+    assert_eq!(&records[0][safe], "1");
+    assert_eq!(&records[0][memo], "baseline");
+    assert_eq!(&records[1][suspicious], "1");
+    assert!(records[1][memo].contains("[Encoded]"));
+}
+
+#[test]
+fn jsonl_export_writes_one_object_per_row() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "dumped lsass"
+        })),
+    )
+    .expect("flag is stored");
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "Encoded", "query": "\"-enc\"" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let destination = app.root.join("export").join("events.jsonl");
+    export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "format": "jsonl"
+        })),
+    )
+    .expect("export succeeds");
+
+    let text = fs::read_to_string(&destination).expect("export is readable");
+    let records: Vec<Value> = text
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is a JSON object"))
+        .collect();
+    assert_eq!(records.len(), FIXTURE_ROWS);
+    assert!(
+        text.starts_with("{\"trivium-flag\""),
+        "annotations lead each object"
+    );
+    assert!(records[0].get("__rowid").is_none());
+    assert_eq!(records[1]["trivium-flag"], "suspicious");
+    assert_eq!(records[1]["trivium-tags"], json!(["[Encoded]"]));
+    assert_eq!(records[2]["trivium-flag"], "critical");
+    assert_eq!(records[2]["trivium-memo"], "dumped lsass");
+}
+
+#[test]
+fn xlsx_export_highlights_flagged_rows() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 3,
+            "flag": "critical",
+            "memo": "exfiltration"
+        })),
+    )
+    .expect("flag is stored");
+
+    let destination = app.root.join("export").join("events.xlsx");
+    export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "format": "xlsx"
+        })),
+    )
+    .expect("export succeeds");
+
+    let df = crate::xlsx::read_xlsx_dataframe(&destination, None).expect("workbook reads back");
+    assert_eq!(df.height(), FIXTURE_ROWS);
+    assert_eq!(
+        df.get_column_names()[..4],
+        [
+            "trivium-safe",
+            "trivium-suspicious",
+            "trivium-critical",
+            "trivium-memo"
+        ]
+    );
+    let critical = df.column("trivium-critical").expect("critical column");
+    assert_eq!(critical.i64().expect("integer column").get(3), Some(1));
+
+    let sheet = crate::zip_reader::ZipArchive::open(&destination)
+        .and_then(|archive| archive.read_to_string("xl/worksheets/sheet1.xml"))
+        .expect("sheet is readable");
+    let row = &sheet[sheet
+        .find("<row r=\"5\"")
+        .expect("row of the flagged record")..];
+    let row = &row[..row.find("</row>").expect("row ends")];
+    assert!(row.contains("s=\"3\""), "critical rows are filled");
+}
+
+#[test]
+fn element_wise_terms_match_single_list_elements() {
+    use polars::prelude::*;
+
+    let app = TestApp::new();
+    fs::create_dir_all(&app.root).expect("root exists");
+    let mut df = DataFrame::new(vec![
+        Series::new("user", ["alice", "bob", "carol"]),
+        Series::new(
+            "privileges",
+            [
+                Series::new("", ["SeDebugPrivilege", "SeBackupPrivilege"]),
+                Series::new("", ["SeShutdownPrivilege"]),
+                Series::new("", ["SeDebugPrivilege"]),
+            ],
+        ),
+    ])
+    .expect("frame builds");
+    let path = app.root.join("privileges.parquet");
+    ParquetWriter::new(fs::File::create(&path).expect("parquet file"))
+        .finish(&mut df)
+        .expect("parquet written");
+    let project_id = create_project(
+        app.state(),
+        payload(json!({ "path": path, "description": null })),
+    )
+    .expect("project is created")
+    .project
+    .meta
+    .id;
+    let rows = |search: &str| {
+        app.query(project_id, json!({ "search": search }))
+            .rows
+            .iter()
+            .map(|row| row.row_index)
+            .collect::<Vec<_>>()
+    };
+
+    assert_eq!(rows("privileges[]:sedebug"), [0, 2]);
+    assert_eq!(
+        rows("privileges:\"privilege,sebackup\""),
+        [0],
+        "the joined value spans elements"
+    );
+    assert!(rows("privileges[]:\"privilege,sebackup\"").is_empty());
+    assert_eq!(rows("privileges[]:sebackup user:alice"), [0]);
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Backup", "query": "privileges[]:sebackup" },
+                { "flag": "suspicious", "tag": "Spanning", "query": "privileges[]:\"privilege,sebackup\"" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let flagged = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(flagged.total_filtered_rows, 1);
+    assert_eq!(cell(&flagged.rows[0], "user"), "alice");
+    let spanning = app.query(project_id, json!({ "flagFilter": "suspicious" }));
+    assert_eq!(spanning.total_filtered_rows, 0);
+}
+
+#[test]
+fn parquet_export_keeps_column_types() {
+    use polars::prelude::*;
+
+    let app = TestApp::new();
+    let project_id = create_project(
+        app.state(),
+        payload(json!({
+            "path": fixture_path(FIXTURE_EVENTS),
+            "description": null,
+            "parseDates": true
+        })),
+    )
+    .expect("project is created")
+    .project
+    .meta
+    .id;
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 1,
+            "flag": "suspicious",
+            "memo": null
+        })),
+    )
+    .expect("flag is stored");
+
+    let destination = app.root.join("export").join("events.parquet");
+    export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "format": "parquet"
+        })),
+    )
+    .expect("export succeeds");
+
+    let df = ParquetReader::new(fs::File::open(&destination).expect("export exists"))
+        .finish()
+        .expect("export is valid Parquet");
+    assert_eq!(df.height(), FIXTURE_ROWS);
+    assert!(matches!(
+        df.column("timestamp").expect("timestamp column").dtype(),
+        DataType::Datetime(_, _)
+    ));
+    let suspicious = df.column("trivium-suspicious").expect("indicator column");
+    assert_eq!(suspicious.i32().expect("integer column").get(1), Some(1));
+    assert!(df.column("__rowid").is_err());
+}
+
+#[test]
+fn export_can_follow_the_current_view() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    for row_index in [1, 3, 4] {
+        update_flag(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "row_index": row_index,
+                "flag": "suspicious",
+                "memo": null
+            })),
+        )
+        .expect("flag is stored");
+    }
+
+    let destination = app.root.join("export").join("view.csv");
+    export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "search": "host:WS02|SRV01",
+            "flagFilter": "suspicious",
+            "sortKey": "timestamp",
+            "sortDirection": "desc"
+        })),
+    )
+    .expect("export succeeds");
+
+    let mut reader = csv::Reader::from_path(&destination).expect("export is readable");
+    let host = reader
+        .headers()
+        .expect("export has headers")
+        .iter()
+        .position(|header| header == "host")
+        .expect("host column");
+    let hosts: Vec<String> = reader
+        .records()
+        .map(|record| record.expect("record parses")[host].to_string())
+        .collect();
+    assert_eq!(hosts, ["SRV01", "WS02"]);
+}
+
+#[test]
+fn export_can_mask_and_hash_columns() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let export_columns = |name: &str, extra: Value| -> (Vec<String>, Vec<String>) {
+        let destination = app.root.join("export").join(name);
+        let mut request = json!({
+            "projectId": project_id,
+            "destination": destination,
+            "redact": { "user": "hash", "src_ip": "mask" }
+        });
+        request
+            .as_object_mut()
+            .expect("request is an object")
+            .extend(extra.as_object().cloned().unwrap_or_default());
+        export_project(app.state(), payload(request)).expect("export succeeds");
+        let mut reader = csv::Reader::from_path(&destination).expect("export is readable");
+        let headers = reader.headers().expect("export has headers").clone();
+        let position = |column: &str| {
+            headers
+                .iter()
+                .position(|header| header == column)
+                .expect("column is exported")
+        };
+        let (user, src_ip) = (position("user"), position("src_ip"));
+        reader
+            .records()
+            .map(|record| {
+                let record = record.expect("record parses");
+                (record[user].to_string(), record[src_ip].to_string())
+            })
+            .unzip()
+    };
+
+    let (users, ips) = export_columns("first.csv", json!({}));
+    assert!(ips.iter().all(|ip| ip == "[redacted]"));
+    assert!(!users.iter().any(|user| user == "alice" || user == "bob"));
+    assert_eq!(users[0], users[1], "equal values keep one hash");
+    assert_ne!(users[1], users[2]);
+
+    let (again, _) = export_columns("second.csv", json!({}));
+    assert_eq!(again, users, "the project key is reused");
+    let (other_key, _) = export_columns("keyed.csv", json!({ "redactionKey": "shared-case-key" }));
+    assert_ne!(other_key[0], users[0]);
+
+    let unknown = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": app.root.join("export").join("bad.csv"),
+            "redact": { "nope": "mask" }
+        })),
+    );
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn export_can_select_columns_and_skip_hidden_ones() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    set_hidden_columns(
+        app.state(),
+        payload(json!({ "projectId": project_id, "hidden_columns": ["src_ip"] })),
+    )
+    .expect("hidden columns are saved");
+
+    let export_headers = |extra: Value| -> Vec<String> {
+        let destination = app.root.join("export").join("columns.csv");
+        let mut request = json!({ "projectId": project_id, "destination": destination });
+        request
+            .as_object_mut()
+            .expect("request is an object")
+            .extend(extra.as_object().cloned().unwrap_or_default());
+        export_project(app.state(), payload(request)).expect("export succeeds");
+        let mut reader = csv::Reader::from_path(&destination).expect("export is readable");
+        let headers = reader.headers().expect("export has headers");
+        headers
+            .iter()
+            .filter(|header| !header.starts_with("trivium-"))
+            .map(str::to_string)
+            .collect()
+    };
+
+    let without_hidden = export_headers(json!({ "excludeHidden": true }));
+    assert_eq!(
+        without_hidden,
+        ["timestamp", "host", "user", "process", "command_line"]
+    );
+    let chosen = export_headers(json!({
+        "columns": ["user", "host", "src_ip"],
+        "excludeHidden": true
+    }));
+    assert_eq!(chosen, ["user", "host"]);
+
+    let unknown = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": app.root.join("export").join("bad.csv"),
+            "columns": ["nope"]
+        })),
+    );
+    assert!(unknown.is_err());
+}
+
+#[test]
+fn markdown_report_summarizes_findings() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "credential | dumping"
+        })),
+    )
+    .expect("flag is stored");
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "Encoded", "query": "\"-enc\"" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let destination = app.root.join("export").join("report.md");
+    export_report(
+        app.state(),
+        payload(json!({ "projectId": project_id, "destination": destination })),
+    )
+    .expect("report is written");
+
+    let report = fs::read_to_string(&destination).expect("report is readable");
+    assert!(report.starts_with("# Trivium report: "));
+    assert!(report.contains("| Rows | 6 |"));
+    assert!(report.contains("| critical rows | 1 |"));
+    assert!(report
+        .contains("| suspicious | [Encoded] | 1 | 2024-03-01T10:05:00Z | 2024-03-01T10:05:00Z |"));
+    assert!(report.contains("| critical | (manual flag) | 1 |"));
+    assert!(report.contains("| Time range covered | 2024-03-01T10:00:00Z – 2024-03-01T10:15:00Z |"));
+    assert!(
+        report.contains("credential \\| dumping"),
+        "pipes are escaped"
+    );
+}
+
+#[test]
+fn timesketch_export_maps_timeline_fields() {
+    let app = TestApp::new();
+    let source = app.root.join("timeline.csv");
+    fs::write(
+        &source,
+        "when,host,message,action\n\
+         2024-03-01 10:00:00.000 +09:00,WS01,logon ok,logon\n\
+         not a time,WS02,garbled,logon\n\
+         2024-03-01T02:30:00Z,WS03,service installed,install\n",
+    )
+    .expect("write timeline");
+    let project_id = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    )
+    .expect("project is created")
+    .project
+    .meta
+    .id;
+
+    let destination = app.root.join("export").join("timesketch.jsonl");
+    let response = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "format": "timesketch",
+            "timestampColumn": "when",
+            "messageColumns": ["host", "action"],
+            "timestampDesc": "Event Time"
+        })),
+    )
+    .expect("export succeeds");
+    assert_eq!(response.rows_written, 2);
+    assert_eq!(response.rows_skipped, 1);
+
+    let events: Vec<Value> = fs::read_to_string(&destination)
+        .expect("export is readable")
+        .lines()
+        .map(|line| serde_json::from_str(line).expect("each line is a JSON object"))
+        .collect();
+    assert_eq!(events[0]["message"], "WS01 | logon");
+    assert_eq!(events[0]["datetime"], "2024-03-01T01:00:00.000000+00:00");
+    assert_eq!(events[0]["timestamp"], 1_709_254_800_000_000i64);
+    assert_eq!(events[0]["timestamp_desc"], "Event Time");
+    assert_eq!(events[0]["source_message"], "logon ok");
+    assert_eq!(events[1]["host"], "WS03");
+}
+
+#[test]
+fn stix_export_converts_expressible_rules() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                {
+                    "flag": "critical",
+                    "tag": "C2",
+                    "query": "evil.example.com|dst_ip:203.0.113.7",
+                    "author": "alice",
+                    "reference_url": "https://intel.example/c2"
+                },
+                {
+                    "flag": "suspicious",
+                    "tag": "Scanner",
+                    "query": "10.0.0.0-10.0.0.5, 192.168.0.0/16",
+                    "kind": "ip"
+                },
+                { "flag": "critical", "tag": "Encoded", "query": "-enc.*", "kind": "regex" },
+                { "flag": "suspicious", "tag": "Tools", "query": "mimikatz" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let destination = app.root.join("iocs.stix.json");
+    let response = export_iocs_stix(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination.to_string_lossy(),
+        })),
+    )
+    .expect("STIX export succeeds");
+    assert_eq!(response.exported, 2);
+    let skipped: Vec<&str> = response
+        .skipped
+        .iter()
+        .map(|rule| rule.tag.as_str())
+        .collect();
+    assert_eq!(skipped, vec!["Encoded", "Tools"]);
+
+    let bundle: Value =
+        serde_json::from_str(&fs::read_to_string(&destination).expect("bundle is readable"))
+            .expect("bundle is JSON");
+    assert_eq!(bundle["type"], "bundle");
+    let objects = bundle["objects"].as_array().expect("bundle objects");
+    let identity = objects
+        .iter()
+        .find(|object| object["type"] == "identity")
+        .expect("author identity");
+    assert_eq!(identity["name"], "alice");
+    let indicators: Vec<&Value> = objects
+        .iter()
+        .filter(|object| object["type"] == "indicator")
+        .collect();
+    assert_eq!(indicators.len(), 2);
+
+    let c2 = indicators[0];
+    assert_eq!(c2["spec_version"], "2.1");
+    assert_eq!(c2["labels"], json!(["C2"]));
+    assert_eq!(c2["confidence"], 85);
+    assert_eq!(c2["created_by_ref"], identity["id"]);
+    assert_eq!(
+        c2["external_references"][0]["url"],
+        "https://intel.example/c2"
+    );
+    assert_eq!(
+        c2["pattern"],
+        "[domain-name:value = 'evil.example.com'] OR [ipv4-addr:value = '203.0.113.7']"
+    );
+    let scanner = indicators[1];
+    assert_eq!(scanner["confidence"], 50);
+    assert_eq!(
+        scanner["pattern"],
+        "[ipv4-addr:value ISSUBSET '10.0.0.0/30'] OR [ipv4-addr:value ISSUBSET '10.0.0.4/31'] \
+         OR [ipv4-addr:value ISSUBSET '192.168.0.0/16']"
+    );
+}
+
+#[test]
+fn split_exports_write_numbered_parts() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let destination = app.root.join("export").join("events.csv");
+    let response = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "splitRows": 4
+        })),
+    )
+    .expect("split export succeeds");
+    assert_eq!(response.rows_written, FIXTURE_ROWS);
+    assert_eq!(
+        response.files,
+        [
+            app.root.join("export").join("events-part001.csv"),
+            app.root.join("export").join("events-part002.csv"),
+        ]
+        .map(|path| path.to_string_lossy().into_owned())
+    );
+    let mut rows_per_part = Vec::new();
+    for file in &response.files {
+        let mut reader = csv::Reader::from_path(file).expect("part is readable");
+        assert!(reader
+            .headers()
+            .expect("part has headers")
+            .iter()
+            .any(|header| header == "host"));
+        rows_per_part.push(reader.records().count());
+    }
+    assert_eq!(rows_per_part, [4, 2]);
+    assert!(!destination.exists());
+
+    let destination = app.root.join("export").join("events.jsonl");
+    let single_row = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "format": "jsonl",
+            "splitBytes": 1
+        })),
+    )
+    .expect("size-limited export succeeds");
+    assert_eq!(single_row.files.len(), FIXTURE_ROWS);
+    for file in &single_row.files {
+        let text = fs::read_to_string(file).expect("part is readable");
+        assert_eq!(text.lines().count(), 1);
+    }
+
+    let by_size = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": app.root.join("export").join("events.xlsx"),
+            "format": "xlsx",
+            "splitBytes": 1_000_000
+        })),
+    );
+    assert!(by_size.is_err());
+}
+
+#[test]
+fn csv_export_can_change_encoding_and_delimiter() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "認証情報の窃取"
+        })),
+    )
+    .expect("flag is stored");
+
+    let export = |options: Value| -> Vec<u8> {
+        let destination = app.root.join("export").join("encoded.csv");
+        let mut request = json!({ "projectId": project_id, "destination": destination });
+        request
+            .as_object_mut()
+            .expect("request is an object")
+            .extend(options.as_object().cloned().unwrap_or_default());
+        export_project(app.state(), payload(request)).expect("export succeeds");
+        fs::read(&destination).expect("export is readable")
+    };
+
+    let shift_jis = export(json!({ "encoding": "shift_jis", "delimiter": ";" }));
+    let (text, _, malformed) = encoding_rs::SHIFT_JIS.decode(&shift_jis);
+    assert!(!malformed);
+    assert!(text.starts_with("trivium-safe;trivium-suspicious;trivium-critical;trivium-memo;"));
+    assert!(text.contains("認証情報の窃取"));
+
+    let utf16 = export(json!({ "encoding": "utf-16le", "delimiter": "\\t" }));
+    assert_eq!(&utf16[..2], b"\xFF\xFE");
+    let (text, _, malformed) = encoding_rs::UTF_16LE.decode(&utf16);
+    assert!(!malformed);
+    assert!(text.starts_with("trivium-safe\ttrivium-suspicious"));
+    assert!(text.contains("認証情報の窃取"));
+
+    let with_bom = export(json!({ "encoding": "utf-8-bom" }));
+    assert!(with_bom.starts_with(b"\xEF\xBB\xBFtrivium-safe,"));
+
+    let jsonl = export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": app.root.join("export").join("encoded.jsonl"),
+            "format": "jsonl",
+            "encoding": "shift_jis"
+        })),
+    );
+    assert!(jsonl.is_err());
+}
+
+#[test]
+fn cloned_projects_keep_annotations_independently() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "credential dumping"
+        })),
+    )
+    .expect("flag is stored");
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "Server", "query": "host:SRV01" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let copy = clone_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is cloned");
+    let copy_id = copy.meta.id;
+    assert_ne!(copy_id, project_id);
+    assert!(copy.meta.name.ends_with("(copy)"));
+    assert_eq!(copy.meta.total_records, FIXTURE_ROWS);
+    assert_eq!(
+        app.query(copy_id, json!({ "flagFilter": "critical" }))
+            .total_filtered_rows,
+        1
+    );
+    assert_eq!(
+        app.query(copy_id, json!({ "flagFilter": "suspicious" }))
+            .total_filtered_rows,
+        1
+    );
+
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": copy_id, "row_index": 2, "flag": "", "memo": null })),
+    )
+    .expect("flag is cleared in the copy");
+    assert_eq!(
+        app.query(copy_id, json!({ "flagFilter": "critical" }))
+            .total_filtered_rows,
+        0
+    );
+    assert_eq!(
+        app.query(project_id, json!({ "flagFilter": "critical" }))
+            .total_filtered_rows,
+        1
+    );
+}
+
+#[test]
+fn clones_of_encrypted_projects_are_sealed() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let passphrase = "correct horse battery";
+    enable_project_encryption(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": passphrase })),
+    )
+    .expect("project is encrypted");
+    load_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": passphrase })),
+    )
+    .expect("project opens with its passphrase");
+    update_flag(
+        app.state(),
+        payload(
+            json!({ "projectId": project_id, "row_index": 2, "flag": "critical", "memo": null }),
+        ),
+    )
+    .expect("flag is stored");
+
+    let copy_id = clone_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is cloned")
+        .meta
+        .id;
+    lock_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is sealed again");
+    let copy_dir = app.state().projects.project_dir(&copy_id);
+    assert!(copy_dir.join("data.parquet.enc").exists());
+    assert!(!copy_dir.join("data.parquet").exists());
+
+    let err = query_project_rows(
+        app.state(),
+        payload(json!({ "projectId": copy_id, "limit": 100 })),
+    )
+    .expect_err("the copy needs the passphrase");
+    assert_eq!(err.code(), "passphrase_required");
+    load_project(
+        app.state(),
+        payload(json!({ "projectId": copy_id, "passphrase": passphrase })),
+    )
+    .expect("the copy opens with the same passphrase");
+    assert_eq!(
+        app.query(copy_id, json!({ "flagFilter": "critical" }))
+            .total_filtered_rows,
+        1
+    );
+}
+
+#[test]
+fn project_bundles_round_trip_between_installations() {
+    let sender = TestApp::new();
+    let project_id = sender.create_fixture_project();
+    update_flag(
+        sender.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "credential dumping"
+        })),
+    )
+    .expect("flag is stored");
+    save_iocs(
+        sender.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "Server", "query": "host:SRV01" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let bundle = sender.root.join("handover.trivium");
+    export_project_bundle(
+        sender.state(),
+        payload(json!({ "projectId": project_id, "destination": bundle })),
+    )
+    .expect("bundle is written");
+
+    let receiver = TestApp::new();
+    let imported = import_project_bundle(receiver.state(), payload(json!({ "path": bundle })))
+        .expect("bundle is imported");
+    assert_eq!(imported.meta.id, project_id);
+    assert_eq!(imported.meta.total_records, FIXTURE_ROWS);
+    let critical = receiver.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+    assert_eq!(critical.rows[0].memo.as_deref(), Some("credential dumping"));
+    let suspicious = receiver.query(project_id, json!({ "flagFilter": "suspicious" }));
+    assert_eq!(cell(&suspicious.rows[0], "host"), "SRV01");
+
+    // Importing next to the original keeps both projects.
+    let duplicate = import_project_bundle(sender.state(), payload(json!({ "path": bundle })))
+        .expect("bundle is imported again");
+    assert_ne!(duplicate.meta.id, project_id);
+
+    let not_a_bundle = import_project_bundle(
+        receiver.state(),
+        payload(json!({ "path": fixture_path(FIXTURE_EVENTS) })),
+    );
+    assert!(not_a_bundle.is_err());
+}
+
+#[test]
+fn case_notes_keep_text_and_timestamped_entries() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let empty = get_case_notes(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("notes are readable before the first save");
+    assert!(empty.text.is_empty() && empty.entries.is_empty());
+
+    save_case_notes(
+        app.state(),
+        payload(json!({ "projectId": project_id, "text": "Scope: SRV01" })),
+    )
+    .expect("notes are saved");
+    save_case_notes(
+        app.state(),
+        payload(json!({ "projectId": project_id, "entry": "  Contained host  " })),
+    )
+    .expect("entry is appended");
+
+    let notes = get_case_notes(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("notes are loaded");
+    assert_eq!(notes.text, "Scope: SRV01");
+    assert_eq!(notes.entries.len(), 1);
+    assert_eq!(notes.entries[0].text, "Contained host");
+    assert!(notes.updated_at.is_some());
+
+    let nothing = save_case_notes(
+        app.state(),
+        payload(json!({ "projectId": project_id, "entry": " " })),
+    );
+    assert!(nothing.is_err());
+}
+
+#[test]
+fn source_integrity_detects_modified_and_missing_sources() {
+    let app = TestApp::new();
+    let source = app.root.join("evidence.csv");
+    fs::copy(fixture_path(FIXTURE_EVENTS), &source).expect("fixture is copied");
+    let project_id = create_project(app.state(), payload(json!({ "path": source })))
+        .expect("project is created")
+        .project
+        .meta
+        .id;
+    let check = |app: &TestApp| {
+        verify_source_integrity(app.state(), payload(json!({ "projectId": project_id })))
+            .expect("sources are checked")
+    };
+
+    let verified = check(&app);
+    assert_eq!(verified.len(), 1);
+    assert_eq!(verified[0].status, SourceIntegrityStatus::Verified);
+    assert_eq!(
+        verified[0].current_sha256.as_ref(),
+        Some(&verified[0].recorded_sha256)
+    );
+
+    fs::write(&source, "host,user\nSRV99,mallory\n").expect("source is altered");
+    assert_eq!(check(&app)[0].status, SourceIntegrityStatus::Modified);
+
+    fs::remove_file(&source).expect("source is removed");
+    let missing = check(&app);
+    assert_eq!(missing[0].status, SourceIntegrityStatus::Missing);
+    assert!(missing[0].current_sha256.is_none());
+}
+
+#[test]
+fn data_root_relocation_moves_projects() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 1, "flag": "critical" })),
+    )
+    .expect("flag is stored");
+    let old_dir = app.state().projects.project_dir(&project_id);
+
+    let volume = app.root.join("evidence-volume");
+    let moved =
+        set_data_root(app.state(), payload(json!({ "path": volume }))).expect("projects are moved");
+    assert_eq!(PathBuf::from(&moved.path), volume);
+    assert!(!old_dir.exists());
+    assert!(volume.join("projects.json").exists());
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+
+    // The next session opens the relocated workspace.
+    let reopened = AppState::from_root(app.root.clone()).expect("state reopens");
+    assert_eq!(reopened.projects.root_dir(), volume);
+    assert!(reopened.projects.find(&project_id).is_some());
+
+    let occupied = app.root.join("occupied");
+    fs::create_dir_all(occupied.join("projects").join("other")).expect("dir is created");
+    assert!(set_data_root(app.state(), payload(json!({ "path": occupied }))).is_err());
+    assert_eq!(app.state().projects.root_dir(), volume);
+
+    let restored =
+        set_data_root(app.state(), payload(json!({ "path": null }))).expect("projects move back");
+    assert!(restored.configured_path.is_none());
+    assert_eq!(
+        app.query(project_id, json!({})).total_filtered_rows,
+        FIXTURE_ROWS
+    );
+}
+
+#[test]
+fn project_list_orders_by_recency_and_pins() {
+    let app = TestApp::new();
+    let first = app.create_fixture_project();
+    let second = app.create_fixture_project();
+    let third = app.create_fixture_project();
+    let order = |sort: &str| -> Vec<Uuid> {
+        list_projects(app.state(), Some(payload(json!({ "sort": sort }))))
+            .expect("projects are listed")
+            .into_iter()
+            .map(|project| project.meta.id)
+            .collect()
+    };
+    assert_eq!(order("created"), vec![third, second, first]);
+
+    load_project(app.state(), payload(json!({ "projectId": first }))).expect("project loads");
+    let opened = app.state().projects.find(&first).expect("project exists");
+    assert!(opened.last_opened_at.is_some());
+    assert_eq!(order("recent"), vec![first, third, second]);
+
+    let pinned = set_project_pinned(
+        app.state(),
+        payload(json!({ "projectId": second, "pinned": true })),
+    )
+    .expect("project is pinned");
+    assert!(pinned.meta.pinned);
+    assert_eq!(order("pinned"), vec![second, first, third]);
+}
+
+#[test]
+fn storage_info_reports_project_sizes() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 0, "flag": "safe" })),
+    )
+    .expect("flag is stored");
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "mimikatz" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+
+    let info = get_project_storage_info(app.state()).expect("storage is measured");
+    assert_eq!(info.projects.len(), 1);
+    let usage = info.projects[0].usage;
+    let parquet = app
+        .state()
+        .projects
+        .project_dir(&project_id)
+        .join("data.parquet");
+    assert_eq!(
+        usage.data_bytes,
+        fs::metadata(parquet).expect("data exists").len()
+    );
+    assert!(usage.flags_bytes > 0);
+    assert!(usage.ioc_bytes > 0);
+    assert_eq!(
+        usage.total_bytes,
+        usage.data_bytes
+            + usage.flags_bytes
+            + usage.cache_bytes
+            + usage.ioc_bytes
+            + usage.other_bytes
+    );
+    assert_eq!(info.totals.total_bytes, usage.total_bytes);
+}
+
+#[test]
+fn check_project_finds_and_repairs_inconsistencies() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let check = |repair: bool| {
+        check_project(
+            app.state(),
+            payload(json!({ "projectId": project_id, "repair": repair })),
+        )
+        .expect("project is checked")
+    };
+    let clean = check(false);
+    assert!(clean.healthy, "unexpected issues: {:?}", clean.issues);
+    assert!(clean.issues.is_empty());
+
+    let project_dir = app.state().projects.project_dir(&project_id);
+    crate::storage::upsert_flag(
+        &project_dir.join("flags.json"),
+        FIXTURE_ROWS + 10,
+        &crate::models::FlagEntry {
+            flag: "critical".into(),
+            memo: None,
+            comments: Vec::new(),
+        },
+    )
+    .expect("stray flag is written");
+    app.state()
+        .projects
+        .update_counters(&project_id, 999, 0, 0)
+        .expect("counters are overwritten");
+
+    let found = check(false);
+    assert!(!found.healthy);
+    let kinds: Vec<&str> = found.issues.iter().map(|issue| issue.kind).collect();
+    assert!(kinds.contains(&"flags_out_of_range"));
+    assert!(kinds.contains(&"counters"));
+    assert!(found.issues.iter().all(|issue| !issue.repaired));
+
+    let repaired = check(true);
+    assert!(repaired.healthy);
+    assert!(check(false).issues.is_empty());
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(meta.total_records, FIXTURE_ROWS);
+    assert_eq!(meta.flagged_records, 0);
+}
+
+#[test]
+fn compact_project_drops_orphans_and_keeps_annotations() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    for row in 0..FIXTURE_ROWS {
+        update_flag(
+            app.state(),
+            payload(json!({ "projectId": project_id, "row_index": row, "flag": "suspicious" })),
+        )
+        .expect("flag is stored");
+    }
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 3,
+            "flag": "critical",
+            "memo": "kept"
+        })),
+    )
+    .expect("flag is updated");
+    let project_dir = app.state().projects.project_dir(&project_id);
+    fs::write(project_dir.join("data.parquet.reimport"), b"stale").expect("leftover is written");
+
+    let compacted = compact_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is compacted");
+    assert_eq!(
+        compacted.removed_files,
+        vec!["data.parquet.reimport".to_string()]
+    );
+    assert!(compacted.bytes_after <= compacted.bytes_before);
+    assert!(!project_dir.join("data.parquet.reimport").exists());
+
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+    assert_eq!(critical.rows[0].memo.as_deref(), Some("kept"));
+    assert_eq!(
+        app.query(project_id, json!({})).total_filtered_rows,
+        FIXTURE_ROWS
+    );
+}
+
+#[test]
+fn legacy_projects_are_migrated_on_startup() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+
+    // Rewind the project to the unversioned layout: JSON flags, stale counter.
+    fs::remove_dir_all(project_dir.join("flags.db")).expect("flags db is removed");
+    fs::write(
+        project_dir.join("flags.json"),
+        json!({ "2": { "flag": "critical", "memo": "legacy" } }).to_string(),
+    )
+    .expect("legacy flags are written");
+    let index_path = app.root.join("projects.json");
+    let mut index: Value =
+        serde_json::from_slice(&fs::read(&index_path).expect("index exists")).expect("index");
+    let entry = index[0].as_object_mut().expect("project entry");
+    entry.remove("format_version");
+    entry.insert("flagged_records".into(), json!(0));
+    fs::write(&index_path, index.to_string()).expect("index is rewritten");
+
+    let reopened = AppState::from_root(app.root.clone()).expect("state reopens");
+    let meta = reopened.projects.find(&project_id).expect("project exists");
+    assert_eq!(
+        meta.format_version,
+        crate::migrations::CURRENT_FORMAT_VERSION
+    );
+    assert_eq!(meta.flagged_records, 1);
+    assert!(!project_dir.join("flags.json").exists());
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.rows[0].memo.as_deref(), Some("legacy"));
+
+    let mut newer = meta.clone();
+    newer.format_version = crate::migrations::CURRENT_FORMAT_VERSION + 1;
+    assert!(crate::migrations::migrate_project(&project_dir, &mut newer).is_err());
+}
+
+#[test]
+fn annotation_backups_restore_flags_and_iocs() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+
+    assert_eq!(
+        snapshot_project(&project_dir).expect("snapshot runs"),
+        None,
+        "a project without annotations is not snapshotted"
+    );
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 0, "flag": "suspicious", "memo": "keep" })),
+    )
+    .expect("flag is set");
+    let backup_id = snapshot_project(&project_dir)
+        .expect("snapshot runs")
+        .expect("changed annotations are snapshotted");
+    assert_eq!(snapshot_project(&project_dir).expect("snapshot runs"), None);
+
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 0, "flag": "", "memo": null })),
+    )
+    .expect("flag is cleared");
+
+    let backups = list_annotation_backups(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("backups are listed");
+    assert_eq!(backups.len(), 1);
+    assert_eq!(backups[0].id, backup_id);
+    assert_eq!(backups[0].flagged_rows, 1);
+
+    let summary = restore_annotations_backup(
+        app.state(),
+        payload(json!({ "projectId": project_id, "backupId": backup_id })),
+    )
+    .expect("backup is restored");
+    assert_eq!(summary.meta.flagged_records, 1);
+    let rows = app.query(project_id, json!({ "flagFilter": "suspicious" }));
+    assert_eq!(rows.rows.len(), 1);
+    assert_eq!(rows.rows[0].memo.as_deref(), Some("keep"));
+
+    let backups = list_annotation_backups(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("backups are listed");
+    assert_eq!(backups.len(), 2, "the state before the restore is kept");
+
+    let bogus = restore_annotations_backup(
+        app.state(),
+        payload(json!({ "projectId": project_id, "backupId": "../flags" })),
+    );
+    assert!(bogus.is_err());
+}
+
+#[test]
+fn query_pages_carry_every_column_when_only_filter_columns_are_scanned() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let page = app.query(
+        project_id,
+        json!({
+            "search": "user:bob",
+            "sortKey": "timestamp",
+            "sortDirection": "desc",
+            "offset": 1,
+            "limit": 1
+        }),
+    );
+    assert_eq!(page.total_rows, FIXTURE_ROWS);
+    assert_eq!(page.total_filtered_rows, 2);
+    assert_eq!(page.rows.len(), 1);
+    let row = &page.rows[0];
+    assert_eq!(row.row_index, 2);
+    assert_eq!(cell(row, "process"), "mimikatz.exe");
+    assert_eq!(
+        row.data.len(),
+        6,
+        "unfiltered columns are read for the page"
+    );
+}
+
+#[test]
+fn searchable_text_cache_persists_until_hidden_columns_change() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    let columns: Vec<String> = [
+        "timestamp",
+        "host",
+        "user",
+        "process",
+        "command_line",
+        "src_ip",
+    ]
+    .iter()
+    .map(|name| name.to_string())
+    .collect();
+
+    assert_eq!(
+        app.query(project_id, json!({ "search": "mimikatz" }))
+            .total_filtered_rows,
+        1
+    );
+    let cache = load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .expect("search text is persisted");
+    assert!(cache.is_valid_for(FIXTURE_ROWS, &columns, &[]));
+
+    set_hidden_columns(
+        app.state(),
+        payload(json!({ "projectId": project_id, "hidden_columns": ["src_ip"] })),
+    )
+    .expect("hidden columns are saved");
+    let hidden = vec!["src_ip".to_string()];
+    let cache = load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .expect("cache is kept");
+    assert!(!cache.is_valid_for(FIXTURE_ROWS, &columns, &hidden));
+
+    assert_eq!(
+        app.query(project_id, json!({ "search": "whoami" }))
+            .total_filtered_rows,
+        1
+    );
+    let cache = load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .expect("search text is persisted");
+    assert!(cache.is_valid_for(FIXTURE_ROWS, &columns, &hidden));
+}
+
+#[test]
+fn ioc_flag_cache_is_rebuilt_when_rules_or_user_flags_change() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    let critical = |app: &TestApp| {
+        app.query(project_id, json!({ "flagFilter": "critical" }))
+            .rows
+            .iter()
+            .map(|row| row.row_index)
+            .collect::<Vec<_>>()
+    };
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Nothing", "query": "no-such-value" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    assert!(critical(&app).is_empty());
+
+    // Written behind the commands' backs, so no cache is cleared explicitly.
+    let rules: Vec<crate::models::IocEntry> = serde_json::from_value(json!([
+        { "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" },
+        { "flag": "critical", "tag": "Backup", "query": "robocopy" }
+    ]))
+    .expect("rules deserialize");
+    crate::ioc::save_ioc_entries(&project_dir, &rules).expect("rules are written");
+    assert_eq!(critical(&app), [2, 4]);
+
+    let flags_path = project_dir.join("flags.json");
+    crate::storage::upsert_flag(
+        &flags_path,
+        2,
+        &crate::models::FlagEntry {
+            flag: "safe".into(),
+            memo: None,
+            comments: Vec::new(),
+        },
+    )
+    .expect("flag is written");
+    assert_eq!(critical(&app), [4]);
+    crate::storage::remove_flag(&flags_path, 2).expect("flag is removed");
+    assert_eq!(
+        critical(&app),
+        [2, 4],
+        "the IOC flag returns with the user flag gone"
+    );
+}
+
+#[test]
+fn ioc_applied_records_are_counted_column_wise_and_cached() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" },
+                { "flag": "suspicious", "tag": "WS02", "query": "host:ws02" },
+                { "flag": "", "tag": "Chrome", "query": "chrome" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(
+        meta.ioc_applied_records, 3,
+        "rows matched only by a flagless rule count too"
+    );
+
+    let cache = crate::storage::load_ioc_flag_cache(&project_dir)
+        .expect("cache is readable")
+        .expect("IOC flags are persisted while counting");
+    assert_eq!(cache.flags[2], "critical", "the most severe rule wins");
+    assert_eq!(cache.flags[3], "suspicious");
+    assert!(cache.flags[5].is_empty());
+    assert_eq!(
+        app.query(project_id, json!({ "flagFilter": "critical" }))
+            .rows
+            .iter()
+            .map(|row| row.row_index)
+            .collect::<Vec<_>>(),
+        [2]
+    );
+}
+
+#[test]
+fn saving_iocs_reports_rules_to_check() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let saved = save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa |" },
+                { "flag": "suspicious", "tag": "Bob", "query": "usr:bob" },
+                { "flag": "suspicious", "tag": "Nothing", "query": "nomatchtoken" },
+                { "flag": "suspicious", "tag": "WS02", "query": "host:ws02" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let tags = |issues: &[iocs::IocRuleIssue]| {
+        issues
+            .iter()
+            .map(|issue| issue.tag.clone())
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(tags(&saved.report.invalid_syntax), ["Mimikatz"]);
+    assert_eq!(tags(&saved.report.unknown_columns), ["Bob"]);
+    let zero_hits = tags(&saved.report.zero_hits);
+    assert!(zero_hits.contains(&"Nothing".to_string()));
+    assert!(
+        !zero_hits.contains(&"Mimikatz".to_string()),
+        "the malformed rule still matches"
+    );
+    assert!(!zero_hits.contains(&"WS02".to_string()));
+}
+
+#[test]
+fn ioc_groups_are_disabled_and_deleted_together() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let applied = |app: &TestApp| {
+        app.state()
+            .projects
+            .find(&project_id)
+            .expect("project exists")
+            .ioc_applied_records
+    };
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa", "group": "APT-X feed" },
+                { "flag": "suspicious", "tag": "WS02", "query": "host:ws02", "group": "APT-X feed" },
+                { "flag": "", "tag": "Chrome", "query": "chrome" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    assert_eq!(applied(&app), 3);
+
+    let entries = set_ioc_group_enabled(
+        app.state(),
+        payload(json!({ "projectId": project_id, "group": "APT-X feed", "enabled": false })),
+    )
+    .expect("group is disabled");
+    assert_eq!(entries.iter().filter(|entry| entry.disabled).count(), 2);
+    assert_eq!(applied(&app), 1, "disabled rules are not evaluated");
+    assert!(app
+        .query(project_id, json!({ "flagFilter": "critical" }))
+        .rows
+        .is_empty());
+
+    set_ioc_group_enabled(
+        app.state(),
+        payload(json!({ "projectId": project_id, "group": "APT-X feed", "enabled": true })),
+    )
+    .expect("group is enabled");
+    assert_eq!(applied(&app), 3);
+
+    let entries = delete_ioc_group(
+        app.state(),
+        payload(json!({ "projectId": project_id, "group": "APT-X feed" })),
+    )
+    .expect("group is deleted");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].tag, "Chrome");
+    assert_eq!(applied(&app), 1);
+    assert!(delete_ioc_group(
+        app.state(),
+        payload(json!({ "projectId": project_id, "group": "APT-X feed" })),
+    )
+    .is_err());
+}
+
+#[test]
+fn flag_edits_adjust_ioc_applied_records_from_stored_matches() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    let applied = |app: &TestApp| {
+        app.state()
+            .projects
+            .find(&project_id)
+            .expect("project exists")
+            .ioc_applied_records
+    };
+    let set_flag = |app: &TestApp, row: usize, flag: &str| {
+        update_flag(
+            app.state(),
+            payload(
+                json!({ "projectId": project_id, "row_index": row, "flag": flag, "memo": null }),
+            ),
+        )
+        .expect("flag is updated");
+    };
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "suspicious", "tag": "WS02", "query": "host:ws02" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    assert_eq!(applied(&app), 2);
+
+    set_flag(&app, 2, "safe");
+    assert_eq!(applied(&app), 1);
+    set_flag(&app, 0, "critical");
+    assert_eq!(
+        applied(&app),
+        1,
+        "rows no rule matches leave the count alone"
+    );
+    assert_eq!(
+        app.query(project_id, json!({ "flagFilter": "suspicious" }))
+            .rows
+            .iter()
+            .map(|row| row.row_index)
+            .collect::<Vec<_>>(),
+        [3],
+        "IOC flags follow the user flags without a cache reset"
+    );
+    set_flag(&app, 2, "");
+    assert_eq!(applied(&app), 2);
+
+    crate::storage::clear_ioc_flag_cache(&project_dir).expect("caches are cleared");
+    set_flag(&app, 3, "safe");
+    assert_eq!(
+        applied(&app),
+        1,
+        "the count is recomputed without stored matches"
+    );
+    assert!(crate::storage::load_ioc_match_cache(&project_dir)
+        .expect("cache is readable")
+        .is_some());
+}
+
+#[test]
+fn searchable_text_is_stored_in_chunks() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+
+    assert_eq!(
+        app.query(project_id, json!({ "search": "robocopy" }))
+            .total_filtered_rows,
+        1
+    );
+    let cache = load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .expect("search text is persisted");
+    assert_eq!(
+        cache.chunk_rows, FIXTURE_ROWS,
+        "small projects use a single chunk"
+    );
+    let chunk = crate::storage::load_searchable_chunk(&project_dir, 0)
+        .expect("chunk is readable")
+        .expect("chunk is stored");
+    assert_eq!(chunk.len(), FIXTURE_ROWS);
+    assert!(chunk[4].contains("robocopy"));
+
+    crate::storage::clear_searchable_cache(&project_dir).expect("cache is cleared");
+    assert!(load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .is_none());
+    assert!(crate::storage::load_searchable_chunk(&project_dir, 0)
+        .expect("chunk is readable")
+        .is_none());
+}
+
+#[test]
+fn refresh_modified_project_drops_caches_and_recounts() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+
+    app.query(project_id, json!({ "search": "robocopy" }));
+    assert!(load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .is_some());
+    app.state()
+        .projects
+        .update_counters(&project_id, 999, 5, 5)
+        .expect("counters are overwritten");
+
+    crate::watcher::refresh_modified_project(&app.state(), &project_id)
+        .expect("project is refreshed");
+    assert!(load_searchable_cache(&project_dir)
+        .expect("cache is readable")
+        .is_none());
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert_eq!(meta.total_records, FIXTURE_ROWS);
+    assert_eq!(meta.flagged_records, 0);
+    assert_eq!(meta.ioc_applied_records, 0);
+}
+
+#[test]
+fn watched_files_ignore_the_apps_own_writes() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    let state = app.state();
+    state.watched.record(&state, &project_id);
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "mimikatz" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    assert!(state.watched.changes(&state, &project_id).is_empty());
+
+    drop(
+        state
+            .locks
+            .rewrite(&project_id, "compaction")
+            .expect("project is free"),
+    );
+    fs::write(project_dir.join("data.parquet"), b"rewritten").expect("data is replaced");
+    assert!(state.watched.changes(&state, &project_id).is_empty());
+
+    fs::write(project_dir.join("iocs.json"), b"[]").expect("rules are replaced");
+    assert_eq!(
+        state.watched.changes(&state, &project_id),
+        vec!["iocs.json".to_string()]
+    );
+    assert!(state.watched.changes(&state, &project_id).is_empty());
+}
+
+#[test]
+fn session_is_offered_back_only_after_a_crash() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let session = json!({
+        "projectId": project_id,
+        "search": "powershell",
+        "flagFilter": "critical",
+        "sortKey": "timestamp",
+        "sortDirection": "desc",
+        "offset": 200,
+    });
+    save_session_state(app.state(), payload(session.clone())).expect("session is saved");
+
+    // Nothing marked the exit clean, as after a crash.
+    let crashed = AppState::from_root(app.root.clone()).expect("state reopens");
+    let restored = crashed
+        .session
+        .take_recovered()
+        .expect("crashed session is recovered");
+    assert_eq!(restored.project_id, Some(project_id));
+    assert_eq!(restored.search.as_deref(), Some("powershell"));
+    assert_eq!(restored.flag_filter.as_deref(), Some("critical"));
+    assert_eq!(restored.sort_key.as_deref(), Some("timestamp"));
+    assert_eq!(restored.sort_direction.as_deref(), Some("desc"));
+    assert_eq!(restored.offset, 200);
+    assert!(crashed.session.take_recovered().is_none());
+
+    save_session_state(app.state(), payload(session)).expect("session is saved");
+    app.state()
+        .session
+        .mark_clean_exit()
+        .expect("clean exit is recorded");
+    let reopened = AppState::from_root(app.root.clone()).expect("state reopens");
+    assert!(reopened.session.take_recovered().is_none());
+}
+
+#[test]
+fn command_errors_carry_codes_and_context() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    let parquet_path = project_dir.join("data.parquet");
+
+    let unknown = Uuid::new_v4();
+    let err = load_project(app.state(), payload(json!({ "projectId": unknown })))
+        .expect_err("unknown project is rejected");
+    assert_eq!(
+        serde_json::to_value(&err).expect("error serializes"),
+        json!({
+            "code": "project_not_found",
+            "message": "Project not found.",
+            "context": { "projectId": unknown },
+        })
+    );
+
+    fs::write(&parquet_path, b"not a parquet file").expect("data is overwritten");
+    let err = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("corrupt data is rejected");
+    assert_eq!(err.code(), "data_corrupt");
+    assert_eq!(err.context().path, Some(parquet_path.clone()));
+
+    fs::remove_file(&parquet_path).expect("data is removed");
+    let err = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("missing data is rejected");
+    assert_eq!(err.code(), "data_file_missing");
+}
+
+#[test]
+fn log_level_is_persisted_in_settings() {
+    let app = TestApp::new();
+    let logs = get_recent_logs(app.state(), payload(json!({}))).expect("logs are returned");
+    assert_eq!(logs.level, crate::logging::LogLevel::Info);
+
+    set_log_level(app.state(), payload(json!({ "level": "debug" }))).expect("level is set");
+    let logs =
+        get_recent_logs(app.state(), payload(json!({ "limit": 10 }))).expect("logs are returned");
+    assert_eq!(logs.level, crate::logging::LogLevel::Debug);
+    assert!(logs.lines.len() <= 10);
+
+    assert!(serde_json::from_value::<crate::logging::LogLevel>(json!("verbose")).is_err());
+}
+
+#[test]
+fn performance_stats_count_queries_and_cache_hits() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    app.query(project_id, json!({ "search": "robocopy" }));
+    app.query(project_id, json!({ "search": "robocopy" }));
+    let stats = get_performance_stats(app.state()).expect("stats are returned");
+    let query = stats
+        .operations
+        .iter()
+        .find(|operation| operation.name == "query_project_rows")
+        .expect("query stats");
+    assert_eq!(query.count, 2);
+    assert!(query.last_rows_scanned >= FIXTURE_ROWS as u64);
+    let text = stats
+        .caches
+        .iter()
+        .find(|cache| cache.name == "searchable_text")
+        .expect("text cache stats");
+    assert_eq!((text.hits, text.misses), (1, 1));
+    assert_eq!(text.hit_rate, Some(0.5));
+
+    reset_performance_stats(app.state()).expect("stats are reset");
+    let stats = get_performance_stats(app.state()).expect("stats are returned");
+    assert!(stats
+        .operations
+        .iter()
+        .all(|operation| operation.count == 0));
+    assert!(stats.caches.iter().all(|cache| cache.hit_rate.is_none()));
+}
+
+#[test]
+fn debug_queries_report_phase_timings() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    assert!(app.query(project_id, json!({})).timings.is_none());
+    let response = app.query(
+        project_id,
+        json!({ "search": "robocopy", "sortKey": "user", "debug": true }),
+    );
+    assert_eq!(response.total_filtered_rows, 1);
+    let timings = response.timings.expect("timings are returned");
+    let phases = timings.read_ms
+        + timings.search_ms
+        + timings.ioc_ms
+        + timings.sort_ms
+        + timings.filter_ms
+        + timings.materialize_ms;
+    assert!(timings.total_ms >= phases);
+}
+
+#[test]
+fn sort_orders_are_reused_across_pages() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let page = |app: &TestApp, offset: usize, direction: &str| {
+        app.query(
+            project_id,
+            json!({ "sortKey": "user", "sortDirection": direction, "offset": offset, "limit": 2 }),
+        )
+        .rows
+        .iter()
+        .map(|row| row.row_index)
+        .collect::<Vec<_>>()
+    };
+
+    assert_eq!(page(&app, 0, "asc"), [0, 1]);
+    assert_eq!(page(&app, 2, "asc"), [2, 3]);
+    assert_eq!(page(&app, 4, "asc"), [5, 4]);
+    assert_eq!(page(&app, 0, "desc"), [4, 5]);
+    let stats = get_performance_stats(app.state()).expect("stats are returned");
+    let sorts = stats
+        .caches
+        .iter()
+        .find(|cache| cache.name == "sort_order")
+        .expect("sort cache stats");
+    assert_eq!((sorts.hits, sorts.misses), (2, 2));
+}
+
+#[test]
+fn script_column_is_added_from_each_row() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    add_script_column(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "script": "if row.command_line.contains(\"robocopy\") { \"copy\" }",
+            "inputColumns": ["command_line"],
+            "column": "activity",
+        })),
+    )
+    .expect("column is added");
+    add_script_column(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "script": "decode_base64(row.command_line)",
+            "column": "decoded",
+        })),
+    )
+    .expect("column is added");
+    let rows = app.query(project_id, json!({}));
+    let tagged: Vec<usize> = rows
+        .rows
+        .iter()
+        .filter(|row| cell(row, "activity") == "copy")
+        .map(|row| row.row_index)
+        .collect();
+    assert_eq!(tagged, [4]);
+    assert_eq!(cell(&rows.rows[1], "decoded"), "IEX");
+    assert_eq!(cell(&rows.rows[0], "decoded"), "");
+
+    for script in ["loop {}", "eval(\"1\")", "import \"enrich\" as enrich; 1"] {
+        let rejected = add_script_column(
+            app.state(),
+            payload(json!({ "projectId": project_id, "script": script, "column": "sandboxed" })),
+        );
+        assert!(rejected.is_err(), "{} is not allowed", script);
+    }
+    let unknown = add_script_column(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "script": "1",
+            "inputColumns": ["missing"],
+            "column": "unknown",
+        })),
+    )
+    .expect_err("unknown input columns are rejected");
+    assert!(unknown.to_string().contains("missing"));
+}
+
+#[test]
+fn script_columns_are_added_while_importing() {
+    let app = TestApp::new();
+    let script_columns = json!([
+        { "column": "risk", "script": "if row.user == \"bob\" { 10 } else { 0 }" },
+        { "column": "flagged", "script": "row.risk == \"10\"", "inputColumns": ["risk"] }
+    ]);
+    let response = create_project(
+        app.state(),
+        payload(json!({
+            "path": fixture_path(FIXTURE_EVENTS),
+            "scriptColumns": script_columns,
+        })),
+    )
+    .expect("project is created");
+    let project_id = response.project.meta.id;
+    assert_eq!(response.project.meta.import_options.script_columns.len(), 2);
+
+    let rows = app.query(project_id, json!({}));
+    let risky: Vec<usize> = rows
+        .rows
+        .iter()
+        .filter(|row| cell(row, "risk") == "10")
+        .map(|row| row.row_index)
+        .collect();
+    assert_eq!(risky, [2, 3]);
+    assert_eq!(cell(&rows.rows[2], "flagged"), "true");
+    assert_eq!(cell(&rows.rows[0], "flagged"), "false");
+
+    let invalid = create_project(
+        app.state(),
+        payload(json!({
+            "path": fixture_path(FIXTURE_EVENTS),
+            "scriptColumns": [{ "column": "broken", "script": "if {" }],
+        })),
+    );
+    assert!(invalid.is_err(), "scripts are checked before importing");
+    assert_eq!(app.state().projects.all().len(), 1);
+}
+
+#[test]
+fn critical_ioc_rows_raise_one_alert() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let alerts = Arc::new(Mutex::new(Vec::new()));
+    let captured = Arc::clone(&alerts);
+    app.state().alerts.set_listener(Box::new(move |alert| {
+        captured.lock().unwrap().push(alert.clone())
+    }));
+
+    let rejected = set_project_alerts(
+        app.state(),
+        payload(json!({ "projectId": project_id, "webhookUrl": "file:///etc/passwd" })),
+    );
+    assert!(rejected.is_err());
+    let summary = set_project_alerts(
+        app.state(),
+        payload(json!({ "projectId": project_id, "notify": true })),
+    )
+    .expect("alert settings are saved");
+    assert!(summary.meta.alerts.notify);
+
+    let rules = json!({
+        "projectId": project_id,
+        "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" }]
+    });
+    save_iocs(app.state(), payload(rules.clone())).expect("IOC rules are saved");
+    save_iocs(app.state(), payload(rules)).expect("IOC rules are saved again");
+
+    let alerts = alerts.lock().unwrap();
+    assert_eq!(alerts.len(), 1);
+    assert_eq!(alerts[0].new_critical_rows, 1);
+    assert_eq!(alerts[0].rows[0].tags, vec!["[Mimikatz]".to_string()]);
+}
+
+/// Answers one HTTP request on a local port with `status`; the handle
+/// yields the request body.
+fn serve_once(status: &'static str) -> (String, std::thread::JoinHandle<String>) {
+    use std::io::{Read, Write};
+
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").expect("port is free");
+    let url = format!("http://{}/hook", listener.local_addr().expect("bound"));
+    let handle = std::thread::spawn(move || {
+        let (mut stream, _) = listener.accept().expect("request arrives");
+        let mut request = Vec::new();
+        let mut buffer = [0u8; 4096];
+        let body_start = loop {
+            let read = stream.read(&mut buffer).expect("request is readable");
+            request.extend_from_slice(&buffer[..read]);
+            if let Some(end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+                break end + 4;
+            }
+        };
+        let head = String::from_utf8_lossy(&request[..body_start]).to_ascii_lowercase();
+        let length: usize = head
+            .lines()
+            .find_map(|line| line.strip_prefix("content-length:"))
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(0);
+        while request.len() < body_start + length {
+            let read = stream.read(&mut buffer).expect("body is readable");
+            request.extend_from_slice(&buffer[..read]);
+        }
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            status
+        );
+        stream
+            .write_all(response.as_bytes())
+            .expect("response is sent");
+        String::from_utf8_lossy(&request[body_start..]).into_owned()
+    });
+    (url, handle)
+}
+
+#[test]
+fn test_alerts_surface_webhook_errors() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let err = send_test_alert(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("a webhook is needed");
+    assert!(err.to_string().contains("no webhook URL"));
+
+    let (url, server) = serve_once("200 OK");
+    set_project_alerts(
+        app.state(),
+        payload(json!({ "projectId": project_id, "webhookUrl": url })),
+    )
+    .expect("alert settings are saved");
+    send_test_alert(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("webhook accepts the alert");
+    let body: Value =
+        serde_json::from_str(&server.join().expect("server finishes")).expect("alert is JSON");
+    assert_eq!(body["project_id"], json!(project_id));
+    assert_eq!(body["new_critical_rows"], json!(0));
+
+    let (url, server) = serve_once("404 Not Found");
+    set_project_alerts(
+        app.state(),
+        payload(json!({ "projectId": project_id, "webhookUrl": url })),
+    )
+    .expect("alert settings are saved");
+    let err = send_test_alert(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("error status fails the test");
+    server.join().expect("server finishes");
+    assert_eq!(err.code(), "invalid_request");
+    assert!(err.to_string().contains("HTTP 404"), "{}", err);
+}
+
+#[test]
+fn list_entities_indexes_ips_and_domains() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let listed = list_entities(
+        app.state(),
+        payload(json!({ "projectId": project_id, "kinds": ["ip"] })),
+    )
+    .expect("entities are listed");
+    assert_eq!(listed.total_entities, 5);
+    assert_eq!(listed.entities[0].value, "10.0.0.5");
+    assert_eq!(listed.entities[0].row_count, 2);
+    assert_eq!(listed.entities[0].rows, vec![0, 1]);
+
+    let project_dir = app.state().projects.project_dir(&project_id);
+    assert!(project_dir.join("entities.json").exists());
+
+    // File names such as `explorer.exe` are not domains.
+    let domains = list_entities(
+        app.state(),
+        payload(json!({ "projectId": project_id, "kinds": ["domain"] })),
+    )
+    .expect("entities are listed");
+    let values: Vec<&str> = domains.entities.iter().map(|e| e.value.as_str()).collect();
+    assert_eq!(values, vec!["example.com"]);
+    assert_eq!(domains.entities[0].rows, vec![5]);
+}
+
+#[test]
+fn pivot_entity_groups_rows_by_column_and_flag() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    update_flag(
+        app.state(),
+        payload(json!({ "projectId": project_id, "row_index": 3, "flag": "suspicious" })),
+    )
+    .expect("flag is stored");
+
+    let pivot = pivot_entity(
+        app.state(),
+        payload(json!({ "projectId": project_id, "value": "10.0.0.9" })),
+    )
+    .expect("pivot succeeds");
+    assert_eq!(pivot.row_indices, vec![2, 3]);
+    assert_eq!(pivot.flags.critical, 1);
+    assert_eq!(pivot.flags.suspicious, 1);
+    assert_eq!(pivot.columns.len(), 1);
+    assert_eq!(pivot.columns[0].column, "src_ip");
+    assert_eq!(pivot.rows[0].flag, "critical");
+
+    // A prefix of another address is not a match.
+    let prefix = pivot_entity(
+        app.state(),
+        payload(json!({ "projectId": project_id, "value": "10.0.0.1", "columns": ["src_ip"] })),
+    )
+    .expect("pivot succeeds");
+    assert!(prefix.row_indices.is_empty());
+}
+
+#[test]
+fn defanged_indicators_match_plain_data() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let hits = app.query(project_id, json!({ "search": "\"hxxps://example[.]com\"" }));
+    assert_eq!(hits.total_filtered_rows, 1);
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "suspicious", "tag": "Intel", "query": "example[.]com" },
+                { "flag": "critical", "tag": "Lateral", "query": "10.0.0[.]9", "kind": "ip" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let suspicious = app.query(project_id, json!({ "flagFilter": "suspicious" }));
+    assert_eq!(suspicious.total_filtered_rows, 1);
+    assert_eq!(suspicious.rows[0].row_index, 5);
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 2);
+}
+
+#[test]
+fn decoded_column_makes_encoded_commands_searchable() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let request =
+        json!({ "projectId": project_id, "source": "command_line", "encoding": "base64" });
+
+    add_decoded_column(app.state(), payload(request.clone())).expect("column is added");
+    let hits = app.query(project_id, json!({ "search": "command_line_decoded:iex" }));
+    assert_eq!(hits.total_filtered_rows, 1);
+    assert_eq!(hits.rows[0].row_index, 1);
+    assert_eq!(hits.rows[0].data["command_line_decoded"], json!("IEX"));
+    let plain = app.query(project_id, json!({}));
+    assert_eq!(plain.rows[0].data["command_line_decoded"], Value::Null);
+
+    let duplicate = add_decoded_column(app.state(), payload(request));
+    assert!(duplicate.is_err());
+}
+
+#[test]
+fn timestamp_columns_are_stored_in_utc_and_shown_in_display_zone() {
+    let app = TestApp::new();
+    let source = app.root.join("logons.csv");
+    fs::write(
+        &source,
+        "host,logon_time\nWS01,2024-03-10 01:30:00\nWS02,2024-03-10 12:00:00 +00:00\n",
+    )
+    .expect("write export");
+
+    let response = create_project(
+        app.state(),
+        payload(json!({
+            "path": source,
+            "timestampColumns": ["logon_time"],
+            "sourceTimezone": "America/New_York",
+        })),
+    )
+    .expect("project with local timestamps is created");
+    let meta = response.project.meta;
+    assert_eq!(meta.timestamp_column.as_deref(), Some("logon_time"));
+    let logon_type = response
+        .column_types
+        .iter()
+        .find(|column| column.name == "logon_time")
+        .map(|column| column.dtype.clone())
+        .unwrap_or_default();
+    assert!(logon_type.starts_with("datetime"));
+
+    let rows = app.query(meta.id, json!({ "sortKey": "logon_time" }));
+    assert_eq!(cell(&rows.rows[0], "logon_time"), "2024-03-10 06:30:00");
+    assert_eq!(cell(&rows.rows[1], "logon_time"), "2024-03-10 12:00:00");
+
+    let updated = set_display_timezone(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "timezone": "Asia/Tokyo" })),
+    )
+    .expect("display time zone is set");
+    assert_eq!(updated.meta.display_timezone.as_deref(), Some("Asia/Tokyo"));
+    let rows = app.query(meta.id, json!({ "sortKey": "logon_time" }));
+    assert_eq!(
+        cell(&rows.rows[0], "logon_time"),
+        "2024-03-10 15:30:00 +09:00"
+    );
+
+    let err = set_display_timezone(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "timezone": "Mars/Olympus" })),
+    )
+    .expect_err("unknown time zone is rejected");
+    assert!(err.to_string().contains("Mars/Olympus"));
+    let err = create_project(
+        app.state(),
+        payload(json!({ "path": source, "timestampColumns": ["logon"] })),
+    )
+    .expect_err("unknown timestamp column is rejected");
+    assert!(err.to_string().contains("logon"));
+}
+
+#[test]
+fn timeline_view_orders_and_windows_rows_by_time_column() {
+    let app = TestApp::new();
+    let source = app.root.join("events.csv");
+    fs::write(
+        &source,
+        "host,event_time,note\n\
+         WS01,2024-03-01 12:00:00,third\n\
+         WS02,2024-03-01T08:00:00+09:00,first\n\
+         WS03,2024-03-01 10:00:00,second\n\
+         WS04,,unknown\n",
+    )
+    .expect("write export");
+    let meta = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    )
+    .expect("project is created")
+    .project
+    .meta;
+    assert_eq!(meta.timestamp_column.as_deref(), Some("event_time"));
+
+    let notes = |response: &rows::QueryRowsResponse| -> Vec<String> {
+        response.rows.iter().map(|row| cell(row, "note")).collect()
+    };
+    let timeline = app.query(meta.id, json!({ "timeline": true, "sortKey": "host" }));
+    assert_eq!(timeline.time_column.as_deref(), Some("event_time"));
+    assert_eq!(notes(&timeline), ["first", "second", "third", "unknown"]);
+
+    let window = app.query(
+        meta.id,
+        json!({
+            "timeline": true,
+            "timeFrom": "2024-03-01 09:00:00",
+            "timeTo": "2024-03-01T21:00:00+09:00",
+        }),
+    );
+    assert_eq!(window.total_filtered_rows, 2);
+    assert_eq!(notes(&window), ["second", "third"]);
+
+    let err = set_timestamp_column(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "column": "missing" })),
+    )
+    .expect_err("unknown column is rejected");
+    assert!(err.to_string().contains("missing"));
+    let cleared = set_timestamp_column(app.state(), payload(json!({ "projectId": meta.id })))
+        .expect("time column is cleared");
+    assert_eq!(cleared.meta.timestamp_column, None);
+    let err = query_project_rows(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "timeline": true })),
+    )
+    .expect_err("timeline view needs a time column");
+    assert!(err.to_string().contains("time column"));
+}
+
+#[test]
+fn sessions_split_on_idle_gap_per_key() {
+    let app = TestApp::new();
+    let source = app.root.join("logons.csv");
+    fs::write(
+        &source,
+        "user,host,time\n\
+         alice,WS01,2024-03-01 10:00:00\n\
+         bob,WS01,2024-03-01 10:05:00\n\
+         alice,WS01,2024-03-01 10:20:00\n\
+         alice,WS01,2024-03-01 11:00:00\n\
+         alice,WS02,2024-03-01 10:25:00\n\
+         ,WS01,2024-03-01 10:30:00\n",
+    )
+    .expect("write export");
+    let meta = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    )
+    .expect("project is created")
+    .project
+    .meta;
+
+    let response = assign_sessions(
+        app.state(),
+        payload(json!({
+            "projectId": meta.id,
+            "keyColumns": ["user", "host"],
+            "gapSeconds": 1800,
+        })),
+    )
+    .expect("sessions are assigned");
+    assert_eq!(response.column, "session_id");
+    assert_eq!(response.sessions, 4);
+    assert_eq!(response.assigned_rows, 5);
+
+    let rows = app.query(meta.id, json!({}));
+    let sessions: Vec<Value> = rows
+        .rows
+        .iter()
+        .map(|row| row.data["session_id"].clone())
+        .collect();
+    assert_eq!(
+        sessions,
+        [
+            json!(1),
+            json!(2),
+            json!(1),
+            json!(4),
+            json!(3),
+            Value::Null
+        ]
+    );
+
+    let err = assign_sessions(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "keyColumns": ["account"] })),
+    )
+    .expect_err("unknown key column is rejected");
+    assert!(err.to_string().contains("account"));
+}
+
+#[test]
+fn dedupe_keeps_first_copy_and_carries_flags() {
+    let app = TestApp::new();
+    let source = app.root.join("merged.csv");
+    fs::write(
+        &source,
+        "host,user,process\n\
+         WS01,alice,cmd.exe\n\
+         WS02,bob,powershell.exe\n\
+         WS01,alice,cmd.exe\n\
+         WS03,carol,rundll32.exe\n\
+         WS01,alice,cmd.exe\n\
+         WS02,bob,powershell.exe\n",
+    )
+    .expect("write export");
+    let meta = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    )
+    .expect("project is created")
+    .project
+    .meta;
+
+    let found = find_duplicates(app.state(), payload(json!({ "projectId": meta.id })))
+        .expect("duplicates are found");
+    assert_eq!(found.columns, ["host", "user", "process"]);
+    assert_eq!(found.total_groups, 2);
+    assert_eq!(found.duplicate_rows, 3);
+    assert_eq!(found.groups[0].rows, [0, 2, 4]);
+    assert_eq!(found.groups[1].rows, [1, 5]);
+
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": meta.id,
+            "row_index": 5,
+            "flag": "critical",
+            "memo": "lateral movement"
+        })),
+    )
+    .expect("flag is stored");
+    let result = dedupe_project(app.state(), payload(json!({ "projectId": meta.id })))
+        .expect("project is deduplicated");
+    assert_eq!(result.removed_rows, 3);
+    assert_eq!(result.carried_annotations, 1);
+    assert_eq!(result.dropped_annotations, 0);
+    assert_eq!(result.project.meta.total_records, 3);
+    assert_eq!(result.project.meta.flagged_records, 1);
+
+    let critical = app.query(meta.id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+    assert_eq!(critical.rows[0].row_index, 1);
+    assert_eq!(cell(&critical.rows[0], "process"), "powershell.exe");
+    assert_eq!(critical.rows[0].memo.as_deref(), Some("lateral movement"));
+
+    let err = find_duplicates(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "columns": ["account"] })),
+    )
+    .expect_err("unknown column is rejected");
+    assert!(err.to_string().contains("account"));
+}
+
+#[test]
+fn rare_values_are_scored_across_columns() {
+    let app = TestApp::new();
+    let source = app.root.join("processes.csv");
+    fs::write(
+        &source,
+        "host,process,parent\n\
+         WS01,svchost.exe,services.exe\n\
+         WS02,svchost.exe,services.exe\n\
+         WS03,svchost.exe,services.exe\n\
+         WS01,notepad.exe,explorer.exe\n\
+         WS02,notepad.exe,explorer.exe\n\
+         WS03,svchost.exe,winword.exe\n\
+         WS01,psexesvc.exe,services.exe\n",
+    )
+    .expect("write export");
+    let meta = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    )
+    .expect("project is created")
+    .project
+    .meta;
+
+    let response = find_rare_values(
+        app.state(),
+        payload(json!({
+            "projectId": meta.id,
+            "columns": ["process", "parent"],
+            "addColumn": true,
+        })),
+    )
+    .expect("rare values are found");
+    assert_eq!(response.max_count, 1);
+    assert_eq!(response.total_rare_rows, 2);
+    let rare: Vec<(usize, usize)> = response
+        .rows
+        .iter()
+        .map(|row| (row.row_index, row.score))
+        .collect();
+    assert_eq!(rare, [(5, 1), (6, 1)]);
+    assert_eq!(response.columns[0].column, "process");
+    assert_eq!(response.columns[0].distinct_values, 3);
+    assert_eq!(response.columns[0].values[0].value, "psexesvc.exe");
+    assert_eq!(response.columns[1].values[0].value, "winword.exe");
+    assert_eq!(response.column.as_deref(), Some("rarity"));
+
+    let rows = app.query(meta.id, json!({}));
+    let scores: Vec<Value> = rows
+        .rows
+        .iter()
+        .map(|row| row.data["rarity"].clone())
+        .collect();
+    assert_eq!(
+        scores,
+        [
+            json!(4),
+            json!(4),
+            json!(4),
+            json!(2),
+            json!(2),
+            json!(1),
+            json!(1)
+        ]
+    );
+
+    let wider = find_rare_values(
+        app.state(),
+        payload(json!({ "projectId": meta.id, "columns": ["process"], "maxCount": 2 })),
+    )
+    .expect("threshold is applied");
+    assert_eq!(wider.total_rare_rows, 3);
+    assert_eq!(wider.rows[0].row_index, 6);
+    assert!(wider.column.is_none());
+}
+
+#[test]
+fn bookmarks_filter_rows_without_touching_flags() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    add_bookmarks(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rows": [4, 1] })),
+    )
+    .expect("rows are bookmarked");
+    let bookmarks = add_bookmarks(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rows": [4], "label": "check parent" })),
+    )
+    .expect("label is updated");
+    let rows: Vec<usize> = bookmarks
+        .iter()
+        .map(|bookmark| bookmark.row_index)
+        .collect();
+    assert_eq!(rows, [1, 4]);
+    assert_eq!(bookmarks[1].label.as_deref(), Some("check parent"));
+
+    let bookmarked = app.query(project_id, json!({ "bookmarkedOnly": true }));
+    assert_eq!(bookmarked.total_filtered_rows, 2);
+    assert_eq!(bookmarked.rows[0].row_index, 1);
+    assert_eq!(bookmarked.total_flagged, 0);
+    assert!(bookmarked.rows.iter().all(|row| row.flag.is_empty()));
+
+    let remaining = remove_bookmarks(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rows": [1] })),
+    )
+    .expect("bookmark is removed");
+    assert_eq!(remaining.len(), 1);
+    let listed = list_bookmarks(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("bookmarks are listed");
+    assert_eq!(listed[0].row_index, 4);
+
+    let err = add_bookmarks(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rows": [FIXTURE_ROWS] })),
+    )
+    .expect_err("out-of-range row is rejected");
+    assert!(err.to_string().contains("out of range"));
+}
+
+#[test]
+fn row_comments_accumulate_and_render_the_memo() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "suspicious",
+            "memo": "odd parent"
+        })),
+    )
+    .expect("flag is stored");
+    let added = add_row_comment(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "rowIndex": 2,
+            "text": "same hash on WS02",
+            "author": "alice"
+        })),
+    )
+    .expect("comment is added");
+    assert_eq!(added.comments.len(), 2);
+    assert_eq!(added.comments[0].text, "odd parent");
+    assert_eq!(added.comments[1].author.as_deref(), Some("alice"));
+    let memo = added.memo.expect("memo is rendered");
+    assert!(memo.contains("odd parent\n"));
+    assert!(memo.ends_with("alice: same hash on WS02"));
+
+    // Setting the flag keeps the comments and their summary.
+    let row = update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 2,
+            "flag": "critical",
+            "memo": "overwritten"
+        })),
+    )
+    .expect("flag is updated");
+    assert_eq!(row.memo.as_deref(), Some(memo.as_str()));
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.rows[0].memo.as_deref(), Some(memo.as_str()));
+
+    let remaining = delete_row_comment(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIndex": 2, "index": 0 })),
+    )
+    .expect("comment is deleted");
+    assert_eq!(remaining.comments.len(), 1);
+    let listed = list_row_comments(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIndex": 2 })),
+    )
+    .expect("comments are listed");
+    assert_eq!(listed.comments[0].text, "same hash on WS02");
+    assert!(!listed.memo.unwrap_or_default().contains("odd parent"));
+
+    let err = delete_row_comment(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIndex": 2, "index": 5 })),
+    )
+    .expect_err("missing comment is rejected");
+    assert!(err.to_string().contains("not found"));
+}
+
+#[test]
+fn hidden_columns_can_be_left_out_of_search_and_iocs() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let hide = |search_visible_only: Value| {
+        set_hidden_columns(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "hidden_columns": ["command_line"],
+                "searchVisibleOnly": search_visible_only,
+            })),
+        )
+        .expect("hidden columns are stored");
+    };
+
+    hide(Value::Null);
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+
+    hide(json!(true));
+    let meta = app.state().projects.find(&project_id).expect("project");
+    assert!(meta.search_visible_only);
+    assert_eq!(meta.ioc_applied_records, 0);
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 0);
+    let hidden_hit = app.query(project_id, json!({ "search": "sekurlsa" }));
+    assert_eq!(hidden_hit.total_filtered_rows, 0);
+    let visible_hit = app.query(project_id, json!({ "search": "mimikatz" }));
+    assert_eq!(visible_hit.total_filtered_rows, 1);
+
+    // Omitting the option keeps it.
+    hide(Value::Null);
+    let meta = app.state().projects.find(&project_id).expect("project");
+    assert!(meta.search_visible_only);
+}
+
+#[test]
+fn column_aliases_apply_to_search_and_exports() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let summary = set_column_aliases(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "aliases": { "src_ip": "Client  IP", "host": "" }
+        })),
+    )
+    .expect("aliases are stored");
+    assert_eq!(
+        summary.meta.column_aliases,
+        BTreeMap::from([("src_ip".to_string(), "Client IP".to_string())])
+    );
+
+    let hits = app.query(project_id, json!({ "search": "client_ip:10.0.0.9" }));
+    assert_eq!(hits.total_filtered_rows, 2);
+    let by_name = app.query(project_id, json!({ "search": "src_ip:10.0.0.9" }));
+    assert_eq!(by_name.total_filtered_rows, 2);
+
+    let destination = app.root.join("export").join("aliased.csv");
+    export_project(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": destination,
+            "search": "client_ip:192.168.1.20",
+        })),
+    )
+    .expect("export succeeds");
+    let mut reader = csv::Reader::from_path(&destination).expect("export is readable");
+    let headers = reader.headers().expect("export has headers").clone();
+    assert!(headers.iter().any(|header| header == "Client IP"));
+    assert!(!headers.iter().any(|header| header == "src_ip"));
+    assert_eq!(reader.records().count(), 1);
+
+    let clash = set_column_aliases(
+        app.state(),
+        payload(json!({ "projectId": project_id, "aliases": { "src_ip": "Host" } })),
+    );
+    assert!(clash.is_err());
+    let shared = set_column_aliases(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "aliases": { "src_ip": "Address", "user": "address" }
+        })),
+    );
+    assert!(shared.is_err());
+}
+
+#[test]
+fn computed_columns_evaluate_expressions() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let add = |column: &str, expression: &str| {
+        add_computed_column(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "column": column,
+                "expression": expression,
+            })),
+        )
+    };
+    add("elapsed", "timestamp - '2024-03-01T10:00:00Z'").expect("arithmetic column is added");
+    add("label", "upper(host) || ':' || substr(`process`, 1, 3)").expect("text column is added");
+    add("tool", "extract(process, '^([a-z]+)\\.exe$')").expect("extract column is added");
+
+    let rows = app.query(project_id, json!({})).rows;
+    let elapsed: Vec<Value> = rows.iter().map(|row| row.data["elapsed"].clone()).collect();
+    assert_eq!(elapsed, [0, 300, 390, 420, 720, 900].map(Value::from));
+    assert_eq!(rows[0].data["label"], json!("WS01:exp"));
+    assert_eq!(rows[2].data["tool"], json!("mimikatz"));
+    let hits = app.query(project_id, json!({ "search": "tool:robocopy" }));
+    assert_eq!(hits.total_filtered_rows, 1);
+
+    assert!(add("broken", "lower(host").is_err());
+    assert!(add("unknown", "missing_column * 2").is_err());
+    assert!(add("label", "host").is_err());
+}
+
+#[test]
+fn json_columns_expand_into_flat_columns() {
+    let app = TestApp::new();
+    let source = app.root.join("edr.csv");
+    fs::write(
+        &source,
+        concat!(
+            "host,event\n",
+            "WS01,\"{\"\"process\"\":{\"\"name\"\":\"\"cmd.exe\"\",\"\"pid\"\":42},\"\"user\"\":\"\"alice\"\"}\"\n",
+            "WS02,\"{\"\"process\"\":{\"\"name\"\":\"\"powershell.exe\"\"},\"\"user\"\":null}\"\n",
+            "WS03,not json\n",
+        ),
+    )
+    .expect("write export");
+    let project_id = create_project(app.state(), payload(json!({ "path": source })))
+        .expect("project is created")
+        .project
+        .meta
+        .id;
+
+    let listed = list_json_keys(
+        app.state(),
+        payload(json!({ "projectId": project_id, "source": "event" })),
+    )
+    .expect("keys are listed");
+    assert_eq!(listed.object_rows, 2);
+    let keys: Vec<(&str, usize)> = listed
+        .keys
+        .iter()
+        .map(|key| (key.key.as_str(), key.count))
+        .collect();
+    assert_eq!(keys, [("process.name", 2), ("user", 2), ("process.pid", 1)]);
+
+    let expanded = expand_json_column(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "source": "event",
+            "keys": ["process.name", "process.pid", "user"],
+        })),
+    )
+    .expect("keys are expanded");
+    assert_eq!(
+        expanded.columns,
+        ["event.process.name", "event.process.pid", "event.user"]
+    );
+    let rows = app.query(project_id, json!({})).rows;
+    assert_eq!(rows[0].data["event.process.pid"], json!("42"));
+    assert_eq!(rows[1].data["event.user"], Value::Null);
+    assert_eq!(rows[2].data["event.process.name"], Value::Null);
+    let hits = app.query(
+        project_id,
+        json!({ "search": "event.process.name:powershell" }),
+    );
+    assert_eq!(hits.total_filtered_rows, 1);
+
+    let again = expand_json_column(
+        app.state(),
+        payload(json!({ "projectId": project_id, "source": "event", "keys": ["user"] })),
+    );
+    assert!(again.is_err());
+}
+
+#[test]
+fn import_normalizes_values_before_typing_columns() {
+    let app = TestApp::new();
+    let source = app.root.join("proxy.csv");
+    fs::write(
+        &source,
+        concat!(
+            "host,user,bytes,ratio\n",
+            " WS01 ,Alice,\"1,234,567\",\"1,5\"\n",
+            "WS02,ALICE ,12,\"2,000.25\"\n",
+        ),
+    )
+    .expect("write export");
+    let response = create_project(
+        app.state(),
+        payload(json!({
+            "path": source,
+            "dtypes": { "bytes": "int64" },
+            "trimWhitespace": true,
+            "lowercaseColumns": ["user"],
+            "stripThousandsColumns": ["bytes", "ratio"],
+        })),
+    )
+    .expect("project is created");
+    let options = &response.project.meta.import_options;
+    assert!(options.trim_whitespace);
+    assert_eq!(options.lowercase_columns, ["user"]);
+
+    let rows = app.query(response.project.meta.id, json!({})).rows;
+    assert_eq!(rows[0].data["host"], json!("WS01"));
+    assert_eq!(rows[0].data["user"], rows[1].data["user"]);
+    assert_eq!(rows[0].data["bytes"], json!(1234567));
+    // Only numbers grouped in threes lose their separators.
+    assert_eq!(rows[0].data["ratio"], json!("1,5"));
+    assert_eq!(rows[1].data["ratio"], json!("2000.25"));
+
+    let missing = create_project(
+        app.state(),
+        payload(json!({ "path": source, "lowercaseColumns": ["account"] })),
+    );
+    assert!(missing.is_err());
+}
+
+#[test]
+fn column_descriptions_are_returned_with_the_project() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let describe = |column: &str, description: Value| {
+        set_column_description(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "column": column,
+                "description": description,
+            })),
+        )
+    };
+    describe("src_ip", json!("  Address the logon came from  ")).expect("description is stored");
+    describe("host", json!("Reporting endpoint")).expect("description is stored");
+
+    let loaded = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project loads");
+    let descriptions = &loaded.project.meta.column_descriptions;
+    assert_eq!(descriptions["src_ip"], "Address the logon came from");
+    assert_eq!(descriptions.len(), 2);
+
+    let cleared = describe("host", Value::Null).expect("description is removed");
+    assert!(!cleared.meta.column_descriptions.contains_key("host"));
+    assert!(describe("missing", json!("nothing here")).is_err());
+}
+
+#[test]
+fn default_view_is_applied_when_the_project_loads() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    save_view(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "name": "WS02 processes",
+            "search": "host:WS02",
+            "sortKey": "process",
+            "sortDirection": "desc",
+            "visibleColumns": ["host", "process"],
+            "isDefault": true
+        })),
+    )
+    .expect("view is saved");
+    let views = save_view(
+        app.state(),
+        payload(json!({ "projectId": project_id, "name": "Critical", "flagFilter": "critical" })),
+    )
+    .expect("second view is saved");
+    let names: Vec<&str> = views.iter().map(|view| view.name.as_str()).collect();
+    assert_eq!(names, ["Critical", "WS02 processes"]);
+    assert!(views[1].is_default);
+
+    let loaded = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project loads");
+    assert_eq!(
+        loaded.view.map(|view| view.name).as_deref(),
+        Some("WS02 processes")
+    );
+    assert_eq!(loaded.hidden_columns.len(), 4);
+    assert!(!loaded.hidden_columns.contains(&"process".to_string()));
+    assert_eq!(loaded.initial_rows.len(), 2);
+    assert_eq!(cell(&loaded.initial_rows[0], "process"), "mimikatz.exe");
+
+    let invalid = save_view(
+        app.state(),
+        payload(json!({ "projectId": project_id, "name": "Broken", "sortKey": "missing" })),
+    );
+    assert!(invalid.is_err());
+
+    let views = set_default_view(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("default is cleared");
+    assert!(views.iter().all(|view| !view.is_default));
+    let views = delete_view(
+        app.state(),
+        payload(json!({ "projectId": project_id, "name": "Critical" })),
+    )
+    .expect("view is deleted");
+    assert_eq!(views.len(), 1);
+    let loaded = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project loads");
+    assert!(loaded.view.is_none());
+    assert_eq!(loaded.initial_rows.len(), FIXTURE_ROWS);
+}
+
+#[test]
+fn column_widths_are_sampled_unless_exact_widths_are_set() {
+    use polars::prelude::*;
+
+    let df = df!("value" => ["a", "bb", "ccc", "dddddddddddd"]).expect("frame builds");
+    let sampled = crate::storage::compute_column_max_chars(&df, Some(2));
+    assert_eq!(
+        sampled["value"], 5,
+        "rows 0 and 2 and the header are measured"
+    );
+    let exact = crate::storage::compute_column_max_chars(&df, None);
+    assert_eq!(exact["value"], 12);
+
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let metrics_path = app
+        .state()
+        .projects
+        .project_dir(&project_id)
+        .join(crate::storage::COLUMN_METRICS_FILE);
+    let _ = fs::remove_file(&metrics_path);
+    let loaded = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project loads");
+    assert_eq!(loaded.column_max_chars["command_line"], 41);
+    assert!(!metrics_path.exists(), "sampled widths are not stored");
+
+    set_performance_settings(app.state(), payload(json!({ "exactColumnWidths": true })))
+        .expect("settings are saved");
+    load_project(app.state(), payload(json!({ "projectId": project_id }))).expect("project loads");
+    assert!(metrics_path.exists(), "exact widths are stored");
+}
+
+#[test]
+fn settings_change_page_size_date_format_and_export_encoding() {
+    let app = TestApp::new();
+    let source = app.root.join("logons.csv");
+    fs::write(
+        &source,
+        "host,logon_time\nWS01,2024-03-10 01:30:00\nWS02,2024-03-10 12:00:00\nWS03,2024-03-11 08:15:00\n",
+    )
+    .expect("write source");
+    let project_id = create_project(
+        app.state(),
+        payload(json!({ "path": source, "timestampColumns": ["logon_time"] })),
+    )
+    .expect("project is created")
+    .project
+    .meta
+    .id;
+
+    let settings = set_settings(
+        app.state(),
+        payload(json!({
+            "pageSize": 2,
+            "dateFormat": "%d/%m/%Y %H:%M",
+            "exportEncoding": "utf-8-bom"
+        })),
+    )
+    .expect("settings are saved");
+    assert_eq!(settings.page_size, 2);
+    assert!(settings.data_root.configured_path.is_none());
+
+    let rows = query_project_rows(
+        app.state(),
+        payload(json!({ "projectId": project_id, "sortKey": "logon_time" })),
+    )
+    .expect("query succeeds");
+    assert_eq!(rows.rows.len(), 2);
+    assert_eq!(cell(&rows.rows[0], "logon_time"), "10/03/2024 01:30");
+
+    let destination = app.root.join("logons-export.csv");
+    export_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "destination": destination })),
+    )
+    .expect("export succeeds");
+    let bytes = fs::read(&destination).expect("export is readable");
+    assert!(bytes.starts_with(b"\xEF\xBB\xBF"));
+
+    let invalid = set_settings(app.state(), payload(json!({ "dateFormat": "%Q" })));
+    assert!(invalid.is_err());
+    let current = get_settings(app.state()).expect("settings are returned");
+    assert_eq!(current.page_size, 2);
+    assert_eq!(current.date_format.as_deref(), Some("%d/%m/%Y %H:%M"));
+}
+
+#[test]
+fn corrupt_project_index_is_recovered_from_backup() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    set_project_pinned(
+        app.state(),
+        payload(json!({ "projectId": project_id, "pinned": true })),
+    )
+    .expect("project is pinned");
+    assert!(app.root.join("projects.json.bak").exists());
+    assert!(!app.root.join("projects.json.tmp").exists());
+
+    let index_path = app.root.join("projects.json");
+    fs::write(&index_path, "[{\"id\": ").expect("index is truncated");
+    let reopened = AppState::from_root(app.root.clone()).expect("state reopens");
+    assert!(reopened.projects.find(&project_id).is_some());
+    let index: Value =
+        serde_json::from_slice(&fs::read(&index_path).expect("index exists")).expect("index");
+    assert_eq!(index.as_array().map(Vec::len), Some(1));
+}
+
+#[test]
+fn busy_projects_reject_conflicting_commands() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let state = app.state();
+    let reading = state.locks.read(&project_id).expect("read lock");
+    let err = dedupe_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("rewrite waits for reads");
+    assert_eq!(err.code(), "project_busy");
+    assert_eq!(err.context().project_id, Some(project_id));
+    let status = get_project_status(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("status is returned");
+    assert!(!status.busy);
+    assert_eq!(status.active_reads, 1);
+    assert_eq!(app.query(project_id, json!({})).rows.len(), FIXTURE_ROWS);
+    drop(reading);
+
+    let rewriting = state
+        .locks
+        .rewrite(&project_id, "reimport_project")
+        .expect("rewrite lock");
+    let err = query_project_rows(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("reads wait for the rewrite");
+    assert_eq!(err.code(), "project_busy");
+    assert!(err.to_string().contains("reimport_project"));
+    let status = get_project_status(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("status is returned");
+    assert!(status.busy);
+    assert_eq!(status.operation, Some("reimport_project"));
+    drop(rewriting);
+
+    let status = get_project_status(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("status is returned");
+    assert!(!status.busy && status.active_reads == 0);
+    dedupe_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("rewrite runs once the project is free");
+}
+
+#[test]
+fn annotation_edits_wait_for_rewrites() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let state = app.state();
+    let flag = || {
+        update_flag(
+            app.state(),
+            payload(
+                json!({ "projectId": project_id, "row_index": 2, "flag": "critical", "memo": null }),
+            ),
+        )
+    };
+
+    let rewriting = state
+        .locks
+        .rewrite(&project_id, "dedupe_project")
+        .expect("rewrite lock");
+    let err = flag().expect_err("flags wait for the rewrite");
+    assert_eq!(err.code(), "project_busy");
+    assert!(err.to_string().contains("dedupe_project"));
+    let err = add_row_comment(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIndex": 2, "text": "dumped LSASS" })),
+    )
+    .expect_err("comments wait for the rewrite");
+    assert_eq!(err.code(), "project_busy");
+    let err = list_row_comments(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIndex": 2 })),
+    )
+    .expect_err("comments are not read during the rewrite");
+    assert_eq!(err.code(), "project_busy");
+    let err = save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" }]
+        })),
+    )
+    .expect_err("IOC rules wait for the rewrite");
+    assert_eq!(err.code(), "project_busy");
+    drop(rewriting);
+
+    let reading = state.locks.read(&project_id).expect("read lock");
+    assert_eq!(
+        flag().expect_err("flags wait for reads").code(),
+        "project_busy"
+    );
+    drop(reading);
+    flag().expect("flag is stored once the project is free");
+    let meta = state.projects.find(&project_id).expect("project exists");
+    assert_eq!(meta.flagged_records, 1);
+    assert_eq!(meta.ioc_applied_records, 0, "no rule was saved while busy");
+}
+
+#[test]
+fn flag_ioc_and_metadata_changes_are_broadcast() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let events = Arc::new(Mutex::new(Vec::new()));
+    let captured = Arc::clone(&events);
+    app.state().changes.set_listener(Box::new(move |event| {
+        let value = serde_json::to_value(event).expect("event serializes");
+        captured.lock().unwrap().push((event.name(), value));
+    }));
+
+    update_flag(
+        app.state(),
+        payload(
+            json!({ "projectId": project_id, "row_index": 1, "flag": "suspicious", "memo": null }),
+        ),
+    )
+    .expect("flag is updated");
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [{ "flag": "critical", "tag": "Mimikatz", "query": "sekurlsa" }]
+        })),
+    )
+    .expect("IOC rules are saved");
+    set_project_pinned(
+        app.state(),
+        payload(json!({ "projectId": project_id, "pinned": true })),
+    )
+    .expect("project is pinned");
+
+    let events = events.lock().unwrap();
+    let flag = events
+        .iter()
+        .find(|(name, _)| *name == "flag-updated")
+        .expect("flag change is reported");
+    assert_eq!(flag.1["project_id"], json!(project_id));
+    assert_eq!(flag.1["row_indices"], json!([1]));
+    assert!(events.iter().any(|(name, value)| {
+        *name == "iocs-updated" && value["project_id"] == json!(project_id)
+    }));
+    let (_, last) = events.last().expect("events were sent");
+    assert_eq!(last["project"]["id"], json!(project_id));
+    assert_eq!(last["project"]["pinned"], json!(true));
+}
+
+#[test]
+fn encrypted_projects_are_sealed_until_opened_with_the_passphrase() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    update_flag(
+        app.state(),
+        payload(
+            json!({ "projectId": project_id, "row_index": 2, "flag": "critical", "memo": null }),
+        ),
+    )
+    .expect("flag is updated");
+    save_case_notes(
+        app.state(),
+        payload(json!({ "projectId": project_id, "text": "lateral movement from WS02" })),
+    )
+    .expect("case notes are saved");
+
+    let err = enable_project_encryption(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": "short" })),
+    )
+    .expect_err("short passphrases are rejected");
+    assert_eq!(err.code(), "invalid_request");
+    let summary = enable_project_encryption(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": "correct horse battery" })),
+    )
+    .expect("project is encrypted");
+    assert!(summary.meta.encryption.is_some());
+    assert!(!project_dir.join("data.parquet").exists());
+    let sealed = fs::read(project_dir.join("data.parquet.enc")).expect("sealed data exists");
+    assert!(!sealed.windows(4).any(|window| window == b"PAR1"));
+    assert!(!project_dir.join("case_notes.json").exists());
+    assert!(project_dir.join("case_notes.json.enc").exists());
+
+    let err = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("sealed project needs the passphrase");
+    assert_eq!(err.code(), "passphrase_required");
+    let err = set_hidden_columns(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "hidden_columns": ["user"],
+            "searchVisibleOnly": true
+        })),
+    )
+    .expect_err("sealed projects keep their hidden columns");
+    assert_eq!(err.code(), "passphrase_required");
+    let meta = app
+        .state()
+        .projects
+        .find(&project_id)
+        .expect("project exists");
+    assert!(meta.hidden_columns.is_empty());
+    let err = export_project_bundle(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "destination": app.root.join("export").join("sealed.trivium")
+        })),
+    )
+    .expect_err("sealed projects are not bundled");
+    assert_eq!(err.code(), "passphrase_required");
+    let err = load_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": "wrong horse battery" })),
+    )
+    .expect_err("wrong passphrase is rejected");
+    assert_eq!(err.code(), "passphrase_incorrect");
+    assert!(project_dir.join("data.parquet.enc").exists());
+
+    load_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": "correct horse battery" })),
+    )
+    .expect("project opens with its passphrase");
+    let flagged = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(flagged.total_filtered_rows, 1);
+    assert_eq!(flagged.rows[0].row_index, 2);
+    let notes = get_case_notes(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("case notes are readable");
+    assert_eq!(notes.text, "lateral movement from WS02");
+
+    // A crash leaves the files unsealed and forgets the key.
+    app.state().unlocked.take_all();
+    let err = query_project_rows(
+        app.state(),
+        payload(json!({ "projectId": project_id, "limit": 100 })),
+    )
+    .expect_err("unsealed leftovers are not served without the passphrase");
+    assert_eq!(err.code(), "passphrase_required");
+    let err = get_memo_term_frequencies(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("unsealed leftovers are not served without the passphrase");
+    assert_eq!(err.code(), "passphrase_required");
+    let err = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect_err("unsealed leftovers still need the passphrase");
+    assert_eq!(err.code(), "passphrase_required");
+    load_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": "correct horse battery" })),
+    )
+    .expect("project opens with its passphrase");
+
+    lock_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is sealed again");
+    assert!(!project_dir.join("data.parquet").exists());
+    disable_project_encryption(
+        app.state(),
+        payload(json!({ "projectId": project_id, "passphrase": "correct horse battery" })),
+    )
+    .expect("encryption is turned off");
+    assert!(!project_dir.join("data.parquet.enc").exists());
+    let loaded = load_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("plain project opens without a passphrase");
+    assert!(loaded.project.meta.encryption.is_none());
+}
+
+#[test]
+fn compare_rows_reports_differing_columns() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let diff = compare_rows(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIdA": 0, "rowIdB": 1 })),
+    )
+    .expect("rows compare");
+    let differing: Vec<&str> = diff
+        .columns
+        .iter()
+        .filter(|column| !column.equal)
+        .map(|column| column.column.as_str())
+        .collect();
+    assert_eq!(differing, ["timestamp", "process", "command_line"]);
+    assert_eq!(diff.different_columns, 3);
+
+    let out_of_range = compare_rows(
+        app.state(),
+        payload(json!({ "projectId": project_id, "rowIdA": 0, "rowIdB": FIXTURE_ROWS })),
+    );
+    assert!(out_of_range.is_err());
+}
+
+#[test]
+fn delete_project_removes_data() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let project_dir = app.state().projects.project_dir(&project_id);
+    assert!(project_dir.exists());
+
+    delete_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is deleted");
+    assert!(!project_dir.exists());
+    assert!(list_projects(app.state(), None)
+        .expect("projects are listed")
+        .is_empty());
+}
+
+#[test]
+fn ioc_csv_import_reads_headers_and_column_mapping() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let write_csv = |name: &str, contents: &str| {
+        let path = app.root.join(name);
+        fs::write(&path, contents).expect("IOC CSV is written");
+        path
+    };
+    let import = |path: &Path, mapping: Value| {
+        import_iocs(
+            app.state(),
+            payload(json!({ "projectId": project_id, "path": path, "mapping": mapping })),
+        )
+    };
+
+    let feed = write_csv(
+        "feed.csv",
+        "Indicator,Severity,Comment,Name\nsekurlsa,critical,LSASS access,Mimikatz\n",
+    );
+    let entries = import(&feed, json!({})).expect("aliased headers import");
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].query, "sekurlsa");
+    assert_eq!(entries[0].flag, "critical");
+    assert_eq!(entries[0].tag, "Mimikatz");
+    assert_eq!(entries[0].description.as_deref(), Some("LSASS access"));
+
+    let export = write_csv(
+        "export.csv",
+        "ioc_value,severity,rule_name\nhost:ws02,suspicious,WS02\n",
+    );
+    assert!(import(&export, json!({})).is_err(), "no query column");
+    let entries = import(&export, json!({ "query": "ioc_value", "tag": "Rule Name" }))
+        .expect("mapped columns import");
+    assert_eq!(entries[0].query, "host:ws02");
+    assert_eq!(entries[0].flag, "suspicious");
+    assert_eq!(entries[0].tag, "WS02");
+    assert!(import(&export, json!({ "query": "missing" })).is_err());
+    assert!(import(&export, json!({ "severity": "ioc_value" })).is_err());
+
+    let positional = write_csv(
+        "positional.csv",
+        "critical,Mimikatz,sekurlsa\nsuspicious,WS02,host:ws02\n",
+    );
+    let entries = import(&positional, json!({})).expect("headerless CSV imports");
+    assert_eq!(entries.len(), 2, "the first row is a rule, not a header");
+}
+
+#[test]
+fn misp_exports_become_ioc_entries() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    let event = json!({
+        "id": "42",
+        "info": "Credential theft campaign",
+        "Orgc": { "name": "CERT-EU" },
+        "Attribute": [
+            { "type": "ip-src|port", "value": "10.0.0.5|4444", "to_ids": true },
+            {
+                "type": "filename|sha256",
+                "value": "evil.exe|9f86d081884c7d65",
+                "comment": "Dropper"
+            },
+            { "type": "comment", "value": "no indicator" }
+        ],
+        "Object": [{ "Attribute": [{ "type": "hostname", "value": "WS01", "to_ids": true }] }]
+    });
+    let shapes = [
+        ("bare.json", event.clone()),
+        ("wrapped.json", json!({ "Event": event })),
+        ("search.json", json!({ "response": [{ "Event": event }] })),
+    ];
+
+    for (name, export) in shapes {
+        let path = app.root.join(name);
+        fs::write(&path, export.to_string()).expect("MISP export is written");
+        let entries = import_iocs(
+            app.state(),
+            payload(json!({ "projectId": project_id, "path": path })),
+        )
+        .unwrap_or_else(|err| panic!("{name} imports: {err:?}"));
+        assert_eq!(entries.len(), 3, "{name}: the comment attribute is skipped");
+        let entry = |tag: &str| {
+            entries
+                .iter()
+                .find(|entry| entry.tag == tag)
+                .unwrap_or_else(|| panic!("{name}: missing {tag}"))
+        };
+
+        let address = entry("MISP-42:ip-src|port");
+        assert_eq!(address.query, "10.0.0.5", "{name}: the port is split off");
+        assert_eq!(address.kind, crate::models::IocKind::Ip);
+        assert_eq!(address.flag, "critical", "{name}: to_ids is critical");
+        assert_eq!(address.columns, vec!["src_ip".to_string()]);
+        assert_eq!(
+            address.description.as_deref(),
+            Some("Credential theft campaign")
+        );
+        assert_eq!(address.author.as_deref(), Some("CERT-EU"));
+
+        let hash = entry("MISP-42:filename|sha256");
+        assert_eq!(
+            hash.query, "\"9f86d081884c7d65\"",
+            "{name}: the hash is kept"
+        );
+        assert_eq!(hash.flag, "suspicious");
+        assert!(
+            hash.columns.is_empty(),
+            "{name}: no hash column in the project"
+        );
+        assert_eq!(hash.description.as_deref(), Some("Dropper"));
+
+        let host = entry("MISP-42:hostname");
+        assert_eq!(host.query, "\"WS01\"", "{name}: object attributes are read");
+        assert_eq!(host.columns, vec!["host".to_string()]);
+    }
+}
+
+#[test]
+fn sigma_rules_become_ioc_entries() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let response = import_sigma_rules(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "paths": [fixture_path("sigma_credential_dumping.yml")],
+            "mapping": {
+                "Image": ["process"],
+                "CommandLine": ["command_line"],
+                "Computer": ["host"]
+            }
+        })),
+    )
+    .expect("Sigma rules import");
+    assert_eq!(response.imported, 1);
+    assert_eq!(response.skipped.len(), 1);
+    assert_eq!(response.entries[0].flag, "critical");
+    assert_eq!(response.entries[0].tag, "Credential Dumping Tools");
+
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+    assert_eq!(cell(&critical.rows[0], "user"), "bob");
+}
+
+#[test]
+fn safe_rules_suppress_hits_under_allowlist_policy() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "critical", "tag": "Dumping", "query": "sekurlsa" },
+                { "flag": "safe", "tag": "Lab host", "query": "host:WS02" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+
+    set_ioc_policy(
+        app.state(),
+        payload(json!({ "projectId": project_id, "policy": "safe_suppresses" })),
+    )
+    .expect("policy is stored");
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 0);
+    let safe = app.query(
+        project_id,
+        json!({ "flagFilter": "safe", "search": "mimikatz" }),
+    );
+    assert_eq!(safe.total_filtered_rows, 1);
+    let memo = safe.rows[0].memo.clone().unwrap_or_default();
+    assert_eq!(memo, "[Lab host]");
+}
+
+#[test]
+fn equivalent_ioc_rules_are_merged_on_save() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+
+    let response = save_iocs(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "entries": [
+                { "flag": "suspicious", "tag": "Dumping", "query": "sekurlsa mimikatz" },
+                { "flag": "critical", "tag": "Credential theft", "query": "MIMIKATZ  sekurlsa" },
+                { "flag": "suspicious", "tag": "Internal", "query": "10.0.0.0-10.0.0.255", "kind": "ip" },
+                { "flag": "suspicious", "tag": "Internal", "query": "10.0.0.0/24", "kind": "ip" }
+            ]
+        })),
+    )
+    .expect("IOC rules are saved");
+    assert_eq!(response.entries.len(), 2);
+    assert_eq!(response.collapsed.len(), 2);
+    assert!(response.collapsed.iter().all(|item| item.removed == 1));
+
+    let merged = response
+        .entries
+        .iter()
+        .find(|entry| entry.query.contains("sekurlsa"))
+        .expect("query rule is kept");
+    assert_eq!(merged.flag, "critical");
+    assert_eq!(merged.tag, "Dumping / Credential theft");
+}
+
+#[test]
+fn jsonl_import_flattens_nested_objects() {
+    let app = TestApp::new();
+    let summary = create_project(
+        app.state(),
+        payload(json!({ "path": fixture_path("events.jsonl"), "description": null })),
+    )
+    .expect("JSON Lines project is created")
+    .project;
+    assert_eq!(summary.meta.total_records, 3);
+
+    let loaded = load_project(
+        app.state(),
+        payload(json!({ "projectId": summary.meta.id })),
+    )
+    .expect("project loads");
+    for column in ["host.name", "host.ip", "process.pid", "user", "elevated"] {
+        assert!(
+            loaded.columns.iter().any(|name| name == column),
+            "{}",
+            column
+        );
+    }
+
+    let hits = app.query(summary.meta.id, json!({ "search": "host.name:WS01" }));
+    assert_eq!(hits.total_filtered_rows, 2);
+}
+
+#[test]
+fn xlsx_import_reads_selected_sheet() {
+    let app = TestApp::new();
+    let workbook = fixture_path("logons.xlsx");
+
+    let sheets = list_sheets(payload(json!({ "path": workbook }))).expect("sheets are listed");
+    assert_eq!(sheets, ["Summary", "Logons"]);
+
+    let summary = create_project(
+        app.state(),
+        payload(json!({ "path": workbook, "description": null, "sheet": "Logons" })),
+    )
+    .expect("workbook project is created")
+    .project;
+    assert_eq!(summary.meta.total_records, 3);
+
+    let rows = app.query(summary.meta.id, json!({ "sortKey": "time" }));
+    assert_eq!(cell(&rows.rows[0], "time"), "2024-05-01 10:30:00");
+    assert_eq!(cell(&rows.rows[1], "user"), "bob (admin)");
+    assert_eq!(cell(&rows.rows[2], "source & notes"), "vpn-gw");
+
+    let missing = create_project(
+        app.state(),
+        payload(json!({ "path": workbook, "description": null, "sheet": "Nope" })),
+    );
+    assert!(missing.is_err());
+}
+
+#[test]
+fn parquet_and_arrow_imports_keep_dtypes() {
+    use polars::prelude::*;
+
+    let app = TestApp::new();
+    fs::create_dir_all(&app.root).expect("root exists");
+    let timestamps = Series::new("ts", [1_714_559_400_000i64, 1_714_563_000_000])
+        .cast(&DataType::Datetime(TimeUnit::Milliseconds, None))
+        .expect("datetime cast");
+    let mut df = DataFrame::new(vec![
+        timestamps,
+        Series::new("account_id", ["007", "042"]),
+        Series::new("bytes", [1_024i64, 2_048]),
+    ])
+    .expect("frame builds");
+
+    let parquet_path = app.root.join("prepared.parquet");
+    ParquetWriter::new(fs::File::create(&parquet_path).expect("parquet file"))
+        .finish(&mut df)
+        .expect("parquet written");
+    let arrow_path = app.root.join("prepared.arrow");
+    IpcWriter::new(fs::File::create(&arrow_path).expect("arrow file"))
+        .finish(&mut df)
+        .expect("arrow written");
+
+    for path in [parquet_path, arrow_path] {
+        let summary = create_project(
+            app.state(),
+            payload(json!({ "path": path, "description": null })),
+        )
+        .expect("columnar project is created")
+        .project;
+        assert_eq!(summary.meta.total_records, 2);
+
+        let rows = app.query(
+            summary.meta.id,
+            json!({ "sortKey": "bytes", "sortDirection": "desc" }),
+        );
+        assert_eq!(rows.rows[0].data.get("bytes"), Some(&json!(2_048)));
+        assert_eq!(cell(&rows.rows[0], "account_id"), "042");
+        assert!(cell(&rows.rows[1], "ts").starts_with("2024-05-01 10:30:00"));
+    }
+}
+
+#[test]
+fn evtx_import_extracts_system_and_event_data() {
+    let app = TestApp::new();
+    let summary = create_project(
+        app.state(),
+        payload(json!({ "path": fixture_path("security.evtx"), "description": null })),
+    )
+    .expect("event log project is created")
+    .project;
+    assert_eq!(summary.meta.total_records, 3);
+
+    let loaded = load_project(
+        app.state(),
+        payload(json!({ "projectId": summary.meta.id })),
+    )
+    .expect("project loads");
+    assert_eq!(
+        &loaded.columns[..4],
+        ["TimeCreated", "EventRecordID", "EventID", "Level"]
+    );
+    for column in ["Provider", "Computer", "TargetUserName", "SubjectUserSid"] {
+        assert!(
+            loaded.columns.iter().any(|name| name == column),
+            "{}",
+            column
+        );
+    }
+    assert!(!loaded.columns.iter().any(|name| name == "Message"));
+
+    let hits = app.query(summary.meta.id, json!({ "search": "TargetUserName:bob" }));
+    assert_eq!(hits.total_filtered_rows, 1);
+    let row = &hits.rows[0];
+    assert_eq!(cell(row, "TimeCreated"), "2024-05-01T10:31:10.0000000Z");
+    assert_eq!(cell(row, "IpAddress"), "203.0.113.7");
+    assert_eq!(
+        row.data.get("EventID").and_then(|value| value.as_i64()),
+        Some(4625)
+    );
+}
+
+#[test]
+fn malformed_evtx_logs_fail_cleanly_or_keep_readable_records() {
+    let app = TestApp::new();
+    let fixture = fs::read(fixture_path("security.evtx")).expect("fixture is readable");
+    let import = |name: &str, data: &[u8]| {
+        let path = app.root.join(name);
+        fs::write(&path, data).expect("log is written");
+        create_project(app.state(), payload(json!({ "path": path })))
+    };
+
+    let mut bad_header = fixture.clone();
+    bad_header[..8].copy_from_slice(b"NotElf\0\0");
+    assert!(import("bad-header.evtx", &bad_header).is_err());
+    assert!(import("short.evtx", &fixture[..1024]).is_err());
+    let mut bad_chunk = fixture.clone();
+    bad_chunk[4096..4104].copy_from_slice(b"NotChnk\0");
+    assert!(import("bad-chunk.evtx", &bad_chunk).is_err());
+
+    // The fixture's records start 512, 1965 and 2453 bytes into its chunk;
+    // cut inside the second, as when a log is copied while being written.
+    let truncated =
+        import("truncated.evtx", &fixture[..4096 + 2100]).expect("records before the cut are read");
+    assert_eq!(truncated.project.meta.total_records, 1);
+
+    // Replace the third record's body with elements nested past the limit;
+    // the first element defines the name `a` inline, the others refer to it.
+    let body = 4096 + 2453 + 24;
+    let name_offset: u32 = 2453 + 24 + 4 + 11;
+    let mut nested_xml = vec![0x0f, 0x01, 0x01, 0x00];
+    for level in 0..40 {
+        nested_xml.extend_from_slice(&[0x01, 0xff, 0xff, 0, 0, 0, 0]);
+        nested_xml.extend_from_slice(&name_offset.to_le_bytes());
+        if level == 0 {
+            nested_xml.extend_from_slice(&[0, 0, 0, 0, 0, 0, 1, 0, b'a', 0, 0, 0]);
+        }
+        nested_xml.push(0x02);
+    }
+    let mut nested = fixture.clone();
+    nested[body..body + nested_xml.len()].copy_from_slice(&nested_xml);
+    let nested = import("nested.evtx", &nested).expect("the other records are read");
+    assert_eq!(nested.project.meta.total_records, 2);
+}
+
+#[test]
+fn rendered_event_exports_share_evtx_columns() {
+    let app = TestApp::new();
+
+    for fixture in ["security.xml", "winlogbeat.ndjson"] {
+        let summary = create_project(
+            app.state(),
+            payload(json!({ "path": fixture_path(fixture), "description": null })),
+        )
+        .expect("event export project is created")
+        .project;
+        assert_eq!(summary.meta.total_records, 2);
+
+        let loaded = load_project(
+            app.state(),
+            payload(json!({ "projectId": summary.meta.id })),
+        )
+        .expect("project loads");
+        for column in ["TimeCreated", "EventID", "Channel", "Provider", "Message"] {
+            assert!(
+                loaded.columns.iter().any(|name| name == column),
+                "{} in {}",
+                column,
+                fixture
+            );
+        }
+        assert!(!loaded.columns.iter().any(|name| name.starts_with("agent.")));
+
+        let hits = app.query(summary.meta.id, json!({ "search": "TargetUserName:bob" }));
+        assert_eq!(hits.total_filtered_rows, 1);
+        let row = &hits.rows[0];
+        assert_eq!(cell(row, "IpAddress"), "203.0.113.7");
+        assert_eq!(
+            row.data.get("EventID").and_then(|value| value.as_i64()),
+            Some(4625)
+        );
+    }
+}
+
+#[test]
+fn sqlite_import_reads_selected_table() {
+    let app = TestApp::new();
+    let database = fixture_path("history.sqlite");
+
+    let tables =
+        list_sqlite_tables(payload(json!({ "path": database }))).expect("tables are listed");
+    assert_eq!(tables, ["downloads", "urls", "frequent"]);
+
+    let summary = create_project(
+        app.state(),
+        payload(json!({ "path": database, "description": null, "table": "urls" })),
+    )
+    .expect("database project is created")
+    .project;
+    assert_eq!(summary.meta.total_records, 3);
+
+    let hits = app.query(summary.meta.id, json!({ "search": "paste.example" }));
+    assert_eq!(hits.total_filtered_rows, 1);
+    let row = &hits.rows[0];
+    assert_eq!(cell(row, "favicon"), "89504e47");
+    assert_eq!(
+        row.data.get("visit_count").and_then(|value| value.as_i64()),
+        Some(1)
+    );
+
+    let missing = create_project(
+        app.state(),
+        payload(json!({ "path": database, "description": null, "table": "cookies" })),
+    );
+    assert!(missing.is_err());
+}
+
+#[test]
+fn glob_import_merges_files_with_source_column() {
+    let app = TestApp::new();
+    let daily = app.root.join("daily");
+    fs::create_dir_all(&daily).expect("daily dir");
+    for day in ["2024-05-01.csv", "2024-05-02.csv"] {
+        fs::copy(fixture_path(FIXTURE_EVENTS), daily.join(day)).expect("copy fixture");
+    }
+    let pattern = daily.join("*.csv").to_string_lossy().into_owned();
+
+    let summary = create_project(
+        app.state(),
+        payload(json!({ "path": "", "paths": [pattern], "description": null })),
+    )
+    .expect("merged project is created")
+    .project;
+    assert_eq!(summary.meta.total_records, FIXTURE_ROWS * 2);
+
+    let loaded = load_project(
+        app.state(),
+        payload(json!({ "projectId": summary.meta.id })),
+    )
+    .expect("project loads");
+    assert!(loaded.columns.iter().any(|name| name == "__source_file"));
+
+    let hits = app.query(
+        summary.meta.id,
+        json!({ "search": "__source_file:2024-05-02.csv" }),
+    );
+    assert_eq!(hits.total_filtered_rows, FIXTURE_ROWS);
+
+    let missing = daily.join("*.jsonl").to_string_lossy().into_owned();
+    let err = create_project(
+        app.state(),
+        payload(json!({ "path": missing, "description": null })),
+    )
+    .expect_err("unmatched pattern is rejected");
+    assert!(err.to_string().contains("no files match"), "{}", err);
+}
+
+#[test]
+fn reimport_carries_flags_to_matching_rows() {
+    let app = TestApp::new();
+    let source = app.root.join("export.csv");
+    fs::copy(fixture_path(FIXTURE_EVENTS), &source).expect("copy fixture");
+    let summary = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    )
+    .expect("project is created")
+    .project;
+    let project_id = summary.meta.id;
+    assert_eq!(summary.meta.sources.len(), 1);
+    assert_eq!(summary.meta.sources[0].sha256.len(), 64);
+
+    for (row_index, flag) in [(0, "safe"), (2, "critical")] {
+        update_flag(
+            app.state(),
+            payload(json!({
+                "projectId": project_id,
+                "row_index": row_index,
+                "flag": flag,
+                "memo": "reviewed"
+            })),
+        )
+        .expect("flag is stored");
+    }
+
+    // The corrected export drops the first event and appends a new one.
+    let original = fs::read_to_string(&source).expect("read export");
+    let mut lines: Vec<&str> = original.lines().collect();
+    lines.remove(1);
+    lines.push("2024-03-01T10:20:00Z,WS03,carol,rundll32.exe,rundll32.exe evil.dll,10.0.0.12");
+    fs::write(&source, lines.join("\n")).expect("rewrite export");
+
+    let result = reimport_project(app.state(), payload(json!({ "projectId": project_id })))
+        .expect("project is re-imported");
+    assert_eq!(result.matched_rows, FIXTURE_ROWS - 1);
+    assert_eq!(result.added_rows, 1);
+    assert_eq!(result.removed_rows, 1);
+    assert_eq!(result.carried_annotations, 1);
+    assert_eq!(result.dropped_annotations, 1);
+    assert_eq!(result.project.meta.total_records, FIXTURE_ROWS);
+    assert_eq!(result.project.meta.flagged_records, 1);
+    assert_ne!(result.project.meta.sources, summary.meta.sources);
+
+    let critical = app.query(project_id, json!({ "flagFilter": "critical" }));
+    assert_eq!(critical.total_filtered_rows, 1);
+    assert_eq!(critical.rows[0].row_index, 1);
+    assert_eq!(cell(&critical.rows[0], "process"), "mimikatz.exe");
+    assert_eq!(critical.rows[0].memo.as_deref(), Some("reviewed"));
+    let safe = app.query(project_id, json!({ "flagFilter": "safe" }));
+    assert_eq!(safe.total_filtered_rows, 0);
+}
+
+#[test]
+fn compressed_inputs_are_streamed() {
+    let app = TestApp::new();
+
+    for fixture in ["events.csv.gz", "events.zip"] {
+        let summary = create_project(
+            app.state(),
+            payload(json!({ "path": fixture_path(fixture), "description": null })),
+        )
+        .expect("compressed project is created")
+        .project;
+        assert_eq!(summary.meta.name, "events");
+        assert_eq!(summary.meta.total_records, FIXTURE_ROWS);
+
+        let hits = app.query(summary.meta.id, json!({ "search": "mimikatz" }));
+        assert_eq!(hits.total_filtered_rows, 1);
+    }
+}
+
+#[test]
+fn import_applies_dtype_overrides_and_parses_dates() {
+    let app = TestApp::new();
+    let source = app.root.join("logons.csv");
+    fs::write(
+        &source,
+        "user_id,logon_time,count\n007,2024-03-01 10:15:00,3\n042,2024-03-02 11:00:00,5\n",
+    )
+    .expect("write export");
+
+    let response = create_project(
+        app.state(),
+        payload(json!({
+            "path": source,
+            "description": null,
+            "dtypes": { "user_id": "string" },
+            "parseDates": true,
+        })),
+    )
+    .expect("typed project is created");
+    let dtype_of = |name: &str| {
+        response
+            .column_types
+            .iter()
+            .find(|column| column.name == name)
+            .map(|column| column.dtype.clone())
+            .unwrap_or_default()
+    };
+    assert_eq!(dtype_of("user_id"), "str");
+    assert!(dtype_of("logon_time").starts_with("datetime"));
+    assert_eq!(dtype_of("count"), "i64");
+    assert_eq!(
+        response.project.meta.import_options.dtypes.get("user_id"),
+        Some(&crate::models::ColumnDtype::String)
+    );
+
+    let rows = app.query(response.project.meta.id, json!({ "sortKey": "logon_time" }));
+    assert_eq!(cell(&rows.rows[0], "user_id"), "007");
+    assert_eq!(cell(&rows.rows[1], "user_id"), "042");
+
+    let err = create_project(
+        app.state(),
+        payload(json!({ "path": source, "dtypes": { "userid": "int64" } })),
+    )
+    .expect_err("unknown override column is rejected");
+    assert!(err.to_string().contains("userid"), "{}", err);
+}
+
+#[test]
+fn headerless_csv_gets_generated_column_names() {
+    let app = TestApp::new();
+    let flows = "10.0.0.1,443,tcp\n10.0.0.2,53,udp\n";
+    let plain = app.root.join("flows.csv");
+    fs::write(&plain, flows).expect("write flows");
+    let compressed = app.root.join("flows.csv.gz");
+    let mut encoder = flate2::write::GzEncoder::new(
+        fs::File::create(&compressed).expect("create archive"),
+        flate2::Compression::default(),
+    );
+    std::io::Write::write_all(&mut encoder, flows.as_bytes()).expect("compress flows");
+    encoder.finish().expect("finish archive");
+
+    for source in [plain, compressed] {
+        let response = create_project(
+            app.state(),
+            payload(json!({ "path": source, "description": null, "hasHeaders": false })),
+        )
+        .expect("header-less project is created");
+        assert_eq!(response.project.meta.total_records, 2);
+        assert!(!response.project.meta.import_options.has_headers);
+        let names: Vec<&str> = response
+            .column_types
+            .iter()
+            .map(|column| column.name.as_str())
+            .collect();
+        assert_eq!(names, ["column_1", "column_2", "column_3"]);
+
+        let rows = app.query(response.project.meta.id, json!({ "sortKey": "column_2" }));
+        assert_eq!(cell(&rows.rows[0], "column_1"), "10.0.0.2");
+        assert_eq!(cell(&rows.rows[1], "column_3"), "tcp");
+    }
+}
+
+#[test]
+fn lenient_import_repairs_ragged_rows_and_reports_them() {
+    let app = TestApp::new();
+    let source = app.root.join("ragged.csv");
+    fs::write(
+        &source,
+        "host,port,proto\n10.0.0.1,443,tcp\n10.0.0.2,53\n10.0.0.3,80,tcp,extra\n\"10.0.0.4,22,ssh\n",
+    )
+    .expect("write export");
+
+    let strict = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null })),
+    );
+    assert!(strict.is_err(), "ragged rows fail a strict import");
+
+    let response = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null, "lenient": true })),
+    )
+    .expect("lenient project is created");
+    let report = response.import_report.expect("lenient imports report");
+    assert_eq!(report.rows_imported, 3);
+    assert_eq!(report.rows_repaired, 2);
+    assert_eq!(report.rows_skipped, 1);
+    let lines: Vec<(usize, bool)> = report
+        .issues
+        .iter()
+        .map(|issue| (issue.line, issue.skipped))
+        .collect();
+    assert_eq!(lines, [(3, false), (4, false), (5, true)]);
+
+    let project_id = response.project.meta.id;
+    let saved = app
+        .state()
+        .projects
+        .project_dir(&project_id)
+        .join("import_report.json");
+    assert!(saved.exists(), "full report is stored with the project");
+
+    let rows = app.query(project_id, json!({ "search": "10.0.0.3" }));
+    assert_eq!(rows.total_filtered_rows, 1);
+    assert_eq!(cell(&rows.rows[0], "proto"), "tcp");
+}
+
+#[test]
+fn exported_flags_survive_a_streamed_import() {
+    let app = TestApp::new();
+    let project_id = app.create_fixture_project();
+    update_flag(
+        app.state(),
+        payload(json!({
+            "projectId": project_id,
+            "row_index": 4,
+            "flag": "critical",
+            "memo": "lateral movement"
+        })),
+    )
+    .expect("flag is stored");
+    let destination = app.root.join("export").join("events.csv");
+    export_project(
+        app.state(),
+        payload(json!({ "projectId": project_id, "destination": destination })),
+    )
+    .expect("export succeeds");
+
+    let response = create_project(
+        app.state(),
+        payload(json!({ "path": destination, "description": null })),
+    )
+    .expect("exported file is imported");
+    assert_eq!(response.project.meta.total_records, FIXTURE_ROWS);
+    assert_eq!(response.project.meta.flagged_records, 1);
+    assert!(!response
+        .column_types
+        .iter()
+        .any(|column| column.name.starts_with("trivium-") || column.name == "__rowid"));
+
+    let critical = app.query(
+        response.project.meta.id,
+        json!({ "flagFilter": "critical" }),
+    );
+    assert_eq!(critical.total_filtered_rows, 1);
+    assert_eq!(critical.rows[0].row_index, 4);
+    assert_eq!(critical.rows[0].memo.as_deref(), Some("lateral movement"));
+}
+
+#[test]
+fn hayabusa_timelines_get_the_tool_preset() {
+    let app = TestApp::new();
+    let source = app.root.join("hayabusa.csv");
+    fs::write(
+        &source,
+        "Timestamp,RuleTitle,Level,Computer,Channel,EventID,RecordID,Details,RuleFile\n\
+         2024-03-01 10:00:00.000 +00:00,Mimikatz Use,crit,WS01,Sec,4688,101,Cmd: mimikatz.exe,a.yml\n\
+         2024-03-01 09:00:00.000 +00:00,Logon,info,WS01,Sec,4624,100,User: alice,b.yml\n",
+    )
+    .expect("write timeline");
+
+    let response = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null, "preset": "auto" })),
+    )
+    .expect("project is created");
+    let meta = &response.project.meta;
+    assert_eq!(meta.preset.as_deref(), Some("hayabusa"));
+    assert_eq!(meta.timestamp_column.as_deref(), Some("Timestamp"));
+    assert!(meta.hidden_columns.contains(&"RecordID".to_string()));
+    assert!(meta.hidden_columns.contains(&"RuleFile".to_string()));
+
+    let mapping = get_sigma_mapping(
+        app.state(),
+        payload(json!({ "projectId": meta.id.to_string() })),
+    )
+    .expect("mapping is stored");
+    assert_eq!(mapping["EventID"], ["EventID"]);
+    assert_eq!(mapping["CommandLine"], ["Details"]);
+
+    let unknown = create_project(
+        app.state(),
+        payload(json!({ "path": source, "description": null, "preset": "plaso" })),
+    );
+    assert!(unknown.is_err(), "unknown presets are rejected");
+}
@@ -1,66 +1,135 @@
-use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
 
 use polars::prelude::*;
 use serde_json::Value;
 
-use crate::value_utils::{anyvalue_to_json, anyvalue_to_search_string};
+use crate::models::ProjectMeta;
+use crate::search::LIST_ELEMENT_SUFFIX;
+use crate::settings::SettingsStore;
+use crate::timezones::{format_datetime_value, ProjectTimezone, TimeDisplay};
+use crate::value_utils::{
+    anyvalue_to_element_search_string, anyvalue_to_json, anyvalue_to_search_string,
+    normalize_search_text,
+};
+
+/// The time zone the project shows timestamps in, when one is set.
+pub(crate) fn display_zone(meta: &ProjectMeta) -> Option<ProjectTimezone> {
+    let name = meta.display_timezone.as_deref()?;
+    match ProjectTimezone::parse(name) {
+        Ok(zone) => Some(zone),
+        Err(err) => {
+            tracing::warn!("ignoring display time zone of {}: {:?}", meta.id, err);
+            None
+        }
+    }
+}
+
+/// How the grid renders timestamps; `None` keeps the stored UTC text when
+/// neither a display time zone nor a date format is set.
+pub(crate) fn time_display(meta: &ProjectMeta, settings: &SettingsStore) -> Option<TimeDisplay> {
+    let zone = display_zone(meta);
+    let format = settings.date_format();
+    if zone.is_none() && format.is_none() {
+        return None;
+    }
+    Some(TimeDisplay {
+        zone: zone.unwrap_or_else(ProjectTimezone::utc),
+        format,
+    })
+}
+
+/// How an alias is written as a search column prefix: lowercase, with
+/// whitespace as `_`, so `Client IP` is searched as `client_ip:`.
+pub(crate) fn alias_search_key(alias: &str) -> String {
+    alias
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("_")
+        .to_lowercase()
+}
+
+/// The column whose alias a lowercase search prefix names.
+pub(crate) fn aliased_column<'a>(
+    key: &str,
+    aliases: &'a BTreeMap<String, String>,
+) -> Option<&'a String> {
+    aliases
+        .iter()
+        .find(|(_, alias)| alias_search_key(alias) == key)
+        .map(|(column, _)| column)
+}
+
+/// Adds the project's aliases to a lowercase column lookup so `alias:term`
+/// filters resolve to their column. Real column names take precedence.
+pub(crate) fn add_alias_lookups(
+    series_lower: &mut HashMap<String, &Series>,
+    aliases: &BTreeMap<String, String>,
+) {
+    for (column, alias) in aliases {
+        if let Some(series) = series_lower.get(&column.to_lowercase()).copied() {
+            series_lower
+                .entry(alias_search_key(alias))
+                .or_insert(series);
+        }
+    }
+}
 
 /// Collects a row's data into a JSON map using the provided column ordering.
 pub(crate) fn collect_row_record(
     df: &DataFrame,
     column_names: &[String],
     row_idx: usize,
+    display: Option<&TimeDisplay>,
 ) -> HashMap<String, Value> {
     let series_map: HashMap<&str, &Series> = df
         .get_columns()
         .iter()
         .map(|series| (series.name(), series))
         .collect();
-    collect_row_record_from_series(&series_map, column_names, row_idx)
+    collect_row_record_from_series(&series_map, column_names, row_idx, display)
 }
 
-/// Collects a row's data using a precomputed series map to avoid repeated lookups.
+/// Collects a row's data using a precomputed series map to avoid repeated
+/// lookups. Datetimes are rendered as `display` asks when given, as stored
+/// UTC text otherwise.
 pub(crate) fn collect_row_record_from_series(
     series_map: &HashMap<&str, &Series>,
     column_names: &[String],
     row_idx: usize,
+    display: Option<&TimeDisplay>,
 ) -> HashMap<String, Value> {
     let mut record = HashMap::new();
     for column in column_names {
         if let Some(series) = series_map.get(column.as_str()) {
             if let Ok(value) = series.get(row_idx) {
-                record.insert(column.clone(), anyvalue_to_json(&value));
+                let json = match display.and_then(|display| format_datetime_value(&value, display))
+                {
+                    Some(text) => Value::String(text),
+                    None => anyvalue_to_json(&value),
+                };
+                record.insert(column.clone(), json);
             }
         }
     }
     record
 }
 
-/// Ensures lowercase string caches exist for a column, returning the cached vector.
-pub(crate) fn ensure_column_text_cache<'a>(
-    column: &str,
-    column_series_lower: &HashMap<String, &Series>,
-    cache: &'a mut HashMap<String, Vec<String>>,
-    row_count: usize,
-) -> &'a Vec<String> {
-    let key = column.to_lowercase();
-    if !cache.contains_key(&key) {
-        let mut col_vec: Vec<String> = vec![String::new(); row_count];
-        if let Some(series) = column_series_lower.get(&key) {
-            for row_idx in 0..row_count {
-                if let Ok(value) = series.get(row_idx) {
-                    if let Some(text) = anyvalue_to_search_string(&value) {
-                        let lower = text.to_lowercase();
-                        if !lower.is_empty() {
-                            col_vec[row_idx] = lower;
-                        }
-                    }
-                }
-            }
+/// Hashes every row over `columns` so identical rows can be found
+/// regardless of their position or the column order, within one import or
+/// between two.
+pub(crate) fn row_fingerprints(df: &DataFrame, columns: &[String]) -> Vec<u64> {
+    let mut hashers = vec![DefaultHasher::new(); df.height()];
+    for column in columns {
+        let Ok(series) = df.column(column) else {
+            continue;
+        };
+        for (hasher, value) in hashers.iter_mut().zip(series.iter()) {
+            anyvalue_to_search_string(&value).hash(hasher);
         }
-        cache.insert(key.clone(), col_vec);
     }
-    cache.get(&key).expect("column cache must exist")
+    hashers.into_iter().map(|hasher| hasher.finish()).collect()
 }
 
 /// Builds concatenated row text and per-column single-row caches.
@@ -75,12 +144,20 @@ pub(crate) fn build_row_search_text(
         if let Some(series) = column_series.get(column.as_str()) {
             if let Ok(value) = series.get(row_idx) {
                 if let Some(text) = anyvalue_to_search_string(&value) {
-                    let lower = text.to_lowercase();
+                    let lower = normalize_search_text(&text);
                     if !lower.is_empty() {
                         if !row_text.is_empty() {
                             row_text.push(' ');
                         }
                         row_text.push_str(&lower);
+                        if let AnyValue::List(_) = value {
+                            if let Some(elements) = anyvalue_to_element_search_string(&value) {
+                                per_column.insert(
+                                    format!("{}{}", column.to_lowercase(), LIST_ELEMENT_SUFFIX),
+                                    vec![normalize_search_text(&elements)],
+                                );
+                            }
+                        }
                         per_column.insert(column.to_lowercase(), vec![lower]);
                     }
                 }
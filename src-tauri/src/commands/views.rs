@@ -0,0 +1,200 @@
+use std::path::PathBuf;
+
+use chrono::Utc;
+use serde::Deserialize;
+use tauri::State;
+use uuid::Uuid;
+
+use crate::{
+    error::AppError,
+    models::SavedView,
+    project_io::read_project_columns,
+    state::AppState,
+    storage::{load_views, save_views, VIEWS_FILE},
+};
+
+const FLAG_FILTERS: [&str; 6] = ["all", "none", "priority", "safe", "suspicious", "critical"];
+
+#[derive(Debug, Deserialize)]
+pub struct ViewsPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SaveViewPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Replaces the view of the same name, if there is one.
+    pub name: String,
+    #[serde(default)]
+    pub search: Option<String>,
+    #[serde(default, rename = "wholeWord")]
+    pub whole_word: bool,
+    #[serde(default, rename = "flagFilter")]
+    pub flag_filter: Option<String>,
+    #[serde(rename = "sortKey", default)]
+    pub sort_key: Option<String>,
+    #[serde(rename = "sortDirection", default)]
+    pub sort_direction: Option<String>,
+    #[serde(rename = "visibleColumns", default)]
+    pub visible_columns: Vec<String>,
+    #[serde(rename = "pageSize", default)]
+    pub page_size: Option<usize>,
+    #[serde(rename = "isDefault", default)]
+    pub is_default: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DeleteViewPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct DefaultViewPayload {
+    #[serde(rename = "projectId")]
+    pub project_id: Uuid,
+    /// Clears the default when omitted.
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
+fn views_path(state: &AppState, project_id: Uuid) -> Result<PathBuf, AppError> {
+    let Some(meta) = state.projects.find(&project_id) else {
+        return Err(AppError::ProjectNotFound(project_id));
+    };
+    Ok(state.projects.project_dir(&meta.id).join(VIEWS_FILE))
+}
+
+fn non_empty(value: Option<String>) -> Option<String> {
+    value
+        .map(|value| value.trim().to_string())
+        .filter(|value| !value.is_empty())
+}
+
+/// Returns the project's saved views in name order.
+#[tauri::command]
+pub fn list_views(
+    state: State<AppState>,
+    payload: ViewsPayload,
+) -> Result<Vec<SavedView>, AppError> {
+    let path = views_path(&state, payload.project_id)?;
+    load_views(&path).map_err(AppError::from)
+}
+
+/// Saves the current search, flag filter, sort, visible columns and page size
+/// under a name. Making it the default unsets the previous default.
+#[tauri::command]
+pub fn save_view(
+    state: State<AppState>,
+    payload: SaveViewPayload,
+) -> Result<Vec<SavedView>, AppError> {
+    let path = views_path(&state, payload.project_id)?;
+    let name = payload.name.trim().to_string();
+    if name.is_empty() {
+        return Err(AppError::Message("Enter a name for the view.".into()));
+    }
+    let flag_filter = non_empty(payload.flag_filter);
+    if let Some(filter) = flag_filter
+        .as_deref()
+        .filter(|filter| !FLAG_FILTERS.contains(filter))
+    {
+        return Err(AppError::Message(format!(
+            "Unknown flag filter '{}'.",
+            filter
+        )));
+    }
+    let sort_direction = non_empty(payload.sort_direction);
+    if let Some(direction) = sort_direction
+        .as_deref()
+        .filter(|direction| !matches!(*direction, "asc" | "desc"))
+    {
+        return Err(AppError::Message(format!(
+            "Unknown sort direction '{}'.",
+            direction
+        )));
+    }
+    if payload.page_size == Some(0) {
+        return Err(AppError::Message(
+            "The page size must be at least one row.".into(),
+        ));
+    }
+    let parquet_path = path.with_file_name("data.parquet");
+    if !parquet_path.exists() {
+        return Err(AppError::DataFileMissing(parquet_path));
+    }
+    let columns = read_project_columns(&parquet_path).map_err(AppError::from)?;
+    let sort_key = non_empty(payload.sort_key);
+    if let Some(unknown) = sort_key
+        .iter()
+        .chain(&payload.visible_columns)
+        .find(|column| column.as_str() == "__rowid" || !columns.contains(column))
+    {
+        return Err(AppError::Message(format!("Unknown column '{}'.", unknown)));
+    }
+
+    let mut views = load_views(&path).map_err(AppError::from)?;
+    if payload.is_default {
+        for view in &mut views {
+            view.is_default = false;
+        }
+    }
+    let view = SavedView {
+        name,
+        search: non_empty(payload.search),
+        whole_word: payload.whole_word,
+        flag_filter,
+        sort_key,
+        sort_direction,
+        visible_columns: payload.visible_columns,
+        page_size: payload.page_size,
+        is_default: payload.is_default,
+        updated_at: Utc::now(),
+    };
+    match views.iter_mut().find(|existing| existing.name == view.name) {
+        Some(existing) => *existing = view,
+        None => views.push(view),
+    }
+    views.sort_by_key(|view| view.name.to_lowercase());
+    save_views(&path, &views).map_err(AppError::from)?;
+    Ok(views)
+}
+
+/// Removes a saved view; removing the default leaves the project without one.
+#[tauri::command]
+pub fn delete_view(
+    state: State<AppState>,
+    payload: DeleteViewPayload,
+) -> Result<Vec<SavedView>, AppError> {
+    let path = views_path(&state, payload.project_id)?;
+    let mut views = load_views(&path).map_err(AppError::from)?;
+    let before = views.len();
+    views.retain(|view| view.name != payload.name);
+    if views.len() == before {
+        return Err(AppError::Message("The view no longer exists.".into()));
+    }
+    save_views(&path, &views).map_err(AppError::from)?;
+    Ok(views)
+}
+
+/// Chooses the view `load_project` applies, or none when no name is given.
+#[tauri::command]
+pub fn set_default_view(
+    state: State<AppState>,
+    payload: DefaultViewPayload,
+) -> Result<Vec<SavedView>, AppError> {
+    let path = views_path(&state, payload.project_id)?;
+    let mut views = load_views(&path).map_err(AppError::from)?;
+    if let Some(name) = &payload.name {
+        if views.iter().all(|view| &view.name != name) {
+            return Err(AppError::Message("The view no longer exists.".into()));
+        }
+    }
+    for view in &mut views {
+        view.is_default = Some(&view.name) == payload.name.as_ref();
+    }
+    save_views(&path, &views).map_err(AppError::from)?;
+    Ok(views)
+}
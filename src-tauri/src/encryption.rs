@@ -0,0 +1,413 @@
+//! Optional encryption at rest.
+//!
+//! Evidence holding personal data often may not sit unencrypted on an
+//! analyst's laptop. An encrypted project keeps its data, flags and IOC
+//! rules sealed in `*.enc` files while it is closed. The key is derived from
+//! a passphrase with PBKDF2-HMAC-SHA256 and the salt kept in the project
+//! metadata; files are sealed with AES-256-GCM in `CHUNK_SIZE` chunks so a
+//! large Parquet file never has to fit in memory twice. `load_project`
+//! unseals a project with the passphrase and keeps the key in memory; the
+//! plain files are sealed again by `lock_project` and when the app exits.
+//! Plain files left behind by a run that ended without sealing still need
+//! the passphrase to be opened.
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use aes_gcm::{
+    aead::{rand_core::RngCore, Aead, AeadCore, KeyInit, OsRng, Payload},
+    Aes256Gcm, Key, Nonce,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use parking_lot::Mutex;
+use sha2::Sha256;
+use thiserror::Error;
+use uuid::Uuid;
+
+use crate::{
+    alerts::ALERTED_ROWS_FILE,
+    entities::ENTITY_INDEX_FILE,
+    models::{FlagEntry, ProjectEncryption},
+    state::AppState,
+    storage::{
+        load_flags, reset_cache_db, save_flags, CASE_NOTES_FILE, COLUMN_METRICS_FILE,
+        IMPORT_REPORT_FILE,
+    },
+};
+
+/// Present while the project's data is sealed.
+pub const SEALED_DATA_FILE: &str = "data.parquet.enc";
+const SEALED_FLAGS_FILE: &str = "flags.json.enc";
+const SEALED_IOCS_FILE: &str = "iocs.json.enc";
+/// Other files with case details, sealed as `<name>.enc` when present.
+const SEALED_NOTE_FILES: [&str; 3] = [CASE_NOTES_FILE, IMPORT_REPORT_FILE, ALERTED_ROWS_FILE];
+const MAGIC: &[u8] = b"TRIVENC1";
+#[cfg(not(test))]
+const PBKDF2_ROUNDS: u32 = 600_000;
+/// Keeps key derivation from dominating the test run; the rounds a project
+/// was encrypted with are stored with it.
+#[cfg(test)]
+const PBKDF2_ROUNDS: u32 = 1_000;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const CHUNK_SIZE: usize = 1 << 20;
+/// Sealed with the key to tell a wrong passphrase apart before any file is
+/// touched.
+const CHECK_PLAINTEXT: &[u8] = b"trivium-project-key";
+
+/// Marks a failure caused by a passphrase that does not match the project.
+#[derive(Debug, Error)]
+#[error("The passphrase is incorrect.")]
+pub struct IncorrectPassphrase;
+
+pub struct ProjectKey(Key<Aes256Gcm>);
+
+impl ProjectKey {
+    fn cipher(&self) -> Aes256Gcm {
+        Aes256Gcm::new(&self.0)
+    }
+}
+
+/// Keys of encrypted projects unsealed in this session.
+#[derive(Default)]
+pub struct UnlockedProjects {
+    inner: Mutex<HashMap<Uuid, ProjectKey>>,
+}
+
+impl UnlockedProjects {
+    pub fn insert(&self, id: Uuid, key: ProjectKey) {
+        self.inner.lock().insert(id, key);
+    }
+
+    pub fn contains(&self, id: &Uuid) -> bool {
+        self.inner.lock().contains_key(id)
+    }
+
+    /// Runs `action` with the key of a project unlocked in this session.
+    pub fn with_key<T>(&self, id: &Uuid, action: impl FnOnce(&ProjectKey) -> T) -> Option<T> {
+        self.inner.lock().get(id).map(action)
+    }
+
+    pub fn take(&self, id: &Uuid) -> Option<ProjectKey> {
+        self.inner.lock().remove(id)
+    }
+
+    pub fn take_all(&self) -> Vec<(Uuid, ProjectKey)> {
+        self.inner.lock().drain().collect()
+    }
+}
+
+/// Creates the settings for a new passphrase and the key they derive.
+pub fn new_encryption(passphrase: &str) -> Result<(ProjectEncryption, ProjectKey)> {
+    let mut salt = [0u8; SALT_LEN];
+    OsRng.fill_bytes(&mut salt);
+    let key = derive_key(passphrase, &salt, PBKDF2_ROUNDS);
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let check = key
+        .cipher()
+        .encrypt(&nonce, CHECK_PLAINTEXT)
+        .map_err(|_| anyhow!("failed to seal the key check"))?;
+    let settings = ProjectEncryption {
+        salt: to_hex(&salt),
+        rounds: PBKDF2_ROUNDS,
+        check: to_hex(&[nonce.as_slice(), check.as_slice()].concat()),
+    };
+    Ok((settings, key))
+}
+
+/// Derives the project key, failing with `IncorrectPassphrase` when it does
+/// not open the key check.
+pub fn unlock_key(settings: &ProjectEncryption, passphrase: &str) -> Result<ProjectKey> {
+    let salt = from_hex(&settings.salt).context("invalid encryption salt")?;
+    let check = from_hex(&settings.check).context("invalid encryption key check")?;
+    if check.len() < NONCE_LEN {
+        bail!("invalid encryption key check");
+    }
+    let key = derive_key(passphrase, &salt, settings.rounds);
+    let (nonce, sealed) = check.split_at(NONCE_LEN);
+    match key.cipher().decrypt(Nonce::from_slice(nonce), sealed) {
+        Ok(plain) if plain == CHECK_PLAINTEXT => Ok(key),
+        _ => Err(IncorrectPassphrase.into()),
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8], rounds: u32) -> ProjectKey {
+    let mut key = Key::<Aes256Gcm>::default();
+    pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, rounds, &mut key);
+    ProjectKey(key)
+}
+
+pub fn is_sealed(project_dir: &Path) -> bool {
+    project_dir.join(SEALED_DATA_FILE).exists()
+}
+
+/// Replaces the project's data, flags, IOC rules and notes with sealed
+/// copies and drops the caches derived from them. The data is sealed last,
+/// so a project only counts as sealed once everything is.
+pub fn seal_project(project_dir: &Path, key: &ProjectKey) -> Result<()> {
+    let flags_path = project_dir.join("flags.json");
+    let flags = serde_json::to_vec(&load_flags(&flags_path)?)
+        .context("failed to serialize flags for sealing")?;
+    seal_stream(key, flags.as_slice(), &project_dir.join(SEALED_FLAGS_FILE))?;
+    let iocs_path = project_dir.join("iocs.json");
+    if iocs_path.exists() {
+        let iocs =
+            fs::read(&iocs_path).with_context(|| format!("failed to read {:?}", iocs_path))?;
+        seal_stream(key, iocs.as_slice(), &project_dir.join(SEALED_IOCS_FILE))?;
+    }
+    for name in SEALED_NOTE_FILES {
+        let path = project_dir.join(name);
+        if path.exists() {
+            let source = File::open(&path).with_context(|| format!("failed to open {:?}", path))?;
+            seal_stream(key, source, &sealed_path(&path))?;
+        }
+    }
+    let data_path = project_dir.join("data.parquet");
+    let source =
+        File::open(&data_path).with_context(|| format!("failed to open {:?}", data_path))?;
+    seal_stream(key, source, &project_dir.join(SEALED_DATA_FILE))?;
+
+    remove_file_if_exists(&data_path)?;
+    remove_file_if_exists(&iocs_path)?;
+    for name in SEALED_NOTE_FILES {
+        remove_file_if_exists(&project_dir.join(name))?;
+    }
+    let flags_db = flags_path.with_extension("db");
+    if flags_db.exists() {
+        fs::remove_dir_all(&flags_db)
+            .with_context(|| format!("failed to remove {:?}", flags_db))?;
+    }
+    // Cleared entries may linger in sled's log, so the whole cache goes.
+    reset_cache_db(project_dir)?;
+    remove_file_if_exists(&project_dir.join(COLUMN_METRICS_FILE))?;
+    remove_file_if_exists(&project_dir.join(ENTITY_INDEX_FILE))?;
+    Ok(())
+}
+
+/// Restores the plain files of a sealed project. The flags are opened
+/// first, so a wrong key fails before anything is written.
+pub fn unseal_project(project_dir: &Path, key: &ProjectKey) -> Result<()> {
+    let sealed_flags = project_dir.join(SEALED_FLAGS_FILE);
+    let flags: HashMap<usize, FlagEntry> = serde_json::from_slice(&open_bytes(key, &sealed_flags)?)
+        .context("failed to parse sealed flags")?;
+    let sealed_data = project_dir.join(SEALED_DATA_FILE);
+    let data_path = project_dir.join("data.parquet");
+    open_to_file(key, &sealed_data, &data_path)?;
+    let sealed_iocs = project_dir.join(SEALED_IOCS_FILE);
+    if sealed_iocs.exists() {
+        open_to_file(key, &sealed_iocs, &project_dir.join("iocs.json"))?;
+    }
+    for name in SEALED_NOTE_FILES {
+        let path = project_dir.join(name);
+        let sealed = sealed_path(&path);
+        if sealed.exists() {
+            open_to_file(key, &sealed, &path)?;
+        }
+    }
+    save_flags(&project_dir.join("flags.json"), &flags)?;
+
+    remove_file_if_exists(&sealed_flags)?;
+    remove_file_if_exists(&sealed_iocs)?;
+    for name in SEALED_NOTE_FILES {
+        remove_file_if_exists(&sealed_path(&project_dir.join(name)))?;
+    }
+    remove_file_if_exists(&sealed_data)?;
+    Ok(())
+}
+
+fn remove_file_if_exists(path: &Path) -> Result<()> {
+    match fs::remove_file(path) {
+        Err(err) if err.kind() != ErrorKind::NotFound => {
+            Err(err).with_context(|| format!("failed to remove {:?}", path))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn sealed_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".enc");
+    path.with_file_name(name)
+}
+
+fn staged_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".tmp");
+    path.with_file_name(name)
+}
+
+/// Writes `MAGIC`, then each chunk as nonce, length and ciphertext. The
+/// chunk number and a last-chunk marker are authenticated with every chunk,
+/// so chunks cannot be reordered, dropped or the file cut short unnoticed.
+fn seal_stream(key: &ProjectKey, mut source: impl Read, target: &Path) -> Result<()> {
+    let cipher = key.cipher();
+    let staged = staged_path(target);
+    let file = File::create(&staged).with_context(|| format!("failed to create {:?}", staged))?;
+    let mut writer = BufWriter::new(file);
+    writer.write_all(MAGIC)?;
+    let mut chunk = vec![0u8; CHUNK_SIZE];
+    let mut next = vec![0u8; CHUNK_SIZE];
+    let mut filled = read_chunk(&mut source, &mut chunk)?;
+    let mut index = 0u64;
+    loop {
+        let next_filled = if filled == CHUNK_SIZE {
+            read_chunk(&mut source, &mut next)?
+        } else {
+            0
+        };
+        let last = next_filled == 0;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = cipher
+            .encrypt(
+                &nonce,
+                Payload {
+                    msg: &chunk[..filled],
+                    aad: &chunk_aad(index, last),
+                },
+            )
+            .map_err(|_| anyhow!("failed to encrypt {:?}", target))?;
+        writer.write_all(&nonce)?;
+        writer.write_all(&(sealed.len() as u32).to_be_bytes())?;
+        writer.write_all(&sealed)?;
+        if last {
+            break;
+        }
+        std::mem::swap(&mut chunk, &mut next);
+        filled = next_filled;
+        index += 1;
+    }
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("failed to write {:?}", staged))?;
+    fs::rename(&staged, target).with_context(|| format!("failed to write {:?}", target))
+}
+
+fn read_chunk(source: &mut impl Read, buf: &mut [u8]) -> Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match source.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read) => filled += read,
+            Err(err) if err.kind() == ErrorKind::Interrupted => {}
+            Err(err) => return Err(err).context("failed to read data to seal"),
+        }
+    }
+    Ok(filled)
+}
+
+fn chunk_aad(index: u64, last: bool) -> [u8; 9] {
+    let mut aad = [0u8; 9];
+    aad[..8].copy_from_slice(&index.to_be_bytes());
+    aad[8] = u8::from(last);
+    aad
+}
+
+/// Decrypts a sealed file chunk by chunk into `sink`.
+fn open_stream(key: &ProjectKey, path: &Path, sink: &mut impl Write) -> Result<()> {
+    let cipher = key.cipher();
+    let file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    let mut reader = BufReader::new(file);
+    let mut magic = [0u8; MAGIC.len()];
+    reader
+        .read_exact(&mut magic)
+        .with_context(|| format!("{:?} is not a sealed project file", path))?;
+    if magic != MAGIC {
+        bail!("{:?} is not a sealed project file", path);
+    }
+    let mut index = 0u64;
+    loop {
+        let mut nonce = [0u8; NONCE_LEN];
+        let mut len = [0u8; 4];
+        reader
+            .read_exact(&mut nonce)
+            .and_then(|_| reader.read_exact(&mut len))
+            .with_context(|| format!("{:?} is truncated", path))?;
+        let mut sealed = vec![0u8; u32::from_be_bytes(len) as usize];
+        reader
+            .read_exact(&mut sealed)
+            .with_context(|| format!("{:?} is truncated", path))?;
+        let nonce = Nonce::from_slice(&nonce);
+        let (plain, last) = match cipher.decrypt(
+            nonce,
+            Payload {
+                msg: &sealed,
+                aad: &chunk_aad(index, false),
+            },
+        ) {
+            Ok(plain) => (plain, false),
+            Err(_) => {
+                let plain = cipher
+                    .decrypt(
+                        nonce,
+                        Payload {
+                            msg: &sealed,
+                            aad: &chunk_aad(index, true),
+                        },
+                    )
+                    .map_err(|_| anyhow!("{:?} cannot be decrypted with this key", path))?;
+                (plain, true)
+            }
+        };
+        sink.write_all(&plain)?;
+        if last {
+            return Ok(());
+        }
+        index += 1;
+    }
+}
+
+fn open_bytes(key: &ProjectKey, path: &Path) -> Result<Vec<u8>> {
+    let mut data = Vec::new();
+    open_stream(key, path, &mut data)?;
+    Ok(data)
+}
+
+fn open_to_file(key: &ProjectKey, source: &Path, target: &Path) -> Result<()> {
+    let staged = staged_path(target);
+    let file = File::create(&staged).with_context(|| format!("failed to create {:?}", staged))?;
+    let mut writer = BufWriter::new(file);
+    if let Err(err) = open_stream(key, source, &mut writer) {
+        drop(writer);
+        let _ = fs::remove_file(&staged);
+        return Err(err);
+    }
+    writer
+        .into_inner()
+        .map_err(|err| err.into_error())
+        .and_then(|file| file.sync_all())
+        .with_context(|| format!("failed to write {:?}", staged))?;
+    fs::rename(&staged, target).with_context(|| format!("failed to write {:?}", target))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Result<Vec<u8>> {
+    if !text.len().is_multiple_of(2) || !text.is_ascii() {
+        bail!("invalid hex string");
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|at| u8::from_str_radix(&text[at..at + 2], 16).map_err(anyhow::Error::from))
+        .collect()
+}
+
+/// Seals every project unsealed in this session; run when the app exits.
+pub fn seal_unlocked(state: &AppState) {
+    for (id, key) in state.unlocked.take_all() {
+        let project_dir = state.projects.project_dir(&id);
+        if is_sealed(&project_dir) {
+            continue;
+        }
+        if let Err(err) = seal_project(&project_dir, &key) {
+            tracing::warn!("failed to seal project {} on exit: {:?}", id, err);
+        }
+    }
+}
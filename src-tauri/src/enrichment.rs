@@ -0,0 +1,525 @@
+//! Derived columns: decoded copies of encoded values, session ids, rarity
+//! scores, keys expanded from JSON values, and columns computed by user
+//! scripts.
+//!
+//! Scripts are written in Rhai and run in an embedded, sandboxed engine,
+//! either on demand or for every batch of rows while a project is imported.
+//! A script runs once per row with the row's input columns in the map `row`
+//! (column name to display value, empty when missing); the value of its last
+//! expression becomes the row's value in the new column. `()` or an empty
+//! string leaves the value empty. Besides Rhai's built-ins, scripts may call
+//! `decode_base64(text)` and `decode_hex(text)`, which decode like
+//! `decode_value` and return `()` when nothing decodes:
+//!
+//! ```text
+//! let score = 0;
+//! if row.command_line.contains("-enc") { score += 50; }
+//! if row.user == "SYSTEM" { score += 10; }
+//! score
+//! ```
+
+use std::collections::HashMap;
+
+use anyhow::{anyhow, bail, Context, Result};
+use polars::prelude::{DataFrame, NamedFrom, Series};
+use rhai::{module_resolvers::DummyModuleResolver, Dynamic, Engine, Scope, AST};
+use serde::Deserialize;
+use serde_json::{Map, Value};
+
+use crate::{models::ScriptColumn, value_utils::anyvalue_to_search_string};
+
+/// Shortest value taken as encoded, so short words that happen to be valid
+/// base64 or hex (`WS01`, `cafe`) are left alone.
+const MIN_ENCODED_TOKEN_LEN: usize = 8;
+/// Share of decoded characters that must be printable for the result to be kept.
+const MIN_PRINTABLE_RATIO: f64 = 0.9;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Encoding {
+    Base64,
+    Hex,
+}
+
+fn base64_digit(byte: u8) -> Option<u32> {
+    let value = match byte {
+        b'A'..=b'Z' => byte - b'A',
+        b'a'..=b'z' => byte - b'a' + 26,
+        b'0'..=b'9' => byte - b'0' + 52,
+        b'+' | b'-' => 62,
+        b'/' | b'_' => 63,
+        _ => return None,
+    };
+    Some(u32::from(value))
+}
+
+/// Decodes padded base64 in the standard or URL-safe alphabet.
+fn decode_base64(text: &str) -> Option<Vec<u8>> {
+    let digits = text.trim_end_matches('=').as_bytes();
+    if digits.is_empty() || !text.len().is_multiple_of(4) || text.len() - digits.len() > 2 {
+        return None;
+    }
+    let mut bytes = Vec::with_capacity(digits.len() * 3 / 4);
+    let mut buffer = 0u32;
+    let mut bits = 0;
+    for &digit in digits {
+        buffer = (buffer << 6) | base64_digit(digit)?;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(bytes)
+}
+
+/// Decodes hex digits, optionally prefixed with `0x`.
+fn decode_hex(text: &str) -> Option<Vec<u8>> {
+    let digits = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .unwrap_or(text);
+    if digits.is_empty() || !digits.len().is_multiple_of(2) {
+        return None;
+    }
+    digits
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            let high = char::from(pair[0]).to_digit(16)?;
+            let low = char::from(pair[1]).to_digit(16)?;
+            Some((high * 16 + low) as u8)
+        })
+        .collect()
+}
+
+/// Turns decoded bytes into text when they look like text: UTF-16LE (as in
+/// PowerShell `-EncodedCommand`) when most odd bytes are zero, UTF-8 with
+/// invalid sequences replaced otherwise.
+fn readable_text(bytes: &[u8]) -> Option<String> {
+    let utf16 = bytes.len() >= 2
+        && bytes.len().is_multiple_of(2)
+        && bytes
+            .iter()
+            .skip(1)
+            .step_by(2)
+            .filter(|byte| **byte == 0)
+            .count()
+            * 2
+            >= bytes.len() / 2;
+    let text = if utf16 {
+        let units: Vec<u16> = bytes
+            .chunks(2)
+            .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    } else {
+        String::from_utf8_lossy(bytes).into_owned()
+    };
+    let text = text.trim_end_matches('\0').trim().to_string();
+    let total = text.chars().count();
+    if total == 0 {
+        return None;
+    }
+    let printable = text
+        .chars()
+        .filter(|c| *c != char::REPLACEMENT_CHARACTER && (!c.is_control() || c.is_whitespace()))
+        .count();
+    (printable as f64 / total as f64 >= MIN_PRINTABLE_RATIO).then_some(text)
+}
+
+fn decode_text(text: &str, encoding: Encoding) -> Option<String> {
+    let bytes = match encoding {
+        Encoding::Base64 => decode_base64(text)?,
+        Encoding::Hex => decode_hex(text)?,
+    };
+    readable_text(&bytes)
+}
+
+/// Decodes a cell: the whole value when it is encoded, otherwise every
+/// encoded token in it (e.g. the argument of `powershell -enc`), joined by
+/// spaces. Cells with nothing decodable yield `None`.
+pub fn decode_value(text: &str, encoding: Encoding) -> Option<String> {
+    let text = text.trim();
+    if text.len() < MIN_ENCODED_TOKEN_LEN {
+        return None;
+    }
+    if let Some(decoded) = decode_text(text, encoding) {
+        return Some(decoded);
+    }
+    let decoded: Vec<String> = text
+        .split(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | ';'))
+        .filter(|token| token.len() >= MIN_ENCODED_TOKEN_LEN)
+        .filter_map(|token| decode_text(token, encoding))
+        .collect();
+    (!decoded.is_empty()).then(|| decoded.join(" "))
+}
+
+/// Decodes every value of `source` into a new string column named `column`.
+pub fn decode_column(source: &Series, encoding: Encoding, column: &str) -> Series {
+    let values: Vec<Option<String>> = (0..source.len())
+        .map(|row| {
+            source
+                .get(row)
+                .ok()
+                .and_then(|value| anyvalue_to_search_string(&value))
+                .and_then(|text| decode_value(&text, encoding))
+        })
+        .collect();
+    Series::new(column, values)
+}
+
+/// Session of every row, numbered from 1 in order of each session's first
+/// row in time; `None` for rows without a key or a readable time.
+#[derive(Debug, Clone, Default)]
+pub struct SessionAssignment {
+    pub session_ids: Vec<Option<i64>>,
+    pub sessions: usize,
+}
+
+/// Groups rows sharing the values of `keys` (user, host, source IP, ...)
+/// into sessions: a row starts a new session when its key was idle for
+/// more than `gap_micros` since the key's previous row. `instants` holds
+/// each row's time in microseconds, as from `timeline_instants`.
+pub fn group_sessions(
+    keys: &[&Series],
+    instants: &[Option<i64>],
+    gap_micros: i64,
+) -> SessionAssignment {
+    let row_key = |row: usize| -> Option<Vec<String>> {
+        keys.iter()
+            .map(|series| {
+                series
+                    .get(row)
+                    .ok()
+                    .and_then(|value| anyvalue_to_search_string(&value))
+                    .filter(|text| !text.trim().is_empty())
+            })
+            .collect()
+    };
+    let mut timed: Vec<(i64, usize, Vec<String>)> = instants
+        .iter()
+        .enumerate()
+        .filter_map(|(row, time)| Some(((*time)?, row, row_key(row)?)))
+        .collect();
+    timed.sort_by_key(|(time, row, _)| (*time, *row));
+
+    let mut assignment = SessionAssignment {
+        session_ids: vec![None; instants.len()],
+        sessions: 0,
+    };
+    // Last row time and current session of every key seen so far.
+    let mut open: HashMap<Vec<String>, (i64, i64)> = HashMap::new();
+    for (time, row, key) in timed {
+        let session = match open.get(&key) {
+            Some(&(last, session)) if time - last <= gap_micros => session,
+            _ => {
+                assignment.sessions += 1;
+                assignment.sessions as i64
+            }
+        };
+        open.insert(key, (time, session));
+        assignment.session_ids[row] = Some(session);
+    }
+    assignment
+}
+
+/// How often each value occurs in one column, empty values left out.
+#[derive(Debug, Clone)]
+pub struct ValueFrequencies {
+    pub column: String,
+    pub counts: HashMap<String, usize>,
+}
+
+/// Value frequencies of the scored columns and each row's score: how many
+/// rows share its rarest value, `None` when all its values are empty.
+#[derive(Debug, Clone)]
+pub struct RarityScores {
+    pub columns: Vec<ValueFrequencies>,
+    pub scores: Vec<Option<usize>>,
+}
+
+/// Counts every value of `columns` over the whole project and scores each
+/// row by its least frequent value, for least-frequency-of-occurrence
+/// hunting: a process or parent seen on one host only stands out with a
+/// score of 1 however many rows there are.
+pub fn rarity_scores(columns: &[&Series]) -> RarityScores {
+    let height = columns.first().map_or(0, |series| series.len());
+    let mut scores: Vec<Option<usize>> = vec![None; height];
+    let mut frequencies = Vec::with_capacity(columns.len());
+    for series in columns {
+        let values: Vec<Option<String>> = (0..series.len())
+            .map(|row| {
+                series
+                    .get(row)
+                    .ok()
+                    .and_then(|value| anyvalue_to_search_string(&value))
+                    .filter(|text| !text.trim().is_empty())
+            })
+            .collect();
+        let mut counts: HashMap<String, usize> = HashMap::new();
+        for value in values.iter().flatten() {
+            *counts.entry(value.clone()).or_default() += 1;
+        }
+        for (score, value) in scores.iter_mut().zip(&values) {
+            if let Some(count) = value.as_ref().and_then(|value| counts.get(value)) {
+                *score = Some(score.map_or(*count, |score| score.min(*count)));
+            }
+        }
+        frequencies.push(ValueFrequencies {
+            column: series.name().to_string(),
+            counts,
+        });
+    }
+    RarityScores {
+        columns: frequencies,
+        scores,
+    }
+}
+
+/// The JSON object held by a value, if it holds one.
+fn json_object(value: Option<String>) -> Option<Map<String, Value>> {
+    match serde_json::from_str(value?.trim()).ok()? {
+        Value::Object(object) => Some(object),
+        _ => None,
+    }
+}
+
+fn collect_json_keys(object: &Map<String, Value>, prefix: &str, keys: &mut Vec<String>) {
+    for (key, value) in object {
+        let path = format!("{}{}", prefix, key);
+        match value {
+            Value::Object(inner) if !inner.is_empty() => {
+                collect_json_keys(inner, &format!("{}.", path), keys)
+            }
+            _ => keys.push(path),
+        }
+    }
+}
+
+/// Key paths of a column holding JSON objects, nested keys joined with `.`
+/// (`process.name`), with the number of rows holding each. Values that are
+/// not JSON objects are skipped; the second value counts the rows that were.
+pub fn json_key_counts(source: &Series) -> (HashMap<String, usize>, usize) {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    let mut objects = 0;
+    for value in source.iter() {
+        let Some(object) = json_object(anyvalue_to_search_string(&value)) else {
+            continue;
+        };
+        objects += 1;
+        let mut keys = Vec::new();
+        collect_json_keys(&object, "", &mut keys);
+        for key in keys {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    (counts, objects)
+}
+
+fn json_path<'a>(object: &'a Map<String, Value>, path: &str) -> Option<&'a Value> {
+    // A key may itself contain dots, so the whole remaining path is tried
+    // before descending.
+    if let Some(value) = object.get(path) {
+        return Some(value);
+    }
+    let mut split = path.match_indices('.').map(|(index, _)| index);
+    split.find_map(|index| match object.get(&path[..index]) {
+        Some(Value::Object(inner)) => json_path(inner, &path[index + 1..]),
+        _ => None,
+    })
+}
+
+/// Expands `keys` of the JSON objects in `source` into text columns named by
+/// `columns`. Strings are taken as they are, other values as JSON text;
+/// missing keys, `null` and values that are not JSON objects stay empty.
+pub fn expand_json_keys(source: &Series, keys: &[String], columns: &[String]) -> Vec<Series> {
+    let mut values: Vec<Vec<Option<String>>> = vec![Vec::with_capacity(source.len()); keys.len()];
+    for value in source.iter() {
+        let object = json_object(anyvalue_to_search_string(&value));
+        for (key, column) in keys.iter().zip(values.iter_mut()) {
+            let value = object
+                .as_ref()
+                .and_then(|object| json_path(object, key))
+                .and_then(|value| match value {
+                    Value::Null => None,
+                    Value::String(text) => Some(text.clone()),
+                    other => Some(other.to_string()),
+                });
+            column.push(value);
+        }
+    }
+    columns
+        .iter()
+        .zip(values)
+        .map(|(column, values)| Series::new(column, values))
+        .collect()
+}
+
+/// Most operations one script run may take for a row, so a runaway loop
+/// fails the column instead of hanging the app.
+const MAX_SCRIPT_OPERATIONS: u64 = 1_000_000;
+const MAX_SCRIPT_CALL_LEVELS: usize = 32;
+const MAX_SCRIPT_EXPR_DEPTH: usize = 64;
+/// Longest string, and most array or map entries, a script may build.
+const MAX_SCRIPT_STRING_SIZE: usize = 1 << 20;
+const MAX_SCRIPT_COLLECTION_SIZE: usize = 100_000;
+
+/// Decoded text of a value for scripts, `()` when nothing decodes.
+fn script_decode(text: &str, encoding: Encoding) -> Dynamic {
+    decode_value(text, encoding).map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+/// A Rhai engine without module loading or `eval`, limited in run time,
+/// nesting and memory. Rhai has no file, process or network access of its
+/// own; scripts see only the row they are given.
+fn script_engine() -> Engine {
+    let mut engine = Engine::new();
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_operations(MAX_SCRIPT_OPERATIONS)
+        .set_max_call_levels(MAX_SCRIPT_CALL_LEVELS)
+        .set_max_expr_depths(MAX_SCRIPT_EXPR_DEPTH, MAX_SCRIPT_EXPR_DEPTH)
+        .set_max_string_size(MAX_SCRIPT_STRING_SIZE)
+        .set_max_array_size(MAX_SCRIPT_COLLECTION_SIZE)
+        .set_max_map_size(MAX_SCRIPT_COLLECTION_SIZE)
+        .disable_symbol("eval");
+    engine.on_print(|text| tracing::debug!("enrichment script: {}", text));
+    engine.on_debug(|text, _, position| {
+        tracing::debug!("enrichment script at {}: {}", position, text)
+    });
+    engine.register_fn("decode_base64", |text: &str| {
+        script_decode(text, Encoding::Base64)
+    });
+    engine.register_fn("decode_hex", |text: &str| {
+        script_decode(text, Encoding::Hex)
+    });
+    engine
+}
+
+/// The cell a script result becomes: `()` and empty strings leave it empty,
+/// anything else is stored as text.
+fn script_value(value: Dynamic) -> Option<String> {
+    if value.is_unit() {
+        return None;
+    }
+    let text = if value.is_string() {
+        value.into_string().unwrap_or_default()
+    } else {
+        value.to_string()
+    };
+    (!text.is_empty()).then_some(text)
+}
+
+/// A user script compiled for one derived column.
+pub struct CompiledScript {
+    engine: Engine,
+    ast: AST,
+}
+
+impl CompiledScript {
+    pub fn compile(source: &str) -> Result<Self> {
+        let engine = script_engine();
+        let ast = engine.compile(source).map_err(|err| anyhow!("{}", err))?;
+        Ok(Self { engine, ast })
+    }
+
+    /// Runs the script once per row of `input` and returns the results as a
+    /// string column named `column`.
+    pub fn run_column(&self, input: &DataFrame, column: &str) -> Result<Series> {
+        let series = input.get_columns();
+        let values = (0..input.height())
+            .map(|row| {
+                let record: rhai::Map = series
+                    .iter()
+                    .map(|s| {
+                        let value = s
+                            .get(row)
+                            .ok()
+                            .and_then(|value| anyvalue_to_search_string(&value))
+                            .unwrap_or_default();
+                        (s.name().into(), Dynamic::from(value))
+                    })
+                    .collect();
+                let mut scope = Scope::new();
+                scope.push("row", record);
+                self.engine
+                    .eval_ast_with_scope::<Dynamic>(&mut scope, &self.ast)
+                    .map(script_value)
+                    .map_err(|err| anyhow!("row {}: {}", row, err))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Series::new(column, values))
+    }
+}
+
+/// The columns a script reads: the chosen ones, or every data column. A
+/// chosen column missing from `existing` is returned as the error.
+pub fn script_input_columns(
+    existing: &[String],
+    chosen: &[String],
+) -> std::result::Result<Vec<String>, String> {
+    if chosen.is_empty() {
+        return Ok(existing
+            .iter()
+            .filter(|name| !name.starts_with("__"))
+            .cloned()
+            .collect());
+    }
+    if let Some(unknown) = chosen.iter().find(|name| !existing.contains(name)) {
+        return Err(unknown.clone());
+    }
+    Ok(chosen.to_vec())
+}
+
+/// The script columns of an import, compiled once and added to every batch
+/// of rows as it is read.
+pub struct ImportScripts<'a> {
+    scripts: Vec<(&'a ScriptColumn, CompiledScript)>,
+}
+
+impl<'a> ImportScripts<'a> {
+    pub fn compile(columns: &'a [ScriptColumn]) -> Result<Self> {
+        let scripts = columns
+            .iter()
+            .map(|spec| {
+                CompiledScript::compile(&spec.script)
+                    .map(|script| (spec, script))
+                    .with_context(|| format!("invalid script for column '{}'", spec.column))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Self { scripts })
+    }
+
+    /// Adds the script columns to `df`, in order, so later scripts may read
+    /// the columns of earlier ones.
+    pub fn apply(&self, df: &mut DataFrame) -> Result<()> {
+        for (spec, script) in &self.scripts {
+            let existing: Vec<String> = df
+                .get_column_names()
+                .iter()
+                .map(|name| name.to_string())
+                .collect();
+            if existing
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&spec.column))
+            {
+                bail!("column '{}' already exists", spec.column);
+            }
+            let input_columns =
+                script_input_columns(&existing, &spec.input_columns).map_err(|unknown| {
+                    anyhow!(
+                        "script for column '{}' reads unknown column '{}'",
+                        spec.column,
+                        unknown
+                    )
+                })?;
+            let series = script
+                .run_column(&df.select(&input_columns)?, &spec.column)
+                .with_context(|| format!("script for column '{}' failed", spec.column))?;
+            df.with_column(series)?;
+        }
+        Ok(())
+    }
+}
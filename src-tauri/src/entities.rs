@@ -0,0 +1,235 @@
+//! Index of the IP addresses, domains, hashes and email addresses found in a
+//! project's text columns, with the rows each one appears in.
+//!
+//! The index is built with a single pass over the data and stored in
+//! `ENTITY_INDEX_FILE`, keyed on the size and modification time of the data
+//! file, so pivoting on an entity never needs another full-text scan. Any
+//! change to the data (re-import, script column, external edit) makes it
+//! stale and it is rebuilt on next use. Extraction is heuristic: file names
+//! such as `cmd.exe` are not reported as domains, but other look-alikes may be.
+
+use std::{collections::HashMap, fs, net::Ipv6Addr, path::Path};
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use polars::prelude::DataType;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    project_io::read_project_dataframe,
+    value_utils::{anyvalue_to_search_string, refang},
+};
+
+pub const ENTITY_INDEX_FILE: &str = "entities.json";
+
+/// Last labels that name file types rather than top-level domains.
+const FILE_EXTENSIONS: &[&str] = &[
+    "bat", "bin", "cfg", "cmd", "com", "cpl", "csv", "dat", "db", "dll", "doc", "docx", "evtx",
+    "exe", "gz", "hta", "htm", "html", "ini", "jar", "jpg", "js", "json", "lnk", "log", "msi",
+    "pdf", "png", "ps1", "psm1", "py", "rar", "scr", "sh", "sys", "tmp", "txt", "vbs", "xls",
+    "xlsx", "xml", "zip",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EntityKind {
+    Ip,
+    Domain,
+    Email,
+    Md5,
+    Sha1,
+    Sha256,
+}
+
+/// One distinct value and the rows it appears in, in ascending order.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EntityEntry {
+    pub kind: EntityKind,
+    pub value: String,
+    pub rows: Vec<usize>,
+}
+
+/// Identifies the data file an index was built from.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct DataFingerprint {
+    size: u64,
+    modified: Option<DateTime<Utc>>,
+}
+
+impl DataFingerprint {
+    fn of(path: &Path) -> Result<Self> {
+        let metadata = fs::metadata(path).with_context(|| format!("failed to stat {:?}", path))?;
+        Ok(Self {
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(DateTime::<Utc>::from),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EntityIndex {
+    source: DataFingerprint,
+    pub built_at: DateTime<Utc>,
+    /// Text columns that were scanned.
+    pub columns: Vec<String>,
+    /// Sorted by row count, most frequent first.
+    pub entities: Vec<EntityEntry>,
+}
+
+struct EntityMatchers {
+    ipv4: Regex,
+    ipv6: Regex,
+    email: Regex,
+    domain: Regex,
+    hash: Regex,
+}
+
+impl EntityMatchers {
+    fn new() -> Self {
+        let compile = |pattern: &str| Regex::new(pattern).expect("entity pattern is valid");
+        Self {
+            ipv4: compile(
+                r"\b(?:(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\.){3}(?:25[0-5]|2[0-4]\d|1\d\d|[1-9]?\d)\b",
+            ),
+            ipv6: compile(r"(?i)(?:[0-9a-f]{0,4}:){2,7}[0-9a-f]{0,4}"),
+            email: compile(r"\b[A-Za-z0-9._%+-]+@(?:[A-Za-z0-9-]+\.)+[A-Za-z]{2,24}\b"),
+            domain: compile(
+                r"\b(?:[A-Za-z0-9](?:[A-Za-z0-9-]{0,61}[A-Za-z0-9])?\.)+[A-Za-z]{2,24}\b",
+            ),
+            hash: compile(r"\b[0-9A-Fa-f]{32,64}\b"),
+        }
+    }
+
+    /// Calls `found` for every entity in `text`; values are normalized to
+    /// lowercase so differently cased spellings share an entry.
+    fn scan(&self, text: &str, mut found: impl FnMut(EntityKind, String)) {
+        for m in self.ipv4.find_iter(text) {
+            found(EntityKind::Ip, m.as_str().to_string());
+        }
+        if text.contains("::") || text.matches(':').count() >= 7 {
+            for m in self.ipv6.find_iter(text) {
+                // A bare `::` is more likely a scope separator (`Foo::Bar`).
+                match m.as_str().parse::<Ipv6Addr>() {
+                    Ok(addr) if !addr.is_unspecified() => found(EntityKind::Ip, addr.to_string()),
+                    _ => {}
+                }
+            }
+        }
+        if text.contains('@') {
+            for m in self.email.find_iter(text) {
+                found(EntityKind::Email, m.as_str().to_ascii_lowercase());
+            }
+        }
+        if text.contains('.') {
+            for m in self.domain.find_iter(text) {
+                if is_plausible_domain(m.as_str()) {
+                    found(EntityKind::Domain, m.as_str().to_ascii_lowercase());
+                }
+            }
+        }
+        for m in self.hash.find_iter(text) {
+            let kind = match m.len() {
+                32 => EntityKind::Md5,
+                40 => EntityKind::Sha1,
+                64 => EntityKind::Sha256,
+                _ => continue,
+            };
+            found(kind, m.as_str().to_ascii_lowercase());
+        }
+    }
+}
+
+/// Rejects file names and dotted identifiers such as `System.Management.Automation`.
+fn is_plausible_domain(candidate: &str) -> bool {
+    let Some(tld) = candidate.rsplit('.').next() else {
+        return false;
+    };
+    let uniform_case =
+        tld.chars().all(|c| c.is_ascii_lowercase()) || tld.chars().all(|c| c.is_ascii_uppercase());
+    uniform_case && !FILE_EXTENSIONS.contains(&tld.to_ascii_lowercase().as_str())
+}
+
+/// Whether `text` contains `value` as a whole token, ignoring case: the
+/// characters around the match must not be letters or digits, so `10.0.0.1`
+/// does not match inside `10.0.0.12`. Both are expected in lowercase.
+pub fn contains_value(text: &str, value: &str) -> bool {
+    if value.is_empty() {
+        return false;
+    }
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric());
+    text.match_indices(value).any(|(start, _)| {
+        let end = start + value.len();
+        !is_word(text[..start].chars().next_back()) && !is_word(text[end..].chars().next())
+    })
+}
+
+/// Scans every text column of the project and writes a fresh index.
+pub fn build_entity_index(project_dir: &Path) -> Result<EntityIndex> {
+    let data_path = project_dir.join("data.parquet");
+    let source = DataFingerprint::of(&data_path)?;
+    let df = read_project_dataframe(&data_path)?;
+    let text_columns: Vec<_> = df
+        .get_columns()
+        .iter()
+        .filter(|s| s.name() != "__rowid" && matches!(s.dtype(), DataType::String))
+        .collect();
+
+    let matchers = EntityMatchers::new();
+    let mut rows_by_entity: HashMap<(EntityKind, String), Vec<usize>> = HashMap::new();
+    for row_idx in 0..df.height() {
+        for series in &text_columns {
+            let Some(text) = series
+                .get(row_idx)
+                .ok()
+                .and_then(|value| anyvalue_to_search_string(&value))
+            else {
+                continue;
+            };
+            matchers.scan(&refang(&text), |kind, value| {
+                let rows = rows_by_entity.entry((kind, value)).or_default();
+                if rows.last() != Some(&row_idx) {
+                    rows.push(row_idx);
+                }
+            });
+        }
+    }
+
+    let mut entities: Vec<EntityEntry> = rows_by_entity
+        .into_iter()
+        .map(|((kind, value), rows)| EntityEntry { kind, value, rows })
+        .collect();
+    entities.sort_by(|a, b| {
+        b.rows
+            .len()
+            .cmp(&a.rows.len())
+            .then(a.kind.cmp(&b.kind))
+            .then_with(|| a.value.cmp(&b.value))
+    });
+    let index = EntityIndex {
+        source,
+        built_at: Utc::now(),
+        columns: text_columns.iter().map(|s| s.name().to_string()).collect(),
+        entities,
+    };
+    let path = project_dir.join(ENTITY_INDEX_FILE);
+    fs::write(&path, serde_json::to_vec(&index)?)
+        .with_context(|| format!("failed to write {:?}", path))?;
+    Ok(index)
+}
+
+/// Returns the stored index while it matches the data file, building it
+/// otherwise (or always, with `rebuild`).
+pub fn load_entity_index(project_dir: &Path, rebuild: bool) -> Result<EntityIndex> {
+    let path = project_dir.join(ENTITY_INDEX_FILE);
+    if !rebuild && path.exists() {
+        let current = DataFingerprint::of(&project_dir.join("data.parquet"))?;
+        let stored = fs::read(&path)
+            .ok()
+            .and_then(|data| serde_json::from_slice::<EntityIndex>(&data).ok());
+        if let Some(index) = stored.filter(|index| index.source == current) {
+            return Ok(index);
+        }
+    }
+    build_entity_index(project_dir)
+}
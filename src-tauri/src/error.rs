@@ -1,16 +1,118 @@
+use std::{
+    io::{self, ErrorKind},
+    path::PathBuf,
+};
+
 use anyhow::Error;
+use polars::prelude::PolarsError;
+use serde::{ser::SerializeStruct, Serialize, Serializer};
 use thiserror::Error;
+use uuid::Uuid;
+
+use crate::encryption::IncorrectPassphrase;
 
+/// Error returned by every command. It crosses the IPC boundary as
+/// `{ code, message, context }` so the frontend can tell failures apart
+/// without parsing the message.
 #[derive(Debug, Error)]
 pub enum AppError {
     #[error("{0}")]
     Message(String),
+    #[error("Project not found.")]
+    ProjectNotFound(Uuid),
+    #[error("Project data file missing.")]
+    DataFileMissing(PathBuf),
+    /// Another command is using the project; carries what it is doing.
+    #[error("The project is busy with {1}; try again when it finishes.")]
+    ProjectBusy(Uuid, String),
+    /// The project is encrypted and sealed; opening it needs the passphrase.
+    #[error("The project is encrypted; enter its passphrase to open it.")]
+    PassphraseRequired(Uuid),
     #[error(transparent)]
     Other(#[from] Error),
 }
 
-impl From<AppError> for String {
-    fn from(err: AppError) -> Self {
-        err.to_string()
+/// Context attached to a `read_project_*` failure, marking the project's
+/// Parquet file itself as unreadable rather than the query that used it.
+#[derive(Debug, Error)]
+#[error("failed to read parquet file {0:?}")]
+pub struct DataFileUnreadable(pub PathBuf);
+
+#[derive(Debug, Default, Serialize)]
+pub struct ErrorContext {
+    #[serde(rename = "projectId", skip_serializing_if = "Option::is_none")]
+    pub project_id: Option<Uuid>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+}
+
+impl AppError {
+    /// Machine-readable code; stable across releases.
+    pub fn code(&self) -> &'static str {
+        match self {
+            AppError::Message(_) => "invalid_request",
+            AppError::ProjectNotFound(_) => "project_not_found",
+            AppError::DataFileMissing(_) => "data_file_missing",
+            AppError::ProjectBusy(..) => "project_busy",
+            AppError::PassphraseRequired(_) => "passphrase_required",
+            AppError::Other(err) => {
+                if err.downcast_ref::<DataFileUnreadable>().is_some() {
+                    return "data_corrupt";
+                }
+                if err.downcast_ref::<IncorrectPassphrase>().is_some() {
+                    return "passphrase_incorrect";
+                }
+                match io_error_kind(err) {
+                    Some(ErrorKind::PermissionDenied) => "permission_denied",
+                    Some(ErrorKind::NotFound) => "file_not_found",
+                    _ => "internal",
+                }
+            }
+        }
+    }
+
+    pub fn context(&self) -> ErrorContext {
+        match self {
+            AppError::Message(_) => ErrorContext::default(),
+            AppError::ProjectNotFound(id)
+            | AppError::ProjectBusy(id, _)
+            | AppError::PassphraseRequired(id) => ErrorContext {
+                project_id: Some(*id),
+                path: None,
+            },
+            AppError::DataFileMissing(path) => ErrorContext {
+                project_id: None,
+                path: Some(path.clone()),
+            },
+            AppError::Other(err) => ErrorContext {
+                project_id: None,
+                path: err
+                    .downcast_ref::<DataFileUnreadable>()
+                    .map(|unreadable| unreadable.0.clone()),
+            },
+        }
+    }
+}
+
+/// Kind of the first I/O error in the chain, including one wrapped by Polars.
+fn io_error_kind(err: &Error) -> Option<ErrorKind> {
+    err.chain().find_map(|cause| {
+        if let Some(io) = cause.downcast_ref::<io::Error>() {
+            return Some(io.kind());
+        }
+        match cause.downcast_ref::<PolarsError>() {
+            Some(PolarsError::IO { error, .. }) => Some(error.kind()),
+            _ => None,
+        }
+    })
+}
+
+impl Serialize for AppError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("AppError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("context", &self.context())?;
+        state.end()
     }
 }
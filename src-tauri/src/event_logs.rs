@@ -0,0 +1,364 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use polars::prelude::DataFrame;
+use serde_json::{Map, Value};
+
+use crate::jsonl::flatten_object;
+use crate::value_utils::JsonColumnBuilder;
+use crate::xml_scan::{attributes, unescape, XmlEvent, XmlEvents};
+
+/// Element tree of one rendered event (`<Event>` and its descendants).
+#[derive(Debug, Clone, Default)]
+pub struct EventElement {
+    pub name: String,
+    pub attrs: Vec<(String, String)>,
+    pub children: Vec<EventNode>,
+}
+
+#[derive(Debug, Clone)]
+pub enum EventNode {
+    Element(EventElement),
+    Text(String),
+}
+
+impl EventElement {
+    pub fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    pub fn elements(&self) -> impl Iterator<Item = &EventElement> {
+        self.children.iter().filter_map(|child| match child {
+            EventNode::Element(element) => Some(element),
+            EventNode::Text(_) => None,
+        })
+    }
+
+    pub fn child(&self, name: &str) -> Option<&EventElement> {
+        self.elements().find(|element| element.name == name)
+    }
+
+    /// Concatenated text of this element and its descendants.
+    pub fn text(&self) -> String {
+        let mut out = String::new();
+        self.collect_text(&mut out);
+        out.trim().to_string()
+    }
+
+    fn collect_text(&self, out: &mut String) {
+        for child in &self.children {
+            match child {
+                EventNode::Text(text) => out.push_str(text),
+                EventNode::Element(element) => element.collect_text(out),
+            }
+        }
+    }
+}
+
+/// Columns every event row starts with, in display order. Numeric fields are
+/// stored as integers so they sort numerically.
+pub const WELL_KNOWN_COLUMNS: &[&str] = &[
+    "TimeCreated",
+    "EventRecordID",
+    "EventID",
+    "Level",
+    "Channel",
+    "Provider",
+    "Computer",
+    "Task",
+    "Opcode",
+    "Keywords",
+    "ProcessID",
+    "ThreadID",
+    "UserID",
+    "Message",
+];
+
+const NUMERIC_COLUMNS: &[&str] = &[
+    "EventRecordID",
+    "EventID",
+    "Level",
+    "Task",
+    "Opcode",
+    "ProcessID",
+    "ThreadID",
+];
+
+fn cell(column: &str, text: Option<String>) -> Value {
+    let Some(text) = text.filter(|text| !text.is_empty()) else {
+        return Value::Null;
+    };
+    if NUMERIC_COLUMNS.contains(&column) {
+        if let Ok(number) = text.parse::<i64>() {
+            return Value::from(number);
+        }
+    }
+    Value::String(text)
+}
+
+/// Pairs values given in [`WELL_KNOWN_COLUMNS`] order with their columns.
+fn system_row(values: [Option<String>; 14]) -> Vec<(String, Value)> {
+    WELL_KNOWN_COLUMNS
+        .iter()
+        .zip(values)
+        .map(|(column, text)| (column.to_string(), cell(column, text)))
+        .collect()
+}
+
+/// Event data names that collide with a well-known column get an
+/// `EventData.` prefix instead of overwriting it.
+fn data_column(name: &str) -> String {
+    if WELL_KNOWN_COLUMNS.contains(&name) {
+        format!("EventData.{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn flatten_user_data(prefix: &str, element: &EventElement, out: &mut Vec<(String, Value)>) {
+    for child in element.elements() {
+        let name = if prefix.is_empty() {
+            child.name.clone()
+        } else {
+            format!("{}.{}", prefix, child.name)
+        };
+        if child.elements().next().is_some() {
+            flatten_user_data(&name, child, out);
+        } else {
+            out.push((data_column(&name), cell("", Some(child.text()))));
+        }
+    }
+}
+
+/// Flattens one `<Event>` into a row: `System` fields under well-known
+/// names, then `EventData`/`UserData` values under their own names.
+pub fn event_row(event: &EventElement) -> Vec<(String, Value)> {
+    let system = event.child("System");
+    let system_child = |name: &str| system.and_then(|system| system.child(name));
+    let system_text = |name: &str| system_child(name).map(EventElement::text);
+    let system_attr = |name: &str, attr: &str| {
+        system_child(name).and_then(|element| element.attr(attr).map(str::to_string))
+    };
+    let message = event
+        .child("RenderingInfo")
+        .and_then(|info| info.child("Message"))
+        .map(EventElement::text);
+
+    let mut row = system_row([
+        system_attr("TimeCreated", "SystemTime"),
+        system_text("EventRecordID"),
+        system_text("EventID"),
+        system_text("Level"),
+        system_text("Channel"),
+        system_attr("Provider", "Name"),
+        system_text("Computer"),
+        system_text("Task"),
+        system_text("Opcode"),
+        system_text("Keywords"),
+        system_attr("Execution", "ProcessID"),
+        system_attr("Execution", "ThreadID"),
+        system_attr("Security", "UserID"),
+        message,
+    ]);
+
+    if let Some(event_data) = event.child("EventData") {
+        let mut unnamed = 0;
+        for data in event_data.elements() {
+            let name = match data.attr("Name") {
+                Some(name) if !name.is_empty() => name.to_string(),
+                _ if data.name == "Data" => {
+                    unnamed += 1;
+                    format!("Data{}", unnamed)
+                }
+                _ => data.name.clone(),
+            };
+            row.push((data_column(&name), cell("", Some(data.text()))));
+        }
+    }
+    if let Some(user_data) = event.child("UserData") {
+        for root in user_data.elements() {
+            flatten_user_data("", root, &mut row);
+        }
+    }
+    row
+}
+
+fn json_text(value: &Value) -> Option<String> {
+    match value {
+        Value::Null => None,
+        Value::String(text) => Some(text.clone()),
+        Value::Array(items) => Some(
+            items
+                .iter()
+                .filter_map(json_text)
+                .collect::<Vec<_>>()
+                .join(", "),
+        ),
+        other => Some(other.to_string()),
+    }
+}
+
+/// Whether a JSON Lines record was shipped by Winlogbeat (Elastic Beats).
+pub fn is_winlogbeat_record(object: &Map<String, Value>) -> bool {
+    object.get("winlog").is_some_and(Value::is_object)
+}
+
+/// Maps a Winlogbeat record onto the same columns as [`event_row`]: the
+/// `winlog.*` system fields, then `winlog.event_data` and `winlog.user_data`.
+/// Beat and agent metadata is dropped.
+pub fn winlogbeat_row(object: &Map<String, Value>) -> Vec<(String, Value)> {
+    let field = |path: &str| {
+        path.split('.')
+            .try_fold(object.get("winlog")?, |value, key| value.get(key))
+            .and_then(json_text)
+    };
+    let top = |key: &str| object.get(key).and_then(json_text);
+
+    let mut row = system_row([
+        top("@timestamp"),
+        field("record_id"),
+        field("event_id"),
+        object
+            .get("log")
+            .and_then(|log| log.get("level"))
+            .and_then(json_text),
+        field("channel"),
+        field("provider_name"),
+        field("computer_name"),
+        field("task"),
+        field("opcode"),
+        field("keywords"),
+        field("process.pid"),
+        field("process.thread.id"),
+        field("user.identifier"),
+        top("message"),
+    ]);
+
+    let winlog = &object["winlog"];
+    if let Some(event_data) = winlog.get("event_data").and_then(Value::as_object) {
+        for (name, value) in event_data {
+            row.push((data_column(name), cell("", json_text(value))));
+        }
+    }
+    if let Some(Value::Object(user_data)) = winlog.get("user_data") {
+        let mut fields = Vec::new();
+        flatten_object("", user_data.clone(), &mut fields);
+        for (name, value) in fields {
+            row.push((data_column(&name), cell("", json_text(&value))));
+        }
+    }
+    // JSON Lines columns are not pruned afterwards, so skip fields this shipper left out.
+    row.retain(|(_, value)| !value.is_null());
+    row
+}
+
+/// Collects every `<Event>` element from rendered event XML, whether the
+/// events are concatenated (`wevtutil qe /f:xml`) or wrapped in an
+/// `<Events>` root (Event Viewer "Save as XML").
+fn parse_event_xml(src: &str) -> Vec<EventElement> {
+    fn close(element: EventElement, stack: &mut [EventElement], events: &mut Vec<EventElement>) {
+        match stack.last_mut() {
+            Some(parent) => parent.children.push(EventNode::Element(element)),
+            None => events.push(element),
+        }
+    }
+
+    let mut events = Vec::new();
+    let mut stack: Vec<EventElement> = Vec::new();
+    for event in XmlEvents::new(src) {
+        match event {
+            XmlEvent::Start { name, attrs, empty } => {
+                if stack.is_empty() && name != "Event" {
+                    continue;
+                }
+                let element = EventElement {
+                    name: name.to_string(),
+                    attrs: attributes(attrs)
+                        .map(|(key, value)| (key.to_string(), value.into_owned()))
+                        .collect(),
+                    children: Vec::new(),
+                };
+                if empty {
+                    close(element, &mut stack, &mut events);
+                } else {
+                    stack.push(element);
+                }
+            }
+            XmlEvent::End { .. } => {
+                if let Some(element) = stack.pop() {
+                    close(element, &mut stack, &mut events);
+                }
+            }
+            XmlEvent::Text(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    if !text.trim().is_empty() {
+                        parent
+                            .children
+                            .push(EventNode::Text(unescape(text).into_owned()));
+                    }
+                }
+            }
+            XmlEvent::CData(text) => {
+                if let Some(parent) = stack.last_mut() {
+                    parent.children.push(EventNode::Text(text.to_string()));
+                }
+            }
+        }
+    }
+    events
+}
+
+/// Decodes an XML export, honouring the UTF-16 byte order marks PowerShell
+/// redirection produces.
+fn decode_xml_text(bytes: Vec<u8>) -> Result<String> {
+    let utf16 = |bytes: &[u8], from: fn([u8; 2]) -> u16| {
+        let units: Vec<u16> = bytes
+            .chunks_exact(2)
+            .map(|pair| from([pair[0], pair[1]]))
+            .collect();
+        String::from_utf16_lossy(&units)
+    };
+    match bytes.as_slice() {
+        [0xff, 0xfe, rest @ ..] => Ok(utf16(rest, u16::from_le_bytes)),
+        [0xfe, 0xff, rest @ ..] => Ok(utf16(rest, u16::from_be_bytes)),
+        [0xef, 0xbb, 0xbf, rest @ ..] => Ok(String::from_utf8(rest.to_vec())?),
+        _ => Ok(String::from_utf8(bytes)?),
+    }
+}
+
+/// Reads rendered Windows event XML (`wevtutil qe … /f:xml` or
+/// `/f:RenderedXml`, Event Viewer exports) into one row per event.
+pub fn read_event_xml_dataframe(path: &Path) -> Result<DataFrame> {
+    let bytes = fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+    let text = decode_xml_text(bytes).with_context(|| format!("{:?} is not valid text", path))?;
+    events_dataframe(parse_event_xml(&text).iter().map(event_row))
+        .with_context(|| format!("failed to read event XML from {:?}", path))
+}
+
+/// Builds the frame for a set of event rows, dropping columns that no event
+/// populated (e.g. `Message` for logs without rendering info).
+pub fn events_dataframe(rows: impl IntoIterator<Item = Vec<(String, Value)>>) -> Result<DataFrame> {
+    let mut builder = JsonColumnBuilder::default();
+    for row in rows {
+        builder.push_row(row);
+    }
+    if builder.rows() == 0 {
+        return Err(anyhow!("no event records were found"));
+    }
+    let df = builder
+        .finish()
+        .context("failed to assemble event columns")?;
+    let height = df.height();
+    let populated: Vec<String> = df
+        .get_columns()
+        .iter()
+        .filter(|series| series.null_count() < height)
+        .map(|series| series.name().to_string())
+        .collect();
+    df.select(populated)
+        .context("failed to assemble event columns")
+}
@@ -0,0 +1,77 @@
+//! Change notifications that keep every window showing a project in sync.
+//!
+//! Commands report flag and IOC rule changes here, and `ProjectsStore`
+//! reports every metadata update; the app registers a listener that
+//! forwards each change to the frontend as a Tauri event.
+
+use parking_lot::Mutex;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::models::ProjectMeta;
+
+pub const FLAG_UPDATED_EVENT: &str = "flag-updated";
+pub const IOCS_UPDATED_EVENT: &str = "iocs-updated";
+pub const PROJECT_META_UPDATED_EVENT: &str = "project-meta-updated";
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum ChangeEvent {
+    /// A row's flag, memo or comments changed. `row_indices` is `None` when
+    /// so many rows changed, e.g. after a restore, that the view should
+    /// reload its flags.
+    Flag {
+        project_id: Uuid,
+        row_indices: Option<Vec<usize>>,
+    },
+    /// The project's IOC rules were replaced; IOC flags may have changed on
+    /// any row.
+    Iocs {
+        project_id: Uuid,
+    },
+    ProjectMeta {
+        project: Box<ProjectMeta>,
+    },
+}
+
+impl ChangeEvent {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ChangeEvent::Flag { .. } => FLAG_UPDATED_EVENT,
+            ChangeEvent::Iocs { .. } => IOCS_UPDATED_EVENT,
+            ChangeEvent::ProjectMeta { .. } => PROJECT_META_UPDATED_EVENT,
+        }
+    }
+}
+
+pub type ChangeListener = Box<dyn Fn(&ChangeEvent) + Send + Sync>;
+
+/// Delivers change events; the app registers a listener that emits them to
+/// every window.
+#[derive(Default)]
+pub struct ChangeSink {
+    listener: Mutex<Option<ChangeListener>>,
+}
+
+impl ChangeSink {
+    pub fn set_listener(&self, listener: ChangeListener) {
+        *self.listener.lock() = Some(listener);
+    }
+
+    pub fn notify(&self, event: ChangeEvent) {
+        if let Some(listener) = self.listener.lock().as_ref() {
+            listener(&event);
+        }
+    }
+
+    pub fn flags_updated(&self, project_id: Uuid, row_indices: Option<Vec<usize>>) {
+        self.notify(ChangeEvent::Flag {
+            project_id,
+            row_indices,
+        });
+    }
+
+    pub fn iocs_updated(&self, project_id: Uuid) {
+        self.notify(ChangeEvent::Iocs { project_id });
+    }
+}
@@ -0,0 +1,585 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::DateTime;
+use polars::prelude::DataFrame;
+
+use crate::event_logs::{event_row, events_dataframe, EventElement, EventNode};
+
+const FILE_SIGNATURE: &[u8] = b"ElfFile\0";
+const CHUNK_SIGNATURE: &[u8] = b"ElfChnk\0";
+const FILE_HEADER_BLOCK: usize = 4096;
+const CHUNK_SIZE: usize = 65536;
+/// Records follow the chunk header, string table, and template table.
+const CHUNK_RECORDS_START: usize = 512;
+const RECORD_SIGNATURE: u32 = 0x0000_2a2a;
+const RECORD_HEADER_SIZE: usize = 24;
+/// Guards against malformed, self-referencing templates.
+const MAX_NESTING: usize = 32;
+/// Seconds between 1601-01-01 (FILETIME epoch) and 1970-01-01.
+const FILETIME_UNIX_OFFSET: i64 = 11_644_473_600;
+
+// Binary XML tokens; `TOKEN_HAS_MORE` marks elements with attributes and
+// values/attributes that are followed by more of the same kind.
+const TOKEN_EOF: u8 = 0x00;
+const TOKEN_OPEN_START_ELEMENT: u8 = 0x01;
+const TOKEN_CLOSE_START_ELEMENT: u8 = 0x02;
+const TOKEN_CLOSE_EMPTY_ELEMENT: u8 = 0x03;
+const TOKEN_END_ELEMENT: u8 = 0x04;
+const TOKEN_VALUE: u8 = 0x05;
+const TOKEN_ATTRIBUTE: u8 = 0x06;
+const TOKEN_CDATA_SECTION: u8 = 0x07;
+const TOKEN_CHAR_REF: u8 = 0x08;
+const TOKEN_ENTITY_REF: u8 = 0x09;
+const TOKEN_PI_TARGET: u8 = 0x0a;
+const TOKEN_PI_DATA: u8 = 0x0b;
+const TOKEN_TEMPLATE_INSTANCE: u8 = 0x0c;
+const TOKEN_NORMAL_SUBSTITUTION: u8 = 0x0d;
+const TOKEN_OPTIONAL_SUBSTITUTION: u8 = 0x0e;
+const TOKEN_FRAGMENT_HEADER: u8 = 0x0f;
+const TOKEN_HAS_MORE: u8 = 0x40;
+
+// Substitution value types.
+const VALUE_NULL: u8 = 0x00;
+const VALUE_WSTRING: u8 = 0x01;
+const VALUE_STRING: u8 = 0x02;
+const VALUE_INT8: u8 = 0x03;
+const VALUE_UINT8: u8 = 0x04;
+const VALUE_INT16: u8 = 0x05;
+const VALUE_UINT16: u8 = 0x06;
+const VALUE_INT32: u8 = 0x07;
+const VALUE_UINT32: u8 = 0x08;
+const VALUE_INT64: u8 = 0x09;
+const VALUE_UINT64: u8 = 0x0a;
+const VALUE_REAL32: u8 = 0x0b;
+const VALUE_REAL64: u8 = 0x0c;
+const VALUE_BOOL: u8 = 0x0d;
+const VALUE_BINARY: u8 = 0x0e;
+const VALUE_GUID: u8 = 0x0f;
+const VALUE_SIZE_T: u8 = 0x10;
+const VALUE_FILETIME: u8 = 0x11;
+const VALUE_SYSTEMTIME: u8 = 0x12;
+const VALUE_SID: u8 = 0x13;
+const VALUE_HEX_INT32: u8 = 0x14;
+const VALUE_HEX_INT64: u8 = 0x15;
+const VALUE_BINXML: u8 = 0x21;
+const VALUE_ARRAY_FLAG: u8 = 0x80;
+
+/// Template body with placeholders for the instance's substitution values.
+#[derive(Debug)]
+enum TemplateNode {
+    Element {
+        name: String,
+        attrs: Vec<(String, Vec<TemplateNode>)>,
+        children: Vec<TemplateNode>,
+    },
+    Text(String),
+    Substitution(usize),
+    /// A nested template instance, already rendered.
+    Rendered(Vec<EventNode>),
+}
+
+#[derive(Debug)]
+enum SubstitutionValue {
+    Null,
+    Text(String),
+    Xml(Vec<EventNode>),
+}
+
+fn utf16_text(bytes: &[u8]) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| u16::from_le_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+        .trim_end_matches('\0')
+        .to_string()
+}
+
+fn hex_bytes(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02X}", byte)).collect()
+}
+
+fn filetime_text(value: u64) -> Option<String> {
+    let ticks = value as i64;
+    let seconds = ticks / 10_000_000 - FILETIME_UNIX_OFFSET;
+    let fraction = ticks % 10_000_000;
+    let timestamp = DateTime::from_timestamp(seconds, 0)?;
+    Some(format!(
+        "{}.{:07}Z",
+        timestamp.format("%Y-%m-%dT%H:%M:%S"),
+        fraction
+    ))
+}
+
+fn systemtime_text(bytes: &[u8]) -> String {
+    let field = |index: usize| u16::from_le_bytes([bytes[index * 2], bytes[index * 2 + 1]]);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+        field(0),
+        field(1),
+        field(3),
+        field(4),
+        field(5),
+        field(6),
+        field(7)
+    )
+}
+
+fn guid_text(bytes: &[u8]) -> String {
+    format!(
+        "{{{:08X}-{:04X}-{:04X}-{}-{}}}",
+        u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]),
+        u16::from_le_bytes([bytes[4], bytes[5]]),
+        u16::from_le_bytes([bytes[6], bytes[7]]),
+        hex_bytes(&bytes[8..10]),
+        hex_bytes(&bytes[10..16])
+    )
+}
+
+fn sid_text(bytes: &[u8]) -> Option<String> {
+    let revision = *bytes.first()?;
+    let count = usize::from(*bytes.get(1)?);
+    let authority = bytes
+        .get(2..8)?
+        .iter()
+        .fold(0u64, |acc, byte| (acc << 8) | u64::from(*byte));
+    let mut text = format!("S-{}-{}", revision, authority);
+    for index in 0..count {
+        let start = 8 + index * 4;
+        let part = bytes.get(start..start + 4)?;
+        text.push_str(&format!(
+            "-{}",
+            u32::from_le_bytes([part[0], part[1], part[2], part[3]])
+        ));
+    }
+    Some(text)
+}
+
+/// Fixed element size of a value type, used to split array values.
+fn fixed_value_size(value_type: u8) -> Option<usize> {
+    match value_type {
+        VALUE_INT8 | VALUE_UINT8 => Some(1),
+        VALUE_INT16 | VALUE_UINT16 => Some(2),
+        VALUE_INT32 | VALUE_UINT32 | VALUE_REAL32 | VALUE_BOOL | VALUE_HEX_INT32 => Some(4),
+        VALUE_INT64 | VALUE_UINT64 | VALUE_REAL64 | VALUE_FILETIME | VALUE_HEX_INT64 => Some(8),
+        VALUE_GUID | VALUE_SYSTEMTIME => Some(16),
+        _ => None,
+    }
+}
+
+/// Renders a scalar substitution value the way Windows renders it in XML.
+fn scalar_text(value_type: u8, bytes: &[u8]) -> Option<String> {
+    let array = |len: usize| -> Option<[u8; 8]> {
+        let mut buf = [0u8; 8];
+        buf[..len].copy_from_slice(bytes.get(..len)?);
+        Some(buf)
+    };
+    let text = match value_type {
+        VALUE_WSTRING => utf16_text(bytes),
+        VALUE_STRING => String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .to_string(),
+        VALUE_INT8 => (*bytes.first()? as i8).to_string(),
+        VALUE_UINT8 => bytes.first()?.to_string(),
+        VALUE_INT16 => i16::from_le_bytes([*bytes.first()?, *bytes.get(1)?]).to_string(),
+        VALUE_UINT16 => u16::from_le_bytes([*bytes.first()?, *bytes.get(1)?]).to_string(),
+        VALUE_INT32 => (u64::from_le_bytes(array(4)?) as u32 as i32).to_string(),
+        VALUE_UINT32 => (u64::from_le_bytes(array(4)?) as u32).to_string(),
+        VALUE_INT64 => i64::from_le_bytes(array(8)?).to_string(),
+        VALUE_UINT64 => u64::from_le_bytes(array(8)?).to_string(),
+        VALUE_REAL32 => f32::from_bits(u64::from_le_bytes(array(4)?) as u32).to_string(),
+        VALUE_REAL64 => f64::from_bits(u64::from_le_bytes(array(8)?)).to_string(),
+        VALUE_BOOL => (bytes.iter().any(|byte| *byte != 0)).to_string(),
+        VALUE_BINARY => hex_bytes(bytes),
+        VALUE_GUID if bytes.len() >= 16 => guid_text(bytes),
+        VALUE_SIZE_T | VALUE_HEX_INT32 | VALUE_HEX_INT64 => {
+            format!("0x{:x}", u64::from_le_bytes(array(bytes.len().min(8))?))
+        }
+        VALUE_FILETIME => filetime_text(u64::from_le_bytes(array(8)?))?,
+        VALUE_SYSTEMTIME if bytes.len() >= 16 => systemtime_text(bytes),
+        VALUE_SID => sid_text(bytes)?,
+        _ => hex_bytes(bytes),
+    };
+    Some(text)
+}
+
+fn array_text(value_type: u8, bytes: &[u8]) -> String {
+    let items: Vec<String> = match (value_type, fixed_value_size(value_type)) {
+        (VALUE_WSTRING, _) => utf16_text(bytes).split('\0').map(str::to_string).collect(),
+        (VALUE_STRING, _) => String::from_utf8_lossy(bytes)
+            .trim_end_matches('\0')
+            .split('\0')
+            .map(str::to_string)
+            .collect(),
+        (_, Some(size)) => bytes
+            .chunks_exact(size)
+            .filter_map(|item| scalar_text(value_type, item))
+            .collect(),
+        _ => vec![hex_bytes(bytes)],
+    };
+    items.join(", ")
+}
+
+/// Decodes the binary XML of one 64 KiB chunk. All name and template
+/// offsets inside a chunk are relative to its start.
+struct ChunkParser<'a> {
+    data: &'a [u8],
+    names: HashMap<usize, String>,
+    templates: HashMap<usize, Rc<Vec<TemplateNode>>>,
+}
+
+impl<'a> ChunkParser<'a> {
+    fn new(data: &'a [u8]) -> Self {
+        Self {
+            data,
+            names: HashMap::new(),
+            templates: HashMap::new(),
+        }
+    }
+
+    fn bytes(&self, offset: usize, len: usize) -> Result<&'a [u8]> {
+        self.data
+            .get(offset..offset + len)
+            .ok_or_else(|| anyhow!("record data runs past the end of the chunk"))
+    }
+
+    fn u8_at(&self, offset: usize) -> Result<u8> {
+        Ok(self.bytes(offset, 1)?[0])
+    }
+
+    fn u16_at(&self, offset: usize) -> Result<u16> {
+        let bytes = self.bytes(offset, 2)?;
+        Ok(u16::from_le_bytes([bytes[0], bytes[1]]))
+    }
+
+    fn u32_at(&self, offset: usize) -> Result<u32> {
+        let bytes = self.bytes(offset, 4)?;
+        Ok(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]))
+    }
+
+    /// Name strings: next-offset (4), hash (2), character count (2), UTF-16 text.
+    fn name_at(&mut self, offset: usize) -> Result<String> {
+        if let Some(name) = self.names.get(&offset) {
+            return Ok(name.clone());
+        }
+        let count = usize::from(self.u16_at(offset + 6)?);
+        let name = utf16_text(self.bytes(offset + 8, count * 2)?);
+        self.names.insert(offset, name.clone());
+        Ok(name)
+    }
+
+    /// Reads a name reference, skipping the string when it is defined inline.
+    fn read_name(&mut self, pos: &mut usize) -> Result<String> {
+        let offset = self.u32_at(*pos)? as usize;
+        *pos += 4;
+        let name = self.name_at(offset)?;
+        if offset == *pos {
+            let count = usize::from(self.u16_at(offset + 6)?);
+            *pos += 8 + (count + 1) * 2;
+        }
+        Ok(name)
+    }
+
+    /// Parses nodes until `EOF`, or until the matching end element when
+    /// `inside_element` is set.
+    fn parse_content(
+        &mut self,
+        pos: &mut usize,
+        depth: usize,
+        inside_element: bool,
+    ) -> Result<Vec<TemplateNode>> {
+        if depth > MAX_NESTING {
+            bail!("binary XML is nested too deeply");
+        }
+        let mut nodes = Vec::new();
+        loop {
+            let token = self.u8_at(*pos)?;
+            match token & !TOKEN_HAS_MORE {
+                TOKEN_EOF => {
+                    *pos += 1;
+                    if inside_element {
+                        bail!("element is not closed before end of fragment");
+                    }
+                    return Ok(nodes);
+                }
+                TOKEN_END_ELEMENT => {
+                    *pos += 1;
+                    if !inside_element {
+                        bail!("unexpected end element");
+                    }
+                    return Ok(nodes);
+                }
+                TOKEN_FRAGMENT_HEADER => *pos += 4,
+                TOKEN_OPEN_START_ELEMENT => nodes.push(self.parse_element(pos, depth)?),
+                TOKEN_VALUE
+                | TOKEN_NORMAL_SUBSTITUTION
+                | TOKEN_OPTIONAL_SUBSTITUTION
+                | TOKEN_CHAR_REF
+                | TOKEN_ENTITY_REF
+                | TOKEN_CDATA_SECTION => nodes.push(self.parse_value(pos)?),
+                TOKEN_PI_TARGET => {
+                    *pos += 1;
+                    self.read_name(pos)?;
+                }
+                TOKEN_PI_DATA => {
+                    let len = usize::from(self.u16_at(*pos + 1)?);
+                    *pos += 3 + len * 2;
+                }
+                TOKEN_TEMPLATE_INSTANCE => {
+                    let rendered = self.parse_template_instance(pos, depth + 1)?;
+                    nodes.push(TemplateNode::Rendered(rendered));
+                }
+                other => bail!("unsupported binary XML token 0x{:02x}", other),
+            }
+        }
+    }
+
+    fn parse_element(&mut self, pos: &mut usize, depth: usize) -> Result<TemplateNode> {
+        let has_attributes = self.u8_at(*pos)? & TOKEN_HAS_MORE != 0;
+        // Token, dependency identifier, and element data size.
+        *pos += 1 + 2 + 4;
+        let name = self.read_name(pos)?;
+        if has_attributes {
+            *pos += 4;
+        }
+        let mut attrs = Vec::new();
+        while self.u8_at(*pos)? & !TOKEN_HAS_MORE == TOKEN_ATTRIBUTE {
+            *pos += 1;
+            let attr_name = self.read_name(pos)?;
+            let mut value = Vec::new();
+            while matches!(
+                self.u8_at(*pos)? & !TOKEN_HAS_MORE,
+                TOKEN_VALUE
+                    | TOKEN_NORMAL_SUBSTITUTION
+                    | TOKEN_OPTIONAL_SUBSTITUTION
+                    | TOKEN_CHAR_REF
+                    | TOKEN_ENTITY_REF
+            ) {
+                value.push(self.parse_value(pos)?);
+            }
+            attrs.push((attr_name, value));
+        }
+        let close = self.u8_at(*pos)?;
+        *pos += 1;
+        let children = match close {
+            TOKEN_CLOSE_START_ELEMENT => self.parse_content(pos, depth + 1, true)?,
+            TOKEN_CLOSE_EMPTY_ELEMENT => Vec::new(),
+            other => bail!("unexpected token 0x{:02x} after element {}", other, name),
+        };
+        Ok(TemplateNode::Element {
+            name,
+            attrs,
+            children,
+        })
+    }
+
+    fn parse_value(&mut self, pos: &mut usize) -> Result<TemplateNode> {
+        let token = self.u8_at(*pos)? & !TOKEN_HAS_MORE;
+        *pos += 1;
+        match token {
+            TOKEN_VALUE => {
+                let value_type = self.u8_at(*pos)?;
+                *pos += 1;
+                if value_type != VALUE_WSTRING {
+                    bail!("unsupported inline value type 0x{:02x}", value_type);
+                }
+                let len = usize::from(self.u16_at(*pos)?) * 2;
+                let text = utf16_text(self.bytes(*pos + 2, len)?);
+                *pos += 2 + len;
+                Ok(TemplateNode::Text(text))
+            }
+            TOKEN_NORMAL_SUBSTITUTION | TOKEN_OPTIONAL_SUBSTITUTION => {
+                let index = usize::from(self.u16_at(*pos)?);
+                // Substitution id and the (informational) value type.
+                *pos += 3;
+                Ok(TemplateNode::Substitution(index))
+            }
+            TOKEN_CHAR_REF => {
+                let code = self.u16_at(*pos)?;
+                *pos += 2;
+                Ok(TemplateNode::Text(
+                    char::from_u32(u32::from(code))
+                        .map(String::from)
+                        .unwrap_or_default(),
+                ))
+            }
+            TOKEN_ENTITY_REF => {
+                let name = self.read_name(pos)?;
+                let text = match name.as_str() {
+                    "amp" => "&",
+                    "lt" => "<",
+                    "gt" => ">",
+                    "quot" => "\"",
+                    "apos" => "'",
+                    _ => "",
+                };
+                Ok(TemplateNode::Text(text.to_string()))
+            }
+            _ => {
+                let len = usize::from(self.u16_at(*pos)?) * 2;
+                let text = utf16_text(self.bytes(*pos + 2, len)?);
+                *pos += 2 + len;
+                Ok(TemplateNode::Text(text))
+            }
+        }
+    }
+
+    /// Template definitions: next-offset (4), GUID (16), data size (4), body.
+    fn template_at(&mut self, offset: usize, depth: usize) -> Result<Rc<Vec<TemplateNode>>> {
+        if let Some(template) = self.templates.get(&offset) {
+            return Ok(Rc::clone(template));
+        }
+        let mut body = offset + 24;
+        let template = Rc::new(self.parse_content(&mut body, depth, false)?);
+        self.templates.insert(offset, Rc::clone(&template));
+        Ok(template)
+    }
+
+    fn parse_template_instance(&mut self, pos: &mut usize, depth: usize) -> Result<Vec<EventNode>> {
+        // Token, unknown byte, template id.
+        *pos += 1 + 1 + 4;
+        let definition = self.u32_at(*pos)? as usize;
+        *pos += 4;
+        if definition == *pos {
+            let size = self.u32_at(definition + 20)? as usize;
+            *pos += 24 + size;
+        }
+        let template = self.template_at(definition, depth)?;
+
+        let count = self.u32_at(*pos)? as usize;
+        *pos += 4;
+        let descriptors = self.bytes(*pos, count * 4)?;
+        *pos += count * 4;
+        let mut values = Vec::with_capacity(count);
+        for descriptor in descriptors.chunks_exact(4) {
+            let size = usize::from(u16::from_le_bytes([descriptor[0], descriptor[1]]));
+            let value_type = descriptor[2];
+            values.push(self.substitution_value(value_type, *pos, size, depth)?);
+            *pos += size;
+        }
+        Ok(instantiate(&template, &values))
+    }
+
+    fn substitution_value(
+        &mut self,
+        value_type: u8,
+        offset: usize,
+        size: usize,
+        depth: usize,
+    ) -> Result<SubstitutionValue> {
+        if value_type == VALUE_NULL || size == 0 {
+            return Ok(SubstitutionValue::Null);
+        }
+        if value_type == VALUE_BINXML {
+            let mut nested = offset;
+            let nodes = self.parse_content(&mut nested, depth + 1, false)?;
+            return Ok(SubstitutionValue::Xml(instantiate(&nodes, &[])));
+        }
+        let bytes = self.bytes(offset, size)?;
+        let text = if value_type & VALUE_ARRAY_FLAG != 0 {
+            array_text(value_type & !VALUE_ARRAY_FLAG, bytes)
+        } else {
+            scalar_text(value_type, bytes).unwrap_or_else(|| hex_bytes(bytes))
+        };
+        Ok(SubstitutionValue::Text(text))
+    }
+
+    /// Renders the record whose header starts at `offset`, returning its `<Event>`.
+    fn parse_record(&mut self, offset: usize) -> Result<Option<EventElement>> {
+        let mut pos = offset + RECORD_HEADER_SIZE;
+        let nodes = self.parse_content(&mut pos, 0, false)?;
+        Ok(instantiate(&nodes, &[])
+            .into_iter()
+            .find_map(|node| match node {
+                EventNode::Element(element) if element.name == "Event" => Some(element),
+                _ => None,
+            }))
+    }
+}
+
+fn attribute_text(nodes: &[TemplateNode], values: &[SubstitutionValue]) -> String {
+    instantiate(nodes, values)
+        .into_iter()
+        .map(|node| match node {
+            EventNode::Text(text) => text,
+            EventNode::Element(element) => element.text(),
+        })
+        .collect()
+}
+
+fn instantiate(nodes: &[TemplateNode], values: &[SubstitutionValue]) -> Vec<EventNode> {
+    let mut out = Vec::new();
+    for node in nodes {
+        match node {
+            TemplateNode::Element {
+                name,
+                attrs,
+                children,
+            } => out.push(EventNode::Element(EventElement {
+                name: name.clone(),
+                attrs: attrs
+                    .iter()
+                    .map(|(key, value)| (key.clone(), attribute_text(value, values)))
+                    .filter(|(_, value)| !value.is_empty())
+                    .collect(),
+                children: instantiate(children, values),
+            })),
+            TemplateNode::Text(text) => out.push(EventNode::Text(text.clone())),
+            TemplateNode::Substitution(index) => match values.get(*index) {
+                Some(SubstitutionValue::Text(text)) => out.push(EventNode::Text(text.clone())),
+                Some(SubstitutionValue::Xml(nodes)) => out.extend(nodes.iter().cloned()),
+                Some(SubstitutionValue::Null) | None => {}
+            },
+            TemplateNode::Rendered(nodes) => out.extend(nodes.iter().cloned()),
+        }
+    }
+    out
+}
+
+/// Reads a Windows `.evtx` log into one row per event record: well-known
+/// `System` fields followed by the flattened `EventData`/`UserData` values.
+/// Records that cannot be decoded are skipped and counted in the log.
+pub fn read_evtx_dataframe(path: &Path) -> Result<DataFrame> {
+    let data = fs::read(path).with_context(|| format!("failed to read {:?}", path))?;
+    if data.len() < FILE_HEADER_BLOCK || !data.starts_with(FILE_SIGNATURE) {
+        bail!("{:?} is not an EVTX file", path);
+    }
+    let mut rows = Vec::new();
+    let mut skipped = 0usize;
+    // Dirty logs may hold more chunks than the header counts, so scan every
+    // block. A log copied while being written may end inside a chunk; the
+    // records before the cut are still read.
+    for chunk in data[FILE_HEADER_BLOCK..].chunks(CHUNK_SIZE) {
+        if !chunk.starts_with(CHUNK_SIGNATURE) {
+            continue;
+        }
+        let mut parser = ChunkParser::new(chunk);
+        let free_space = parser
+            .u32_at(48)
+            .map_or(0, |free_space| free_space as usize)
+            .min(chunk.len());
+        let mut offset = CHUNK_RECORDS_START;
+        while offset + RECORD_HEADER_SIZE <= free_space {
+            if parser.u32_at(offset)? != RECORD_SIGNATURE {
+                break;
+            }
+            let size = parser.u32_at(offset + 4)? as usize;
+            if size < RECORD_HEADER_SIZE + 4 || offset + size > chunk.len() {
+                break;
+            }
+            match parser.parse_record(offset) {
+                Ok(Some(event)) => rows.push(event_row(&event)),
+                Ok(None) | Err(_) => skipped += 1,
+            }
+            offset += size;
+        }
+    }
+    if skipped > 0 {
+        tracing::warn!("skipped {} undecodable records in {:?}", skipped, path);
+    }
+    events_dataframe(rows)
+}
@@ -0,0 +1,135 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result};
+
+/// Rows rendered at once; a batch that would overflow the size limit is
+/// halved until it fits.
+const RENDER_BATCH_ROWS: usize = 1024;
+
+/// Where an export is cut into numbered part files. Without limits the
+/// export is a single file at the chosen destination.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SplitLimits {
+    pub max_rows: Option<usize>,
+    pub max_bytes: Option<u64>,
+}
+
+impl SplitLimits {
+    pub fn is_split(&self) -> bool {
+        self.max_rows.is_some() || self.max_bytes.is_some()
+    }
+}
+
+/// `events.csv` becomes `events-part001.csv`, `events-part002.csv`, ...
+pub fn part_path(destination: &Path, number: usize) -> PathBuf {
+    let stem = destination
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "export".to_string());
+    let name = match destination.extension() {
+        Some(extension) => format!("{}-part{:03}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-part{:03}", stem, number),
+    };
+    destination.with_file_name(name)
+}
+
+struct Part {
+    writer: BufWriter<File>,
+    bytes: u64,
+    rows: usize,
+}
+
+impl Part {
+    fn create(path: &Path, header: &[u8]) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create export file {:?}", path))?;
+        let mut writer = BufWriter::new(file);
+        writer.write_all(header)?;
+        Ok(Self {
+            writer,
+            bytes: header.len() as u64,
+            rows: 0,
+        })
+    }
+
+    fn finish(mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Writes a line-oriented export, starting every part with `header`.
+/// `render` produces the bytes of a range of rows. A part never goes over
+/// `max_bytes` unless a single row is larger than that on its own.
+pub fn write_line_parts(
+    destination: &Path,
+    limits: SplitLimits,
+    header: &[u8],
+    rows: usize,
+    mut render: impl FnMut(Range<usize>) -> Result<Vec<u8>>,
+) -> Result<Vec<PathBuf>> {
+    let mut paths = Vec::new();
+    let mut current: Option<Part> = None;
+    let mut start = 0;
+    loop {
+        let part = match current {
+            Some(ref mut part) => part,
+            None => {
+                let path = if limits.is_split() {
+                    part_path(destination, paths.len() + 1)
+                } else {
+                    destination.to_path_buf()
+                };
+                let part = current.insert(Part::create(&path, header)?);
+                paths.push(path);
+                part
+            }
+        };
+        if start >= rows {
+            break;
+        }
+        let room = limits
+            .max_rows
+            .map_or(usize::MAX, |max| max.saturating_sub(part.rows));
+        let mut count = RENDER_BATCH_ROWS.min(rows - start).min(room).max(1);
+        let full = loop {
+            let chunk = render(start..start + count)?;
+            let fits = limits
+                .max_bytes
+                .is_none_or(|max| part.bytes + chunk.len() as u64 <= max);
+            if fits || (part.rows == 0 && count == 1) {
+                part.writer.write_all(&chunk)?;
+                part.bytes += chunk.len() as u64;
+                part.rows += count;
+                start += count;
+                break limits.max_rows.is_some_and(|max| part.rows >= max);
+            }
+            if count == 1 {
+                break true;
+            }
+            count /= 2;
+        };
+        if full && start < rows {
+            if let Some(part) = current.take() {
+                part.finish()?;
+            }
+        }
+    }
+    if let Some(part) = current.take() {
+        part.finish()?;
+    }
+    Ok(paths)
+}
+
+/// Row ranges of the parts when only a row limit applies, as used for
+/// formats written whole (workbooks, Parquet). Always at least one range.
+pub fn row_parts(rows: usize, max_rows: Option<usize>) -> Vec<Range<usize>> {
+    let max = max_rows.unwrap_or(usize::MAX).max(1);
+    (0..rows.max(1))
+        .step_by(max)
+        .map(|start| start..start.saturating_add(max).min(rows))
+        .collect()
+}
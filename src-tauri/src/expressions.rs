@@ -0,0 +1,498 @@
+//! Computed columns: a small expression language evaluated column by column
+//! over the project data.
+//!
+//! ```text
+//! extract(path, '[^\\/]+$')          file name of a full path
+//! end - start                        seconds between two times
+//! host || '/' || upper(user)         text joined from several columns
+//! ```
+//!
+//! Columns are named bare (`src_ip`) or in backticks when the name holds
+//! other characters (`` `c-ip` ``); text is written in single or double
+//! quotes. `+ - * / %` work on numbers and read date/time values and
+//! timestamp text as seconds since the epoch, so the difference of two
+//! times is a duration in seconds. `||` joins values as text, reading empty
+//! values as empty text. The functions are `substr(text, start[, length])`
+//! (1-based; a negative start counts from the end), `extract(text,
+//! 'regex'[, group])` (the first capture group by default), `lower(text)`
+//! and `upper(text)`.
+
+use anyhow::{anyhow, bail, Result};
+use polars::prelude::{DataFrame, DataType, NamedFrom, Series};
+use regex::Regex;
+
+use crate::{
+    column_types::{parse_datetime, timeline_instants},
+    value_utils::anyvalue_to_search_string,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Column(String),
+    Text(String),
+    Number(f64),
+    Op(char),
+    Concat,
+    Open,
+    Close,
+    Comma,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum ArithmeticOp {
+    Add,
+    Sub,
+    Mul,
+    Div,
+    Rem,
+}
+
+#[derive(Debug)]
+enum Expr {
+    Column(String),
+    Text(String),
+    Number(f64),
+    Negate(Box<Expr>),
+    Arithmetic(Box<Expr>, ArithmeticOp, Box<Expr>),
+    Concat(Box<Expr>, Box<Expr>),
+    Substr {
+        text: Box<Expr>,
+        start: Box<Expr>,
+        length: Option<Box<Expr>>,
+    },
+    Extract {
+        text: Box<Expr>,
+        pattern: Regex,
+        group: usize,
+    },
+    Lower(Box<Expr>),
+    Upper(Box<Expr>),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            _ if c.is_whitespace() => i += 1,
+            '(' | ')' | ',' => {
+                tokens.push(match c {
+                    '(' => Token::Open,
+                    ')' => Token::Close,
+                    _ => Token::Comma,
+                });
+                i += 1;
+            }
+            '+' | '-' | '*' | '/' | '%' => {
+                tokens.push(Token::Op(c));
+                i += 1;
+            }
+            '|' if chars.get(i + 1) == Some(&'|') => {
+                tokens.push(Token::Concat);
+                i += 2;
+            }
+            '\'' | '"' | '`' => {
+                let mut value = String::new();
+                let mut j = i + 1;
+                loop {
+                    match chars.get(j) {
+                        None => bail!("unterminated {} at position {}", c, i + 1),
+                        // A doubled quote stands for the quote itself.
+                        Some(&q) if q == c && chars.get(j + 1) == Some(&c) => {
+                            value.push(c);
+                            j += 2;
+                        }
+                        Some(&q) if q == c => break,
+                        Some(&other) => {
+                            value.push(other);
+                            j += 1;
+                        }
+                    }
+                }
+                tokens.push(if c == '`' {
+                    Token::Column(value)
+                } else {
+                    Token::Text(value)
+                });
+                i = j + 1;
+            }
+            _ if c.is_ascii_digit()
+                || (c == '.' && chars.get(i + 1).is_some_and(char::is_ascii_digit)) =>
+            {
+                let start = i;
+                while i < chars.len() && (chars[i].is_ascii_digit() || chars[i] == '.') {
+                    i += 1;
+                }
+                let number: String = chars[start..i].iter().collect();
+                let value = number
+                    .parse()
+                    .map_err(|_| anyhow!("invalid number {} at position {}", number, start + 1))?;
+                tokens.push(Token::Number(value));
+            }
+            _ if c.is_alphanumeric() || c == '_' => {
+                let start = i;
+                while i < chars.len()
+                    && (chars[i].is_alphanumeric() || matches!(chars[i], '_' | '.'))
+                {
+                    i += 1;
+                }
+                tokens.push(Token::Ident(chars[start..i].iter().collect()));
+            }
+            _ => bail!("unexpected {:?} at position {}", c, i + 1),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<()> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            _ => Err(anyhow!("expected {}", what)),
+        }
+    }
+
+    /// `||` binds loosest, so `a || b + c` joins `a` to a sum.
+    fn concat(&mut self) -> Result<Expr> {
+        let mut expr = self.additive()?;
+        while self.peek() == Some(&Token::Concat) {
+            self.pos += 1;
+            expr = Expr::Concat(Box::new(expr), Box::new(self.additive()?));
+        }
+        Ok(expr)
+    }
+
+    fn additive(&mut self) -> Result<Expr> {
+        let mut expr = self.multiplicative()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('+')) => ArithmeticOp::Add,
+                Some(Token::Op('-')) => ArithmeticOp::Sub,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Arithmetic(Box::new(expr), op, Box::new(self.multiplicative()?));
+        }
+    }
+
+    fn multiplicative(&mut self) -> Result<Expr> {
+        let mut expr = self.unary()?;
+        loop {
+            let op = match self.peek() {
+                Some(Token::Op('*')) => ArithmeticOp::Mul,
+                Some(Token::Op('/')) => ArithmeticOp::Div,
+                Some(Token::Op('%')) => ArithmeticOp::Rem,
+                _ => return Ok(expr),
+            };
+            self.pos += 1;
+            expr = Expr::Arithmetic(Box::new(expr), op, Box::new(self.unary()?));
+        }
+    }
+
+    fn unary(&mut self) -> Result<Expr> {
+        if self.peek() == Some(&Token::Op('-')) {
+            self.pos += 1;
+            return Ok(Expr::Negate(Box::new(self.unary()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<Expr> {
+        match self.next() {
+            Some(Token::Number(value)) => Ok(Expr::Number(value)),
+            Some(Token::Text(value)) => Ok(Expr::Text(value)),
+            Some(Token::Column(name)) => Ok(Expr::Column(name)),
+            Some(Token::Ident(name)) if self.peek() == Some(&Token::Open) => {
+                self.pos += 1;
+                self.call(&name)
+            }
+            Some(Token::Ident(name)) => Ok(Expr::Column(name)),
+            Some(Token::Open) => {
+                let expr = self.concat()?;
+                self.expect(Token::Close, "`)`")?;
+                Ok(expr)
+            }
+            Some(token) => Err(anyhow!("unexpected {:?}", token)),
+            None => Err(anyhow!("the expression ends too early")),
+        }
+    }
+
+    /// Arguments of a call whose `(` was consumed, through its `)`.
+    fn arguments(&mut self) -> Result<Vec<Expr>> {
+        let mut args = Vec::new();
+        if self.peek() == Some(&Token::Close) {
+            self.pos += 1;
+            return Ok(args);
+        }
+        loop {
+            args.push(self.concat()?);
+            match self.next() {
+                Some(Token::Comma) => continue,
+                Some(Token::Close) => return Ok(args),
+                _ => bail!("expected `,` or `)` in the arguments"),
+            }
+        }
+    }
+
+    fn call(&mut self, name: &str) -> Result<Expr> {
+        let function = name.to_ascii_lowercase();
+        let mut args = self.arguments()?.into_iter();
+        let count = args.len();
+        let expr = match (function.as_str(), count) {
+            ("substr", 2 | 3) => Expr::Substr {
+                text: Box::new(args.next().expect("argument count checked")),
+                start: Box::new(args.next().expect("argument count checked")),
+                length: args.next().map(Box::new),
+            },
+            ("extract", 2 | 3) => {
+                let text = args.next().expect("argument count checked");
+                let Some(Expr::Text(pattern)) = args.next() else {
+                    bail!("the pattern of extract() must be quoted text");
+                };
+                let pattern = Regex::new(&pattern)
+                    .map_err(|err| anyhow!("invalid pattern {:?}: {}", pattern, err))?;
+                let group = match args.next() {
+                    None => usize::from(pattern.captures_len() > 1),
+                    Some(Expr::Number(group)) if group.fract() == 0.0 && group >= 0.0 => {
+                        group as usize
+                    }
+                    Some(_) => bail!("the group of extract() must be a whole number"),
+                };
+                if group >= pattern.captures_len() {
+                    bail!("the pattern of extract() has no group {}", group);
+                }
+                Expr::Extract {
+                    text: Box::new(text),
+                    pattern,
+                    group,
+                }
+            }
+            ("lower", 1) => Expr::Lower(Box::new(args.next().expect("argument count checked"))),
+            ("upper", 1) => Expr::Upper(Box::new(args.next().expect("argument count checked"))),
+            ("substr" | "extract" | "lower" | "upper", _) => {
+                bail!("{}() does not take {} arguments", function, count)
+            }
+            _ => bail!("unknown function {}()", name),
+        };
+        Ok(expr)
+    }
+}
+
+fn parse(text: &str) -> Result<Expr> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+    if parser.tokens.is_empty() {
+        bail!("the expression is empty");
+    }
+    let expr = parser.concat()?;
+    if let Some(token) = parser.peek() {
+        bail!("unexpected {:?} after the expression", token);
+    }
+    Ok(expr)
+}
+
+/// Values of a column read as numbers: numeric types as they are, times as
+/// seconds since the epoch, and text as a number or else a timestamp.
+fn numbers(series: &Series) -> Result<Vec<Option<f64>>> {
+    let dtype = series.dtype();
+    if dtype.is_numeric() || matches!(dtype, DataType::Boolean) {
+        let cast = series.cast(&DataType::Float64)?;
+        return Ok(cast.f64()?.into_iter().collect());
+    }
+    if matches!(dtype, DataType::Datetime(_, _) | DataType::Date) {
+        let instants = timeline_instants(series)?;
+        return Ok(instants
+            .into_iter()
+            .map(|micros| micros.map(|micros| micros as f64 / 1_000_000.0))
+            .collect());
+    }
+    Ok(series
+        .iter()
+        .map(|value| {
+            let text = anyvalue_to_search_string(&value)?;
+            let text = text.trim();
+            text.parse::<f64>().ok().or_else(|| {
+                parse_datetime(text).map(|time| time.and_utc().timestamp_micros() as f64 / 1e6)
+            })
+        })
+        .collect())
+}
+
+fn texts(series: &Series) -> Vec<Option<String>> {
+    series
+        .iter()
+        .map(|value| anyvalue_to_search_string(&value))
+        .collect()
+}
+
+/// Characters of `text` from a 1-based `start`, counted from the end when
+/// negative.
+fn substring(text: &str, start: f64, length: Option<f64>) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let start = start.trunc() as i64;
+    let from = if start < 0 {
+        chars.len().saturating_sub(start.unsigned_abs() as usize)
+    } else {
+        (start.max(1) as usize - 1).min(chars.len())
+    };
+    let to = match length {
+        Some(length) => from + (length.max(0.0).trunc() as usize).min(chars.len() - from),
+        None => chars.len(),
+    };
+    chars[from..to].iter().collect()
+}
+
+fn text_series(values: Vec<Option<String>>) -> Series {
+    let values: Vec<Option<String>> = values
+        .into_iter()
+        .map(|value| value.filter(|value| !value.is_empty()))
+        .collect();
+    Series::new("", values)
+}
+
+fn evaluate(expr: &Expr, df: &DataFrame) -> Result<Series> {
+    let rows = df.height();
+    Ok(match expr {
+        Expr::Column(name) => {
+            if name.starts_with("__") {
+                bail!("unknown column {}", name);
+            }
+            df.column(name)
+                .map_err(|_| anyhow!("unknown column {}", name))?
+                .clone()
+        }
+        Expr::Text(value) => Series::new("", vec![value.as_str(); rows]),
+        Expr::Number(value) => Series::new("", vec![*value; rows]),
+        Expr::Negate(inner) => {
+            let values: Vec<Option<f64>> = numbers(&evaluate(inner, df)?)?
+                .into_iter()
+                .map(|value| value.map(|value| -value))
+                .collect();
+            Series::new("", values)
+        }
+        Expr::Arithmetic(left, op, right) => {
+            let left = Series::new("", numbers(&evaluate(left, df)?)?);
+            let right = Series::new("", numbers(&evaluate(right, df)?)?);
+            match op {
+                ArithmeticOp::Add => &left + &right,
+                ArithmeticOp::Sub => &left - &right,
+                ArithmeticOp::Mul => &left * &right,
+                ArithmeticOp::Div => &left / &right,
+                ArithmeticOp::Rem => &left % &right,
+            }
+        }
+        Expr::Concat(left, right) => {
+            let left = texts(&evaluate(left, df)?);
+            let right = texts(&evaluate(right, df)?);
+            let joined: Vec<Option<String>> = left
+                .into_iter()
+                .zip(right)
+                .map(|(left, right)| {
+                    Some(left.unwrap_or_default() + right.as_deref().unwrap_or_default())
+                })
+                .collect();
+            text_series(joined)
+        }
+        Expr::Substr {
+            text,
+            start,
+            length,
+        } => {
+            let text = texts(&evaluate(text, df)?);
+            let start = numbers(&evaluate(start, df)?)?;
+            let length = match length {
+                Some(length) => numbers(&evaluate(length, df)?)?,
+                None => Vec::new(),
+            };
+            let values = text
+                .into_iter()
+                .zip(start)
+                .enumerate()
+                .map(|(row, (text, start))| {
+                    let length = match length.get(row) {
+                        Some(length) => Some((*length)?),
+                        None => None,
+                    };
+                    Some(substring(&text?, start?, length))
+                })
+                .collect();
+            text_series(values)
+        }
+        Expr::Extract {
+            text,
+            pattern,
+            group,
+        } => {
+            let values = texts(&evaluate(text, df)?)
+                .into_iter()
+                .map(|text| {
+                    let captures = pattern.captures(text.as_deref()?)?;
+                    captures.get(*group).map(|found| found.as_str().to_string())
+                })
+                .collect();
+            text_series(values)
+        }
+        Expr::Lower(inner) => text_series(
+            texts(&evaluate(inner, df)?)
+                .into_iter()
+                .map(|text| text.map(|text| text.to_lowercase()))
+                .collect(),
+        ),
+        Expr::Upper(inner) => text_series(
+            texts(&evaluate(inner, df)?)
+                .into_iter()
+                .map(|text| text.map(|text| text.to_uppercase()))
+                .collect(),
+        ),
+    })
+}
+
+/// Evaluates `expression` over every row of `df` into a new column named
+/// `column`. Numeric results are whole numbers when every value is one;
+/// results that are not finite, such as a division by zero, are left empty.
+pub fn compute_column(df: &DataFrame, expression: &str, column: &str) -> Result<Series> {
+    let expr = parse(expression)?;
+    let mut series = evaluate(&expr, df)?;
+    if series.dtype() == &DataType::Float64 {
+        let values: Vec<Option<f64>> = series
+            .f64()?
+            .into_iter()
+            .map(|value| value.filter(|value| value.is_finite()))
+            .collect();
+        series = if values
+            .iter()
+            .flatten()
+            .all(|value| value.fract() == 0.0 && value.abs() < i64::MAX as f64)
+        {
+            let whole: Vec<Option<i64>> = values
+                .into_iter()
+                .map(|value| value.map(|value| value as i64))
+                .collect();
+            Series::new("", whole)
+        } else {
+            Series::new("", values)
+        };
+    }
+    series.rename(column);
+    Ok(series)
+}
@@ -1,3 +1,7 @@
+use std::collections::HashMap;
+
+use crate::models::RowComment;
+
 pub fn normalize_flag_value(flag: &str) -> String {
     let trimmed = flag.trim();
     if trimmed.is_empty() {
@@ -22,3 +26,65 @@ pub fn severity_rank(value: &str) -> u8 {
         _ => 0,
     }
 }
+
+/// Renders a row's comments as its memo, one `timestamp author: text` line
+/// per comment; `None` when there are none.
+pub fn render_comments_memo(comments: &[RowComment]) -> Option<String> {
+    let lines: Vec<String> = comments
+        .iter()
+        .map(|comment| {
+            let timestamp = comment.timestamp.format("%Y-%m-%d %H:%M");
+            match &comment.author {
+                Some(author) => format!("{} {}: {}", timestamp, author, comment.text),
+                None => format!("{} {}", timestamp, comment.text),
+            }
+        })
+        .collect();
+    (!lines.is_empty()).then(|| lines.join("\n"))
+}
+
+/// Words too common in analyst notes to say anything about their content.
+const MEMO_STOPWORDS: &[&str] = &[
+    "a", "an", "and", "are", "as", "at", "be", "by", "for", "from", "has", "have", "in", "is",
+    "it", "its", "of", "on", "or", "that", "the", "this", "to", "was", "were", "with",
+];
+
+/// Splits a memo into lowercase terms, dropping punctuation, stopwords, pure
+/// numbers and single characters.
+pub fn tokenize_memo(memo: &str) -> impl Iterator<Item = String> + '_ {
+    memo.split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+        .map(|raw| {
+            raw.trim_matches(|c: char| c == '-' || c == '.')
+                .to_lowercase()
+        })
+        .filter(|term| {
+            term.chars().count() > 1
+                && !term.chars().all(|c| c.is_ascii_digit())
+                && !MEMO_STOPWORDS.contains(&term.as_str())
+        })
+}
+
+/// Counts term occurrences and the number of memos containing each term,
+/// ordered by occurrence count (then alphabetically).
+pub fn memo_term_frequencies<'a>(
+    memos: impl IntoIterator<Item = &'a str>,
+) -> Vec<(String, usize, usize)> {
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    for memo in memos {
+        let mut seen: Vec<String> = Vec::new();
+        for term in tokenize_memo(memo) {
+            let entry = counts.entry(term.clone()).or_default();
+            entry.0 += 1;
+            if !seen.contains(&term) {
+                entry.1 += 1;
+                seen.push(term);
+            }
+        }
+    }
+    let mut frequencies: Vec<(String, usize, usize)> = counts
+        .into_iter()
+        .map(|(term, (count, memos))| (term, count, memos))
+        .collect();
+    frequencies.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    frequencies
+}
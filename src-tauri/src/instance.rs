@@ -0,0 +1,121 @@
+use std::{
+    fs,
+    io::{BufRead, BufReader, Write},
+    net::{SocketAddr, TcpListener, TcpStream},
+    path::Path,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager, Runtime};
+
+use crate::{links::parse_launch_args, state::AppState};
+
+const PORT_FILE: &str = "instance.port";
+const HANDSHAKE: &str = "trivium-instance-v1";
+pub const INSTANCE_ACTIVATED_EVENT: &str = "instance-activated";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ForwardMessage {
+    handshake: String,
+    args: Vec<String>,
+}
+
+pub enum InstanceRole {
+    /// This process owns the workspace and listens for hand-offs from later launches.
+    Primary(TcpListener),
+    /// Another process is already running and has received this launch's arguments.
+    Forwarded,
+}
+
+/// Decides whether this launch becomes the running instance or forwards its
+/// arguments to an existing one over a loopback socket.
+pub fn acquire(base_dir: &Path, args: &[String]) -> Result<InstanceRole> {
+    let port_path = base_dir.join(PORT_FILE);
+    if let Some(port) = fs::read_to_string(&port_path)
+        .ok()
+        .and_then(|text| text.trim().parse::<u16>().ok())
+    {
+        if forward_args(port, args).is_ok() {
+            return Ok(InstanceRole::Forwarded);
+        }
+    }
+
+    let listener =
+        TcpListener::bind(("127.0.0.1", 0)).context("failed to bind single-instance socket")?;
+    let port = listener.local_addr()?.port();
+    fs::write(&port_path, port.to_string())
+        .with_context(|| format!("failed to write instance port file {:?}", port_path))?;
+    Ok(InstanceRole::Primary(listener))
+}
+
+pub fn release(base_dir: &Path) {
+    let _ = fs::remove_file(base_dir.join(PORT_FILE));
+}
+
+fn forward_args(port: u16, args: &[String]) -> Result<()> {
+    let addr = SocketAddr::from(([127, 0, 0, 1], port));
+    let mut stream = TcpStream::connect_timeout(&addr, Duration::from_millis(500))?;
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let message = serde_json::to_string(&ForwardMessage {
+        handshake: HANDSHAKE.to_string(),
+        args: args.to_vec(),
+    })?;
+    stream.write_all(message.as_bytes())?;
+    stream.write_all(b"\n")?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    if reply.trim() == HANDSHAKE {
+        Ok(())
+    } else {
+        Err(anyhow!("unexpected reply from running instance"))
+    }
+}
+
+fn read_forwarded(stream: &TcpStream) -> Result<Vec<String>> {
+    stream.set_read_timeout(Some(Duration::from_secs(2)))?;
+    let mut line = String::new();
+    BufReader::new(stream).read_line(&mut line)?;
+    let message: ForwardMessage =
+        serde_json::from_str(&line).context("failed to parse forwarded arguments")?;
+    if message.handshake != HANDSHAKE {
+        return Err(anyhow!("unknown single-instance handshake"));
+    }
+    Ok(message.args)
+}
+
+/// Accepts hand-offs from later launches for the lifetime of the app.
+pub fn serve<R: Runtime>(listener: TcpListener, app: AppHandle<R>) {
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            match read_forwarded(&stream) {
+                Ok(args) => {
+                    handle_activation(&app, args);
+                    // Answer once the requests are queued; the later launch exits then.
+                    let _ = writeln!(stream, "{}", HANDSHAKE);
+                }
+                Err(err) => tracing::warn!("ignored connection: {:?}", err),
+            }
+        }
+    });
+}
+
+fn handle_activation<R: Runtime>(app: &AppHandle<R>, args: Vec<String>) {
+    let requests = parse_launch_args(args.iter().cloned());
+    if !requests.is_empty() {
+        app.state::<AppState>()
+            .pending_launch
+            .lock()
+            .extend(requests);
+    }
+    if let Some(window) = app.get_window("main") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    if let Err(err) = app.emit_all(INSTANCE_ACTIVATED_EVENT, args) {
+        tracing::warn!("failed to emit activation event: {:?}", err);
+    }
+}
@@ -2,102 +2,411 @@ use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
 
-use anyhow::{Context, Result};
-use csv::{ReaderBuilder, WriterBuilder};
+use anyhow::{anyhow, bail, Context, Result};
+use csv::{ReaderBuilder, StringRecord, WriterBuilder};
+use polars::prelude::{AnyValue, DataFrame, Series};
+use regex::{Regex, RegexBuilder};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
 
 use crate::flags::{normalize_flag_value, severity_rank};
-use crate::models::{IocEntry, ProjectRow};
-use crate::project_io::read_project_dataframe;
-use crate::search::{build_search_mask_boolean, to_rpn, tokenize_search_query, SearchToken};
-use crate::storage::load_flags;
-use crate::value_utils::{anyvalue_to_search_string, value_to_search_string};
+use crate::ip_rules::{parse_ip_rules, parse_ip_value, IpRule};
+use crate::models::{IocConflictPolicy, IocEntry, IocKind, ProjectRow};
+use crate::project_io::{
+    read_project_columns, read_project_dataframe, read_project_row_count, read_project_row_range,
+};
+use crate::search::{
+    build_search_mask_boolean, collect_search_terms, ensure_column_text_cache,
+    ensure_searchable_text, to_rpn, tokenize_search_query, SearchToken, LIST_ELEMENT_SUFFIX,
+};
+use crate::storage::{
+    load_flags, load_ioc_match_cache, save_ioc_flag_cache, save_ioc_match_cache, IocFlagCache,
+    IocMatchCache,
+};
+use crate::value_utils::{
+    anyvalue_to_element_search_string, anyvalue_to_search_string, normalize_search_text, refang,
+    value_to_element_search_string, value_to_search_string, SEARCH_TEXT_FORMAT,
+};
 
-pub fn row_contains_query(row: &ProjectRow, query: &str) -> bool {
-    if query.trim().is_empty() {
-        return false;
+/// Parsed form of an IOC rule's query, built once and evaluated against many rows.
+enum IocMatcher {
+    Query {
+        rpn: Vec<SearchToken>,
+        terms: Vec<(Option<String>, String)>,
+    },
+    Regex(Regex),
+    Ip(Vec<IpRule>),
+}
+
+pub struct CompiledIoc<'a> {
+    pub entry: &'a IocEntry,
+    matcher: IocMatcher,
+    /// Lowercase keys of `entry.columns`; empty when the rule is unrestricted.
+    columns: Vec<String>,
+}
+
+/// Trims an optional text field, treating blank values as absent.
+pub fn normalize_optional_text(value: Option<&str>) -> Option<String> {
+    value
+        .map(|text| text.trim())
+        .filter(|text| !text.is_empty())
+        .map(|text| text.to_string())
+}
+
+/// Trims, drops empty names and removes duplicates (case-insensitively).
+pub fn normalize_ioc_columns(columns: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for column in columns {
+        let column = column.trim();
+        if column.is_empty()
+            || normalized
+                .iter()
+                .any(|existing| existing.eq_ignore_ascii_case(column))
+        {
+            continue;
+        }
+        normalized.push(column.to_string());
+    }
+    normalized
+}
+
+/// Compiles a single rule; empty queries yield `None`, invalid regexes an error.
+pub fn compile_ioc(entry: &IocEntry) -> Result<Option<CompiledIoc<'_>>> {
+    let query = entry.query.trim();
+    if query.is_empty() {
+        return Ok(None);
+    }
+    let matcher = match entry.kind {
+        IocKind::Query => {
+            let tokens = tokenize_search_query(query);
+            let terms = collect_search_terms(&tokens);
+            if terms.is_empty() {
+                return Ok(None);
+            }
+            IocMatcher::Query {
+                rpn: to_rpn(&tokens),
+                terms,
+            }
+        }
+        IocKind::Regex => {
+            let regex = RegexBuilder::new(query)
+                .case_insensitive(true)
+                .build()
+                .with_context(|| format!("invalid regular expression {:?}", query))?;
+            IocMatcher::Regex(regex)
+        }
+        IocKind::Ip => IocMatcher::Ip(parse_ip_rules(&refang(query))?),
+    };
+    let columns = normalize_ioc_columns(&entry.columns)
+        .into_iter()
+        .map(|column| column.to_lowercase())
+        .collect();
+    Ok(Some(CompiledIoc {
+        entry,
+        matcher,
+        columns,
+    }))
+}
+
+/// Ensures every rule compiles, reporting the first offending rule.
+pub fn validate_ioc_entries(entries: &[IocEntry]) -> Result<()> {
+    for entry in entries {
+        compile_ioc(entry).with_context(|| format!("IOC rule {:?} is invalid", entry.tag))?;
+    }
+    Ok(())
+}
+
+/// Compiles every usable rule, skipping (and logging) rules that fail to compile.
+pub fn compile_iocs(entries: &[IocEntry]) -> Vec<CompiledIoc<'_>> {
+    entries
+        .iter()
+        .filter_map(|entry| match compile_ioc(entry) {
+            Ok(compiled) => compiled,
+            Err(err) => {
+                tracing::warn!("skipping rule {:?}: {:?}", entry.tag, err);
+                None
+            }
+        })
+        .collect()
+}
+
+impl CompiledIoc<'_> {
+    /// Columns named by `col:` prefixes or the rule's column restriction; their
+    /// per-column text must be available.
+    pub fn scoped_columns(&self) -> Vec<String> {
+        let prefixed: Vec<&String> = match &self.matcher {
+            IocMatcher::Query { terms, .. } => {
+                terms.iter().filter_map(|(col, _)| col.as_ref()).collect()
+            }
+            IocMatcher::Regex(_) => Vec::new(),
+            IocMatcher::Ip(rules) => rules
+                .iter()
+                .filter_map(|rule| rule.column.as_ref())
+                .collect(),
+        };
+        let mut columns: Vec<String> = Vec::new();
+        for column in prefixed.into_iter().chain(self.columns.iter()) {
+            if !columns.contains(column) {
+                columns.push(column.clone());
+            }
+        }
+        columns
+    }
+
+    /// Whether the rule is evaluated per column value rather than on row-wide text.
+    pub fn needs_column_values(&self) -> bool {
+        matches!(self.matcher, IocMatcher::Regex(_) | IocMatcher::Ip(_))
+    }
+
+    /// Evaluates the rule for every row. `value_columns` lists the lowercase
+    /// column keys of `per_column` that regex rules are matched against.
+    pub fn evaluate(
+        &self,
+        searchable_text: &[String],
+        per_column: &HashMap<String, Vec<String>>,
+        value_columns: &[String],
+    ) -> Vec<bool> {
+        let value_columns = if self.columns.is_empty() {
+            value_columns
+        } else {
+            self.columns.as_slice()
+        };
+        match &self.matcher {
+            IocMatcher::Query { rpn, terms } if !self.columns.is_empty() => {
+                let restricted =
+                    restricted_search_text(&self.columns, per_column, searchable_text.len());
+                build_search_mask_boolean(
+                    rpn,
+                    terms,
+                    &restricted,
+                    Some(per_column),
+                    self.entry.whole_word,
+                )
+            }
+            IocMatcher::Query { rpn, terms } => build_search_mask_boolean(
+                rpn,
+                terms,
+                searchable_text,
+                Some(per_column),
+                self.entry.whole_word,
+            ),
+            IocMatcher::Regex(regex) => (0..searchable_text.len())
+                .map(|row_idx| {
+                    value_columns.iter().any(|column| {
+                        per_column
+                            .get(column)
+                            .and_then(|texts| texts.get(row_idx))
+                            .map(|text| !text.is_empty() && regex.is_match(text))
+                            .unwrap_or(false)
+                    })
+                })
+                .collect(),
+            IocMatcher::Ip(rules) => {
+                let cell_ip = |column: &String, row_idx: usize| {
+                    per_column
+                        .get(column)
+                        .and_then(|texts| texts.get(row_idx))
+                        .and_then(|text| parse_ip_value(text))
+                };
+                (0..searchable_text.len())
+                    .map(|row_idx| {
+                        rules.iter().any(|rule| match &rule.column {
+                            Some(column) => cell_ip(column, row_idx)
+                                .map(|ip| rule.contains(ip))
+                                .unwrap_or(false),
+                            None => value_columns.iter().any(|column| {
+                                cell_ip(column, row_idx)
+                                    .map(|ip| rule.contains(ip))
+                                    .unwrap_or(false)
+                            }),
+                        })
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// Evaluates the rule against a single row's text and per-column values.
+    pub fn matches_row(&self, row_text: &str, per_column: &HashMap<String, Vec<String>>) -> bool {
+        let value_columns: Vec<String> = per_column
+            .keys()
+            .filter(|column| !column.ends_with(LIST_ELEMENT_SUFFIX))
+            .cloned()
+            .collect();
+        self.evaluate(&[row_text.to_string()], per_column, &value_columns)
+            .first()
+            .copied()
+            .unwrap_or(false)
     }
+}
+
+/// Row-wide search text limited to `columns`, used for unprefixed terms of
+/// column-restricted rules.
+fn restricted_search_text(
+    columns: &[String],
+    per_column: &HashMap<String, Vec<String>>,
+    row_count: usize,
+) -> Vec<String> {
+    (0..row_count)
+        .map(|row_idx| {
+            let mut text = String::new();
+            for value in columns
+                .iter()
+                .filter_map(|column| per_column.get(column).and_then(|texts| texts.get(row_idx)))
+            {
+                if value.is_empty() {
+                    continue;
+                }
+                if !text.is_empty() {
+                    text.push(' ');
+                }
+                text.push_str(value);
+            }
+            text
+        })
+        .collect()
+}
+
+fn project_row_search_text(
+    row: &ProjectRow,
+    unsearched: &[String],
+) -> (String, HashMap<String, Vec<String>>) {
     // Build concatenated lowercase text and per-column lowercase texts for the row
     let mut row_text = String::new();
     let mut per_col: HashMap<String, Vec<String>> = HashMap::new();
-    for (col, value) in &row.data {
+    for (col, value) in row.data.iter().filter(|(col, _)| !unsearched.contains(col)) {
         if let Some(text) = value_to_search_string(value) {
-            let lower = text.to_lowercase();
+            let lower = normalize_search_text(&text);
             if !lower.is_empty() {
                 if !row_text.is_empty() {
                     row_text.push(' ');
                 }
                 row_text.push_str(&lower);
+                if value.is_array() {
+                    if let Some(elements) = value_to_element_search_string(value) {
+                        per_col.insert(
+                            format!("{}{}", col.to_lowercase(), LIST_ELEMENT_SUFFIX),
+                            vec![normalize_search_text(&elements)],
+                        );
+                    }
+                }
                 per_col.insert(col.to_lowercase(), vec![lower]);
             }
         }
     }
-    if row_text.is_empty() {
-        return false;
+    (row_text, per_col)
+}
+
+/// Orders the enabled rules so that, under `policy`, the first matching rule
+/// with a severity decides a row's flag. Sorting is stable, so rule order
+/// breaks ties.
+pub fn order_iocs_for_policy(entries: &[IocEntry], policy: IocConflictPolicy) -> Vec<IocEntry> {
+    policy_order(entries, policy)
+        .into_iter()
+        .map(|index| entries[index].clone())
+        .collect()
+}
+
+/// Positions in `entries` of the rules in `order_iocs_for_policy` order.
+fn policy_order(entries: &[IocEntry], policy: IocConflictPolicy) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..entries.len())
+        .filter(|index| !entries[*index].disabled)
+        .collect();
+    let rank = |index: &usize| severity_rank(&normalize_flag_value(&entries[*index].flag));
+    match policy {
+        IocConflictPolicy::HighestSeverity => {
+            order.sort_by_key(|index| std::cmp::Reverse(rank(index)))
+        }
+        IocConflictPolicy::FirstMatch => {}
+        IocConflictPolicy::SafeSuppresses => {
+            order.sort_by_key(|index| (rank(index) != 1, std::cmp::Reverse(rank(index))))
+        }
     }
-    // Boolean evaluation using the shared tokenizer and RPN evaluator
-    let tokens = tokenize_search_query(query);
-    let mut terms: Vec<(Option<String>, String)> = Vec::new();
-    for t in &tokens {
-        if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } = t {
-            let key = (col.clone(), text.clone());
-            if !text.is_empty() && !terms.contains(&key) {
-                terms.push(key);
+    order
+}
+
+/// Flag and memo tags produced by the rules matching a single row.
+pub struct IocResolution {
+    pub flag: String,
+    pub tags: Vec<String>,
+}
+
+/// Combines the rules matching a row, given in `order_iocs_for_policy` order.
+pub fn resolve_ioc_matches<'a>(
+    matched: impl IntoIterator<Item = &'a IocEntry>,
+    policy: IocConflictPolicy,
+) -> IocResolution {
+    let matched: Vec<&IocEntry> = matched.into_iter().collect();
+    let flag = matched
+        .iter()
+        .map(|entry| normalize_flag_value(&entry.flag))
+        .find(|flag| severity_rank(flag) > 0)
+        .unwrap_or_default();
+    // An allowlist hit drops the tags of the rules it suppresses.
+    let suppress = policy == IocConflictPolicy::SafeSuppresses && flag == "safe";
+    let mut tags: Vec<String> = Vec::new();
+    for entry in matched {
+        if suppress && normalize_flag_value(&entry.flag) != "safe" {
+            continue;
+        }
+        let tag = entry.tag.trim();
+        if !tag.is_empty() {
+            let token = format!("[{}]", tag);
+            if !tags.contains(&token) {
+                tags.push(token);
             }
         }
     }
-    if terms.is_empty() {
-        return false;
+    IocResolution { flag, tags }
+}
+
+/// Appends `[tag]` tokens missing from `memo`; returns whether it changed.
+pub fn append_memo_tags(memo: &mut String, tags: &[String]) -> bool {
+    let mut changed = false;
+    for tag in tags {
+        if !memo.contains(tag.as_str()) {
+            if !memo.is_empty() && !memo.ends_with(' ') {
+                memo.push(' ');
+            }
+            memo.push_str(tag);
+            changed = true;
+        }
     }
-    let rpn = to_rpn(&tokens);
-    let mask = build_search_mask_boolean(&rpn, &terms, &vec![row_text], Some(&per_col));
-    mask.get(0).copied().unwrap_or(false)
+    changed
 }
 
-pub fn apply_iocs_to_rows(rows: &mut [ProjectRow], entries: &[IocEntry]) {
+/// Applies the rules to rows already read; `unsearched` columns are not
+/// matched against.
+pub fn apply_iocs_to_rows(
+    rows: &mut [ProjectRow],
+    entries: &[IocEntry],
+    policy: IocConflictPolicy,
+    unsearched: &[String],
+) {
     if entries.is_empty() {
         return;
     }
+    let ordered = order_iocs_for_policy(entries, policy);
+    let compiled = compile_iocs(&ordered);
     for row in rows {
-        let mut best_flag = normalize_flag_value(&row.flag);
-        let mut best_rank = severity_rank(&best_flag);
-        let mut memo = row.memo.clone().unwrap_or_default();
-        let mut memo_changed = false;
-
-        for entry in entries {
-            let query = entry.query.trim();
-            if query.is_empty() {
-                continue;
-            }
-            if !row_contains_query(row, query) {
-                continue;
-            }
-
-            let severity = normalize_flag_value(&entry.flag);
-            let severity_rank_value = severity_rank(&severity);
-            // If user already set a flag, keep it (user wins). Only apply IOC when no user flag.
-            if best_rank == 0 && severity_rank_value > 0 {
-                best_rank = severity_rank_value;
-                best_flag = severity.clone();
-            }
-
-            let tag = entry.tag.trim();
-            if !tag.is_empty() {
-                let token = format!("[{}]", tag);
-                if !memo.contains(&token) {
-                    if !memo.is_empty() && !memo.ends_with(' ') {
-                        memo.push(' ');
-                    }
-                    memo.push_str(&token);
-                    memo_changed = true;
-                }
-            }
+        let (row_text, per_col) = project_row_search_text(row, unsearched);
+        if row_text.is_empty() {
+            continue;
         }
+        let resolution = resolve_ioc_matches(
+            compiled
+                .iter()
+                .filter(|ioc| ioc.matches_row(&row_text, &per_col))
+                .map(|ioc| ioc.entry),
+            policy,
+        );
 
-        // Persist the resolved flag into the row only if it was empty before
-        if severity_rank(&normalize_flag_value(&row.flag)) == 0 && best_rank > 0 {
-            row.flag = best_flag.clone();
+        // If user already set a flag, keep it (user wins). Only apply IOC when no user flag.
+        if severity_rank(&normalize_flag_value(&row.flag)) == 0 && !resolution.flag.is_empty() {
+            row.flag = resolution.flag;
         }
-        if memo_changed {
+        let mut memo = row.memo.clone().unwrap_or_default();
+        if append_memo_tags(&mut memo, &resolution.tags) {
             let trimmed = memo.trim().to_string();
             row.memo = if trimmed.is_empty() {
                 None
@@ -120,6 +429,10 @@ pub fn load_ioc_entries(project_dir: &Path) -> Result<Vec<IocEntry>> {
         entry.flag = normalize_flag_value(&entry.flag);
         entry.tag = entry.tag.trim().to_string();
         entry.query = entry.query.trim().to_string();
+        entry.columns = normalize_ioc_columns(&entry.columns);
+        entry.description = normalize_optional_text(entry.description.as_deref());
+        entry.reference_url = normalize_optional_text(entry.reference_url.as_deref());
+        entry.author = normalize_optional_text(entry.author.as_deref());
     }
     Ok(entries)
 }
@@ -134,17 +447,159 @@ pub fn save_ioc_entries(project_dir: &Path, entries: &[IocEntry]) -> Result<()>
     fs::write(&path, data).with_context(|| format!("failed to write ioc file {:?}", path))
 }
 
-pub fn read_ioc_csv(path: &Path) -> Result<Vec<IocEntry>> {
+/// Rule fields an IOC CSV column can fill, in the order `write_ioc_csv`
+/// writes them. Files without a header row are read in this order.
+const IOC_CSV_FIELDS: [&str; 11] = [
+    "flag",
+    "tag",
+    "query",
+    "kind",
+    "columns",
+    "description",
+    "reference_url",
+    "author",
+    "whole_word",
+    "group",
+    "disabled",
+];
+
+/// Header names other tools export the rule fields under.
+const IOC_CSV_ALIASES: &[(&str, &str)] = &[
+    ("severity", "flag"),
+    ("level", "flag"),
+    ("threat_level", "flag"),
+    ("name", "tag"),
+    ("label", "tag"),
+    ("title", "tag"),
+    ("indicator", "query"),
+    ("ioc", "query"),
+    ("value", "query"),
+    ("pattern", "query"),
+    ("observable", "query"),
+    ("type", "kind"),
+    ("match_type", "kind"),
+    ("fields", "columns"),
+    ("comment", "description"),
+    ("notes", "description"),
+    ("reference", "reference_url"),
+    ("link", "reference_url"),
+    ("creator", "author"),
+];
+
+/// IOC CSV column to read a rule field from, by header name, e.g.
+/// `{"query": "ioc_value"}`.
+pub type IocCsvMapping = HashMap<String, String>;
+
+fn normalize_csv_header(header: &str) -> String {
+    header
+        .trim()
+        .to_ascii_lowercase()
+        .replace([' ', '-', '.'], "_")
+}
+
+fn ioc_csv_field_index(field: &str) -> Option<usize> {
+    IOC_CSV_FIELDS.iter().position(|known| *known == field)
+}
+
+/// Position in `IOC_CSV_FIELDS` of the field a normalized header names.
+fn ioc_csv_field(header: &str) -> Option<usize> {
+    let field = IOC_CSV_ALIASES
+        .iter()
+        .find(|(alias, _)| *alias == header)
+        .map_or(header, |(_, field)| field);
+    ioc_csv_field_index(field)
+}
+
+/// The column of each `IOC_CSV_FIELDS` entry, and whether `first` is a
+/// header row rather than a rule. The first column naming a field wins;
+/// `mapping` overrides that.
+fn ioc_csv_layout(
+    first: &StringRecord,
+    mapping: &IocCsvMapping,
+) -> Result<(Vec<Option<usize>>, bool)> {
+    let mut mapped = Vec::with_capacity(mapping.len());
+    for (field, column) in mapping {
+        let Some(field_index) = ioc_csv_field_index(field) else {
+            bail!(
+                "unknown IOC field '{}' in the column mapping; expected one of {}",
+                field,
+                IOC_CSV_FIELDS.join(", ")
+            );
+        };
+        mapped.push((field_index, column));
+    }
+    let headers: Vec<String> = first.iter().map(normalize_csv_header).collect();
+    let has_header = !mapping.is_empty() || headers.iter().any(|h| ioc_csv_field(h).is_some());
+    if !has_header {
+        return Ok(((0..IOC_CSV_FIELDS.len()).map(Some).collect(), false));
+    }
+
+    let mut positions = vec![None; IOC_CSV_FIELDS.len()];
+    for (index, header) in headers.iter().enumerate() {
+        if let Some(field_index) = ioc_csv_field(header) {
+            positions[field_index].get_or_insert(index);
+        }
+    }
+    for (field_index, column) in mapped {
+        let wanted = normalize_csv_header(column);
+        let index = headers
+            .iter()
+            .position(|header| *header == wanted)
+            .ok_or_else(|| anyhow!("the IOC CSV has no column named '{}'", column))?;
+        for position in positions.iter_mut() {
+            if *position == Some(index) {
+                *position = None;
+            }
+        }
+        positions[field_index] = Some(index);
+    }
+    if ioc_csv_field_index("query")
+        .and_then(|index| positions[index])
+        .is_none()
+    {
+        bail!("the IOC CSV header has no query column; map one to 'query'");
+    }
+    Ok((positions, true))
+}
+
+/// Reads IOC rules from a CSV. A first row naming rule fields, by the names
+/// `write_ioc_csv` uses or aliases such as `indicator` or `severity`, is a
+/// header, so the columns may come in any order and unknown ones are
+/// skipped.
+pub fn read_ioc_csv(path: &Path, mapping: &IocCsvMapping) -> Result<Vec<IocEntry>> {
     let mut reader = ReaderBuilder::new()
-        .has_headers(true)
+        .has_headers(false)
+        .flexible(true)
         .from_path(path)
         .with_context(|| format!("failed to open IOC CSV {:?}", path))?;
+    let mut records = reader.records();
+    let Some(first) = records.next() else {
+        return Ok(Vec::new());
+    };
+    let first = first.with_context(|| "failed to read IOC CSV record")?;
+    let (positions, has_header) = ioc_csv_layout(&first, mapping)?;
+
     let mut entries = Vec::new();
-    for record in reader.records() {
+    let rows = (!has_header)
+        .then_some(Ok(first))
+        .into_iter()
+        .chain(records);
+    for record in rows {
         let record = record.with_context(|| "failed to read IOC CSV record")?;
-        let flag_value = record.get(0).unwrap_or("").trim().to_string();
-        let tag = record.get(1).unwrap_or("").trim().to_string();
-        let query = record.get(2).unwrap_or("").trim().to_string();
+        let cell = |field: &str| {
+            ioc_csv_field_index(field)
+                .and_then(|field| positions[field])
+                .and_then(|index| record.get(index))
+        };
+        let flag_value = cell("flag").unwrap_or("").trim().to_string();
+        let tag = cell("tag").unwrap_or("").trim().to_string();
+        let query = cell("query").unwrap_or("").trim().to_string();
+        let kind = IocKind::parse(cell("kind").unwrap_or(""));
+        let columns: Vec<String> = cell("columns")
+            .unwrap_or("")
+            .split(';')
+            .map(|column| column.to_string())
+            .collect();
         if query.is_empty() {
             continue;
         }
@@ -152,11 +607,27 @@ pub fn read_ioc_csv(path: &Path) -> Result<Vec<IocEntry>> {
             flag: normalize_flag_value(&flag_value),
             tag,
             query,
+            kind,
+            columns: normalize_ioc_columns(&columns),
+            description: normalize_optional_text(cell("description")),
+            reference_url: normalize_optional_text(cell("reference_url")),
+            author: normalize_optional_text(cell("author")),
+            whole_word: parse_csv_bool(cell("whole_word")),
+            group: normalize_optional_text(cell("group")),
+            disabled: parse_csv_bool(cell("disabled")),
         });
     }
     Ok(entries)
 }
 
+/// Reads a yes/no cell of an IOC CSV; blank means no.
+fn parse_csv_bool(value: Option<&str>) -> bool {
+    matches!(
+        value.unwrap_or("").trim().to_ascii_lowercase().as_str(),
+        "true" | "yes" | "1"
+    )
+}
+
 pub fn write_ioc_csv(entries: &[IocEntry], path: &Path) -> Result<()> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent)
@@ -167,7 +638,19 @@ pub fn write_ioc_csv(entries: &[IocEntry], path: &Path) -> Result<()> {
         .from_path(path)
         .with_context(|| format!("failed to create IOC CSV {:?}", path))?;
     writer
-        .write_record(["flag", "tag", "query"])
+        .write_record([
+            "flag",
+            "tag",
+            "query",
+            "kind",
+            "columns",
+            "description",
+            "reference_url",
+            "author",
+            "whole_word",
+            "group",
+            "disabled",
+        ])
         .context("failed to write IOC CSV header")?;
     for entry in entries {
         writer
@@ -175,120 +658,558 @@ pub fn write_ioc_csv(entries: &[IocEntry], path: &Path) -> Result<()> {
                 entry.flag.as_str(),
                 entry.tag.as_str(),
                 entry.query.as_str(),
+                entry.kind.as_str(),
+                entry.columns.join(";").as_str(),
+                entry.description.as_deref().unwrap_or(""),
+                entry.reference_url.as_deref().unwrap_or(""),
+                entry.author.as_deref().unwrap_or(""),
+                if entry.whole_word { "true" } else { "" },
+                entry.group.as_deref().unwrap_or(""),
+                if entry.disabled { "true" } else { "" },
             ])
             .context("failed to write IOC CSV row")?;
     }
     writer.flush().context("failed to flush IOC CSV writer")
 }
 
-pub fn calculate_ioc_applied_records(project_dir: &Path) -> Result<usize> {
-    let parquet_path = project_dir.join("data.parquet");
-    let df = read_project_dataframe(&parquet_path)?;
-    let flags_path = project_dir.join("flags.json");
-    let flags = load_flags(&flags_path)?;
-    let iocs = load_ioc_entries(project_dir)?;
+fn ioc_rules_hasher(ordered: &[IocEntry], value_columns: &[String]) -> Sha256 {
+    let mut hasher = Sha256::new();
+    hasher.update(SEARCH_TEXT_FORMAT.to_be_bytes());
+    hasher.update(serde_json::to_vec(ordered).unwrap_or_default());
+    for column in value_columns {
+        hasher.update(column.as_bytes());
+        hasher.update([0]);
+    }
+    hasher
+}
 
-    let mut ioc_applied_count = 0;
-    let column_names: Vec<String> = df
-        .get_column_names()
-        .into_iter()
-        .filter(|column| *column != "__rowid")
-        .map(|column| column.to_string())
-        .collect();
+fn hex_digest(hasher: Sha256) -> String {
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
 
-    for row_idx in 0..df.height() {
-        let flag_entry = flags.get(&row_idx);
-        let user_flag = flag_entry
-            .as_ref()
-            .map(|entry| normalize_flag_value(&entry.flag))
-            .unwrap_or_default();
+/// Fingerprints the inputs of the per-row IOC flags: the rules in evaluation
+/// order, the columns unrestricted rules search, and which rows carry a user
+/// flag (those never take an IOC flag). Memo edits and the user flag's value
+/// do not change the result and so are left out.
+pub fn ioc_flag_cache_key(
+    ordered: &[IocEntry],
+    value_columns: &[String],
+    user_flags: &[String],
+) -> String {
+    let mut hasher = ioc_rules_hasher(ordered, value_columns);
+    hasher.update([0xff]);
+    for (row_idx, flag) in user_flags.iter().enumerate() {
+        if !flag.is_empty() {
+            hasher.update((row_idx as u64).to_be_bytes());
+        }
+    }
+    hex_digest(hasher)
+}
 
-        // Count IOC applications (only when no user flag exists)
-        if severity_rank(&user_flag) == 0 && !iocs.is_empty() {
-            let mut has_ioc_match = false;
-
-            // Build searchable text once for this row
-            let mut row_text = String::new();
-            for column in &column_names {
-                if let Ok(series) = df.column(column) {
-                    if let Ok(value) = series.get(row_idx) {
-                        if let Some(text) = anyvalue_to_search_string(&value) {
-                            let lower = text.to_lowercase();
-                            if !lower.is_empty() {
-                                if !row_text.is_empty() {
-                                    row_text.push(' ');
-                                }
-                                row_text.push_str(&lower);
-                            }
-                        }
-                    }
-                }
-            }
-            for entry in &iocs {
-                let query = entry.query.trim();
-                if query.is_empty() {
-                    continue;
+/// Fingerprints the inputs of the IOC matches, which unlike the IOC flags do
+/// not depend on user flags.
+pub fn ioc_match_cache_key(ordered: &[IocEntry], value_columns: &[String]) -> String {
+    hex_digest(ioc_rules_hasher(ordered, value_columns))
+}
+
+/// Lowercased search text of one row, plus each column's own text for
+/// column-scoped rules.
+fn row_search_text_at(
+    column_names: &[String],
+    column_series: &HashMap<&str, &Series>,
+    row_idx: usize,
+) -> (String, HashMap<String, Vec<String>>) {
+    let mut row_text = String::new();
+    let mut single_per_col: HashMap<String, Vec<String>> = HashMap::new();
+    for column in column_names {
+        let mut lower = String::new();
+        if let Some(series) = column_series.get(column.as_str()) {
+            if let Ok(value) = series.get(row_idx) {
+                if let Some(text) = anyvalue_to_search_string(&value) {
+                    lower = normalize_search_text(&text);
                 }
-                let tokens = tokenize_search_query(query);
-                let mut terms: Vec<(Option<String>, String)> = Vec::new();
-                for t in &tokens {
-                    if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } =
-                        t
-                    {
-                        let key = (col.clone(), text.clone());
-                        if !text.is_empty() && !terms.contains(&key) {
-                            terms.push(key);
-                        }
+                if let AnyValue::List(_) = value {
+                    if let Some(elements) = anyvalue_to_element_search_string(&value) {
+                        single_per_col.insert(
+                            format!("{}{}", column.to_lowercase(), LIST_ELEMENT_SUFFIX),
+                            vec![normalize_search_text(&elements)],
+                        );
                     }
                 }
-                if terms.is_empty() {
+            }
+        }
+        if !lower.is_empty() {
+            if !row_text.is_empty() {
+                row_text.push(' ');
+            }
+            row_text.push_str(&lower);
+        }
+        single_per_col.insert(column.to_lowercase(), vec![lower]);
+    }
+    (row_text, single_per_col)
+}
+
+/// Columns IOC rules are matched against.
+fn data_column_names(df: &DataFrame, unsearched: &[String]) -> Vec<String> {
+    df.get_column_names()
+        .into_iter()
+        .filter(|column| *column != "__rowid" && !unsearched.iter().any(|name| name == column))
+        .map(|column| column.to_string())
+        .collect()
+}
+
+/// Rows evaluated at a time when matching IOC rules for the counts, so the
+/// row text held in memory stays bounded on large projects.
+const IOC_COUNT_CHUNK_ROWS: usize = 250_000;
+
+/// Columns IOC rules are matched against, read from the Parquet schema.
+fn ioc_search_columns(parquet_path: &Path, unsearched: &[String]) -> Result<Vec<String>> {
+    Ok(read_project_columns(parquet_path)?
+        .into_iter()
+        .filter(|column| !unsearched.contains(column))
+        .collect())
+}
+
+fn load_current_ioc_matches(
+    project_dir: &Path,
+    key: &str,
+    row_count: usize,
+) -> Option<IocMatchCache> {
+    load_ioc_match_cache(project_dir)
+        .unwrap_or_else(|err| {
+            tracing::warn!(
+                "failed to load IOC match cache for {:?}: {:?}",
+                project_dir,
+                err
+            );
+            None
+        })
+        .filter(|cached| cached.key == key && cached.row_count() == row_count)
+}
+
+/// Evaluates each rule once per chunk of rows over whole columns, recording
+/// the rows any rule matches, the first rule flag each row gets and how many
+/// rows each rule matches. `compiled` holds the usable rules of `ordered`.
+fn evaluate_ioc_matches(
+    parquet_path: &Path,
+    ordered: &[IocEntry],
+    compiled: &[CompiledIoc],
+    search_cols: &[String],
+    value_columns: &[String],
+    matches: &mut IocMatchCache,
+) -> Result<()> {
+    let row_count = matches.row_count();
+    for chunk_start in (0..row_count).step_by(IOC_COUNT_CHUNK_ROWS) {
+        let chunk_len = IOC_COUNT_CHUNK_ROWS.min(row_count - chunk_start);
+        let df = read_project_row_range(parquet_path, search_cols, chunk_start, chunk_len)?;
+        let column_series: HashMap<&str, &Series> =
+            df.get_columns().iter().map(|s| (s.name(), s)).collect();
+        let column_series_lower: HashMap<String, &Series> = df
+            .get_columns()
+            .iter()
+            .map(|s| (s.name().to_lowercase(), s))
+            .collect();
+        let mut searchable_text: Option<Vec<String>> = None;
+        let mut text_built = false;
+        let mut per_column: HashMap<String, Vec<String>> = HashMap::new();
+        for ioc in compiled {
+            let mut needed = ioc.scoped_columns();
+            if ioc.needs_column_values() {
+                needed.extend(value_columns.iter().cloned());
+            }
+            for column in needed {
+                ensure_column_text_cache(&column, &column_series_lower, &mut per_column, chunk_len);
+            }
+            let text = ensure_searchable_text(
+                &mut searchable_text,
+                &mut text_built,
+                chunk_len,
+                search_cols,
+                &column_series,
+            );
+            let mask = ioc.evaluate(text, &per_column, value_columns);
+            let flag = normalize_flag_value(&ioc.entry.flag);
+            let rule = ordered
+                .iter()
+                .position(|entry| std::ptr::eq(entry, ioc.entry));
+            for (offset, hit) in mask.into_iter().enumerate() {
+                if !hit {
                     continue;
                 }
-                let rpn = to_rpn(&tokens);
-                // Build single-row per-column map
-                let mut single_per_col: HashMap<String, Vec<String>> = HashMap::new();
-                for column in &column_names {
-                    let mut s = String::new();
-                    if let Ok(series) = df.column(column) {
-                        if let Ok(v) = series.get(row_idx) {
-                            if let Some(t) = anyvalue_to_search_string(&v) {
-                                s = t.to_lowercase();
-                            }
-                        }
-                    }
-                    single_per_col.insert(column.to_lowercase(), vec![s]);
+                if let Some(rule) = rule {
+                    matches.rule_hits[rule] += 1;
                 }
-                let mask = build_search_mask_boolean(
-                    &rpn,
-                    &terms,
-                    &vec![row_text.clone()],
-                    Some(&single_per_col),
-                );
-                if mask.get(0).copied().unwrap_or(false) {
-                    has_ioc_match = true;
-                    break;
+                let row = chunk_start + offset;
+                matches.set_matched(row);
+                if matches.flags[row].is_empty() {
+                    matches.flags[row] = flag.clone();
                 }
             }
+        }
+    }
+    Ok(())
+}
+
+/// The IOC flag of every row: the rules' flag, except on rows with a user
+/// flag.
+pub fn ioc_flags_from_matches(matches: &IocMatchCache, user_flags: &[String]) -> Vec<String> {
+    matches
+        .flags
+        .iter()
+        .zip(user_flags)
+        .map(|(flag, user_flag)| {
+            if user_flag.is_empty() {
+                flag.clone()
+            } else {
+                String::new()
+            }
+        })
+        .collect()
+}
+
+/// Rows without a user flag that some IOC rule matches; `unsearched`
+/// columns are not matched against. The stored rule matches are reused when
+/// they are current. Otherwise the rules are evaluated over the data, and
+/// the matches and the per-row IOC flags are stored for later counts and
+/// row queries.
+pub fn calculate_ioc_applied_records(
+    project_dir: &Path,
+    policy: IocConflictPolicy,
+    unsearched: &[String],
+) -> Result<usize> {
+    let iocs = load_ioc_entries(project_dir)?;
+    let ordered = order_iocs_for_policy(&iocs, policy);
+    let compiled = compile_iocs(&ordered);
+    if compiled.is_empty() {
+        return Ok(0);
+    }
+    let parquet_path = project_dir.join("data.parquet");
+    let row_count = read_project_row_count(&parquet_path)?;
+    let search_cols = ioc_search_columns(&parquet_path, unsearched)?;
+    let value_columns: Vec<String> = search_cols.iter().map(|c| c.to_lowercase()).collect();
+    let flags = load_flags(&project_dir.join("flags.json"))?;
+    let mut user_flags = vec![String::new(); row_count];
+    for (row_idx, entry) in flags.iter() {
+        if *row_idx < row_count {
+            user_flags[*row_idx] = normalize_flag_value(&entry.flag);
+        }
+    }
 
-            if has_ioc_match {
-                ioc_applied_count += 1;
+    let match_key = ioc_match_cache_key(&ordered, &value_columns);
+    let matches = match load_current_ioc_matches(project_dir, &match_key, row_count) {
+        Some(matches) => matches,
+        None => {
+            let mut matches = IocMatchCache::new(match_key, row_count, ordered.len());
+            evaluate_ioc_matches(
+                &parquet_path,
+                &ordered,
+                &compiled,
+                &search_cols,
+                &value_columns,
+                &mut matches,
+            )?;
+            let cache = IocFlagCache {
+                key: ioc_flag_cache_key(&ordered, &value_columns, &user_flags),
+                flags: ioc_flags_from_matches(&matches, &user_flags),
+            };
+            for (cache, result) in [
+                ("match", save_ioc_match_cache(project_dir, &matches)),
+                ("flag", save_ioc_flag_cache(project_dir, &cache)),
+            ] {
+                if let Err(err) = result {
+                    tracing::warn!(
+                        "failed to persist IOC {} cache for {:?}: {:?}",
+                        cache,
+                        project_dir,
+                        err
+                    );
+                }
             }
+            matches
+        }
+    };
+
+    Ok(user_flags
+        .iter()
+        .enumerate()
+        .filter(|(row, user_flag)| user_flag.is_empty() && matches.is_matched(*row))
+        .count())
+}
+
+/// Rows each of the project's rules matches, user flags aside, in the order
+/// the rules are stored; disabled rules match none. Taken from the stored
+/// rule matches, so `None` until `calculate_ioc_applied_records` has
+/// evaluated the current rules.
+pub fn stored_ioc_rule_hits(
+    project_dir: &Path,
+    policy: IocConflictPolicy,
+    unsearched: &[String],
+) -> Result<Option<Vec<usize>>> {
+    let iocs = load_ioc_entries(project_dir)?;
+    let order = policy_order(&iocs, policy);
+    let ordered = order_iocs_for_policy(&iocs, policy);
+    let parquet_path = project_dir.join("data.parquet");
+    let row_count = read_project_row_count(&parquet_path)?;
+    let value_columns: Vec<String> = ioc_search_columns(&parquet_path, unsearched)?
+        .iter()
+        .map(|c| c.to_lowercase())
+        .collect();
+    let match_key = ioc_match_cache_key(&ordered, &value_columns);
+    let Some(matches) = load_current_ioc_matches(project_dir, &match_key, row_count)
+        .filter(|matches| matches.rule_hits.len() == order.len())
+    else {
+        return Ok(None);
+    };
+    let mut hits = vec![0; iocs.len()];
+    for (position, index) in order.into_iter().enumerate() {
+        hits[index] = matches.rule_hits[position];
+    }
+    Ok(Some(hits))
+}
+
+/// The IOC-applied row count after the user flag of `row` changed from
+/// `previous` to `flag`, adjusted from `current` by that row alone. Without
+/// current stored rule matches the count is computed afresh.
+pub fn adjust_ioc_applied_records(
+    project_dir: &Path,
+    policy: IocConflictPolicy,
+    unsearched: &[String],
+    current: usize,
+    row: usize,
+    previous: &str,
+    flag: &str,
+) -> Result<usize> {
+    let iocs = load_ioc_entries(project_dir)?;
+    let ordered = order_iocs_for_policy(&iocs, policy);
+    let parquet_path = project_dir.join("data.parquet");
+    let row_count = read_project_row_count(&parquet_path)?;
+    let value_columns: Vec<String> = ioc_search_columns(&parquet_path, unsearched)?
+        .iter()
+        .map(|c| c.to_lowercase())
+        .collect();
+    let match_key = ioc_match_cache_key(&ordered, &value_columns);
+    let Some(matches) = load_current_ioc_matches(project_dir, &match_key, row_count) else {
+        return calculate_ioc_applied_records(project_dir, policy, unsearched);
+    };
+    if !matches.is_matched(row) {
+        return Ok(current);
+    }
+    let was_flagged = !normalize_flag_value(previous).is_empty();
+    let is_flagged = !normalize_flag_value(flag).is_empty();
+    Ok(match (was_flagged, is_flagged) {
+        (true, false) => current + 1,
+        (false, true) => current.saturating_sub(1),
+        _ => current,
+    })
+}
+
+/// A row the IOC rules flag as critical, with the tags of the rules behind it.
+#[derive(Debug, Clone, Serialize)]
+pub struct CriticalIocRow {
+    pub row_index: usize,
+    pub tags: Vec<String>,
+}
+
+/// Rows flagged critical by the IOC rules under `policy`. Rows the analyst
+/// flagged by hand are skipped, as the user's flag wins.
+pub fn critical_ioc_rows(
+    project_dir: &Path,
+    policy: IocConflictPolicy,
+    unsearched: &[String],
+) -> Result<Vec<CriticalIocRow>> {
+    let iocs = load_ioc_entries(project_dir)?;
+    if !iocs
+        .iter()
+        .any(|entry| normalize_flag_value(&entry.flag) == "critical")
+    {
+        return Ok(Vec::new());
+    }
+    let df = read_project_dataframe(&project_dir.join("data.parquet"))?;
+    let flags = load_flags(&project_dir.join("flags.json"))?;
+    let ordered = order_iocs_for_policy(&iocs, policy);
+    let compiled = compile_iocs(&ordered);
+    let column_names = data_column_names(&df, unsearched);
+    let column_series: HashMap<&str, &Series> =
+        df.get_columns().iter().map(|s| (s.name(), s)).collect();
+
+    let mut critical = Vec::new();
+    for row_idx in 0..df.height() {
+        let user_flag = flags
+            .get(&row_idx)
+            .map(|entry| normalize_flag_value(&entry.flag))
+            .unwrap_or_default();
+        if severity_rank(&user_flag) > 0 {
+            continue;
+        }
+        let (row_text, single_per_col) = row_search_text_at(&column_names, &column_series, row_idx);
+        let resolution = resolve_ioc_matches(
+            compiled
+                .iter()
+                .filter(|ioc| ioc.matches_row(&row_text, &single_per_col))
+                .map(|ioc| ioc.entry),
+            policy,
+        );
+        if resolution.flag == "critical" {
+            critical.push(CriticalIocRow {
+                row_index: row_idx,
+                tags: resolution.tags,
+            });
         }
     }
+    Ok(critical)
+}
+
+/// Rules folded into an equivalent rule while preparing an IOC set.
+#[derive(Debug, Clone, Serialize)]
+pub struct CollapsedIoc {
+    /// Query of the rule that was kept.
+    pub query: String,
+    /// Tag of the kept rule after merging.
+    pub tag: String,
+    /// Number of rules folded into the kept one.
+    pub removed: usize,
+}
 
-    Ok(ioc_applied_count)
+/// Canonical form of a query: AND-only or OR-only queries compare as term
+/// sets, anything else by its token sequence.
+fn canonical_query(query: &str) -> String {
+    let tokens = tokenize_search_query(query);
+    let has_or = tokens.iter().any(|token| matches!(token, SearchToken::Or));
+    let has_and = tokens.iter().any(|token| matches!(token, SearchToken::And));
+    let has_not = tokens.iter().any(|token| matches!(token, SearchToken::Not));
+    let term =
+        |col: &Option<String>, text: &String| format!("{}:{}", col.as_deref().unwrap_or(""), text);
+    if !(has_not || has_or && has_and) {
+        let mut terms: Vec<String> = collect_search_terms(&tokens)
+            .iter()
+            .map(|(col, text)| term(col, text))
+            .collect();
+        terms.sort();
+        terms.dedup();
+        let op = if has_or { "or" } else { "and" };
+        return format!("{}({})", op, terms.join("\u{1f}"));
+    }
+    tokens
+        .iter()
+        .map(|token| match token {
+            SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } => {
+                term(col, text)
+            }
+            SearchToken::And => "&".to_string(),
+            SearchToken::Or => "|".to_string(),
+            SearchToken::Not => "!".to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("\u{1f}")
 }
 
-pub fn prepare_ioc_entries(entries: Vec<IocEntry>) -> Vec<IocEntry> {
-    let mut prepared: Vec<IocEntry> = entries
+fn equivalence_key(entry: &IocEntry) -> String {
+    let query = match entry.kind {
+        IocKind::Query => canonical_query(&entry.query),
+        IocKind::Regex => entry.query.clone(),
+        IocKind::Ip => match parse_ip_rules(&refang(&entry.query)) {
+            Ok(rules) => {
+                let mut parts: Vec<String> = rules.iter().map(|rule| rule.canonical()).collect();
+                parts.sort();
+                parts.dedup();
+                parts.join(",")
+            }
+            Err(_) => entry.query.to_lowercase(),
+        },
+    };
+    let mut columns: Vec<String> = entry.columns.iter().map(|c| c.to_lowercase()).collect();
+    columns.sort();
+    format!(
+        "{}|{}|{}|{}|{}",
+        entry.group.as_deref().unwrap_or(""),
+        entry.kind.as_str(),
+        entry.whole_word,
+        columns.join(";"),
+        query
+    )
+}
+
+/// Folds rules with equivalent queries in the same group into the first
+/// occurrence: tags are merged, the highest severity is kept, missing
+/// context is filled in, and the rule stays enabled if any copy was.
+fn dedupe_ioc_entries(entries: Vec<IocEntry>) -> (Vec<IocEntry>, Vec<CollapsedIoc>) {
+    let mut kept: Vec<IocEntry> = Vec::new();
+    let mut removed: Vec<usize> = Vec::new();
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+    for entry in entries {
+        let key = equivalence_key(&entry);
+        let Some(&index) = index_by_key.get(&key) else {
+            index_by_key.insert(key, kept.len());
+            kept.push(entry);
+            removed.push(0);
+            continue;
+        };
+        let target = &mut kept[index];
+        if !entry.tag.is_empty()
+            && !target
+                .tag
+                .split(" / ")
+                .any(|existing| existing == entry.tag)
+        {
+            target.tag = if target.tag.is_empty() {
+                entry.tag
+            } else {
+                format!("{} / {}", target.tag, entry.tag)
+            };
+        }
+        if severity_rank(&entry.flag) > severity_rank(&target.flag) {
+            target.flag = entry.flag;
+        }
+        target.description = target.description.take().or(entry.description);
+        target.reference_url = target.reference_url.take().or(entry.reference_url);
+        target.author = target.author.take().or(entry.author);
+        target.disabled &= entry.disabled;
+        removed[index] += 1;
+    }
+    let collapsed = kept
+        .iter()
+        .zip(&removed)
+        .filter(|(_, removed)| **removed > 0)
+        .map(|(entry, removed)| CollapsedIoc {
+            query: entry.query.clone(),
+            tag: entry.tag.clone(),
+            removed: *removed,
+        })
+        .collect();
+    (kept, collapsed)
+}
+
+/// Normalizes rules for storage and folds duplicates, reporting what was
+/// collapsed. Rules are kept sorted by tag, except under first-match-wins
+/// where the caller's order is significant.
+pub fn prepare_ioc_entries(
+    entries: Vec<IocEntry>,
+    policy: IocConflictPolicy,
+) -> (Vec<IocEntry>, Vec<CollapsedIoc>) {
+    let prepared: Vec<IocEntry> = entries
         .into_iter()
         .map(|entry| IocEntry {
             flag: normalize_flag_value(&entry.flag),
             tag: entry.tag.trim().to_string(),
             query: entry.query.trim().to_string(),
+            kind: entry.kind,
+            columns: normalize_ioc_columns(&entry.columns),
+            description: normalize_optional_text(entry.description.as_deref()),
+            reference_url: normalize_optional_text(entry.reference_url.as_deref()),
+            author: normalize_optional_text(entry.author.as_deref()),
+            whole_word: entry.whole_word,
+            group: normalize_optional_text(entry.group.as_deref()),
+            disabled: entry.disabled,
         })
         .filter(|entry| !entry.query.is_empty())
         .collect();
-    prepared.sort_by(|a, b| a.tag.cmp(&b.tag));
-    prepared
+    let (mut prepared, collapsed) = dedupe_ioc_entries(prepared);
+    if policy != IocConflictPolicy::FirstMatch {
+        prepared.sort_by(|a, b| a.tag.cmp(&b.tag));
+    }
+    (prepared, collapsed)
 }
@@ -0,0 +1,150 @@
+use std::net::{IpAddr, SocketAddr};
+
+use anyhow::{anyhow, Context, Result};
+
+/// Address normalized to a comparable integer; IPv4 and IPv6 never compare equal.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum IpKey {
+    V4(u32),
+    V6(u128),
+}
+
+fn ip_key(ip: IpAddr) -> IpKey {
+    match ip {
+        IpAddr::V4(v4) => IpKey::V4(u32::from(v4)),
+        IpAddr::V6(v6) => match v6.to_ipv4_mapped() {
+            Some(v4) => IpKey::V4(u32::from(v4)),
+            None => IpKey::V6(u128::from(v6)),
+        },
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct IpRule {
+    /// Lowercase column the rule is pinned to; `None` checks every column value.
+    pub column: Option<String>,
+    start: IpKey,
+    end: IpKey,
+}
+
+impl IpRule {
+    pub fn contains(&self, ip: IpAddr) -> bool {
+        let key = ip_key(ip);
+        self.start <= key && key <= self.end
+    }
+
+    /// The covered addresses as `first-last`, or the address of a one-address rule.
+    pub fn describe_range(&self) -> String {
+        let address = |key: IpKey| match key {
+            IpKey::V4(value) => std::net::Ipv4Addr::from(value).to_string(),
+            IpKey::V6(value) => std::net::Ipv6Addr::from(value).to_string(),
+        };
+        if self.start == self.end {
+            address(self.start)
+        } else {
+            format!("{}-{}", address(self.start), address(self.end))
+        }
+    }
+
+    /// Representation that is equal for rules covering the same addresses.
+    pub fn canonical(&self) -> String {
+        format!(
+            "{}:{:?}-{:?}",
+            self.column.as_deref().unwrap_or(""),
+            self.start,
+            self.end
+        )
+    }
+}
+
+fn parse_cidr(text: &str) -> Result<(IpKey, IpKey)> {
+    let (addr, prefix) = text
+        .split_once('/')
+        .ok_or_else(|| anyhow!("missing prefix length"))?;
+    let addr: IpAddr = addr.trim().parse().context("invalid network address")?;
+    let prefix: u32 = prefix.trim().parse().context("invalid prefix length")?;
+    match ip_key(addr) {
+        IpKey::V4(base) => {
+            if prefix > 32 {
+                return Err(anyhow!("IPv4 prefix length must be at most 32"));
+            }
+            let mask = if prefix == 0 {
+                0
+            } else {
+                u32::MAX << (32 - prefix)
+            };
+            Ok((IpKey::V4(base & mask), IpKey::V4(base | !mask)))
+        }
+        IpKey::V6(base) => {
+            if prefix > 128 {
+                return Err(anyhow!("IPv6 prefix length must be at most 128"));
+            }
+            let mask = if prefix == 0 {
+                0
+            } else {
+                u128::MAX << (128 - prefix)
+            };
+            Ok((IpKey::V6(base & mask), IpKey::V6(base | !mask)))
+        }
+    }
+}
+
+fn parse_range(text: &str) -> Result<(IpKey, IpKey)> {
+    if text.contains('/') {
+        return parse_cidr(text);
+    }
+    if let Some((start, end)) = text.split_once('-') {
+        let start = ip_key(start.trim().parse().context("invalid range start")?);
+        let end = ip_key(end.trim().parse().context("invalid range end")?);
+        if std::mem::discriminant(&start) != std::mem::discriminant(&end) {
+            return Err(anyhow!("range mixes IPv4 and IPv6 addresses"));
+        }
+        return Ok((start.min(end), start.max(end)));
+    }
+    let single = ip_key(text.parse().context("invalid IP address")?);
+    Ok((single, single))
+}
+
+/// Parses an IP rule query: CIDR blocks, `start-end` ranges, or single
+/// addresses separated by whitespace, commas, or `|`. Each item may carry a
+/// `column:` prefix to restrict it to that column.
+pub fn parse_ip_rules(query: &str) -> Result<Vec<IpRule>> {
+    let mut rules = Vec::new();
+    for item in query
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '|')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        // IPv6 literals contain colons, so a prefix is only a column when
+        // the whole item is not an address or range by itself.
+        let (column, text) = match item.split_once(':') {
+            Some((column, rest))
+                if !column.is_empty()
+                    && parse_range(item).is_err()
+                    && parse_range(rest).is_ok() =>
+            {
+                (Some(column.to_lowercase()), rest)
+            }
+            _ => (None, item),
+        };
+        let (start, end) =
+            parse_range(text).with_context(|| format!("invalid IP rule item {:?}", item))?;
+        rules.push(IpRule { column, start, end });
+    }
+    if rules.is_empty() {
+        return Err(anyhow!("IP rule does not contain any address"));
+    }
+    Ok(rules)
+}
+
+/// Parses a cell value as an IP address, tolerating surrounding whitespace and a port suffix.
+pub fn parse_ip_value(text: &str) -> Option<IpAddr> {
+    let trimmed = text.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    trimmed
+        .parse::<IpAddr>()
+        .ok()
+        .or_else(|| trimmed.parse::<SocketAddr>().ok().map(|addr| addr.ip()))
+}
@@ -0,0 +1,67 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use polars::prelude::DataFrame;
+use serde_json::{Map, Value};
+
+use crate::event_logs::{is_winlogbeat_record, winlogbeat_row};
+use crate::value_utils::JsonColumnBuilder;
+
+/// Separator between parent and child keys of flattened nested objects.
+const KEY_SEPARATOR: char = '.';
+
+pub fn flatten_object(prefix: &str, object: Map<String, Value>, out: &mut Vec<(String, Value)>) {
+    for (key, value) in object {
+        let name = if prefix.is_empty() {
+            key
+        } else {
+            format!("{}{}{}", prefix, KEY_SEPARATOR, key)
+        };
+        match value {
+            Value::Object(child) if !child.is_empty() => flatten_object(&name, child, out),
+            other => out.push((name, other)),
+        }
+    }
+}
+
+/// Reads a JSON Lines (`.jsonl` / `.ndjson`) file with one object per line.
+/// Nested objects are flattened into dotted column names (`process.name`).
+pub fn read_jsonl_dataframe(path: &Path) -> Result<DataFrame> {
+    let file =
+        File::open(path).with_context(|| format!("failed to open JSON Lines file {:?}", path))?;
+    read_jsonl_stream(BufReader::new(file))
+        .with_context(|| format!("failed to parse JSON Lines file {:?}", path))
+}
+
+/// Parses JSON Lines from any reader, e.g. a decompressing stream. Winlogbeat
+/// records are mapped onto the Windows event columns instead of flattened.
+pub fn read_jsonl_stream<R: BufRead>(reader: R) -> Result<DataFrame> {
+    let mut builder = JsonColumnBuilder::default();
+    for (line_index, line) in reader.lines().enumerate() {
+        let line = line.with_context(|| format!("failed to read line {}", line_index + 1))?;
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let value: Value = serde_json::from_str(line)
+            .with_context(|| format!("invalid JSON on line {}", line_index + 1))?;
+        let Value::Object(object) = value else {
+            return Err(anyhow!("line {} is not a JSON object", line_index + 1));
+        };
+        if is_winlogbeat_record(&object) {
+            builder.push_row(winlogbeat_row(&object));
+            continue;
+        }
+        let mut fields = Vec::with_capacity(object.len());
+        flatten_object("", object, &mut fields);
+        builder.push_row(fields);
+    }
+    if builder.rows() == 0 {
+        return Err(anyhow!("input contains no JSON objects"));
+    }
+    builder
+        .finish()
+        .context("failed to assemble JSON Lines columns")
+}
@@ -0,0 +1,65 @@
+use std::path::Path;
+
+use uuid::Uuid;
+
+use crate::models::{LaunchRequest, RowLink};
+
+pub const DEEP_LINK_SCHEME: &str = "trivium";
+pub const ARCHIVE_EXTENSION: &str = "trivium";
+
+/// Builds a `trivium://project/{id}/row/{rowid}` link for a single row.
+pub fn format_row_permalink(project_id: &Uuid, row_id: usize) -> String {
+    format!(
+        "{}://project/{}/row/{}",
+        DEEP_LINK_SCHEME, project_id, row_id
+    )
+}
+
+pub fn parse_row_permalink(url: &str) -> Option<RowLink> {
+    let rest = url
+        .trim()
+        .strip_prefix(DEEP_LINK_SCHEME)?
+        .strip_prefix("://")?;
+    let parts: Vec<&str> = rest.trim_end_matches('/').split('/').collect();
+    match parts.as_slice() {
+        ["project", project_id, "row", row_id] => Some(RowLink {
+            project_id: Uuid::parse_str(project_id).ok()?,
+            row_id: row_id.parse().ok()?,
+        }),
+        _ => None,
+    }
+}
+
+fn classify_file_arg(arg: &str) -> Option<LaunchRequest> {
+    let path = Path::new(arg);
+    if !path.is_file() {
+        return None;
+    }
+    let extension = path.extension()?.to_string_lossy().to_lowercase();
+    match extension.as_str() {
+        ARCHIVE_EXTENSION => Some(LaunchRequest::OpenArchive {
+            path: arg.to_string(),
+        }),
+        "csv" | "jsonl" | "ndjson" | "xlsx" | "xlsm" | "parquet" | "arrow" | "feather" | "evtx"
+        | "xml" | "sqlite" | "sqlite3" | "db" | "db3" | "gz" | "zip" => {
+            Some(LaunchRequest::ImportCsv {
+                path: arg.to_string(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// Interprets process arguments passed on activation: deep links and files opened from the shell.
+pub fn parse_launch_args<I>(args: I) -> Vec<LaunchRequest>
+where
+    I: IntoIterator<Item = String>,
+{
+    args.into_iter()
+        .filter_map(|arg| {
+            parse_row_permalink(&arg)
+                .map(LaunchRequest::RowLink)
+                .or_else(|| classify_file_arg(&arg))
+        })
+        .collect()
+}
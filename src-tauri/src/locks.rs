@@ -0,0 +1,124 @@
+//! Per-project locks. Commands that rewrite a project's data take it
+//! exclusively; commands that read the data share it. A command that cannot
+//! get the lock fails with `AppError::ProjectBusy` instead of reading a
+//! half-written file or rewriting one that is being read.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use parking_lot::Mutex;
+use serde::Serialize;
+use uuid::Uuid;
+
+use crate::error::AppError;
+
+#[derive(Debug, Default)]
+struct Activity {
+    /// Command rewriting the project and when it started.
+    rewrite: Option<(&'static str, DateTime<Utc>)>,
+    reads: usize,
+}
+
+/// What a project is doing, reported by `get_project_status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectStatus {
+    /// Set while a command rewrites the project; reads are rejected then.
+    pub busy: bool,
+    /// The command rewriting the project, e.g. `dedupe_project`.
+    pub operation: Option<&'static str>,
+    pub started_at: Option<DateTime<Utc>>,
+    /// Commands reading the project right now.
+    pub active_reads: usize,
+}
+
+#[derive(Default)]
+pub struct ProjectLocks {
+    inner: Mutex<HashMap<Uuid, Activity>>,
+    /// Rewrites finished per project, so the file watcher can tell the
+    /// app's own writes from outside changes.
+    rewrites: Mutex<HashMap<Uuid, u64>>,
+}
+
+/// Holds a project's lock until dropped.
+pub struct ProjectGuard<'a> {
+    locks: &'a ProjectLocks,
+    id: Uuid,
+    rewrite: bool,
+}
+
+impl ProjectLocks {
+    /// Shares the project with other reads; fails while it is rewritten.
+    pub fn read(&self, id: &Uuid) -> Result<ProjectGuard<'_>, AppError> {
+        let mut guard = self.inner.lock();
+        let activity = guard.entry(*id).or_default();
+        if let Some((operation, _)) = activity.rewrite {
+            return Err(AppError::ProjectBusy(*id, operation.to_string()));
+        }
+        activity.reads += 1;
+        Ok(ProjectGuard {
+            locks: self,
+            id: *id,
+            rewrite: false,
+        })
+    }
+
+    /// Takes the project for `operation` alone; fails while anything else
+    /// reads or rewrites it.
+    pub fn rewrite(
+        &self,
+        id: &Uuid,
+        operation: &'static str,
+    ) -> Result<ProjectGuard<'_>, AppError> {
+        let mut guard = self.inner.lock();
+        let activity = guard.entry(*id).or_default();
+        if let Some((running, _)) = activity.rewrite {
+            return Err(AppError::ProjectBusy(*id, running.to_string()));
+        }
+        if activity.reads > 0 {
+            return Err(AppError::ProjectBusy(*id, "a query or export".into()));
+        }
+        activity.rewrite = Some((operation, Utc::now()));
+        Ok(ProjectGuard {
+            locks: self,
+            id: *id,
+            rewrite: true,
+        })
+    }
+
+    /// Rewrites of the project finished since the app started.
+    pub fn rewrite_count(&self, id: &Uuid) -> u64 {
+        self.rewrites.lock().get(id).copied().unwrap_or(0)
+    }
+
+    pub fn status(&self, id: &Uuid) -> ProjectStatus {
+        let guard = self.inner.lock();
+        let activity = guard.get(id);
+        let rewrite = activity.and_then(|activity| activity.rewrite);
+        ProjectStatus {
+            busy: rewrite.is_some(),
+            operation: rewrite.map(|(operation, _)| operation),
+            started_at: rewrite.map(|(_, started_at)| started_at),
+            active_reads: activity.map_or(0, |activity| activity.reads),
+        }
+    }
+}
+
+impl Drop for ProjectGuard<'_> {
+    fn drop(&mut self) {
+        if self.rewrite {
+            *self.locks.rewrites.lock().entry(self.id).or_default() += 1;
+        }
+        let mut guard = self.locks.inner.lock();
+        let Some(activity) = guard.get_mut(&self.id) else {
+            return;
+        };
+        if self.rewrite {
+            activity.rewrite = None;
+        } else {
+            activity.reads = activity.reads.saturating_sub(1);
+        }
+        if activity.rewrite.is_none() && activity.reads == 0 {
+            guard.remove(&self.id);
+        }
+    }
+}
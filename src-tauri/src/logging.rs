@@ -0,0 +1,200 @@
+//! Application log: `tracing` events go to stderr and to a size-rotated file
+//! under the app data directory, and the most recent lines are kept in
+//! memory for the diagnostics panel.
+
+use std::{
+    collections::VecDeque,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{Arc, OnceLock},
+};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+use tracing_subscriber::{
+    filter::LevelFilter, fmt, layer::SubscriberExt, reload, util::SubscriberInitExt, Registry,
+};
+
+const LOG_DIR: &str = "logs";
+const LOG_FILE: &str = "trivium.log";
+/// The active file is rotated once it would grow past this size.
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+/// Rotated files kept next to the active one (`trivium.log.1` is the newest).
+const KEPT_LOG_FILES: usize = 3;
+/// Lines kept in memory for `get_recent_logs`.
+const RECENT_LINES: usize = 2000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+    Trace,
+}
+
+impl From<LogLevel> for LevelFilter {
+    fn from(level: LogLevel) -> Self {
+        match level {
+            LogLevel::Error => LevelFilter::ERROR,
+            LogLevel::Warn => LevelFilter::WARN,
+            LogLevel::Info => LevelFilter::INFO,
+            LogLevel::Debug => LevelFilter::DEBUG,
+            LogLevel::Trace => LevelFilter::TRACE,
+        }
+    }
+}
+
+struct SinkState {
+    file: Option<File>,
+    size: u64,
+    recent: VecDeque<String>,
+}
+
+/// Rotating log file plus the in-memory tail of recent lines.
+struct LogSink {
+    dir: PathBuf,
+    state: Mutex<SinkState>,
+}
+
+impl LogSink {
+    fn open(dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("failed to create log directory {:?}", dir))?;
+        let file = open_log_file(&dir.join(LOG_FILE))?;
+        let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+        Ok(Self {
+            dir,
+            state: Mutex::new(SinkState {
+                file: Some(file),
+                size,
+                recent: VecDeque::with_capacity(RECENT_LINES),
+            }),
+        })
+    }
+
+    fn rotate(&self, state: &mut SinkState) -> io::Result<()> {
+        state.file = None;
+        let rotated = |index: usize| self.dir.join(format!("{}.{}", LOG_FILE, index));
+        let _ = fs::remove_file(rotated(KEPT_LOG_FILES));
+        for index in (1..KEPT_LOG_FILES).rev() {
+            let from = rotated(index);
+            if from.exists() {
+                fs::rename(&from, rotated(index + 1))?;
+            }
+        }
+        let active = self.dir.join(LOG_FILE);
+        fs::rename(&active, rotated(1))?;
+        state.file = Some(open_log_file(&active).map_err(io::Error::other)?);
+        state.size = 0;
+        Ok(())
+    }
+
+    fn write_event(&self, buf: &[u8]) -> io::Result<()> {
+        let mut state = self.state.lock();
+        for line in String::from_utf8_lossy(buf).lines() {
+            if state.recent.len() == RECENT_LINES {
+                state.recent.pop_front();
+            }
+            state.recent.push_back(line.to_string());
+        }
+        if state.size > 0 && state.size + buf.len() as u64 > MAX_LOG_BYTES {
+            self.rotate(&mut state)?;
+        }
+        if let Some(file) = state.file.as_mut() {
+            file.write_all(buf)?;
+            state.size += buf.len() as u64;
+        }
+        Ok(())
+    }
+}
+
+fn open_log_file(path: &Path) -> Result<File> {
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("failed to open log file {:?}", path))
+}
+
+/// Writer handed to the fmt layer; it writes each formatted event in one call.
+struct SinkWriter(Arc<LogSink>);
+
+impl Write for SinkWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.write_event(buf)?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+struct Logging {
+    sink: Arc<LogSink>,
+    filter: reload::Handle<LevelFilter, Registry>,
+}
+
+static LOGGING: OnceLock<Logging> = OnceLock::new();
+
+/// Installs the global subscriber writing to `<app_dir>/logs`. Falls back to
+/// stderr only when the log file cannot be opened.
+pub fn init(app_dir: &Path) {
+    let (filter, handle) = reload::Layer::new(LevelFilter::from(LogLevel::default()));
+    let stderr = fmt::layer().with_writer(io::stderr);
+    let sink = match LogSink::open(app_dir.join(LOG_DIR)) {
+        Ok(sink) => Arc::new(sink),
+        Err(err) => {
+            let _ = Registry::default().with(filter).with(stderr).try_init();
+            tracing::warn!("logging to stderr only: {:?}", err);
+            return;
+        }
+    };
+    let file_sink = Arc::clone(&sink);
+    let file = fmt::layer()
+        .with_ansi(false)
+        .with_writer(move || SinkWriter(Arc::clone(&file_sink)));
+    if Registry::default()
+        .with(filter)
+        .with(stderr)
+        .with(file)
+        .try_init()
+        .is_ok()
+    {
+        let _ = LOGGING.set(Logging {
+            sink,
+            filter: handle,
+        });
+    }
+}
+
+/// Changes the level filter of the running subscriber.
+pub fn set_level(level: LogLevel) -> Result<()> {
+    let Some(logging) = LOGGING.get() else {
+        return Ok(());
+    };
+    logging
+        .filter
+        .modify(|filter| *filter = LevelFilter::from(level))
+        .context("failed to change the log level")
+}
+
+/// Up to `limit` of the most recent log lines, oldest first.
+pub fn recent_lines(limit: usize) -> Vec<String> {
+    let Some(logging) = LOGGING.get() else {
+        return Vec::new();
+    };
+    let state = logging.sink.state.lock();
+    let skip = state.recent.len().saturating_sub(limit);
+    state.recent.iter().skip(skip).cloned().collect()
+}
+
+/// Path of the active log file, when logging to a file.
+pub fn log_file_path() -> Option<PathBuf> {
+    LOGGING.get().map(|logging| logging.sink.dir.join(LOG_FILE))
+}
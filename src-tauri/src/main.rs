@@ -1,40 +1,189 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows release builds
 
+mod alerts;
+mod backups;
+mod column_types;
 mod commands;
+mod encryption;
+mod enrichment;
+mod entities;
 mod error;
+mod event_logs;
+mod events;
+mod evtx;
+mod export_parts;
+mod expressions;
 mod flags;
+mod instance;
 mod ioc;
+mod ip_rules;
+mod jsonl;
+mod links;
+mod locks;
+mod logging;
+mod migrations;
+mod misp;
 mod models;
+mod perf;
+mod presets;
 mod project_io;
+mod redaction;
 mod search;
+mod session;
+mod settings;
+mod sigma;
+mod sort_cache;
+mod sqlite;
 mod state;
+mod stix;
 mod storage;
+mod timezones;
 mod value_utils;
+mod watcher;
+mod xlsx;
+mod xlsx_writer;
+mod xml_scan;
+mod zip_reader;
+mod zip_writer;
 
 use tauri::Manager;
 
-use crate::state::AppState;
+use crate::{
+    instance::InstanceRole,
+    state::{app_data_root, AppState},
+};
 
 fn main() {
+    let context = tauri::generate_context!();
+    let base_dir = app_data_root(context.config()).expect("failed to prepare app data dir");
+    logging::init(&base_dir);
+    settings::apply_startup_settings(&base_dir);
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let listener = match instance::acquire(&base_dir, &args) {
+        Ok(InstanceRole::Forwarded) => return,
+        Ok(InstanceRole::Primary(listener)) => Some(listener),
+        Err(err) => {
+            tracing::warn!("single-instance check failed: {:?}", err);
+            None
+        }
+    };
+
     tauri::Builder::new()
-        .setup(|app| {
+        .setup(move |app| {
             let state = AppState::new(app)?;
+            let handle = app.handle();
+            state.alerts.set_listener(Box::new(move |alert| {
+                if let Err(err) = handle.emit_all(alerts::CRITICAL_ALERT_EVENT, alert.clone()) {
+                    tracing::warn!("failed to emit critical alert: {:?}", err);
+                }
+            }));
+            let handle = app.handle();
+            state.changes.set_listener(Box::new(move |event| {
+                if let Err(err) = handle.emit_all(event.name(), event.clone()) {
+                    tracing::warn!("failed to emit {}: {:?}", event.name(), err);
+                }
+            }));
             app.manage(state);
+            if let Some(listener) = listener {
+                instance::serve(listener, app.handle());
+            }
+            backups::spawn(app.handle());
+            watcher::spawn(app.handle());
             Ok(())
         })
         .invoke_handler(tauri::generate_handler![
             commands::list_projects,
             commands::create_project,
+            commands::list_sheets,
+            commands::list_sqlite_tables,
+            commands::list_import_presets,
+            commands::reimport_project,
             commands::delete_project,
+            commands::clone_project,
+            commands::set_project_pinned,
+            commands::enable_project_encryption,
+            commands::disable_project_encryption,
+            commands::lock_project,
+            commands::set_display_timezone,
+            commands::set_timestamp_column,
+            commands::set_column_aliases,
+            commands::set_column_description,
+            commands::get_project_storage_info,
+            commands::check_project,
+            commands::compact_project,
+            commands::verify_source_integrity,
+            commands::export_project_bundle,
+            commands::import_project_bundle,
+            commands::get_case_notes,
+            commands::save_case_notes,
+            commands::list_bookmarks,
+            commands::add_bookmarks,
+            commands::remove_bookmarks,
+            commands::list_views,
+            commands::save_view,
+            commands::delete_view,
+            commands::set_default_view,
+            commands::list_annotation_backups,
+            commands::restore_annotations_backup,
             commands::load_project,
+            commands::get_project_status,
             commands::query_project_rows,
+            commands::explain_search_query,
+            commands::compare_rows,
             commands::save_iocs,
             commands::import_iocs,
+            commands::set_ioc_group_enabled,
+            commands::delete_ioc_group,
             commands::export_iocs,
+            commands::export_iocs_stix,
+            commands::import_sigma_rules,
+            commands::get_sigma_mapping,
+            commands::set_ioc_policy,
             commands::update_flag,
+            commands::list_row_comments,
+            commands::add_row_comment,
+            commands::delete_row_comment,
+            commands::get_memo_term_frequencies,
             commands::set_hidden_columns,
-            commands::export_project
+            commands::export_project,
+            commands::export_report,
+            commands::save_session_state,
+            commands::get_session_restore,
+            commands::get_row_permalink,
+            commands::take_launch_requests,
+            commands::get_performance_settings,
+            commands::set_performance_settings,
+            commands::get_data_root,
+            commands::set_data_root,
+            commands::get_settings,
+            commands::set_settings,
+            commands::get_recent_logs,
+            commands::set_log_level,
+            commands::get_performance_stats,
+            commands::reset_performance_stats,
+            commands::add_script_column,
+            commands::add_decoded_column,
+            commands::add_computed_column,
+            commands::list_json_keys,
+            commands::expand_json_column,
+            commands::assign_sessions,
+            commands::find_rare_values,
+            commands::find_duplicates,
+            commands::dedupe_project,
+            commands::list_entities,
+            commands::pivot_entity,
+            commands::set_project_alerts,
+            commands::send_test_alert
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(context)
+        .expect("error while building tauri application")
+        .run(move |app_handle, event| {
+            if let tauri::RunEvent::Exit = event {
+                encryption::seal_unlocked(&app_handle.state::<AppState>());
+                if let Err(err) = app_handle.state::<AppState>().session.mark_clean_exit() {
+                    tracing::warn!("failed to record clean exit: {:?}", err);
+                }
+                instance::release(&base_dir);
+            }
+        });
 }
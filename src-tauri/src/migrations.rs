@@ -0,0 +1,71 @@
+//! Upgrades project directories and metadata written by older versions.
+//!
+//! Each project records the layout version it was last written with in
+//! `ProjectMeta::format_version`; projects from before versioning read as 0.
+//! A migration moves a project from one version to the next and runs at most
+//! once. Add new steps to `MIGRATIONS` and bump `CURRENT_FORMAT_VERSION`.
+
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+use crate::{
+    models::ProjectMeta,
+    storage::{count_flagged, migrate_legacy_flags},
+};
+
+/// Layout version written by this build.
+pub const CURRENT_FORMAT_VERSION: u32 = 2;
+
+struct Migration {
+    /// Version the project is at once the step has run.
+    to: u32,
+    description: &'static str,
+    apply: fn(&Path, &mut ProjectMeta) -> Result<()>,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        to: 1,
+        description: "move JSON flags into the flags database",
+        apply: |project_dir, _| {
+            migrate_legacy_flags(&project_dir.join("flags.json"))?;
+            Ok(())
+        },
+    },
+    Migration {
+        to: 2,
+        description: "recount flagged rows",
+        apply: |project_dir, meta| {
+            meta.flagged_records = count_flagged(&project_dir.join("flags.json"))?;
+            Ok(())
+        },
+    },
+];
+
+/// Brings a project up to `CURRENT_FORMAT_VERSION`, returning whether its
+/// metadata changed. On failure the version stays at the last step that
+/// succeeded, so the remaining steps are retried on the next start.
+pub fn migrate_project(project_dir: &Path, meta: &mut ProjectMeta) -> Result<bool> {
+    if meta.format_version > CURRENT_FORMAT_VERSION {
+        return Err(anyhow!(
+            "project {} uses format version {}, newer than this build supports ({})",
+            meta.id,
+            meta.format_version,
+            CURRENT_FORMAT_VERSION
+        ));
+    }
+    let start = meta.format_version;
+    let mut changed = false;
+    for migration in MIGRATIONS.iter().filter(|migration| migration.to > start) {
+        (migration.apply)(project_dir, meta).with_context(|| {
+            format!(
+                "failed to {} (format version {})",
+                migration.description, migration.to
+            )
+        })?;
+        meta.format_version = migration.to;
+        changed = true;
+    }
+    Ok(changed)
+}
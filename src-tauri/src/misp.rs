@@ -0,0 +1,236 @@
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+
+use crate::ioc::normalize_optional_text;
+use crate::models::{IocEntry, IocKind};
+
+#[derive(Debug, Deserialize)]
+struct MispAttribute {
+    #[serde(rename = "type")]
+    attr_type: String,
+    #[serde(default)]
+    value: String,
+    #[serde(default)]
+    to_ids: bool,
+    #[serde(default)]
+    comment: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MispObject {
+    #[serde(rename = "Attribute", default)]
+    attributes: Vec<MispAttribute>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MispOrg {
+    #[serde(default)]
+    name: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MispEvent {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(default)]
+    info: Option<String>,
+    #[serde(rename = "Orgc", default)]
+    orgc: Option<MispOrg>,
+    #[serde(rename = "Attribute", default)]
+    attributes: Vec<MispAttribute>,
+    #[serde(rename = "Object", default)]
+    objects: Vec<MispObject>,
+}
+
+/// Attribute types that carry no matchable indicator.
+const SKIPPED_TYPES: &[&str] = &[
+    "attachment",
+    "boolean",
+    "comment",
+    "counter",
+    "datetime",
+    "float",
+    "link",
+    "other",
+    "size-in-bytes",
+    "snort",
+    "sigma",
+    "text",
+    "yara",
+];
+
+/// Column names commonly holding each family of MISP attribute types; only
+/// names present in the project are applied as a column restriction.
+fn column_hints(attr_type: &str) -> &'static [&'static str] {
+    match attr_type {
+        "ip-src" | "ip-src|port" => &[
+            "src_ip",
+            "source_ip",
+            "sourceip",
+            "sourceaddress",
+            "ipaddress",
+            "client_ip",
+        ],
+        "ip-dst" | "ip-dst|port" => &[
+            "dst_ip",
+            "dest_ip",
+            "destination_ip",
+            "destinationip",
+            "destinationaddress",
+            "remote_ip",
+        ],
+        "domain" | "hostname" | "domain|ip" => &[
+            "domain",
+            "hostname",
+            "host",
+            "query",
+            "queryname",
+            "destinationhostname",
+        ],
+        "url" | "uri" => &["url", "uri", "request_url"],
+        "filename" | "filename-pattern" => {
+            &["filename", "file_name", "image", "targetfilename", "path"]
+        }
+        "md5" | "sha1" | "sha256" | "sha512" | "imphash" | "filename|md5" | "filename|sha1"
+        | "filename|sha256" | "malware-sample" => &["hash", "hashes", "md5", "sha1", "sha256"],
+        "email-src" | "email-dst" | "email" => &["sender", "recipient", "email", "from", "to"],
+        "user-agent" => &["user_agent", "useragent"],
+        "regkey" | "regkey|value" => &["targetobject", "key", "registry_key", "keypath"],
+        _ => &[],
+    }
+}
+
+/// Picks the indicator part of composite `a|b` values: hashes for
+/// `filename|<hash>`, the address for `ip|port`, otherwise the first part.
+fn indicator_value<'a>(attr_type: &str, value: &'a str) -> &'a str {
+    let Some((first, second)) = value.split_once('|') else {
+        return value;
+    };
+    if !attr_type.contains('|') {
+        return value;
+    }
+    if attr_type.starts_with("filename|") || attr_type == "malware-sample" {
+        second
+    } else {
+        first
+    }
+}
+
+fn to_ioc_entry(
+    attribute: &MispAttribute,
+    tag_prefix: &str,
+    project_columns: &[String],
+) -> Option<IocEntry> {
+    let attr_type = attribute.attr_type.trim().to_lowercase();
+    if SKIPPED_TYPES.contains(&attr_type.as_str()) {
+        return None;
+    }
+    let value = indicator_value(&attr_type, attribute.value.trim()).trim();
+    if value.is_empty() {
+        return None;
+    }
+    let is_ip = attr_type.starts_with("ip-");
+    let query = if is_ip {
+        value.to_string()
+    } else {
+        // Quote so punctuation in URLs and paths is matched literally.
+        format!("\"{}\"", value.replace('"', ""))
+    };
+    let columns = column_hints(&attr_type)
+        .iter()
+        .filter_map(|hint| {
+            project_columns
+                .iter()
+                .find(|column| column.eq_ignore_ascii_case(hint))
+                .cloned()
+        })
+        .collect();
+    Some(IocEntry {
+        flag: if attribute.to_ids {
+            "critical".to_string()
+        } else {
+            "suspicious".to_string()
+        },
+        tag: format!("{}:{}", tag_prefix, attr_type),
+        query,
+        kind: if is_ip { IocKind::Ip } else { IocKind::Query },
+        columns,
+        description: normalize_optional_text(attribute.comment.as_deref()),
+        reference_url: None,
+        author: None,
+        whole_word: false,
+        group: None,
+        disabled: false,
+    })
+}
+
+fn parse_events(root: serde_json::Value) -> Result<Vec<MispEvent>> {
+    // Accept a bare event, `{"Event": {...}}`, or a REST search `{"response": [...]}`.
+    let candidates: Vec<serde_json::Value> = match root {
+        serde_json::Value::Object(mut map) => {
+            if let Some(response) = map.remove("response") {
+                match response {
+                    serde_json::Value::Array(items) => items,
+                    other => vec![other],
+                }
+            } else {
+                vec![serde_json::Value::Object(map)]
+            }
+        }
+        serde_json::Value::Array(items) => items,
+        _ => return Err(anyhow!("MISP export must be a JSON object")),
+    };
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            let event = match candidate {
+                serde_json::Value::Object(mut map) if map.contains_key("Event") => {
+                    map.remove("Event").unwrap_or_default()
+                }
+                other => other,
+            };
+            serde_json::from_value(event).context("failed to parse MISP event")
+        })
+        .collect()
+}
+
+/// Reads a MISP event export and converts its attributes into IOC rules.
+/// Attributes marked `to_ids` become critical, the rest suspicious.
+pub fn read_misp_event(path: &Path, project_columns: &[String]) -> Result<Vec<IocEntry>> {
+    let data = fs::read(path).with_context(|| format!("failed to read MISP export {:?}", path))?;
+    let root: serde_json::Value = serde_json::from_slice(&data)
+        .with_context(|| format!("failed to parse MISP export {:?}", path))?;
+    let mut entries = Vec::new();
+    for event in parse_events(root)? {
+        let tag_prefix = match event.id.as_ref() {
+            Some(serde_json::Value::String(id)) => format!("MISP-{}", id),
+            Some(serde_json::Value::Number(id)) => format!("MISP-{}", id),
+            _ => "MISP".to_string(),
+        };
+        let attributes = event.attributes.iter().chain(
+            event
+                .objects
+                .iter()
+                .flat_map(|object| object.attributes.iter()),
+        );
+        let info = normalize_optional_text(event.info.as_deref());
+        let author =
+            normalize_optional_text(event.orgc.as_ref().and_then(|org| org.name.as_deref()));
+        entries.extend(
+            attributes
+                .filter_map(|attribute| to_ioc_entry(attribute, &tag_prefix, project_columns))
+                .map(|mut entry| {
+                    // Attribute comments are more specific than the event title.
+                    if entry.description.is_none() {
+                        entry.description = info.clone();
+                    }
+                    entry.author = author.clone();
+                    entry
+                }),
+        );
+    }
+    Ok(entries)
+}
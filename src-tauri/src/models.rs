@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
@@ -8,6 +8,10 @@ use uuid::Uuid;
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProjectMeta {
     pub id: Uuid,
+    /// On-disk layout version, see `migrations`; 0 for projects written
+    /// before layouts were versioned.
+    #[serde(default)]
+    pub format_version: u32,
     pub name: String,
     pub description: Option<String>,
     pub created_at: DateTime<Utc>,
@@ -17,6 +21,261 @@ pub struct ProjectMeta {
     #[serde(default)]
     pub ioc_applied_records: usize,
     pub hidden_columns: Vec<String>,
+    /// Leaves hidden columns out of row-wide search and IOC matching, e.g.
+    /// raw XML that produces hits nobody can see.
+    #[serde(default)]
+    pub search_visible_only: bool,
+    /// Display names of columns, e.g. `Client IP` for `c-ip`. The data keeps
+    /// its column names; search prefixes, exports and reports use the alias.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_aliases: BTreeMap<String, String>,
+    /// Notes on what a column holds, e.g. a tool's field semantics, for
+    /// teammates reviewing the project.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub column_descriptions: BTreeMap<String, String>,
+    #[serde(default)]
+    pub ioc_policy: IocConflictPolicy,
+    /// Files the data was imported from; empty for projects created before
+    /// sources were recorded.
+    #[serde(default)]
+    pub sources: Vec<SourceFile>,
+    #[serde(default)]
+    pub import_options: ImportOptions,
+    /// Tool preset (e.g. `hayabusa`) the project was set up with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<String>,
+    /// Event time column: the grid opens sorted by it and the timeline view
+    /// orders rows by it. Taken from the import and changeable later.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_column: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_opened_at: Option<DateTime<Utc>>,
+    /// Pinned projects stay at the top of the project list.
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub alerts: AlertSettings,
+    /// Time zone timestamps are shown in; they are shown in UTC when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_timezone: Option<String>,
+    /// Set when the project's files are kept encrypted at rest.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub encryption: Option<ProjectEncryption>,
+}
+
+impl ProjectMeta {
+    /// Columns row-wide search and IOC rules skip.
+    pub fn unsearched_columns(&self) -> &[String] {
+        if self.search_visible_only {
+            &self.hidden_columns
+        } else {
+            &[]
+        }
+    }
+
+    /// The name a column is shown under: its alias, else the column itself.
+    pub fn column_label<'a>(&'a self, column: &'a str) -> &'a str {
+        self.column_aliases
+            .get(column)
+            .map(String::as_str)
+            .unwrap_or(column)
+    }
+}
+
+/// How an encrypted project derives its key from the passphrase, see
+/// `encryption`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ProjectEncryption {
+    /// Hex-encoded PBKDF2 salt.
+    pub salt: String,
+    pub rounds: u32,
+    /// Hex-encoded nonce and sealed marker that only the right key opens.
+    pub check: String,
+}
+
+/// Where to report rows that IOC rules newly flag as critical.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct AlertSettings {
+    /// Receives a JSON summary of each batch of new critical rows.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    /// Shows the summary as a notification in the app.
+    #[serde(default)]
+    pub notify: bool,
+}
+
+impl AlertSettings {
+    pub fn enabled(&self) -> bool {
+        self.notify || self.webhook_url.is_some()
+    }
+}
+
+/// Order of the project list.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProjectSort {
+    /// Newest created first.
+    #[default]
+    Created,
+    /// Most recently opened first; never-opened projects follow by creation time.
+    Recent,
+    /// Pinned projects first, each group in recently-opened order.
+    Pinned,
+}
+
+/// An imported input file and the SHA-256 of its contents at import time.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct SourceFile {
+    pub path: String,
+    pub sha256: String,
+}
+
+/// Result of re-hashing a recorded source file.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum SourceIntegrityStatus {
+    /// The file still has the hash recorded at import.
+    Verified,
+    /// The file exists but its contents changed since import.
+    Modified,
+    /// The file is no longer at its recorded path.
+    Missing,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SourceIntegrity {
+    pub path: String,
+    pub recorded_sha256: String,
+    pub current_sha256: Option<String>,
+    pub status: SourceIntegrityStatus,
+}
+
+/// Source-format specific choices made when creating a project.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportOptions {
+    /// Worksheet to read from an Excel workbook; the first one when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sheet: Option<String>,
+    /// Table or view to read from a SQLite database; the first table when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub table: Option<String>,
+    /// Column types forced instead of the inferred ones, keyed by column name.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub dtypes: BTreeMap<String, ColumnDtype>,
+    /// Convert text columns holding dates or timestamps into temporal columns.
+    #[serde(default)]
+    pub parse_dates: bool,
+    /// Whether the first CSV line names the columns; when false the columns
+    /// are called `column_1` .. `column_n` and the first line is data.
+    #[serde(default = "default_has_headers")]
+    pub has_headers: bool,
+    /// Repair or skip malformed CSV records instead of failing the import.
+    #[serde(default)]
+    pub lenient: bool,
+    /// Columns read as timestamps and stored in UTC.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub timestamp_columns: Vec<String>,
+    /// Time zone of timestamps written without an offset, as an IANA name
+    /// or a fixed offset; UTC when unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_timezone: Option<String>,
+    /// Trim leading and trailing whitespace from every text value.
+    #[serde(default)]
+    pub trim_whitespace: bool,
+    /// Columns whose text is lowercased, so values differing only in case
+    /// match exactly.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub lowercase_columns: Vec<String>,
+    /// Columns whose numbers lose their thousands separators
+    /// (`1,234,567` → `1234567`), so they can be read as numbers.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub strip_thousands_columns: Vec<String>,
+    /// Columns added to every row by enrichment scripts while importing,
+    /// and again on reimport.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub script_columns: Vec<ScriptColumn>,
+}
+
+/// A column computed by a Rhai script; see `crate::enrichment`.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScriptColumn {
+    /// Name of the new column.
+    pub column: String,
+    /// Rhai source run once per row.
+    pub script: String,
+    /// Columns the script reads; every data column when empty.
+    #[serde(
+        default,
+        rename = "inputColumns",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub input_columns: Vec<String>,
+}
+
+fn default_has_headers() -> bool {
+    true
+}
+
+impl Default for ImportOptions {
+    fn default() -> Self {
+        Self {
+            sheet: None,
+            table: None,
+            dtypes: BTreeMap::new(),
+            parse_dates: false,
+            has_headers: default_has_headers(),
+            lenient: false,
+            timestamp_columns: Vec::new(),
+            source_timezone: None,
+            trim_whitespace: false,
+            lowercase_columns: Vec::new(),
+            strip_thousands_columns: Vec::new(),
+            script_columns: Vec::new(),
+        }
+    }
+}
+
+/// Column type that can be requested for an imported column.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnDtype {
+    String,
+    Int64,
+    Float64,
+    Boolean,
+    Date,
+    Datetime,
+}
+
+/// A malformed CSV record that a lenient import repaired or dropped.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ImportIssue {
+    /// Input file name; only set when several files were merged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file: Option<String>,
+    /// Line on which the record starts (1-based, header included).
+    pub line: usize,
+    pub message: String,
+    /// Whether the record was dropped rather than repaired.
+    pub skipped: bool,
+}
+
+/// What a lenient import changed while reading its CSV inputs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    pub rows_imported: usize,
+    pub rows_repaired: usize,
+    pub rows_skipped: usize,
+    /// Issues in input order; capped, so it may hold fewer entries than
+    /// `rows_repaired + rows_skipped`.
+    pub issues: Vec<ImportIssue>,
+}
+
+/// Name and resulting type of an imported column.
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnType {
+    pub name: String,
+    pub dtype: String,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -32,11 +291,192 @@ pub struct ProjectRow {
     pub memo: Option<String>,
 }
 
+/// How the flags of several IOC rules matching the same row are combined.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IocConflictPolicy {
+    /// The most severe matching rule decides the flag.
+    #[default]
+    HighestSeverity,
+    /// The first matching rule in rule order decides the flag.
+    FirstMatch,
+    /// A matching safe rule acts as an allowlist and clears every other hit.
+    SafeSuppresses,
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum IocKind {
+    /// Query in the boolean search syntax.
+    #[default]
+    Query,
+    /// Regular expression matched (case-insensitively) against each column value.
+    Regex,
+    /// CIDR blocks, address ranges, or single addresses matched against IP column values.
+    Ip,
+}
+
+impl IocKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            IocKind::Query => "query",
+            IocKind::Regex => "regex",
+            IocKind::Ip => "ip",
+        }
+    }
+
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_lowercase().as_str() {
+            "regex" => IocKind::Regex,
+            "ip" | "cidr" => IocKind::Ip,
+            _ => IocKind::Query,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IocEntry {
     pub flag: String,
     pub tag: String,
     pub query: String,
+    #[serde(default)]
+    pub kind: IocKind,
+    /// Restricts evaluation to these columns; empty means every searchable column.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub columns: Vec<String>,
+    /// Why the rule exists; free text shown alongside the rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reference_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    /// Query terms only match whole words, so `cmd` skips `cmdlet`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub whole_word: bool,
+    /// Named set of rules, such as one feed or an internal allowlist, that
+    /// can be switched off or deleted together.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Kept with the other rules but not evaluated.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+}
+
+/// On-disk bytes used by the parts of one or more projects.
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct StorageUsage {
+    /// The imported data (`data.parquet`).
+    pub data_bytes: u64,
+    /// Flags and memos database.
+    pub flags_bytes: u64,
+    /// Search and IOC match caches; rebuilt on demand.
+    pub cache_bytes: u64,
+    /// IOC rules and the Sigma field mapping.
+    pub ioc_bytes: u64,
+    pub other_bytes: u64,
+    pub total_bytes: u64,
+}
+
+impl StorageUsage {
+    pub fn add(&mut self, other: &StorageUsage) {
+        self.data_bytes += other.data_bytes;
+        self.flags_bytes += other.flags_bytes;
+        self.cache_bytes += other.cache_bytes;
+        self.ioc_bytes += other.ioc_bytes;
+        self.other_bytes += other.other_bytes;
+        self.total_bytes += other.total_bytes;
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectStorageInfo {
+    pub project_id: Uuid,
+    pub name: String,
+    #[serde(flatten)]
+    pub usage: StorageUsage,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct WorkspaceStorageInfo {
+    pub data_root: String,
+    /// Largest project first.
+    pub projects: Vec<ProjectStorageInfo>,
+    pub totals: StorageUsage,
+}
+
+/// A stored snapshot of a project's flags and IOC rules.
+#[derive(Debug, Clone, Serialize)]
+pub struct AnnotationsBackup {
+    pub id: String,
+    pub created_at: DateTime<Utc>,
+    pub flagged_rows: usize,
+    pub ioc_count: usize,
+    pub size_bytes: u64,
+}
+
+/// Free-form investigation notes kept with a project.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct CaseNotes {
+    /// The notes document, edited as a whole.
+    #[serde(default)]
+    pub text: String,
+    /// Timestamped log entries, oldest first.
+    #[serde(default)]
+    pub entries: Vec<CaseNoteEntry>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CaseNoteEntry {
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+}
+
+/// A row marked to come back to, kept apart from its flag and memo.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Bookmark {
+    pub row_index: usize,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// A named triage perspective: a query saved together with the columns and
+/// page size it is read with.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedView {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub search: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub whole_word: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag_filter: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_key: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_direction: Option<String>,
+    /// Columns shown; the project's hidden columns apply when empty.
+    #[serde(default)]
+    pub visible_columns: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+    /// Applied when the project is opened. At most one view is the default.
+    #[serde(default)]
+    pub is_default: bool,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CreateProjectResponse {
+    pub project: ProjectSummary,
+    /// Column types of the imported data, after inference and overrides.
+    pub column_types: Vec<ColumnType>,
+    /// Set for lenient imports; lists only the first issues.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub import_report: Option<ImportReport>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -47,10 +487,68 @@ pub struct LoadProjectResponse {
     pub column_max_chars: HashMap<String, usize>,
     pub iocs: Vec<IocEntry>,
     pub initial_rows: Vec<ProjectRow>,
+    /// The project's default view, already applied to `hidden_columns` and
+    /// `initial_rows`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub view: Option<SavedView>,
+}
+
+/// Outcome of refreshing a project from its (updated) source files.
+#[derive(Debug, Clone, Serialize)]
+pub struct ReimportProjectResponse {
+    pub project: ProjectSummary,
+    /// Rows whose content also exists in the previous import.
+    pub matched_rows: usize,
+    pub added_rows: usize,
+    pub removed_rows: usize,
+    /// Flags and memos moved onto matching rows of the new data.
+    pub carried_annotations: usize,
+    /// Annotations whose row no longer exists and were dropped.
+    pub dropped_annotations: usize,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct FlagEntry {
     pub flag: String,
+    /// Free text for rows without comments; for rows with comments, their
+    /// rendered summary, so everything reading memos sees the comments.
     pub memo: Option<String>,
+    /// Observations on the row, oldest first.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub comments: Vec<RowComment>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RowComment {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub author: Option<String>,
+    pub timestamp: DateTime<Utc>,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionState {
+    pub project_id: Option<Uuid>,
+    pub search: Option<String>,
+    pub flag_filter: Option<String>,
+    pub sort_key: Option<String>,
+    pub sort_direction: Option<String>,
+    #[serde(default)]
+    pub offset: usize,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct RowLink {
+    pub project_id: Uuid,
+    pub row_id: usize,
+}
+
+/// Work requested by how the app was launched (deep link or file passed by the OS).
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum LaunchRequest {
+    RowLink(RowLink),
+    OpenArchive { path: String },
+    ImportCsv { path: String },
 }
@@ -0,0 +1,175 @@
+//! In-process timing and cache counters for the slow paths, reported by
+//! `get_performance_stats`. Counters live for the lifetime of the process.
+
+use std::time::Duration;
+
+use parking_lot::Mutex;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Query,
+    IocEvaluation,
+    Export,
+}
+
+impl Operation {
+    const ALL: [Operation; 3] = [
+        Operation::Query,
+        Operation::IocEvaluation,
+        Operation::Export,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Operation::Query => "query_project_rows",
+            Operation::IocEvaluation => "ioc_evaluation",
+            Operation::Export => "export_project",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Cache {
+    SearchableText,
+    IocFlags,
+    SortOrder,
+}
+
+impl Cache {
+    const ALL: [Cache; 3] = [Cache::SearchableText, Cache::IocFlags, Cache::SortOrder];
+
+    fn name(self) -> &'static str {
+        match self {
+            Cache::SearchableText => "searchable_text",
+            Cache::IocFlags => "ioc_flags",
+            Cache::SortOrder => "sort_order",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct OperationTotals {
+    count: u64,
+    total: Duration,
+    last: Duration,
+    max: Duration,
+    rows_scanned: u64,
+    last_rows_scanned: u64,
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct CacheTotals {
+    hits: u64,
+    misses: u64,
+}
+
+#[derive(Debug, Default)]
+struct Totals {
+    operations: [OperationTotals; Operation::ALL.len()],
+    caches: [CacheTotals; Cache::ALL.len()],
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationStats {
+    pub name: &'static str,
+    pub count: u64,
+    pub last_ms: f64,
+    pub average_ms: f64,
+    pub max_ms: f64,
+    /// Project rows read from disk, summed over every run.
+    pub rows_scanned: u64,
+    pub last_rows_scanned: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CacheStats {
+    pub name: &'static str,
+    pub hits: u64,
+    pub misses: u64,
+    /// Share of lookups answered by the cache; `None` before the first lookup.
+    pub hit_rate: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PerformanceStats {
+    pub operations: Vec<OperationStats>,
+    pub caches: Vec<CacheStats>,
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+#[derive(Default)]
+pub struct PerfStats {
+    inner: Mutex<Totals>,
+}
+
+impl PerfStats {
+    pub fn record(&self, operation: Operation, elapsed: Duration, rows_scanned: usize) {
+        let mut totals = self.inner.lock();
+        let entry = &mut totals.operations[operation as usize];
+        entry.count += 1;
+        entry.total += elapsed;
+        entry.last = elapsed;
+        entry.max = entry.max.max(elapsed);
+        entry.rows_scanned += rows_scanned as u64;
+        entry.last_rows_scanned = rows_scanned as u64;
+        tracing::debug!(
+            operation = operation.name(),
+            elapsed_ms = millis(elapsed),
+            rows_scanned,
+            "operation finished"
+        );
+    }
+
+    pub fn record_cache(&self, cache: Cache, hit: bool) {
+        let mut totals = self.inner.lock();
+        let entry = &mut totals.caches[cache as usize];
+        if hit {
+            entry.hits += 1;
+        } else {
+            entry.misses += 1;
+        }
+    }
+
+    pub fn snapshot(&self) -> PerformanceStats {
+        let totals = self.inner.lock();
+        let operations = Operation::ALL
+            .iter()
+            .zip(totals.operations.iter())
+            .map(|(operation, entry)| OperationStats {
+                name: operation.name(),
+                count: entry.count,
+                last_ms: millis(entry.last),
+                average_ms: if entry.count == 0 {
+                    0.0
+                } else {
+                    millis(entry.total) / entry.count as f64
+                },
+                max_ms: millis(entry.max),
+                rows_scanned: entry.rows_scanned,
+                last_rows_scanned: entry.last_rows_scanned,
+            })
+            .collect();
+        let caches = Cache::ALL
+            .iter()
+            .zip(totals.caches.iter())
+            .map(|(cache, entry)| {
+                let lookups = entry.hits + entry.misses;
+                CacheStats {
+                    name: cache.name(),
+                    hits: entry.hits,
+                    misses: entry.misses,
+                    hit_rate: (lookups > 0).then(|| entry.hits as f64 / lookups as f64),
+                }
+            })
+            .collect();
+        PerformanceStats { operations, caches }
+    }
+
+    pub fn reset(&self) {
+        *self.inner.lock() = Totals::default();
+    }
+}
@@ -0,0 +1,259 @@
+use serde::Serialize;
+
+use crate::sigma::SigmaFieldMapping;
+
+/// Column layout of a well-known DFIR tool's output and how a project built
+/// from it should be set up.
+struct ToolPreset {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    /// Alternative header sets; the preset matches when every column of any
+    /// one set is present (case-insensitive).
+    signatures: &'static [&'static [&'static str]],
+    /// Candidates for the timeline column, in order of preference.
+    timestamp_columns: &'static [&'static str],
+    /// Bookkeeping columns hidden by default.
+    hidden_columns: &'static [&'static str],
+    /// Sigma field names and the columns their values end up in.
+    sigma_fields: &'static [(&'static str, &'static [&'static str])],
+}
+
+/// Sigma fields that these tools fold into free-text detail columns.
+const EVENT_DATA_FIELDS: &[&str] = &[
+    "Image",
+    "CommandLine",
+    "ParentImage",
+    "ParentCommandLine",
+    "TargetFilename",
+    "TargetObject",
+    "User",
+    "TargetUserName",
+    "SubjectUserName",
+    "IpAddress",
+    "DestinationIp",
+    "DestinationHostname",
+    "ServiceName",
+    "ImagePath",
+    "Hashes",
+];
+
+const PRESETS: &[ToolPreset] = &[
+    ToolPreset {
+        id: "hayabusa",
+        name: "Hayabusa",
+        description: "Hayabusa csv-timeline / json-timeline output",
+        signatures: &[&[
+            "Timestamp",
+            "RuleTitle",
+            "Level",
+            "Computer",
+            "Channel",
+            "EventID",
+        ]],
+        timestamp_columns: &["Timestamp"],
+        hidden_columns: &[
+            "RecordID",
+            "RuleFile",
+            "EvtxFile",
+            "RuleAuthor",
+            "RuleCreationDate",
+            "RuleModifiedDate",
+            "Status",
+            "RuleID",
+            "Provider",
+        ],
+        sigma_fields: &[
+            ("EventID", &["EventID"]),
+            ("Computer", &["Computer"]),
+            ("Channel", &["Channel"]),
+            ("Provider_Name", &["Provider"]),
+        ],
+    },
+    ToolPreset {
+        id: "chainsaw",
+        name: "Chainsaw",
+        description: "Chainsaw hunt --csv output",
+        signatures: &[&["timestamp", "detections", "Computer", "Event ID"]],
+        timestamp_columns: &["timestamp"],
+        hidden_columns: &["path", "Record ID", "count"],
+        sigma_fields: &[
+            ("EventID", &["Event ID"]),
+            ("Computer", &["Computer"]),
+            ("Provider_Name", &["Event.System.Provider"]),
+        ],
+    },
+    ToolPreset {
+        id: "kape",
+        name: "KAPE (EZ Tools)",
+        description: "EvtxECmd and MFTECmd CSV output from KAPE modules",
+        signatures: &[
+            &[
+                "TimeCreated",
+                "EventId",
+                "Channel",
+                "MapDescription",
+                "PayloadData1",
+            ],
+            &["EntryNumber", "ParentPath", "FileName", "Created0x10"],
+        ],
+        timestamp_columns: &["TimeCreated", "Created0x10"],
+        hidden_columns: &[
+            "RecordNumber",
+            "ChunkNumber",
+            "ProcessId",
+            "ThreadId",
+            "ExtraDataOffset",
+            "HiddenRecord",
+            "Keywords",
+            "SequenceNumber",
+            "ParentEntryNumber",
+            "ParentSequenceNumber",
+            "ReferenceCount",
+            "ReparseTarget",
+            "LogfileSequenceNumber",
+            "SecurityId",
+            "ObjectIdFileDroid",
+            "FnAttributeId",
+            "OtherAttributeId",
+        ],
+        sigma_fields: &[
+            ("EventID", &["EventId"]),
+            ("Computer", &["Computer"]),
+            ("Channel", &["Channel"]),
+            ("Provider_Name", &["Provider"]),
+            ("TargetFilename", &["FileName", "ParentPath"]),
+        ],
+    },
+    ToolPreset {
+        id: "velociraptor",
+        name: "Velociraptor",
+        description: "Velociraptor artifact and hunt exports",
+        signatures: &[&["_Source"], &["ClientId", "Fqdn"]],
+        timestamp_columns: &[
+            "EventTime",
+            "Timestamp",
+            "TimeCreated",
+            "System.TimeCreated.SystemTime",
+            "Time",
+            "_ts",
+        ],
+        hidden_columns: &["_Source", "ClientId", "FlowId", "_ts"],
+        sigma_fields: &[
+            ("EventID", &["EventID", "System.EventID.Value"]),
+            ("Computer", &["Computer", "System.Computer", "Fqdn"]),
+            ("Channel", &["Channel", "System.Channel"]),
+        ],
+    },
+];
+
+/// A preset as offered in the import dialog.
+#[derive(Debug, Clone, Serialize)]
+pub struct ImportPresetInfo {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+}
+
+/// Project settings derived from a preset for one imported file.
+#[derive(Debug, Clone, Default)]
+pub struct PresetSetup {
+    pub preset: String,
+    pub timestamp_column: Option<String>,
+    pub hidden_columns: Vec<String>,
+    pub sigma_mapping: SigmaFieldMapping,
+}
+
+pub fn list_presets() -> Vec<ImportPresetInfo> {
+    PRESETS
+        .iter()
+        .map(|preset| ImportPresetInfo {
+            id: preset.id,
+            name: preset.name,
+            description: preset.description,
+        })
+        .collect()
+}
+
+/// Finds `wanted` among `columns` ignoring case, returning the actual name.
+fn find_column<'a>(columns: &'a [String], wanted: &str) -> Option<&'a String> {
+    columns
+        .iter()
+        .find(|column| column.eq_ignore_ascii_case(wanted))
+}
+
+fn matches(preset: &ToolPreset, columns: &[String]) -> bool {
+    preset.signatures.iter().any(|signature| {
+        signature
+            .iter()
+            .all(|wanted| find_column(columns, wanted).is_some())
+    })
+}
+
+/// Looks a preset up by id, or with `auto` picks the first one whose header
+/// signature matches `columns`.
+fn resolve<'a>(selection: &str, columns: &[String]) -> Result<Option<&'a ToolPreset>, String> {
+    if selection.eq_ignore_ascii_case("auto") {
+        return Ok(PRESETS.iter().find(|preset| matches(preset, columns)));
+    }
+    PRESETS
+        .iter()
+        .find(|preset| preset.id.eq_ignore_ascii_case(selection))
+        .map(Some)
+        .ok_or_else(|| format!("unknown import preset {}", selection))
+}
+
+/// Works out the timeline column, hidden columns and Sigma field mapping for
+/// an import with the selected preset (`auto` to detect one). Only columns
+/// the data actually has are used; `None` when no preset applies.
+pub fn setup_for_columns(
+    selection: &str,
+    columns: &[String],
+) -> Result<Option<PresetSetup>, String> {
+    let Some(preset) = resolve(selection, columns)? else {
+        return Ok(None);
+    };
+    let present = |names: &[&str]| -> Vec<String> {
+        names
+            .iter()
+            .filter_map(|name| find_column(columns, name).cloned())
+            .collect()
+    };
+
+    let mut sigma_mapping = SigmaFieldMapping::new();
+    for (field, targets) in preset.sigma_fields {
+        let targets = present(targets);
+        if !targets.is_empty() {
+            sigma_mapping.insert(field.to_string(), targets);
+        }
+    }
+    // Event data is folded into free-text columns whose names differ per tool.
+    let detail_columns = present(&[
+        "Details",
+        "ExtraFieldInfo",
+        "Event Data",
+        "PayloadData1",
+        "PayloadData2",
+        "PayloadData3",
+        "PayloadData4",
+        "PayloadData5",
+        "PayloadData6",
+        "ExecutableInfo",
+        "EventData",
+        "Message",
+    ]);
+    if !detail_columns.is_empty() {
+        for field in EVENT_DATA_FIELDS {
+            sigma_mapping
+                .entry(field.to_string())
+                .or_insert_with(|| detail_columns.clone());
+        }
+    }
+
+    Ok(Some(PresetSetup {
+        preset: preset.id.to_string(),
+        timestamp_column: present(preset.timestamp_columns).into_iter().next(),
+        hidden_columns: present(preset.hidden_columns),
+        sigma_mapping,
+    }))
+}
@@ -1,19 +1,907 @@
-use std::fs::File;
-use std::path::Path;
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
 
-use anyhow::{Context, Result};
-use polars::prelude::{DataFrame, ParquetReader, ParquetWriter, SerReader};
+use anyhow::{anyhow, Context, Result};
+use flate2::read::MultiGzDecoder;
+use glob::glob;
+use polars::prelude::{
+    col, concat, lit, CsvReadOptions, DataFrame, DataType, Expr, Field, IdxCa, IdxSize, IntoLazy,
+    IpcReader, LazyFrame, NamedFrom, ParquetCompression, ParquetReader, ParquetWriter,
+    ScanArgsParquet, Schema, SerReader, Series, UnionArgs, ZstdLevel,
+};
+use sha2::{Digest, Sha256};
+
+use crate::column_types::{apply_column_types, conform_to_schema, polars_dtype};
+use crate::error::DataFileUnreadable;
+use crate::event_logs::read_event_xml_dataframe;
+use crate::evtx::read_evtx_dataframe;
+use crate::jsonl::{read_jsonl_dataframe, read_jsonl_stream};
+use crate::models::{
+    ImportIssue, ImportOptions, ImportReport, SourceFile, SourceIntegrity, SourceIntegrityStatus,
+};
+use crate::settings::PerformanceSettings;
+use crate::sqlite::read_sqlite_dataframe;
+use crate::xlsx::read_xlsx_dataframe;
+use crate::zip_reader::ZipArchive;
+
+/// Default upper bound on concurrent input parsers during multi-file import; each
+/// worker holds one parsed file in memory until it has been spilled to a Parquet part.
+const MAX_IMPORT_WORKERS: usize = 4;
+
+/// Column added by multi-file imports naming the file each row came from.
+pub const SOURCE_FILE_COLUMN: &str = "__source_file";
+
+/// Decompressed CSV is parsed in batches of roughly this many bytes, so the
+/// uncompressed file is never materialized in memory or on disk.
+const STREAM_BATCH_BYTES: usize = 64 * 1024 * 1024;
+
+/// Extensions read by a parser other than the CSV one.
+const NON_CSV_EXTENSIONS: &[&str] = &[
+    "jsonl", "ndjson", "xlsx", "xlsm", "parquet", "pq", "arrow", "ipc", "feather", "evtx", "xml",
+    "sqlite", "sqlite3", "db", "db3", "gz", "zip",
+];
+
+/// Most issues kept in an import report; later ones are only counted.
+const MAX_REPORTED_IMPORT_ISSUES: usize = 10_000;
+/// Zstandard level used when compacting a project's Parquet file.
+const COMPACT_ZSTD_LEVEL: i32 = 9;
+/// Rows per row group in project Parquet files. Page reads skip row groups
+/// whose `__rowid` statistics fall outside the page, so smaller groups mean
+/// less data decoded per page.
+const PROJECT_ROW_GROUP_SIZE: usize = 64 * 1024;
 
 pub fn read_project_dataframe(path: &Path) -> Result<DataFrame> {
     ParquetReader::new(File::open(path)?)
         .finish()
-        .context("failed to read parquet file")
+        .with_context(|| DataFileUnreadable(path.to_path_buf()))
+}
+
+/// Reads the data column names from the Parquet footer without loading rows.
+pub fn read_project_columns(path: &Path) -> Result<Vec<String>> {
+    let schema = ParquetReader::new(File::open(path)?)
+        .schema()
+        .with_context(|| DataFileUnreadable(path.to_path_buf()))?;
+    Ok(schema
+        .fields
+        .iter()
+        .map(|field| field.name.to_string())
+        .filter(|name| name != "__rowid")
+        .collect())
+}
+
+/// Lazily scans the project data. Column selections and filters on the
+/// returned frame are pushed down into the Parquet reader.
+pub fn scan_project(path: &Path) -> Result<LazyFrame> {
+    LazyFrame::scan_parquet(path, ScanArgsParquet::default())
+        .with_context(|| format!("failed to scan parquet file {:?}", path))
+}
+
+/// Reads the row count from the Parquet footer.
+pub fn read_project_row_count(path: &Path) -> Result<usize> {
+    ParquetReader::new(File::open(path)?)
+        .num_rows()
+        .with_context(|| DataFileUnreadable(path.to_path_buf()))
+}
+
+/// Reads only the named columns of every row.
+pub fn read_project_column_subset(path: &Path, columns: &[String]) -> Result<DataFrame> {
+    if columns.is_empty() {
+        return Ok(DataFrame::default());
+    }
+    let exprs: Vec<Expr> = columns.iter().map(|name| col(name)).collect();
+    scan_project(path)?
+        .select(exprs)
+        .collect()
+        .context("failed to read parquet columns")
+}
+
+/// Reads the named columns of `len` rows starting at `start`. The range is
+/// pushed down as a `__rowid` filter, so only the row groups it covers are
+/// decoded.
+pub fn read_project_row_range(
+    path: &Path,
+    columns: &[String],
+    start: usize,
+    len: usize,
+) -> Result<DataFrame> {
+    if columns.is_empty() {
+        return Ok(DataFrame::default());
+    }
+    let exprs: Vec<Expr> = columns.iter().map(|name| col(name)).collect();
+    let scan = scan_project(path)?;
+    let schema = scan.schema().context("failed to read parquet schema")?;
+    let scan = if schema.get("__rowid").is_some() {
+        scan.filter(
+            col("__rowid")
+                .gt_eq(lit(start as i64))
+                .and(col("__rowid").lt(lit((start + len) as i64))),
+        )
+    } else {
+        scan.slice(start as i64, len as IdxSize)
+    };
+    scan.select(exprs)
+        .collect()
+        .context("failed to read parquet rows")
+}
+
+/// Reads the named columns of the given rows, in the given order. The range
+/// of the rows' `__rowid`s is pushed down to the scan, so row groups outside
+/// it are never decoded.
+pub fn read_project_rows(path: &Path, columns: &[String], rows: &[usize]) -> Result<DataFrame> {
+    let exprs: Vec<Expr> = columns.iter().map(|name| col(name)).collect();
+    let scan = scan_project(path)?;
+    let (Some(&first), Some(&last)) = (rows.iter().min(), rows.iter().max()) else {
+        return scan
+            .select(exprs)
+            .limit(0)
+            .collect()
+            .context("failed to read parquet schema");
+    };
+    let schema = scan.schema().context("failed to read parquet schema")?;
+    if schema.get("__rowid").is_none() {
+        // Data written before row ids were stored: fall back to positions.
+        let df = scan
+            .select(exprs)
+            .collect()
+            .context("failed to read parquet rows")?;
+        let take = rows.iter().map(|&row| row as IdxSize).collect();
+        return df
+            .take(&IdxCa::from_vec("take_idx", take))
+            .context("failed to select project rows");
+    }
+
+    let mut projection = exprs;
+    projection.push(col("__rowid"));
+    let df = scan
+        .filter(
+            col("__rowid")
+                .gt_eq(lit(first as i64))
+                .and(col("__rowid").lt_eq(lit(last as i64))),
+        )
+        .select(projection)
+        .collect()
+        .context("failed to read parquet rows")?;
+    let row_ids = df.column("__rowid")?.cast(&DataType::Int64)?;
+    let positions: HashMap<i64, IdxSize> = row_ids
+        .i64()?
+        .into_iter()
+        .enumerate()
+        .filter_map(|(position, id)| id.map(|id| (id, position as IdxSize)))
+        .collect();
+    let take = rows
+        .iter()
+        .map(|&row| {
+            positions
+                .get(&(row as i64))
+                .copied()
+                .ok_or_else(|| anyhow!("row {} is missing from the project data", row))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    let page = df
+        .take(&IdxCa::from_vec("take_idx", take))
+        .context("failed to select project rows")?;
+    Ok(page.drop("__rowid")?)
 }
 
 pub fn write_project_dataframe(path: &Path, df: &mut DataFrame) -> Result<()> {
     let file =
         File::create(path).with_context(|| format!("failed to create parquet file {:?}", path))?;
-    let writer = ParquetWriter::new(file);
-    writer.finish(df).context("failed to write parquet file")?;
+    ParquetWriter::new(file)
+        .with_row_group_size(Some(PROJECT_ROW_GROUP_SIZE))
+        .finish(df)
+        .context("failed to write parquet file")?;
+    Ok(())
+}
+
+/// Writes the project data with stronger Zstandard compression than the
+/// import path uses, trading write time for a smaller file.
+pub fn write_compacted_dataframe(path: &Path, df: &mut DataFrame) -> Result<()> {
+    let file =
+        File::create(path).with_context(|| format!("failed to create parquet file {:?}", path))?;
+    ParquetWriter::new(file)
+        .with_compression(ParquetCompression::Zstd(Some(ZstdLevel::try_new(
+            COMPACT_ZSTD_LEVEL,
+        )?)))
+        .with_statistics(true)
+        .with_row_group_size(Some(PROJECT_ROW_GROUP_SIZE))
+        .finish(df)
+        .context("failed to write parquet file")?;
     Ok(())
 }
+
+/// CSV reader options honoring the configured import thread and chunk limits.
+/// Overridden columns are parsed straight into their requested type, so text
+/// such as leading-zero IDs never passes through an inferred integer.
+pub fn csv_read_options(
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+) -> CsvReadOptions {
+    let mut read_options = CsvReadOptions::default()
+        .with_n_threads(performance.import_threads)
+        .with_has_header(options.has_headers)
+        .map_parse_options(|parse| parse.with_try_parse_dates(options.parse_dates));
+    if options.lenient {
+        // Type every column from all of its values, so a stray value far down
+        // the file turns the column into text instead of failing the parse.
+        read_options = read_options.with_infer_schema_length(None);
+    }
+    if let Some(chunk_size) = performance.import_chunk_size {
+        read_options = read_options.with_chunk_size(chunk_size);
+    }
+    if !options.dtypes.is_empty() {
+        // Columns that are normalized first are read as text and converted
+        // once cleaned up, see `apply_column_types`.
+        let overrides: Schema = options
+            .dtypes
+            .iter()
+            .map(|(name, dtype)| {
+                let normalized = options.trim_whitespace
+                    || options.lowercase_columns.contains(name)
+                    || options.strip_thousands_columns.contains(name);
+                let dtype = if normalized {
+                    DataType::String
+                } else {
+                    polars_dtype(*dtype)
+                };
+                Field::new(name, dtype)
+            })
+            .collect();
+        read_options = read_options.with_schema_overwrite(Some(Arc::new(overrides)));
+    }
+    read_options
+}
+
+/// Reads a CSV file; lenient imports go through the record-checking stream
+/// reader and note every repaired or dropped record in `report`.
+pub fn read_csv_dataframe(
+    path: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame> {
+    let file = File::open(path).with_context(|| format!("failed to open CSV file {:?}", path))?;
+    if options.lenient {
+        return read_csv_stream(file, options, performance, report)
+            .with_context(|| format!("failed to parse CSV file {:?}", path));
+    }
+    csv_read_options(options, performance)
+        .into_reader_with_file_handle(file)
+        .finish()
+        .with_context(|| format!("failed to parse CSV file {:?}", path))
+}
+
+fn lowercase_extension(path: &Path) -> String {
+    path.extension()
+        .map(|ext| ext.to_string_lossy().to_lowercase())
+        .unwrap_or_default()
+}
+
+/// Project name derived from the source file: `events.csv.gz` → `events`.
+pub fn source_display_name(path: &Path) -> String {
+    let inner = match lowercase_extension(path).as_str() {
+        "gz" => path.file_stem().map(PathBuf::from),
+        _ => Some(path.to_path_buf()),
+    };
+    inner
+        .as_deref()
+        .and_then(Path::file_stem)
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| "Imported Project".to_string())
+}
+
+/// Appends one CSV record to `buf`; a record spans lines while a quoted
+/// field is open. Returns `false` once the input is exhausted.
+fn read_csv_record<R: BufRead>(reader: &mut R, buf: &mut Vec<u8>) -> Result<bool> {
+    let mut in_quotes = false;
+    let mut read_any = false;
+    loop {
+        let start = buf.len();
+        if reader.read_until(b'\n', buf)? == 0 {
+            return Ok(read_any);
+        }
+        read_any = true;
+        if buf[start..].iter().filter(|&&byte| byte == b'"').count() % 2 == 1 {
+            in_quotes = !in_quotes;
+        }
+        if !in_quotes || !buf.ends_with(b"\n") {
+            return Ok(true);
+        }
+    }
+}
+
+fn record_issue(report: &mut ImportReport, line: usize, message: String, skipped: bool) {
+    if skipped {
+        report.rows_skipped += 1;
+    } else {
+        report.rows_repaired += 1;
+    }
+    if report.issues.len() < MAX_REPORTED_IMPORT_ISSUES {
+        report.issues.push(ImportIssue {
+            file: None,
+            line,
+            message,
+            skipped,
+        });
+    }
+}
+
+/// Length of a CSV record without its trailing line break.
+fn record_content_len(record: &[u8]) -> usize {
+    let mut end = record.len();
+    while end > 0 && matches!(record[end - 1], b'\n' | b'\r') {
+        end -= 1;
+    }
+    end
+}
+
+/// Offsets of the field separators outside quoted fields.
+fn field_separators(record: &[u8]) -> Vec<usize> {
+    let mut in_quotes = false;
+    let mut separators = Vec::new();
+    for (offset, &byte) in record.iter().enumerate() {
+        match byte {
+            b'"' => in_quotes = !in_quotes,
+            b',' if !in_quotes => separators.push(offset),
+            _ => {}
+        }
+    }
+    separators
+}
+
+/// Checks records for a lenient import as they are read from the stream.
+struct LenientRecords<'a> {
+    expected_fields: Option<usize>,
+    next_line: usize,
+    report: &'a mut ImportReport,
+}
+
+impl LenientRecords<'_> {
+    fn new(report: &mut ImportReport) -> LenientRecords<'_> {
+        LenientRecords {
+            expected_fields: None,
+            next_line: 1,
+            report,
+        }
+    }
+
+    /// Advances past `record` and returns the line it started on.
+    fn advance(&mut self, record: &[u8]) -> usize {
+        let line = self.next_line;
+        self.next_line += record.iter().filter(|&&byte| byte == b'\n').count().max(1);
+        line
+    }
+
+    fn header(&mut self, header: &[u8]) {
+        self.advance(header);
+        let content = &header[..record_content_len(header)];
+        self.expected_fields = Some(field_separators(content).len() + 1);
+    }
+
+    /// Repairs the record appended to `buf` at `start`: invalid UTF-8 is
+    /// replaced, short records are padded with empty fields, long ones lose
+    /// their extra fields, and a record left open by an unterminated quote
+    /// is dropped.
+    fn repair(&mut self, buf: &mut Vec<u8>, start: usize) {
+        let line = self.advance(&buf[start..]);
+        if record_content_len(&buf[start..]) == 0 {
+            return;
+        }
+        if buf[start..].iter().filter(|&&byte| byte == b'"').count() % 2 == 1 {
+            buf.truncate(start);
+            record_issue(
+                self.report,
+                line,
+                "unterminated quoted field runs to the end of the input".into(),
+                true,
+            );
+            return;
+        }
+        let mut problems = Vec::new();
+        if std::str::from_utf8(&buf[start..]).is_err() {
+            let text = String::from_utf8_lossy(&buf[start..]).into_owned();
+            buf.truncate(start);
+            buf.extend_from_slice(text.as_bytes());
+            problems.push("invalid UTF-8 replaced".to_string());
+        }
+        let content_end = start + record_content_len(&buf[start..]);
+        let separators = field_separators(&buf[start..content_end]);
+        let fields = separators.len() + 1;
+        let expected = *self.expected_fields.get_or_insert(fields);
+        if fields < expected {
+            let padding = vec![b','; expected - fields];
+            buf.splice(content_end..content_end, padding);
+            problems.push(format!(
+                "expected {} fields, found {}; padded with empty values",
+                expected, fields
+            ));
+        } else if fields > expected {
+            buf.drain(start + separators[expected - 1]..content_end);
+            problems.push(format!(
+                "expected {} fields, found {}; extra values dropped",
+                expected, fields
+            ));
+        }
+        if !problems.is_empty() {
+            record_issue(self.report, line, problems.join("; "), false);
+        }
+    }
+}
+
+fn concat_frames(mut frames: Vec<DataFrame>) -> Result<DataFrame> {
+    if frames.len() == 1 {
+        return Ok(frames.remove(0));
+    }
+    concat(
+        frames.into_iter().map(|df| df.lazy()).collect::<Vec<_>>(),
+        UnionArgs {
+            rechunk: true,
+            to_supertypes: true,
+            ..Default::default()
+        },
+    )
+    .and_then(|merged| merged.collect())
+    .context("failed to combine CSV batches")
+}
+
+/// Parses CSV from a forward-only stream in record-aligned batches, each
+/// prefixed with the header line (if the input has one), and hands every
+/// parsed batch to `sink`. Lenient imports repair records on the way, see
+/// [`LenientRecords::repair`].
+fn for_each_csv_batch<R: Read>(
+    reader: R,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+    mut sink: impl FnMut(DataFrame) -> Result<()>,
+) -> Result<()> {
+    let mut reader = BufReader::new(reader);
+    let mut lenient = options.lenient.then(|| LenientRecords::new(report));
+    let mut header = Vec::new();
+    if options.has_headers {
+        if !read_csv_record(&mut reader, &mut header)? {
+            return Err(anyhow!("input is empty"));
+        }
+        if let Some(records) = lenient.as_mut() {
+            records.header(&header);
+        }
+        if !header.ends_with(b"\n") {
+            header.push(b'\n');
+        }
+    }
+    let mut batches = 0;
+    loop {
+        let mut batch = header.clone();
+        let mut exhausted = false;
+        while batch.len() < STREAM_BATCH_BYTES {
+            let start = batch.len();
+            if !read_csv_record(&mut reader, &mut batch)? {
+                exhausted = true;
+                break;
+            }
+            if let Some(records) = lenient.as_mut() {
+                records.repair(&mut batch, start);
+            }
+        }
+        if batch.len() > header.len() || batches == 0 {
+            batches += 1;
+            let frame = csv_read_options(options, performance)
+                .into_reader_with_file_handle(Cursor::new(batch))
+                .finish()
+                .with_context(|| format!("failed to parse CSV batch {}", batches))?;
+            sink(frame)?;
+        }
+        if exhausted {
+            break;
+        }
+    }
+    Ok(())
+}
+
+/// Parses a CSV stream batch by batch (see [`for_each_csv_batch`]) into one
+/// frame. Column types are widened across batches.
+pub fn read_csv_stream<R: Read>(
+    reader: R,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame> {
+    let mut frames = Vec::new();
+    for_each_csv_batch(reader, options, performance, report, |frame| {
+        frames.push(frame);
+        Ok(())
+    })?;
+    concat_frames(frames)
+}
+
+/// Opens the payload of a `.gz` file or single-file `.zip` archive as a
+/// decompressing stream, returning the inner file name alongside it.
+fn open_compressed_input(path: &Path) -> Result<(String, Box<dyn Read + Send>)> {
+    if lowercase_extension(path) == "gz" {
+        let file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+        let inner = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        return Ok((inner, Box::new(MultiGzDecoder::new(BufReader::new(file)))));
+    }
+    let archive = ZipArchive::open(path)?;
+    let members: Vec<_> = archive
+        .entries()
+        .iter()
+        .filter(|entry| {
+            let file_name = entry.name.rsplit('/').next().unwrap_or_default();
+            !entry.is_dir() && !entry.name.starts_with("__MACOSX/") && !file_name.starts_with('.')
+        })
+        .collect();
+    let [member] = members.as_slice() else {
+        return Err(anyhow!(
+            "{:?} contains {} files; only single-file archives can be imported",
+            path,
+            members.len()
+        ));
+    };
+    Ok((member.name.clone(), archive.open_entry(member)?))
+}
+
+fn read_compressed_dataframe(
+    path: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame> {
+    let (inner, reader) = open_compressed_input(path)?;
+    match lowercase_extension(Path::new(&inner)).as_str() {
+        "jsonl" | "ndjson" => read_jsonl_stream(BufReader::new(reader)),
+        extension if NON_CSV_EXTENSIONS.contains(&extension) => Err(anyhow!(
+            "{} cannot be read from inside a compressed file; extract it first",
+            inner
+        )),
+        _ => read_csv_stream(reader, options, performance, report),
+    }
+    .with_context(|| format!("failed to import {} from {:?}", inner, path))
+}
+
+/// Opens an input read by the CSV parser as a byte stream: plain files as
+/// they are, `.gz`/`.zip` through their decompressor. `None` for inputs in
+/// any other format.
+fn open_csv_source(path: &Path) -> Result<Option<Box<dyn Read + Send>>> {
+    let extension = lowercase_extension(path);
+    if extension == "gz" || extension == "zip" {
+        let (inner, reader) = open_compressed_input(path)?;
+        let inner_extension = lowercase_extension(Path::new(&inner));
+        return Ok((!NON_CSV_EXTENSIONS.contains(&inner_extension.as_str())).then_some(reader));
+    }
+    if NON_CSV_EXTENSIONS.contains(&extension.as_str()) {
+        return Ok(None);
+    }
+    let file = File::open(path).with_context(|| format!("failed to open CSV file {:?}", path))?;
+    Ok(Some(Box::new(file)))
+}
+
+/// Outcome of streaming a CSV input into a Parquet file.
+pub struct StreamedImport {
+    pub rows: usize,
+    /// Schema of the written file.
+    pub schema: Schema,
+}
+
+/// Streams a CSV input (plain or compressed) into a Parquet file one batch
+/// at a time, so memory use follows the batch size instead of the input
+/// size. Every batch gets the requested column types, and later batches are
+/// converted to the types chosen for the first one. `prepare` then sees
+/// each batch together with the index of its first row, right before it is
+/// written as a row group. Returns `None` for inputs that are not CSV.
+pub fn stream_csv_to_parquet(
+    path: &Path,
+    output: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+    mut prepare: impl FnMut(&mut DataFrame, usize) -> Result<()>,
+) -> Result<Option<StreamedImport>> {
+    let Some(reader) = open_csv_source(path)? else {
+        return Ok(None);
+    };
+    let mut parsed_schema: Option<Schema> = None;
+    let mut writer = None;
+    let mut rows = 0;
+    for_each_csv_batch(reader, options, performance, report, |batch| {
+        let batch = apply_column_types(batch, options)?;
+        let mut batch = match &parsed_schema {
+            Some(schema) => conform_to_schema(batch, schema)
+                .with_context(|| format!("rows after {} changed type", rows))?,
+            None => {
+                parsed_schema = Some(batch.schema());
+                batch
+            }
+        };
+        prepare(&mut batch, rows)?;
+        rows += batch.height();
+        if writer.is_none() {
+            let file = File::create(output)
+                .with_context(|| format!("failed to create parquet file {:?}", output))?;
+            let schema = batch.schema();
+            writer = Some((ParquetWriter::new(file).batched(&schema)?, schema));
+        }
+        if let Some((writer, _)) = writer.as_mut() {
+            writer
+                .write_batch(&batch)
+                .context("failed to write parquet row group")?;
+        }
+        Ok(())
+    })
+    .with_context(|| format!("failed to import {:?}", path))?;
+    let (writer, schema) = writer.ok_or_else(|| anyhow!("{:?} contains no data", path))?;
+    writer.finish().context("failed to finish parquet file")?;
+    Ok(Some(StreamedImport { rows, schema }))
+}
+
+/// Reads an Arrow IPC file (`.arrow`, Feather v2) keeping its column types.
+fn read_ipc_dataframe(path: &Path) -> Result<DataFrame> {
+    let file = File::open(path).with_context(|| format!("failed to open Arrow file {:?}", path))?;
+    IpcReader::new(file)
+        .finish()
+        .with_context(|| format!("failed to read Arrow file {:?}", path))
+}
+
+/// Reads an import source, choosing the parser from the file extension:
+/// JSON Lines for `.jsonl`/`.ndjson`, Excel for `.xlsx`/`.xlsm`, Parquet and
+/// Arrow IPC as-is (dtypes are preserved), Windows event logs for `.evtx`,
+/// rendered event XML for `.xml`, SQLite tables for `.sqlite`/`.db`,
+/// `.gz`/`.zip` by streaming their single inner file, CSV otherwise. The
+/// requested column types are applied to the result. Records repaired or
+/// dropped by a lenient CSV import are noted in `report`.
+pub fn read_input_dataframe(
+    path: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame> {
+    let df = match lowercase_extension(path).as_str() {
+        "jsonl" | "ndjson" => read_jsonl_dataframe(path),
+        "xlsx" | "xlsm" => read_xlsx_dataframe(path, options.sheet.as_deref()),
+        "parquet" | "pq" => read_project_dataframe(path)
+            .with_context(|| format!("failed to read Parquet file {:?}", path)),
+        "arrow" | "ipc" | "feather" => read_ipc_dataframe(path),
+        "evtx" => read_evtx_dataframe(path),
+        "xml" => read_event_xml_dataframe(path),
+        "sqlite" | "sqlite3" | "db" | "db3" => {
+            read_sqlite_dataframe(path, options.table.as_deref())
+        }
+        "gz" | "zip" => read_compressed_dataframe(path, options, performance, report),
+        _ => read_csv_dataframe(path, options, performance, report),
+    }?;
+    apply_column_types(df, options).with_context(|| format!("failed to import {:?}", path))
+}
+
+/// Resolves the selected import paths, expanding glob patterns such as
+/// `exports/*.csv` into their matches in name order. Paths that exist are
+/// taken literally even if they contain pattern characters.
+pub fn expand_input_paths(patterns: &[String]) -> Result<Vec<PathBuf>> {
+    let mut paths: Vec<PathBuf> = Vec::new();
+    for pattern in patterns {
+        let literal = PathBuf::from(pattern);
+        if literal.exists() || !pattern.contains(['*', '?', '[']) {
+            if !paths.contains(&literal) {
+                paths.push(literal);
+            }
+            continue;
+        }
+        let mut matches: Vec<PathBuf> = glob(pattern)
+            .with_context(|| format!("invalid file pattern {}", pattern))?
+            .filter_map(|entry| entry.ok())
+            .filter(|path| path.is_file())
+            .collect();
+        if matches.is_empty() {
+            return Err(anyhow!("no files match {}", pattern));
+        }
+        matches.sort();
+        for path in matches {
+            if !paths.contains(&path) {
+                paths.push(path);
+            }
+        }
+    }
+    Ok(paths)
+}
+
+/// Streams a file through SHA-256 and returns the lowercase hex digest.
+pub fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = File::open(path).with_context(|| format!("failed to open {:?}", path))?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher).with_context(|| format!("failed to hash {:?}", path))?;
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Fingerprints the import sources so a project can be refreshed later.
+pub fn describe_sources(paths: &[PathBuf]) -> Result<Vec<SourceFile>> {
+    paths
+        .iter()
+        .map(|path| {
+            Ok(SourceFile {
+                path: path.to_string_lossy().into_owned(),
+                sha256: sha256_file(path)?,
+            })
+        })
+        .collect()
+}
+
+/// Re-hashes recorded sources to check they still match what was imported.
+pub fn verify_sources(sources: &[SourceFile]) -> Result<Vec<SourceIntegrity>> {
+    sources
+        .iter()
+        .map(|source| {
+            let path = Path::new(&source.path);
+            let current_sha256 = if path.is_file() {
+                Some(sha256_file(path)?)
+            } else {
+                None
+            };
+            let status = match &current_sha256 {
+                None => SourceIntegrityStatus::Missing,
+                Some(hash) if hash.eq_ignore_ascii_case(&source.sha256) => {
+                    SourceIntegrityStatus::Verified
+                }
+                Some(_) => SourceIntegrityStatus::Modified,
+            };
+            Ok(SourceIntegrity {
+                path: source.path.clone(),
+                recorded_sha256: source.sha256.clone(),
+                current_sha256,
+                status,
+            })
+        })
+        .collect()
+}
+
+fn source_file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string_lossy().into_owned())
+}
+
+fn with_source_file_column(mut df: DataFrame, path: &Path) -> Result<DataFrame> {
+    let column = Series::new(
+        SOURCE_FILE_COLUMN,
+        vec![source_file_name(path); df.height()],
+    );
+    df.with_column(column)
+        .with_context(|| format!("failed to tag rows from {:?}", path))?;
+    Ok(df)
+}
+
+fn import_worker_count(file_count: usize, performance: &PerformanceSettings) -> usize {
+    let limit = performance.import_threads.unwrap_or(MAX_IMPORT_WORKERS);
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(limit)
+        .min(file_count)
+        .max(1)
+}
+
+fn staging_part_path(staging_dir: &Path, index: usize) -> PathBuf {
+    staging_dir.join(format!("part-{:05}.parquet", index))
+}
+
+/// Parses several input files on a bounded pool of worker threads. Each parsed
+/// file is written to a Parquet part under `staging_dir` and dropped, then the
+/// parts are concatenated in input order so the result is deterministic. Every
+/// row is tagged with its file name in [`SOURCE_FILE_COLUMN`], as is every
+/// issue a lenient import adds to `report`.
+pub fn read_input_dataframes_parallel(
+    paths: &[PathBuf],
+    staging_dir: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame> {
+    if paths.is_empty() {
+        return Err(anyhow!("no input files were provided"));
+    }
+    fs::create_dir_all(staging_dir)
+        .with_context(|| format!("failed to create import staging dir {:?}", staging_dir))?;
+
+    let result = convert_and_merge(paths, staging_dir, options, performance, report);
+    let _ = fs::remove_dir_all(staging_dir);
+    result
+}
+
+fn merge_file_report(report: &mut ImportReport, part: ImportReport, path: &Path) {
+    report.rows_repaired += part.rows_repaired;
+    report.rows_skipped += part.rows_skipped;
+    let room = MAX_REPORTED_IMPORT_ISSUES.saturating_sub(report.issues.len());
+    let file = source_file_name(path);
+    report
+        .issues
+        .extend(part.issues.into_iter().take(room).map(|issue| ImportIssue {
+            file: Some(file.clone()),
+            ..issue
+        }));
+}
+
+fn convert_and_merge(
+    paths: &[PathBuf],
+    staging_dir: &Path,
+    options: &ImportOptions,
+    performance: &PerformanceSettings,
+    report: &mut ImportReport,
+) -> Result<DataFrame> {
+    let next_index = AtomicUsize::new(0);
+    let file_reports = Mutex::new(Vec::new());
+    let aborted = AtomicBool::new(false);
+    let worker_count = import_worker_count(paths.len(), performance);
+
+    std::thread::scope(|scope| -> Result<()> {
+        let handles: Vec<_> = (0..worker_count)
+            .map(|_| {
+                scope.spawn(|| -> Result<()> {
+                    loop {
+                        if aborted.load(Ordering::Relaxed) {
+                            return Ok(());
+                        }
+                        let index = next_index.fetch_add(1, Ordering::Relaxed);
+                        let Some(path) = paths.get(index) else {
+                            return Ok(());
+                        };
+                        let mut file_report = ImportReport::default();
+                        let outcome =
+                            read_input_dataframe(path, options, performance, &mut file_report)
+                                .and_then(|df| with_source_file_column(df, path))
+                                .and_then(|mut df| {
+                                    write_project_dataframe(
+                                        &staging_part_path(staging_dir, index),
+                                        &mut df,
+                                    )
+                                });
+                        if let Err(err) = outcome {
+                            aborted.store(true, Ordering::Relaxed);
+                            return Err(err);
+                        }
+                        if let Ok(mut reports) = file_reports.lock() {
+                            reports.push((index, file_report));
+                        }
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle
+                .join()
+                .map_err(|_| anyhow!("import worker panicked"))??;
+        }
+        Ok(())
+    })?;
+
+    let mut file_reports = file_reports.into_inner().unwrap_or_default();
+    file_reports.sort_by_key(|(index, _)| *index);
+    for (index, file_report) in file_reports {
+        merge_file_report(report, file_report, &paths[index]);
+    }
+
+    let parts: Vec<LazyFrame> = (0..paths.len())
+        .map(|index| {
+            let part_path = staging_part_path(staging_dir, index);
+            LazyFrame::scan_parquet(&part_path, ScanArgsParquet::default())
+                .with_context(|| format!("failed to scan import part {:?}", part_path))
+        })
+        .collect::<Result<_>>()?;
+    concat(
+        parts,
+        UnionArgs {
+            rechunk: true,
+            to_supertypes: true,
+            ..Default::default()
+        },
+    )
+    .and_then(|merged| merged.collect())
+    .context("input files do not share a compatible schema")
+}
@@ -0,0 +1,78 @@
+//! Column redaction for exports shared outside the investigation team.
+//!
+//! A masked column loses its values. A hashed column gets a keyed
+//! HMAC-SHA256 of each value instead, so equal values still correlate across
+//! rows and across exports made with the same key, while nobody without the
+//! key can confirm a guessed user name or IP by hashing it. Every project
+//! has its own random key, created on first use; a key given with the
+//! export replaces it, e.g. to correlate exports of several projects.
+
+use std::{fs, path::Path};
+
+use anyhow::{anyhow, Context, Result};
+use hmac::{Hmac, Mac};
+use polars::prelude::*;
+use serde::Deserialize;
+use sha2::Sha256;
+use uuid::Uuid;
+
+use crate::value_utils::anyvalue_to_search_string;
+
+pub const MASKED_VALUE: &str = "[redacted]";
+const REDACTION_KEY_FILE: &str = "redaction.key";
+/// Hex digits of the HMAC kept per value: 64 bits, enough to tell the
+/// values of one export apart.
+const HASH_HEX_CHARS: usize = 16;
+
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RedactionMode {
+    /// Replaces every value with `MASKED_VALUE`.
+    Mask,
+    /// Replaces every value with its keyed hash.
+    Hash,
+}
+
+/// The project's redaction key, created when it has none yet.
+pub fn project_redaction_key(project_dir: &Path) -> Result<String> {
+    let path = project_dir.join(REDACTION_KEY_FILE);
+    if path.exists() {
+        let key =
+            fs::read_to_string(&path).with_context(|| format!("failed to read {:?}", path))?;
+        return Ok(key.trim().to_string());
+    }
+    let key = format!("{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+    fs::write(&path, &key).with_context(|| format!("failed to write {:?}", path))?;
+    Ok(key)
+}
+
+/// Redacts one column. Nulls and empty values stay as they are, so a
+/// redacted column still shows which rows had a value.
+pub fn redact_series(series: &Series, mode: RedactionMode, key: &str) -> Result<Series> {
+    let mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .map_err(|_| anyhow!("invalid redaction key"))?;
+    let values: Vec<Option<String>> = (0..series.len())
+        .map(|row| {
+            let text = series
+                .get(row)
+                .ok()
+                .and_then(|value| anyvalue_to_search_string(&value))?;
+            if text.is_empty() {
+                return Some(text);
+            }
+            Some(match mode {
+                RedactionMode::Mask => MASKED_VALUE.to_string(),
+                RedactionMode::Hash => {
+                    let mut mac = mac.clone();
+                    mac.update(text.as_bytes());
+                    let digest = mac.finalize().into_bytes();
+                    let mut hex: String =
+                        digest.iter().map(|byte| format!("{:02x}", byte)).collect();
+                    hex.truncate(HASH_HEX_CHARS);
+                    hex
+                }
+            })
+        })
+        .collect();
+    Ok(Series::new(series.name(), values))
+}
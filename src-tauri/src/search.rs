@@ -2,7 +2,107 @@ use std::collections::HashMap;
 
 use polars::prelude::Series;
 
-use crate::value_utils::anyvalue_to_search_string;
+use crate::value_utils::{
+    anyvalue_to_element_search_string, anyvalue_to_search_string, normalize_search_text,
+    LIST_ELEMENT_SEPARATOR,
+};
+
+/// Column suffix selecting element-wise matching on list columns, e.g. `privileges[]:sedebug`.
+pub const LIST_ELEMENT_SUFFIX: &str = "[]";
+
+/// Returns the underlying column name when `col` uses the element-wise suffix.
+pub fn list_element_column(col: &str) -> Option<&str> {
+    col.strip_suffix(LIST_ELEMENT_SUFFIX)
+        .filter(|base| !base.is_empty())
+}
+
+/// Whether `text` contains `term`. With `whole_word`, an occurrence must not
+/// continue a word on either side, like `\bterm\b`: `cmd` then matches
+/// `cmd.exe /c` but not `cmdlet`. Edges of the term that are not word
+/// characters themselves (`.ps1`) need no boundary.
+pub fn text_matches(text: &str, term: &str, whole_word: bool) -> bool {
+    if !whole_word {
+        return text.contains(term);
+    }
+    if term.is_empty() {
+        return false;
+    }
+    let is_word = |c: Option<char>| c.is_some_and(|c| c.is_alphanumeric() || c == '_');
+    let check_start = is_word(term.chars().next());
+    let check_end = is_word(term.chars().next_back());
+    text.match_indices(term).any(|(start, _)| {
+        let end = start + term.len();
+        (!check_start || !is_word(text[..start].chars().next_back()))
+            && (!check_end || !is_word(text[end..].chars().next()))
+    })
+}
+
+/// Matches `term` against per-column text; element-wise columns require the
+/// term to fall inside a single list element.
+fn column_text_matches(text: &str, term: &str, element_wise: bool, whole_word: bool) -> bool {
+    if element_wise {
+        text.split(LIST_ELEMENT_SEPARATOR)
+            .any(|element| text_matches(element, term, whole_word))
+    } else {
+        text_matches(text, term, whole_word)
+    }
+}
+
+/// Characters a backslash makes literal, with the stand-ins that carry them
+/// through tokenizing until the terms are built.
+const ESCAPES: [(char, char); 4] = [
+    (':', '\u{E000}'),
+    ('|', '\u{E001}'),
+    ('-', '\u{E002}'),
+    ('"', '\u{E003}'),
+];
+
+/// Stand-in for `next` after a backslash, or `None` when the backslash is an
+/// ordinary character, as in `C:\Windows` or `\\host\share`. Inside quotes
+/// only `\"` is an escape; a leading `-` only matters at the start of a term.
+fn escape_placeholder(next: char, in_quotes: bool, at_term_start: bool) -> Option<char> {
+    let escapable = match next {
+        '"' => true,
+        ':' | '|' => !in_quotes,
+        '-' => !in_quotes && at_term_start,
+        _ => false,
+    };
+    if !escapable {
+        return None;
+    }
+    ESCAPES
+        .iter()
+        .find(|(c, _)| *c == next)
+        .map(|(_, placeholder)| *placeholder)
+}
+
+fn restore_escaped(text: String) -> String {
+    if !text.chars().any(|c| ESCAPES.iter().any(|(_, p)| *p == c)) {
+        return text;
+    }
+    text.chars()
+        .map(|c| {
+            ESCAPES
+                .iter()
+                .find(|(_, placeholder)| *placeholder == c)
+                .map_or(c, |(original, _)| *original)
+        })
+        .collect()
+}
+
+/// Position of the colon ending a `column:` prefix. The colon of a drive
+/// path (`C:\Windows`, `c:/temp`) or a URL scheme (`https://`) is text.
+fn column_prefix_end(part: &str) -> Option<usize> {
+    let pos = part.find(':')?;
+    let (prefix, rest) = (&part[..pos], &part[pos + 1..]);
+    let is_drive = prefix.len() == 1
+        && prefix.chars().all(|c| c.is_ascii_alphabetic())
+        && rest.starts_with(['\\', '/']);
+    if is_drive || rest.starts_with("//") {
+        return None;
+    }
+    Some(pos)
+}
 
 // Boolean-search support: tokens, RPN conversion, and evaluation on prebuilt per-row searchable text
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -28,6 +128,7 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
     // - AND operator: explicit "AND" allowed, but also implicit between operands (handled later)
     // - NOT operator: unary, written as leading '-' before a term, or explicit word "NOT"
     // - Case-insensitive matching overall; terms are lowercased here
+    // - Backslash makes `:`, `|`, `"` and a leading `-` literal (`\-rf`, `a\|b`)
     let mut raw_parts: Vec<(String, bool)> = Vec::new(); // (text, quoted)
     let mut buf = String::new();
     let mut in_quotes = false;
@@ -52,6 +153,19 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
                     in_quotes = true;
                 }
             }
+            '\\' => {
+                let at_term_start = buf.trim().is_empty();
+                match chars
+                    .peek()
+                    .and_then(|next| escape_placeholder(*next, in_quotes, at_term_start))
+                {
+                    Some(placeholder) => {
+                        chars.next();
+                        buf.push(placeholder);
+                    }
+                    None => buf.push(ch),
+                }
+            }
             '|' => {
                 if in_quotes {
                     buf.push(ch);
@@ -112,9 +226,9 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
         if !quoted && part.starts_with('-') && part.len() > 1 {
             tokens.push(SearchToken::Not);
             let rest = &part[1..];
-            if let Some(pos) = (!quoted).then(|| rest.find(':')).flatten() {
+            if let Some(pos) = column_prefix_end(rest) {
                 let (c, t) = rest.split_at(pos);
-                let text = t[1..].to_lowercase();
+                let text = normalize_search_text(&t[1..]);
                 let col = c.to_lowercase();
                 tokens.push(SearchToken::Term {
                     col: Some(col),
@@ -123,15 +237,15 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
             } else {
                 tokens.push(SearchToken::Term {
                     col: None,
-                    text: rest.to_lowercase(),
+                    text: normalize_search_text(rest),
                 });
             }
             continue;
         }
         // Column prefix: col:term (unquoted) or col:"phrase" (merged, quoted=true)
-        if let Some(pos) = (!quoted).then(|| part.find(':')).flatten() {
+        if let Some(pos) = (!quoted).then(|| column_prefix_end(&part)).flatten() {
             let (c, t) = part.split_at(pos);
-            let text = t[1..].to_lowercase();
+            let text = normalize_search_text(&t[1..]);
             let col = c.to_lowercase();
             tokens.push(SearchToken::Term {
                 col: Some(col),
@@ -144,7 +258,7 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
             if let Some(pos) = part.find(":\"") {
                 let (c, t) = part.split_at(pos);
                 let text_raw = t[1..].trim();
-                let text = text_raw.trim_matches('"').to_lowercase();
+                let text = normalize_search_text(text_raw.trim_matches('"'));
                 let col = c.to_lowercase();
                 tokens.push(SearchToken::QuotedTerm {
                     col: Some(col),
@@ -153,17 +267,32 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
             } else {
                 tokens.push(SearchToken::QuotedTerm {
                     col: None,
-                    text: part.to_lowercase(),
+                    text: normalize_search_text(&part),
                 });
             }
         } else {
             tokens.push(SearchToken::Term {
                 col: None,
-                text: part.to_lowercase(),
+                text: normalize_search_text(&part),
             });
         }
     }
 
+    let tokens: Vec<SearchToken> = tokens
+        .into_iter()
+        .map(|token| match token {
+            SearchToken::Term { col, text } => SearchToken::Term {
+                col: col.map(restore_escaped),
+                text: restore_escaped(text),
+            },
+            SearchToken::QuotedTerm { col, text } => SearchToken::QuotedTerm {
+                col: col.map(restore_escaped),
+                text: restore_escaped(text),
+            },
+            other => other,
+        })
+        .collect();
+
     // Column carry-over across OR: if an operand with a column is followed by
     // an OR and then an operand without a column, apply the same column to the
     // following operand. This enables queries like `com:WS01|WS02|WS03` to be
@@ -228,6 +357,20 @@ pub fn tokenize_search_query(input: &str) -> Vec<SearchToken> {
     with_and
 }
 
+/// Collects the distinct non-empty (column, term) pairs referenced by a token stream.
+pub fn collect_search_terms(tokens: &[SearchToken]) -> Vec<(Option<String>, String)> {
+    let mut terms: Vec<(Option<String>, String)> = Vec::new();
+    for token in tokens {
+        if let SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } = token {
+            let key = (col.clone(), text.clone());
+            if !text.is_empty() && !terms.contains(&key) {
+                terms.push(key);
+            }
+        }
+    }
+    terms
+}
+
 pub fn to_rpn(tokens: &[SearchToken]) -> Vec<SearchToken> {
     // Shunting-yard without parentheses. Precedence: NOT(3, right), AND(2, left), OR(1, left)
     fn precedence(tok: &SearchToken) -> (u8, bool) {
@@ -270,6 +413,66 @@ pub fn to_rpn(tokens: &[SearchToken]) -> Vec<SearchToken> {
     output
 }
 
+/// Renders a token the way it is matched: operators by name, terms with
+/// their column and normalized text, e.g. `host:"ws01"`.
+pub fn describe_token(token: &SearchToken) -> String {
+    match token {
+        SearchToken::Term { col, text } | SearchToken::QuotedTerm { col, text } => match col {
+            Some(col) => format!("{}:{:?}", col, text),
+            None => format!("{:?}", text),
+        },
+        SearchToken::And => "AND".to_string(),
+        SearchToken::Or => "OR".to_string(),
+        SearchToken::Not => "NOT".to_string(),
+    }
+}
+
+/// Infix form of an RPN token stream with the grouping made explicit, e.g.
+/// `("a" OR "b") AND NOT "c"`.
+pub fn rpn_to_expression(rpn: &[SearchToken]) -> String {
+    // (text, precedence): terms 4, NOT 3, AND 2, OR 1; `?` marks a missing operand
+    let mut stack: Vec<(String, u8)> = Vec::new();
+    let missing = || ("?".to_string(), 4);
+    // Operands joined by a different operator are parenthesized even where
+    // precedence alone would decide, so `a | b c` reads `"a" OR ("b" AND "c")`.
+    let wrap = |(text, prec): (String, u8), parent: u8| {
+        if prec <= 2 && prec != parent {
+            format!("({})", text)
+        } else {
+            text
+        }
+    };
+    for token in rpn {
+        match token {
+            SearchToken::Term { .. } | SearchToken::QuotedTerm { .. } => {
+                stack.push((describe_token(token), 4))
+            }
+            SearchToken::Not => {
+                let operand = stack.pop().unwrap_or_else(missing);
+                stack.push((format!("NOT {}", wrap(operand, 3)), 3));
+            }
+            SearchToken::And | SearchToken::Or => {
+                let prec = if matches!(token, SearchToken::And) {
+                    2
+                } else {
+                    1
+                };
+                let right = stack.pop().unwrap_or_else(missing);
+                let left = stack.pop().unwrap_or_else(missing);
+                stack.push((
+                    format!(
+                        "{} {} {}",
+                        wrap(left, prec),
+                        describe_token(token),
+                        wrap(right, prec)
+                    ),
+                    prec,
+                ));
+            }
+        }
+    }
+    stack.pop().map(|(text, _)| text).unwrap_or_default()
+}
 
 #[allow(clippy::too_many_arguments)]
 pub fn build_search_mask_boolean(
@@ -278,6 +481,7 @@ pub fn build_search_mask_boolean(
     searchable_text: &[String],
     // Optional: per-column searchable texts; when None, falls back to row-wide text
     per_column: Option<&HashMap<String, Vec<String>>>,
+    whole_word: bool,
 ) -> Vec<bool> {
     // Precompute per-(col,term) masks
     let mut key_masks: HashMap<(Option<String>, String), Vec<bool>> = HashMap::new();
@@ -289,10 +493,18 @@ pub fn build_search_mask_boolean(
         let mut mask = vec![false; searchable_text.len()];
         match (col_opt.as_ref().map(|c| c.to_lowercase()), per_column) {
             (Some(col), Some(per_col)) => {
-                if let Some(col_texts) = per_col.get(&col) {
+                let element_wise = list_element_column(&col).is_some();
+                // Row-level caches only carry element text for list values; other
+                // values are a single element, so the plain column text suffices.
+                let col_texts = per_col
+                    .get(&col)
+                    .or_else(|| list_element_column(&col).and_then(|base| per_col.get(base)));
+                if let Some(col_texts) = col_texts {
                     for i in 0..searchable_text.len() {
                         if let Some(t) = col_texts.get(i) {
-                            if !t.is_empty() && t.contains(term) {
+                            if !t.is_empty()
+                                && column_text_matches(t, term, element_wise, whole_word)
+                            {
                                 mask[i] = true;
                             }
                         }
@@ -301,7 +513,9 @@ pub fn build_search_mask_boolean(
             }
             _ => {
                 for i in 0..searchable_text.len() {
-                    if !searchable_text[i].is_empty() && searchable_text[i].contains(term) {
+                    if !searchable_text[i].is_empty()
+                        && text_matches(&searchable_text[i], term, whole_word)
+                    {
                         mask[i] = true;
                     }
                 }
@@ -357,7 +571,7 @@ pub fn build_searchable_text(
             for row_idx in 0..row_count {
                 if let Ok(value) = series.get(row_idx) {
                     if let Some(text) = anyvalue_to_search_string(&value) {
-                        let lower = text.to_lowercase();
+                        let lower = normalize_search_text(&text);
                         if lower.is_empty() {
                             continue;
                         }
@@ -390,3 +604,36 @@ pub fn ensure_searchable_text<'a>(
     }
     storage.as_ref().unwrap()
 }
+
+/// Ensures lowercase string caches exist for a column, returning the cached vector.
+pub fn ensure_column_text_cache<'a>(
+    column: &str,
+    column_series_lower: &HashMap<String, &Series>,
+    cache: &'a mut HashMap<String, Vec<String>>,
+    row_count: usize,
+) -> &'a Vec<String> {
+    let key = column.to_lowercase();
+    if !cache.contains_key(&key) {
+        let mut col_vec: Vec<String> = vec![String::new(); row_count];
+        let element_base = list_element_column(&key);
+        let to_text = if element_base.is_some() {
+            anyvalue_to_element_search_string
+        } else {
+            anyvalue_to_search_string
+        };
+        if let Some(series) = column_series_lower.get(element_base.unwrap_or(&key)) {
+            for row_idx in 0..row_count {
+                if let Ok(value) = series.get(row_idx) {
+                    if let Some(text) = to_text(&value) {
+                        let lower = normalize_search_text(&text);
+                        if !lower.is_empty() {
+                            col_vec[row_idx] = lower;
+                        }
+                    }
+                }
+            }
+        }
+        cache.insert(key.clone(), col_vec);
+    }
+    cache.get(&key).expect("column cache must exist")
+}
@@ -0,0 +1,68 @@
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::models::SessionState;
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionFile {
+    clean_exit: bool,
+    state: Option<SessionState>,
+}
+
+/// Tracks the last open project and query context so a crashed run can be restored.
+pub struct SessionStore {
+    path: PathBuf,
+    inner: Mutex<SessionFile>,
+    recovered: Mutex<Option<SessionState>>,
+}
+
+impl SessionStore {
+    pub fn new(root_dir: PathBuf) -> Result<Self> {
+        let path = root_dir.join("session.json");
+        // A session file without the clean-exit marker means the previous run did not shut down normally.
+        let recovered = if path.exists() {
+            fs::read(&path)
+                .ok()
+                .and_then(|data| serde_json::from_slice::<SessionFile>(&data).ok())
+                .filter(|file| !file.clean_exit)
+                .and_then(|file| file.state)
+                .filter(|state| state.project_id.is_some())
+        } else {
+            None
+        };
+
+        let store = Self {
+            path,
+            inner: Mutex::new(SessionFile::default()),
+            recovered: Mutex::new(recovered),
+        };
+        store.persist_locked(&store.inner.lock())?;
+        Ok(store)
+    }
+
+    pub fn save(&self, state: SessionState) -> Result<()> {
+        let mut guard = self.inner.lock();
+        guard.state = Some(state);
+        self.persist_locked(&guard)
+    }
+
+    /// Returns the session left behind by a crashed run, at most once per launch.
+    pub fn take_recovered(&self) -> Option<SessionState> {
+        self.recovered.lock().take()
+    }
+
+    pub fn mark_clean_exit(&self) -> Result<()> {
+        let mut guard = self.inner.lock();
+        guard.clean_exit = true;
+        self.persist_locked(&guard)
+    }
+
+    fn persist_locked(&self, file: &SessionFile) -> Result<()> {
+        let data = serde_json::to_vec_pretty(file)?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("failed to write session file {:?}", self.path))
+    }
+}
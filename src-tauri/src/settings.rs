@@ -0,0 +1,202 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{anyhow, Context, Result};
+use chrono::format::{Item, StrftimeItems};
+use parking_lot::Mutex;
+use serde::{Deserialize, Serialize};
+
+use crate::logging::{self, LogLevel};
+
+const SETTINGS_FILE: &str = "settings.json";
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PerformanceSettings {
+    /// Size of the Polars/rayon thread pool; applied at startup.
+    #[serde(default)]
+    pub max_threads: Option<usize>,
+    /// Threads used to parse CSV input and convert files during multi-file import.
+    #[serde(default)]
+    pub import_threads: Option<usize>,
+    /// Rows per parse chunk when reading CSV input.
+    #[serde(default)]
+    pub import_chunk_size: Option<usize>,
+    /// Measure column widths on every row rather than a sample; slower to
+    /// open large projects, but long values far down a column are not cut.
+    #[serde(default)]
+    pub exact_column_widths: bool,
+}
+
+/// Character encoding of CSV exports.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CsvEncoding {
+    #[default]
+    #[serde(rename = "utf-8")]
+    Utf8,
+    /// UTF-8 with a byte order mark, which Excel needs to detect UTF-8.
+    #[serde(rename = "utf-8-bom")]
+    Utf8Bom,
+    #[serde(rename = "utf-16le")]
+    Utf16Le,
+    /// Characters Shift-JIS cannot represent are written as `?`.
+    #[serde(rename = "shift_jis")]
+    ShiftJis,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AppSettings {
+    #[serde(default)]
+    pub performance: PerformanceSettings,
+    /// Directory holding the projects when not in the app data directory.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data_root: Option<PathBuf>,
+    #[serde(default)]
+    pub log_level: LogLevel,
+    /// Rows per page when a query does not ask for a number.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub page_size: Option<usize>,
+    /// chrono format timestamps are shown in, e.g. `%Y/%m/%d %H:%M:%S`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub date_format: Option<String>,
+    /// Encoding of CSV exports that do not choose one.
+    #[serde(default)]
+    pub export_encoding: CsvEncoding,
+}
+
+/// Rejects formats chrono cannot render, which would otherwise fail only
+/// when a timestamp is shown.
+pub fn check_date_format(format: &str) -> Result<()> {
+    if format.trim().is_empty() {
+        return Err(anyhow!("the date format is empty"));
+    }
+    if StrftimeItems::new(format).any(|item| matches!(item, Item::Error)) {
+        return Err(anyhow!("invalid date format {:?}", format));
+    }
+    Ok(())
+}
+
+fn read_settings_file(path: &Path) -> Result<AppSettings> {
+    if !path.exists() {
+        return Ok(AppSettings::default());
+    }
+    let data =
+        fs::read(path).with_context(|| format!("failed to read settings file {:?}", path))?;
+    serde_json::from_slice(&data)
+        .with_context(|| format!("failed to parse settings file {:?}", path))
+}
+
+/// Applies settings that must be in place before Polars' global thread pool
+/// starts, along with the log level.
+pub fn apply_startup_settings(root_dir: &Path) {
+    let settings = match read_settings_file(&root_dir.join(SETTINGS_FILE)) {
+        Ok(settings) => settings,
+        Err(err) => {
+            tracing::warn!("using default settings: {:?}", err);
+            return;
+        }
+    };
+    if let Err(err) = logging::set_level(settings.log_level) {
+        tracing::warn!("{:?}", err);
+    }
+    if let Some(threads) = settings.performance.max_threads.filter(|n| *n > 0) {
+        for var in ["POLARS_MAX_THREADS", "RAYON_NUM_THREADS"] {
+            if std::env::var_os(var).is_none() {
+                std::env::set_var(var, threads.to_string());
+            }
+        }
+    }
+}
+
+pub struct SettingsStore {
+    path: PathBuf,
+    inner: Mutex<AppSettings>,
+}
+
+impl SettingsStore {
+    pub fn new(root_dir: PathBuf) -> Result<Self> {
+        let path = root_dir.join(SETTINGS_FILE);
+        let settings = read_settings_file(&path)?;
+        Ok(Self {
+            path,
+            inner: Mutex::new(settings),
+        })
+    }
+
+    pub fn performance(&self) -> PerformanceSettings {
+        self.inner.lock().performance.clone()
+    }
+
+    pub fn update_performance(&self, performance: PerformanceSettings) -> Result<()> {
+        let mut guard = self.inner.lock();
+        guard.performance = performance;
+        self.persist_locked(&guard)
+    }
+
+    pub fn data_root(&self) -> Option<PathBuf> {
+        self.inner.lock().data_root.clone()
+    }
+
+    pub fn update_data_root(&self, data_root: Option<PathBuf>) -> Result<()> {
+        let mut guard = self.inner.lock();
+        guard.data_root = data_root;
+        self.persist_locked(&guard)
+    }
+
+    pub fn log_level(&self) -> LogLevel {
+        self.inner.lock().log_level
+    }
+
+    pub fn update_log_level(&self, log_level: LogLevel) -> Result<()> {
+        let mut guard = self.inner.lock();
+        guard.log_level = log_level;
+        self.persist_locked(&guard)
+    }
+
+    pub fn settings(&self) -> AppSettings {
+        self.inner.lock().clone()
+    }
+
+    pub fn page_size(&self) -> Option<usize> {
+        self.inner.lock().page_size.filter(|size| *size > 0)
+    }
+
+    /// The configured date format, unless it was edited into one chrono
+    /// cannot render.
+    pub fn date_format(&self) -> Option<String> {
+        let format = self.inner.lock().date_format.clone()?;
+        match check_date_format(&format) {
+            Ok(()) => Some(format),
+            Err(err) => {
+                tracing::warn!("ignoring date format setting: {:?}", err);
+                None
+            }
+        }
+    }
+
+    pub fn export_encoding(&self) -> CsvEncoding {
+        self.inner.lock().export_encoding
+    }
+
+    /// Stores the query and display defaults; the data root, performance
+    /// limits and log level have their own setters.
+    pub fn update_defaults(
+        &self,
+        page_size: Option<usize>,
+        date_format: Option<String>,
+        export_encoding: CsvEncoding,
+    ) -> Result<()> {
+        let mut guard = self.inner.lock();
+        guard.page_size = page_size;
+        guard.date_format = date_format;
+        guard.export_encoding = export_encoding;
+        self.persist_locked(&guard)
+    }
+
+    fn persist_locked(&self, settings: &AppSettings) -> Result<()> {
+        let data = serde_json::to_vec_pretty(settings)?;
+        fs::write(&self.path, data)
+            .with_context(|| format!("failed to write settings file {:?}", self.path))
+    }
+}
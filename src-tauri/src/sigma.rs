@@ -0,0 +1,502 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_yaml::Value as YamlValue;
+
+use crate::ioc::normalize_optional_text;
+use crate::models::{IocEntry, IocKind};
+
+/// Upper bound on OR-clauses produced when flattening a rule; the search syntax
+/// has no parentheses, so conditions are expanded into disjunctive normal form.
+const MAX_CLAUSES: usize = 256;
+
+const MAPPING_FILE: &str = "sigma_mapping.json";
+
+/// Maps Sigma field names (case-insensitive) to one or more project columns.
+pub type SigmaFieldMapping = HashMap<String, Vec<String>>;
+
+#[derive(Debug, Deserialize)]
+struct SigmaRule {
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    level: Option<String>,
+    #[serde(default)]
+    description: Option<String>,
+    #[serde(default)]
+    author: Option<String>,
+    #[serde(default)]
+    references: Vec<String>,
+    #[serde(default)]
+    detection: Option<serde_yaml::Mapping>,
+}
+
+#[derive(Debug, Clone)]
+enum Expr {
+    Term {
+        column: Option<String>,
+        text: String,
+    },
+    And(Vec<Expr>),
+    Or(Vec<Expr>),
+    Not(Box<Expr>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Literal {
+    column: Option<String>,
+    text: String,
+    negated: bool,
+}
+
+/// A rule that could not be converted, with the reason shown to the user.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedSigmaRule {
+    pub source: String,
+    pub reason: String,
+}
+
+/// Loads the project's Sigma field mapping; missing files yield an empty mapping.
+pub fn load_sigma_mapping(project_dir: &Path) -> Result<SigmaFieldMapping> {
+    let path = project_dir.join(MAPPING_FILE);
+    if !path.exists() {
+        return Ok(SigmaFieldMapping::new());
+    }
+    let data =
+        fs::read(&path).with_context(|| format!("failed to read Sigma mapping {:?}", path))?;
+    serde_json::from_slice(&data)
+        .with_context(|| format!("failed to parse Sigma mapping {:?}", path))
+}
+
+pub fn save_sigma_mapping(project_dir: &Path, mapping: &SigmaFieldMapping) -> Result<()> {
+    let path = project_dir.join(MAPPING_FILE);
+    let data = serde_json::to_vec_pretty(mapping).context("failed to serialize Sigma mapping")?;
+    fs::write(&path, data).with_context(|| format!("failed to write Sigma mapping {:?}", path))
+}
+
+fn yaml_scalar(value: &YamlValue) -> Result<String> {
+    match value {
+        YamlValue::String(text) => Ok(text.clone()),
+        YamlValue::Number(number) => Ok(number.to_string()),
+        YamlValue::Bool(flag) => Ok(flag.to_string()),
+        YamlValue::Null => bail!("null values (field absence) are not supported"),
+        _ => bail!("unsupported value {:?}", value),
+    }
+}
+
+fn map_columns(field: &str, mapping: &SigmaFieldMapping) -> Vec<String> {
+    mapping
+        .iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(field))
+        .map(|(_, columns)| columns.clone())
+        .filter(|columns| !columns.is_empty())
+        .unwrap_or_else(|| vec![field.to_string()])
+}
+
+/// Sigma wildcards become AND-ed substring terms, since matching is substring-based.
+fn value_expr(column: Option<&str>, value: &str) -> Expr {
+    let parts: Vec<Expr> = value
+        .split(['*', '?'])
+        .filter(|part| !part.is_empty())
+        .map(|part| Expr::Term {
+            column: column.map(|c| c.to_string()),
+            text: part.to_string(),
+        })
+        .collect();
+    match parts.len() {
+        1 => parts.into_iter().next().unwrap(),
+        _ => Expr::And(parts),
+    }
+}
+
+fn field_expr(key: &str, value: &YamlValue, mapping: &SigmaFieldMapping) -> Result<Expr> {
+    let mut pieces = key.split('|');
+    let field = pieces.next().unwrap_or_default().trim();
+    let mut match_all = false;
+    for modifier in pieces {
+        match modifier {
+            "contains" | "startswith" | "endswith" => {}
+            "all" => match_all = true,
+            other => bail!("field modifier '{}' is not supported", other),
+        }
+    }
+    let values: Vec<String> = match value {
+        YamlValue::Sequence(items) => items.iter().map(yaml_scalar).collect::<Result<_>>()?,
+        other => vec![yaml_scalar(other)?],
+    };
+    let columns = map_columns(field, mapping);
+    let per_value: Vec<Expr> = values
+        .iter()
+        .map(|value| {
+            let alternatives: Vec<Expr> = columns
+                .iter()
+                .map(|column| value_expr(Some(column), value))
+                .collect();
+            Expr::Or(alternatives)
+        })
+        .collect();
+    Ok(if match_all {
+        Expr::And(per_value)
+    } else {
+        Expr::Or(per_value)
+    })
+}
+
+fn selection_expr(value: &YamlValue, mapping: &SigmaFieldMapping) -> Result<Expr> {
+    match value {
+        YamlValue::Mapping(fields) => {
+            let parts = fields
+                .iter()
+                .map(|(key, value)| {
+                    let key = key
+                        .as_str()
+                        .ok_or_else(|| anyhow!("field names must be strings"))?;
+                    field_expr(key, value, mapping)
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Expr::And(parts))
+        }
+        YamlValue::Sequence(items) => {
+            let parts = items
+                .iter()
+                .map(|item| match item {
+                    YamlValue::Mapping(_) => selection_expr(item, mapping),
+                    other => Ok(value_expr(None, &yaml_scalar(other)?)),
+                })
+                .collect::<Result<Vec<_>>>()?;
+            Ok(Expr::Or(parts))
+        }
+        other => Ok(value_expr(None, &yaml_scalar(other)?)),
+    }
+}
+
+fn tokenize_condition(condition: &str) -> Vec<String> {
+    let mut tokens = Vec::new();
+    let mut current = String::new();
+    for ch in condition.chars() {
+        match ch {
+            '(' | ')' => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+                tokens.push(ch.to_string());
+            }
+            c if c.is_whitespace() => {
+                if !current.is_empty() {
+                    tokens.push(std::mem::take(&mut current));
+                }
+            }
+            c => current.push(c),
+        }
+    }
+    if !current.is_empty() {
+        tokens.push(current);
+    }
+    tokens
+}
+
+struct ConditionParser<'a> {
+    tokens: Vec<String>,
+    pos: usize,
+    selections: &'a HashMap<String, Expr>,
+}
+
+impl ConditionParser<'_> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.pos).map(|token| token.as_str())
+    }
+
+    fn next(&mut self) -> Option<String> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn parse_or(&mut self) -> Result<Expr> {
+        let mut parts = vec![self.parse_and()?];
+        while self.peek().map(|t| t.eq_ignore_ascii_case("or")) == Some(true) {
+            self.pos += 1;
+            parts.push(self.parse_and()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Expr::Or(parts)
+        })
+    }
+
+    fn parse_and(&mut self) -> Result<Expr> {
+        let mut parts = vec![self.parse_unary()?];
+        while self.peek().map(|t| t.eq_ignore_ascii_case("and")) == Some(true) {
+            self.pos += 1;
+            parts.push(self.parse_unary()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.pop().unwrap()
+        } else {
+            Expr::And(parts)
+        })
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr> {
+        let token = self
+            .next()
+            .ok_or_else(|| anyhow!("condition ends unexpectedly"))?;
+        let lower = token.to_lowercase();
+        match lower.as_str() {
+            "not" => Ok(Expr::Not(Box::new(self.parse_unary()?))),
+            "(" => {
+                let inner = self.parse_or()?;
+                match self.next().as_deref() {
+                    Some(")") => Ok(inner),
+                    _ => bail!("unbalanced parentheses in condition"),
+                }
+            }
+            "1" | "any" | "all" => {
+                if self.next().map(|t| t.eq_ignore_ascii_case("of")) != Some(true) {
+                    bail!("expected 'of' after '{}'", token);
+                }
+                let target = self
+                    .next()
+                    .ok_or_else(|| anyhow!("expected a selection after 'of'"))?;
+                let matched = self.matching_selections(&target)?;
+                Ok(if lower == "all" {
+                    Expr::And(matched)
+                } else {
+                    Expr::Or(matched)
+                })
+            }
+            "|" => bail!("aggregation conditions are not supported"),
+            _ => self
+                .selections
+                .get(&token)
+                .cloned()
+                .ok_or_else(|| anyhow!("condition references unknown selection '{}'", token)),
+        }
+    }
+
+    fn matching_selections(&self, pattern: &str) -> Result<Vec<Expr>> {
+        let mut names: Vec<&String> = self
+            .selections
+            .keys()
+            .filter(|name| match pattern {
+                "them" => !name.starts_with('_'),
+                _ => match pattern.strip_suffix('*') {
+                    Some(prefix) => name.starts_with(prefix),
+                    None => name.as_str() == pattern,
+                },
+            })
+            .collect();
+        if names.is_empty() {
+            bail!("no selection matches '{}'", pattern);
+        }
+        names.sort();
+        Ok(names
+            .into_iter()
+            .map(|name| self.selections[name].clone())
+            .collect())
+    }
+}
+
+/// Pushes negations down to terms and expands into OR-of-AND clauses.
+fn to_dnf(expr: &Expr, negated: bool) -> Result<Vec<Vec<Literal>>> {
+    let clauses = match (expr, negated) {
+        (Expr::Term { column, text }, _) => vec![vec![Literal {
+            column: column.clone(),
+            text: text.clone(),
+            negated,
+        }]],
+        (Expr::Not(inner), _) => to_dnf(inner, !negated)?,
+        (Expr::Or(parts), false) | (Expr::And(parts), true) => {
+            let mut clauses = Vec::new();
+            for part in parts {
+                clauses.extend(to_dnf(part, negated)?);
+            }
+            clauses
+        }
+        (Expr::And(parts), false) | (Expr::Or(parts), true) => {
+            let mut clauses: Vec<Vec<Literal>> = vec![Vec::new()];
+            for part in parts {
+                let part_clauses = to_dnf(part, negated)?;
+                let mut next = Vec::with_capacity(clauses.len() * part_clauses.len());
+                for clause in &clauses {
+                    for extra in &part_clauses {
+                        let mut combined = clause.clone();
+                        for literal in extra {
+                            if !combined.contains(literal) {
+                                combined.push(literal.clone());
+                            }
+                        }
+                        next.push(combined);
+                    }
+                }
+                if next.len() > MAX_CLAUSES {
+                    bail!("detection expands into too many alternatives");
+                }
+                clauses = next;
+            }
+            clauses
+        }
+    };
+    if clauses.len() > MAX_CLAUSES {
+        bail!("detection expands into too many alternatives");
+    }
+    Ok(clauses)
+}
+
+fn render_literal(literal: &Literal) -> Result<String> {
+    let text = literal.text.trim();
+    if text.contains('"') {
+        bail!("values containing double quotes are not supported");
+    }
+    let needs_quotes = text.is_empty()
+        || text.starts_with('-')
+        || text
+            .chars()
+            .any(|c| c.is_whitespace() || c == '|' || (literal.column.is_none() && c == ':'));
+    let body = match (&literal.column, needs_quotes) {
+        (Some(column), true) => format!("{}:\"{}\"", column, text),
+        (Some(column), false) => format!("{}:{}", column, text),
+        (None, true) => format!("\"{}\"", text),
+        (None, false) => text.to_string(),
+    };
+    if literal.negated {
+        if needs_quotes {
+            bail!("cannot negate value '{}' that needs quoting", text);
+        }
+        Ok(format!("-{}", body))
+    } else {
+        Ok(body)
+    }
+}
+
+/// Renders clauses in the search syntax. Scoped terms lead each clause and
+/// unscoped-only clauses come first so OR column carry-over never applies.
+fn render_query(mut clauses: Vec<Vec<Literal>>) -> Result<String> {
+    for clause in &mut clauses {
+        clause.sort_by_key(|literal| literal.column.is_none());
+    }
+    clauses.sort_by_key(|clause| clause.iter().any(|literal| literal.column.is_some()));
+    let rendered = clauses
+        .iter()
+        .map(|clause| {
+            clause
+                .iter()
+                .map(render_literal)
+                .collect::<Result<Vec<_>>>()
+                .map(|parts| parts.join(" "))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    Ok(rendered.join(" | "))
+}
+
+fn level_to_flag(level: Option<&str>) -> &'static str {
+    match level.map(|l| l.trim().to_lowercase()).as_deref() {
+        Some("critical") | Some("high") => "critical",
+        _ => "suspicious",
+    }
+}
+
+fn compile_rule(rule: SigmaRule, mapping: &SigmaFieldMapping) -> Result<IocEntry> {
+    let detection = rule
+        .detection
+        .ok_or_else(|| anyhow!("rule has no detection section"))?;
+    let mut selections: HashMap<String, Expr> = HashMap::new();
+    let mut condition: Option<String> = None;
+    for (key, value) in &detection {
+        let name = key
+            .as_str()
+            .ok_or_else(|| anyhow!("selection names must be strings"))?;
+        match name {
+            "condition" => {
+                condition = Some(match value {
+                    YamlValue::Sequence(items) => {
+                        let parts = items.iter().map(yaml_scalar).collect::<Result<Vec<_>>>()?;
+                        parts
+                            .iter()
+                            .map(|part| format!("({})", part))
+                            .collect::<Vec<_>>()
+                            .join(" or ")
+                    }
+                    other => yaml_scalar(other)?,
+                })
+            }
+            "timeframe" => bail!("timeframe correlations are not supported"),
+            _ => {
+                let expr = selection_expr(value, mapping)
+                    .with_context(|| format!("in selection '{}'", name))?;
+                selections.insert(name.to_string(), expr);
+            }
+        }
+    }
+    let condition = condition.ok_or_else(|| anyhow!("rule has no condition"))?;
+    let mut parser = ConditionParser {
+        tokens: tokenize_condition(&condition),
+        pos: 0,
+        selections: &selections,
+    };
+    let expr = parser.parse_or()?;
+    if parser.pos < parser.tokens.len() {
+        bail!("could not parse condition '{}'", condition);
+    }
+    let query = render_query(to_dnf(&expr, false)?)?;
+    if query.trim().is_empty() {
+        bail!("detection produced an empty query");
+    }
+    let title = rule.title.trim();
+    Ok(IocEntry {
+        flag: level_to_flag(rule.level.as_deref()).to_string(),
+        tag: if title.is_empty() {
+            "Sigma".to_string()
+        } else {
+            title.to_string()
+        },
+        query,
+        kind: IocKind::Query,
+        columns: Vec::new(),
+        description: normalize_optional_text(rule.description.as_deref()),
+        reference_url: normalize_optional_text(rule.references.first().map(|r| r.as_str())),
+        author: normalize_optional_text(rule.author.as_deref()),
+        whole_word: false,
+        group: None,
+        disabled: false,
+    })
+}
+
+/// Converts every rule document in the given Sigma YAML files into IOC
+/// entries; rules that cannot be expressed are reported instead of failing the batch.
+pub fn read_sigma_rules(
+    paths: &[impl AsRef<Path>],
+    mapping: &SigmaFieldMapping,
+) -> Result<(Vec<IocEntry>, Vec<SkippedSigmaRule>)> {
+    let mut entries = Vec::new();
+    let mut skipped = Vec::new();
+    for path in paths {
+        let path = path.as_ref();
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read Sigma rule {:?}", path))?;
+        let source = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        for document in serde_yaml::Deserializer::from_str(&text) {
+            let outcome = SigmaRule::deserialize(document)
+                .context("invalid Sigma YAML")
+                .and_then(|rule| {
+                    let title = rule.title.clone();
+                    compile_rule(rule, mapping).map_err(|err| err.context(title))
+                });
+            match outcome {
+                Ok(entry) => entries.push(entry),
+                Err(err) => skipped.push(SkippedSigmaRule {
+                    source: source.clone(),
+                    reason: format!("{:#}", err),
+                }),
+            }
+        }
+    }
+    Ok((entries, skipped))
+}
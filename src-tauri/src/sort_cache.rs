@@ -0,0 +1,95 @@
+//! Row orderings of recent column sorts, kept in memory so paging through a
+//! sorted view or going back to an earlier sort does not compare every row
+//! again.
+
+use std::{fs, path::Path, sync::Arc, time::SystemTime};
+
+use parking_lot::Mutex;
+use uuid::Uuid;
+
+/// Orderings kept at a time, the most recently used first; one ordering of
+/// two million rows takes 16 MB.
+const MAX_CACHED_SORTS: usize = 4;
+
+/// Modification time and size of `data.parquet`. Rewriting the data changes
+/// it, which retires the orderings computed before.
+pub type DataStamp = (SystemTime, u64);
+
+pub fn data_stamp(parquet_path: &Path) -> Option<DataStamp> {
+    let metadata = fs::metadata(parquet_path).ok()?;
+    Some((metadata.modified().ok()?, metadata.len()))
+}
+
+struct CachedSort {
+    project_id: Uuid,
+    column: String,
+    descending: bool,
+    stamp: DataStamp,
+    order: Arc<Vec<usize>>,
+}
+
+#[derive(Default)]
+pub struct SortCache {
+    inner: Mutex<Vec<CachedSort>>,
+}
+
+impl SortCache {
+    /// The row ordering by `column`, when it was computed from the data
+    /// `stamp` describes.
+    pub fn get(
+        &self,
+        project_id: &Uuid,
+        column: &str,
+        descending: bool,
+        stamp: DataStamp,
+    ) -> Option<Arc<Vec<usize>>> {
+        let mut entries = self.inner.lock();
+        let position = entries.iter().position(|entry| {
+            entry.project_id == *project_id
+                && entry.column == column
+                && entry.descending == descending
+        })?;
+        if entries[position].stamp != stamp {
+            entries.remove(position);
+            return None;
+        }
+        let entry = entries.remove(position);
+        let order = entry.order.clone();
+        entries.insert(0, entry);
+        Some(order)
+    }
+
+    pub fn insert(
+        &self,
+        project_id: Uuid,
+        column: String,
+        descending: bool,
+        stamp: DataStamp,
+        order: Arc<Vec<usize>>,
+    ) {
+        let mut entries = self.inner.lock();
+        entries.retain(|entry| {
+            !(entry.project_id == project_id
+                && entry.column == column
+                && entry.descending == descending)
+        });
+        entries.insert(
+            0,
+            CachedSort {
+                project_id,
+                column,
+                descending,
+                stamp,
+                order,
+            },
+        );
+        entries.truncate(MAX_CACHED_SORTS);
+    }
+
+    /// Drops the orderings of a deleted project.
+    pub fn forget(&self, project_id: &Uuid) {
+        self.inner
+            .lock()
+            .retain(|entry| entry.project_id != *project_id);
+    }
+}
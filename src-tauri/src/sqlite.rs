@@ -0,0 +1,90 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use polars::prelude::DataFrame;
+use rusqlite::types::ValueRef;
+use rusqlite::{Connection, OpenFlags};
+use serde_json::Value;
+
+use crate::value_utils::JsonColumnBuilder;
+
+/// Opens evidence databases read-only so importing never modifies them.
+fn open_database(path: &Path) -> Result<Connection> {
+    Connection::open_with_flags(
+        path,
+        OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX,
+    )
+    .with_context(|| format!("failed to open SQLite database {:?}", path))
+}
+
+fn table_names(connection: &Connection) -> Result<Vec<String>> {
+    let mut statement = connection.prepare(
+        "SELECT name FROM sqlite_master \
+         WHERE type IN ('table', 'view') AND name NOT LIKE 'sqlite_%' \
+         ORDER BY type = 'view', name",
+    )?;
+    let names = statement
+        .query_map([], |row| row.get::<_, String>(0))?
+        .collect::<rusqlite::Result<Vec<_>>>()?;
+    Ok(names)
+}
+
+/// Lists the tables (then views) of a SQLite database, in name order.
+pub fn list_tables(path: &Path) -> Result<Vec<String>> {
+    let connection = open_database(path)?;
+    table_names(&connection).context("file is not a readable SQLite database")
+}
+
+fn cell_value(value: ValueRef<'_>) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(number) => Value::from(number),
+        ValueRef::Real(number) => Value::from(number),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        // Blobs (plists, protobufs, thumbnails) are kept searchable as hex.
+        ValueRef::Blob(bytes) => {
+            Value::String(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+        }
+    }
+}
+
+/// Reads one table or view of a SQLite database; the first table when
+/// `table` is unset. Column types follow the stored values, so a column
+/// holding both integers and text becomes text.
+pub fn read_sqlite_dataframe(path: &Path, table: Option<&str>) -> Result<DataFrame> {
+    let connection = open_database(path)?;
+    let tables = table_names(&connection).context("file is not a readable SQLite database")?;
+    let table = match table {
+        Some(name) => tables
+            .iter()
+            .find(|candidate| candidate.as_str() == name)
+            .ok_or_else(|| anyhow!("table {} not found in {:?}", name, path))?,
+        None => tables
+            .first()
+            .ok_or_else(|| anyhow!("{:?} contains no tables", path))?,
+    };
+
+    let mut statement = connection
+        .prepare(&format!("SELECT * FROM \"{}\"", table.replace('"', "\"\"")))
+        .with_context(|| format!("failed to query table {}", table))?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut builder = JsonColumnBuilder::default();
+    let mut rows = statement.query([])?;
+    while let Some(row) = rows.next()? {
+        let mut fields = Vec::with_capacity(columns.len());
+        for (index, name) in columns.iter().enumerate() {
+            fields.push((name.clone(), cell_value(row.get_ref(index)?)));
+        }
+        builder.push_row(fields);
+    }
+    if builder.rows() == 0 {
+        return Err(anyhow!("table {} has no rows", table));
+    }
+    builder
+        .finish()
+        .with_context(|| format!("failed to assemble columns of table {}", table))
+}
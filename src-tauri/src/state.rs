@@ -1,63 +1,87 @@
-use std::{fs, path::PathBuf};
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
 
-use anyhow::{Context, Result};
-use parking_lot::Mutex;
+use anyhow::{anyhow, Context, Result};
+use chrono::Utc;
+use parking_lot::{Mutex, MutexGuard, RwLock};
 use uuid::Uuid;
 
-use crate::{models::ProjectMeta, storage::load_flags};
+use crate::{
+    alerts::AlertSink,
+    encryption::UnlockedProjects,
+    events::{ChangeEvent, ChangeSink},
+    links::parse_launch_args,
+    locks::ProjectLocks,
+    migrations::migrate_project,
+    models::{
+        AlertSettings, IocConflictPolicy, LaunchRequest, ProjectEncryption, ProjectMeta, SourceFile,
+    },
+    perf::PerfStats,
+    session::SessionStore,
+    settings::SettingsStore,
+    sort_cache::SortCache,
+    storage::copy_dir_recursive,
+    watcher::WatchedFiles,
+};
+
+const PROJECTS_INDEX: &str = "projects.json";
+/// The index as it was before the last write, read when `projects.json`
+/// cannot be parsed.
+const PROJECTS_INDEX_BACKUP: &str = "projects.json.bak";
 
 pub struct ProjectsStore {
-    root_dir: PathBuf,
-    meta_path: PathBuf,
+    /// Directory holding `projects.json` and the `projects/` folder; changes
+    /// when the workspace is relocated.
+    root_dir: RwLock<PathBuf>,
     inner: Mutex<Vec<ProjectMeta>>,
+    changes: Arc<ChangeSink>,
 }
 
 impl ProjectsStore {
-    pub fn new(root_dir: PathBuf) -> Result<Self> {
+    pub fn new(root_dir: PathBuf, changes: Arc<ChangeSink>) -> Result<Self> {
         let projects_dir = root_dir.join("projects");
         fs::create_dir_all(&projects_dir)
             .with_context(|| format!("failed to create projects dir at {:?}", projects_dir))?;
 
-        let meta_path = root_dir.join("projects.json");
-        let mut projects: Vec<ProjectMeta> = if meta_path.exists() {
-            let data = fs::read(&meta_path)
-                .with_context(|| format!("failed to read metadata file {:?}", meta_path))?;
-            serde_json::from_slice(&data)
-                .with_context(|| format!("failed to parse metadata file {:?}", meta_path))?
-        } else {
-            Vec::new()
+        let meta_path = root_dir.join(PROJECTS_INDEX);
+        let mut needs_save = false;
+        let mut projects: Vec<ProjectMeta> = match read_index(&meta_path) {
+            Ok(projects) => projects,
+            Err(err) => {
+                let backup_path = root_dir.join(PROJECTS_INDEX_BACKUP);
+                let Ok(projects) = read_index(&backup_path) else {
+                    return Err(err);
+                };
+                tracing::warn!("{:#}; recovered the projects from {:?}", err, backup_path);
+                needs_save = true;
+                projects
+            }
         };
 
-        // Migrate existing project data (calculate if flagged_records is 0)
-        let mut needs_save = false;
         for project in &mut projects {
-            if project.flagged_records == 0 {
-                let project_dir = root_dir.join("projects").join(project.id.to_string());
-                let flags_path = project_dir.join("flags.json");
-                if flags_path.exists() {
-                    if let Ok(flags) = load_flags(&flags_path) {
-                        project.flagged_records = flags
-                            .values()
-                            .filter(|entry| !entry.flag.trim().is_empty())
-                            .count();
-                        needs_save = true;
-                    }
+            let project_dir = projects_dir.join(project.id.to_string());
+            match migrate_project(&project_dir, project) {
+                Ok(changed) => needs_save |= changed,
+                Err(err) => {
+                    // Keep the project listed; what did migrate is still saved.
+                    needs_save = true;
+                    tracing::warn!("{:?}: {:#}", project_dir, err);
                 }
             }
         }
-
-        // Save migrated data
         if needs_save {
-            let data = serde_json::to_vec_pretty(&projects)
-                .with_context(|| format!("failed to serialize metadata to {:?}", meta_path))?;
-            fs::write(&meta_path, data)
-                .with_context(|| format!("failed to write metadata file {:?}", meta_path))?;
+            write_index(&meta_path, &projects)?;
         }
 
         Ok(Self {
-            root_dir,
-            meta_path,
+            root_dir: RwLock::new(root_dir),
             inner: Mutex::new(projects),
+            changes,
         })
     }
 
@@ -67,16 +91,26 @@ impl ProjectsStore {
 
     pub fn insert(&self, project: ProjectMeta) -> Result<()> {
         let mut guard = self.inner.lock();
+        let id = project.id;
         guard.push(project);
-        self.persist_locked(&guard)
+        self.persist_and_notify(guard, &id)
     }
 
-    pub fn update_hidden_columns(&self, id: &Uuid, hidden_columns: Vec<String>) -> Result<()> {
+    /// Stores the hidden columns, and whether search skips them when given.
+    pub fn update_hidden_columns(
+        &self,
+        id: &Uuid,
+        hidden_columns: Vec<String>,
+        search_visible_only: Option<bool>,
+    ) -> Result<()> {
         let mut guard = self.inner.lock();
         if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
             meta.hidden_columns = hidden_columns;
+            if let Some(search_visible_only) = search_visible_only {
+                meta.search_visible_only = search_visible_only;
+            }
         }
-        self.persist_locked(&guard)
+        self.persist_and_notify(guard, id)
     }
 
     pub fn update_flagged_records(&self, id: &Uuid, flagged_records: usize) -> Result<()> {
@@ -84,7 +118,15 @@ impl ProjectsStore {
         if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
             meta.flagged_records = flagged_records;
         }
-        self.persist_locked(&guard)
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_ioc_policy(&self, id: &Uuid, policy: IocConflictPolicy) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.ioc_policy = policy;
+        }
+        self.persist_and_notify(guard, id)
     }
 
     pub fn update_ioc_applied_records(&self, id: &Uuid, count: usize) -> Result<()> {
@@ -92,7 +134,127 @@ impl ProjectsStore {
         if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
             meta.ioc_applied_records = count;
         }
-        self.persist_locked(&guard)
+        self.persist_and_notify(guard, id)
+    }
+
+    /// Records a refreshed import: new row counts and source fingerprints.
+    pub fn update_source(
+        &self,
+        id: &Uuid,
+        total_records: usize,
+        flagged_records: usize,
+        sources: Vec<SourceFile>,
+    ) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.total_records = total_records;
+            meta.flagged_records = flagged_records;
+            meta.sources = sources;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    /// Overwrites the row and flag counters, e.g. after a consistency repair.
+    pub fn update_counters(
+        &self,
+        id: &Uuid,
+        total_records: usize,
+        flagged_records: usize,
+        ioc_applied_records: usize,
+    ) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.total_records = total_records;
+            meta.flagged_records = flagged_records;
+            meta.ioc_applied_records = ioc_applied_records;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn mark_opened(&self, id: &Uuid) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.last_opened_at = Some(Utc::now());
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_alerts(&self, id: &Uuid, alerts: AlertSettings) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.alerts = alerts;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_display_timezone(&self, id: &Uuid, timezone: Option<String>) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.display_timezone = timezone;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_timestamp_column(&self, id: &Uuid, column: Option<String>) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.timestamp_column = column;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_column_aliases(
+        &self,
+        id: &Uuid,
+        aliases: BTreeMap<String, String>,
+    ) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.column_aliases = aliases;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    /// Sets or, given `None`, removes the description of one column.
+    pub fn update_column_description(
+        &self,
+        id: &Uuid,
+        column: &str,
+        description: Option<String>,
+    ) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            match description {
+                Some(description) => {
+                    meta.column_descriptions
+                        .insert(column.to_string(), description);
+                }
+                None => {
+                    meta.column_descriptions.remove(column);
+                }
+            }
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_encryption(
+        &self,
+        id: &Uuid,
+        encryption: Option<ProjectEncryption>,
+    ) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.encryption = encryption;
+        }
+        self.persist_and_notify(guard, id)
+    }
+
+    pub fn update_pinned(&self, id: &Uuid, pinned: bool) -> Result<()> {
+        let mut guard = self.inner.lock();
+        if let Some(meta) = guard.iter_mut().find(|meta| &meta.id == id) {
+            meta.pinned = pinned;
+        }
+        self.persist_and_notify(guard, id)
     }
 
     pub fn remove(&self, id: &Uuid) -> Result<()> {
@@ -110,28 +272,188 @@ impl ProjectsStore {
     }
 
     pub fn project_dir(&self, id: &Uuid) -> PathBuf {
-        self.root_dir.join("projects").join(id.to_string())
+        self.root_dir.read().join("projects").join(id.to_string())
+    }
+
+    pub fn root_dir(&self) -> PathBuf {
+        self.root_dir.read().clone()
+    }
+
+    /// Moves every project and the index to `new_root`. Projects are copied
+    /// first and `commit` runs before the switch, so a failure at any point
+    /// leaves the current workspace untouched; the old copies are removed
+    /// only once the new root is in use.
+    pub fn relocate(&self, new_root: &Path, commit: impl FnOnce() -> Result<()>) -> Result<()> {
+        let guard = self.inner.lock();
+        let mut root = self.root_dir.write();
+        if new_root == root.as_path() {
+            return commit();
+        }
+        if new_root.starts_with(root.join("projects")) {
+            return Err(anyhow!(
+                "the data root cannot be inside the current projects folder"
+            ));
+        }
+        if new_root.join(PROJECTS_INDEX).exists() {
+            return Err(anyhow!(
+                "{:?} already contains a Trivium workspace",
+                new_root
+            ));
+        }
+        let new_projects = new_root.join("projects");
+        if new_projects.exists() && fs::read_dir(&new_projects)?.next().is_some() {
+            return Err(anyhow!("{:?} is not empty", new_projects));
+        }
+
+        let copy = || -> Result<()> {
+            fs::create_dir_all(&new_projects)
+                .with_context(|| format!("failed to create projects dir at {:?}", new_projects))?;
+            for meta in guard.iter() {
+                let source = root.join("projects").join(meta.id.to_string());
+                if source.exists() {
+                    copy_dir_recursive(&source, &new_projects.join(meta.id.to_string()))?;
+                }
+            }
+            write_index(&new_root.join(PROJECTS_INDEX), &guard)?;
+            commit()
+        };
+        if let Err(err) = copy() {
+            let _ = fs::remove_dir_all(&new_projects);
+            let _ = fs::remove_file(new_root.join(PROJECTS_INDEX));
+            let _ = fs::remove_file(new_root.join(PROJECTS_INDEX_BACKUP));
+            return Err(err);
+        }
+
+        let old_root = std::mem::replace(&mut *root, new_root.to_path_buf());
+        for meta in guard.iter() {
+            let old_dir = old_root.join("projects").join(meta.id.to_string());
+            if let Err(err) = fs::remove_dir_all(&old_dir) {
+                tracing::warn!("failed to remove {:?}: {:?}", old_dir, err);
+            }
+        }
+        let _ = fs::remove_file(old_root.join(PROJECTS_INDEX));
+        let _ = fs::remove_file(old_root.join(PROJECTS_INDEX_BACKUP));
+        let _ = fs::remove_dir(old_root.join("projects"));
+        Ok(())
     }
 
     fn persist_locked(&self, guard: &[ProjectMeta]) -> Result<()> {
-        let data = serde_json::to_vec_pretty(guard)?;
-        fs::write(&self.meta_path, data)
-            .with_context(|| format!("failed to write metadata file {:?}", self.meta_path))
+        write_index(&self.root_dir.read().join(PROJECTS_INDEX), guard)
+    }
+
+    /// Persists the index, then reports the project `id` as it now stands.
+    fn persist_and_notify(&self, guard: MutexGuard<'_, Vec<ProjectMeta>>, id: &Uuid) -> Result<()> {
+        self.persist_locked(&guard)?;
+        let updated = guard.iter().find(|meta| &meta.id == id).cloned();
+        drop(guard);
+        if let Some(project) = updated {
+            self.changes.notify(ChangeEvent::ProjectMeta {
+                project: Box::new(project),
+            });
+        }
+        Ok(())
+    }
+}
+
+fn read_index(path: &Path) -> Result<Vec<ProjectMeta>> {
+    if !path.exists() {
+        return Ok(Vec::new());
     }
+    let data =
+        fs::read(path).with_context(|| format!("failed to read metadata file {:?}", path))?;
+    serde_json::from_slice(&data)
+        .with_context(|| format!("failed to parse metadata file {:?}", path))
+}
+
+/// Writes the index to a staged file and renames it into place, so a crash
+/// leaves either the old or the new index whole. The previous index is kept
+/// as `projects.json.bak`.
+fn write_index(path: &Path, projects: &[ProjectMeta]) -> Result<()> {
+    let data = serde_json::to_vec_pretty(projects)
+        .with_context(|| format!("failed to serialize metadata to {:?}", path))?;
+    let staged = path.with_extension("json.tmp");
+    let mut file =
+        File::create(&staged).with_context(|| format!("failed to write {:?}", staged))?;
+    file.write_all(&data)
+        .and_then(|()| file.sync_all())
+        .with_context(|| format!("failed to write {:?}", staged))?;
+    if path.exists() && read_index(path).is_ok() {
+        let backup = path.with_file_name(PROJECTS_INDEX_BACKUP);
+        if let Err(err) = fs::copy(path, &backup) {
+            tracing::warn!("failed to back up {:?}: {:?}", path, err);
+        }
+    }
+    fs::rename(&staged, path).with_context(|| format!("failed to write metadata file {:?}", path))
 }
 
 pub struct AppState {
+    /// Application data directory; the default data root.
+    pub app_dir: PathBuf,
     pub projects: ProjectsStore,
+    pub session: SessionStore,
+    pub settings: SettingsStore,
+    pub pending_launch: Mutex<Vec<LaunchRequest>>,
+    pub perf: PerfStats,
+    pub alerts: AlertSink,
+    pub locks: ProjectLocks,
+    /// Flag, IOC and metadata changes, shared with `projects`.
+    pub changes: Arc<ChangeSink>,
+    /// Keys of encrypted projects opened in this session.
+    pub unlocked: UnlockedProjects,
+    /// Row orderings of recent column sorts.
+    pub sorts: SortCache,
+    /// Project files as the app last wrote them, for the file watcher.
+    pub watched: WatchedFiles,
+}
+
+/// Resolves (and creates) the directory holding the workspace index and projects.
+pub fn app_data_root(config: &tauri::Config) -> Result<PathBuf> {
+    let base_dir = tauri::api::path::app_local_data_dir(config)
+        .context("failed to resolve app data dir")?
+        .join("trivium");
+    fs::create_dir_all(&base_dir)
+        .with_context(|| format!("failed to create app data dir {:?}", base_dir))?;
+    Ok(base_dir)
 }
 
 impl AppState {
     pub fn new(app: &tauri::App<tauri::Wry>) -> Result<Self> {
-        let base_dir = tauri::api::path::app_local_data_dir(&app.config())
-            .context("failed to resolve app data dir")?
-            .join("trivium");
-        fs::create_dir_all(&base_dir)
-            .with_context(|| format!("failed to create app data dir {:?}", base_dir))?;
-        let projects = ProjectsStore::new(base_dir)?;
-        Ok(Self { projects })
+        let base_dir = app_data_root(&app.config())?;
+        let state = Self::from_root(base_dir)?;
+        *state.pending_launch.lock() = parse_launch_args(std::env::args().skip(1));
+        Ok(state)
+    }
+
+    /// Builds the state over an arbitrary root directory without a Tauri `App`,
+    /// so the command layer can run headless (e.g. in tests).
+    pub fn from_root(root_dir: PathBuf) -> Result<Self> {
+        fs::create_dir_all(&root_dir)
+            .with_context(|| format!("failed to create app data dir {:?}", root_dir))?;
+        let settings = SettingsStore::new(root_dir.clone())?;
+        // A data root on an unmounted volume is not recreated on the OS drive;
+        // the default root is used until the volume is back.
+        let data_root = match settings.data_root() {
+            Some(path) if path.is_dir() => path,
+            Some(path) => {
+                tracing::warn!("data root {:?} is unavailable, using {:?}", path, root_dir);
+                root_dir.clone()
+            }
+            None => root_dir.clone(),
+        };
+        let changes = Arc::new(ChangeSink::default());
+        Ok(Self {
+            projects: ProjectsStore::new(data_root, changes.clone())?,
+            session: SessionStore::new(root_dir.clone())?,
+            settings,
+            app_dir: root_dir,
+            pending_launch: Mutex::new(Vec::new()),
+            perf: PerfStats::default(),
+            alerts: AlertSink::default(),
+            locks: ProjectLocks::default(),
+            changes,
+            unlocked: UnlockedProjects::default(),
+            sorts: SortCache::default(),
+            watched: WatchedFiles::default(),
+        })
     }
 }
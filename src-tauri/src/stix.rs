@@ -0,0 +1,382 @@
+use std::collections::BTreeMap;
+use std::net::IpAddr;
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use serde_json::{json, Value};
+use uuid::Uuid;
+
+use crate::models::{IocEntry, IocKind};
+use crate::value_utils::refang;
+
+/// File extensions that mark a term as a file name rather than a domain.
+const FILE_EXTENSIONS: &[&str] = &[
+    "exe", "dll", "sys", "scr", "bat", "cmd", "ps1", "psm1", "vbs", "vbe", "js", "jse", "wsf",
+    "hta", "lnk", "msi", "jar", "docm", "xlsm", "iso", "zip", "rar", "7z",
+];
+
+/// A rule left out of a STIX bundle and why.
+#[derive(Debug, Clone, Serialize)]
+pub struct SkippedStixRule {
+    pub tag: String,
+    pub query: String,
+    pub reason: String,
+}
+
+/// Severity expressed as STIX confidence (0-100) and indicator type.
+fn severity_mapping(flag: &str) -> (u8, &'static str) {
+    match flag {
+        "critical" => (85, "malicious-activity"),
+        "suspicious" => (50, "anomalous-activity"),
+        "safe" => (15, "benign"),
+        _ => (0, "unknown"),
+    }
+}
+
+fn quote_pattern(value: &str) -> String {
+    format!("'{}'", value.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn is_hex(text: &str) -> bool {
+    text.chars().all(|ch| ch.is_ascii_hexdigit())
+}
+
+fn is_domain(text: &str) -> bool {
+    let labels: Vec<&str> = text.trim_end_matches('.').split('.').collect();
+    labels.len() >= 2
+        && labels.iter().all(|label| {
+            !label.is_empty()
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label
+                    .chars()
+                    .all(|ch| ch.is_ascii_alphanumeric() || ch == '-')
+        })
+        && labels
+            .last()
+            .is_some_and(|tld| tld.len() >= 2 && tld.chars().all(|ch| ch.is_ascii_alphabetic()))
+}
+
+fn is_file_name(text: &str) -> bool {
+    !text.contains(['/', '\\', ':'])
+        && text.rsplit_once('.').is_some_and(|(stem, extension)| {
+            !stem.is_empty() && FILE_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+        })
+}
+
+/// STIX comparison for a single observable value, or `None` when the value
+/// has no recognizable observable type.
+fn observable_comparison(value: &str) -> Option<String> {
+    let quoted = quote_pattern(value);
+    if let Ok(ip) = value.parse::<IpAddr>() {
+        let object = if ip.is_ipv4() {
+            "ipv4-addr"
+        } else {
+            "ipv6-addr"
+        };
+        return Some(format!("{}:value = {}", object, quoted));
+    }
+    if is_hex(value) {
+        let algorithm = match value.len() {
+            32 => "MD5",
+            40 => "SHA-1",
+            64 => "SHA-256",
+            128 => "SHA-512",
+            _ => return None,
+        };
+        return Some(format!("file:hashes.'{}' = {}", algorithm, quoted));
+    }
+    if let Some((scheme, rest)) = value.split_once("://") {
+        if !scheme.is_empty() && !rest.is_empty() && !value.contains(char::is_whitespace) {
+            return Some(format!("url:value = {}", quoted));
+        }
+        return None;
+    }
+    if let Some((local, domain)) = value.split_once('@') {
+        if !local.is_empty() && !local.contains(char::is_whitespace) && is_domain(domain) {
+            return Some(format!("email-addr:value = {}", quoted));
+        }
+        return None;
+    }
+    if is_file_name(value) {
+        return Some(format!("file:name = {}", quoted));
+    }
+    if is_domain(value) {
+        return Some(format!("domain-name:value = {}", quoted));
+    }
+    None
+}
+
+/// Splits text on `|` outside double quotes.
+fn split_alternatives(query: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    let mut current = String::new();
+    let mut in_quotes = false;
+    for ch in query.chars() {
+        match ch {
+            '"' => {
+                in_quotes = !in_quotes;
+                current.push(ch);
+            }
+            '|' if !in_quotes => parts.push(std::mem::take(&mut current)),
+            _ => current.push(ch),
+        }
+    }
+    parts.push(current);
+    parts
+}
+
+/// Converts one search term, optionally written as `column:value`, into a
+/// STIX comparison.
+fn term_comparison(term: &str) -> Result<String, String> {
+    let unquoted = |text: &str| text.trim().trim_matches('"').to_string();
+    let whole = unquoted(term);
+    if whole.contains(char::is_whitespace) && term.trim().starts_with('"') {
+        return Err(format!("phrase {:?} has no STIX observable type", whole));
+    }
+    if !whole.contains('"') {
+        if let Some(comparison) = observable_comparison(&whole) {
+            return Ok(comparison);
+        }
+    }
+    // Column prefixes only restrict where Trivium looks; the value is what STIX matches.
+    if let Some((column, value)) = term.split_once(':') {
+        let value = unquoted(value);
+        if !column.is_empty() && !column.contains('"') && !value.is_empty() {
+            if let Some(comparison) = observable_comparison(&value) {
+                return Ok(comparison);
+            }
+        }
+    }
+    Err(format!("term {:?} has no STIX observable type", whole))
+}
+
+/// Search queries are expressible when they are a single term or terms
+/// joined by `|`; AND and NOT combinations have no single-observable form.
+fn query_pattern(query: &str) -> Result<String, String> {
+    let mut comparisons = Vec::new();
+    for alternative in split_alternatives(query) {
+        let alternative = alternative.trim();
+        if alternative.is_empty() {
+            continue;
+        }
+        let mut in_quotes = false;
+        let has_and = alternative.chars().any(|ch| {
+            if ch == '"' {
+                in_quotes = !in_quotes;
+            }
+            !in_quotes && ch.is_whitespace()
+        });
+        if has_and || alternative.starts_with('-') {
+            return Err("AND and NOT combinations cannot be expressed as STIX patterns".into());
+        }
+        comparisons.push(term_comparison(alternative)?);
+    }
+    if comparisons.is_empty() {
+        return Err("rule does not contain any term".into());
+    }
+    Ok(comparisons
+        .iter()
+        .map(|comparison| format!("[{}]", comparison))
+        .collect::<Vec<_>>()
+        .join(" OR "))
+}
+
+/// Breaks the inclusive range `start..=end` of `bits`-wide addresses into
+/// the fewest aligned CIDR blocks, as `(base, prefix length)`.
+fn range_to_cidrs(mut start: u128, end: u128, bits: u32) -> Vec<(u128, u32)> {
+    let host_mask = |size: u32| {
+        if size >= 128 {
+            u128::MAX
+        } else {
+            (1u128 << size) - 1
+        }
+    };
+    let mut blocks = Vec::new();
+    loop {
+        let mut size = start.trailing_zeros().min(bits);
+        while size > 0 && start | host_mask(size) > end {
+            size -= 1;
+        }
+        blocks.push((start, bits - size));
+        let last = start | host_mask(size);
+        if last >= end {
+            break;
+        }
+        start = last + 1;
+    }
+    blocks
+}
+
+fn ip_item_comparisons(text: &str) -> Result<Vec<String>, String> {
+    let invalid = || format!("invalid IP rule item {:?}", text);
+    let comparison = |ip: IpAddr, prefix: Option<u32>| {
+        let object = if ip.is_ipv4() {
+            "ipv4-addr"
+        } else {
+            "ipv6-addr"
+        };
+        match prefix {
+            Some(prefix) => format!(
+                "{}:value ISSUBSET {}",
+                object,
+                quote_pattern(&format!("{}/{}", ip, prefix))
+            ),
+            None => format!("{}:value = {}", object, quote_pattern(&ip.to_string())),
+        }
+    };
+    if let Some((addr, prefix)) = text.split_once('/') {
+        let ip: IpAddr = addr.trim().parse().map_err(|_| invalid())?;
+        let prefix: u32 = prefix.trim().parse().map_err(|_| invalid())?;
+        return Ok(vec![comparison(ip, Some(prefix))]);
+    }
+    if let Some((start, end)) = text.split_once('-') {
+        let start: IpAddr = start.trim().parse().map_err(|_| invalid())?;
+        let end: IpAddr = end.trim().parse().map_err(|_| invalid())?;
+        let (low, high, bits) = match (start, end) {
+            (IpAddr::V4(a), IpAddr::V4(b)) => (u32::from(a) as u128, u32::from(b) as u128, 32),
+            (IpAddr::V6(a), IpAddr::V6(b)) => (u128::from(a), u128::from(b), 128),
+            _ => return Err("range mixes IPv4 and IPv6 addresses".into()),
+        };
+        let (low, high) = (low.min(high), low.max(high));
+        return Ok(range_to_cidrs(low, high, bits)
+            .into_iter()
+            .map(|(base, prefix)| {
+                let ip = if bits == 32 {
+                    IpAddr::from((base as u32).to_be_bytes())
+                } else {
+                    IpAddr::from(base.to_be_bytes())
+                };
+                if prefix == bits {
+                    comparison(ip, None)
+                } else {
+                    comparison(ip, Some(prefix))
+                }
+            })
+            .collect());
+    }
+    let ip: IpAddr = text.trim().parse().map_err(|_| invalid())?;
+    Ok(vec![comparison(ip, None)])
+}
+
+/// IP rules list CIDR blocks, ranges and addresses; ranges are broken into
+/// the CIDR blocks STIX can express.
+fn ip_pattern(query: &str) -> Result<String, String> {
+    let mut comparisons = Vec::new();
+    for item in query
+        .split(|c: char| c.is_whitespace() || c == ',' || c == '|')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+    {
+        let text = match item.split_once(':') {
+            Some((column, rest)) if !column.is_empty() && ip_item_comparisons(rest).is_ok() => rest,
+            _ => item,
+        };
+        comparisons.extend(ip_item_comparisons(text)?);
+    }
+    if comparisons.is_empty() {
+        return Err("IP rule does not contain any address".into());
+    }
+    Ok(comparisons
+        .iter()
+        .map(|comparison| format!("[{}]", comparison))
+        .collect::<Vec<_>>()
+        .join(" OR "))
+}
+
+fn stix_pattern(entry: &IocEntry) -> Result<String, String> {
+    match entry.kind {
+        IocKind::Query => query_pattern(&refang(&entry.query)),
+        IocKind::Ip => ip_pattern(&refang(&entry.query)),
+        IocKind::Regex => Err(
+            "regular expressions are matched against any column, which STIX cannot express".into(),
+        ),
+    }
+}
+
+fn stix_timestamp(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string()
+}
+
+/// Converts IOC rules into a STIX 2.1 bundle of indicators. Tags become
+/// labels, severity maps to confidence and indicator type, and authors
+/// become identity objects. Rules without a STIX pattern equivalent are
+/// returned as skipped.
+pub fn build_stix_bundle(entries: &[IocEntry]) -> (Value, Vec<SkippedStixRule>) {
+    let now = stix_timestamp(Utc::now());
+    let mut identities: BTreeMap<String, String> = BTreeMap::new();
+    let mut indicators = Vec::new();
+    let mut skipped = Vec::new();
+
+    for entry in entries {
+        let pattern = match stix_pattern(entry) {
+            Ok(pattern) => pattern,
+            Err(reason) => {
+                skipped.push(SkippedStixRule {
+                    tag: entry.tag.clone(),
+                    query: entry.query.clone(),
+                    reason,
+                });
+                continue;
+            }
+        };
+        let (confidence, indicator_type) = severity_mapping(&entry.flag);
+        let mut indicator = json!({
+            "type": "indicator",
+            "spec_version": "2.1",
+            "id": format!("indicator--{}", Uuid::new_v4()),
+            "created": now,
+            "modified": now,
+            "name": if entry.tag.trim().is_empty() { entry.query.clone() } else { entry.tag.clone() },
+            "indicator_types": [indicator_type],
+            "pattern": pattern,
+            "pattern_type": "stix",
+            "valid_from": now,
+            "confidence": confidence,
+        });
+        let object = indicator
+            .as_object_mut()
+            .expect("indicator is a JSON object");
+        if !entry.tag.trim().is_empty() {
+            object.insert("labels".into(), json!([entry.tag.trim()]));
+        }
+        if let Some(description) = &entry.description {
+            object.insert("description".into(), json!(description));
+        }
+        if let Some(url) = &entry.reference_url {
+            object.insert(
+                "external_references".into(),
+                json!([{ "source_name": "reference", "url": url }]),
+            );
+        }
+        if let Some(author) = &entry.author {
+            let identity = identities
+                .entry(author.clone())
+                .or_insert_with(|| format!("identity--{}", Uuid::new_v4()));
+            object.insert("created_by_ref".into(), json!(identity));
+        }
+        indicators.push(indicator);
+    }
+
+    let mut objects: Vec<Value> = identities
+        .iter()
+        .map(|(name, id)| {
+            json!({
+                "type": "identity",
+                "spec_version": "2.1",
+                "id": id,
+                "created": now,
+                "modified": now,
+                "name": name,
+                "identity_class": "individual",
+            })
+        })
+        .collect();
+    objects.extend(indicators);
+    let bundle = json!({
+        "type": "bundle",
+        "id": format!("bundle--{}", Uuid::new_v4()),
+        "objects": objects,
+    });
+    (bundle, skipped)
+}
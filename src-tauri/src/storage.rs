@@ -5,16 +5,26 @@ use std::{
 };
 
 use anyhow::{Context, Result};
-use polars::prelude::DataFrame;
+use polars::prelude::{AnyValue, DataFrame};
+use serde::{Deserialize, Serialize};
 use sled::Db;
 
 use crate::{
-    models::FlagEntry,
-    value_utils::{anyvalue_to_json, value_display_length},
+    models::{Bookmark, CaseNotes, FlagEntry, ImportReport, SavedView, StorageUsage},
+    value_utils::{anyvalue_to_json, value_display_length, SEARCH_TEXT_FORMAT},
 };
 
+/// Cached display width of every column, derived from the project data.
+pub const COLUMN_METRICS_FILE: &str = "column_max_chars.json";
+pub const BOOKMARKS_FILE: &str = "bookmarks.json";
+pub const VIEWS_FILE: &str = "views.json";
+pub const CASE_NOTES_FILE: &str = "case_notes.json";
+/// Issues recorded by the last lenient import or reimport.
+pub const IMPORT_REPORT_FILE: &str = "import_report.json";
 const SEARCHABLE_CACHE_KEY: &[u8] = b"searchable_cache";
+const SEARCHABLE_CHUNK_PREFIX: &[u8] = b"searchable_cache/";
 const IOC_FLAG_CACHE_KEY: &[u8] = b"ioc_flag_cache";
+const IOC_MATCH_CACHE_KEY: &[u8] = b"ioc_match_cache";
 
 fn encode_row_key(row_index: usize) -> [u8; 8] {
     (row_index as u64).to_be_bytes()
@@ -109,13 +119,22 @@ pub fn load_flags(path: &Path) -> Result<HashMap<usize, FlagEntry>> {
     if db_path.exists() {
         return read_flags_from_db(&db_path);
     }
-    if path.exists() {
+    Ok(HashMap::new())
+}
+
+/// Moves flags from the JSON file older versions wrote into the flags
+/// database. Returns whether there was anything to move.
+pub fn migrate_legacy_flags(path: &Path) -> Result<bool> {
+    if !path.exists() {
+        return Ok(false);
+    }
+    let db_path = flags_db_path(path);
+    if !db_path.exists() {
         let flags = read_flags_from_json(path)?;
         write_flags_to_db(&db_path, &flags)?;
-        remove_legacy_flags_file(path);
-        return Ok(flags);
     }
-    Ok(HashMap::new())
+    remove_legacy_flags_file(path);
+    Ok(true)
 }
 
 pub fn save_flags(path: &Path, flags: &HashMap<usize, FlagEntry>) -> Result<()> {
@@ -146,36 +165,203 @@ pub fn save_column_metrics(path: &Path, metrics: &HashMap<String, usize>) -> Res
     fs::write(path, data).with_context(|| format!("failed to write column metrics file {:?}", path))
 }
 
-pub fn compute_column_max_chars(df: &DataFrame) -> HashMap<String, usize> {
-    let columns: Vec<String> = df
-        .get_column_names()
-        .into_iter()
-        .filter(|name| name != &"__rowid")
-        .map(|name| name.to_string())
-        .collect();
-    let mut column_max_chars: HashMap<String, usize> = columns
-        .iter()
-        .map(|name| {
-            let header_len = name.chars().count();
-            (name.clone(), header_len)
-        })
-        .collect();
-    for column in &columns {
-        if let Ok(series) = df.column(column) {
-            for value in series.iter() {
-                let json_value = anyvalue_to_json(&value);
-                let display_len = value_display_length(&json_value);
-                if let Some(entry) = column_max_chars.get_mut(column) {
-                    if display_len > *entry {
-                        *entry = display_len;
-                    }
-                }
-            }
+fn path_size(path: &Path) -> Result<u64> {
+    let metadata =
+        fs::symlink_metadata(path).with_context(|| format!("failed to stat {:?}", path))?;
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+    let mut total = 0;
+    for entry in
+        fs::read_dir(path).with_context(|| format!("failed to read directory {:?}", path))?
+    {
+        total += path_size(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Sums the size of a project directory by what each entry holds.
+pub fn project_storage_usage(project_dir: &Path) -> Result<StorageUsage> {
+    let mut usage = StorageUsage::default();
+    if !project_dir.exists() {
+        return Ok(usage);
+    }
+    for entry in fs::read_dir(project_dir)
+        .with_context(|| format!("failed to read project dir {:?}", project_dir))?
+    {
+        let entry = entry?;
+        let size = path_size(&entry.path())?;
+        let bucket = match entry.file_name().to_string_lossy().as_ref() {
+            "data.parquet" => &mut usage.data_bytes,
+            "flags.db" | "flags.json" => &mut usage.flags_bytes,
+            "cache.db" => &mut usage.cache_bytes,
+            "iocs.json" | "sigma_mapping.json" => &mut usage.ioc_bytes,
+            _ => &mut usage.other_bytes,
+        };
+        *bucket += size;
+        usage.total_bytes += size;
+    }
+    Ok(usage)
+}
+
+/// Copies a directory tree, such as a project with its sled databases.
+pub fn copy_dir_recursive(source: &Path, destination: &Path) -> Result<()> {
+    fs::create_dir_all(destination)
+        .with_context(|| format!("failed to create directory {:?}", destination))?;
+    for entry in
+        fs::read_dir(source).with_context(|| format!("failed to read directory {:?}", source))?
+    {
+        let entry = entry?;
+        let target = destination.join(entry.file_name());
+        if entry.file_type()?.is_dir() {
+            copy_dir_recursive(&entry.path(), &target)?;
+        } else {
+            fs::copy(entry.path(), &target)
+                .with_context(|| format!("failed to copy {:?}", entry.path()))?;
+        }
+    }
+    Ok(())
+}
+
+/// Loads a project's case notes; a missing file yields empty notes.
+pub fn load_case_notes(path: &Path) -> Result<CaseNotes> {
+    if !path.exists() {
+        return Ok(CaseNotes::default());
+    }
+    let data = fs::read(path).with_context(|| format!("failed to read case notes {:?}", path))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse case notes {:?}", path))
+}
+
+pub fn save_case_notes(path: &Path, notes: &CaseNotes) -> Result<()> {
+    let data = serde_json::to_vec_pretty(notes)
+        .with_context(|| format!("failed to serialize case notes for {:?}", path))?;
+    fs::write(path, data).with_context(|| format!("failed to write case notes {:?}", path))
+}
+
+/// Loads a project's bookmarks in row order; a missing file yields none.
+pub fn load_bookmarks(path: &Path) -> Result<Vec<Bookmark>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read(path).with_context(|| format!("failed to read bookmarks {:?}", path))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse bookmarks {:?}", path))
+}
+
+pub fn save_bookmarks(path: &Path, bookmarks: &[Bookmark]) -> Result<()> {
+    let data = serde_json::to_vec_pretty(bookmarks)
+        .with_context(|| format!("failed to serialize bookmarks for {:?}", path))?;
+    fs::write(path, data).with_context(|| format!("failed to write bookmarks {:?}", path))
+}
+
+/// Moves bookmarks to the rows' new positions after the data was rewritten;
+/// `new_row` yields `None` for rows that are gone. When two bookmarks land
+/// on one row the first is kept.
+pub fn remap_bookmarks(path: &Path, new_row: impl Fn(usize) -> Option<usize>) -> Result<()> {
+    let bookmarks = load_bookmarks(path)?;
+    if bookmarks.is_empty() {
+        return Ok(());
+    }
+    let mut remapped: Vec<Bookmark> = Vec::with_capacity(bookmarks.len());
+    for mut bookmark in bookmarks {
+        let Some(row_index) = new_row(bookmark.row_index) else {
+            continue;
+        };
+        if remapped.iter().all(|kept| kept.row_index != row_index) {
+            bookmark.row_index = row_index;
+            remapped.push(bookmark);
+        }
+    }
+    remapped.sort_by_key(|bookmark| bookmark.row_index);
+    save_bookmarks(path, &remapped)
+}
+
+/// Loads a project's saved views in name order; a missing file yields none.
+pub fn load_views(path: &Path) -> Result<Vec<SavedView>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let data = fs::read(path).with_context(|| format!("failed to read views {:?}", path))?;
+    serde_json::from_slice(&data).with_context(|| format!("failed to parse views {:?}", path))
+}
+
+pub fn save_views(path: &Path, views: &[SavedView]) -> Result<()> {
+    let data = serde_json::to_vec_pretty(views)
+        .with_context(|| format!("failed to serialize views for {:?}", path))?;
+    fs::write(path, data).with_context(|| format!("failed to write views {:?}", path))
+}
+
+/// Writes the full report of a lenient import next to the project data, or
+/// removes a stale one when the import needed no repairs.
+pub fn save_import_report(path: &Path, report: &ImportReport) -> Result<()> {
+    if report.issues.is_empty() {
+        if path.exists() {
+            fs::remove_file(path)
+                .with_context(|| format!("failed to remove import report {:?}", path))?;
         }
+        return Ok(());
+    }
+    let data = serde_json::to_vec_pretty(report)
+        .with_context(|| format!("failed to serialize import report for {:?}", path))?;
+    fs::write(path, data).with_context(|| format!("failed to write import report {:?}", path))
+}
+
+fn cell_display_length(value: AnyValue) -> usize {
+    value_display_length(&anyvalue_to_json(&value))
+}
+
+/// Rows measured when column widths are computed from a sample.
+pub const COLUMN_WIDTH_SAMPLE_ROWS: usize = 10_000;
+
+/// Display width of every column: the longest of its header and values.
+/// With `sample_rows`, larger frames are measured on that many evenly
+/// spaced rows only, so opening a large project does not walk every cell.
+pub fn compute_column_max_chars(
+    df: &DataFrame,
+    sample_rows: Option<usize>,
+) -> HashMap<String, usize> {
+    let height = df.height();
+    let sample: Option<Vec<usize>> = sample_rows
+        .filter(|sample| *sample > 0 && height > *sample)
+        .map(|sample| (0..sample).map(|step| step * height / sample).collect());
+    let mut column_max_chars = HashMap::new();
+    for series in df.get_columns() {
+        if series.name() == "__rowid" {
+            continue;
+        }
+        let header_len = series.name().chars().count();
+        let max_len = match &sample {
+            Some(rows) => rows
+                .iter()
+                .filter_map(|&row| series.get(row).ok())
+                .map(cell_display_length)
+                .fold(header_len, usize::max),
+            None => series
+                .iter()
+                .map(cell_display_length)
+                .fold(header_len, usize::max),
+        };
+        column_max_chars.insert(series.name().to_string(), max_len);
     }
     column_max_chars
 }
 
+pub fn load_flag(path: &Path, row_index: usize) -> Result<Option<FlagEntry>> {
+    let db_path = flags_db_path(path);
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let db = open_flags_db(&db_path)?;
+    let Some(value) = db
+        .get(encode_row_key(row_index))
+        .with_context(|| format!("failed to read flag entry for row {}", row_index))?
+    else {
+        return Ok(None);
+    };
+    let entry = serde_json::from_slice(&value)
+        .with_context(|| format!("failed to deserialize flag entry for row {}", row_index))?;
+    Ok(Some(entry))
+}
+
 pub fn upsert_flag(path: &Path, row_index: usize, entry: &FlagEntry) -> Result<()> {
     let db = open_flags_db(&flags_db_path(path))?;
     let key = encode_row_key(row_index);
@@ -212,20 +398,76 @@ pub fn count_flagged(path: &Path) -> Result<usize> {
     Ok(count)
 }
 
-pub fn load_searchable_cache(project_dir: &Path) -> Result<Option<Vec<String>>> {
+/// Describes the per-row search text a row query keeps in the cache database,
+/// so the first search after a restart does not rebuild it. The text itself
+/// is stored in chunks of `chunk_rows` rows (see `load_searchable_chunk`), so
+/// large projects never load all of it at once.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchableCache {
+    pub row_count: usize,
+    pub chunk_rows: usize,
+    /// Columns the text was built from.
+    pub columns: Vec<String>,
+    /// Hidden columns of the project when the text was built.
+    pub hidden_columns: Vec<String>,
+    /// `SEARCH_TEXT_FORMAT` the text was normalized with; 0 before it existed.
+    #[serde(default)]
+    pub text_format: u32,
+}
+
+impl SearchableCache {
+    /// Whether the text still describes the data: same row count, built from
+    /// the same columns, with the same columns hidden, normalized the current way.
+    pub fn is_valid_for(&self, row_count: usize, columns: &[String], hidden: &[String]) -> bool {
+        self.row_count == row_count
+            && self.columns == columns
+            && self.hidden_columns == hidden
+            && self.text_format == SEARCH_TEXT_FORMAT
+    }
+}
+
+fn searchable_chunk_key(chunk: usize) -> Vec<u8> {
+    let mut key = SEARCHABLE_CHUNK_PREFIX.to_vec();
+    key.extend_from_slice(&(chunk as u64).to_be_bytes());
+    key
+}
+
+/// Loads the description of the cached row search text. Caches written by
+/// older versions read as missing.
+pub fn load_searchable_cache(project_dir: &Path) -> Result<Option<SearchableCache>> {
     let db = open_cache_db(&cache_db_path(project_dir))?;
     match db.get(SEARCHABLE_CACHE_KEY) {
-        Ok(Some(value)) => {
-            let cache: Vec<String> =
-                serde_json::from_slice(&value).context("failed to deserialize searchable cache")?;
-            Ok(Some(cache))
-        }
+        Ok(Some(value)) => Ok(serde_json::from_slice(&value).ok()),
         Ok(None) => Ok(None),
         Err(err) => Err(err).with_context(|| "failed to read searchable cache"),
     }
 }
 
-pub fn save_searchable_cache(project_dir: &Path, cache: &[String]) -> Result<()> {
+/// Loads one chunk of cached row search text; only meaningful while the
+/// cache description is valid.
+pub fn load_searchable_chunk(project_dir: &Path, chunk: usize) -> Result<Option<Vec<String>>> {
+    let db = open_cache_db(&cache_db_path(project_dir))?;
+    match db.get(searchable_chunk_key(chunk)) {
+        Ok(Some(value)) => serde_json::from_slice(&value)
+            .map(Some)
+            .context("failed to deserialize searchable cache chunk"),
+        Ok(None) => Ok(None),
+        Err(err) => Err(err).with_context(|| "failed to read searchable cache chunk"),
+    }
+}
+
+pub fn save_searchable_chunk(project_dir: &Path, chunk: usize, text: &[String]) -> Result<()> {
+    let db = open_cache_db(&cache_db_path(project_dir))?;
+    let data = serde_json::to_vec(text).context("failed to serialize searchable cache chunk")?;
+    db.insert(searchable_chunk_key(chunk), data)
+        .with_context(|| "failed to persist searchable cache chunk")?;
+    db.flush()
+        .with_context(|| "failed to flush searchable cache db")?;
+    Ok(())
+}
+
+/// Records the cache description once every chunk has been written.
+pub fn save_searchable_cache(project_dir: &Path, cache: &SearchableCache) -> Result<()> {
     let db = open_cache_db(&cache_db_path(project_dir))?;
     let data = serde_json::to_vec(cache).context("failed to serialize searchable cache")?;
     db.insert(SEARCHABLE_CACHE_KEY, data)
@@ -239,25 +481,92 @@ pub fn clear_searchable_cache(project_dir: &Path) -> Result<()> {
     let db = open_cache_db(&cache_db_path(project_dir))?;
     db.remove(SEARCHABLE_CACHE_KEY)
         .with_context(|| "failed to clear searchable cache")?;
+    for result in db.scan_prefix(SEARCHABLE_CHUNK_PREFIX) {
+        let (key, _) = result.with_context(|| "failed to iterate searchable cache chunks")?;
+        db.remove(key)
+            .with_context(|| "failed to clear searchable cache chunk")?;
+    }
     db.flush()
         .with_context(|| "failed to flush searchable cache db")?;
     Ok(())
 }
 
-pub fn load_ioc_flag_cache(project_dir: &Path) -> Result<Option<Vec<String>>> {
+/// Copies a sled database into a fresh directory and swaps it in. Sled does
+/// not shrink its files on its own, so heavy churn leaves them growing.
+fn compact_sled_db(path: &Path) -> Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    let staged = path.with_extension("db.compact");
+    let retired = path.with_extension("db.old");
+    for leftover in [&staged, &retired] {
+        if leftover.exists() {
+            fs::remove_dir_all(leftover)
+                .with_context(|| format!("failed to remove {:?}", leftover))?;
+        }
+    }
+    {
+        let source =
+            sled::open(path).with_context(|| format!("failed to open sled db at {:?}", path))?;
+        let target = sled::open(&staged)
+            .with_context(|| format!("failed to create sled db at {:?}", staged))?;
+        for result in source.iter() {
+            let (key, value) = result.with_context(|| format!("failed to read {:?}", path))?;
+            target
+                .insert(key, value)
+                .with_context(|| format!("failed to write {:?}", staged))?;
+        }
+        target
+            .flush()
+            .with_context(|| format!("failed to flush {:?}", staged))?;
+    }
+    fs::rename(path, &retired).with_context(|| format!("failed to replace {:?}", path))?;
+    if let Err(err) = fs::rename(&staged, path) {
+        let _ = fs::rename(&retired, path);
+        return Err(err).with_context(|| format!("failed to replace {:?}", path));
+    }
+    fs::remove_dir_all(&retired).with_context(|| format!("failed to remove {:?}", retired))
+}
+
+pub fn compact_flags_db(path: &Path) -> Result<()> {
+    compact_sled_db(&flags_db_path(path))
+}
+
+pub fn compact_cache_db(project_dir: &Path) -> Result<()> {
+    compact_sled_db(&cache_db_path(project_dir))
+}
+
+/// Deletes the cache database outright, for when sled can no longer open it.
+/// Every cached value is rebuilt on the next query.
+pub fn reset_cache_db(project_dir: &Path) -> Result<()> {
+    let path = cache_db_path(project_dir);
+    if path.exists() {
+        fs::remove_dir_all(&path)
+            .with_context(|| format!("failed to remove cache db {:?}", path))?;
+    }
+    Ok(())
+}
+
+/// IOC-derived flag of every row, stored with the fingerprint of the rules
+/// and user flags it was computed from (see `ioc::ioc_flag_cache_key`).
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IocFlagCache {
+    pub key: String,
+    pub flags: Vec<String>,
+}
+
+/// Loads the cached IOC flags. Caches written by older versions, which had
+/// no fingerprint, read as missing.
+pub fn load_ioc_flag_cache(project_dir: &Path) -> Result<Option<IocFlagCache>> {
     let db = open_cache_db(&cache_db_path(project_dir))?;
     match db.get(IOC_FLAG_CACHE_KEY) {
-        Ok(Some(value)) => {
-            let cache: Vec<String> =
-                serde_json::from_slice(&value).context("failed to deserialize IOC flag cache")?;
-            Ok(Some(cache))
-        }
+        Ok(Some(value)) => Ok(serde_json::from_slice(&value).ok()),
         Ok(None) => Ok(None),
         Err(err) => Err(err).with_context(|| "failed to read IOC flag cache"),
     }
 }
 
-pub fn save_ioc_flag_cache(project_dir: &Path, cache: &[String]) -> Result<()> {
+pub fn save_ioc_flag_cache(project_dir: &Path, cache: &IocFlagCache) -> Result<()> {
     let db = open_cache_db(&cache_db_path(project_dir))?;
     let data = serde_json::to_vec(cache).context("failed to serialize IOC flag cache")?;
     db.insert(IOC_FLAG_CACHE_KEY, data)
@@ -267,10 +576,75 @@ pub fn save_ioc_flag_cache(project_dir: &Path, cache: &[String]) -> Result<()> {
     Ok(())
 }
 
+/// What the IOC rules make of every row regardless of user flags, stored
+/// with the fingerprint of the rules and columns it was computed from (see
+/// `ioc::ioc_match_cache_key`). A user flag edit adjusts the IOC counts and
+/// flags from it without evaluating the rules again.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct IocMatchCache {
+    pub key: String,
+    /// Rows some rule matches, one bit per row.
+    pub matched: Vec<u8>,
+    /// The flag the rules give each row that has no user flag.
+    pub flags: Vec<String>,
+    /// Rows each rule matches, in the rules' evaluation order.
+    #[serde(default)]
+    pub rule_hits: Vec<usize>,
+}
+
+impl IocMatchCache {
+    pub fn new(key: String, row_count: usize, rule_count: usize) -> Self {
+        Self {
+            key,
+            matched: vec![0; row_count.div_ceil(8)],
+            flags: vec![String::new(); row_count],
+            rule_hits: vec![0; rule_count],
+        }
+    }
+
+    pub fn row_count(&self) -> usize {
+        self.flags.len()
+    }
+
+    pub fn is_matched(&self, row: usize) -> bool {
+        self.matched
+            .get(row / 8)
+            .is_some_and(|byte| byte & (1 << (row % 8)) != 0)
+    }
+
+    pub fn set_matched(&mut self, row: usize) {
+        if let Some(byte) = self.matched.get_mut(row / 8) {
+            *byte |= 1 << (row % 8);
+        }
+    }
+}
+
+pub fn load_ioc_match_cache(project_dir: &Path) -> Result<Option<IocMatchCache>> {
+    let db = open_cache_db(&cache_db_path(project_dir))?;
+    match db.get(IOC_MATCH_CACHE_KEY) {
+        Ok(Some(value)) => Ok(serde_json::from_slice(&value).ok()),
+        Ok(None) => Ok(None),
+        Err(err) => Err(err).with_context(|| "failed to read IOC match cache"),
+    }
+}
+
+pub fn save_ioc_match_cache(project_dir: &Path, cache: &IocMatchCache) -> Result<()> {
+    let db = open_cache_db(&cache_db_path(project_dir))?;
+    let data = serde_json::to_vec(cache).context("failed to serialize IOC match cache")?;
+    db.insert(IOC_MATCH_CACHE_KEY, data)
+        .with_context(|| "failed to persist IOC match cache")?;
+    db.flush()
+        .with_context(|| "failed to flush IOC match cache db")?;
+    Ok(())
+}
+
+/// Drops the cached IOC flags together with the rule matches behind them.
 pub fn clear_ioc_flag_cache(project_dir: &Path) -> Result<()> {
     let db = open_cache_db(&cache_db_path(project_dir))?;
     db.remove(IOC_FLAG_CACHE_KEY)
         .with_context(|| "failed to clear IOC flag cache")?;
+    db.remove(IOC_MATCH_CACHE_KEY)
+        .with_context(|| "failed to clear IOC match cache")?;
     db.flush()
         .with_context(|| "failed to flush IOC flag cache db")?;
     Ok(())
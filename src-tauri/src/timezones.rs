@@ -0,0 +1,150 @@
+//! Time zones for reading local timestamps at import and for showing the
+//! stored values.
+//!
+//! Designated timestamp columns are stored as UTC datetimes. Values written
+//! without an offset are read in the project's source time zone; values
+//! that carry an offset keep the instant they name. The display time zone
+//! only changes how stored values are rendered for the grid.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Duration, FixedOffset, LocalResult, NaiveDateTime, Offset, TimeZone};
+use chrono_tz::Tz;
+use polars::prelude::{AnyValue, DataType, Series, TimeUnit};
+
+/// How stored timestamps are shown in a display time zone.
+const DISPLAY_FORMAT: &str = "%Y-%m-%d %H:%M:%S%.f %:z";
+
+/// An IANA zone such as `Asia/Tokyo`, whose offset follows daylight saving
+/// time, or a fixed offset such as `+09:00` (`UTC` and `Z` mean `+00:00`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProjectTimezone {
+    Named(Tz),
+    Fixed(FixedOffset),
+}
+
+fn parse_fixed_offset(text: &str) -> Option<FixedOffset> {
+    let upper = text.to_ascii_uppercase();
+    let rest = upper
+        .strip_prefix("UTC")
+        .or_else(|| upper.strip_prefix("GMT"))
+        .unwrap_or(&upper);
+    if rest.is_empty() || rest == "Z" {
+        return FixedOffset::east_opt(0);
+    }
+    let (sign, digits) = match rest.as_bytes()[0] {
+        b'+' => (1, &rest[1..]),
+        b'-' => (-1, &rest[1..]),
+        _ => return None,
+    };
+    let (hours, minutes) = match digits.split_once(':') {
+        Some((hours, minutes)) => (hours, minutes),
+        None if digits.len() > 2 => digits.split_at(digits.len() - 2),
+        None => (digits, "0"),
+    };
+    if hours.is_empty() || !(hours.len() <= 2 && minutes.len() <= 2) {
+        return None;
+    }
+    let hours: i32 = hours.parse().ok()?;
+    let minutes: i32 = minutes.parse().ok()?;
+    if hours > 14 || minutes > 59 {
+        return None;
+    }
+    FixedOffset::east_opt(sign * (hours * 3600 + minutes * 60))
+}
+
+impl ProjectTimezone {
+    pub fn utc() -> Self {
+        Self::Fixed(FixedOffset::east_opt(0).expect("zero offset is valid"))
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(anyhow!("time zone is empty"));
+        }
+        if let Some(offset) = parse_fixed_offset(name) {
+            return Ok(Self::Fixed(offset));
+        }
+        name.parse::<Tz>().map(Self::Named).map_err(|_| {
+            anyhow!(
+                "unknown time zone {:?}; use an IANA name such as Asia/Tokyo or an offset such as +09:00",
+                name
+            )
+        })
+    }
+
+    /// Reads a wall-clock time in this zone as UTC. Times repeated when
+    /// clocks go back take the earlier instant; times skipped when clocks go
+    /// forward use the offset in effect before the change.
+    pub fn local_to_utc(&self, local: NaiveDateTime) -> NaiveDateTime {
+        let offset = match self {
+            Self::Fixed(offset) => *offset,
+            Self::Named(tz) => match tz.from_local_datetime(&local) {
+                LocalResult::Single(value) | LocalResult::Ambiguous(value, _) => {
+                    return value.naive_utc()
+                }
+                LocalResult::None => tz
+                    .offset_from_utc_datetime(&(local - Duration::hours(3)))
+                    .fix(),
+            },
+        };
+        local - Duration::seconds(i64::from(offset.local_minus_utc()))
+    }
+
+    /// Renders a UTC timestamp as wall-clock time in this zone, with its offset.
+    pub fn format_utc(&self, utc: NaiveDateTime) -> String {
+        self.format_utc_as(utc, DISPLAY_FORMAT)
+    }
+
+    /// Renders a UTC timestamp as wall-clock time in this zone using a
+    /// chrono format that has been checked to render.
+    pub fn format_utc_as(&self, utc: NaiveDateTime, format: &str) -> String {
+        match self {
+            Self::Fixed(offset) => offset.from_utc_datetime(&utc).format(format),
+            Self::Named(tz) => tz.from_utc_datetime(&utc).format(format),
+        }
+        .to_string()
+    }
+}
+
+/// How stored timestamps are rendered for the grid: the project's display
+/// time zone, or UTC, in the date format from the settings, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TimeDisplay {
+    pub zone: ProjectTimezone,
+    pub format: Option<String>,
+}
+
+fn to_micros(value: i64, unit: TimeUnit) -> i64 {
+    match unit {
+        TimeUnit::Nanoseconds => value.div_euclid(1000),
+        TimeUnit::Microseconds => value,
+        TimeUnit::Milliseconds => value * 1000,
+    }
+}
+
+/// Physical values of a datetime column as microseconds since the epoch.
+pub fn datetime_micros(series: &Series) -> Result<Vec<Option<i64>>> {
+    let DataType::Datetime(unit, _) = series.dtype() else {
+        return Err(anyhow!("column {} is not a datetime column", series.name()));
+    };
+    let unit = *unit;
+    Ok(series
+        .to_physical_repr()
+        .i64()?
+        .into_iter()
+        .map(|value| value.map(|value| to_micros(value, unit)))
+        .collect())
+}
+
+/// Renders a stored datetime value as `display` asks; `None` for other values.
+pub fn format_datetime_value(value: &AnyValue, display: &TimeDisplay) -> Option<String> {
+    let AnyValue::Datetime(raw, unit, _) = value else {
+        return None;
+    };
+    let utc = DateTime::from_timestamp_micros(to_micros(*raw, *unit))?.naive_utc();
+    Some(match &display.format {
+        Some(format) => display.zone.format_utc_as(utc, format),
+        None => display.zone.format_utc(utc),
+    })
+}
@@ -1,6 +1,74 @@
-use polars::prelude::AnyValue;
+use std::{borrow::Cow, collections::HashMap};
+
+use polars::prelude::{AnyValue, DataFrame, NamedFrom, PolarsResult, Series};
 use serde_json::Value;
 
+/// Separator between list elements in element-wise search text (see `col[]:` queries).
+pub const LIST_ELEMENT_SEPARATOR: char = '\u{1f}';
+
+/// Version of `normalize_search_text`. Bump it whenever the normalization
+/// changes so cached search text and IOC flags built the old way are rebuilt.
+pub const SEARCH_TEXT_FORMAT: u32 = 1;
+
+/// Defanged spellings of indicators (as written in threat intel) and what
+/// they stand for; matched ignoring ASCII case.
+const DEFANG_REPLACEMENTS: &[(&str, &str)] = &[
+    ("hxxp", "http"),
+    ("[://]", "://"),
+    ("[:]", ":"),
+    ("[.]", "."),
+    ("(.)", "."),
+    ("{.}", "."),
+    ("[dot]", "."),
+    ("(dot)", "."),
+    ("[@]", "@"),
+    ("[at]", "@"),
+    ("(at)", "@"),
+];
+
+/// Undoes common defanging, e.g. `hxxp://evil[.]com` becomes
+/// `http://evil.com`. Text without any defanged spelling is borrowed as is.
+pub fn refang(text: &str) -> Cow<'_, str> {
+    let maybe_defanged = text.contains(['[', '(', '{'])
+        || text
+            .as_bytes()
+            .windows(4)
+            .any(|window| window.eq_ignore_ascii_case(b"hxxp"));
+    if !maybe_defanged {
+        return Cow::Borrowed(text);
+    }
+    let mut refanged = Cow::Borrowed(text);
+    // ASCII lowercasing keeps byte offsets, so matches map back directly.
+    let mut lower = text.to_ascii_lowercase();
+    for (defanged, plain) in DEFANG_REPLACEMENTS {
+        if !lower.contains(defanged) {
+            continue;
+        }
+        let mut replaced = String::with_capacity(refanged.len());
+        let mut last = 0;
+        for (start, _) in lower.match_indices(defanged) {
+            replaced.push_str(&refanged[last..start]);
+            replaced.push_str(plain);
+            last = start + defanged.len();
+        }
+        replaced.push_str(&refanged[last..]);
+        lower = replaced.to_ascii_lowercase();
+        refanged = Cow::Owned(replaced);
+    }
+    refanged
+}
+
+/// Form in which both data and query terms are compared: lowercase and
+/// refanged, so `evil[.]com` in a rule matches `evil.com` in the data and
+/// the other way round.
+pub fn normalize_search_text(text: &str) -> String {
+    let lower = text.to_lowercase();
+    match refang(&lower) {
+        Cow::Borrowed(_) => lower,
+        Cow::Owned(refanged) => refanged,
+    }
+}
+
 pub fn anyvalue_to_json(value: &AnyValue) -> Value {
     match value {
         AnyValue::Null => Value::Null,
@@ -16,9 +84,11 @@ pub fn anyvalue_to_json(value: &AnyValue) -> Value {
         AnyValue::Float32(v) => Value::from(f64::from(*v)),
         AnyValue::Float64(v) => Value::from(*v),
         AnyValue::String(v) => Value::String(v.to_string()),
-        AnyValue::Date(v) => Value::String(v.to_string()),
-        AnyValue::Datetime(v, _, _) => Value::String(v.to_string()),
-        AnyValue::Time(v) => Value::String(v.to_string()),
+        AnyValue::StringOwned(v) => Value::String(v.to_string()),
+        // Temporal values render the same way as in search text, not as raw epoch counts.
+        AnyValue::Date(_) | AnyValue::Datetime(_, _, _) | AnyValue::Time(_) => {
+            Value::String(value.to_string())
+        }
         AnyValue::List(series) => {
             let values: Vec<Value> = series.iter().map(|v| anyvalue_to_json(&v)).collect();
             Value::Array(values)
@@ -85,3 +155,118 @@ pub fn anyvalue_to_search_string(value: &AnyValue) -> Option<String> {
         other => Some(other.to_string()),
     }
 }
+
+/// Like `anyvalue_to_search_string`, but list elements are joined with
+/// `LIST_ELEMENT_SEPARATOR` so they can be matched one at a time.
+pub fn anyvalue_to_element_search_string(value: &AnyValue) -> Option<String> {
+    match value {
+        AnyValue::List(series) => {
+            let parts: Vec<String> = series
+                .iter()
+                .filter_map(|inner| anyvalue_to_search_string(&inner))
+                .collect();
+            if parts.is_empty() {
+                None
+            } else {
+                Some(parts.join(&LIST_ELEMENT_SEPARATOR.to_string()))
+            }
+        }
+        other => anyvalue_to_search_string(other),
+    }
+}
+
+/// JSON counterpart of `anyvalue_to_element_search_string`.
+pub fn value_to_element_search_string(value: &Value) -> Option<String> {
+    match value {
+        Value::Array(items) => {
+            let parts: Vec<String> = items.iter().filter_map(value_to_search_string).collect();
+            if parts.is_empty() {
+                None
+            } else {
+                Some(parts.join(&LIST_ELEMENT_SEPARATOR.to_string()))
+            }
+        }
+        other => value_to_search_string(other),
+    }
+}
+
+/// Picks the narrowest column type shared by every non-null value: booleans,
+/// integers, floats, otherwise text. Arrays are kept as their JSON text and
+/// all-null columns become text.
+pub fn json_values_to_series(name: &str, cells: Vec<Option<Value>>) -> Series {
+    let present = || cells.iter().flatten();
+    let typed = present().next().is_some();
+    if typed && present().all(Value::is_boolean) {
+        let values: Vec<Option<bool>> = cells.iter().map(|v| v.as_ref()?.as_bool()).collect();
+        return Series::new(name, values);
+    }
+    if typed && present().all(Value::is_i64) {
+        let values: Vec<Option<i64>> = cells.iter().map(|v| v.as_ref()?.as_i64()).collect();
+        return Series::new(name, values);
+    }
+    if typed && present().all(Value::is_number) {
+        let values: Vec<Option<f64>> = cells.iter().map(|v| v.as_ref()?.as_f64()).collect();
+        return Series::new(name, values);
+    }
+    let values: Vec<Option<String>> = cells
+        .into_iter()
+        .map(|cell| {
+            cell.map(|value| match value {
+                Value::String(text) => text,
+                other => other.to_string(),
+            })
+        })
+        .collect();
+    Series::new(name, values)
+}
+
+/// Builds a frame from rows of named JSON values. Columns appear in
+/// first-seen order; rows missing a key hold nulls.
+#[derive(Default)]
+pub struct JsonColumnBuilder {
+    names: Vec<String>,
+    index: HashMap<String, usize>,
+    values: Vec<Vec<Option<Value>>>,
+    rows: usize,
+}
+
+impl JsonColumnBuilder {
+    pub fn push_row(&mut self, fields: Vec<(String, Value)>) {
+        for (name, value) in fields {
+            let column = match self.index.get(&name) {
+                Some(&column) => column,
+                None => {
+                    let column = self.names.len();
+                    self.index.insert(name.clone(), column);
+                    self.names.push(name);
+                    self.values.push(vec![None; self.rows]);
+                    column
+                }
+            };
+            let cells = &mut self.values[column];
+            // Duplicate keys after flattening (e.g. `a.b` next to `a: {b}`) keep the last value.
+            if cells.len() > self.rows {
+                cells.pop();
+            }
+            cells.push(Some(value).filter(|value| !value.is_null()));
+        }
+        self.rows += 1;
+        for cells in &mut self.values {
+            cells.resize(self.rows, None);
+        }
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    pub fn finish(self) -> PolarsResult<DataFrame> {
+        let columns: Vec<Series> = self
+            .names
+            .iter()
+            .zip(self.values)
+            .map(|(name, cells)| json_values_to_series(name, cells))
+            .collect();
+        DataFrame::new(columns)
+    }
+}
@@ -0,0 +1,250 @@
+//! Notices project files changed outside the app.
+//!
+//! A project's data or IOC rules can be replaced behind Trivium's back, for
+//! example when a folder is restored from backup or synced from another
+//! machine. Derived caches would then describe the old files and searches
+//! would silently return wrong rows. The projects folder is watched with
+//! `notify`; once changes to a watched file have settled for
+//! `SETTLE_DELAY`, the project's files are compared with the stamps
+//! `WatchedFiles` recorded when the app last wrote or checked them. Only a
+//! real outside change drops the derived caches, recounts the project and
+//! emits `PROJECT_MODIFIED_EVENT` so the frontend reloads.
+//!
+//! The app's own writes are recognized in two ways: commands that rewrite a
+//! project hold its rewrite lock, and a finished rewrite takes the files as
+//! they are; IOC rule saves record the new stamps themselves. An outside
+//! change made while the app rewrites the same project is not noticed.
+
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::mpsc::{self, RecvTimeoutError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::Result;
+use notify::{RecursiveMode, Watcher};
+use parking_lot::Mutex;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, Wry};
+use uuid::Uuid;
+
+use crate::{
+    alerts,
+    encryption::is_sealed,
+    ioc::calculate_ioc_applied_records,
+    project_io::read_project_row_count,
+    state::AppState,
+    storage::{clear_ioc_flag_cache, clear_searchable_cache, count_flagged, COLUMN_METRICS_FILE},
+};
+
+pub const PROJECT_MODIFIED_EVENT: &str = "project-modified";
+/// Quiet time after the last change to a watched file before it is
+/// handled, so a file being copied in is handled once.
+const SETTLE_DELAY: Duration = Duration::from_secs(1);
+/// How often the watcher checks whether the data root moved.
+const IDLE_WAIT: Duration = Duration::from_secs(5);
+const WATCHED_FILES: &[&str] = &["data.parquet", "iocs.json"];
+
+/// Modification time and size of a watched file; `None` while it is missing.
+type FileStamp = Option<(SystemTime, u64)>;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ProjectModifiedEvent {
+    pub project_id: Uuid,
+    pub files: Vec<String>,
+}
+
+struct Recorded {
+    stamps: Vec<FileStamp>,
+    /// `ProjectLocks::rewrite_count` when the stamps were taken.
+    rewrites: u64,
+}
+
+/// The watched files of each project as the app last wrote or checked them.
+#[derive(Default)]
+pub struct WatchedFiles {
+    inner: Mutex<HashMap<Uuid, Recorded>>,
+}
+
+impl WatchedFiles {
+    /// Takes the project's files as they are now; called after the app
+    /// writes them outside a rewrite lock.
+    pub fn record(&self, state: &AppState, id: &Uuid) {
+        let project_dir = state.projects.project_dir(id);
+        self.inner.lock().insert(
+            *id,
+            Recorded {
+                stamps: file_stamps(&project_dir),
+                rewrites: state.locks.rewrite_count(id),
+            },
+        );
+    }
+
+    /// The watched files changed since they were recorded, recording them
+    /// anew. A project seen for the first time or rewritten by the app since
+    /// reports no change.
+    pub(crate) fn changes(&self, state: &AppState, id: &Uuid) -> Vec<String> {
+        let current = file_stamps(&state.projects.project_dir(id));
+        let rewrites = state.locks.rewrite_count(id);
+        let previous = self.inner.lock().insert(
+            *id,
+            Recorded {
+                stamps: current.clone(),
+                rewrites,
+            },
+        );
+        match previous {
+            Some(previous) if previous.rewrites == rewrites => {
+                changed_files(&previous.stamps, &current)
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    fn forget(&self, id: &Uuid) {
+        self.inner.lock().remove(id);
+    }
+}
+
+fn file_stamps(project_dir: &Path) -> Vec<FileStamp> {
+    WATCHED_FILES
+        .iter()
+        .map(|name| {
+            let metadata = fs::metadata(project_dir.join(name)).ok()?;
+            Some((metadata.modified().ok()?, metadata.len()))
+        })
+        .collect()
+}
+
+fn changed_files(previous: &[FileStamp], current: &[FileStamp]) -> Vec<String> {
+    WATCHED_FILES
+        .iter()
+        .zip(previous.iter().zip(current))
+        .filter(|(_, (before, after))| before != after)
+        .map(|(name, _)| name.to_string())
+        .collect()
+}
+
+/// The project a changed path belongs to, when it is one of its watched files.
+fn watched_project(path: &Path) -> Option<Uuid> {
+    let name = path.file_name()?.to_str()?;
+    if !WATCHED_FILES.contains(&name) {
+        return None;
+    }
+    path.parent()?.file_name()?.to_str()?.parse().ok()
+}
+
+/// Drops everything derived from a project's files and recounts it. Also
+/// safe to run after the app's own writes; the caches are rebuilt lazily.
+pub fn refresh_modified_project(state: &AppState, project_id: &Uuid) -> Result<()> {
+    let project_dir = state.projects.project_dir(project_id);
+    clear_searchable_cache(&project_dir)?;
+    clear_ioc_flag_cache(&project_dir)?;
+    let metrics_path = project_dir.join(COLUMN_METRICS_FILE);
+    if metrics_path.exists() {
+        fs::remove_file(&metrics_path)?;
+    }
+    let total_records = read_project_row_count(&project_dir.join("data.parquet"))?;
+    let flagged_records = count_flagged(&project_dir.join("flags.json"))?;
+    let (policy, unsearched) = state
+        .projects
+        .find(project_id)
+        .map(|meta| (meta.ioc_policy, meta.unsearched_columns().to_vec()))
+        .unwrap_or_default();
+    let ioc_applied_records = calculate_ioc_applied_records(&project_dir, policy, &unsearched)?;
+    state.projects.update_counters(
+        project_id,
+        total_records,
+        flagged_records,
+        ioc_applied_records,
+    )?;
+    alerts::check_project(state, project_id);
+    Ok(())
+}
+
+/// Refreshes a project whose files changed outside the app. Returns `false`
+/// while the project is being rewritten, so it is tried again later.
+fn handle_change(app: &AppHandle<Wry>, state: &AppState, project_id: &Uuid) -> bool {
+    if state.projects.find(project_id).is_none() {
+        state.watched.forget(project_id);
+        return true;
+    }
+    let Ok(_lock) = state.locks.read(project_id) else {
+        return false;
+    };
+    // Sealing replaces the watched files; that is no outside change.
+    if is_sealed(&state.projects.project_dir(project_id)) {
+        state.watched.record(state, project_id);
+        return true;
+    }
+    let files = state.watched.changes(state, project_id);
+    if files.is_empty() {
+        return true;
+    }
+    if let Err(err) = refresh_modified_project(state, project_id) {
+        tracing::warn!("failed to refresh project {}: {:?}", project_id, err);
+    }
+    let event = ProjectModifiedEvent {
+        project_id: *project_id,
+        files,
+    };
+    if let Err(err) = app.emit_all(PROJECT_MODIFIED_EVENT, event) {
+        tracing::warn!("failed to emit modification event: {:?}", err);
+    }
+    true
+}
+
+/// Starts the thread that watches the projects folder for external changes.
+pub fn spawn(app: AppHandle<Wry>) {
+    thread::spawn(move || {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = match notify::recommended_watcher(sender) {
+            Ok(watcher) => watcher,
+            Err(err) => {
+                tracing::warn!("failed to start the project file watcher: {:?}", err);
+                return;
+            }
+        };
+        let state = app.state::<AppState>();
+        for meta in state.projects.all() {
+            state.watched.record(&state, &meta.id);
+        }
+        let mut watched_root: Option<PathBuf> = None;
+        let mut pending: HashSet<Uuid> = HashSet::new();
+        let mut settle_at: Option<Instant> = None;
+        loop {
+            // `set_data_root` moves the projects folder.
+            let root = state.projects.root_dir().join("projects");
+            if watched_root.as_ref() != Some(&root) {
+                if let Some(previous) = watched_root.take() {
+                    let _ = watcher.unwatch(&previous);
+                }
+                match watcher.watch(&root, RecursiveMode::Recursive) {
+                    Ok(()) => watched_root = Some(root),
+                    Err(err) => tracing::warn!("failed to watch {:?}: {:?}", root, err),
+                }
+            }
+
+            let wait =
+                settle_at.map_or(IDLE_WAIT, |at| at.saturating_duration_since(Instant::now()));
+            match events.recv_timeout(wait) {
+                Ok(Ok(event)) => {
+                    for project_id in event.paths.iter().filter_map(|path| watched_project(path)) {
+                        pending.insert(project_id);
+                        settle_at = Some(Instant::now() + SETTLE_DELAY);
+                    }
+                }
+                Ok(Err(err)) => tracing::warn!("project file watcher error: {:?}", err),
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+            if settle_at.is_some_and(|at| at <= Instant::now()) {
+                pending.retain(|project_id| !handle_change(&app, &state, project_id));
+                settle_at = (!pending.is_empty()).then(|| Instant::now() + SETTLE_DELAY);
+            }
+        }
+    });
+}
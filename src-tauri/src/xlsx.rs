@@ -0,0 +1,495 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Duration, NaiveDate};
+use polars::prelude::{DataFrame, Series};
+use serde_json::Value;
+
+use crate::value_utils::json_values_to_series;
+use crate::xml_scan::{attr, unescape, XmlEvent, XmlEvents};
+use crate::zip_reader::ZipArchive;
+
+const DEFAULT_WORKBOOK_PATH: &str = "xl/workbook.xml";
+
+struct SheetRef {
+    name: String,
+    part: String,
+}
+
+struct Workbook {
+    archive: ZipArchive,
+    sheets: Vec<SheetRef>,
+    date1904: bool,
+    shared_strings: Vec<String>,
+    date_styles: Vec<bool>,
+}
+
+/// Resolves a relationship target relative to the part that declared it.
+fn resolve_part(base_dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = base_dir.split('/').filter(|p| !p.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            other => parts.push(other),
+        }
+    }
+    parts.join("/")
+}
+
+fn part_dir(part: &str) -> &str {
+    part.rsplit_once('/').map(|(dir, _)| dir).unwrap_or("")
+}
+
+fn rels_path(part: &str) -> String {
+    let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+    if dir.is_empty() {
+        format!("_rels/{}.rels", file)
+    } else {
+        format!("{}/_rels/{}.rels", dir, file)
+    }
+}
+
+fn read_relationships(
+    archive: &ZipArchive,
+    part: &str,
+) -> Result<HashMap<String, (String, String)>> {
+    let xml = archive.read_to_string(&rels_path(part))?;
+    let mut targets = HashMap::new();
+    for event in XmlEvents::new(&xml) {
+        if let XmlEvent::Start {
+            name: "Relationship",
+            attrs,
+            ..
+        } = event
+        {
+            if let (Some(id), Some(target)) = (attr(attrs, "Id"), attr(attrs, "Target")) {
+                let kind = attr(attrs, "Type").unwrap_or_default().into_owned();
+                targets.insert(id.into_owned(), (target.into_owned(), kind));
+            }
+        }
+    }
+    Ok(targets)
+}
+
+fn workbook_part(archive: &ZipArchive) -> String {
+    read_relationships(archive, "")
+        .ok()
+        .and_then(|rels| {
+            rels.into_values()
+                .find(|(_, kind)| kind.ends_with("/officeDocument"))
+                .map(|(target, _)| resolve_part("", &target))
+        })
+        .unwrap_or_else(|| DEFAULT_WORKBOOK_PATH.to_string())
+}
+
+/// Shared strings table; phonetic (`rPh`) runs are excluded from the text.
+fn read_shared_strings(archive: &ZipArchive, part: &str) -> Result<Vec<String>> {
+    let xml = archive.read_to_string(part)?;
+    let mut strings = Vec::new();
+    let mut current: Option<String> = None;
+    let mut in_text = false;
+    let mut phonetic_depth = 0usize;
+    for event in XmlEvents::new(&xml) {
+        match event {
+            XmlEvent::Start {
+                name: "si", empty, ..
+            } => {
+                if empty {
+                    strings.push(String::new());
+                } else {
+                    current = Some(String::new());
+                }
+            }
+            XmlEvent::End { name: "si" } => strings.push(current.take().unwrap_or_default()),
+            XmlEvent::Start {
+                name: "rPh",
+                empty: false,
+                ..
+            } => phonetic_depth += 1,
+            XmlEvent::End { name: "rPh" } => phonetic_depth = phonetic_depth.saturating_sub(1),
+            XmlEvent::Start {
+                name: "t", empty, ..
+            } => in_text = !empty,
+            XmlEvent::End { name: "t" } => in_text = false,
+            XmlEvent::Text(text) if in_text && phonetic_depth == 0 => {
+                if let Some(buffer) = current.as_mut() {
+                    buffer.push_str(&unescape(text));
+                }
+            }
+            XmlEvent::CData(text) if in_text && phonetic_depth == 0 => {
+                if let Some(buffer) = current.as_mut() {
+                    buffer.push_str(text);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(strings)
+}
+
+fn is_builtin_date_format(id: u32) -> bool {
+    matches!(id, 14..=22 | 27..=36 | 45..=47 | 50..=58)
+}
+
+/// Custom number formats are dates when they contain date/time tokens
+/// outside quoted literals, escapes, and bracketed colour/locale sections.
+fn is_date_format_code(code: &str) -> bool {
+    if code.eq_ignore_ascii_case("general") {
+        return false;
+    }
+    let mut chars = code.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '"' => {
+                for inner in chars.by_ref() {
+                    if inner == '"' {
+                        break;
+                    }
+                }
+            }
+            '\\' => {
+                chars.next();
+            }
+            '[' => {
+                let bracket: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                // Elapsed-time sections such as `[h]` are still time formats.
+                if matches!(
+                    bracket.to_ascii_lowercase().as_str(),
+                    "h" | "hh" | "m" | "mm" | "s" | "ss"
+                ) {
+                    return true;
+                }
+            }
+            'd' | 'D' | 'm' | 'M' | 'y' | 'Y' | 'h' | 'H' | 's' | 'S' => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// One flag per `cellXfs` entry telling whether that style renders a date.
+fn read_date_styles(archive: &ZipArchive, part: &str) -> Result<Vec<bool>> {
+    let xml = archive.read_to_string(part)?;
+    let mut custom_formats: HashMap<u32, bool> = HashMap::new();
+    let mut styles = Vec::new();
+    let mut in_cell_xfs = false;
+    for event in XmlEvents::new(&xml) {
+        match event {
+            XmlEvent::Start {
+                name: "numFmt",
+                attrs,
+                ..
+            } => {
+                if let (Some(id), Some(code)) = (attr(attrs, "numFmtId"), attr(attrs, "formatCode"))
+                {
+                    if let Ok(id) = id.parse() {
+                        custom_formats.insert(id, is_date_format_code(&code));
+                    }
+                }
+            }
+            XmlEvent::Start {
+                name: "cellXfs",
+                empty,
+                ..
+            } => in_cell_xfs = !empty,
+            XmlEvent::End { name: "cellXfs" } => in_cell_xfs = false,
+            XmlEvent::Start {
+                name: "xf", attrs, ..
+            } if in_cell_xfs => {
+                let id: u32 = attr(attrs, "numFmtId")
+                    .and_then(|id| id.parse().ok())
+                    .unwrap_or(0);
+                let is_date = custom_formats
+                    .get(&id)
+                    .copied()
+                    .unwrap_or_else(|| is_builtin_date_format(id));
+                styles.push(is_date);
+            }
+            _ => {}
+        }
+    }
+    Ok(styles)
+}
+
+impl Workbook {
+    fn open(path: &Path) -> Result<Self> {
+        let archive = ZipArchive::open(path)?;
+        let workbook = workbook_part(&archive);
+        let xml = archive
+            .read_to_string(&workbook)
+            .context("workbook part is missing; is this an .xlsx file?")?;
+        let rels = read_relationships(&archive, &workbook).unwrap_or_default();
+        let base_dir = part_dir(&workbook);
+
+        let mut sheets = Vec::new();
+        let mut date1904 = false;
+        for event in XmlEvents::new(&xml) {
+            match event {
+                XmlEvent::Start {
+                    name: "workbookPr",
+                    attrs,
+                    ..
+                } => {
+                    date1904 = matches!(attr(attrs, "date1904").as_deref(), Some("1" | "true"));
+                }
+                XmlEvent::Start {
+                    name: "sheet",
+                    attrs,
+                    ..
+                } => {
+                    let Some(name) = attr(attrs, "name") else {
+                        continue;
+                    };
+                    // Chart sheets and macro sheets have no worksheet part.
+                    let Some((target, _)) = attr(attrs, "id").and_then(|id| rels.get(id.as_ref()))
+                    else {
+                        continue;
+                    };
+                    sheets.push(SheetRef {
+                        name: name.into_owned(),
+                        part: resolve_part(base_dir, target),
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        let part_of = |suffix: &str| {
+            rels.values()
+                .find(|(_, kind)| kind.ends_with(suffix))
+                .map(|(target, _)| resolve_part(base_dir, target))
+        };
+        let shared_strings = match part_of("/sharedStrings") {
+            Some(part) => read_shared_strings(&archive, &part)?,
+            None => Vec::new(),
+        };
+        let date_styles = match part_of("/styles") {
+            Some(part) => read_date_styles(&archive, &part)?,
+            None => Vec::new(),
+        };
+        Ok(Self {
+            archive,
+            sheets,
+            date1904,
+            shared_strings,
+            date_styles,
+        })
+    }
+
+    fn serial_to_datetime(&self, serial: f64) -> Option<String> {
+        let epoch = if self.date1904 {
+            NaiveDate::from_ymd_opt(1904, 1, 1)?
+        } else {
+            // Day 60 is the fictitious 1900-02-29; 1899-12-30 as day zero absorbs it.
+            NaiveDate::from_ymd_opt(1899, 12, 30)?
+        };
+        let millis = (serial * 86_400_000.0).round() as i64;
+        let timestamp = epoch.and_hms_opt(0, 0, 0)? + Duration::milliseconds(millis);
+        Some(if millis % 86_400_000 == 0 {
+            timestamp.format("%Y-%m-%d").to_string()
+        } else {
+            timestamp.format("%Y-%m-%d %H:%M:%S").to_string()
+        })
+    }
+
+    fn cell_value(&self, cell_type: &str, style: Option<usize>, raw: &str) -> Option<Value> {
+        match cell_type {
+            "s" => raw
+                .trim()
+                .parse::<usize>()
+                .ok()
+                .and_then(|index| self.shared_strings.get(index))
+                .map(|text| Value::String(text.clone())),
+            "inlineStr" | "str" | "e" | "d" => Some(Value::String(raw.to_string())),
+            "b" => Some(Value::Bool(raw.trim() == "1")),
+            _ => {
+                let text = raw.trim();
+                let number: f64 = text.parse().ok()?;
+                let is_date = style
+                    .and_then(|index| self.date_styles.get(index))
+                    .copied()
+                    .unwrap_or(false);
+                if is_date {
+                    return self.serial_to_datetime(number).map(Value::String);
+                }
+                if !text.contains(['.', 'e', 'E']) {
+                    if let Ok(integer) = text.parse::<i64>() {
+                        return Some(Value::from(integer));
+                    }
+                }
+                serde_json::Number::from_f64(number).map(Value::Number)
+            }
+        }
+    }
+
+    /// Reads a worksheet into sparse rows of `(column index, value)`.
+    fn read_rows(&self, part: &str) -> Result<Vec<Vec<(usize, Value)>>> {
+        let xml = self.archive.read_to_string(part)?;
+        let mut rows: Vec<Vec<(usize, Value)>> = Vec::new();
+        let mut current_row: Vec<(usize, Value)> = Vec::new();
+        let mut next_column = 0usize;
+        let mut cell: Option<(usize, String, Option<usize>)> = None;
+        let mut cell_text = String::new();
+        let mut in_value = false;
+        for event in XmlEvents::new(&xml) {
+            match event {
+                XmlEvent::Start {
+                    name: "row", empty, ..
+                } => {
+                    current_row = Vec::new();
+                    next_column = 0;
+                    if empty {
+                        rows.push(Vec::new());
+                    }
+                }
+                XmlEvent::End { name: "row" } => rows.push(std::mem::take(&mut current_row)),
+                XmlEvent::Start {
+                    name: "c",
+                    attrs,
+                    empty,
+                } => {
+                    let column = attr(attrs, "r")
+                        .and_then(|reference| column_index(&reference))
+                        .unwrap_or(next_column);
+                    next_column = column + 1;
+                    if !empty {
+                        let cell_type = attr(attrs, "t").unwrap_or_default().into_owned();
+                        let style = attr(attrs, "s").and_then(|s| s.parse().ok());
+                        cell = Some((column, cell_type, style));
+                        cell_text.clear();
+                    }
+                }
+                XmlEvent::End { name: "c" } => {
+                    if let Some((column, cell_type, style)) = cell.take() {
+                        if let Some(value) = self.cell_value(&cell_type, style, &cell_text) {
+                            current_row.push((column, value));
+                        }
+                    }
+                }
+                // Inline strings use `<is><t>`, everything else `<v>`; formulas (`<f>`) are ignored.
+                XmlEvent::Start {
+                    name: "v" | "t",
+                    empty,
+                    ..
+                } if cell.is_some() => {
+                    in_value = !empty;
+                }
+                XmlEvent::End { name: "v" | "t" } => in_value = false,
+                XmlEvent::Text(text) if in_value => cell_text.push_str(&unescape(text)),
+                XmlEvent::CData(text) if in_value => cell_text.push_str(text),
+                _ => {}
+            }
+        }
+        Ok(rows)
+    }
+}
+
+/// Converts the letters of an `A1` reference into a zero-based column index.
+fn column_index(reference: &str) -> Option<usize> {
+    let letters: Vec<u8> = reference
+        .bytes()
+        .take_while(u8::is_ascii_alphabetic)
+        .collect();
+    if letters.is_empty() {
+        return None;
+    }
+    letters
+        .iter()
+        .try_fold(0usize, |acc, letter| {
+            acc.checked_mul(26)?
+                .checked_add(usize::from(letter.to_ascii_uppercase() - b'A') + 1)
+        })
+        .map(|index| index - 1)
+}
+
+fn header_names(header: &[(usize, Value)], width: usize) -> Vec<String> {
+    let mut names = vec![String::new(); width];
+    for (column, value) in header {
+        names[*column] = match value {
+            Value::String(text) => text.trim().to_string(),
+            other => other.to_string(),
+        };
+    }
+    let mut seen = HashSet::new();
+    names
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| {
+            let base = if name.is_empty() {
+                format!("column_{}", index + 1)
+            } else {
+                name
+            };
+            let mut candidate = base.clone();
+            let mut suffix = 2;
+            while !seen.insert(candidate.to_lowercase()) {
+                candidate = format!("{}_{}", base, suffix);
+                suffix += 1;
+            }
+            candidate
+        })
+        .collect()
+}
+
+/// Lists the worksheet names of an `.xlsx` workbook in tab order.
+pub fn list_sheets(path: &Path) -> Result<Vec<String>> {
+    let workbook = Workbook::open(path)?;
+    Ok(workbook
+        .sheets
+        .into_iter()
+        .map(|sheet| sheet.name)
+        .collect())
+}
+
+/// Reads one worksheet (the first when `sheet` is `None`) using its first
+/// row as headers. Cells styled as dates become `YYYY-MM-DD[ HH:MM:SS]` text.
+pub fn read_xlsx_dataframe(path: &Path, sheet: Option<&str>) -> Result<DataFrame> {
+    let workbook = Workbook::open(path)?;
+    let sheet_ref = match sheet {
+        Some(name) => workbook
+            .sheets
+            .iter()
+            .find(|candidate| candidate.name == name)
+            .ok_or_else(|| anyhow!("worksheet {:?} not found", name))?,
+        None => workbook
+            .sheets
+            .first()
+            .ok_or_else(|| anyhow!("workbook has no worksheets"))?,
+    };
+    let mut rows = workbook
+        .read_rows(&sheet_ref.part)
+        .with_context(|| format!("failed to read worksheet {:?}", sheet_ref.name))?
+        .into_iter()
+        .skip_while(|row| row.is_empty());
+    let header = rows
+        .next()
+        .ok_or_else(|| anyhow!("worksheet {:?} is empty", sheet_ref.name))?;
+    let data: Vec<Vec<(usize, Value)>> = rows.filter(|row| !row.is_empty()).collect();
+    let width = header
+        .iter()
+        .chain(data.iter().flatten())
+        .map(|(column, _)| column + 1)
+        .max()
+        .unwrap_or(0);
+    let names = header_names(&header, width);
+
+    let mut columns: Vec<Vec<Option<Value>>> = vec![vec![None; data.len()]; width];
+    for (row_index, row) in data.into_iter().enumerate() {
+        for (column, value) in row {
+            columns[column][row_index] = Some(value);
+        }
+    }
+    let series: Vec<Series> = names
+        .iter()
+        .zip(columns)
+        .map(|(name, cells)| json_values_to_series(name, cells))
+        .collect();
+    DataFrame::new(series).context("failed to assemble worksheet columns")
+}
@@ -0,0 +1,192 @@
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use polars::prelude::DataFrame;
+use serde_json::Value;
+
+use crate::value_utils::anyvalue_to_json;
+use crate::zip_writer::ZipWriter;
+
+const SHEET_NAME: &str = "trivium";
+/// Rows per worksheet, the header included.
+pub const XLSX_MAX_ROWS: usize = 1_048_576;
+const XLSX_MAX_COLUMNS: usize = 16_384;
+/// Longest text Excel keeps in one cell.
+const XLSX_MAX_CELL_CHARS: usize = 32_767;
+
+/// Highlight applied to a whole data row.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RowHighlight {
+    None,
+    Suspicious,
+    Critical,
+}
+
+/// Indices into `cellXfs` of `STYLES_XML`.
+const STYLE_HEADER: u32 = 1;
+const STYLE_SUSPICIOUS: u32 = 2;
+const STYLE_CRITICAL: u32 = 3;
+
+const CONTENT_TYPES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/><Override PartName="/xl/styles.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.styles+xml"/></Types>"#;
+
+const ROOT_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+
+const WORKBOOK_RELS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/><Relationship Id="rId2" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/styles" Target="styles.xml"/></Relationships>"#;
+
+/// Default, bold header, amber fill for suspicious rows and red fill for
+/// critical rows.
+const STYLES_XML: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<styleSheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><fonts count="2"><font><sz val="11"/><name val="Calibri"/></font><font><b/><sz val="11"/><name val="Calibri"/></font></fonts><fills count="4"><fill><patternFill patternType="none"/></fill><fill><patternFill patternType="gray125"/></fill><fill><patternFill patternType="solid"><fgColor rgb="FFFFE699"/><bgColor indexed="64"/></patternFill></fill><fill><patternFill patternType="solid"><fgColor rgb="FFF4A6A6"/><bgColor indexed="64"/></patternFill></fill></fills><borders count="1"><border><left/><right/><top/><bottom/><diagonal/></border></borders><cellStyleXfs count="1"><xf numFmtId="0" fontId="0" fillId="0" borderId="0"/></cellStyleXfs><cellXfs count="4"><xf numFmtId="0" fontId="0" fillId="0" borderId="0" xfId="0"/><xf numFmtId="0" fontId="1" fillId="0" borderId="0" xfId="0" applyFont="1"/><xf numFmtId="0" fontId="0" fillId="2" borderId="0" xfId="0" applyFill="1"/><xf numFmtId="0" fontId="0" fillId="3" borderId="0" xfId="0" applyFill="1"/></cellXfs><cellStyles count="1"><cellStyle name="Normal" xfId="0" builtinId="0"/></cellStyles></styleSheet>"#;
+
+/// Workbook with one sheet; the hidden `_FilterDatabase` name is what Excel
+/// itself records for the header autofilter.
+fn workbook_xml(sheet_name: &str, filter_range: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="{}" sheetId="1" r:id="rId1"/></sheets><definedNames><definedName name="_xlnm._FilterDatabase" localSheetId="0" hidden="1">'{}'!{}</definedName></definedNames></workbook>"#,
+        escape_xml(sheet_name),
+        escape_xml(&sheet_name.replace('\'', "''")),
+        filter_range
+    )
+}
+
+/// Converts a zero-based column index into its `A1` letters.
+fn column_letters(mut index: usize) -> String {
+    let mut letters = Vec::new();
+    loop {
+        letters.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    letters.reverse();
+    String::from_utf8(letters).unwrap_or_default()
+}
+
+/// Escapes text for XML, dropping control characters XML 1.0 cannot carry.
+fn escape_xml(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\t' | '\n' | '\r' => escaped.push(ch),
+            ch if (ch as u32) < 0x20 || ch == '\u{fffe}' || ch == '\u{ffff}' => {}
+            ch => escaped.push(ch),
+        }
+    }
+    escaped
+}
+
+fn write_text_cell(out: &mut impl Write, reference: &str, style: u32, text: &str) -> Result<()> {
+    let text: String = text.chars().take(XLSX_MAX_CELL_CHARS).collect();
+    write!(
+        out,
+        r#"<c r="{}" s="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+        reference,
+        style,
+        escape_xml(&text)
+    )?;
+    Ok(())
+}
+
+fn write_cell(out: &mut impl Write, reference: &str, style: u32, value: &Value) -> Result<()> {
+    match value {
+        Value::Null => {
+            if style != 0 {
+                write!(out, r#"<c r="{}" s="{}"/>"#, reference, style)?;
+            }
+        }
+        Value::Bool(flag) => write!(
+            out,
+            r#"<c r="{}" s="{}" t="b"><v>{}</v></c>"#,
+            reference,
+            style,
+            u8::from(*flag)
+        )?,
+        // Excel keeps 15 significant digits; larger integers stay exact as text.
+        Value::Number(number) if number.as_f64().is_some_and(|n| n.abs() < 1e15) => write!(
+            out,
+            r#"<c r="{}" s="{}"><v>{}</v></c>"#,
+            reference, style, number
+        )?,
+        Value::String(text) => write_text_cell(out, reference, style, text)?,
+        other => write_text_cell(out, reference, style, &other.to_string())?,
+    }
+    Ok(())
+}
+
+/// Writes `df` as a single-sheet workbook with a bold, frozen and filterable
+/// header row; `highlights` holds one entry per data row.
+pub fn write_xlsx(path: &Path, df: &DataFrame, highlights: &[RowHighlight]) -> Result<()> {
+    if df.height() >= XLSX_MAX_ROWS {
+        return Err(anyhow!(
+            "Excel sheets hold at most {} rows; filter the data or export CSV instead",
+            XLSX_MAX_ROWS - 1
+        ));
+    }
+    if df.width() > XLSX_MAX_COLUMNS {
+        return Err(anyhow!(
+            "Excel sheets hold at most {} columns",
+            XLSX_MAX_COLUMNS
+        ));
+    }
+    let letters: Vec<String> = (0..df.width().max(1)).map(column_letters).collect();
+    let last_column = &letters[letters.len() - 1];
+    let last_cell = format!("{}{}", last_column, df.height() + 1);
+    let filter_range = format!("$A$1:${}${}", last_column, df.height() + 1);
+
+    let mut zip = ZipWriter::create(path)?;
+    zip.add_file("[Content_Types].xml", CONTENT_TYPES_XML.as_bytes())?;
+    zip.add_file("_rels/.rels", ROOT_RELS_XML.as_bytes())?;
+    zip.add_file(
+        "xl/workbook.xml",
+        workbook_xml(SHEET_NAME, &filter_range).as_bytes(),
+    )?;
+    zip.add_file("xl/_rels/workbook.xml.rels", WORKBOOK_RELS_XML.as_bytes())?;
+    zip.add_file("xl/styles.xml", STYLES_XML.as_bytes())?;
+    zip.add_streamed("xl/worksheets/sheet1.xml", |entry| {
+        let out = &mut BufWriter::new(entry);
+        write!(
+            out,
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?>
+<worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><dimension ref="A1:{}"/><sheetViews><sheetView workbookViewId="0"><pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/></sheetView></sheetViews><sheetData>"#,
+            last_cell
+        )?;
+        write!(out, r#"<row r="1">"#)?;
+        for (index, series) in df.get_columns().iter().enumerate() {
+            write_text_cell(out, &format!("{}1", letters[index]), STYLE_HEADER, series.name())?;
+        }
+        write!(out, "</row>")?;
+
+        for row in 0..df.height() {
+            let number = row + 2;
+            let style = match highlights.get(row).copied().unwrap_or(RowHighlight::None) {
+                RowHighlight::None => 0,
+                RowHighlight::Suspicious => STYLE_SUSPICIOUS,
+                RowHighlight::Critical => STYLE_CRITICAL,
+            };
+            write!(out, r#"<row r="{}">"#, number)?;
+            for (index, series) in df.get_columns().iter().enumerate() {
+                let value = anyvalue_to_json(&series.get(row)?);
+                write_cell(out, &format!("{}{}", letters[index], number), style, &value)?;
+            }
+            write!(out, "</row>")?;
+        }
+        write!(
+            out,
+            r#"</sheetData><autoFilter ref="A1:{}"/></worksheet>"#,
+            last_cell
+        )?;
+        out.flush()?;
+        Ok(())
+    })?;
+    zip.finish()
+}
@@ -0,0 +1,183 @@
+use std::borrow::Cow;
+
+/// A single markup event from [`XmlEvents`]. Element names have any
+/// namespace prefix stripped (`x:row` → `row`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum XmlEvent<'a> {
+    Start {
+        name: &'a str,
+        attrs: &'a str,
+        empty: bool,
+    },
+    End {
+        name: &'a str,
+    },
+    /// Raw character data; pass it through [`unescape`] before use.
+    Text(&'a str),
+    /// Contents of a CDATA section, already literal.
+    CData(&'a str),
+}
+
+/// Forward-only scanner over well-formed XML. It does no validation and
+/// skips declarations, processing instructions, comments, and doctypes;
+/// that is all the structured exports we import need.
+pub struct XmlEvents<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> XmlEvents<'a> {
+    pub fn new(src: &'a str) -> Self {
+        Self { src, pos: 0 }
+    }
+}
+
+fn local_name(name: &str) -> &str {
+    name.rsplit_once(':')
+        .map(|(_, local)| local)
+        .unwrap_or(name)
+}
+
+impl<'a> Iterator for XmlEvents<'a> {
+    type Item = XmlEvent<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let rest = &self.src[self.pos..];
+            if rest.is_empty() {
+                return None;
+            }
+            if !rest.starts_with('<') {
+                let end = rest.find('<').unwrap_or(rest.len());
+                self.pos += end;
+                return Some(XmlEvent::Text(&rest[..end]));
+            }
+            if let Some(body) = rest.strip_prefix("<![CDATA[") {
+                let end = body.find("]]>").unwrap_or(body.len());
+                self.pos += 9 + (end + 3).min(body.len());
+                return Some(XmlEvent::CData(&body[..end]));
+            }
+            let skip_until = if rest.starts_with("<!--") {
+                Some("-->")
+            } else if rest.starts_with("<?") {
+                Some("?>")
+            } else if rest.starts_with("<!") {
+                Some(">")
+            } else {
+                None
+            };
+            if let Some(terminator) = skip_until {
+                self.pos += rest
+                    .find(terminator)
+                    .map(|end| end + terminator.len())
+                    .unwrap_or(rest.len());
+                continue;
+            }
+
+            let end = find_tag_end(rest)?;
+            self.pos += end + 1;
+            let inner = &rest[1..end];
+            if let Some(name) = inner.strip_prefix('/') {
+                return Some(XmlEvent::End {
+                    name: local_name(name.trim()),
+                });
+            }
+            let (inner, empty) = match inner.strip_suffix('/') {
+                Some(stripped) => (stripped, true),
+                None => (inner, false),
+            };
+            let name_end = inner
+                .find(|c: char| c.is_ascii_whitespace())
+                .unwrap_or(inner.len());
+            return Some(XmlEvent::Start {
+                name: local_name(&inner[..name_end]),
+                attrs: &inner[name_end..],
+                empty,
+            });
+        }
+    }
+}
+
+/// Finds the `>` closing a tag, ignoring any inside quoted attribute values.
+fn find_tag_end(tag: &str) -> Option<usize> {
+    let mut quote: Option<u8> = None;
+    for (index, byte) in tag.bytes().enumerate() {
+        match (quote, byte) {
+            (Some(open), b) if b == open => quote = None,
+            (Some(_), _) => {}
+            (None, b'"' | b'\'') => quote = Some(byte),
+            (None, b'>') => return Some(index),
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Returns the unescaped value of attribute `key` (matched on its local name).
+pub fn attr<'a>(attrs: &'a str, key: &str) -> Option<Cow<'a, str>> {
+    attributes(attrs)
+        .find(|(name, _)| *name == key)
+        .map(|(_, value)| value)
+}
+
+/// Iterates over all attributes as (local name, unescaped value) pairs.
+pub fn attributes(attrs: &str) -> impl Iterator<Item = (&str, Cow<'_, str>)> {
+    let mut rest = attrs;
+    std::iter::from_fn(move || {
+        rest = rest.trim_start();
+        let eq = rest.find('=')?;
+        let name = rest[..eq].trim();
+        let value_part = rest[eq + 1..].trim_start();
+        let quote = value_part.chars().next()?;
+        if quote != '"' && quote != '\'' {
+            return None;
+        }
+        let close = value_part[1..].find(quote)? + 1;
+        rest = &value_part[close + 1..];
+        Some((local_name(name), unescape(&value_part[1..close])))
+    })
+}
+
+/// Decodes the predefined entities and numeric character references.
+pub fn unescape(text: &str) -> Cow<'_, str> {
+    if !text.contains('&') {
+        return Cow::Borrowed(text);
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        out.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        let Some(end) = after.find(';').filter(|&end| end <= 10) else {
+            out.push('&');
+            rest = after;
+            continue;
+        };
+        let entity = &after[..end];
+        let decoded = match entity {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            _ => entity
+                .strip_prefix("#x")
+                .or_else(|| entity.strip_prefix("#X"))
+                .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                .and_then(char::from_u32),
+        };
+        match decoded {
+            Some(ch) => {
+                out.push(ch);
+                rest = &after[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = after;
+            }
+        }
+    }
+    out.push_str(rest);
+    Cow::Owned(out)
+}
@@ -0,0 +1,204 @@
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use flate2::read::DeflateDecoder;
+
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATED: u16 = 8;
+/// End-of-central-directory record plus the longest possible archive comment.
+const EOCD_SEARCH_WINDOW: u64 = 22 + u16::MAX as u64;
+
+fn u16_at(buf: &[u8], offset: usize) -> Result<u16> {
+    buf.get(offset..offset + 2)
+        .map(|bytes| u16::from_le_bytes([bytes[0], bytes[1]]))
+        .ok_or_else(|| anyhow!("truncated ZIP record"))
+}
+
+fn u32_at(buf: &[u8], offset: usize) -> Result<u32> {
+    buf.get(offset..offset + 4)
+        .map(|bytes| u32::from_le_bytes(bytes.try_into().unwrap_or_default()))
+        .ok_or_else(|| anyhow!("truncated ZIP record"))
+}
+
+fn u64_at(buf: &[u8], offset: usize) -> Result<u64> {
+    buf.get(offset..offset + 8)
+        .map(|bytes| u64::from_le_bytes(bytes.try_into().unwrap_or_default()))
+        .ok_or_else(|| anyhow!("truncated ZIP record"))
+}
+
+fn read_exact_at(file: &mut File, offset: u64, len: usize) -> Result<Vec<u8>> {
+    file.seek(SeekFrom::Start(offset))?;
+    let mut buf = vec![0u8; len];
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[derive(Debug, Clone)]
+pub struct ZipEntry {
+    pub name: String,
+    method: u16,
+    encrypted: bool,
+    compressed_size: u64,
+    local_header_offset: u64,
+}
+
+impl ZipEntry {
+    pub fn is_dir(&self) -> bool {
+        self.name.ends_with('/')
+    }
+}
+
+/// Minimal read-only ZIP reader: enough of the format (including ZIP64) to
+/// stream stored or deflated members out of import archives and workbooks.
+pub struct ZipArchive {
+    path: PathBuf,
+    entries: Vec<ZipEntry>,
+}
+
+impl ZipArchive {
+    pub fn open(path: &Path) -> Result<Self> {
+        let mut file =
+            File::open(path).with_context(|| format!("failed to open archive {:?}", path))?;
+        let entries = read_central_directory(&mut file)
+            .with_context(|| format!("{:?} is not a readable ZIP archive", path))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries,
+        })
+    }
+
+    pub fn entries(&self) -> &[ZipEntry] {
+        &self.entries
+    }
+
+    pub fn find(&self, name: &str) -> Option<&ZipEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+
+    /// Opens a member for streaming decompression.
+    pub fn open_entry(&self, entry: &ZipEntry) -> Result<Box<dyn Read + Send>> {
+        if entry.encrypted {
+            return Err(anyhow!("{} is encrypted", entry.name));
+        }
+        let mut file = File::open(&self.path)?;
+        let header = read_exact_at(&mut file, entry.local_header_offset, 30)?;
+        if u32_at(&header, 0)? != LOCAL_HEADER_SIGNATURE {
+            return Err(anyhow!("corrupt local header for {}", entry.name));
+        }
+        let data_offset = entry.local_header_offset
+            + 30
+            + u64::from(u16_at(&header, 26)?)
+            + u64::from(u16_at(&header, 28)?);
+        file.seek(SeekFrom::Start(data_offset))?;
+        let data = file.take(entry.compressed_size);
+        match entry.method {
+            METHOD_STORED => Ok(Box::new(data)),
+            METHOD_DEFLATED => Ok(Box::new(DeflateDecoder::new(data))),
+            other => Err(anyhow!(
+                "{} uses unsupported compression method {}",
+                entry.name,
+                other
+            )),
+        }
+    }
+
+    pub fn read_to_string(&self, name: &str) -> Result<String> {
+        let entry = self
+            .find(name)
+            .ok_or_else(|| anyhow!("archive member {} is missing", name))?;
+        let mut text = String::new();
+        self.open_entry(entry)?
+            .read_to_string(&mut text)
+            .with_context(|| format!("failed to read archive member {}", name))?;
+        Ok(text)
+    }
+}
+
+fn read_central_directory(file: &mut File) -> Result<Vec<ZipEntry>> {
+    let file_len = file.metadata()?.len();
+    let window = EOCD_SEARCH_WINDOW.min(file_len);
+    let tail = read_exact_at(file, file_len - window, window as usize)?;
+    let eocd = (0..tail.len().saturating_sub(21))
+        .rev()
+        .find(|&pos| u32_at(&tail, pos).ok() == Some(EOCD_SIGNATURE))
+        .ok_or_else(|| anyhow!("end of central directory not found"))?;
+
+    let mut entry_count = u64::from(u16_at(&tail, eocd + 10)?);
+    let mut directory_size = u64::from(u32_at(&tail, eocd + 12)?);
+    let mut directory_offset = u64::from(u32_at(&tail, eocd + 16)?);
+    if directory_offset == u64::from(u32::MAX) || entry_count == u64::from(u16::MAX) {
+        let locator = eocd
+            .checked_sub(20)
+            .filter(|&pos| u32_at(&tail, pos).ok() == Some(ZIP64_LOCATOR_SIGNATURE))
+            .ok_or_else(|| anyhow!("ZIP64 locator not found"))?;
+        let record = read_exact_at(file, u64_at(&tail, locator + 8)?, 56)?;
+        if u32_at(&record, 0)? != ZIP64_EOCD_SIGNATURE {
+            return Err(anyhow!("corrupt ZIP64 end of central directory"));
+        }
+        entry_count = u64_at(&record, 32)?;
+        directory_size = u64_at(&record, 40)?;
+        directory_offset = u64_at(&record, 48)?;
+    }
+
+    let directory = read_exact_at(file, directory_offset, directory_size as usize)?;
+    let mut entries = Vec::with_capacity(entry_count as usize);
+    let mut pos = 0usize;
+    for _ in 0..entry_count {
+        if u32_at(&directory, pos)? != CENTRAL_HEADER_SIGNATURE {
+            return Err(anyhow!("corrupt central directory"));
+        }
+        let flags = u16_at(&directory, pos + 8)?;
+        let method = u16_at(&directory, pos + 10)?;
+        let mut compressed_size = u64::from(u32_at(&directory, pos + 20)?);
+        let mut uncompressed_size = u64::from(u32_at(&directory, pos + 24)?);
+        let name_len = usize::from(u16_at(&directory, pos + 28)?);
+        let extra_len = usize::from(u16_at(&directory, pos + 30)?);
+        let comment_len = usize::from(u16_at(&directory, pos + 32)?);
+        let mut local_header_offset = u64::from(u32_at(&directory, pos + 42)?);
+        let name_start = pos + 46;
+        let name = directory
+            .get(name_start..name_start + name_len)
+            .map(|bytes| String::from_utf8_lossy(bytes).replace('\\', "/"))
+            .ok_or_else(|| anyhow!("truncated central directory"))?;
+
+        // ZIP64 extra field: only the values saturated in the fixed header are present, in order.
+        let mut extra = name_start + name_len;
+        let extra_end = extra + extra_len;
+        while extra + 4 <= extra_end {
+            let id = u16_at(&directory, extra)?;
+            let size = usize::from(u16_at(&directory, extra + 2)?);
+            if id == ZIP64_EXTRA_ID {
+                let mut field = extra + 4;
+                for value in [
+                    &mut uncompressed_size,
+                    &mut compressed_size,
+                    &mut local_header_offset,
+                ] {
+                    if *value == u64::from(u32::MAX) {
+                        *value = u64_at(&directory, field)?;
+                        field += 8;
+                    }
+                }
+            }
+            extra += 4 + size;
+        }
+
+        entries.push(ZipEntry {
+            name,
+            method,
+            encrypted: flags & 1 != 0,
+            compressed_size,
+            local_header_offset,
+        });
+        pos = extra_end + comment_len;
+    }
+    Ok(entries)
+}
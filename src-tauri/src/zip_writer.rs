@@ -0,0 +1,209 @@
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Local, Timelike};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const METHOD_DEFLATED: u16 = 8;
+const VERSION_NEEDED: u16 = 20;
+/// Bit 11: member names are UTF-8.
+const FLAG_UTF8: u16 = 0x0800;
+
+struct CentralEntry {
+    name: String,
+    crc: u32,
+    compressed_size: u32,
+    uncompressed_size: u32,
+    local_header_offset: u32,
+}
+
+/// Counts the compressed bytes written through to the archive file.
+struct CountingWriter<'a> {
+    inner: &'a mut BufWriter<File>,
+    written: u64,
+}
+
+impl Write for CountingWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Deflates one member while tracking its CRC and uncompressed size.
+pub struct ZipEntryWriter<'a> {
+    encoder: DeflateEncoder<CountingWriter<'a>>,
+    crc: Crc,
+    uncompressed: u64,
+}
+
+impl Write for ZipEntryWriter<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.encoder.write(buf)?;
+        self.crc.update(&buf[..written]);
+        self.uncompressed += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.encoder.flush()
+    }
+}
+
+/// Minimal ZIP writer for generated documents such as workbooks: deflated
+/// members, no ZIP64, so each member and the archive stay below 4 GiB.
+pub struct ZipWriter {
+    file: BufWriter<File>,
+    entries: Vec<CentralEntry>,
+    modified: (u16, u16),
+}
+
+impl ZipWriter {
+    pub fn create(path: &Path) -> Result<Self> {
+        let file =
+            File::create(path).with_context(|| format!("failed to create archive {:?}", path))?;
+        Ok(Self {
+            file: BufWriter::new(file),
+            entries: Vec::new(),
+            modified: dos_timestamp(),
+        })
+    }
+
+    pub fn add_file(&mut self, name: &str, contents: &[u8]) -> Result<()> {
+        self.add_streamed(name, |writer| Ok(writer.write_all(contents)?))
+    }
+
+    /// Adds a member whose contents are produced by `write`; the local header
+    /// is patched with the CRC and sizes once the data is written.
+    pub fn add_streamed(
+        &mut self,
+        name: &str,
+        write: impl FnOnce(&mut ZipEntryWriter<'_>) -> Result<()>,
+    ) -> Result<()> {
+        let header_offset = self.file.stream_position()?;
+        self.write_local_header(name, 0, 0, 0)?;
+
+        let (crc, uncompressed, compressed) = {
+            let mut entry = ZipEntryWriter {
+                encoder: DeflateEncoder::new(
+                    CountingWriter {
+                        inner: &mut self.file,
+                        written: 0,
+                    },
+                    Compression::default(),
+                ),
+                crc: Crc::new(),
+                uncompressed: 0,
+            };
+            write(&mut entry)?;
+            let ZipEntryWriter {
+                encoder,
+                crc,
+                uncompressed,
+            } = entry;
+            (crc.sum(), uncompressed, encoder.finish()?.written)
+        };
+
+        let too_large = || anyhow!("archive member {} exceeds 4 GiB", name);
+        let entry = CentralEntry {
+            name: name.to_string(),
+            crc,
+            compressed_size: u32::try_from(compressed).map_err(|_| too_large())?,
+            uncompressed_size: u32::try_from(uncompressed).map_err(|_| too_large())?,
+            local_header_offset: u32::try_from(header_offset).map_err(|_| too_large())?,
+        };
+        let end = self.file.stream_position()?;
+        self.file.seek(SeekFrom::Start(header_offset))?;
+        self.write_local_header(
+            name,
+            entry.crc,
+            entry.compressed_size,
+            entry.uncompressed_size,
+        )?;
+        self.file.seek(SeekFrom::Start(end))?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    fn write_local_header(
+        &mut self,
+        name: &str,
+        crc: u32,
+        compressed_size: u32,
+        uncompressed_size: u32,
+    ) -> Result<()> {
+        let mut header = Vec::with_capacity(30 + name.len());
+        header.extend_from_slice(&LOCAL_HEADER_SIGNATURE.to_le_bytes());
+        header.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+        header.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+        header.extend_from_slice(&METHOD_DEFLATED.to_le_bytes());
+        header.extend_from_slice(&self.modified.0.to_le_bytes());
+        header.extend_from_slice(&self.modified.1.to_le_bytes());
+        header.extend_from_slice(&crc.to_le_bytes());
+        header.extend_from_slice(&compressed_size.to_le_bytes());
+        header.extend_from_slice(&uncompressed_size.to_le_bytes());
+        header.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        header.extend_from_slice(&0u16.to_le_bytes());
+        header.extend_from_slice(name.as_bytes());
+        self.file.write_all(&header)?;
+        Ok(())
+    }
+
+    /// Writes the central directory and closes the archive.
+    pub fn finish(mut self) -> Result<()> {
+        let directory_offset = self.file.stream_position()?;
+        let mut directory = Vec::new();
+        for entry in &self.entries {
+            directory.extend_from_slice(&CENTRAL_HEADER_SIGNATURE.to_le_bytes());
+            directory.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+            directory.extend_from_slice(&VERSION_NEEDED.to_le_bytes());
+            directory.extend_from_slice(&FLAG_UTF8.to_le_bytes());
+            directory.extend_from_slice(&METHOD_DEFLATED.to_le_bytes());
+            directory.extend_from_slice(&self.modified.0.to_le_bytes());
+            directory.extend_from_slice(&self.modified.1.to_le_bytes());
+            directory.extend_from_slice(&entry.crc.to_le_bytes());
+            directory.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            directory.extend_from_slice(&entry.uncompressed_size.to_le_bytes());
+            directory.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            // Extra field, comment, disk number, internal and external attributes.
+            directory.extend_from_slice(&[0u8; 12]);
+            directory.extend_from_slice(&entry.local_header_offset.to_le_bytes());
+            directory.extend_from_slice(entry.name.as_bytes());
+        }
+        let count =
+            u16::try_from(self.entries.len()).map_err(|_| anyhow!("too many archive members"))?;
+        let too_large = || anyhow!("archive exceeds 4 GiB");
+        let directory_offset = u32::try_from(directory_offset).map_err(|_| too_large())?;
+        let directory_len = u32::try_from(directory.len()).map_err(|_| too_large())?;
+
+        directory.extend_from_slice(&EOCD_SIGNATURE.to_le_bytes());
+        directory.extend_from_slice(&[0u8; 4]);
+        directory.extend_from_slice(&count.to_le_bytes());
+        directory.extend_from_slice(&count.to_le_bytes());
+        directory.extend_from_slice(&directory_len.to_le_bytes());
+        directory.extend_from_slice(&directory_offset.to_le_bytes());
+        directory.extend_from_slice(&0u16.to_le_bytes());
+        self.file.write_all(&directory)?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Current local time as MS-DOS `(time, date)` fields.
+fn dos_timestamp() -> (u16, u16) {
+    let now = Local::now();
+    let time = (now.hour() << 11) | (now.minute() << 5) | (now.second() / 2);
+    let date = ((now.year().clamp(1980, 2107) as u32 - 1980) << 9) | (now.month() << 5) | now.day();
+    (time as u16, date as u16)
+}
@@ -1,14 +1,272 @@
-import { invoke } from "@tauri-apps/api/tauri";
+import { invoke as tauriInvoke } from "@tauri-apps/api/tauri";
 import type {
+  AlertSettings,
+  AppErrorCode,
+  AppErrorPayload,
+  Bookmark,
+  RowComments,
+  SavedView,
+  ScriptColumn,
+  CaseNotes,
+  CheckProjectResponse,
+  CompactProjectResponse,
+  ColumnDtype,
+  DataRoot,
+  EntityKind,
+  FlagBreakdown,
+  CreateProjectResponse,
+  ImportPresetInfo,
+  ListEntitiesResponse,
+  PivotEntityResponse,
+  IocConflictPolicy,
   IocEntry,
+  IocKind,
+  LaunchRequest,
   LoadProjectResponse,
+  LogLevel,
+  PerformanceSettings,
+  PerformanceStats,
+  PerformanceSettingsResponse,
   ProjectRow,
+  ProjectStatus,
+  ProjectSort,
   ProjectSummary,
+  QueryTimings,
+  RecentLogs,
+  ReimportProjectResponse,
+  SessionState,
+  SourceIntegrity,
+  WorkspaceStorageInfo,
+  AnnotationsBackup,
 } from "./types";
 
 export interface CreateProjectArgs {
   description?: string | null;
   path?: string;
+  paths?: string[];
+  sheet?: string | null;
+  table?: string | null;
+  dtypes?: Record<string, ColumnDtype>;
+  parseDates?: boolean;
+  hasHeaders?: boolean;
+  lenient?: boolean;
+  /** Columns to read as timestamps and store in UTC. */
+  timestampColumns?: string[];
+  /** Zone of timestamps without an offset, e.g. `Asia/Tokyo` or `+09:00`. */
+  sourceTimezone?: string | null;
+  /** Trim surrounding whitespace from every text value. */
+  trimWhitespace?: boolean;
+  /** Columns whose text is lowercased. */
+  lowercaseColumns?: string[];
+  /** Columns whose numbers lose their thousands separators (`1,234` → `1234`). */
+  stripThousandsColumns?: string[];
+  /** Columns computed by Rhai scripts for every imported row. */
+  scriptColumns?: ScriptColumn[];
+  /** Tool preset id, `auto` to detect one, or null for none. */
+  preset?: string | null;
+}
+
+export interface ComputedColumnArgs {
+  projectId: string;
+  /** Expression such as `end - start` or `upper(host) || ':' || user`. */
+  expression: string;
+  /** Name of the new column. */
+  column: string;
+}
+
+export interface DecodedColumnArgs {
+  projectId: string;
+  /** Column holding the encoded values. */
+  source: string;
+  encoding: 'base64' | 'hex';
+  /** Name of the new column; `<source>_decoded` when omitted. */
+  column?: string | null;
+}
+
+export interface AssignSessionsArgs {
+  projectId: string;
+  /** Columns identifying who or what a session belongs to, e.g. user and host. */
+  keyColumns: string[];
+  /** The project's time column when omitted. */
+  timeColumn?: string | null;
+  /** Longest idle time within a session; 30 minutes when omitted. */
+  gapSeconds?: number | null;
+  /** Name of the new column; `session_id` when omitted. */
+  column?: string | null;
+}
+
+export interface AssignSessionsResponse {
+  project: ProjectSummary;
+  column: string;
+  sessions: number;
+  assigned_rows: number;
+}
+
+export interface RareValuesArgs {
+  projectId: string;
+  /** Columns whose values are counted, e.g. process and parent. */
+  columns: string[];
+  /** Values seen in at most this many rows are rare; 1 when omitted. */
+  maxCount?: number | null;
+  /** Rare rows listed; `total_rare_rows` counts all of them. */
+  limit?: number | null;
+  /** Also store every row's score in a new column. */
+  addColumn?: boolean;
+  /** Name of the new column; `rarity` when omitted. */
+  column?: string | null;
+}
+
+export interface RareValue {
+  value: string;
+  count: number;
+}
+
+export interface ColumnRarity {
+  column: string;
+  distinct_values: number;
+  rare_values: number;
+  /** Rarest first. */
+  values: RareValue[];
+}
+
+export interface RareRow {
+  row_index: number;
+  /** Rows sharing this row's rarest value. */
+  score: number;
+}
+
+export interface RareValuesResponse {
+  project: ProjectSummary;
+  max_count: number;
+  columns: ColumnRarity[];
+  total_rare_rows: number;
+  rows: RareRow[];
+  column: string | null;
+}
+
+export interface DuplicatesArgs {
+  projectId: string;
+  /** Columns rows must agree on; every data column except the source file when empty. */
+  columns?: string[];
+  /** Groups listed; the counts cover all of them. */
+  limit?: number | null;
+}
+
+export interface DuplicateGroup {
+  /** Ascending; deduplication keeps the first. */
+  rows: number[];
+  count: number;
+}
+
+export interface FindDuplicatesResponse {
+  columns: string[];
+  total_groups: number;
+  /** Rows deduplication would remove. */
+  duplicate_rows: number;
+  /** Largest groups first. */
+  groups: DuplicateGroup[];
+}
+
+export interface DedupeResponse {
+  project: ProjectSummary;
+  removed_rows: number;
+  carried_annotations: number;
+  dropped_annotations: number;
+}
+
+export interface JsonKey {
+  /** Key path, nested keys joined with `.`. */
+  key: string;
+  count: number;
+}
+
+export interface JsonKeysResponse {
+  source: string;
+  /** Rows whose value parsed as a JSON object. */
+  object_rows: number;
+  total_keys: number;
+  /** Most common first. */
+  keys: JsonKey[];
+}
+
+export interface ExpandJsonArgs {
+  projectId: string;
+  /** Column holding JSON objects. */
+  source: string;
+  keys: string[];
+  /** Put in front of each key to name its column; `<source>.` when omitted. */
+  prefix?: string | null;
+}
+
+export interface ExpandJsonResponse {
+  project: ProjectSummary;
+  columns: string[];
+}
+
+export interface ListEntitiesArgs {
+  projectId: string;
+  /** Every kind when empty or omitted. */
+  kinds?: EntityKind[];
+  /** Case-insensitive substring of the value. */
+  search?: string | null;
+  limit?: number | null;
+  /** Rescan the data even if the stored index is current. */
+  rebuild?: boolean;
+}
+
+export interface PivotEntityArgs {
+  projectId: string;
+  /** An extracted entity or any literal value, matched as a whole token. */
+  value: string;
+  /** Every column when empty or omitted. */
+  columns?: string[];
+  offset?: number | null;
+  limit?: number | null;
+}
+
+export interface ScriptColumnArgs {
+  projectId: string;
+  /** Rhai source run once per row; the row's values are in the map `row`. */
+  script: string;
+  /** Columns the script reads; every column when omitted. */
+  inputColumns?: string[] | null;
+  column: string;
+}
+
+export interface SaveCaseNotesArgs {
+  projectId: string;
+  /** Replaces the notes document. */
+  text?: string | null;
+  /** Appended as a timestamped entry. */
+  entry?: string | null;
+}
+
+export interface UpdateBookmarksArgs {
+  projectId: string;
+  rows: number[];
+  /** Replaces the label of rows already bookmarked; ignored when removing. */
+  label?: string | null;
+}
+
+export interface SaveViewArgs {
+  projectId: string;
+  /** Replaces the view of the same name, if there is one. */
+  name: string;
+  search?: string | null;
+  wholeWord?: boolean;
+  flagFilter?: string | null;
+  sortKey?: string | null;
+  sortDirection?: "asc" | "desc" | null;
+  visibleColumns?: string[];
+  pageSize?: number | null;
+  isDefault?: boolean;
+}
+
+export interface PerformanceSettingsArgs {
+  maxThreads?: number | null;
+  importThreads?: number | null;
+  importChunkSize?: number | null;
+  exactColumnWidths?: boolean;
 }
 
 export interface UpdateFlagArgs {
@@ -18,14 +276,77 @@ export interface UpdateFlagArgs {
   memo: string | null;
 }
 
+export interface AddRowCommentArgs {
+  projectId: string;
+  rowIndex: number;
+  text: string;
+  author?: string | null;
+}
+
 export interface HiddenColumnsArgs {
   projectId: string;
   hiddenColumns: string[];
+  /** Leave hidden columns out of search and IOC matching; unchanged when omitted. */
+  searchVisibleOnly?: boolean | null;
+}
+
+export type ExportFormat = "csv" | "jsonl" | "xlsx" | "parquet" | "timesketch";
+export type CsvEncoding = "utf-8" | "utf-8-bom" | "utf-16le" | "shift_jis";
+/** `mask` blanks a column's values; `hash` replaces them with a keyed hash. */
+export type RedactionMode = "mask" | "hash";
+
+/** Application-wide defaults kept in `settings.json`. */
+export interface AppSettings {
+  page_size: number;
+  /** chrono format timestamps are shown in, e.g. `%Y/%m/%d %H:%M:%S`. */
+  date_format: string | null;
+  export_encoding: CsvEncoding;
+  data_root: DataRoot;
+}
+
+export interface SettingsArgs {
+  pageSize?: number | null;
+  dateFormat?: string | null;
+  exportEncoding?: CsvEncoding;
+  /** Changing it moves all projects; `null` uses the app data directory. */
+  dataRoot?: string | null;
 }
 
 export interface ExportProjectArgs {
   projectId: string;
   destination?: string;
+  format?: ExportFormat;
+  search?: string;
+  wholeWord?: boolean;
+  flagFilter?: FlagFilterValue;
+  sortKey?: string | null;
+  sortDirection?: string | null;
+  columns?: string[];
+  excludeHidden?: boolean;
+  /** Timesketch mapping; the backend falls back to the project's timeline column. */
+  timestampColumn?: string | null;
+  messageColumns?: string[];
+  timestampDesc?: string | null;
+  /** Numbered part files of at most this many rows or bytes. */
+  splitRows?: number | null;
+  splitBytes?: number | null;
+  /** CSV only; the encoding chosen in the settings when omitted. */
+  encoding?: CsvEncoding;
+  delimiter?: string | null;
+  /** Columns to redact; hashes use the project's key unless one is given. */
+  redact?: Record<string, RedactionMode>;
+  redactionKey?: string | null;
+}
+
+export interface ExportReportArgs {
+  redact?: Record<string, RedactionMode>;
+  redactionKey?: string | null;
+}
+
+export interface ExportProjectResponse {
+  rows_written: number;
+  rows_skipped: number;
+  files: string[];
 }
 
 export interface SaveIocsArgs {
@@ -36,6 +357,45 @@ export interface SaveIocsArgs {
 export interface ImportIocsArgs {
   projectId: string;
   path: string;
+  /** Rule field to CSV header, for headers that are not recognized. */
+  mapping?: Record<string, string>;
+}
+
+export interface ImportSigmaArgs {
+  projectId: string;
+  paths: string[];
+  mapping?: Record<string, string[]>;
+}
+
+export interface CollapsedIoc {
+  query: string;
+  tag: string;
+  removed: number;
+}
+
+export interface IocRuleIssue {
+  index: number;
+  tag: string;
+  message: string;
+}
+
+export interface IocSaveReport {
+  invalid_syntax: IocRuleIssue[];
+  unknown_columns: IocRuleIssue[];
+  zero_hits: IocRuleIssue[];
+}
+
+export interface SaveIocsResponse {
+  entries: IocEntry[];
+  collapsed: CollapsedIoc[];
+  report: IocSaveReport;
+}
+
+export interface ImportSigmaResponse {
+  entries: IocEntry[];
+  imported: number;
+  skipped: { source: string; reason: string }[];
+  collapsed: CollapsedIoc[];
 }
 
 export interface ExportIocsArgs {
@@ -43,6 +403,20 @@ export interface ExportIocsArgs {
   destination: string;
 }
 
+export interface ExportIocsStixResponse {
+  exported: number;
+  skipped: { tag: string; query: string; reason: string }[];
+}
+
+export interface SaveSessionArgs {
+  projectId: string | null;
+  search?: string;
+  flagFilter?: string;
+  sortKey?: string | null;
+  sortDirection?: string | null;
+  offset?: number;
+}
+
 export type FlagFilterValue =
   | "all"
   | "none"
@@ -54,68 +428,429 @@ export type FlagFilterValue =
 export interface QueryProjectRowsArgs {
   projectId: string;
   search?: string;
+  /** Search terms match whole words only, so `cmd` skips `cmdlet`. */
+  wholeWord?: boolean;
   flagFilter?: FlagFilterValue;
   columns?: string[];
   offset?: number;
   limit?: number;
   sortKey?: string | null;
   sortDirection?: "asc" | "desc";
+  /** Order by the project's time column instead of `sortKey`. */
+  timeline?: boolean;
+  /** Inclusive time window bounds for the timeline view. */
+  timeFrom?: string | null;
+  timeTo?: string | null;
+  /** Return only bookmarked rows. */
+  bookmarkedOnly?: boolean;
+  /** Return how long each phase of the query took. */
+  debug?: boolean;
 }
 
 export interface QueryProjectRowsResponse {
   rows: ProjectRow[];
   total_flagged: number;
+  /** Filtered rows per effective flag, across every page. */
+  flag_counts: FlagBreakdown;
   total_rows: number;
   total_filtered_rows: number;
   offset: number;
+  time_column?: string | null;
+  timings?: QueryTimings | null;
+}
+
+export interface ExplainSearchArgs {
+  projectId: string;
+  query: string;
+  /** Explains an IOC rule of this kind instead of a search. */
+  kind?: IocKind | null;
+  /** The view's columns for a search, the rule's column restriction for an IOC rule. */
+  columns?: string[];
+  wholeWord?: boolean;
+}
+
+export interface ExplainedTerm {
+  /** Lowercased and refanged, as it is matched. */
+  text: string;
+  quoted: boolean;
+  prefix: string | null;
+  scope: "row_text" | "column" | "list_elements" | "column_values";
+  columns: string[];
+  /** Served from the stored row text instead of the column data. */
+  uses_cache: boolean;
+}
+
+export interface SearchExplanation {
+  kind: IocKind;
+  /** The query with implicit ANDs and grouping spelled out. */
+  expression: string;
+  tokens: string[];
+  rpn: string[];
+  terms: ExplainedTerm[];
+  whole_word: boolean;
+  searchable_text_cached: boolean;
+  warnings: string[];
+}
+
+export interface ColumnComparison {
+  column: string;
+  equal: boolean;
+  value_a: unknown;
+  value_b: unknown;
+}
+
+export interface CompareRowsResponse {
+  row_a: number;
+  row_b: number;
+  different_columns: number;
+  columns: ColumnComparison[];
+}
+
+export interface MemoTermFrequency {
+  term: string;
+  count: number;
+  memos: number;
+}
+
+/** A failed command, carrying the backend's error code and context. */
+export class BackendError extends Error {
+  readonly code: AppErrorCode;
+  readonly context: AppErrorPayload["context"];
+
+  constructor(payload: AppErrorPayload) {
+    super(payload.message);
+    this.name = "BackendError";
+    this.code = payload.code;
+    this.context = payload.context ?? {};
+  }
+}
+
+function isAppErrorPayload(value: unknown): value is AppErrorPayload {
+  return (
+    typeof value === "object" &&
+    value !== null &&
+    typeof (value as AppErrorPayload).code === "string" &&
+    typeof (value as AppErrorPayload).message === "string"
+  );
+}
+
+async function invoke<T>(command: string, args?: Record<string, unknown>): Promise<T> {
+  try {
+    return await tauriInvoke<T>(command, args);
+  } catch (error) {
+    throw isAppErrorPayload(error) ? new BackendError(error) : error;
+  }
 }
 
 export interface Backend {
   readonly isNative: boolean;
-  listProjects(): Promise<ProjectSummary[]>;
-  createProject(args: CreateProjectArgs): Promise<ProjectSummary>;
+  listProjects(sort?: ProjectSort): Promise<ProjectSummary[]>;
+  setProjectPinned(projectId: string, pinned: boolean): Promise<ProjectSummary>;
+  setDisplayTimezone(projectId: string, timezone: string | null): Promise<ProjectSummary>;
+  setTimestampColumn(projectId: string, column: string | null): Promise<ProjectSummary>;
+  setColumnAliases(projectId: string, aliases: Record<string, string>): Promise<ProjectSummary>;
+  setColumnDescription(
+    projectId: string,
+    column: string,
+    description: string | null,
+  ): Promise<ProjectSummary>;
+  createProject(args: CreateProjectArgs): Promise<CreateProjectResponse>;
+  listSheets(path: string): Promise<string[]>;
+  listSqliteTables(path: string): Promise<string[]>;
+  listImportPresets(): Promise<ImportPresetInfo[]>;
+  listImportPresets(): Promise<ImportPresetInfo[]> {
+    return invoke("list_import_presets");
+  }
+
+  reimportProject(projectId: string, path?: string | null): Promise<ReimportProjectResponse>;
   deleteProject(projectId: string): Promise<void>;
-  loadProject(projectId: string): Promise<LoadProjectResponse>;
+  cloneProject(projectId: string, name?: string | null): Promise<ProjectSummary>;
+  verifySourceIntegrity(projectId: string): Promise<SourceIntegrity[]>;
+  getProjectStorageInfo(): Promise<WorkspaceStorageInfo>;
+  getProjectStatus(projectId: string): Promise<ProjectStatus>;
+  checkProject(projectId: string, repair: boolean): Promise<CheckProjectResponse>;
+  compactProject(projectId: string): Promise<CompactProjectResponse>;
+  listAnnotationBackups(projectId: string): Promise<AnnotationsBackup[]>;
+  restoreAnnotationsBackup(projectId: string, backupId: string): Promise<ProjectSummary>;
+  exportProjectBundle(projectId: string, destination: string): Promise<void>;
+  importProjectBundle(path: string): Promise<ProjectSummary>;
+  getCaseNotes(projectId: string): Promise<CaseNotes>;
+  saveCaseNotes(args: SaveCaseNotesArgs): Promise<CaseNotes>;
+  listBookmarks(projectId: string): Promise<Bookmark[]>;
+  addBookmarks(args: UpdateBookmarksArgs): Promise<Bookmark[]>;
+  removeBookmarks(args: UpdateBookmarksArgs): Promise<Bookmark[]>;
+  listViews(projectId: string): Promise<SavedView[]>;
+  saveView(args: SaveViewArgs): Promise<SavedView[]>;
+  deleteView(projectId: string, name: string): Promise<SavedView[]>;
+  /** Clears the default view when `name` is null. */
+  setDefaultView(projectId: string, name: string | null): Promise<SavedView[]>;
+  loadProject(projectId: string, passphrase?: string | null): Promise<LoadProjectResponse>;
+  enableProjectEncryption(projectId: string, passphrase: string): Promise<ProjectSummary>;
+  disableProjectEncryption(projectId: string, passphrase: string): Promise<ProjectSummary>;
+  lockProject(projectId: string, passphrase?: string | null): Promise<ProjectSummary>;
   queryProjectRows(
     args: QueryProjectRowsArgs
   ): Promise<QueryProjectRowsResponse>;
-  saveIocs(args: SaveIocsArgs): Promise<void>;
+  explainSearchQuery(args: ExplainSearchArgs): Promise<SearchExplanation>;
+  saveIocs(args: SaveIocsArgs): Promise<SaveIocsResponse>;
   importIocs(args: ImportIocsArgs): Promise<void>;
   exportIocs(args: ExportIocsArgs): Promise<void>;
+  exportIocsStix(args: ExportIocsArgs): Promise<ExportIocsStixResponse>;
+  importSigmaRules(args: ImportSigmaArgs): Promise<ImportSigmaResponse>;
+  setIocPolicy(projectId: string, policy: IocConflictPolicy): Promise<void>;
+  setIocGroupEnabled(projectId: string, group: string, enabled: boolean): Promise<IocEntry[]>;
+  deleteIocGroup(projectId: string, group: string): Promise<IocEntry[]>;
+  getSigmaMapping(projectId: string): Promise<Record<string, string[]>>;
   updateFlag(args: UpdateFlagArgs): Promise<ProjectRow>;
+  listRowComments(projectId: string, rowIndex: number): Promise<RowComments>;
+  addRowComment(args: AddRowCommentArgs): Promise<RowComments>;
+  /** `index` is the comment's position, oldest first. */
+  deleteRowComment(projectId: string, rowIndex: number, index: number): Promise<RowComments>;
   setHiddenColumns(args: HiddenColumnsArgs): Promise<void>;
-  exportProject(args: ExportProjectArgs): Promise<void>;
+  exportProject(args: ExportProjectArgs): Promise<ExportProjectResponse>;
+  exportReport(
+    projectId: string,
+    destination: string,
+    args?: ExportReportArgs
+  ): Promise<void>;
+  saveSessionState(args: SaveSessionArgs): Promise<void>;
+  getSessionRestore(): Promise<SessionState | null>;
+  getRowPermalink(projectId: string, rowId: number): Promise<string>;
+  compareRows(
+    projectId: string,
+    rowIdA: number,
+    rowIdB: number
+  ): Promise<CompareRowsResponse>;
+  getMemoTermFrequencies(
+    projectId: string,
+    limit?: number
+  ): Promise<MemoTermFrequency[]>;
+  takeLaunchRequests(): Promise<LaunchRequest[]>;
+  getPerformanceSettings(): Promise<PerformanceSettings>;
+  setPerformanceSettings(
+    args: PerformanceSettingsArgs
+  ): Promise<PerformanceSettingsResponse>;
+  getDataRoot(): Promise<DataRoot>;
+  /** Moves all projects; `null` returns them to the app data directory. */
+  setDataRoot(path: string | null): Promise<DataRoot>;
+  getSettings(): Promise<AppSettings>;
+  /** Replaces the defaults; omitted values return to the built-in ones. */
+  setSettings(args: SettingsArgs): Promise<AppSettings>;
+  getRecentLogs(limit?: number): Promise<RecentLogs>;
+  setLogLevel(level: LogLevel): Promise<void>;
+  getPerformanceStats(): Promise<PerformanceStats>;
+  resetPerformanceStats(): Promise<void>;
+  addScriptColumn(args: ScriptColumnArgs): Promise<ProjectSummary>;
+  addDecodedColumn(args: DecodedColumnArgs): Promise<ProjectSummary>;
+  addComputedColumn(args: ComputedColumnArgs): Promise<ProjectSummary>;
+  assignSessions(args: AssignSessionsArgs): Promise<AssignSessionsResponse>;
+  findRareValues(args: RareValuesArgs): Promise<RareValuesResponse>;
+  findDuplicates(args: DuplicatesArgs): Promise<FindDuplicatesResponse>;
+  dedupeProject(args: DuplicatesArgs): Promise<DedupeResponse>;
+  listJsonKeys(projectId: string, source: string, limit?: number): Promise<JsonKeysResponse>;
+  expandJsonColumn(args: ExpandJsonArgs): Promise<ExpandJsonResponse>;
+  setProjectAlerts(projectId: string, alerts: AlertSettings): Promise<ProjectSummary>;
+  /** Posts a sample alert to the saved webhook; fails with the receiver's error status. */
+  sendTestAlert(projectId: string): Promise<void>;
+  listEntities(args: ListEntitiesArgs): Promise<ListEntitiesResponse>;
+  pivotEntity(args: PivotEntityArgs): Promise<PivotEntityResponse>;
 }
 
 class NativeBackend implements Backend {
   readonly isNative = true;
 
-  listProjects(): Promise<ProjectSummary[]> {
-    return invoke("list_projects");
+  listProjects(sort: ProjectSort = "created"): Promise<ProjectSummary[]> {
+    return invoke("list_projects", { payload: { sort } });
   }
 
-  createProject(args: CreateProjectArgs): Promise<ProjectSummary> {
-    if (!args.path) {
+  setProjectPinned(projectId: string, pinned: boolean): Promise<ProjectSummary> {
+    return invoke("set_project_pinned", { payload: { projectId, pinned } });
+  }
+
+  setDisplayTimezone(projectId: string, timezone: string | null): Promise<ProjectSummary> {
+    return invoke("set_display_timezone", { payload: { projectId, timezone } });
+  }
+
+  setTimestampColumn(projectId: string, column: string | null): Promise<ProjectSummary> {
+    return invoke("set_timestamp_column", { payload: { projectId, column } });
+  }
+
+  setColumnAliases(projectId: string, aliases: Record<string, string>): Promise<ProjectSummary> {
+    return invoke("set_column_aliases", { payload: { projectId, aliases } });
+  }
+
+  setColumnDescription(
+    projectId: string,
+    column: string,
+    description: string | null,
+  ): Promise<ProjectSummary> {
+    return invoke("set_column_description", { payload: { projectId, column, description } });
+  }
+
+  createProject(args: CreateProjectArgs): Promise<CreateProjectResponse> {
+    const paths = args.paths ?? [];
+    if (!args.path && paths.length === 0) {
       return Promise.reject(new Error("Path is required to create a project."));
     }
     return invoke("create_project", {
       payload: {
-        path: args.path,
+        path: args.path ?? paths[0],
+        paths,
         description: args.description ?? null,
+        sheet: args.sheet ?? null,
+        table: args.table ?? null,
+        dtypes: args.dtypes ?? {},
+        parseDates: args.parseDates ?? false,
+        hasHeaders: args.hasHeaders ?? true,
+        lenient: args.lenient ?? false,
+        timestampColumns: args.timestampColumns ?? [],
+        sourceTimezone: args.sourceTimezone ?? null,
+        trimWhitespace: args.trimWhitespace ?? false,
+        lowercaseColumns: args.lowercaseColumns ?? [],
+        stripThousandsColumns: args.stripThousandsColumns ?? [],
+        scriptColumns: args.scriptColumns ?? [],
+        preset: args.preset ?? null,
       },
     });
   }
 
+  listSheets(path: string): Promise<string[]> {
+    return invoke("list_sheets", { payload: { path } });
+  }
+
+  listSqliteTables(path: string): Promise<string[]> {
+    return invoke("list_sqlite_tables", { payload: { path } });
+  }
+
+  reimportProject(projectId: string, path?: string | null): Promise<ReimportProjectResponse> {
+    return invoke("reimport_project", { payload: { projectId, path: path ?? null } });
+  }
+
   deleteProject(projectId: string): Promise<void> {
     return invoke("delete_project", { request: { projectId: projectId } });
   }
 
-  loadProject(projectId: string): Promise<LoadProjectResponse> {
+  cloneProject(projectId: string, name?: string | null): Promise<ProjectSummary> {
+    return invoke("clone_project", { payload: { projectId, name: name ?? null } });
+  }
+
+  verifySourceIntegrity(projectId: string): Promise<SourceIntegrity[]> {
+    return invoke("verify_source_integrity", { request: { projectId } });
+  }
+
+  getProjectStorageInfo(): Promise<WorkspaceStorageInfo> {
+    return invoke("get_project_storage_info");
+  }
+
+  getProjectStatus(projectId: string): Promise<ProjectStatus> {
+    return invoke("get_project_status", { request: { projectId } });
+  }
+
+  checkProject(projectId: string, repair: boolean): Promise<CheckProjectResponse> {
+    return invoke("check_project", { payload: { projectId, repair } });
+  }
+
+  compactProject(projectId: string): Promise<CompactProjectResponse> {
+    return invoke("compact_project", { payload: { projectId } });
+  }
+
+  listAnnotationBackups(projectId: string): Promise<AnnotationsBackup[]> {
+    return invoke("list_annotation_backups", { payload: { projectId } });
+  }
+
+  restoreAnnotationsBackup(projectId: string, backupId: string): Promise<ProjectSummary> {
+    return invoke("restore_annotations_backup", { payload: { projectId, backupId } });
+  }
+
+  exportProjectBundle(projectId: string, destination: string): Promise<void> {
+    return invoke("export_project_bundle", { payload: { projectId, destination } });
+  }
+
+  importProjectBundle(path: string): Promise<ProjectSummary> {
+    return invoke("import_project_bundle", { payload: { path } });
+  }
+
+  getCaseNotes(projectId: string): Promise<CaseNotes> {
+    return invoke("get_case_notes", { payload: { projectId } });
+  }
+
+  saveCaseNotes(args: SaveCaseNotesArgs): Promise<CaseNotes> {
+    return invoke("save_case_notes", {
+      payload: {
+        projectId: args.projectId,
+        text: args.text ?? null,
+        entry: args.entry ?? null,
+      },
+    });
+  }
+
+  listBookmarks(projectId: string): Promise<Bookmark[]> {
+    return invoke("list_bookmarks", { payload: { projectId } });
+  }
+
+  addBookmarks(args: UpdateBookmarksArgs): Promise<Bookmark[]> {
+    return invoke("add_bookmarks", {
+      payload: {
+        projectId: args.projectId,
+        rows: args.rows,
+        label: args.label ?? null,
+      },
+    });
+  }
+
+  removeBookmarks(args: UpdateBookmarksArgs): Promise<Bookmark[]> {
+    return invoke("remove_bookmarks", {
+      payload: { projectId: args.projectId, rows: args.rows },
+    });
+  }
+
+  listViews(projectId: string): Promise<SavedView[]> {
+    return invoke("list_views", { payload: { projectId } });
+  }
+
+  saveView(args: SaveViewArgs): Promise<SavedView[]> {
+    return invoke("save_view", {
+      payload: {
+        projectId: args.projectId,
+        name: args.name,
+        search: args.search ?? null,
+        wholeWord: args.wholeWord ?? false,
+        flagFilter: args.flagFilter ?? null,
+        sortKey: args.sortKey ?? null,
+        sortDirection: args.sortDirection ?? null,
+        visibleColumns: args.visibleColumns ?? [],
+        pageSize: args.pageSize ?? null,
+        isDefault: args.isDefault ?? false,
+      },
+    });
+  }
+
+  deleteView(projectId: string, name: string): Promise<SavedView[]> {
+    return invoke("delete_view", { payload: { projectId, name } });
+  }
+
+  setDefaultView(projectId: string, name: string | null): Promise<SavedView[]> {
+    return invoke("set_default_view", { payload: { projectId, name } });
+  }
+
+  loadProject(projectId: string, passphrase?: string | null): Promise<LoadProjectResponse> {
     return invoke("load_project", {
-      request: { projectId: projectId },
+      request: { projectId: projectId, passphrase: passphrase ?? null },
     });
   }
 
+  enableProjectEncryption(projectId: string, passphrase: string): Promise<ProjectSummary> {
+    return invoke("enable_project_encryption", { payload: { projectId, passphrase } });
+  }
+
+  disableProjectEncryption(projectId: string, passphrase: string): Promise<ProjectSummary> {
+    return invoke("disable_project_encryption", { payload: { projectId, passphrase } });
+  }
+
+  lockProject(projectId: string, passphrase?: string | null): Promise<ProjectSummary> {
+    return invoke("lock_project", { payload: { projectId, passphrase: passphrase ?? null } });
+  }
+
   queryProjectRows(
     args: QueryProjectRowsArgs
   ): Promise<QueryProjectRowsResponse> {
@@ -123,17 +858,35 @@ class NativeBackend implements Backend {
       payload: {
         projectId: args.projectId,
         search: args.search ?? null,
+        wholeWord: args.wholeWord ?? false,
         flagFilter: args.flagFilter ?? null,
         visible_columns: args.columns ?? null,
         offset: args.offset ?? null,
         limit: args.limit ?? null,
         sortKey: args.sortKey ?? null,
         sortDirection: args.sortDirection ?? null,
+        timeline: args.timeline ?? false,
+        timeFrom: args.timeFrom ?? null,
+        timeTo: args.timeTo ?? null,
+        bookmarkedOnly: args.bookmarkedOnly ?? false,
+        debug: args.debug ?? false,
+      },
+    });
+  }
+
+  explainSearchQuery(args: ExplainSearchArgs): Promise<SearchExplanation> {
+    return invoke("explain_search_query", {
+      payload: {
+        projectId: args.projectId,
+        query: args.query,
+        kind: args.kind ?? null,
+        columns: args.columns ?? [],
+        wholeWord: args.wholeWord ?? false,
       },
     });
   }
 
-  saveIocs(args: SaveIocsArgs): Promise<void> {
+  saveIocs(args: SaveIocsArgs): Promise<SaveIocsResponse> {
     return invoke("save_iocs", {
       payload: {
         projectId: args.projectId,
@@ -147,9 +900,30 @@ class NativeBackend implements Backend {
       payload: {
         projectId: args.projectId,
         path: args.path,
+        mapping: args.mapping,
       },
     });
   }
+  importSigmaRules(args: ImportSigmaArgs): Promise<ImportSigmaResponse> {
+    return invoke("import_sigma_rules", { payload: args });
+  }
+
+  setIocPolicy(projectId: string, policy: IocConflictPolicy): Promise<void> {
+    return invoke("set_ioc_policy", { payload: { projectId, policy } });
+  }
+
+  setIocGroupEnabled(projectId: string, group: string, enabled: boolean): Promise<IocEntry[]> {
+    return invoke("set_ioc_group_enabled", { payload: { projectId, group, enabled } });
+  }
+
+  deleteIocGroup(projectId: string, group: string): Promise<IocEntry[]> {
+    return invoke("delete_ioc_group", { payload: { projectId, group } });
+  }
+
+  getSigmaMapping(projectId: string): Promise<Record<string, string[]>> {
+    return invoke("get_sigma_mapping", { payload: { projectId } });
+  }
+
 
   exportIocs(args: ExportIocsArgs): Promise<void> {
     return invoke("export_iocs", {
@@ -160,6 +934,15 @@ class NativeBackend implements Backend {
     });
   }
 
+  exportIocsStix(args: ExportIocsArgs): Promise<ExportIocsStixResponse> {
+    return invoke("export_iocs_stix", {
+      payload: {
+        projectId: args.projectId,
+        destination: args.destination,
+      },
+    });
+  }
+
   updateFlag(args: UpdateFlagArgs): Promise<ProjectRow> {
     return invoke("update_flag", {
       payload: {
@@ -171,16 +954,36 @@ class NativeBackend implements Backend {
     });
   }
 
+  listRowComments(projectId: string, rowIndex: number): Promise<RowComments> {
+    return invoke("list_row_comments", { payload: { projectId, rowIndex } });
+  }
+
+  addRowComment(args: AddRowCommentArgs): Promise<RowComments> {
+    return invoke("add_row_comment", {
+      payload: {
+        projectId: args.projectId,
+        rowIndex: args.rowIndex,
+        text: args.text,
+        author: args.author ?? null,
+      },
+    });
+  }
+
+  deleteRowComment(projectId: string, rowIndex: number, index: number): Promise<RowComments> {
+    return invoke("delete_row_comment", { payload: { projectId, rowIndex, index } });
+  }
+
   setHiddenColumns(args: HiddenColumnsArgs): Promise<void> {
     return invoke("set_hidden_columns", {
       payload: {
         projectId: args.projectId,
         hidden_columns: args.hiddenColumns,
+        searchVisibleOnly: args.searchVisibleOnly ?? null,
       },
     });
   }
 
-  exportProject(args: ExportProjectArgs): Promise<void> {
+  exportProject(args: ExportProjectArgs): Promise<ExportProjectResponse> {
     if (!args.destination) {
       return Promise.reject(
         new Error("Destination path is required to export a project.")
@@ -190,6 +993,259 @@ class NativeBackend implements Backend {
       payload: {
         projectId: args.projectId,
         destination: args.destination,
+        format: args.format ?? "csv",
+        search: args.search ?? null,
+        wholeWord: args.wholeWord ?? false,
+        flagFilter: args.flagFilter ?? null,
+        sortKey: args.sortKey ?? null,
+        sortDirection: args.sortDirection ?? null,
+        columns: args.columns ?? null,
+        excludeHidden: args.excludeHidden ?? false,
+        timestampColumn: args.timestampColumn ?? null,
+        messageColumns: args.messageColumns ?? null,
+        timestampDesc: args.timestampDesc ?? null,
+        splitRows: args.splitRows ?? null,
+        splitBytes: args.splitBytes ?? null,
+        encoding: args.encoding ?? null,
+        delimiter: args.delimiter ?? null,
+        redact: args.redact ?? {},
+        redactionKey: args.redactionKey ?? null,
+      },
+    });
+  }
+
+  exportReport(
+    projectId: string,
+    destination: string,
+    args: ExportReportArgs = {}
+  ): Promise<void> {
+    return invoke("export_report", {
+      payload: {
+        projectId,
+        destination,
+        redact: args.redact ?? {},
+        redactionKey: args.redactionKey ?? null,
+      },
+    });
+  }
+
+  saveSessionState(args: SaveSessionArgs): Promise<void> {
+    return invoke("save_session_state", {
+      payload: {
+        projectId: args.projectId,
+        search: args.search ?? null,
+        flagFilter: args.flagFilter ?? null,
+        sortKey: args.sortKey ?? null,
+        sortDirection: args.sortDirection ?? null,
+        offset: args.offset ?? null,
+      },
+    });
+  }
+
+  getSessionRestore(): Promise<SessionState | null> {
+    return invoke("get_session_restore");
+  }
+
+  getRowPermalink(projectId: string, rowId: number): Promise<string> {
+    return invoke("get_row_permalink", {
+      payload: { projectId, rowId },
+    });
+  }
+
+  compareRows(
+    projectId: string,
+    rowIdA: number,
+    rowIdB: number
+  ): Promise<CompareRowsResponse> {
+    return invoke("compare_rows", {
+      payload: { projectId, rowIdA, rowIdB },
+    });
+  }
+
+  getMemoTermFrequencies(
+    projectId: string,
+    limit?: number
+  ): Promise<MemoTermFrequency[]> {
+    return invoke("get_memo_term_frequencies", {
+      payload: { projectId, limit },
+    });
+  }
+
+  takeLaunchRequests(): Promise<LaunchRequest[]> {
+    return invoke("take_launch_requests");
+  }
+
+  getPerformanceSettings(): Promise<PerformanceSettings> {
+    return invoke("get_performance_settings");
+  }
+
+  setPerformanceSettings(
+    args: PerformanceSettingsArgs
+  ): Promise<PerformanceSettingsResponse> {
+    return invoke("set_performance_settings", { payload: args });
+  }
+
+  getDataRoot(): Promise<DataRoot> {
+    return invoke("get_data_root");
+  }
+
+  setDataRoot(path: string | null): Promise<DataRoot> {
+    return invoke("set_data_root", { payload: { path } });
+  }
+
+  getSettings(): Promise<AppSettings> {
+    return invoke("get_settings");
+  }
+
+  setSettings(args: SettingsArgs): Promise<AppSettings> {
+    return invoke("set_settings", {
+      payload: {
+        pageSize: args.pageSize ?? null,
+        dateFormat: args.dateFormat ?? null,
+        exportEncoding: args.exportEncoding ?? "utf-8",
+        dataRoot: args.dataRoot ?? null,
+      },
+    });
+  }
+
+  getRecentLogs(limit?: number): Promise<RecentLogs> {
+    return invoke("get_recent_logs", { payload: { limit: limit ?? null } });
+  }
+
+  setLogLevel(level: LogLevel): Promise<void> {
+    return invoke("set_log_level", { payload: { level } });
+  }
+
+  getPerformanceStats(): Promise<PerformanceStats> {
+    return invoke("get_performance_stats");
+  }
+
+  resetPerformanceStats(): Promise<void> {
+    return invoke("reset_performance_stats");
+  }
+
+  addComputedColumn(args: ComputedColumnArgs): Promise<ProjectSummary> {
+    return invoke("add_computed_column", {
+      payload: {
+        projectId: args.projectId,
+        expression: args.expression,
+        column: args.column,
+      },
+    });
+  }
+
+  addDecodedColumn(args: DecodedColumnArgs): Promise<ProjectSummary> {
+    return invoke("add_decoded_column", {
+      payload: {
+        projectId: args.projectId,
+        source: args.source,
+        encoding: args.encoding,
+        column: args.column ?? null,
+      },
+    });
+  }
+  assignSessions(args: AssignSessionsArgs): Promise<AssignSessionsResponse> {
+    return invoke("assign_sessions", {
+      payload: {
+        projectId: args.projectId,
+        keyColumns: args.keyColumns,
+        timeColumn: args.timeColumn ?? null,
+        gapSeconds: args.gapSeconds ?? null,
+        column: args.column ?? null,
+      },
+    });
+  }
+  findRareValues(args: RareValuesArgs): Promise<RareValuesResponse> {
+    return invoke("find_rare_values", {
+      payload: {
+        projectId: args.projectId,
+        columns: args.columns,
+        maxCount: args.maxCount ?? null,
+        limit: args.limit ?? null,
+        addColumn: args.addColumn ?? false,
+        column: args.column ?? null,
+      },
+    });
+  }
+  findDuplicates(args: DuplicatesArgs): Promise<FindDuplicatesResponse> {
+    return invoke("find_duplicates", {
+      payload: {
+        projectId: args.projectId,
+        columns: args.columns ?? [],
+        limit: args.limit ?? null,
+      },
+    });
+  }
+  dedupeProject(args: DuplicatesArgs): Promise<DedupeResponse> {
+    return invoke("dedupe_project", {
+      payload: {
+        projectId: args.projectId,
+        columns: args.columns ?? [],
+      },
+    });
+  }
+  listJsonKeys(projectId: string, source: string, limit?: number): Promise<JsonKeysResponse> {
+    return invoke("list_json_keys", {
+      payload: { projectId, source, limit: limit ?? null },
+    });
+  }
+  expandJsonColumn(args: ExpandJsonArgs): Promise<ExpandJsonResponse> {
+    return invoke("expand_json_column", {
+      payload: {
+        projectId: args.projectId,
+        source: args.source,
+        keys: args.keys,
+        prefix: args.prefix ?? null,
+      },
+    });
+  }
+
+
+  setProjectAlerts(projectId: string, alerts: AlertSettings): Promise<ProjectSummary> {
+    return invoke("set_project_alerts", {
+      payload: {
+        projectId,
+        webhookUrl: alerts.webhook_url ?? null,
+        notify: alerts.notify,
+      },
+    });
+  }
+
+  sendTestAlert(projectId: string): Promise<void> {
+    return invoke("send_test_alert", { payload: { projectId } });
+  }
+
+  addScriptColumn(args: ScriptColumnArgs): Promise<ProjectSummary> {
+    return invoke("add_script_column", {
+      payload: {
+        projectId: args.projectId,
+        script: args.script,
+        inputColumns: args.inputColumns ?? null,
+        column: args.column,
+      },
+    });
+  }
+
+  listEntities(args: ListEntitiesArgs): Promise<ListEntitiesResponse> {
+    return invoke("list_entities", {
+      payload: {
+        projectId: args.projectId,
+        kinds: args.kinds ?? [],
+        search: args.search ?? null,
+        limit: args.limit ?? null,
+        rebuild: args.rebuild ?? false,
+      },
+    });
+  }
+
+  pivotEntity(args: PivotEntityArgs): Promise<PivotEntityResponse> {
+    return invoke("pivot_entity", {
+      payload: {
+        projectId: args.projectId,
+        value: args.value,
+        columns: args.columns ?? [],
+        offset: args.offset ?? null,
+        limit: args.limit ?? null,
       },
     });
   }
@@ -1,5 +1,5 @@
 import { derived, get, writable } from "svelte/store";
-import type { Backend } from "../../backend";
+import type { Backend, CsvEncoding, RedactionMode } from "../../backend";
 import type {
   FlagSymbol,
   IocEntry,
@@ -70,12 +70,66 @@ export const hiddenColumns = writable(new Set<string>());
 export const columnsOpen = writable(false);
 
 export const search = writable("");
+/** Search terms match whole words only, so `cmd` skips `cmdlet`. */
+export const wholeWord = writable(false);
 export const flagFilter = writable<FlagFilterValue>("all");
 
 export const sortKey = writable<string | null>(null);
 export const sortDirection = writable<"asc" | "desc">("asc");
 
 export const isExporting = writable(false);
+
+/** Cuts exports into numbered part files for size-limited destinations. */
+export type ExportSplit = "none" | "rows-1m" | "rows-100k" | "mb-100" | "mb-25";
+export const EXPORT_SPLIT_OPTIONS: { value: ExportSplit; label: string }[] = [
+  { value: "none", label: "Single file" },
+  { value: "rows-1m", label: "Parts of 1M rows" },
+  { value: "rows-100k", label: "Parts of 100k rows" },
+  { value: "mb-100", label: "Parts of 100 MB" },
+  { value: "mb-25", label: "Parts of 25 MB" },
+];
+export const exportSplit = writable<ExportSplit>("none");
+
+/** Encoding and separator of CSV exports; other formats are always UTF-8. */
+export const CSV_ENCODING_OPTIONS: { value: CsvEncoding; label: string }[] = [
+  { value: "utf-8", label: "UTF-8" },
+  { value: "utf-8-bom", label: "UTF-8 with BOM (Excel)" },
+  { value: "utf-16le", label: "UTF-16LE" },
+  { value: "shift_jis", label: "Shift-JIS" },
+];
+export const CSV_DELIMITER_OPTIONS: { value: string; label: string }[] = [
+  { value: ",", label: "Comma" },
+  { value: ";", label: "Semicolon" },
+  { value: "\t", label: "Tab" },
+  { value: "|", label: "Pipe" },
+];
+export const csvEncoding = writable<CsvEncoding>("utf-8");
+export const csvDelimiter = writable(",");
+
+/** Per-column redaction applied to exports and reports, e.g. user names or IPs. */
+export const REDACTION_OPTIONS: { value: RedactionMode | ""; label: string }[] = [
+  { value: "", label: "Export as is" },
+  { value: "mask", label: "Mask" },
+  { value: "hash", label: "Hash" },
+];
+export const exportRedaction = writable<Record<string, RedactionMode>>({});
+
+export const exportSplitArgs = (
+  split: ExportSplit
+): { splitRows?: number; splitBytes?: number } => {
+  switch (split) {
+    case "rows-1m":
+      return { splitRows: 1_000_000 };
+    case "rows-100k":
+      return { splitRows: 100_000 };
+    case "mb-100":
+      return { splitBytes: 100 * 1024 * 1024 };
+    case "mb-25":
+      return { splitBytes: 25 * 1024 * 1024 };
+    default:
+      return {};
+  }
+};
 export const isUpdatingColumns = writable(false);
 
 export const rowsCache = writable<Map<number, CachedRow>>(new Map()); // Keyed by row_index
@@ -93,6 +147,24 @@ export const expandedCell = writable<{ column: string; value: string } | null>(
 );
 export const memoEditor = writable<{ row: CachedRow } | null>(null);
 export const iocManagerOpen = writable(false);
+export const caseNotesOpen = writable(false);
+
+/**
+ * Where the next project to load should open instead of at the top, such as
+ * the row of a row link. Consumed once the project is shown.
+ */
+export type ViewTarget = {
+  projectId: string;
+  /** Display position scrolled to the top. */
+  offset: number;
+  sortKey: string | null;
+  sortDirection: "asc" | "desc";
+  /** `row_index` of the row to highlight. */
+  highlightRow: number | null;
+};
+
+export const viewTarget = writable<ViewTarget | null>(null);
+export const highlightedRow = writable<number | null>(null);
 
 export const viewportHeight = writable(0);
 export const scrollTop = writable(0);
@@ -186,9 +258,22 @@ export const escapeCsvValue = (value: string): string =>
   /[\",\n\r]/.test(value) ? `"${value.replace(/"/g, '""')}"` : value;
 
 export const buildIocCsv = (entries: IocEntry[]) => {
-  const header = "flag,tag,query";
+  const header =
+    "flag,tag,query,kind,columns,description,reference_url,author,whole_word";
   const rows = entries.map((entry) =>
-    [entry.flag, entry.tag, entry.query].map(escapeCsvValue).join(",")
+    [
+      entry.flag,
+      entry.tag,
+      entry.query,
+      entry.kind ?? "query",
+      (entry.columns ?? []).join(";"),
+      entry.description ?? "",
+      entry.reference_url ?? "",
+      entry.author ?? "",
+      entry.whole_word ? "true" : "",
+    ]
+      .map(escapeCsvValue)
+      .join(",")
   );
   return [header, ...rows].join("\n");
 };
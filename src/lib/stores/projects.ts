@@ -1,9 +1,28 @@
 import { derived, get, writable } from "svelte/store";
-import type { Backend } from "../backend";
-import type { LoadProjectResponse, ProjectSummary } from "../types";
+import {
+  BackendError,
+  type AssignSessionsArgs,
+  type Backend,
+  type ComputedColumnArgs,
+  type DecodedColumnArgs,
+  type DuplicatesArgs,
+  type ExpandJsonArgs,
+  type RareValuesArgs,
+  type ScriptColumnArgs,
+} from "../backend";
+import type {
+  AlertSettings,
+  ColumnDtype,
+  ImportPresetInfo,
+  LoadProjectResponse,
+  ProjectMeta,
+  ProjectSort,
+  ProjectSummary,
+} from "../types";
 
 export interface ProjectsState {
   projects: ProjectSummary[];
+  projectSort: ProjectSort;
   selectedProjectId: string | null;
   projectDetail: LoadProjectResponse | null;
   isLoadingProjects: boolean;
@@ -12,12 +31,60 @@ export interface ProjectsState {
   projectsLoaded: boolean;
   pendingDescription: string;
   pendingFilePath: string | null;
+  /** All selected files when several are merged into one project. */
+  pendingFilePaths: string[];
   pendingFileName: string;
+  pendingSheets: string[];
+  pendingSheet: string | null;
+  pendingTables: string[];
+  pendingTable: string | null;
+  pendingParseDates: boolean;
+  pendingHasHeaders: boolean;
+  pendingLenient: boolean;
+  /** Column type overrides as typed by the user, e.g. `user_id:string`. */
+  pendingDtypes: string;
+  /** Timestamp columns as typed by the user, comma-separated. */
+  pendingTimestampColumns: string;
+  pendingSourceTimezone: string;
+  importPresets: ImportPresetInfo[];
+  /** Preset id, `auto` to detect one from the headers, or `none`. */
+  pendingPreset: string;
+}
+
+const COLUMN_DTYPES: ColumnDtype[] = [
+  "string",
+  "int64",
+  "float64",
+  "boolean",
+  "date",
+  "datetime",
+];
+
+/** Parses `name:type` pairs separated by commas or new lines. */
+export function parseDtypeOverrides(text: string): Record<string, ColumnDtype> {
+  const overrides: Record<string, ColumnDtype> = {};
+  for (const entry of text.split(/[,\n]/)) {
+    if (!entry.trim()) {
+      continue;
+    }
+    const separator = entry.lastIndexOf(":");
+    const name = entry.slice(0, separator).trim();
+    const dtype = entry.slice(separator + 1).trim().toLowerCase() as ColumnDtype;
+    if (separator < 0 || !name || !COLUMN_DTYPES.includes(dtype)) {
+      throw new Error(
+        `Invalid column type "${entry.trim()}"; use name:${COLUMN_DTYPES.join("|")}.`
+      );
+    }
+    overrides[name] = dtype;
+  }
+  return overrides;
 }
 
 interface LoadDetailOptions {
   force?: boolean;
   skipLoadingState?: boolean;
+  /** Unseals an encrypted project. */
+  passphrase?: string;
 }
 
 export function createProjectController(backend: Backend) {
@@ -25,6 +92,7 @@ export function createProjectController(backend: Backend) {
 
   const initialState: ProjectsState = {
     projects: [],
+    projectSort: "pinned",
     selectedProjectId: null,
     projectDetail: null,
     isLoadingProjects: false,
@@ -33,7 +101,20 @@ export function createProjectController(backend: Backend) {
     projectsLoaded: false,
     pendingDescription: "",
     pendingFilePath: null,
+    pendingFilePaths: [],
     pendingFileName: "",
+    pendingSheets: [],
+    pendingSheet: null,
+    pendingTables: [],
+    pendingTable: null,
+    pendingParseDates: false,
+    pendingHasHeaders: true,
+    pendingLenient: false,
+    pendingDtypes: "",
+    pendingTimestampColumns: "",
+    pendingSourceTimezone: "",
+    importPresets: [],
+    pendingPreset: "auto",
   };
 
   const state = writable<ProjectsState>(initialState);
@@ -52,7 +133,132 @@ export function createProjectController(backend: Backend) {
     state.update((current) => ({
       ...current,
       pendingFilePath: path,
+      pendingFilePaths: path ? [path] : [],
       pendingFileName: path ? extractFileName(path) : "",
+      pendingSheets: [],
+      pendingSheet: null,
+      pendingTables: [],
+      pendingTable: null,
+    }));
+    if (path && /\.xls[xm]$/i.test(path)) {
+      void loadPendingSheets(path);
+    }
+    if (path && /\.(sqlite3?|db3?)$/i.test(path)) {
+      void loadPendingTables(path);
+    }
+  }
+
+  function setPendingFiles(paths: string[]) {
+    if (paths.length <= 1) {
+      setPendingFile(paths[0] ?? null);
+      return;
+    }
+    state.update((current) => ({
+      ...current,
+      pendingFilePath: paths[0],
+      pendingFilePaths: paths,
+      pendingFileName: `${extractFileName(paths[0])} + ${paths.length - 1} more`,
+      pendingSheets: [],
+      pendingSheet: null,
+      pendingTables: [],
+      pendingTable: null,
+    }));
+  }
+
+  async function loadPendingSheets(path: string) {
+    try {
+      const sheets = await backend.listSheets(path);
+      state.update((current) =>
+        current.pendingFilePath === path
+          ? { ...current, pendingSheets: sheets, pendingSheet: sheets[0] ?? null }
+          : current
+      );
+    } catch (error) {
+      console.error(error);
+    }
+  }
+
+  async function loadPendingTables(path: string) {
+    try {
+      const tables = await backend.listSqliteTables(path);
+      state.update((current) =>
+        current.pendingFilePath === path
+          ? { ...current, pendingTables: tables, pendingTable: tables[0] ?? null }
+          : current
+      );
+    } catch (error) {
+      console.error(error);
+    }
+  }
+
+  function setPendingTable(table: string | null) {
+    state.update((current) => ({
+      ...current,
+      pendingTable: table,
+    }));
+  }
+
+  function setPendingParseDates(parseDates: boolean) {
+    state.update((current) => ({
+      ...current,
+      pendingParseDates: parseDates,
+    }));
+  }
+
+  function setPendingHasHeaders(hasHeaders: boolean) {
+    state.update((current) => ({
+      ...current,
+      pendingHasHeaders: hasHeaders,
+    }));
+  }
+
+  function setPendingLenient(lenient: boolean) {
+    state.update((current) => ({
+      ...current,
+      pendingLenient: lenient,
+    }));
+  }
+
+  function setPendingDtypes(dtypes: string) {
+    state.update((current) => ({
+      ...current,
+      pendingDtypes: dtypes,
+    }));
+  }
+
+  function setPendingTimestampColumns(columns: string) {
+    state.update((current) => ({
+      ...current,
+      pendingTimestampColumns: columns,
+    }));
+  }
+
+  function setPendingSourceTimezone(timezone: string) {
+    state.update((current) => ({
+      ...current,
+      pendingSourceTimezone: timezone,
+    }));
+  }
+
+  function setPendingPreset(preset: string) {
+    state.update((current) => ({
+      ...current,
+      pendingPreset: preset,
+    }));
+  }
+
+  async function loadImportPresets() {
+    const importPresets = await backend.listImportPresets();
+    state.update((current) => ({
+      ...current,
+      importPresets,
+    }));
+  }
+
+  function setPendingSheet(sheet: string | null) {
+    state.update((current) => ({
+      ...current,
+      pendingSheet: sheet,
     }));
   }
 
@@ -61,7 +267,19 @@ export function createProjectController(backend: Backend) {
       ...current,
       pendingDescription: "",
       pendingFilePath: null,
+      pendingFilePaths: [],
       pendingFileName: "",
+      pendingSheets: [],
+      pendingSheet: null,
+      pendingTables: [],
+      pendingTable: null,
+      pendingParseDates: false,
+      pendingHasHeaders: true,
+      pendingLenient: false,
+      pendingDtypes: "",
+      pendingTimestampColumns: "",
+      pendingSourceTimezone: "",
+      pendingPreset: "auto",
     }));
   }
 
@@ -77,7 +295,7 @@ export function createProjectController(backend: Backend) {
     }));
 
     try {
-      const projects = await backend.listProjects();
+      const projects = await backend.listProjects(current.projectSort);
       state.update((value) => {
         const stillExists = value.selectedProjectId
           ? projects.some((item) => item.meta.id === value.selectedProjectId)
@@ -109,7 +327,7 @@ export function createProjectController(backend: Backend) {
     projectId: string,
     options: LoadDetailOptions = {}
   ) {
-    const { force = false, skipLoadingState = false } = options;
+    const { force = false, skipLoadingState = false, passphrase } = options;
 
     if (!force) {
       const cached = projectCache.get(projectId);
@@ -134,7 +352,7 @@ export function createProjectController(backend: Backend) {
     }
 
     try {
-      const detail = await backend.loadProject(projectId);
+      const detail = await backend.loadProject(projectId, passphrase);
       projectCache.set(projectId, detail);
       state.update((value) => ({
         ...value,
@@ -147,11 +365,16 @@ export function createProjectController(backend: Backend) {
         ...value,
         isLoadingDetail: false,
       }));
+      // Deleted outside the app; drop it from the list as well.
+      if (error instanceof BackendError && error.code === "project_not_found") {
+        projectCache.delete(projectId);
+        void loadProjects(true).catch((reloadError) => console.error(reloadError));
+      }
       throw error;
     }
   }
 
-  async function selectProject(projectId: string) {
+  async function selectProject(projectId: string, passphrase?: string) {
     projectCache.delete(projectId);
     state.update((value) => ({
       ...value,
@@ -161,7 +384,7 @@ export function createProjectController(backend: Backend) {
     }));
 
     try {
-      await loadProjectDetail(projectId, { force: true, skipLoadingState: true });
+      await loadProjectDetail(projectId, { force: true, skipLoadingState: true, passphrase });
     } catch (error) {
       state.update((value) => ({
         ...value,
@@ -171,6 +394,32 @@ export function createProjectController(backend: Backend) {
     }
   }
 
+  /** Sealing removes the plain files, so an open view of the project is closed. */
+  function closeIfSelected(projectId: string) {
+    projectCache.delete(projectId);
+    state.update((value) =>
+      value.selectedProjectId === projectId
+        ? { ...value, selectedProjectId: null, projectDetail: null }
+        : value
+    );
+  }
+
+  async function enableEncryption(projectId: string, passphrase: string) {
+    await backend.enableProjectEncryption(projectId, passphrase);
+    closeIfSelected(projectId);
+    await loadProjects(true);
+  }
+
+  async function disableEncryption(projectId: string, passphrase: string) {
+    await backend.disableProjectEncryption(projectId, passphrase);
+    await loadProjects(true);
+  }
+
+  async function lockProject(projectId: string, passphrase?: string) {
+    await backend.lockProject(projectId, passphrase);
+    closeIfSelected(projectId);
+  }
+
   async function refreshSelected() {
     const current = get(state);
     if (current.selectedProjectId) {
@@ -183,8 +432,9 @@ export function createProjectController(backend: Backend) {
   async function createProject() {
     const current = get(state);
     if (!current.pendingFilePath) {
-      throw new Error("Select a CSV file first.");
+      throw new Error("Select a file to import first.");
     }
+    const dtypes = parseDtypeOverrides(current.pendingDtypes);
 
     state.update((value) => ({
       ...value,
@@ -192,21 +442,34 @@ export function createProjectController(backend: Backend) {
     }));
 
     try {
-      const summary = await backend.createProject({
+      const response = await backend.createProject({
         path: current.pendingFilePath,
+        paths: current.pendingFilePaths,
         description: current.pendingDescription || null,
+        sheet: current.pendingSheet,
+        table: current.pendingTable,
+        dtypes,
+        parseDates: current.pendingParseDates,
+        hasHeaders: current.pendingHasHeaders,
+        lenient: current.pendingLenient,
+        timestampColumns: current.pendingTimestampColumns
+          .split(",")
+          .map((column) => column.trim())
+          .filter(Boolean),
+        sourceTimezone: current.pendingSourceTimezone.trim() || null,
+        preset: current.pendingPreset === "none" ? null : current.pendingPreset,
       });
 
       resetPending();
       await loadProjects(true);
-      await loadProjectDetail(summary.meta.id, { force: true });
+      await loadProjectDetail(response.project.meta.id, { force: true });
 
       state.update((value) => ({
         ...value,
         creating: false,
       }));
 
-      return summary;
+      return response;
     } catch (error) {
       state.update((value) => ({
         ...value,
@@ -216,6 +479,233 @@ export function createProjectController(backend: Backend) {
     }
   }
 
+  async function reimportProject(projectId: string, path?: string | null) {
+    const result = await backend.reimportProject(projectId, path);
+    projectCache.delete(projectId);
+    if (get(state).selectedProjectId === projectId) {
+      await loadProjectDetail(projectId, { force: true });
+    }
+    await loadProjects(true);
+    return result;
+  }
+
+  async function cloneProject(projectId: string) {
+    const summary = await backend.cloneProject(projectId);
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function setProjectSort(sort: ProjectSort) {
+    state.update((value) => ({ ...value, projectSort: sort }));
+    await loadProjects(true);
+  }
+
+  async function setProjectPinned(projectId: string, pinned: boolean) {
+    await backend.setProjectPinned(projectId, pinned);
+    await loadProjects(true);
+  }
+
+  async function checkProject(projectId: string, repair = false) {
+    const result = await backend.checkProject(projectId, repair);
+    if (repair && result.issues.length) {
+      projectCache.delete(projectId);
+      if (get(state).selectedProjectId === projectId) {
+        await loadProjectDetail(projectId, { force: true });
+      }
+      await loadProjects(true);
+    }
+    return result;
+  }
+
+  async function compactProject(projectId: string) {
+    const result = await backend.compactProject(projectId);
+    projectCache.delete(projectId);
+    return result;
+  }
+
+  async function listAnnotationBackups(projectId: string) {
+    return backend.listAnnotationBackups(projectId);
+  }
+
+  async function restoreAnnotationsBackup(projectId: string, backupId: string) {
+    const summary = await backend.restoreAnnotationsBackup(projectId, backupId);
+    projectCache.delete(projectId);
+    if (get(state).selectedProjectId === projectId) {
+      await loadProjectDetail(projectId, { force: true });
+    }
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function setDisplayTimezone(projectId: string, timezone: string | null) {
+    const summary = await backend.setDisplayTimezone(projectId, timezone);
+    projectCache.delete(projectId);
+    if (get(state).selectedProjectId === projectId) {
+      await loadProjectDetail(projectId, { force: true });
+    }
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function setTimestampColumn(projectId: string, column: string | null) {
+    const summary = await backend.setTimestampColumn(projectId, column);
+    projectCache.delete(projectId);
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function setColumnAliases(projectId: string, aliases: Record<string, string>) {
+    const summary = await backend.setColumnAliases(projectId, aliases);
+    projectCache.delete(projectId);
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function setColumnDescription(
+    projectId: string,
+    column: string,
+    description: string | null,
+  ) {
+    const summary = await backend.setColumnDescription(projectId, column, description);
+    projectCache.delete(projectId);
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function setProjectAlerts(projectId: string, alerts: AlertSettings) {
+    const summary = await backend.setProjectAlerts(projectId, alerts);
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function addScriptColumn(args: ScriptColumnArgs) {
+    const summary = await backend.addScriptColumn(args);
+    projectCache.delete(args.projectId);
+    if (get(state).selectedProjectId === args.projectId) {
+      await loadProjectDetail(args.projectId, { force: true });
+    }
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function addComputedColumn(args: ComputedColumnArgs) {
+    const summary = await backend.addComputedColumn(args);
+    projectCache.delete(args.projectId);
+    if (get(state).selectedProjectId === args.projectId) {
+      await loadProjectDetail(args.projectId, { force: true });
+    }
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function addDecodedColumn(args: DecodedColumnArgs) {
+    const summary = await backend.addDecodedColumn(args);
+    projectCache.delete(args.projectId);
+    if (get(state).selectedProjectId === args.projectId) {
+      await loadProjectDetail(args.projectId, { force: true });
+    }
+    await loadProjects(true);
+    return summary;
+  }
+
+  async function assignSessions(args: AssignSessionsArgs) {
+    const response = await backend.assignSessions(args);
+    projectCache.delete(args.projectId);
+    if (get(state).selectedProjectId === args.projectId) {
+      await loadProjectDetail(args.projectId, { force: true });
+    }
+    await loadProjects(true);
+    return response;
+  }
+
+  async function findRareValues(args: RareValuesArgs) {
+    const response = await backend.findRareValues(args);
+    if (response.column) {
+      projectCache.delete(args.projectId);
+      if (get(state).selectedProjectId === args.projectId) {
+        await loadProjectDetail(args.projectId, { force: true });
+      }
+      await loadProjects(true);
+    }
+    return response;
+  }
+
+  async function findDuplicates(args: DuplicatesArgs) {
+    return backend.findDuplicates(args);
+  }
+
+  async function dedupeProject(args: DuplicatesArgs) {
+    const response = await backend.dedupeProject(args);
+    projectCache.delete(args.projectId);
+    if (get(state).selectedProjectId === args.projectId) {
+      await loadProjectDetail(args.projectId, { force: true });
+    }
+    await loadProjects(true);
+    return response;
+  }
+
+  async function expandJsonColumn(args: ExpandJsonArgs) {
+    const response = await backend.expandJsonColumn(args);
+    projectCache.delete(args.projectId);
+    if (get(state).selectedProjectId === args.projectId) {
+      await loadProjectDetail(args.projectId, { force: true });
+    }
+    await loadProjects(true);
+    return response;
+  }
+
+  async function handleExternalModification(projectId: string) {
+    projectCache.delete(projectId);
+    if (get(state).selectedProjectId === projectId) {
+      await loadProjectDetail(projectId, { force: true, skipLoadingState: true });
+    }
+    await loadProjects(true);
+  }
+
+  /** Applies metadata changed by another window or command without a reload. */
+  function applyProjectMeta(meta: ProjectMeta) {
+    const cached = projectCache.get(meta.id);
+    if (cached) {
+      projectCache.set(meta.id, { ...cached, project: { ...cached.project, meta } });
+    }
+    state.update((value) => ({
+      ...value,
+      projects: value.projects.map((item) =>
+        item.meta.id === meta.id ? { ...item, meta } : item
+      ),
+      projectDetail:
+        value.projectDetail?.project.meta.id === meta.id
+          ? { ...value.projectDetail, project: { ...value.projectDetail.project, meta } }
+          : value.projectDetail,
+    }));
+  }
+
+  /** Flags or IOC rules changed; cached rows of the project are stale. */
+  async function handleAnnotationChange(projectId: string, reloadDetail: boolean) {
+    projectCache.delete(projectId);
+    if (reloadDetail && get(state).selectedProjectId === projectId) {
+      await loadProjectDetail(projectId, { force: true, skipLoadingState: true });
+    }
+  }
+
+  async function getStorageInfo() {
+    return backend.getProjectStorageInfo();
+  }
+
+  async function verifySourceIntegrity(projectId: string) {
+    return backend.verifySourceIntegrity(projectId);
+  }
+
+  async function exportProjectBundle(projectId: string, destination: string) {
+    await backend.exportProjectBundle(projectId, destination);
+  }
+
+  async function importProjectBundle(path: string) {
+    const summary = await backend.importProjectBundle(path);
+    await loadProjects(true);
+    return summary;
+  }
+
   async function deleteProject(projectId: string) {
     await backend.deleteProject(projectId);
     projectCache.delete(projectId);
@@ -282,9 +772,51 @@ export function createProjectController(backend: Backend) {
     selectProject,
     refreshSelected,
     createProject,
+    reimportProject,
+    cloneProject,
+    setProjectSort,
+    setProjectPinned,
+    verifySourceIntegrity,
+    getStorageInfo,
+    checkProject,
+    compactProject,
+    listAnnotationBackups,
+    restoreAnnotationsBackup,
+    handleExternalModification,
+    enableEncryption,
+    disableEncryption,
+    lockProject,
+    applyProjectMeta,
+    handleAnnotationChange,
+    addScriptColumn,
+    addDecodedColumn,
+    addComputedColumn,
+    assignSessions,
+    findRareValues,
+    findDuplicates,
+    dedupeProject,
+    expandJsonColumn,
+    setProjectAlerts,
+    setDisplayTimezone,
+    setTimestampColumn,
+    setColumnAliases,
+    setColumnDescription,
+    importProjectBundle,
+    exportProjectBundle,
     deleteProject,
     setPendingDescription,
     setPendingFile,
+    setPendingFiles,
+    setPendingSheet,
+    setPendingTable,
+    setPendingParseDates,
+    setPendingHasHeaders,
+    setPendingLenient,
+    setPendingDtypes,
+    setPendingTimestampColumns,
+    setPendingSourceTimezone,
+    setPendingPreset,
+    loadImportPresets,
     resetPending,
     updateSummary,
   };
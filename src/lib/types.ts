@@ -1,5 +1,6 @@
 export interface ProjectMeta {
   id: string;
+  format_version?: number;
   name: string;
   description?: string | null;
   created_at: string;
@@ -7,8 +8,155 @@ export interface ProjectMeta {
   flagged_records: number;
   ioc_applied_records: number;
   hidden_columns: string[];
+  /** Search and IOC rules skip the hidden columns. */
+  search_visible_only?: boolean;
+  /** Display names keyed by column, used by search prefixes, exports and reports. */
+  column_aliases?: Record<string, string>;
+  /** Notes on what each column holds, keyed by column. */
+  column_descriptions?: Record<string, string>;
+  ioc_policy?: IocConflictPolicy;
+  sources?: SourceFile[];
+  import_options?: ImportOptions;
+  preset?: string | null;
+  timestamp_column?: string | null;
+  last_opened_at?: string | null;
+  pinned?: boolean;
+  alerts?: AlertSettings;
+  /** IANA name or offset timestamps are shown in; UTC when unset. */
+  display_timezone?: string | null;
+  /** Set when the project's files are encrypted at rest. */
+  encryption?: ProjectEncryption | null;
 }
 
+/** How an encrypted project derives its key; holds no secret. */
+export interface ProjectEncryption {
+  salt: string;
+  rounds: number;
+  check: string;
+}
+
+/** Where rows newly flagged critical by IOC rules are reported. */
+export interface AlertSettings {
+  webhook_url?: string | null;
+  notify: boolean;
+}
+
+export interface CriticalIocRow {
+  row_index: number;
+  tags: string[];
+}
+
+/** Payload of the `critical-ioc-alert` event and of the webhook POST. */
+export interface CriticalAlert {
+  project_id: string;
+  project_name: string;
+  new_critical_rows: number;
+  total_critical_rows: number;
+  rows: CriticalIocRow[];
+  created_at: string;
+}
+
+/** Payload of `flag-updated`; `row_indices` is null after bulk changes. */
+export interface FlagUpdatedEvent {
+  project_id: string;
+  row_indices: number[] | null;
+}
+
+/** Payload of `iocs-updated`. */
+export interface IocsUpdatedEvent {
+  project_id: string;
+}
+
+/** Payload of `project-meta-updated`: the project as it now stands. */
+export interface ProjectMetaUpdatedEvent {
+  project: ProjectMeta;
+}
+
+export type ProjectSort = 'created' | 'recent' | 'pinned';
+
+export interface ImportPresetInfo {
+  id: string;
+  name: string;
+  description: string;
+}
+
+export type SourceIntegrityStatus = 'verified' | 'modified' | 'missing';
+
+export interface SourceIntegrity {
+  path: string;
+  recorded_sha256: string;
+  current_sha256: string | null;
+  status: SourceIntegrityStatus;
+}
+
+export interface SourceFile {
+  path: string;
+  sha256: string;
+}
+
+export type ColumnDtype =
+  | "string"
+  | "int64"
+  | "float64"
+  | "boolean"
+  | "date"
+  | "datetime";
+
+export interface ImportOptions {
+  sheet?: string | null;
+  table?: string | null;
+  dtypes?: Record<string, ColumnDtype>;
+  parse_dates?: boolean;
+  has_headers?: boolean;
+  lenient?: boolean;
+  /** Columns stored as UTC timestamps. */
+  timestamp_columns?: string[];
+  source_timezone?: string | null;
+  trim_whitespace?: boolean;
+  lowercase_columns?: string[];
+  strip_thousands_columns?: string[];
+  /** Columns computed by Rhai scripts while importing, and again on reimport. */
+  script_columns?: ScriptColumn[];
+}
+
+export interface ScriptColumn {
+  column: string;
+  /** Rhai source run once per row; the row's values are in the map `row`. */
+  script: string;
+  /** Columns the script reads; every column when omitted. */
+  inputColumns?: string[];
+}
+
+export interface ImportIssue {
+  file?: string;
+  line: number;
+  message: string;
+  skipped: boolean;
+}
+
+export interface ImportReport {
+  rows_imported: number;
+  rows_repaired: number;
+  rows_skipped: number;
+  issues: ImportIssue[];
+}
+
+export interface ColumnType {
+  name: string;
+  dtype: string;
+}
+
+export interface CreateProjectResponse {
+  project: ProjectSummary;
+  column_types: ColumnType[];
+  import_report?: ImportReport;
+}
+
+export type IocConflictPolicy =
+  | 'highest_severity'
+  | 'first_match'
+  | 'safe_suppresses';
+
 export interface ProjectSummary {
   meta: ProjectMeta;
 }
@@ -22,11 +170,24 @@ export interface ProjectRow {
   memo?: string | null;
 }
 
+export type IocKind = 'query' | 'regex' | 'ip';
+
 export interface IocEntry {
   id?: string;
   flag: FlagSymbol;
   tag: string;
   query: string;
+  kind?: IocKind;
+  columns?: string[];
+  description?: string | null;
+  reference_url?: string | null;
+  author?: string | null;
+  /** Query terms match whole words only. */
+  whole_word?: boolean;
+  /** Named set of rules, e.g. one feed, managed together. */
+  group?: string | null;
+  /** The rule is kept but not evaluated. */
+  disabled?: boolean;
 }
 
 export interface LoadProjectResponse {
@@ -36,12 +197,274 @@ export interface LoadProjectResponse {
   column_max_chars: Record<string, number>;
   iocs: IocEntry[];
   initial_rows: ProjectRow[];
+  /** Default view, already applied to the hidden columns and initial rows. */
+  view?: SavedView;
+}
+
+export interface ReimportProjectResponse {
+  project: ProjectSummary;
+  matched_rows: number;
+  added_rows: number;
+  removed_rows: number;
+  carried_annotations: number;
+  dropped_annotations: number;
 }
 
 export interface QueryRowsResponse {
   rows: ProjectRow[];
   total_flagged: number;
+  /** Filtered rows per effective flag, across every page. */
+  flag_counts: FlagBreakdown;
   total_rows: number;
   total_filtered_rows: number;
   offset: number;
+  time_column?: string | null;
+  /** Set when the query asked for `debug`. */
+  timings?: QueryTimings | null;
+}
+
+/** Milliseconds spent in each phase of a row query. */
+export interface QueryTimings {
+  read_ms: number;
+  search_ms: number;
+  ioc_ms: number;
+  sort_ms: number;
+  filter_ms: number;
+  materialize_ms: number;
+  total_ms: number;
+}
+
+export interface SessionState {
+  project_id: string | null;
+  search?: string | null;
+  flag_filter?: string | null;
+  sort_key?: string | null;
+  sort_direction?: string | null;
+  offset: number;
+  updated_at: string;
+}
+
+export interface RowLink {
+  project_id: string;
+  row_id: number;
+}
+
+export type LaunchRequest =
+  | ({ kind: "row_link" } & RowLink)
+  | { kind: "open_archive"; path: string }
+  | { kind: "import_csv"; path: string };
+
+export interface PerformanceSettings {
+  max_threads: number | null;
+  import_threads: number | null;
+  import_chunk_size: number | null;
+  exact_column_widths: boolean;
+}
+
+export interface DataRoot {
+  path: string;
+  default_path: string;
+  /** Set when projects live outside the app data directory. */
+  configured_path: string | null;
+}
+
+export interface PerformanceSettingsResponse {
+  settings: PerformanceSettings;
+  restart_required: boolean;
+}
+
+export interface OperationStats {
+  name: 'query_project_rows' | 'ioc_evaluation' | 'export_project';
+  count: number;
+  last_ms: number;
+  average_ms: number;
+  max_ms: number;
+  rows_scanned: number;
+  last_rows_scanned: number;
+}
+
+export interface CacheStats {
+  name: 'searchable_text' | 'ioc_flags' | 'sort_order';
+  hits: number;
+  misses: number;
+  hit_rate: number | null;
+}
+
+export interface PerformanceStats {
+  operations: OperationStats[];
+  caches: CacheStats[];
+}
+
+export type LogLevel = 'error' | 'warn' | 'info' | 'debug' | 'trace';
+
+export interface RecentLogs {
+  level: LogLevel;
+  /** Active log file; rotated files sit next to it. */
+  path: string | null;
+  lines: string[];
+}
+
+export interface CaseNoteEntry {
+  timestamp: string;
+  text: string;
+}
+
+export interface CaseNotes {
+  text: string;
+  entries: CaseNoteEntry[];
+  updated_at?: string;
+}
+
+export interface RowComment {
+  author?: string;
+  timestamp: string;
+  text: string;
+}
+
+export interface RowComments {
+  row_index: number;
+  /** Oldest first. */
+  comments: RowComment[];
+  /** Rendered from the comments when the row has any. */
+  memo: string | null;
+}
+
+/** A row marked to come back to, separate from its flag. */
+export interface Bookmark {
+  row_index: number;
+  label?: string;
+  created_at: string;
+}
+
+/** A saved search, flag filter, sort, column layout and page size. */
+export interface SavedView {
+  name: string;
+  search?: string;
+  whole_word?: boolean;
+  flag_filter?: string;
+  sort_key?: string;
+  sort_direction?: "asc" | "desc";
+  /** Columns shown; the project's hidden columns apply when empty. */
+  visible_columns: string[];
+  page_size?: number;
+  is_default: boolean;
+  updated_at: string;
+}
+
+export interface StorageUsage {
+  data_bytes: number;
+  flags_bytes: number;
+  cache_bytes: number;
+  ioc_bytes: number;
+  other_bytes: number;
+  total_bytes: number;
+}
+
+export interface ProjectStorageInfo extends StorageUsage {
+  project_id: string;
+  name: string;
+}
+
+export interface WorkspaceStorageInfo {
+  data_root: string;
+  projects: ProjectStorageInfo[];
+  totals: StorageUsage;
+}
+
+export interface ProjectIssue {
+  kind: string;
+  message: string;
+  repairable: boolean;
+  repaired: boolean;
+}
+
+export interface CheckProjectResponse {
+  issues: ProjectIssue[];
+  healthy: boolean;
+}
+
+export interface AnnotationsBackup {
+  id: string;
+  created_at: string;
+  flagged_rows: number;
+  ioc_count: number;
+  size_bytes: number;
+}
+
+export interface CompactProjectResponse {
+  bytes_before: number;
+  bytes_after: number;
+  removed_files: string[];
+}
+
+export type AppErrorCode =
+  | 'invalid_request'
+  | 'project_not_found'
+  | 'data_file_missing'
+  | 'project_busy'
+  | 'passphrase_required'
+  | 'passphrase_incorrect'
+  | 'data_corrupt'
+  | 'permission_denied'
+  | 'file_not_found'
+  | 'internal';
+
+/** What a project is doing; rewrites reject reads and other rewrites. */
+export interface ProjectStatus {
+  busy: boolean;
+  /** Command rewriting the project, e.g. `dedupe_project`. */
+  operation: string | null;
+  started_at: string | null;
+  active_reads: number;
+}
+
+/** Error payload every backend command rejects with. */
+export interface AppErrorPayload {
+  code: AppErrorCode;
+  message: string;
+  context: {
+    projectId?: string;
+    path?: string;
+  };
+}
+
+export type EntityKind = 'ip' | 'domain' | 'email' | 'md5' | 'sha1' | 'sha256';
+
+export interface EntitySummary {
+  kind: EntityKind;
+  value: string;
+  row_count: number;
+  /** The first rows the value appears in, for pivoting. */
+  rows: number[];
+}
+
+export interface ListEntitiesResponse {
+  built_at: string;
+  columns: string[];
+  /** Entities matching the filters before the limit. */
+  total_entities: number;
+  entities: EntitySummary[];
+}
+
+/** Rows per effective flag (user flag, else IOC flag). */
+export interface FlagBreakdown {
+  critical: number;
+  suspicious: number;
+  safe: number;
+  unflagged: number;
+}
+
+export interface PivotColumn {
+  column: string;
+  row_count: number;
+  flags: FlagBreakdown;
+}
+
+export interface PivotEntityResponse {
+  value: string;
+  row_indices: number[];
+  flags: FlagBreakdown;
+  columns: PivotColumn[];
+  rows: ProjectRow[];
+  offset: number;
 }